@@ -0,0 +1,118 @@
+//! Single entry point to the Athena agent framework.
+//!
+//! The framework is split into internal crates that are versioned together.
+//! This crate re-exports them under one name so applications depend on a
+//! single crate and never mix versions of the pieces:
+//!
+//! - [`core`] - messages, errors and shared context (always available)
+//! - [`config`] - configuration loading and validation (always available)
+//! - [`llm`] - LLM providers and provider wrappers (feature `llm`)
+//! - [`memory`] - conversation storage (feature `memory`)
+//! - [`tools`] - the tool trait, registry and built-in tools (feature `tools`)
+//! - [`planner`] - plan generation from goals (feature `planner`)
+//! - [`executor`] - plan execution (feature `executor`)
+//! - [`guardrails`] - plan validation before execution (feature `guardrails`)
+//! - [`rules`] - behavior rules applied during planning (feature `rules`)
+//! - [`preflight`] - readiness checks before serving the first request
+//!   (feature `preflight`)
+//! - [`telemetry`] - export of the subsystems' `tracing` spans through
+//!   OpenTelemetry (feature `opentelemetry`)
+//!
+//! The default features cover everything needed to plan and run an agent;
+//! `full` adds guardrails, rules and preflight checks. Features pull in the subsystems their
+//! APIs are built on, so enabling `executor` also enables `planner`, `llm`,
+//! `memory` and `tools`.
+//!
+//! The [`prelude`] brings the most used types into scope.
+//!
+//! # Example
+//!
+//! With the `executor` feature, [`AgentBuilder`](executor::AgentBuilder)
+//! assembles an [`Agent`](core::Agent) that calls tools until it can answer:
+//!
+//! ```no_run
+//! use athena_ai::prelude::*;
+//! use athena_ai::tools::Calculator;
+//! use std::path::Path;
+//!
+//! # async fn run() -> Result<()> {
+//! let config = athena_ai::config::load_from_file(Path::new("config.yaml"))?;
+//! let agent = AgentBuilder::new()
+//!     .provider(create_provider(&config.llm)?)
+//!     .system_prompt("You are a careful bookkeeper.")
+//!     .tool(Calculator::new())
+//!     .build()?;
+//! let output = agent.run("What is 17% of 2300?").await?;
+//! println!("{}", output.response);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The planner and executor can also be driven directly:
+//!
+//! ```no_run
+//! use athena_ai::prelude::*;
+//! use std::path::Path;
+//!
+//! # async fn run() -> Result<()> {
+//! let config = athena_ai::config::load_from_file(Path::new("config.yaml"))?;
+//! let tools = ToolRegistry::new();
+//! let planner = Planner::new(create_provider(&config.llm)?, Box::new(InMemoryStore::new()));
+//! let plan = planner.create_plan("What is 2 + 2?", &tools.list_tools()).await?;
+//!
+//! let mut executor = Executor::new(tools, Box::new(InMemoryStore::new()));
+//! let result = executor.execute_plan(plan).await?;
+//! println!("{}", result.final_response);
+//! # Ok(())
+//! # }
+//! ```
+
+pub use agent_core as core;
+pub use config;
+
+#[cfg(feature = "executor")]
+pub use executor;
+#[cfg(feature = "guardrails")]
+pub use guardrails;
+#[cfg(feature = "llm")]
+pub use llm;
+#[cfg(feature = "memory")]
+pub use memory;
+#[cfg(feature = "planner")]
+pub use planner;
+#[cfg(feature = "rules")]
+pub use rules;
+#[cfg(feature = "tools")]
+pub use tools;
+
+#[cfg(feature = "preflight")]
+pub mod preflight;
+#[cfg(feature = "opentelemetry")]
+pub mod telemetry;
+
+/// Version of the framework, shared by every re-exported crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The most used types of each enabled subsystem
+pub mod prelude {
+    pub use agent_core::{
+        Agent, AgentError, AgentOutput, ExecutionContext, Message, Result, ResultExt, Role,
+        Shutdown,
+    };
+    pub use config::{AgentConfig, LLMConfig};
+
+    #[cfg(feature = "executor")]
+    pub use executor::{AgentBuilder, ExecutionResult, Executor, StepKind, StepResult};
+    #[cfg(feature = "guardrails")]
+    pub use guardrails::{Guardrail, GuardrailRegistry};
+    #[cfg(feature = "llm")]
+    pub use llm::{CompletionResponse, LLMProvider, create_provider};
+    #[cfg(feature = "memory")]
+    pub use memory::{InMemoryStore, MemoryStore};
+    #[cfg(feature = "planner")]
+    pub use planner::{Condition, Plan, Planner, Step};
+    #[cfg(feature = "rules")]
+    pub use rules::{Rule, RuleEngine};
+    #[cfg(feature = "tools")]
+    pub use tools::{Tool, ToolInfo, ToolRegistry};
+}
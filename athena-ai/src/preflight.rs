@@ -0,0 +1,256 @@
+//! Preflight validation before the first user request.
+//!
+//! [`preflight`] runs every check a deployment needs to pass before it can
+//! serve users: the configuration is valid, the API key is present, the LLM
+//! provider answers, the memory backend the agent will use honours the
+//! configured limits and every configured tool and guardrail is registered.
+//! The checks are independent, so a single run reports every problem instead
+//! of stopping at the first one.
+//!
+//! ```no_run
+//! use athena_ai::guardrails::GuardrailRegistry;
+//! use athena_ai::memory::InMemoryStore;
+//! use athena_ai::tools::ToolRegistry;
+//! use std::path::Path;
+//!
+//! # async fn run() -> athena_ai::core::Result<()> {
+//! let config = athena_ai::config::load_from_file(Path::new("config.yaml"))?;
+//! let memory = InMemoryStore::new();
+//! let report = athena_ai::preflight::preflight(
+//!     &config,
+//!     &memory,
+//!     &ToolRegistry::new(),
+//!     &GuardrailRegistry::new(),
+//! )
+//! .await;
+//! println!("{}", report);
+//! # Ok(())
+//! # }
+//! ```
+
+use agent_core::Message;
+use config::AgentConfig;
+use guardrails::{GuardrailRegistry, LanguageGuardrail};
+use llm::{LLMProvider, create_provider};
+use memory::{MemoryStore, count_tokens};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tools::ToolRegistry;
+
+/// How long the provider ping may take before it counts as failed
+const PING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Outcome of a single preflight check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The check succeeded, with a short description of what was verified
+    Passed(String),
+    /// The check could not run because an earlier check failed
+    Skipped(String),
+    /// The check failed, with the reason
+    Failed(String),
+}
+
+/// Result of one named preflight check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// Component that was checked (e.g. "config", "provider")
+    pub name: &'static str,
+    /// Outcome of the check
+    pub status: CheckStatus,
+    /// Time the check took
+    pub duration: Duration,
+}
+
+/// Structured readiness report produced by [`preflight`]
+#[derive(Debug, Clone, Default)]
+pub struct ReadinessReport {
+    /// Results in the order the checks ran
+    pub checks: Vec<CheckResult>,
+}
+
+impl ReadinessReport {
+    /// Whether every check passed
+    pub fn is_ready(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| matches!(check.status, CheckStatus::Passed(_)))
+    }
+
+    /// Checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.status, CheckStatus::Failed(_)))
+    }
+
+    fn record(&mut self, name: &'static str, started: Instant, status: CheckStatus) {
+        self.checks.push(CheckResult {
+            name,
+            status,
+            duration: started.elapsed(),
+        });
+    }
+}
+
+impl fmt::Display for ReadinessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let (label, detail) = match &check.status {
+                CheckStatus::Passed(detail) => ("ok", detail),
+                CheckStatus::Skipped(detail) => ("skipped", detail),
+                CheckStatus::Failed(detail) => ("failed", detail),
+            };
+            writeln!(
+                f,
+                "{:<12} {:<8} {} ({} ms)",
+                check.name,
+                label,
+                detail,
+                check.duration.as_millis()
+            )?;
+        }
+        if self.is_ready() {
+            write!(f, "Ready")
+        } else {
+            write!(f, "Not ready")
+        }
+    }
+}
+
+/// Validate a configuration and the components built from it
+///
+/// `memory`, `tools` and `guardrails` are the components the agent will
+/// run with. Checks, in order:
+/// 1. `config`: the configuration passes [`config::validate`]
+/// 2. `secrets`: an API key is present for remote providers
+/// 3. `provider`: the LLM provider can be created and answers a ping
+/// 4. `memory`: the memory backend answers reads within the configured
+///    message limit and token budget
+/// 5. `tools`: every configured or cached tool is registered
+/// 6. `guardrails`: every configured guardrail is registered, and the
+///    configured locale's language can be detected
+///
+/// The provider ping is skipped when the configuration or secrets check
+/// failed, since it could not succeed.
+pub async fn preflight(
+    config: &AgentConfig,
+    memory: &dyn MemoryStore,
+    tools: &ToolRegistry,
+    guardrails: &GuardrailRegistry,
+) -> ReadinessReport {
+    let mut report = ReadinessReport::default();
+
+    let started = Instant::now();
+    let validation = config::validate(config);
+    let config_ok = validation.is_ok();
+    let status = match validation {
+        Ok(()) => CheckStatus::Passed(format!(
+            "{} with model {}",
+            config.llm.provider, config.llm.model
+        )),
+        Err(e) => CheckStatus::Failed(e.to_string()),
+    };
+    report.record("config", started, status);
+
+    let started = Instant::now();
+    let is_local = matches!(config.llm.provider.as_str(), "ollama" | "llamacpp");
+    let secrets_ok = is_local || !config.llm.api_key.trim().is_empty();
+    let status = if is_local {
+        CheckStatus::Passed("no API key required for local provider".to_string())
+    } else if secrets_ok {
+        CheckStatus::Passed(format!("API key set for {}", config.llm.provider))
+    } else {
+        CheckStatus::Failed(format!("no API key set for {}", config.llm.provider))
+    };
+    report.record("secrets", started, status);
+
+    let started = Instant::now();
+    let status = if config_ok && secrets_ok {
+        ping_provider(config).await
+    } else {
+        CheckStatus::Skipped("configuration or secrets check failed".to_string())
+    };
+    report.record("provider", started, status);
+
+    let started = Instant::now();
+    report.record("memory", started, check_memory(config, memory));
+
+    let started = Instant::now();
+    let missing: Vec<&str> = config
+        .tools
+        .iter()
+        .chain(config.tool_cache.keys())
+        .map(String::as_str)
+        .filter(|name| tools.get(name).is_none())
+        .collect();
+    let status = if missing.is_empty() {
+        let names: Vec<String> = tools.list_tools().into_iter().map(|tool| tool.name).collect();
+        CheckStatus::Passed(format!("loaded {}", names.join(", ")))
+    } else {
+        CheckStatus::Failed(format!("unknown tools: {}", missing.join(", ")))
+    };
+    report.record("tools", started, status);
+
+    let started = Instant::now();
+    let registered = guardrails.names();
+    let missing: Vec<&str> = config
+        .guardrails
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !registered.contains(name))
+        .collect();
+    let language = config.locale.as_deref().map(LanguageGuardrail::new).transpose();
+    let status = match (missing.is_empty(), language) {
+        (false, _) => CheckStatus::Failed(format!("unknown guardrails: {}", missing.join(", "))),
+        (true, Err(e)) => CheckStatus::Failed(e.to_string()),
+        (true, Ok(language)) => {
+            let count = guardrails.len() + usize::from(language.is_some());
+            CheckStatus::Passed(format!("loaded {}", count))
+        }
+    };
+    report.record("guardrails", started, status);
+
+    report
+}
+
+/// Read the history the agent would see and check it honours the limits
+fn check_memory(config: &AgentConfig, memory: &dyn MemoryStore) -> CheckStatus {
+    let limit = config.memory.max_messages;
+    let recent = memory.get_recent(limit);
+    if recent.len() > limit {
+        return CheckStatus::Failed(format!(
+            "returned {} messages when asked for at most {}",
+            recent.len(),
+            limit
+        ));
+    }
+
+    let budget = config.memory.token_budget;
+    let tokens: usize = memory.get_within_budget(budget).iter().map(count_tokens).sum();
+    if tokens > budget {
+        return CheckStatus::Failed(format!(
+            "returned {} tokens for a budget of {}",
+            tokens, budget
+        ));
+    }
+    CheckStatus::Passed(format!("{} messages in history", recent.len()))
+}
+
+/// Send a one-word request to the configured provider
+async fn ping_provider(config: &AgentConfig) -> CheckStatus {
+    let provider = match create_provider(&config.llm) {
+        Ok(provider) => provider,
+        Err(e) => return CheckStatus::Failed(e.to_string()),
+    };
+    let messages = [Message::user("Reply with the word: ready")];
+    let ping = provider.send_message(&messages);
+    match tokio::time::timeout(PING_TIMEOUT, ping).await {
+        Ok(Ok(_)) => CheckStatus::Passed(format!("{} responded", config.llm.provider)),
+        Ok(Err(e)) => CheckStatus::Failed(e.to_string()),
+        Err(_) => CheckStatus::Failed(format!(
+            "no response within {} seconds",
+            PING_TIMEOUT.as_secs()
+        )),
+    }
+}
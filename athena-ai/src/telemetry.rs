@@ -0,0 +1,98 @@
+//! Export of the framework's `tracing` spans through OpenTelemetry.
+//!
+//! Provider requests, plan generation, plan steps, tool calls and tool
+//! loops run in `tracing` spans carrying the request's trace ID, the model,
+//! token counts and the step index. [`init_opentelemetry`] installs a
+//! subscriber that sends those spans to an OTLP collector, so a tracing
+//! backend shows where the latency of an agent run goes.
+
+use agent_core::{AgentError, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Keeps the OpenTelemetry exporter running
+///
+/// Spans are exported in batches; dropping the guard, or calling
+/// [`shutdown`](Self::shutdown), sends the spans still buffered. Hold it
+/// for the life of the process, e.g. in `main` or in a
+/// [shutdown hook](agent_core::Shutdown::on_shutdown).
+pub struct OpenTelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl OpenTelemetryGuard {
+    /// Export the buffered spans and stop the exporter
+    pub fn shutdown(mut self) -> Result<()> {
+        match self.provider.take() {
+            Some(provider) => provider.shutdown().map_err(|e| {
+                AgentError::Execution(format!("Failed to shut down OpenTelemetry exporter: {}", e))
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for OpenTelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Install a global subscriber that exports spans to an OTLP collector
+///
+/// Spans are sent over OTLP/HTTP to `endpoint`, such as
+/// `http://localhost:4318/v1/traces`, or to the endpoint named by the
+/// standard `OTEL_EXPORTER_OTLP_*` environment variables if it is `None`.
+/// They are tagged with `service_name`.
+///
+/// # Errors
+/// Returns a configuration error if the exporter cannot be built or a
+/// global subscriber is already installed.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn run() -> athena_ai::core::Result<()> {
+/// let telemetry = athena_ai::telemetry::init_opentelemetry(
+///     "support-agent",
+///     Some("http://localhost:4318/v1/traces"),
+/// )?;
+/// // ... run agents ...
+/// telemetry.shutdown()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn init_opentelemetry(
+    service_name: impl Into<String>,
+    endpoint: Option<&str>,
+) -> Result<OpenTelemetryGuard> {
+    let mut exporter = SpanExporter::builder().with_http();
+    if let Some(endpoint) = endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+    let exporter = exporter.build().map_err(|e| {
+        AgentError::Config(format!("Failed to build OpenTelemetry exporter: {}", e))
+    })?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.into()).build())
+        .build();
+    let tracer = provider.tracer("athena-ai");
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .map_err(|e| {
+            AgentError::Config(format!("Failed to install OpenTelemetry subscriber: {}", e))
+        })?;
+
+    Ok(OpenTelemetryGuard {
+        provider: Some(provider),
+    })
+}
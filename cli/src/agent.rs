@@ -4,14 +4,22 @@
 //! components (LLM, memory, planner, executor, tools, guardrails) to process
 //! user queries.
 
-use agent_core::Result;
-use config::AgentConfig;
-use executor::Executor;
-use guardrails::{FilePathGuardrail, GuardrailRegistry, RateLimitGuardrail};
-use llm::create_provider;
-use memory::{InMemoryStore, MemoryStore};
-use planner::Planner;
-use tools::{Calculator, FileReader, ToolRegistry, WebSearchStub};
+use agent_core::{RequestContext, Result};
+use config::{AgentConfig, LLMConfig};
+use executor::{Budget, Executor, StreamExecution};
+use guardrails::{
+    FilePathGuardrail, Guardrail, GuardrailRegistry, LanguageGuardrail, RateLimitGuardrail,
+};
+use llm::{
+    CompressingProvider, ConcurrencyGovernor, DegradationPolicy, DegradingProvider,
+    GovernedProvider, LLMProvider, PostProcessingPipeline, PostProcessingProvider,
+    PromptCompressor, TransformPipeline, TransformingProvider, create_provider,
+};
+use memory::{InMemoryStore, MemoryStore, TokenEstimator};
+use planner::{Plan, Planner, Step};
+use std::sync::Arc;
+use std::time::Duration;
+use tools::{CachedTool, Calculator, FileReader, Tool, ToolRegistry, WebSearchStub};
 
 /// Main agent structure that orchestrates all framework components.
 ///
@@ -25,6 +33,9 @@ pub struct Agent {
     planner: Planner,
     executor: Executor,
     guardrails: GuardrailRegistry,
+    degradation: DegradationPolicy,
+    session_id: String,
+    language: Option<LanguageGuardrail>,
 }
 
 impl Agent {
@@ -40,67 +51,108 @@ impl Agent {
     /// Returns an error if:
     /// - LLM provider initialization fails
     /// - Configuration is invalid
+    /// - The configured locale's language cannot be detected
     pub fn new(config: AgentConfig) -> Result<Self> {
         // Create memory store
-        let memory = Box::new(InMemoryStore::new());
+        let memory = build_memory();
 
         // Create tool registry and register default tools
-        let mut tools = ToolRegistry::new();
-        
-        // Register tools based on config or use defaults
-        if config.tools.is_empty() || config.tools.contains(&"calculator".to_string()) {
-            tools.register(Box::new(Calculator::new()));
-        }
-        if config.tools.is_empty() || config.tools.contains(&"file_reader".to_string()) {
-            tools.register(Box::new(FileReader::new()));
-        }
-        if config.tools.is_empty() || config.tools.contains(&"web_search".to_string()) {
-            tools.register(Box::new(WebSearchStub::new()));
-        }
+        let tools = build_tools(&config);
 
         // Create planner with LLM and memory
         let planner_memory = Box::new(InMemoryStore::new());
-        let planner_llm = create_provider(&config.llm)?;
-        let planner = Planner::new(planner_llm, planner_memory);
+        let governor = Arc::new(ConcurrencyGovernor::from_config(&config.concurrency));
+        let degradation = DegradationPolicy::from_config(&config.degradation);
+        let planner_llm = Box::new(DegradingProvider::new(
+            TransformingProvider::new(
+                model_provider(&config.llm, &config, &governor)?,
+                TransformPipeline::from_config(&config.transforms),
+            ),
+            degradation.clone(),
+        ));
 
-        // Create executor with tools and memory
+        // Create executor with tools and memory, and a provider per model
+        // profile for text generation steps, whose output is post-processed
         let executor_memory = Box::new(InMemoryStore::new());
-        let executor = Executor::new(tools, executor_memory);
-
-        // Create guardrails registry and register default guardrails
-        let mut guardrails = GuardrailRegistry::new();
-        
-        // Register guardrails based on config
-        if config.guardrails.contains(&"file_path".to_string()) {
-            // Default to allowing /tmp and current directory
-            let allowed_paths = vec![
-                std::path::PathBuf::from("/tmp"),
-                std::env::current_dir().unwrap_or_default(),
-            ];
-            guardrails.register(Box::new(FilePathGuardrail::new(allowed_paths)));
+        let post_processing = PostProcessingPipeline::from_config(&config.post_processing);
+        let mut executor = Executor::new(tools, executor_memory)
+            .with_max_concurrency(config.concurrency.max_concurrent_steps.unwrap_or(1))
+            .with_llm_provider(Box::new(PostProcessingProvider::new(
+                model_provider(&config.llm, &config, &governor)?,
+                post_processing.clone(),
+            )));
+        for (name, profile) in &config.profiles {
+            let provider = PostProcessingProvider::new(
+                model_provider(profile, &config, &governor)?,
+                post_processing.clone(),
+            );
+            executor = executor.with_profile(name.clone(), Box::new(provider));
         }
-        if config.guardrails.contains(&"rate_limit".to_string()) {
-            // Default to 100 calls per minute
-            guardrails.register(Box::new(RateLimitGuardrail::new(100)));
+        if let Some(budget) = &config.budget {
+            executor = executor.with_budget(Budget {
+                max_tokens: budget.max_tokens,
+                max_cost_usd: budget.max_cost_usd,
+                input_usd_per_million: budget.input_usd_per_million,
+                output_usd_per_million: budget.output_usd_per_million,
+            });
         }
 
-        Ok(Self {
+        let planner = Planner::new(planner_llm, planner_memory)
+            .with_model_profiles(executor.profile_names());
+
+        // Create guardrails registry and register default guardrails
+        let guardrails = build_guardrails(&config);
+
+        let mut agent = Self {
             memory,
             planner,
             executor,
             guardrails,
-        })
+            degradation,
+            session_id: RequestContext::new().trace_id,
+            language: None,
+        };
+        agent.set_locale(config.locale)?;
+        Ok(agent)
+    }
+
+    /// Set the locale this session's responses must be written in
+    ///
+    /// The locale is added to the planner's system prompt, exposed to steps
+    /// as `{{context.locale}}`, and enforced by a [`LanguageGuardrail`] on
+    /// every plan's response steps. `None` lets the model answer in any
+    /// language.
+    ///
+    /// # Errors
+    /// Returns an error if the locale's language cannot be detected.
+    pub fn set_locale(&mut self, locale: Option<String>) -> Result<()> {
+        self.language = locale.as_deref().map(LanguageGuardrail::new).transpose()?;
+        self.planner.set_locale(locale.clone());
+        self.executor.context_mut().locale = locale;
+        Ok(())
+    }
+
+    /// The locale this session's responses are written in, if fixed
+    pub fn locale(&self) -> Option<&str> {
+        self.planner.locale()
     }
 
     /// Process a user query and return a response
     ///
     /// This method orchestrates the complete agent workflow:
     /// 1. Adds the user query to memory
-    /// 2. Uses the planner to create a plan from the query
-    /// 3. Validates the plan with guardrails
-    /// 4. Executes the plan with the executor
+    /// 2. Streams a plan for the query from the planner
+    /// 3. Validates each step with guardrails as soon as it arrives
+    /// 4. Executes the plan with the executor once it is complete
     /// 5. Returns the final response
     ///
+    /// The query runs under a new [`RequestContext`] carrying this agent's
+    /// session ID, so provider requests and step errors share a trace ID.
+    ///
+    /// If the LLM provider is down, the configured fallback response is
+    /// returned instead; without one, the error is
+    /// `AgentError::ServiceDegraded`.
+    ///
     /// # Arguments
     /// * `query` - The user's query or request
     ///
@@ -112,22 +164,119 @@ impl Agent {
     /// - Plan generation fails
     /// - Guardrail validation fails
     /// - Plan execution fails
+    /// - The query's text generation steps use up the configured budget
+    ///   (`AgentError::BudgetExceeded`)
+    /// - The LLM provider is down and no fallback response is configured
     pub async fn process(&mut self, query: &str) -> Result<String> {
+        let request = RequestContext::new().with_session_id(self.session_id.clone());
+        let result = request.scope(self.run(query)).await;
+        self.degradation.recover(result)
+    }
+
+    /// Plan, validate and execute a query
+    async fn run(&mut self, query: &str) -> Result<String> {
         // Add user query to memory
         let user_message = agent_core::Message::user(query);
         self.memory.add_message(user_message);
 
-        // Use planner to create plan from query
+        // Stream the plan so each step is checked as soon as it arrives
         let available_tools = self.executor.list_tools();
-        let plan = self.planner.create_plan(query, &available_tools).await?;
+        let plan = self.planner.stream_plan(query, &available_tools).await?;
 
-        // Validate plan with guardrails
-        self.guardrails.validate_all(&plan)?;
-
-        // Execute plan with executor
-        let result = self.executor.execute_plan(plan).await?;
+        // Validate each step with guardrails, then execute the plan
+        let guardrails = &self.guardrails;
+        let language = &self.language;
+        let validate = |step: &Step| {
+            let plan = Plan::new(vec![step.clone()], String::new());
+            guardrails.validate_all(&plan)?;
+            match language {
+                Some(language) => language.validate(&plan),
+                None => Ok(()),
+            }
+        };
+        let result = self
+            .executor
+            .execute_plan_stream(plan, StreamExecution::ValidateFirst, validate)
+            .await?;
 
         // Return final response
         Ok(result.final_response)
     }
 }
+
+/// Create the provider for one model, used for planning and text
+/// generation steps
+///
+/// All requests count against the same concurrency limits, and long
+/// prompts are compressed if `config` enables it.
+fn model_provider(
+    llm: &LLMConfig,
+    config: &AgentConfig,
+    governor: &Arc<ConcurrencyGovernor>,
+) -> Result<Box<dyn LLMProvider>> {
+    let provider =
+        GovernedProvider::new(create_provider(llm)?, llm.provider.clone(), governor.clone());
+    Ok(match &config.compression {
+        Some(compression) => Box::new(CompressingProvider::new(
+            provider,
+            PromptCompressor::from_config(compression)
+                .with_estimator(TokenEstimator::for_model(&llm.provider, &llm.model)),
+        )),
+        None => Box::new(provider),
+    })
+}
+
+/// Build the memory store the agent keeps its conversation in
+///
+/// Preflight checks run against a store built here, so they see the same
+/// backend as the agent.
+pub fn build_memory() -> Box<dyn MemoryStore> {
+    Box::new(InMemoryStore::new())
+}
+
+/// Build the tool registry for the tools enabled in `config`
+///
+/// All tools are registered when none are listed. Tools with a
+/// `tool_cache` entry reuse results of identical calls for that many seconds.
+pub fn build_tools(config: &AgentConfig) -> ToolRegistry {
+    let mut tools = ToolRegistry::new();
+    let enabled = |name: &str| config.tools.is_empty() || config.tools.iter().any(|t| t == name);
+
+    if enabled("calculator") {
+        tools.register(cached(config, Calculator::new()));
+    }
+    if enabled("file_reader") {
+        tools.register(cached(config, FileReader::new()));
+    }
+    if enabled("web_search") {
+        tools.register(cached(config, WebSearchStub::new()));
+    }
+    tools
+}
+
+/// Box a tool, wrapped in a result cache if `config` sets a TTL for it
+fn cached<T: Tool + 'static>(config: &AgentConfig, tool: T) -> Box<dyn Tool> {
+    match config.tool_cache.get(tool.name()) {
+        Some(&ttl) => Box::new(CachedTool::new(tool, Duration::from_secs(ttl))),
+        None => Box::new(tool),
+    }
+}
+
+/// Build the guardrail registry for the guardrails enabled in `config`
+pub fn build_guardrails(config: &AgentConfig) -> GuardrailRegistry {
+    let mut guardrails = GuardrailRegistry::new();
+
+    if config.guardrails.contains(&"file_path".to_string()) {
+        // Default to allowing /tmp and current directory
+        let allowed_paths = vec![
+            std::path::PathBuf::from("/tmp"),
+            std::env::current_dir().unwrap_or_default(),
+        ];
+        guardrails.register(Box::new(FilePathGuardrail::new(allowed_paths)));
+    }
+    if config.guardrails.contains(&"rate_limit".to_string()) {
+        // Default to 100 calls per minute
+        guardrails.register(Box::new(RateLimitGuardrail::new(100)));
+    }
+    guardrails
+}
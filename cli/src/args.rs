@@ -16,6 +16,14 @@ pub struct CliArgs {
     #[arg(short, long)]
     pub query: Option<String>,
 
+    /// Start a realtime session instead of the regular agent loop
+    #[arg(long, conflicts_with = "query")]
+    pub realtime: bool,
+
+    /// Check configuration, provider, memory, tools and guardrails, then exit
+    #[arg(long, conflicts_with_all = ["query", "realtime"])]
+    pub preflight: bool,
+
     /// Enable verbose logging for debugging
     #[arg(short, long)]
     pub verbose: bool,
@@ -3,6 +3,7 @@
 //! This binary provides a CLI for interacting with AI agents. It supports:
 //! - Single-turn mode: Process one query and exit
 //! - REPL mode: Interactive conversation with the agent
+//! - Realtime mode: Streaming conversation over the OpenAI Realtime API
 //!
 //! # Usage
 //!
@@ -16,6 +17,16 @@
 //! ai-agent --config config.yaml
 //! ```
 //!
+//! Realtime mode:
+//! ```bash
+//! ai-agent --config config.yaml --realtime
+//! ```
+//!
+//! Preflight check (validates configuration and pings the provider):
+//! ```bash
+//! ai-agent --config config.yaml --preflight
+//! ```
+//!
 //! Verbose logging:
 //! ```bash
 //! ai-agent --config config.yaml --verbose
@@ -23,6 +34,7 @@
 
 mod agent;
 mod args;
+mod realtime;
 mod repl;
 mod single;
 
@@ -56,6 +68,22 @@ async fn main() -> anyhow::Result<()> {
         );
     }
 
+    if args.preflight {
+        let memory = agent::build_memory();
+        let report = athena_ai::preflight::preflight(
+            &config,
+            memory.as_ref(),
+            &agent::build_tools(&config),
+            &agent::build_guardrails(&config),
+        )
+        .await;
+        println!("{}", report);
+        if !report.is_ready() {
+            anyhow::bail!("Preflight failed: {} check(s) failed", report.failures().count());
+        }
+        return Ok(());
+    }
+
     // Validate configuration
     config::validate(&config).map_err(|e| {
         eprintln!("{} {}", "Configuration Error:".bright_red().bold(), e);
@@ -66,6 +94,17 @@ async fn main() -> anyhow::Result<()> {
         println!("{}", "Configuration validated successfully".bright_green());
     }
 
+    if args.realtime {
+        if args.verbose {
+            println!("{}", "Starting realtime mode".bright_blue());
+        }
+        realtime::run(&config).await.map_err(|e| {
+            eprintln!("{} {}", "Realtime Error:".bright_red().bold(), e);
+            anyhow::anyhow!("Realtime error: {}", e)
+        })?;
+        return Ok(());
+    }
+
     // Initialize agent
     let mut agent = Agent::new(config).map_err(|e| {
         eprintln!("{} {}", "Initialization Error:".bright_red().bold(), e);
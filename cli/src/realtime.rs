@@ -0,0 +1,100 @@
+//! Realtime mode for conversations over the OpenAI Realtime API.
+//!
+//! This module provides a text frontend for a realtime session: each line
+//! the user types is sent as a conversation item and the response is printed
+//! as it streams in.
+
+use agent_core::{AgentError, Result};
+use colored::Colorize;
+use config::AgentConfig;
+use llm::{RealtimeConfig, RealtimeEvent, RealtimeSession};
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use std::io::Write;
+
+/// Run an interactive realtime session
+///
+/// The session uses the API key from the configuration. Audio output is
+/// disabled since the terminal frontend only displays text.
+///
+/// # Arguments
+/// * `config` - Agent configuration providing the API key
+///
+/// # Errors
+/// Returns an error if:
+/// - The configured provider is not OpenAI
+/// - The session cannot be established
+/// - The connection fails mid-conversation
+pub async fn run(config: &AgentConfig) -> Result<()> {
+    if config.llm.provider != "openai" {
+        return Err(AgentError::Config(format!(
+            "Realtime mode requires the openai provider, got '{}'",
+            config.llm.provider
+        )));
+    }
+
+    let realtime_config = RealtimeConfig {
+        audio_output: false,
+        ..Default::default()
+    };
+    let mut session = RealtimeSession::connect(&config.llm.api_key, realtime_config).await?;
+
+    let mut rl = DefaultEditor::new()
+        .map_err(|e| AgentError::Execution(format!("Failed to initialize REPL: {}", e)))?;
+
+    println!("{}", "AI Agent - Realtime Mode".bright_cyan().bold());
+    println!("Type your messages and press Enter. Type 'exit' to quit.");
+    println!();
+
+    loop {
+        let line = match rl.readline(">> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                return Err(AgentError::Execution(format!(
+                    "Failed to read input: {}",
+                    e
+                )));
+            }
+        };
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed == "exit" || trimmed == "quit" {
+            break;
+        }
+        let _ = rl.add_history_entry(trimmed);
+
+        session.send_text(trimmed).await?;
+        print_response(&mut session).await?;
+    }
+
+    println!("{}", "Goodbye!".bright_green());
+    Ok(())
+}
+
+/// Print streamed response events until the response completes
+async fn print_response(session: &mut RealtimeSession) -> Result<()> {
+    while let Some(event) = session.next_event().await? {
+        match event {
+            RealtimeEvent::TextDelta(text) | RealtimeEvent::TranscriptDelta(text) => {
+                print!("{}", text.bright_white());
+                let _ = std::io::stdout().flush();
+            }
+            RealtimeEvent::ResponseDone => {
+                println!("\n");
+                return Ok(());
+            }
+            RealtimeEvent::Error(message) => {
+                eprintln!("{} {}", "Error:".bright_red().bold(), message);
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+    Err(AgentError::LLMProvider(
+        "Realtime session closed unexpectedly".to_string(),
+    ))
+}
@@ -71,6 +71,8 @@ pub async fn run(mut agent: Agent) -> Result<()> {
                         println!("\n{}", "Available commands:".bright_cyan().bold());
                         println!("  {}  - Exit the REPL", "exit, quit".bright_yellow());
                         println!("  {}     - Show conversation history", "history".bright_yellow());
+                        println!("  {}  - Answer in a locale, or any language with 'off'",
+                            "locale <tag>".bright_yellow());
                         println!("  {}        - Show this help message", "help".bright_yellow());
                         println!();
                         continue;
@@ -78,6 +80,26 @@ pub async fn run(mut agent: Agent) -> Result<()> {
                     _ => {}
                 }
 
+                // Switch the session locale
+                if let Some(tag) = trimmed.strip_prefix("locale") {
+                    let tag = tag.trim();
+                    let locale = match tag {
+                        "" => {
+                            println!("Locale: {}\n", agent.locale().unwrap_or("any language"));
+                            continue;
+                        }
+                        "off" => None,
+                        tag => Some(tag.to_string()),
+                    };
+                    match agent.set_locale(locale) {
+                        Ok(()) => {
+                            println!("Locale: {}\n", agent.locale().unwrap_or("any language"))
+                        }
+                        Err(e) => eprintln!("\n{} {}\n", "Error:".bright_red().bold(), e),
+                    }
+                    continue;
+                }
+
                 // Process the query with the agent
                 match agent.process(trimmed).await {
                     Ok(response) => {
@@ -1,22 +1,29 @@
-use agent_core::{AgentError, Result};
-use reqwest::Client;
+use agent_core::{AgentError, RequestContext, Result};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+use crate::http_error::{check_status, request_error};
+use crate::retry::{RetryPolicy, with_retry_policy};
+use crate::stream::{JsonStream, decode_json_stream};
+
 /// HTTP client for API communication with timeout and retry support
+///
+/// Requests that fail with a timeout, a connection error, a 429 or a 5xx
+/// response are retried according to the client's [`RetryPolicy`]
+/// (3 attempts with jittered exponential backoff by default).
 #[derive(Clone)]
 pub struct ApiClient {
     client: Client,
     timeout: Duration,
+    retry_policy: RetryPolicy,
 }
 
 impl ApiClient {
     /// Create a new ApiClient with default timeout of 30 seconds
     pub fn new() -> Self {
-        Self {
-            client: Client::new(),
-            timeout: Duration::from_secs(30),
-        }
+        Self::with_timeout(Duration::from_secs(30))
     }
 
     /// Create a new ApiClient with custom timeout
@@ -24,9 +31,16 @@ impl ApiClient {
         Self {
             client: Client::new(),
             timeout,
+            retry_policy: RetryPolicy::default(),
         }
     }
 
+    /// Set the retry policy for transient failures
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     /// Send a JSON POST request and deserialize the response
     ///
     /// # Arguments
@@ -40,46 +54,72 @@ impl ApiClient {
         T: Serialize,
         R: for<'de> Deserialize<'de>,
     {
-        let response = self
-            .client
-            .post(url)
+        let response = with_retry_policy(|| self.send_json(url, body), &self.retry_policy).await?;
+
+        // Deserialize the response
+        response
+            .json()
+            .await
+            .map_err(|e| AgentError::LLMProvider(format!("Failed to deserialize response: {}", e)))
+    }
+
+    /// Send a JSON POST request and decode the response body incrementally
+    ///
+    /// The body is read as a sequence of JSON values (e.g. JSON Lines) or
+    /// as one array of them, and each value is yielded as soon as it is
+    /// complete, so multi-megabyte
+    /// responses never need to be buffered in full.
+    ///
+    /// # Arguments
+    /// * `url` - The URL to send the request to
+    /// * `body` - The request body to serialize as JSON
+    ///
+    /// # Returns
+    /// A stream of deserialized values, or an error if the request failed
+    pub async fn post_json_stream<T, R>(&self, url: &str, body: &T) -> Result<JsonStream<R>>
+    where
+        T: Serialize,
+        R: DeserializeOwned + Send + 'static,
+    {
+        // Only the initial request is retried; a stream is not resumed mid-way
+        let response = with_retry_policy(|| self.send_json(url, body), &self.retry_policy).await?;
+        Ok(decode_json_stream(response.bytes_stream()))
+    }
+
+    /// Send a JSON POST request and check the response status
+    async fn send_json<T: Serialize>(&self, url: &str, body: &T) -> Result<Response> {
+        let response = with_request_headers(self.client.post(url))
             .json(body)
             .timeout(self.timeout)
             .send()
             .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AgentError::LLMProvider(format!("Request timeout: {}", e))
-                } else if e.is_connect() {
-                    AgentError::LLMProvider(format!("Connection error: {}", e))
-                } else {
-                    AgentError::LLMProvider(format!("Request failed: {}", e))
-                }
-            })?;
-
-        // Check for HTTP errors
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read error response".to_string());
-            return Err(AgentError::LLMProvider(format!(
-                "HTTP {} error: {}",
-                status, error_text
-            )));
-        }
+            .map_err(|e| request_error("", e))?;
 
-        // Deserialize the response
-        response.json().await.map_err(|e| {
-            AgentError::LLMProvider(format!("Failed to deserialize response: {}", e))
-        })
+        check_status("", response).await
     }
 
     /// Get the configured timeout
     pub fn timeout(&self) -> Duration {
         self.timeout
     }
+
+    /// Get the configured retry policy
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+}
+
+/// Add the trace headers of the current [`RequestContext`], if any
+///
+/// Every outgoing provider request should pass through this so the request
+/// can be correlated with the user request that caused it.
+pub fn with_request_headers(mut builder: RequestBuilder) -> RequestBuilder {
+    if let Some(request) = RequestContext::current() {
+        for (name, value) in request.headers() {
+            builder = builder.header(name, value);
+        }
+    }
+    builder
 }
 
 impl Default for ApiClient {
@@ -92,7 +132,7 @@ impl Default for ApiClient {
 mod tests {
     use super::*;
     use serde::{Deserialize, Serialize};
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -121,6 +161,36 @@ mod tests {
     fn test_default_client() {
         let client = ApiClient::default();
         assert_eq!(client.timeout(), Duration::from_secs(30));
+        assert_eq!(client.retry_policy(), RetryPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_is_retried() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/limited"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("Too Many Requests"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/limited"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(TestResponse {
+                reply: "ok".to_string(),
+            }))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = ApiClient::new()
+            .with_retry_policy(RetryPolicy::new(3).with_initial_delay(Duration::from_millis(1)));
+        let request = TestRequest {
+            message: "Hello".to_string(),
+        };
+        let url = format!("{}/limited", mock_server.uri());
+        let result: Result<TestResponse> = client.post_json(&url, &request).await;
+        assert_eq!(result.unwrap().reply, "ok");
     }
 
     #[tokio::test]
@@ -153,6 +223,29 @@ mod tests {
         assert_eq!(response.reply, "Hello back!");
     }
 
+    #[tokio::test]
+    async fn test_request_context_headers_sent() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/traced"))
+            .and(header("X-Request-Id", "req-7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(TestResponse {
+                reply: "ok".to_string(),
+            }))
+            .mount(&mock_server)
+            .await;
+
+        let client = ApiClient::new();
+        let request = TestRequest {
+            message: "Hello".to_string(),
+        };
+        let url = format!("{}/traced", mock_server.uri());
+        let result: Result<TestResponse> = RequestContext::with_trace_id("req-7")
+            .scope(client.post_json(&url, &request))
+            .await;
+        assert_eq!(result.unwrap().reply, "ok");
+    }
+
     #[tokio::test]
     async fn test_http_error_handling() {
         // Start a mock server
@@ -168,7 +261,7 @@ mod tests {
             .await;
 
         // Make request
-        let client = ApiClient::new();
+        let client = ApiClient::new().with_retry_policy(RetryPolicy::none());
         let request = TestRequest {
             message: "Hello".to_string(),
         };
@@ -265,7 +358,8 @@ mod tests {
             .await;
 
         // Create client with very short timeout
-        let client = ApiClient::with_timeout(Duration::from_millis(100));
+        let client = ApiClient::with_timeout(Duration::from_millis(100))
+            .with_retry_policy(RetryPolicy::none());
         let request = TestRequest {
             message: "Hello".to_string(),
         };
@@ -275,17 +369,17 @@ mod tests {
 
         assert!(result.is_err());
         match result {
-            Err(AgentError::LLMProvider(msg)) => {
-                assert!(msg.contains("timeout") || msg.contains("Request timeout"));
+            Err(AgentError::Timeout(msg)) => {
+                assert!(msg.contains("Request timeout"));
             }
-            _ => panic!("Expected LLMProvider error with timeout"),
+            _ => panic!("Expected Timeout error"),
         }
     }
 
     #[tokio::test]
     async fn test_connection_error() {
         // Use an invalid URL that will cause a connection error
-        let client = ApiClient::new();
+        let client = ApiClient::new().with_retry_policy(RetryPolicy::none());
         let request = TestRequest {
             message: "Hello".to_string(),
         };
@@ -398,4 +492,59 @@ mod tests {
         assert_eq!(response.reply, "Finally!");
         assert_eq!(counter.load(Ordering::SeqCst), 3);
     }
+
+    #[tokio::test]
+    async fn test_post_json_stream() {
+        use futures::StreamExt;
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/batch"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "{\"reply\":\"one\"}\n{\"reply\":\"two\"}\n{\"reply\":\"three\"}\n",
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let client = ApiClient::new();
+        let request = TestRequest {
+            message: "Hello".to_string(),
+        };
+
+        let url = format!("{}/batch", mock_server.uri());
+        let stream = client
+            .post_json_stream::<_, TestResponse>(&url, &request)
+            .await
+            .unwrap();
+        let replies: Vec<String> = stream.map(|r| r.unwrap().reply).collect().await;
+
+        assert_eq!(replies, vec!["one", "two", "three"]);
+    }
+
+    #[tokio::test]
+    async fn test_post_json_stream_http_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/batch"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("Internal Server Error"))
+            .mount(&mock_server)
+            .await;
+
+        let client = ApiClient::new();
+        let request = TestRequest {
+            message: "Hello".to_string(),
+        };
+
+        let url = format!("{}/batch", mock_server.uri());
+        let result = client
+            .post_json_stream::<_, TestResponse>(&url, &request)
+            .await;
+
+        match result {
+            Err(AgentError::LLMProvider(msg)) => assert!(msg.contains("HTTP 500")),
+            _ => panic!("Expected LLMProvider error"),
+        }
+    }
 }
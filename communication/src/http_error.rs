@@ -0,0 +1,192 @@
+//! Mapping of failed HTTP requests to typed [`AgentError`]s.
+//!
+//! Provider clients report failures through these functions so callers
+//! and the retry layer can tell a rate limit from a bad API key or an
+//! oversized prompt by the error variant rather than its message.
+
+use agent_core::AgentError;
+use reqwest::{Response, StatusCode, header::RETRY_AFTER};
+use std::time::Duration;
+
+/// Phrases providers use in 400 responses for prompts that do not fit
+const CONTEXT_LENGTH_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "context length",
+    "context window",
+    "prompt is too long",
+    "exceed context limit",
+    "maximum number of tokens",
+];
+
+/// Phrases providers use for requests blocked by a content filter
+const CONTENT_FILTER_MARKERS: &[&str] = &[
+    "content_filter",
+    "content_policy_violation",
+    "content management policy",
+    "safety system",
+];
+
+/// Map an error from sending a request, prefixing messages with `service`
+///
+/// `service` names the API in messages, e.g. `OpenAI API`; it may be empty.
+pub fn request_error(service: &str, error: reqwest::Error) -> AgentError {
+    if error.is_timeout() {
+        AgentError::Timeout(prefixed(service, &format!("request timeout: {}", error)))
+    } else if error.is_connect() {
+        AgentError::LLMProvider(prefixed(service, &format!("connection error: {}", error)))
+    } else {
+        AgentError::LLMProvider(prefixed(service, &format!("request failed: {}", error)))
+    }
+}
+
+/// Map an unsuccessful response, reading its `Retry-After` header and body
+pub async fn response_error(service: &str, response: Response) -> AgentError {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after);
+    let body = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unable to read error response".to_string());
+    status_error(service, status, retry_after, &body)
+}
+
+/// Return the response unchanged if successful, otherwise its mapped error
+pub async fn check_status(service: &str, response: Response) -> agent_core::Result<Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(response_error(service, response).await)
+    }
+}
+
+/// Map an HTTP status and error body to the matching [`AgentError`]
+///
+/// - 401 and 403 become `AuthFailed`
+/// - 429 becomes `RateLimited`, with the given `Retry-After` delay
+/// - 408 and 504 become `Timeout`
+/// - 413, and 400 responses about the context window, become
+///   `ContextLengthExceeded`
+/// - 400 responses about content filtering become `ContentFiltered`
+/// - anything else stays an `LLMProvider` error
+///
+/// Every message reads `<service> HTTP <status> error: <body>`.
+pub fn status_error(
+    service: &str,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+) -> AgentError {
+    let message = prefixed(service, &format!("HTTP {} error: {}", status, body));
+    let lower = body.to_lowercase();
+    let mentions = |markers: &[&str]| markers.iter().any(|marker| lower.contains(marker));
+
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => AgentError::AuthFailed(message),
+        StatusCode::TOO_MANY_REQUESTS => AgentError::RateLimited {
+            message,
+            retry_after,
+        },
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => AgentError::Timeout(message),
+        StatusCode::PAYLOAD_TOO_LARGE => AgentError::ContextLengthExceeded(message),
+        status if status.is_client_error() && mentions(CONTENT_FILTER_MARKERS) => {
+            AgentError::ContentFiltered(message)
+        }
+        status if status.is_client_error() && mentions(CONTEXT_LENGTH_MARKERS) => {
+            AgentError::ContextLengthExceeded(message)
+        }
+        _ => AgentError::LLMProvider(message),
+    }
+}
+
+/// `text` after the service name, or capitalized without one
+fn prefixed(service: &str, text: &str) -> String {
+    if !service.is_empty() {
+        return format!("{} {}", service, text);
+    }
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Parse a `Retry-After` header given in seconds
+///
+/// The HTTP-date form is not supported, since providers send seconds.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let seconds: f64 = value.trim().parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_classification() {
+        let error = |status: u16, body: &str| {
+            status_error(
+                "Test API",
+                StatusCode::from_u16(status).unwrap(),
+                None,
+                body,
+            )
+        };
+
+        assert!(matches!(
+            error(401, "invalid key"),
+            AgentError::AuthFailed(_)
+        ));
+        assert!(matches!(error(403, "forbidden"), AgentError::AuthFailed(_)));
+        assert!(matches!(error(504, ""), AgentError::Timeout(_)));
+        assert!(matches!(
+            error(400, r#"{"error": {"code": "context_length_exceeded"}}"#),
+            AgentError::ContextLengthExceeded(_)
+        ));
+        assert!(matches!(
+            error(400, "prompt is too long: 210000 tokens > 200000 maximum"),
+            AgentError::ContextLengthExceeded(_)
+        ));
+        assert!(matches!(
+            error(400, r#"{"error": {"code": "content_filter"}}"#),
+            AgentError::ContentFiltered(_)
+        ));
+        assert!(matches!(
+            error(400, "bad request"),
+            AgentError::LLMProvider(_)
+        ));
+        assert!(matches!(
+            error(503, "overloaded"),
+            AgentError::LLMProvider(_)
+        ));
+
+        assert_eq!(
+            status_error("", StatusCode::NOT_FOUND, None, "missing").to_string(),
+            "LLM provider error: HTTP 404 Not Found error: missing"
+        );
+        assert_eq!(
+            error(401, "invalid key").to_string(),
+            "Authentication failed: Test API HTTP 401 Unauthorized error: invalid key"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_keeps_retry_after() {
+        let error = status_error(
+            "Test API",
+            StatusCode::TOO_MANY_REQUESTS,
+            parse_retry_after("12"),
+            "slow down",
+        );
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(12)));
+
+        assert_eq!(parse_retry_after(" 0.5 "), Some(Duration::from_millis(500)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        assert_eq!(parse_retry_after("-1"), None);
+    }
+}
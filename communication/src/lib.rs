@@ -6,8 +6,15 @@
 //! # Features
 //! - JSON POST requests with automatic serialization/deserialization
 //! - Configurable timeouts
-//! - Exponential backoff retry logic
-//! - Proper error handling and conversion
+//! - Trace headers from the current `RequestContext` on every request
+//! - Exponential backoff with jitter for timeouts, rate limits and 5xx
+//!   responses, configurable per client with a `RetryPolicy`
+//! - Typed errors for failed requests (`RateLimited` with the server's
+//!   `Retry-After`, `AuthFailed`, `Timeout`, `ContextLengthExceeded`,
+//!   `ContentFiltered`)
+//! - Incremental JSON decoding for large streamed responses
+//! - Server-sent events decoding for streaming completion APIs
+//! - WebSocket transport for bidirectional realtime APIs
 //!
 //! # Example
 //! ```no_run
@@ -41,7 +48,17 @@
 //! ```
 
 mod client;
+mod http_error;
 mod retry;
+mod sse;
+mod stream;
+mod websocket;
 
-pub use client::ApiClient;
-pub use retry::with_retry;
+pub use client::{ApiClient, with_request_headers};
+pub use http_error::{
+    check_status, parse_retry_after, request_error, response_error, status_error,
+};
+pub use retry::{RetryPolicy, should_retry_error, with_retry, with_retry_policy};
+pub use sse::{SseDecoder, SseEvent, SseStream, decode_sse_stream};
+pub use stream::{DecodeError, JsonStream, JsonStreamDecoder, decode_json_stream};
+pub use websocket::{WebSocketConnection, WebSocketReceiver, WebSocketSender};
@@ -1,8 +1,90 @@
 use agent_core::{AgentError, Result};
+use std::collections::hash_map::RandomState;
 use std::future::Future;
-use std::time::Duration;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
+/// Retry behavior for transient failures
+///
+/// Attempts back off exponentially, starting at `initial_delay` and doubling
+/// after every failed attempt up to `max_delay`. With jitter enabled each
+/// wait is a random duration between half and all of the backoff delay, so
+/// clients rate limited at the same moment do not retry in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first (at least 1)
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound on the delay between attempts
+    pub max_delay: Duration,
+    /// Randomize each delay between half and all of its backoff value
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Create a policy with the given number of attempts, a 1 second initial
+    /// delay, a 30 second maximum delay and jitter
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Set the delay before the first retry
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Set the upper bound on the delay between attempts
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Enable or disable jitter
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Backoff delay before retry number `retry` (starting at 1), without
+    /// jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+
+    /// Delay to wait before retry number `retry`, with jitter applied
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter {
+            return backoff;
+        }
+        let random = RandomState::new().hash_one(Instant::now());
+        let fraction = (random % 1_000) as f64 / 1_000.0;
+        backoff.mul_f64(0.5 + fraction / 2.0)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
 /// Retry an async operation with exponential backoff
 ///
 /// # Arguments
@@ -15,16 +97,29 @@ use tokio::time::sleep;
 /// # Behavior
 /// - Initial delay: 1 second
 /// - Backoff strategy: Exponential (doubles each retry)
-/// - Only retries on network errors and 5xx status codes
+/// - Only retries on network errors, rate limits and 5xx status codes
+/// - Waits for the `Retry-After` delay of rate limit responses that have one,
+///   and gives up with the rate limit error when it exceeds the maximum delay
 /// - Does not retry on 4xx errors (client errors)
-pub async fn with_retry<F, Fut, T>(mut operation: F, max_attempts: u32) -> Result<T>
+pub async fn with_retry<F, Fut, T>(operation: F, max_attempts: u32) -> Result<T>
 where
     F: FnMut() -> Fut,
     Fut: Future<Output = Result<T>>,
 {
     let max_attempts = if max_attempts == 0 { 3 } else { max_attempts };
+    with_retry_policy(operation, &RetryPolicy::new(max_attempts)).await
+}
+
+/// Retry an async operation according to a [`RetryPolicy`]
+///
+/// Uses the same retry classification as [`with_retry`].
+pub async fn with_retry_policy<F, Fut, T>(mut operation: F, policy: &RetryPolicy) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let max_attempts = policy.max_attempts.max(1);
     let mut attempt = 0;
-    let mut delay = Duration::from_secs(1);
 
     loop {
         attempt += 1;
@@ -39,9 +134,15 @@ where
                     return Err(e);
                 }
 
-                // Wait before retrying with exponential backoff
+                // Wait as long as the server asked, or back off exponentially.
+                // A server asking for longer than the policy allows is not
+                // waited for, since retrying any sooner would be rejected.
+                let delay = match e.retry_after() {
+                    Some(retry_after) if retry_after > policy.max_delay => return Err(e),
+                    Some(retry_after) => retry_after,
+                    None => policy.delay(attempt),
+                };
                 sleep(delay).await;
-                delay *= 2;
             }
         }
     }
@@ -49,26 +150,22 @@ where
 
 /// Determine if an error should trigger a retry
 ///
-/// Retries on:
+/// Retries on errors that [`AgentError::is_retryable`] considers transient:
 /// - Network/connection errors
 /// - Timeout errors
+/// - 429 rate limit responses
 /// - 5xx server errors
 ///
 /// Does not retry on:
-/// - 4xx client errors (bad request, auth failure, etc.)
+/// - Authentication failures and other 4xx client errors
+/// - Requests exceeding the context window or blocked by a content filter
 /// - Serialization errors
 /// - Other non-transient errors
-fn should_retry_error(error: &AgentError) -> bool {
-    match error {
-        AgentError::LLMProvider(msg) => {
-            // Retry on network, connection, and timeout errors
-            msg.contains("timeout")
-                || msg.contains("Connection error")
-                || msg.contains("HTTP 5") // 5xx errors
-        }
-        // Don't retry on other error types
-        _ => false,
-    }
+///
+/// Context added with `ResultExt` is looked through, so wrapped errors are
+/// classified by their root cause.
+pub fn should_retry_error(error: &AgentError) -> bool {
+    error.is_retryable()
 }
 
 #[cfg(test)]
@@ -196,6 +293,13 @@ mod tests {
             "HTTP 503 error".to_string()
         )));
 
+        assert!(should_retry_error(&AgentError::LLMProvider(
+            "OpenAI API HTTP 429 Too Many Requests error".to_string()
+        )));
+        assert!(should_retry_error(&AgentError::LLMProvider(
+            "OpenAI API connection error: refused".to_string()
+        )));
+
         // Should not retry
         assert!(!should_retry_error(&AgentError::LLMProvider(
             "HTTP 400 error".to_string()
@@ -210,4 +314,130 @@ mod tests {
             "Planning failed".to_string()
         )));
     }
+
+    #[test]
+    fn test_retry_policy_defaults() {
+        assert_eq!(RetryPolicy::default().max_attempts, 3);
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
+        assert_eq!(RetryPolicy::new(0).max_attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_custom_delay() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+        let policy = RetryPolicy::new(4).with_initial_delay(Duration::from_millis(1));
+
+        let result = with_retry_policy(
+            || {
+                let counter = counter_clone.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Err::<i32, AgentError>(AgentError::LLMProvider(
+                        "Connection error".to_string(),
+                    ))
+                }
+            },
+            &policy,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_waits_for_retry_after() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+        // The backoff alone would make this test take a minute
+        let policy = RetryPolicy::new(2).with_initial_delay(Duration::from_secs(60));
+        let started = Instant::now();
+
+        let result = with_retry_policy(
+            || {
+                let counter = counter_clone.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Err(AgentError::RateLimited {
+                            message: "slow down".to_string(),
+                            retry_after: Some(Duration::from_millis(20)),
+                        })
+                    } else {
+                        Ok(42)
+                    }
+                }
+            },
+            &policy,
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(20) && elapsed < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_beyond_max_delay_fails() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+        let policy = RetryPolicy::new(3).with_max_delay(Duration::from_secs(1));
+        let started = Instant::now();
+
+        let result: Result<()> = with_retry_policy(
+            || {
+                let counter = counter_clone.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Err(AgentError::RateLimited {
+                        message: "come back in an hour".to_string(),
+                        retry_after: Some(Duration::from_secs(3600)),
+                    })
+                }
+            },
+            &policy,
+        )
+        .await;
+
+        assert!(matches!(result, Err(AgentError::RateLimited { .. })));
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_should_retry_typed_errors() {
+        assert!(should_retry_error(&AgentError::Timeout("read".to_string())));
+        assert!(!should_retry_error(&AgentError::AuthFailed(
+            "invalid key".to_string()
+        )));
+        assert!(!should_retry_error(&AgentError::ContextLengthExceeded(
+            "too long".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_backoff_is_capped_and_jittered() {
+        let policy = RetryPolicy::new(10)
+            .with_initial_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+
+        for retry in 1..6 {
+            let delay = policy.delay(retry);
+            assert!(delay >= policy.backoff(retry) / 2 && delay <= policy.backoff(retry));
+        }
+        assert_eq!(policy.with_jitter(false).delay(2), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_should_retry_looks_through_context() {
+        use agent_core::ErrorContext;
+
+        let err = AgentError::LLMProvider("HTTP 503 error".to_string())
+            .wrap(ErrorContext::new("send message").provider("openai"));
+        assert!(should_retry_error(&err));
+    }
 }
@@ -0,0 +1,226 @@
+//! Server-sent events (SSE) decoding from byte streams.
+//!
+//! Streaming completion APIs send their output as `text/event-stream`.
+//! Events are parsed incrementally as bytes arrive; chunks may split an
+//! event, a line, or a multi-byte character at any point.
+
+use agent_core::{AgentError, Result};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::pin::Pin;
+
+/// Boxed stream of decoded server-sent events
+pub type SseStream = Pin<Box<dyn Stream<Item = Result<SseEvent>> + Send>>;
+
+/// A single server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type from the `event:` field, if present
+    pub event: Option<String>,
+    /// Event payload; multiple `data:` lines are joined with newlines
+    pub data: String,
+}
+
+/// Incremental decoder for a `text/event-stream` body
+///
+/// # Example
+/// ```
+/// use communication::SseDecoder;
+///
+/// let mut decoder = SseDecoder::new();
+/// assert!(decoder.push(b"event: ping\ndata: {\"a\"").is_empty());
+/// let events = decoder.push(b": 1}\n\n");
+/// assert_eq!(events[0].event.as_deref(), Some("ping"));
+/// assert_eq!(events[0].data, "{\"a\": 1}");
+/// ```
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// Bytes of the line currently being read
+    line: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Create a new empty decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of bytes and return every event completed by it
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+
+            let line = std::mem::take(&mut self.line);
+            let line = String::from_utf8_lossy(&line);
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if line.is_empty() {
+                events.extend(self.dispatch());
+            } else {
+                self.process_field(line);
+            }
+        }
+        events
+    }
+
+    /// Signal the end of input, returning a final event without a trailing
+    /// blank line, if any
+    pub fn finish(mut self) -> Option<SseEvent> {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.process_field(String::from_utf8_lossy(&line).trim_end_matches('\r'));
+        }
+        self.dispatch()
+    }
+
+    fn process_field(&mut self, line: &str) {
+        // Lines starting with ':' are comments (often used as keep-alives)
+        if line.starts_with(':') {
+            return;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some(SseEvent { event, data })
+    }
+}
+
+/// Decode a byte stream into a stream of server-sent events
+///
+/// # Arguments
+/// * `bytes` - Stream of byte chunks, such as `reqwest::Response::bytes_stream()`
+///
+/// # Returns
+/// A stream yielding each event, or an error if the transport fails. The
+/// stream ends after the first error.
+pub fn decode_sse_stream<S, B, E>(bytes: S) -> SseStream
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    struct State<S> {
+        bytes: Pin<Box<S>>,
+        decoder: Option<SseDecoder>,
+        ready: VecDeque<SseEvent>,
+    }
+
+    let state = State {
+        bytes: Box::pin(bytes),
+        decoder: Some(SseDecoder::new()),
+        ready: VecDeque::new(),
+    };
+
+    let stream = stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.ready.pop_front() {
+                return Some((Ok(event), state));
+            }
+
+            let decoder = state.decoder.as_mut()?;
+            match state.bytes.next().await {
+                Some(Ok(chunk)) => state.ready.extend(decoder.push(chunk.as_ref())),
+                Some(Err(e)) => {
+                    state.decoder = None;
+                    let error = AgentError::LLMProvider(format!("Stream read failed: {}", e));
+                    return Some((Err(error), state));
+                }
+                None => {
+                    let decoder = state.decoder.take()?;
+                    state.ready.extend(decoder.finish());
+                }
+            }
+        }
+    });
+
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiple_events_in_one_chunk() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(b"data: one\n\ndata: two\n\n");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].data, "two");
+        assert_eq!(events[1].event, None);
+    }
+
+    #[test]
+    fn test_comments_crlf_and_multiline_data() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(b": keep-alive\r\n\r\ndata: a\r\ndata: b\r\n\r\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "a\nb");
+    }
+
+    #[test]
+    fn test_split_utf8_character() {
+        let mut decoder = SseDecoder::new();
+        let bytes = "data: héllo\n\n".as_bytes();
+        let split = bytes.iter().position(|&b| b == 0xC3).unwrap() + 1;
+        assert!(decoder.push(&bytes[..split]).is_empty());
+        assert_eq!(decoder.push(&bytes[split..])[0].data, "héllo");
+    }
+
+    #[test]
+    fn test_finish_flushes_last_event() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(b"data: [DONE]").is_empty());
+        assert_eq!(decoder.finish().unwrap().data, "[DONE]");
+    }
+
+    #[tokio::test]
+    async fn test_decode_sse_stream() {
+        let chunks: Vec<std::result::Result<&[u8], String>> = vec![
+            Ok(b"event: delta\nda"),
+            Ok(b"ta: hi\n\nevent: stop\ndata: {}\n\n"),
+        ];
+        let events: Vec<_> = decode_sse_stream(stream::iter(chunks))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .map(|event| event.unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.as_deref(), Some("delta"));
+        assert_eq!(events[0].data, "hi");
+        assert_eq!(events[1].event.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_transport_error_ends_stream() {
+        let chunks: Vec<std::result::Result<&[u8], String>> =
+            vec![Ok(b"data: a\n\n"), Err("reset".to_string())];
+        let results: Vec<_> = decode_sse_stream(stream::iter(chunks)).collect().await;
+        assert_eq!(results.len(), 2);
+        assert!(
+            results[1]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("reset")
+        );
+    }
+}
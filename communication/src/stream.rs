@@ -0,0 +1,479 @@
+//! Incremental JSON decoding from byte streams.
+//!
+//! Large responses (batch results, long completions) are parsed value by value
+//! as bytes arrive instead of buffering the whole body. The decoder only keeps
+//! the bytes of the value currently being read, so memory stays flat no matter
+//! how large the full response is.
+//!
+//! This is for bodies holding many values, such as JSON Lines or one large
+//! array of results; Ollama's streamed chat responses are read this way. The
+//! OpenAI and Anthropic providers don't use it: their plain responses are a
+//! single object, which is no use until it is complete, so
+//! [`ApiClient::post_json`](crate::ApiClient::post_json) reads them whole, and
+//! their streamed responses are server-sent events, read by
+//! [`SseDecoder`](crate::SseDecoder).
+
+use agent_core::{AgentError, Result};
+use futures::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// Boxed stream of decoded JSON values
+pub type JsonStream<T> = Pin<Box<dyn Stream<Item = Result<T>> + Send>>;
+
+/// Error from [`JsonStreamDecoder::push`], with the values the chunk
+/// completed before the error
+pub struct DecodeError<T> {
+    /// Values completed before the malformed input, in order
+    pub values: Vec<T>,
+    /// What was wrong with the input
+    pub error: AgentError,
+}
+
+impl<T> fmt::Debug for DecodeError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodeError")
+            .field("values", &self.values.len())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<T> fmt::Display for DecodeError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<T> From<DecodeError<T>> for AgentError {
+    fn from(error: DecodeError<T>) -> Self {
+        error.error
+    }
+}
+
+/// Incremental decoder for a sequence of JSON objects or arrays
+///
+/// Accepts newline-delimited JSON (JSON Lines) as well as values that are
+/// simply concatenated or separated by commas. A top-level array is read as
+/// a list of values: each element is returned as soon as it is complete,
+/// rather than the whole array at the end. Chunks may split a value at any
+/// byte, including inside strings and escape sequences.
+///
+/// # Example
+/// ```
+/// use communication::JsonStreamDecoder;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Item {
+///     id: u32,
+/// }
+///
+/// let mut decoder = JsonStreamDecoder::<Item>::new();
+/// assert_eq!(decoder.push(b"{\"id\": 1}\n{\"i").unwrap().len(), 1);
+/// let items = decoder.push(b"d\": 2}\n").unwrap();
+/// assert_eq!(items[0].id, 2);
+/// decoder.finish().unwrap();
+///
+/// let mut decoder = JsonStreamDecoder::<Item>::new();
+/// assert_eq!(decoder.push(b"[{\"id\": 3}, {\"id\"").unwrap()[0].id, 3);
+/// assert_eq!(decoder.push(b": 4}]").unwrap()[0].id, 4);
+/// decoder.finish().unwrap();
+/// ```
+pub struct JsonStreamDecoder<T> {
+    buffer: Vec<u8>,
+    /// Position in `buffer` up to which bytes have been scanned
+    scan_pos: usize,
+    /// Start of the value currently being read, if any
+    start: Option<usize>,
+    depth: usize,
+    /// Whether the values being read are elements of a top-level array
+    in_array: bool,
+    in_string: bool,
+    escaped: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> JsonStreamDecoder<T> {
+    /// Create a new empty decoder
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            scan_pos: 0,
+            start: None,
+            depth: 0,
+            in_array: false,
+            in_string: false,
+            escaped: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Feed a chunk of bytes and return every value completed by it
+    ///
+    /// # Errors
+    /// Returns an error if the input contains a top-level value or array
+    /// element that is not an object or array, or if a completed value fails
+    /// to deserialize. The
+    /// error carries the values the chunk completed before the bad input.
+    pub fn push(&mut self, chunk: &[u8]) -> std::result::Result<Vec<T>, DecodeError<T>> {
+        self.buffer.extend_from_slice(chunk);
+        let mut values = Vec::new();
+
+        while self.scan_pos < self.buffer.len() {
+            let i = self.scan_pos;
+            let byte = self.buffer[i];
+            self.scan_pos += 1;
+
+            let Some(start) = self.start else {
+                match byte {
+                    b'[' if !self.in_array => self.in_array = true,
+                    b']' if self.in_array => self.in_array = false,
+                    b'{' | b'[' => {
+                        self.start = Some(i);
+                        self.depth = 1;
+                    }
+                    b',' => {}
+                    b if b.is_ascii_whitespace() => {}
+                    other => {
+                        return Err(DecodeError {
+                            values,
+                            error: AgentError::LLMProvider(format!(
+                                "Unexpected byte '{}' between JSON values",
+                                other.escape_ascii()
+                            )),
+                        });
+                    }
+                }
+                continue;
+            };
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        self.start = None;
+                        match serde_json::from_slice(&self.buffer[start..=i]) {
+                            Ok(value) => values.push(value),
+                            Err(e) => {
+                                return Err(DecodeError {
+                                    values,
+                                    error: AgentError::LLMProvider(format!(
+                                        "Failed to deserialize streamed value: {}",
+                                        e
+                                    )),
+                                });
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Drop everything before the value in progress so the buffer only
+        // ever holds a single partial value
+        let keep_from = self.start.unwrap_or(self.buffer.len());
+        self.buffer.drain(..keep_from);
+        self.scan_pos -= keep_from;
+        if let Some(start) = self.start.as_mut() {
+            *start = 0;
+        }
+
+        Ok(values)
+    }
+
+    /// Signal the end of input
+    ///
+    /// # Errors
+    /// Returns an error if the stream ended in the middle of a value or of a
+    /// top-level array.
+    pub fn finish(self) -> Result<()> {
+        if self.start.is_some() || self.in_array {
+            return Err(AgentError::LLMProvider(format!(
+                "Stream ended with {} bytes of incomplete JSON",
+                self.buffer.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Number of bytes currently buffered for an incomplete value
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl<T: DeserializeOwned> Default for JsonStreamDecoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode a byte stream into a stream of JSON values
+///
+/// # Arguments
+/// * `bytes` - Stream of byte chunks, such as `reqwest::Response::bytes_stream()`
+///
+/// # Returns
+/// A stream yielding each decoded value, or an error if the transport fails or
+/// the data is malformed. Values completed before malformed data are still
+/// yielded. The stream ends after the first error.
+pub fn decode_json_stream<S, B, E, T>(bytes: S) -> JsonStream<T>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Display,
+    T: DeserializeOwned + Send + 'static,
+{
+    struct State<S, T> {
+        bytes: Pin<Box<S>>,
+        decoder: Option<JsonStreamDecoder<T>>,
+        ready: VecDeque<T>,
+        failed: Option<AgentError>,
+    }
+
+    let state = State {
+        bytes: Box::pin(bytes),
+        decoder: Some(JsonStreamDecoder::new()),
+        ready: VecDeque::new(),
+        failed: None,
+    };
+
+    let stream = stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(value) = state.ready.pop_front() {
+                return Some((Ok(value), state));
+            }
+            if let Some(error) = state.failed.take() {
+                return Some((Err(error), state));
+            }
+
+            let decoder = state.decoder.as_mut()?;
+            match state.bytes.next().await {
+                Some(Ok(chunk)) => match decoder.push(chunk.as_ref()) {
+                    Ok(values) => state.ready.extend(values),
+                    Err(DecodeError { values, error }) => {
+                        // Yield what was decoded before the error, then the error
+                        state.decoder = None;
+                        state.ready.extend(values);
+                        state.failed = Some(error);
+                    }
+                },
+                Some(Err(e)) => {
+                    state.decoder = None;
+                    let error = AgentError::LLMProvider(format!("Stream read failed: {}", e));
+                    return Some((Err(error), state));
+                }
+                None => {
+                    let decoder = state.decoder.take()?;
+                    return match decoder.finish() {
+                        Ok(()) => None,
+                        Err(e) => Some((Err(e), state)),
+                    };
+                }
+            }
+        }
+    });
+
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: u32,
+        text: String,
+    }
+
+    #[test]
+    fn test_decode_json_lines() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        let items = decoder
+            .push(b"{\"id\":1,\"text\":\"a\"}\n{\"id\":2,\"text\":\"b\"}\n")
+            .unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].id, 2);
+        assert_eq!(decoder.buffered_len(), 0);
+        decoder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_value_split_across_chunks() {
+        let input = b"{\"id\":7,\"text\":\"hello world\"}";
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+
+        for byte in &input[..input.len() - 1] {
+            assert!(decoder.push(&[*byte]).unwrap().is_empty());
+        }
+        let items = decoder.push(&input[input.len() - 1..]).unwrap();
+
+        assert_eq!(
+            items,
+            vec![Item {
+                id: 7,
+                text: "hello world".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_braces_and_escapes_inside_strings() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        let items = decoder
+            .push(br#"{"id":1,"text":"not } a \"brace\" {"}"#)
+            .unwrap();
+
+        assert_eq!(items[0].text, "not } a \"brace\" {");
+    }
+
+    #[test]
+    fn test_comma_separated_values() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        let items = decoder
+            .push(b"{\"id\":1,\"text\":\"a\"}, {\"id\":2,\"text\":\"b\"}")
+            .unwrap();
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn test_top_level_array_elements_are_emitted_as_they_complete() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        let items = decoder
+            .push(b"[{\"id\":1,\"text\":\"a\"}, {\"id\":2,")
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(decoder.buffered_len(), b"{\"id\":2,".len());
+
+        let items = decoder.push(b"\"text\":\"[b]\"}\n]\n").unwrap();
+        assert_eq!(items[0].text, "[b]");
+        decoder.finish().unwrap();
+
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        decoder.push(b"[{\"id\":1,\"text\":\"a\"}").unwrap();
+        let err = decoder.finish().unwrap_err();
+        assert!(err.to_string().contains("incomplete JSON"));
+        assert!(JsonStreamDecoder::<Item>::new().push(b"[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_buffer_only_holds_partial_value() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        decoder
+            .push(b"{\"id\":1,\"text\":\"a\"}\n{\"id\":")
+            .unwrap();
+        assert_eq!(decoder.buffered_len(), b"{\"id\":".len());
+    }
+
+    #[test]
+    fn test_incomplete_value_fails_on_finish() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        decoder.push(b"{\"id\":1,").unwrap();
+        let err = decoder.finish().unwrap_err();
+        assert!(err.to_string().contains("incomplete JSON"));
+    }
+
+    #[test]
+    fn test_unexpected_top_level_byte() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        assert!(decoder.push(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        let err = decoder.push(b"{\"id\":\"nope\"}").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Failed to deserialize streamed value")
+        );
+    }
+
+    #[test]
+    fn test_error_keeps_values_decoded_before_it() {
+        let mut decoder = JsonStreamDecoder::<Item>::new();
+        let err = decoder
+            .push(b"{\"id\":1,\"text\":\"a\"}\n{\"id\":\"nope\"}\n")
+            .unwrap_err();
+        assert_eq!(err.values.len(), 1);
+        assert_eq!(err.values[0].id, 1);
+        assert!(err.to_string().contains("Failed to deserialize"));
+    }
+
+    #[tokio::test]
+    async fn test_decode_json_stream() {
+        let chunks: Vec<std::result::Result<Vec<u8>, String>> = vec![
+            Ok(b"{\"id\":1,\"te".to_vec()),
+            Ok(b"xt\":\"a\"}\n{\"id\":2,\"text\":\"b\"}".to_vec()),
+        ];
+
+        let items: Vec<Result<Item>> = decode_json_stream(stream::iter(chunks)).collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap().id, 1);
+        assert_eq!(items[1].as_ref().unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_decode_json_stream_transport_error() {
+        let chunks: Vec<std::result::Result<Vec<u8>, String>> = vec![
+            Ok(b"{\"id\":1,\"text\":\"a\"}".to_vec()),
+            Err("connection reset".to_string()),
+            Ok(b"{\"id\":2,\"text\":\"b\"}".to_vec()),
+        ];
+
+        let items: Vec<Result<Item>> = decode_json_stream(stream::iter(chunks)).collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_ok());
+        assert!(
+            items[1]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("connection reset")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decode_json_stream_yields_values_before_bad_data() {
+        let chunks: Vec<std::result::Result<Vec<u8>, String>> =
+            vec![Ok(b"{\"id\":1,\"text\":\"a\"}\ngarbage".to_vec())];
+
+        let items: Vec<Result<Item>> = decode_json_stream(stream::iter(chunks)).collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap().id, 1);
+        assert!(items[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_json_stream_truncated() {
+        let chunks: Vec<std::result::Result<Vec<u8>, String>> = vec![Ok(b"{\"id\":1".to_vec())];
+        let items: Vec<Result<Item>> = decode_json_stream(stream::iter(chunks)).collect().await;
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+}
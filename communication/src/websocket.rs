@@ -0,0 +1,219 @@
+//! WebSocket transport for bidirectional JSON messaging.
+//!
+//! Used by realtime APIs that stream events in both directions over a
+//! single long-lived connection. Messages are exchanged as JSON text frames;
+//! ping/pong and other control frames are handled transparently.
+
+use agent_core::{AgentError, Result};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message as Frame;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// An open WebSocket connection exchanging JSON messages
+pub struct WebSocketConnection {
+    stream: Stream,
+}
+
+impl WebSocketConnection {
+    /// Open a connection, sending the given headers with the handshake
+    ///
+    /// # Arguments
+    /// * `url` - The `ws://` or `wss://` URL to connect to
+    /// * `headers` - Extra handshake headers (e.g. authorization)
+    pub async fn connect(url: &str, headers: &[(String, String)]) -> Result<Self> {
+        let mut request = url
+            .into_client_request()
+            .map_err(|e| AgentError::Config(format!("Invalid WebSocket URL '{}': {}", url, e)))?;
+
+        for (name, value) in headers {
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                AgentError::Config(format!("Invalid WebSocket header '{}': {}", name, e))
+            })?;
+            let name: tokio_tungstenite::tungstenite::http::HeaderName =
+                name.parse().map_err(|e| {
+                    AgentError::Config(format!("Invalid WebSocket header '{}': {}", name, e))
+                })?;
+            request.headers_mut().insert(name, value);
+        }
+
+        let (stream, _response) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| AgentError::LLMProvider(format!("WebSocket connection error: {}", e)))?;
+
+        Ok(Self { stream })
+    }
+
+    /// Serialize and send a JSON message
+    pub async fn send_json<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        send_json(&mut self.stream, message).await
+    }
+
+    /// Receive the next JSON message, or `None` once the connection is closed
+    pub async fn recv_json<R: DeserializeOwned>(&mut self) -> Result<Option<R>> {
+        recv_json(&mut self.stream).await
+    }
+
+    /// Close the connection
+    pub async fn close(mut self) -> Result<()> {
+        self.stream
+            .close(None)
+            .await
+            .map_err(|e| AgentError::LLMProvider(format!("WebSocket close failed: {}", e)))
+    }
+
+    /// Split into independent sending and receiving halves
+    ///
+    /// The halves can be moved to separate tasks so the connection can be
+    /// read and written concurrently.
+    pub fn split(self) -> (WebSocketSender, WebSocketReceiver) {
+        let (sink, stream) = self.stream.split();
+        (WebSocketSender { sink }, WebSocketReceiver { stream })
+    }
+}
+
+/// Sending half of a [`WebSocketConnection`]
+pub struct WebSocketSender {
+    sink: SplitSink<Stream, Frame>,
+}
+
+impl WebSocketSender {
+    /// Serialize and send a JSON message
+    pub async fn send_json<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        send_json(&mut self.sink, message).await
+    }
+
+    /// Close the connection
+    pub async fn close(mut self) -> Result<()> {
+        self.sink
+            .close()
+            .await
+            .map_err(|e| AgentError::LLMProvider(format!("WebSocket close failed: {}", e)))
+    }
+}
+
+/// Receiving half of a [`WebSocketConnection`]
+pub struct WebSocketReceiver {
+    stream: SplitStream<Stream>,
+}
+
+impl WebSocketReceiver {
+    /// Receive the next JSON message, or `None` once the connection is closed
+    pub async fn recv_json<R: DeserializeOwned>(&mut self) -> Result<Option<R>> {
+        recv_json(&mut self.stream).await
+    }
+}
+
+async fn send_json<S, T>(sink: &mut S, message: &T) -> Result<()>
+where
+    S: futures::Sink<Frame, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+    T: Serialize,
+{
+    let text = serde_json::to_string(message)?;
+    sink.send(Frame::Text(text))
+        .await
+        .map_err(|e| AgentError::LLMProvider(format!("WebSocket send failed: {}", e)))
+}
+
+async fn recv_json<S, R>(stream: &mut S) -> Result<Option<R>>
+where
+    S: futures::Stream<Item = std::result::Result<Frame, tokio_tungstenite::tungstenite::Error>>
+        + Unpin,
+    R: DeserializeOwned,
+{
+    while let Some(frame) = stream.next().await {
+        let frame =
+            frame.map_err(|e| AgentError::LLMProvider(format!("WebSocket read failed: {}", e)))?;
+        let text = match frame {
+            Frame::Text(text) => text.to_string(),
+            Frame::Binary(data) => String::from_utf8(data.to_vec()).map_err(|e| {
+                AgentError::LLMProvider(format!("WebSocket frame is not UTF-8: {}", e))
+            })?,
+            Frame::Close(_) => return Ok(None),
+            // Ping/pong are answered by tungstenite itself
+            _ => continue,
+        };
+        return serde_json::from_str(&text).map(Some).map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize WebSocket message: {}", e))
+        });
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use tokio::net::TcpListener;
+
+    /// Start a server that echoes every text frame back, returning its URL
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            while let Some(Ok(frame)) = ws.next().await {
+                if frame.is_text() {
+                    ws.send(frame).await.unwrap();
+                } else if frame.is_close() {
+                    break;
+                }
+            }
+        });
+        format!("ws://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_send_and_receive_json() {
+        let url = echo_server().await;
+        let mut conn =
+            WebSocketConnection::connect(&url, &[("X-Test".to_string(), "1".to_string())])
+                .await
+                .unwrap();
+
+        conn.send_json(&json!({"type": "ping", "n": 1}))
+            .await
+            .unwrap();
+        let reply: Value = conn.recv_json().await.unwrap().unwrap();
+        assert_eq!(reply["n"], 1);
+
+        conn.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_split_halves() {
+        let url = echo_server().await;
+        let conn = WebSocketConnection::connect(&url, &[]).await.unwrap();
+        let (mut sender, mut receiver) = conn.split();
+
+        sender.send_json(&json!({"type": "a"})).await.unwrap();
+        sender.send_json(&json!({"type": "b"})).await.unwrap();
+
+        let first: Value = receiver.recv_json().await.unwrap().unwrap();
+        let second: Value = receiver.recv_json().await.unwrap().unwrap();
+        assert_eq!(first["type"], "a");
+        assert_eq!(second["type"], "b");
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused() {
+        let result = WebSocketConnection::connect("ws://127.0.0.1:1", &[]).await;
+        assert!(
+            matches!(result, Err(AgentError::LLMProvider(msg)) if msg.contains("connection error"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_url() {
+        let result = WebSocketConnection::connect("not a url", &[]).await;
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+}
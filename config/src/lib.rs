@@ -17,6 +17,7 @@
 
 use agent_core::{AgentError, Result};
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 /// Top-level configuration structure for the AI agent framework
@@ -32,6 +33,47 @@ pub struct AgentConfig {
     /// List of enabled guardrails
     #[serde(default)]
     pub guardrails: Vec<String>,
+    /// Limits on simultaneous LLM requests
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
+    /// Behaviour when the model service is unavailable
+    #[serde(default)]
+    pub degradation: DegradationConfig,
+    /// BCP 47 locale responses must be written in (e.g. `de-AT`), if fixed
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Seconds to reuse results of identical calls, by tool name
+    #[serde(default)]
+    pub tool_cache: HashMap<String, u64>,
+    /// Transformations applied to messages before they are sent, in order
+    #[serde(default)]
+    pub transforms: Vec<MessageTransformConfig>,
+    /// Stages applied to generated text before it is returned, in order
+    #[serde(default)]
+    pub post_processing: Vec<PostProcessorConfig>,
+    /// Additional models plan steps can select by name, e.g. a cheap
+    /// `summarizer` and a strong `coder`
+    ///
+    /// ```yaml
+    /// profiles:
+    ///   summarizer:
+    ///     provider: openai
+    ///     model: gpt-4o-mini
+    ///     api_key: ${OPENAI_API_KEY}
+    ///   coder:
+    ///     provider: anthropic
+    ///     model: claude-sonnet-4-5
+    ///     api_key: ${ANTHROPIC_API_KEY}
+    /// ```
+    #[serde(default)]
+    pub profiles: HashMap<String, LLMConfig>,
+    /// Compression of long prompts before they are sent; off if unset
+    #[serde(default)]
+    pub compression: Option<CompressionConfig>,
+    /// Token and dollar limits on each query's model calls; unlimited if
+    /// unset
+    #[serde(default)]
+    pub budget: Option<BudgetConfig>,
 }
 
 /// Configuration for LLM providers (OpenAI, Anthropic, etc.)
@@ -49,6 +91,185 @@ pub struct LLMConfig {
     /// Maximum tokens in response
     #[serde(default = "default_max_tokens")]
     pub max_tokens: usize,
+    /// OpenAI organization ID, sent as the `OpenAI-Organization` header
+    #[serde(default)]
+    pub organization: Option<String>,
+    /// OpenAI project ID, sent as the `OpenAI-Project` header
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Gemini blocking threshold for each harm category; categories not
+    /// listed use the API's default
+    #[serde(default)]
+    pub safety_settings: BTreeMap<HarmCategory, HarmBlockThreshold>,
+    /// Azure OpenAI resource and deployment; required by the azure provider
+    #[serde(default)]
+    pub azure: Option<AzureConfig>,
+    /// OpenRouter fallback models, attribution headers and provider routing;
+    /// only used by the openrouter provider
+    #[serde(default)]
+    pub openrouter: Option<OpenRouterConfig>,
+    /// Mark the system prompt and conversation for Anthropic prompt caching,
+    /// so repeated prefixes are billed at the cache rate
+    #[serde(default)]
+    pub prompt_caching: bool,
+}
+
+/// Azure OpenAI deployment the `azure` provider sends requests to
+///
+/// ```yaml
+/// llm:
+///   provider: azure
+///   model: gpt-4o
+///   api_key: ${AZURE_OPENAI_API_KEY}
+///   azure:
+///     endpoint: https://my-resource.openai.azure.com
+///     deployment: chat-prod
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AzureConfig {
+    /// Resource endpoint, e.g. `https://my-resource.openai.azure.com`
+    pub endpoint: String,
+    /// Deployment name; defaults to the model name
+    #[serde(default)]
+    pub deployment: Option<String>,
+    /// Value of the `api-version` query parameter
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+}
+
+impl AzureConfig {
+    /// Target a resource endpoint with the default API version
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            deployment: None,
+            api_version: default_azure_api_version(),
+        }
+    }
+}
+
+/// OpenRouter settings of the `openrouter` provider
+///
+/// ```yaml
+/// llm:
+///   provider: openrouter
+///   model: anthropic/claude-sonnet-4
+///   api_key: ${OPENROUTER_API_KEY}
+///   openrouter:
+///     fallback_models: [openai/gpt-4o, google/gemini-2.5-pro]
+///     app_name: Athena
+///     routing:
+///       order: [anthropic, amazon-bedrock]
+///       data_collection: deny
+///       sort: latency
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct OpenRouterConfig {
+    /// Models tried in order when the main model is unavailable, rate
+    /// limited or refuses the request
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+    /// URL of the calling app, sent as the `HTTP-Referer` header so
+    /// OpenRouter can attribute usage to it
+    #[serde(default)]
+    pub site_url: Option<String>,
+    /// Name of the calling app, sent as the `X-Title` header
+    #[serde(default)]
+    pub app_name: Option<String>,
+    /// Which upstream providers may serve the requests
+    #[serde(default)]
+    pub routing: ProviderRouting,
+}
+
+/// Preferences for the upstream providers OpenRouter routes requests to
+///
+/// Provider names are OpenRouter's slugs, e.g. `anthropic`, `together` or
+/// `amazon-bedrock`. Unset fields use OpenRouter's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ProviderRouting {
+    /// Providers to try first, in order
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// Whether providers outside `order` may serve the request when those
+    /// in it fail
+    #[serde(default)]
+    pub allow_fallbacks: Option<bool>,
+    /// Only use providers that support every parameter of the request
+    #[serde(default)]
+    pub require_parameters: Option<bool>,
+    /// Whether providers that may store or train on prompts may be used
+    #[serde(default)]
+    pub data_collection: Option<DataCollection>,
+    /// Only ever use these providers
+    #[serde(default)]
+    pub only: Vec<String>,
+    /// Never use these providers
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Rank providers by this instead of OpenRouter's load balancing
+    #[serde(default)]
+    pub sort: Option<ProviderSort>,
+}
+
+impl ProviderRouting {
+    /// Whether every preference is left to OpenRouter's defaults
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Whether OpenRouter may route to providers that retain prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataCollection {
+    /// Any provider may be used
+    Allow,
+    /// Only providers that neither store nor train on prompts
+    Deny,
+}
+
+/// What OpenRouter ranks upstream providers by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderSort {
+    /// Lowest price first
+    Price,
+    /// Highest tokens per second first
+    Throughput,
+    /// Lowest time to first token first
+    Latency,
+}
+
+/// Category of harmful content filtered by Gemini's safety settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HarmCategory {
+    /// Negative or harmful comments targeting identity or protected attributes
+    Harassment,
+    /// Rude, disrespectful or profane content
+    HateSpeech,
+    /// References to sexual acts or other lewd content
+    SexuallyExplicit,
+    /// Content that promotes or enables access to harmful goods or activities
+    DangerousContent,
+    /// Content that may be used to harm civic integrity
+    CivicIntegrity,
+}
+
+/// Likelihood of harm at and above which Gemini blocks content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HarmBlockThreshold {
+    /// Always show content
+    BlockNone,
+    /// Block content with a high probability of harm
+    BlockOnlyHigh,
+    /// Block content with a medium or high probability of harm
+    BlockMediumAndAbove,
+    /// Block content with a low, medium or high probability of harm
+    BlockLowAndAbove,
+    /// Turn the safety filter off
+    Off,
 }
 
 /// Configuration for the memory system
@@ -60,6 +281,213 @@ pub struct MemoryConfig {
     /// Token budget for context window
     #[serde(default = "default_token_budget")]
     pub token_budget: usize,
+    /// How long stored transcripts and memories are kept
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+/// How long stored transcripts and memories are kept
+///
+/// Messages older than the applicable rule's `max_age_days` are deleted or
+/// anonymized by the retention job. A rule for the session wins over a
+/// rule for its user, which wins over the default; data no rule applies to
+/// is kept forever.
+///
+/// ```yaml
+/// memory:
+///   retention:
+///     max_age_days: 90
+///     action: anonymize
+///     users:
+///       u-42: { max_age_days: 30, action: delete }
+///     sessions:
+///       audit-7: { max_age_days: 365 }
+///     sweep_interval_secs: 3600
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RetentionConfig {
+    /// Age in days after which data expires, unless a user or session rule
+    /// applies; kept forever if unset
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// What happens to expired data under the default rule
+    #[serde(default)]
+    pub action: RetentionAction,
+    /// Rules for the sessions of individual users, by user ID
+    #[serde(default)]
+    pub users: HashMap<String, RetentionRuleConfig>,
+    /// Rules for individual sessions, by session ID
+    #[serde(default)]
+    pub sessions: HashMap<String, RetentionRuleConfig>,
+    /// Seconds between retention sweeps; hourly if unset
+    #[serde(default)]
+    pub sweep_interval_secs: Option<u64>,
+}
+
+/// A retention rule for one user or session
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RetentionRuleConfig {
+    /// Age in days after which data expires
+    pub max_age_days: u32,
+    /// What happens to expired data
+    #[serde(default)]
+    pub action: RetentionAction,
+}
+
+/// What happens to data once it expires
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    /// Remove the messages entirely
+    #[default]
+    Delete,
+    /// Keep the messages' role and time but replace their content, and
+    /// drop anything linking them to the user
+    Anonymize,
+}
+
+/// Limits on simultaneous LLM requests and plan steps
+///
+/// ```yaml
+/// concurrency:
+///   max_concurrent_requests: 16
+///   per_provider:
+///     openai: 8
+///     anthropic: 4
+///   max_concurrent_steps: 4
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConcurrencyConfig {
+    /// Maximum simultaneous requests across all providers, if limited
+    #[serde(default)]
+    pub max_concurrent_requests: Option<usize>,
+    /// Maximum simultaneous requests to each named provider
+    #[serde(default)]
+    pub per_provider: HashMap<String, usize>,
+    /// Maximum steps of a plan's parallel groups run at the same time;
+    /// grouped steps run one after another if unset
+    #[serde(default)]
+    pub max_concurrent_steps: Option<usize>,
+}
+
+/// Behaviour when the model service is down and fallbacks are exhausted
+///
+/// By default nothing is cached and callers receive a
+/// `ServiceDegraded` error.
+///
+/// ```yaml
+/// degradation:
+///   cache_responses: 100
+///   fallback_response: "The assistant is temporarily unavailable."
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DegradationConfig {
+    /// Number of recent successful responses kept to answer repeated
+    /// requests during an outage
+    #[serde(default)]
+    pub cache_responses: usize,
+    /// Fixed reply returned to users instead of an error during an outage
+    #[serde(default)]
+    pub fallback_response: Option<String>,
+}
+
+/// A declarative transformation applied to messages before they are sent
+///
+/// ```yaml
+/// transforms:
+///   - type: strip_html
+///   - type: normalize_whitespace
+///   - type: truncate_code_blocks
+///     max_lines: 40
+///   - type: inject_date_time
+///     format: "%A, %d %B %Y %H:%M UTC"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageTransformConfig {
+    /// Remove HTML tags, scripts and styles, and decode common entities
+    StripHtml,
+    /// Collapse runs of spaces and blank lines outside code blocks
+    NormalizeWhitespace,
+    /// Shorten fenced code blocks longer than `max_lines`
+    TruncateCodeBlocks {
+        /// Lines of each code block to keep
+        max_lines: usize,
+    },
+    /// Tell the model the current date and time in a system message
+    InjectDateTime {
+        /// `strftime` format of the timestamp, in UTC
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+/// A stage of the post-processing applied to completions
+///
+/// ```yaml
+/// post_processing:
+///   - type: normalize_markdown
+///   - type: link_citations
+///   - type: extract_code_blocks
+///     language: python
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostProcessorConfig {
+    /// Tidy headings, list markers, blank lines and unclosed code fences
+    NormalizeMarkdown,
+    /// Turn `[1]` markers into links to the matching `[1]: url` reference
+    LinkCitations,
+    /// Keep only the contents of fenced code blocks
+    ExtractCodeBlocks {
+        /// Only keep blocks in this language
+        #[serde(default)]
+        language: Option<String>,
+    },
+}
+
+/// Compression of long messages by dropping their least informative
+/// sentences
+///
+/// ```yaml
+/// compression:
+///   target_ratio: 0.4
+///   min_tokens: 1000
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct CompressionConfig {
+    /// Share of each compressed message's tokens to keep, above 0.0 and
+    /// at most 1.0
+    #[serde(default = "default_target_ratio")]
+    pub target_ratio: f32,
+    /// Messages with fewer tokens than this are sent unchanged
+    #[serde(default = "default_compression_min_tokens")]
+    pub min_tokens: usize,
+}
+
+/// Token and dollar limits on the model calls made to answer one query
+///
+/// ```yaml
+/// budget:
+///   max_tokens: 200000
+///   max_cost_usd: 0.50
+///   input_usd_per_million: 3.0
+///   output_usd_per_million: 15.0
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct BudgetConfig {
+    /// Maximum prompt and generated tokens combined
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    /// Maximum cost in US dollars; needs the prices below
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// US dollars per million prompt tokens
+    #[serde(default)]
+    pub input_usd_per_million: f64,
+    /// US dollars per million generated tokens
+    #[serde(default)]
+    pub output_usd_per_million: f64,
 }
 
 // Default value functions for serde
@@ -71,6 +499,18 @@ fn default_max_tokens() -> usize {
     2000
 }
 
+fn default_target_ratio() -> f32 {
+    0.5
+}
+
+fn default_compression_min_tokens() -> usize {
+    500
+}
+
+fn default_azure_api_version() -> String {
+    "2024-10-21".to_string()
+}
+
 fn default_max_messages() -> usize {
     50
 }
@@ -124,7 +564,9 @@ pub fn load_from_file(path: &Path) -> Result<AgentConfig> {
 /// Environment variables override file-based configuration for:
 /// - LLM provider, model, API key, temperature, and max_tokens
 /// - Memory settings are taken from file config if present
-/// - Tools and guardrails are taken from file config
+/// - Tools, tool caching, guardrails, concurrency limits, degradation,
+///   locale, message transforms, post-processing, model profiles, prompt
+///   compression and budgets are taken from file config
 pub fn merge(mut file_config: AgentConfig, env_config: AgentConfig) -> AgentConfig {
     // Override LLM config with env values
     file_config.llm = env_config.llm;
@@ -145,51 +587,187 @@ pub fn merge(mut file_config: AgentConfig, env_config: AgentConfig) -> AgentConf
 ///
 /// # Errors
 /// Returns an error if:
-/// - API key is empty
+/// - API key is empty (local providers such as Ollama do not need one)
 /// - Provider is empty
 /// - Model is empty
+/// - An OpenAI organization or project is set for another provider
+/// - Gemini safety settings are set for another provider
+/// - The azure provider has no Azure settings, or another provider has them
+/// - OpenRouter settings are set for another provider
+/// - Prompt caching is enabled for a provider other than Anthropic
+/// - A model profile fails any of the checks above
+/// - A concurrency limit is zero
+/// - The compression target ratio is not above 0.0 and at most 1.0
+/// - A budget limit is zero, or a cost limit is set without prices
 pub fn validate(config: &AgentConfig) -> Result<()> {
-    if config.llm.api_key.is_empty() {
+    validate_llm(&config.llm)?;
+    for (name, profile) in &config.profiles {
+        validate_llm(profile).map_err(|e| match e {
+            AgentError::Config(message) => {
+                AgentError::Config(format!("Profile '{}': {}", name, message))
+            }
+            other => other,
+        })?;
+    }
+
+    if config.memory.max_messages == 0 {
+        return Err(AgentError::Config(
+            "Max messages must be greater than 0".to_string(),
+        ));
+    }
+
+    if config.memory.token_budget == 0 {
+        return Err(AgentError::Config(
+            "Token budget must be greater than 0".to_string(),
+        ));
+    }
+
+    if config.memory.retention.sweep_interval_secs == Some(0) {
+        return Err(AgentError::Config(
+            "Retention sweep interval must be greater than 0".to_string(),
+        ));
+    }
+
+    if config.concurrency.max_concurrent_requests == Some(0) {
+        return Err(AgentError::Config(
+            "Max concurrent requests must be greater than 0".to_string(),
+        ));
+    }
+
+    if config.concurrency.max_concurrent_steps == Some(0) {
+        return Err(AgentError::Config(
+            "Max concurrent steps must be greater than 0".to_string(),
+        ));
+    }
+
+    if let Some((provider, _)) = config.concurrency.per_provider.iter().find(|(_, limit)| **limit == 0) {
+        return Err(AgentError::Config(format!(
+            "Concurrency limit for provider '{}' must be greater than 0",
+            provider
+        )));
+    }
+
+    if let Some(compression) = &config.compression
+        && !(compression.target_ratio > 0.0 && compression.target_ratio <= 1.0)
+    {
+        return Err(AgentError::Config(format!(
+            "Compression target ratio must be above 0.0 and at most 1.0, got {}",
+            compression.target_ratio
+        )));
+    }
+
+    if let Some(budget) = &config.budget {
+        if budget.max_tokens == Some(0) {
+            return Err(AgentError::Config(
+                "Budget max tokens must be greater than 0".to_string(),
+            ));
+        }
+        if let Some(max_cost_usd) = budget.max_cost_usd {
+            if max_cost_usd <= 0.0 {
+                return Err(AgentError::Config(format!(
+                    "Budget max cost must be greater than 0, got {}",
+                    max_cost_usd
+                )));
+            }
+            if budget.input_usd_per_million <= 0.0 && budget.output_usd_per_million <= 0.0 {
+                return Err(AgentError::Config(
+                    "Budget max cost needs input_usd_per_million or output_usd_per_million"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+
+    let truncate_to_nothing = MessageTransformConfig::TruncateCodeBlocks { max_lines: 0 };
+    if config.transforms.contains(&truncate_to_nothing) {
+        return Err(AgentError::Config(
+            "truncate_code_blocks max_lines must be greater than 0".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate the settings of one model, the main one or a profile
+fn validate_llm(llm: &LLMConfig) -> Result<()> {
+    let is_local = matches!(llm.provider.as_str(), "ollama" | "llamacpp");
+    if llm.api_key.is_empty() && !is_local {
         return Err(AgentError::Config(
             "API key is required but not provided".to_string(),
         ));
     }
 
-    if config.llm.provider.is_empty() {
+    if llm.provider.is_empty() {
         return Err(AgentError::Config(
             "LLM provider is required but not provided".to_string(),
         ));
     }
 
-    if config.llm.model.is_empty() {
+    if llm.model.is_empty() {
         return Err(AgentError::Config(
             "Model name is required but not provided".to_string(),
         ));
     }
 
-    if config.llm.temperature < 0.0 || config.llm.temperature > 2.0 {
+    if llm.temperature < 0.0 || llm.temperature > 2.0 {
         return Err(AgentError::Config(format!(
             "Temperature must be between 0.0 and 2.0, got {}",
-            config.llm.temperature
+            llm.temperature
         )));
     }
 
-    if config.llm.max_tokens == 0 {
+    if llm.max_tokens == 0 {
         return Err(AgentError::Config(
             "Max tokens must be greater than 0".to_string(),
         ));
     }
 
-    if config.memory.max_messages == 0 {
-        return Err(AgentError::Config(
-            "Max messages must be greater than 0".to_string(),
-        ));
+    if llm.provider != "openai" && (llm.organization.is_some() || llm.project.is_some()) {
+        return Err(AgentError::Config(format!(
+            "Organization and project are only supported by the openai provider, not '{}'",
+            llm.provider
+        )));
     }
 
-    if config.memory.token_budget == 0 {
-        return Err(AgentError::Config(
-            "Token budget must be greater than 0".to_string(),
-        ));
+    match (llm.provider.as_str(), &llm.azure) {
+        ("azure", None) => {
+            return Err(AgentError::Config(
+                "The azure provider requires an azure endpoint".to_string(),
+            ));
+        }
+        ("azure", Some(azure)) if azure.endpoint.is_empty() || azure.api_version.is_empty() => {
+            return Err(AgentError::Config(
+                "Azure endpoint and API version must not be empty".to_string(),
+            ));
+        }
+        (provider, Some(_)) if provider != "azure" => {
+            return Err(AgentError::Config(format!(
+                "Azure settings are only supported by the azure provider, not '{}'",
+                provider
+            )));
+        }
+        _ => {}
+    }
+
+    if llm.provider != "openrouter" && llm.openrouter.is_some() {
+        return Err(AgentError::Config(format!(
+            "OpenRouter settings are only supported by the openrouter provider, not '{}'",
+            llm.provider
+        )));
+    }
+
+    if llm.provider != "gemini" && !llm.safety_settings.is_empty() {
+        return Err(AgentError::Config(format!(
+            "Safety settings are only supported by the gemini provider, not '{}'",
+            llm.provider
+        )));
+    }
+
+    if llm.provider != "anthropic" && llm.prompt_caching {
+        return Err(AgentError::Config(format!(
+            "Prompt caching is only supported by the anthropic provider, not '{}'",
+            llm.provider
+        )));
     }
 
     Ok(())
@@ -198,11 +776,17 @@ pub fn validate(config: &AgentConfig) -> Result<()> {
 /// Load agent configuration from environment variables
 ///
 /// Reads the following environment variables:
-/// - `OPENAI_API_KEY` or `ANTHROPIC_API_KEY` - API key for authentication
+/// - `OPENAI_API_KEY`, `ANTHROPIC_API_KEY`, `GEMINI_API_KEY`,
+///   `AZURE_OPENAI_API_KEY` or `OPENROUTER_API_KEY` - API key for
+///   authentication
 /// - `LLM_PROVIDER` - Provider name (defaults to "openai")
 /// - `MODEL` - Model name (defaults to "gpt-3.5-turbo")
 /// - `TEMPERATURE` - Temperature setting (defaults to 0.7)
 /// - `MAX_TOKENS` - Maximum tokens (defaults to 2000)
+/// - `OPENAI_ORG_ID` and `OPENAI_PROJECT_ID` - OpenAI organization and
+///   project (optional, OpenAI only)
+/// - `AZURE_OPENAI_ENDPOINT` - Azure resource endpoint (required for azure),
+///   with optional `AZURE_OPENAI_DEPLOYMENT` and `OPENAI_API_VERSION`
 ///
 /// # Returns
 /// * `Result<AgentConfig>` - Configuration built from environment variables
@@ -219,9 +803,19 @@ pub fn from_env() -> Result<AgentConfig> {
         "anthropic" => std::env::var("ANTHROPIC_API_KEY").map_err(|_| {
             AgentError::Config("ANTHROPIC_API_KEY environment variable not set".to_string())
         })?,
+        "gemini" => std::env::var("GEMINI_API_KEY").map_err(|_| {
+            AgentError::Config("GEMINI_API_KEY environment variable not set".to_string())
+        })?,
+        "azure" => std::env::var("AZURE_OPENAI_API_KEY").map_err(|_| {
+            AgentError::Config("AZURE_OPENAI_API_KEY environment variable not set".to_string())
+        })?,
+        "openrouter" => std::env::var("OPENROUTER_API_KEY").map_err(|_| {
+            AgentError::Config("OPENROUTER_API_KEY environment variable not set".to_string())
+        })?,
         _ => {
             return Err(AgentError::Config(format!(
-                "Unknown provider '{}'. Set OPENAI_API_KEY or ANTHROPIC_API_KEY",
+                "Unknown provider '{}'. Set OPENAI_API_KEY, ANTHROPIC_API_KEY, GEMINI_API_KEY, \
+                 AZURE_OPENAI_API_KEY or OPENROUTER_API_KEY",
                 provider
             )))
         }
@@ -231,6 +825,9 @@ pub fn from_env() -> Result<AgentConfig> {
         match provider.as_str() {
             "openai" => "gpt-3.5-turbo".to_string(),
             "anthropic" => "claude-3-sonnet-20240229".to_string(),
+            "gemini" => "gemini-2.5-flash".to_string(),
+            "azure" => "gpt-4o".to_string(),
+            "openrouter" => "openai/gpt-4o".to_string(),
             _ => "gpt-3.5-turbo".to_string(),
         }
     });
@@ -245,6 +842,29 @@ pub fn from_env() -> Result<AgentConfig> {
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(2000);
 
+    let (organization, project) = if provider == "openai" {
+        (
+            std::env::var("OPENAI_ORG_ID").ok(),
+            std::env::var("OPENAI_PROJECT_ID").ok(),
+        )
+    } else {
+        (None, None)
+    };
+
+    let azure = if provider == "azure" {
+        let endpoint = std::env::var("AZURE_OPENAI_ENDPOINT").map_err(|_| {
+            AgentError::Config("AZURE_OPENAI_ENDPOINT environment variable not set".to_string())
+        })?;
+        let mut azure = AzureConfig::new(endpoint);
+        azure.deployment = std::env::var("AZURE_OPENAI_DEPLOYMENT").ok();
+        if let Ok(api_version) = std::env::var("OPENAI_API_VERSION") {
+            azure.api_version = api_version;
+        }
+        Some(azure)
+    } else {
+        None
+    };
+
     Ok(AgentConfig {
         llm: LLMConfig {
             provider,
@@ -252,13 +872,29 @@ pub fn from_env() -> Result<AgentConfig> {
             api_key,
             temperature,
             max_tokens,
+            organization,
+            project,
+            safety_settings: BTreeMap::new(),
+            azure,
+            openrouter: None,
+            prompt_caching: false,
         },
         memory: MemoryConfig {
             max_messages: default_max_messages(),
             token_budget: default_token_budget(),
+            retention: RetentionConfig::default(),
         },
         tools: Vec::new(),
         guardrails: Vec::new(),
+        concurrency: ConcurrencyConfig::default(),
+        degradation: DegradationConfig::default(),
+        locale: None,
+        tool_cache: HashMap::new(),
+        transforms: Vec::new(),
+        post_processing: Vec::new(),
+        profiles: HashMap::new(),
+        compression: None,
+        budget: None,
     })
 }
 
@@ -502,13 +1138,29 @@ guardrails:
                 api_key: "file-key".to_string(),
                 temperature: 0.5,
                 max_tokens: 1000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 30,
                 token_budget: 3000,
+                retention: RetentionConfig::default(),
             },
             tools: vec!["calculator".to_string()],
             guardrails: vec!["file_path".to_string()],
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         let env_config = AgentConfig {
@@ -518,13 +1170,29 @@ guardrails:
                 api_key: "env-key".to_string(),
                 temperature: 0.9,
                 max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 50,
                 token_budget: 4000,
+                retention: RetentionConfig::default(),
             },
             tools: Vec::new(),
             guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         let merged = merge(file_config, env_config);
@@ -552,13 +1220,29 @@ guardrails:
                 api_key: "test-key".to_string(),
                 temperature: 0.7,
                 max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 50,
                 token_budget: 4000,
+                retention: RetentionConfig::default(),
             },
             tools: Vec::new(),
             guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         assert!(validate(&config).is_ok());
@@ -573,13 +1257,29 @@ guardrails:
                 api_key: "".to_string(),
                 temperature: 0.7,
                 max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 50,
                 token_budget: 4000,
+                retention: RetentionConfig::default(),
             },
             tools: Vec::new(),
             guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         let result = validate(&config);
@@ -587,6 +1287,43 @@ guardrails:
         assert!(result.unwrap_err().to_string().contains("API key"));
     }
 
+    #[test]
+    fn test_validate_local_provider_without_api_key() {
+        let config = AgentConfig {
+            llm: LLMConfig {
+                provider: "ollama".to_string(),
+                model: "llama3.2".to_string(),
+                api_key: "".to_string(),
+                temperature: 0.7,
+                max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
+            },
+            memory: MemoryConfig {
+                max_messages: 50,
+                token_budget: 4000,
+                retention: RetentionConfig::default(),
+            },
+            tools: Vec::new(),
+            guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
+        };
+
+        assert!(validate(&config).is_ok());
+    }
+
     #[test]
     fn test_validate_empty_provider() {
         let config = AgentConfig {
@@ -596,13 +1333,29 @@ guardrails:
                 api_key: "test-key".to_string(),
                 temperature: 0.7,
                 max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 50,
                 token_budget: 4000,
+                retention: RetentionConfig::default(),
             },
             tools: Vec::new(),
             guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         let result = validate(&config);
@@ -619,13 +1372,29 @@ guardrails:
                 api_key: "test-key".to_string(),
                 temperature: 3.0,
                 max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 50,
                 token_budget: 4000,
+                retention: RetentionConfig::default(),
             },
             tools: Vec::new(),
             guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         let result = validate(&config);
@@ -642,17 +1411,416 @@ guardrails:
                 api_key: "test-key".to_string(),
                 temperature: 0.7,
                 max_tokens: 0,
+                organization: None,
+                project: None,
+                safety_settings: BTreeMap::new(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
             },
             memory: MemoryConfig {
                 max_messages: 50,
                 token_budget: 4000,
+                retention: RetentionConfig::default(),
             },
             tools: Vec::new(),
             guardrails: Vec::new(),
+            concurrency: ConcurrencyConfig::default(),
+            degradation: DegradationConfig::default(),
+            locale: None,
+            tool_cache: HashMap::new(),
+            transforms: Vec::new(),
+            post_processing: Vec::new(),
+            profiles: HashMap::new(),
+            compression: None,
+        budget: None,
         };
 
         let result = validate(&config);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Max tokens"));
     }
+
+    #[test]
+    fn test_concurrency_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            concurrency:
+              max_concurrent_requests: 16
+              per_provider:
+                openai: 4
+              max_concurrent_steps: 3
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.concurrency.max_concurrent_requests, Some(16));
+        assert_eq!(config.concurrency.per_provider["openai"], 4);
+        assert_eq!(config.concurrency.max_concurrent_steps, Some(3));
+        assert!(validate(&config).is_ok());
+
+        config.concurrency.max_concurrent_steps = Some(0);
+        assert!(validate(&config).is_err());
+        config.concurrency.max_concurrent_steps = None;
+
+        config.concurrency.per_provider.insert("anthropic".to_string(), 0);
+        let result = validate(&config);
+        assert!(result.unwrap_err().to_string().contains("'anthropic'"));
+    }
+
+    #[test]
+    fn test_retention_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory:
+              retention:
+                max_age_days: 90
+                action: anonymize
+                users:
+                  u-42: { max_age_days: 30, action: delete }
+                sessions:
+                  audit-7: { max_age_days: 365 }
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        let retention = &config.memory.retention;
+        assert_eq!(retention.max_age_days, Some(90));
+        assert_eq!(retention.action, RetentionAction::Anonymize);
+        assert_eq!(
+            retention.users["u-42"],
+            RetentionRuleConfig {
+                max_age_days: 30,
+                action: RetentionAction::Delete,
+            }
+        );
+        assert_eq!(retention.sessions["audit-7"].action, RetentionAction::Delete);
+        assert!(validate(&config).is_ok());
+
+        config.memory.retention.sweep_interval_secs = Some(0);
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_degradation_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            degradation:
+              cache_responses: 50
+              fallback_response: Try again later
+        "#;
+
+        let config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.degradation.cache_responses, 50);
+        assert_eq!(config.locale, None);
+        assert_eq!(
+            config.degradation.fallback_response.as_deref(),
+            Some("Try again later")
+        );
+    }
+
+    #[test]
+    fn test_tool_cache_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            tool_cache:
+              web_search: 300
+        "#;
+
+        let config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.tool_cache.get("web_search"), Some(&300));
+        assert_eq!(config.tool_cache.get("calculator"), None);
+    }
+
+    #[test]
+    fn test_transforms_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            transforms:
+              - type: strip_html
+              - type: truncate_code_blocks
+                max_lines: 40
+              - type: inject_date_time
+        "#;
+
+        let config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(
+            config.transforms,
+            vec![
+                MessageTransformConfig::StripHtml,
+                MessageTransformConfig::TruncateCodeBlocks { max_lines: 40 },
+                MessageTransformConfig::InjectDateTime { format: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_post_processing_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            post_processing:
+              - type: normalize_markdown
+              - type: extract_code_blocks
+                language: rust
+        "#;
+
+        let config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(
+            config.post_processing,
+            vec![
+                PostProcessorConfig::NormalizeMarkdown,
+                PostProcessorConfig::ExtractCodeBlocks {
+                    language: Some("rust".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compression_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            compression:
+              target_ratio: 0.4
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(
+            config.compression,
+            Some(CompressionConfig {
+                target_ratio: 0.4,
+                min_tokens: 500,
+            })
+        );
+        assert!(validate(&config).is_ok());
+
+        config.compression = Some(CompressionConfig {
+            target_ratio: 0.0,
+            min_tokens: 500,
+        });
+        let err = validate(&config).unwrap_err();
+        assert!(err.to_string().contains("Compression target ratio"));
+    }
+
+    #[test]
+    fn test_budget_config() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+            memory: {}
+            budget:
+              max_cost_usd: 0.5
+              input_usd_per_million: 3.0
+              output_usd_per_million: 15.0
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(
+            config.budget,
+            Some(BudgetConfig {
+                max_tokens: None,
+                max_cost_usd: Some(0.5),
+                input_usd_per_million: 3.0,
+                output_usd_per_million: 15.0,
+            })
+        );
+        assert!(validate(&config).is_ok());
+
+        config.budget = Some(BudgetConfig {
+            max_cost_usd: Some(0.5),
+            ..BudgetConfig::default()
+        });
+        let err = validate(&config).unwrap_err();
+        assert!(err.to_string().contains("needs input_usd_per_million"));
+
+        config.budget = Some(BudgetConfig {
+            max_tokens: Some(0),
+            ..BudgetConfig::default()
+        });
+        assert!(validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_openai_organization_and_project() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4
+              api_key: test-key
+              organization: org-123
+              project: proj_abc
+            memory: {}
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.llm.organization.as_deref(), Some("org-123"));
+        assert_eq!(config.llm.project.as_deref(), Some("proj_abc"));
+        assert!(validate(&config).is_ok());
+
+        config.llm.provider = "anthropic".to_string();
+        let result = validate(&config);
+        assert!(result.unwrap_err().to_string().contains("only supported by the openai provider"));
+    }
+
+    #[test]
+    fn test_gemini_safety_settings() {
+        let config_str = r#"
+            llm:
+              provider: gemini
+              model: gemini-2.5-flash
+              api_key: test-key
+              safety_settings:
+                harassment: block_only_high
+                dangerous_content: block_none
+            memory: {}
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(
+            config.llm.safety_settings[&HarmCategory::Harassment],
+            HarmBlockThreshold::BlockOnlyHigh
+        );
+        assert_eq!(config.llm.safety_settings.len(), 2);
+        assert!(validate(&config).is_ok());
+
+        config.llm.provider = "openai".to_string();
+        let result = validate(&config);
+        assert!(result.unwrap_err().to_string().contains("only supported by the gemini provider"));
+    }
+
+    #[test]
+    fn test_prompt_caching_config() {
+        let config_str = r#"
+            llm:
+              provider: anthropic
+              model: claude-sonnet-4-5
+              api_key: test-key
+              prompt_caching: true
+            memory: {}
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert!(config.llm.prompt_caching);
+        assert!(validate(&config).is_ok());
+
+        config.llm.provider = "openai".to_string();
+        let result = validate(&config);
+        assert!(result.unwrap_err().to_string().contains("only supported by the anthropic provider"));
+    }
+
+    #[test]
+    fn test_model_profiles() {
+        let config_str = r#"
+            llm:
+              provider: openai
+              model: gpt-4o
+              api_key: test-key
+            memory: {}
+            profiles:
+              summarizer:
+                provider: openai
+                model: gpt-4o-mini
+                api_key: test-key
+              coder:
+                provider: anthropic
+                model: claude-sonnet-4-5
+                api_key: test-key
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        assert_eq!(config.profiles.len(), 2);
+        assert_eq!(config.profiles["coder"].provider, "anthropic");
+        assert!(validate(&config).is_ok());
+
+        config.profiles.get_mut("coder").unwrap().api_key.clear();
+        let err = validate(&config).unwrap_err().to_string();
+        assert!(err.contains("Profile 'coder': API key is required"));
+    }
+
+    #[test]
+    fn test_azure_config() {
+        let config_str = r#"
+            llm:
+              provider: azure
+              model: gpt-4o
+              api_key: test-key
+              azure:
+                endpoint: https://my-resource.openai.azure.com
+                deployment: chat-prod
+            memory: {}
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        let azure = config.llm.azure.clone().unwrap();
+        assert_eq!(azure.deployment.as_deref(), Some("chat-prod"));
+        assert_eq!(azure.api_version, "2024-10-21");
+        assert!(validate(&config).is_ok());
+
+        config.llm.provider = "openai".to_string();
+        let result = validate(&config);
+        assert!(result.unwrap_err().to_string().contains("only supported by the azure provider"));
+
+        config.llm.provider = "azure".to_string();
+        config.llm.azure = None;
+        assert!(validate(&config).unwrap_err().to_string().contains("requires an azure endpoint"));
+    }
+
+    #[test]
+    fn test_openrouter_config() {
+        let config_str = r#"
+            llm:
+              provider: openrouter
+              model: anthropic/claude-sonnet-4
+              api_key: test-key
+              openrouter:
+                fallback_models: [openai/gpt-4o]
+                app_name: Athena
+                routing:
+                  order: [anthropic]
+                  allow_fallbacks: false
+                  data_collection: deny
+                  sort: throughput
+            memory: {}
+        "#;
+
+        let mut config: AgentConfig = serde_yaml::from_str(config_str).unwrap();
+        let openrouter = config.llm.openrouter.clone().unwrap();
+        assert_eq!(openrouter.fallback_models, vec!["openai/gpt-4o".to_string()]);
+        assert_eq!(openrouter.site_url, None);
+        assert_eq!(openrouter.routing.data_collection, Some(DataCollection::Deny));
+        assert_eq!(openrouter.routing.sort, Some(ProviderSort::Throughput));
+        assert!(!openrouter.routing.is_default());
+        assert!(validate(&config).is_ok());
+
+        config.llm.provider = "openai".to_string();
+        let result = validate(&config);
+        assert!(result.unwrap_err().to_string().contains("only supported by the openrouter provider"));
+    }
 }
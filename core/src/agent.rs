@@ -0,0 +1,71 @@
+//! The common interface of runnable agents.
+
+use crate::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Something that turns a user's input into an answer
+///
+/// Applications and servers depend on this trait rather than on how an
+/// agent is put together, so a planning agent, a tool-calling agent and a
+/// test double are interchangeable.
+#[async_trait]
+pub trait Agent: Send + Sync {
+    /// Handle one input and return the agent's answer
+    async fn run(&self, input: &str) -> Result<AgentOutput>;
+}
+
+/// What an agent produced for one input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentOutput {
+    /// The answer to return to the user
+    pub response: String,
+    /// Whether the agent finished, rather than stopping at an error or limit
+    pub success: bool,
+    /// Names of the tools called while answering, in call order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools_used: Vec<String>,
+    /// Trace ID of the request the run belonged to, if one was in scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+impl AgentOutput {
+    /// A successful answer with no tool calls
+    pub fn new(response: impl Into<String>) -> Self {
+        Self {
+            response: response.into(),
+            success: true,
+            tools_used: Vec::new(),
+            trace_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    #[async_trait]
+    impl Agent for Echo {
+        async fn run(&self, input: &str) -> Result<AgentOutput> {
+            Ok(AgentOutput::new(input))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agents_are_usable_as_trait_objects() {
+        let agent: Box<dyn Agent> = Box::new(Echo);
+        let output = agent.run("hello").await.unwrap();
+        assert_eq!(output, AgentOutput::new("hello"));
+        assert!(output.success);
+    }
+
+    #[test]
+    fn test_output_omits_empty_fields() {
+        let json = serde_json::to_value(AgentOutput::new("4")).unwrap();
+        assert_eq!(json, serde_json::json!({"response": "4", "success": true}));
+    }
+}
@@ -0,0 +1,261 @@
+//! Structured error context.
+//!
+//! [`ResultExt`] attaches an [`ErrorContext`] to a failing result. The
+//! context is kept as structured fields on [`AgentError::WithContext`], so
+//! callers can inspect which provider, step, session, or traced request an
+//! error came from instead of parsing it back out of a message string.
+
+use std::fmt;
+
+use crate::{AgentError, RequestContext, Result};
+
+/// Operation context attached to an error
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Description of the operation that failed
+    pub operation: String,
+    /// LLM provider involved, if any
+    pub provider: Option<String>,
+    /// Plan step identifier, if any
+    pub step_id: Option<String>,
+    /// Session identifier, if any
+    pub session: Option<String>,
+    /// Trace identifier of the request, if any
+    pub trace_id: Option<String>,
+}
+
+impl ErrorContext {
+    /// Create a context for the given operation
+    pub fn new(operation: impl Into<String>) -> Self {
+        Self {
+            operation: operation.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the provider
+    pub fn provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    /// Set the step identifier
+    pub fn step_id(mut self, step_id: impl Into<String>) -> Self {
+        self.step_id = Some(step_id.into());
+        self
+    }
+
+    /// Set the session identifier
+    pub fn session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    /// Set the trace identifier
+    pub fn trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    /// Take the trace and session identifiers from a request context
+    pub fn request(mut self, request: &RequestContext) -> Self {
+        self.trace_id = Some(request.trace_id.clone());
+        if let Some(session_id) = &request.session_id {
+            self.session = Some(session_id.clone());
+        }
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.operation)?;
+
+        let fields: Vec<String> = [
+            ("provider", &self.provider),
+            ("step", &self.step_id),
+            ("session", &self.session),
+            ("trace", &self.trace_id),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_ref().map(|v| format!("{}={}", name, v)))
+        .collect();
+
+        if !fields.is_empty() {
+            write!(f, " ({})", fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for ErrorContext {
+    fn from(operation: &str) -> Self {
+        Self::new(operation)
+    }
+}
+
+impl From<String> for ErrorContext {
+    fn from(operation: String) -> Self {
+        Self::new(operation)
+    }
+}
+
+/// Extension methods for attaching context to errors
+///
+/// # Example
+///
+/// ```
+/// use agent_core::{AgentError, ErrorContext, Result, ResultExt};
+///
+/// fn call_provider() -> Result<String> {
+///     Err(AgentError::LLMProvider("HTTP 503 error".to_string()))
+/// }
+///
+/// let err = call_provider()
+///     .context(ErrorContext::new("send message").provider("openai").session("s-1"))
+///     .unwrap_err();
+///
+/// assert_eq!(err.context().unwrap().provider.as_deref(), Some("openai"));
+/// assert!(matches!(err.root_cause(), AgentError::LLMProvider(_)));
+/// ```
+pub trait ResultExt<T> {
+    /// Wrap the error with the given context
+    fn context(self, context: impl Into<ErrorContext>) -> Result<T>;
+
+    /// Wrap the error with lazily built context
+    ///
+    /// The closure only runs if the result is an error.
+    fn with_context<C, F>(self, f: F) -> Result<T>
+    where
+        C: Into<ErrorContext>,
+        F: FnOnce() -> C;
+}
+
+impl<T, E: Into<AgentError>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, context: impl Into<ErrorContext>) -> Result<T> {
+        self.map_err(|e| e.into().wrap(context.into()))
+    }
+
+    fn with_context<C, F>(self, f: F) -> Result<T>
+    where
+        C: Into<ErrorContext>,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|e| e.into().wrap(f().into()))
+    }
+}
+
+impl AgentError {
+    /// Wrap this error with context
+    pub fn wrap(self, context: ErrorContext) -> Self {
+        AgentError::WithContext {
+            context: Box::new(context),
+            source: Box::new(self),
+        }
+    }
+
+    /// The outermost context attached to this error, if any
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            AgentError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// All contexts attached to this error, outermost first
+    pub fn contexts(&self) -> Vec<&ErrorContext> {
+        let mut contexts = Vec::new();
+        let mut current = self;
+        while let AgentError::WithContext { context, source } = current {
+            contexts.push(context.as_ref());
+            current = source;
+        }
+        contexts
+    }
+
+    /// The underlying error with all context layers removed
+    pub fn root_cause(&self) -> &AgentError {
+        let mut current = self;
+        while let AgentError::WithContext { source, .. } = current {
+            current = source;
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failing() -> Result<()> {
+        Err(AgentError::Planning("no steps".to_string()))
+    }
+
+    #[test]
+    fn test_context_display() {
+        let err = failing()
+            .context(ErrorContext::new("create plan").step_id("3"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "create plan (step=3): Planning error: no steps"
+        );
+    }
+
+    #[test]
+    fn test_context_from_request() {
+        let request = RequestContext::with_trace_id("t-1").with_session_id("s-1");
+        let err = failing()
+            .context(ErrorContext::new("run plan").request(&request))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "run plan (session=s-1, trace=t-1): Planning error: no steps"
+        );
+    }
+
+    #[test]
+    fn test_context_from_str() {
+        let err = failing().context("load config").unwrap_err();
+        assert_eq!(err.context().unwrap().operation, "load config");
+        assert!(err.context().unwrap().provider.is_none());
+    }
+
+    #[test]
+    fn test_nested_contexts() {
+        let err = failing()
+            .context(ErrorContext::new("call tool").step_id("2"))
+            .context(ErrorContext::new("run plan").session("abc"))
+            .unwrap_err();
+
+        let contexts = err.contexts();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].session.as_deref(), Some("abc"));
+        assert_eq!(contexts[1].step_id.as_deref(), Some("2"));
+        assert!(matches!(err.root_cause(), AgentError::Planning(_)));
+    }
+
+    #[test]
+    fn test_with_context_is_lazy() {
+        let ok: Result<i32> = Ok(1);
+        let value = ok
+            .with_context(|| -> ErrorContext { panic!("should not run") })
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn test_converts_foreign_errors() {
+        let result: std::result::Result<i32, serde_json::Error> = serde_json::from_str("nope");
+        let err = result.context("parse response").unwrap_err();
+        assert!(matches!(err.root_cause(), AgentError::Serialization(_)));
+    }
+
+    #[test]
+    fn test_error_without_context() {
+        let err = AgentError::Memory("full".to_string());
+        assert!(err.context().is_none());
+        assert!(err.contexts().is_empty());
+        assert!(matches!(err.root_cause(), AgentError::Memory(_)));
+    }
+}
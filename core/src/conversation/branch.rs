@@ -0,0 +1,255 @@
+//! Forking conversations and comparing or merging branches.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::{Conversation, Turn};
+use crate::{AgentError, Result};
+
+/// Identifier of a turn within a conversation
+///
+/// IDs are positions, so they stay valid in every branch forked at or
+/// after that turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MessageId(pub usize);
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// How [`Conversation::merge`] combines a branch into a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Discard this conversation's turns after the common prefix and adopt
+    /// the branch's continuation
+    Replace,
+    /// Keep this conversation's turns and append the branch's continuation
+    Append,
+}
+
+/// Result of comparing two conversations
+#[derive(Debug, Clone)]
+pub struct BranchDiff<'a> {
+    /// Number of leading turns both conversations share
+    pub common_len: usize,
+    /// Turns only in the first conversation
+    pub ours: &'a [Turn],
+    /// Turns only in the second conversation
+    pub theirs: &'a [Turn],
+}
+
+impl BranchDiff<'_> {
+    /// Returns true if the conversations contain the same turns
+    pub fn is_identical(&self) -> bool {
+        self.ours.is_empty() && self.theirs.is_empty()
+    }
+
+    /// ID of the last shared turn, if any turn is shared
+    pub fn fork_point(&self) -> Option<MessageId> {
+        self.common_len.checked_sub(1).map(MessageId)
+    }
+}
+
+impl Conversation {
+    /// ID of the most recent turn
+    pub fn last_id(&self) -> Option<MessageId> {
+        self.turns.len().checked_sub(1).map(MessageId)
+    }
+
+    /// Create an independent branch containing every turn up to and
+    /// including `id`
+    ///
+    /// Changes to the branch do not affect this conversation. Several forks
+    /// of the same point can be continued differently and compared.
+    ///
+    /// # Errors
+    /// Returns an error if `id` does not refer to a turn in this conversation.
+    pub fn fork_at(&self, id: MessageId) -> Result<Conversation> {
+        if id.0 >= self.turns.len() {
+            return Err(AgentError::Memory(format!(
+                "Cannot fork at {}: conversation has {} turns",
+                id,
+                self.turns.len()
+            )));
+        }
+        Ok(Conversation {
+            turns: self.turns[..=id.0].to_vec(),
+            prompts: self.prompts.clone(),
+            // The summary describes turns the fork does not have
+            summary: None,
+        })
+    }
+
+    /// Compare this conversation with a branch
+    pub fn compare<'a>(&'a self, other: &'a Conversation) -> BranchDiff<'a> {
+        let common_len = self
+            .turns
+            .iter()
+            .zip(&other.turns)
+            .take_while(|(a, b)| same_turn(a, b))
+            .count();
+        BranchDiff {
+            common_len,
+            ours: &self.turns[common_len..],
+            theirs: &other.turns[common_len..],
+        }
+    }
+
+    /// Merge a branch's continuation into this conversation
+    ///
+    /// The branch's turns after the common prefix are either appended
+    /// ([`MergeStrategy::Append`]) or replace this conversation's turns after
+    /// the common prefix ([`MergeStrategy::Replace`]). A stored summary is
+    /// cleared when the turns change, since it no longer describes them.
+    ///
+    /// # Errors
+    /// Returns an error if the conversations share no turns, since the
+    /// branch was then not forked from this conversation.
+    pub fn merge(&mut self, branch: &Conversation, strategy: MergeStrategy) -> Result<()> {
+        let diff = self.compare(branch);
+        if diff.common_len == 0 && !self.turns.is_empty() && !branch.turns.is_empty() {
+            return Err(AgentError::Memory(
+                "Cannot merge: branch shares no history with this conversation".to_string(),
+            ));
+        }
+
+        let common_len = diff.common_len;
+        let continuation = branch.turns[common_len..].to_vec();
+        let changed = !continuation.is_empty()
+            || (strategy == MergeStrategy::Replace && self.turns.len() > common_len);
+        if strategy == MergeStrategy::Replace {
+            self.turns.truncate(common_len);
+        }
+        self.turns.extend(continuation);
+        for reference in &branch.prompts {
+            self.record_prompt(reference.clone());
+        }
+        if changed {
+            self.summary = None;
+        }
+        Ok(())
+    }
+}
+
+/// Turns are compared by content, ignoring timestamps
+fn same_turn(a: &Turn, b: &Turn) -> bool {
+    match (a, b) {
+        (Turn::Message(a), Turn::Message(b)) => {
+            a.role == b.role
+                && a.content == b.content
+                && a.attachments == b.attachments
+                && a.images == b.images
+        }
+        (Turn::ToolCall(a), Turn::ToolCall(b)) => {
+            a.tool_name == b.tool_name
+                && a.parameters == b.parameters
+                && a.output == b.output
+                && a.success == b.success
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConversationSummary, Message, ToolCallRecord};
+    use serde_json::json;
+
+    fn base() -> Conversation {
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::system("Be helpful"));
+        conversation.push_message(Message::user("Plan a trip"));
+        conversation
+    }
+
+    #[test]
+    fn test_fork_is_independent() {
+        let mut main = base();
+        let fork_point = main.last_id().unwrap();
+        let mut branch = main.fork_at(fork_point).unwrap();
+
+        branch.push_message(Message::assistant("Try Lisbon"));
+        main.push_message(Message::assistant("Try Rome"));
+
+        assert_eq!(main.len(), 3);
+        assert_eq!(branch.len(), 3);
+        assert_eq!(branch.fork_at(MessageId(0)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_fork_out_of_range() {
+        assert!(base().fork_at(MessageId(5)).is_err());
+        assert!(Conversation::new().fork_at(MessageId(0)).is_err());
+    }
+
+    #[test]
+    fn test_compare_branches() {
+        let main = base();
+        let mut a = main.fork_at(MessageId(1)).unwrap();
+        let mut b = main.fork_at(MessageId(1)).unwrap();
+        a.push_message(Message::assistant("Lisbon"));
+        b.push_tool_call(ToolCallRecord::success(
+            "search",
+            json!({"q": "trips"}),
+            "Rome",
+        ));
+        b.push_message(Message::assistant("Rome"));
+
+        let diff = a.compare(&b);
+        assert_eq!(diff.common_len, 2);
+        assert_eq!(diff.fork_point(), Some(MessageId(1)));
+        assert_eq!(diff.ours.len(), 1);
+        assert_eq!(diff.theirs.len(), 2);
+        assert!(!diff.is_identical());
+        assert!(main.compare(&main.clone()).is_identical());
+    }
+
+    #[test]
+    fn test_merge_replace_adopts_branch() {
+        let mut main = base();
+        let mut branch = main.fork_at(MessageId(1)).unwrap();
+        main.push_message(Message::assistant("Rome"));
+        branch.push_message(Message::assistant("Lisbon"));
+
+        main.merge(&branch, MergeStrategy::Replace).unwrap();
+        assert_eq!(main.len(), 3);
+        assert_eq!(main.messages().last().unwrap().content, "Lisbon");
+    }
+
+    #[test]
+    fn test_merge_append_keeps_both() {
+        let mut main = base();
+        let mut branch = main.fork_at(MessageId(1)).unwrap();
+        main.push_message(Message::assistant("Rome"));
+        branch.push_message(Message::assistant("Lisbon"));
+
+        main.merge(&branch, MergeStrategy::Append).unwrap();
+        let contents: Vec<_> = main.messages().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents[2..], ["Rome", "Lisbon"]);
+    }
+
+    #[test]
+    fn test_merge_unrelated_conversation_fails() {
+        let mut main = base();
+        let unrelated = Conversation::from(vec![Message::user("Something else")]);
+        assert!(main.merge(&unrelated, MergeStrategy::Append).is_err());
+    }
+
+    #[test]
+    fn test_summary_cleared_by_fork_and_merge() {
+        let mut main = base();
+        main.set_summary(ConversationSummary::new("Capitals", "Asked about capitals."));
+        let mut branch = main.fork_at(MessageId(1)).unwrap();
+        assert!(branch.summary().is_none());
+
+        main.merge(&main.clone(), MergeStrategy::Append).unwrap();
+        assert_eq!(main.title(), Some("Capitals"));
+
+        branch.push_message(Message::assistant("Lisbon"));
+        main.merge(&branch, MergeStrategy::Append).unwrap();
+        assert!(main.summary().is_none());
+    }
+}
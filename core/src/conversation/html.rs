@@ -0,0 +1,366 @@
+//! Self-contained HTML transcript export.
+
+use std::fmt::Write;
+
+use super::{Conversation, ToolCallRecord, Turn};
+use crate::Role;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; background: #f6f7f9; color: #1f2328; margin: 0; }
+main { max-width: 860px; margin: 2rem auto; padding: 0 1rem; }
+h1 { font-size: 1.4rem; }
+.turn { background: #fff; border: 1px solid #d0d7de; border-radius: 8px; padding: 0.75rem 1rem; margin: 0.75rem 0; }
+.turn header { display: flex; justify-content: space-between; font-size: 0.85rem; margin-bottom: 0.5rem; }
+.role { font-weight: 600; text-transform: capitalize; }
+.system { border-left: 4px solid #8250df; }
+.user { border-left: 4px solid #0969da; }
+.assistant { border-left: 4px solid #1a7f37; }
+.tool { border-left: 4px solid #bf8700; }
+.tool.failed { border-left-color: #cf222e; }
+time { color: #656d76; }
+details summary { cursor: pointer; font-weight: 600; }
+.status { font-size: 0.8rem; padding: 0 0.4rem; border-radius: 4px; margin-left: 0.5rem; }
+.status.ok { background: #dafbe1; color: #1a7f37; }
+.status.failed { background: #ffebe9; color: #cf222e; }
+pre { background: #161b22; color: #e6edf3; padding: 0.75rem; border-radius: 6px; overflow-x: auto; }
+.kw { color: #ff7b72; }
+.str { color: #a5d6ff; }
+.num { color: #79c0ff; }
+.com { color: #8b949e; font-style: italic; }
+.summary { display: grid; grid-template-columns: max-content auto; gap: 0.25rem 1rem; }
+"#;
+
+/// Token usage and cost totals shown in an HTML transcript
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostSummary {
+    /// Total prompt tokens sent
+    pub input_tokens: usize,
+    /// Total completion tokens received
+    pub output_tokens: usize,
+    /// Total cost in US dollars, if known
+    pub total_cost_usd: Option<f64>,
+}
+
+impl Conversation {
+    /// Render the conversation as a self-contained, styled HTML page
+    ///
+    /// Tool calls are collapsible, fenced code blocks in message content are
+    /// syntax highlighted, and a summary section lists turn counts plus the
+    /// cost summary if one is given. No external assets are referenced, so
+    /// the output can be shared as a single file.
+    pub fn to_html(&self, cost: Option<&CostSummary>) -> String {
+        let mut body = String::new();
+        for turn in &self.turns {
+            match turn {
+                Turn::Message(message) => {
+                    let class = role_class(&message.role);
+                    let _ = writeln!(
+                        body,
+                        "<section class=\"turn {}\"><header><span class=\"role\">{}</span><time>{}</time></header>{}</section>",
+                        class,
+                        class,
+                        message.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                        render_content(&message.content)
+                    );
+                }
+                Turn::ToolCall(call) => body.push_str(&render_tool_call(call)),
+            }
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Conversation transcript</title>\n<style>{}</style>\n</head>\n<body>\n<main>\n<h1>Conversation transcript</h1>\n{}{}</main>\n</body>\n</html>\n",
+            STYLE,
+            body,
+            self.render_summary(cost)
+        )
+    }
+
+    fn render_summary(&self, cost: Option<&CostSummary>) -> String {
+        let messages = self.messages().count();
+        let tool_calls = self.turns.len() - messages;
+        let failed = self
+            .turns
+            .iter()
+            .filter(|turn| matches!(turn, Turn::ToolCall(call) if !call.success))
+            .count();
+
+        let mut rows = format!(
+            "<span>Messages</span><span>{}</span><span>Tool calls</span><span>{} ({} failed)</span>",
+            messages, tool_calls, failed
+        );
+        if let Some(cost) = cost {
+            let _ = write!(
+                rows,
+                "<span>Input tokens</span><span>{}</span><span>Output tokens</span><span>{}</span>",
+                cost.input_tokens, cost.output_tokens
+            );
+            if let Some(usd) = cost.total_cost_usd {
+                let _ = write!(rows, "<span>Total cost</span><span>${:.4}</span>", usd);
+            }
+        }
+
+        format!(
+            "<section class=\"turn\"><header><span class=\"role\">Summary</span></header><div class=\"summary\">{}</div></section>\n",
+            rows
+        )
+    }
+}
+
+fn role_class(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn render_tool_call(call: &ToolCallRecord) -> String {
+    let (class, label) = if call.success {
+        ("ok", "ok")
+    } else {
+        ("failed", "failed")
+    };
+    let parameters = serde_json::to_string_pretty(&call.parameters)
+        .unwrap_or_else(|_| call.parameters.to_string());
+
+    format!(
+        "<section class=\"turn tool {}\"><details><summary>Tool call: <code>{}</code><span class=\"status {}\">{}</span></summary><p>Parameters</p><pre><code>{}</code></pre><p>{}</p><pre><code>{}</code></pre></details></section>\n",
+        class,
+        escape(&call.tool_name),
+        class,
+        label,
+        highlight(&parameters, "json"),
+        if call.success { "Output" } else { "Error" },
+        escape(&call.output)
+    )
+}
+
+/// Render message text, turning fenced code blocks into highlighted `<pre>` blocks
+fn render_content(content: &str) -> String {
+    let mut out = String::new();
+    let mut text = String::new();
+    let mut code: Option<(String, String)> = None;
+
+    for line in content.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (None, Some(lang)) => {
+                push_paragraphs(&mut out, &text);
+                text.clear();
+                code = Some((lang.trim().to_string(), String::new()));
+            }
+            (Some((lang, source)), Some(_)) => {
+                let _ = write!(
+                    out,
+                    "<pre><code>{}</code></pre>",
+                    highlight(source.trim_end_matches('\n'), lang)
+                );
+                code = None;
+            }
+            (Some((_, source)), None) => {
+                source.push_str(line);
+                source.push('\n');
+            }
+            (None, None) => {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+
+    // An unterminated fence is still shown as code
+    if let Some((lang, source)) = code {
+        let _ = write!(
+            out,
+            "<pre><code>{}</code></pre>",
+            highlight(source.trim_end_matches('\n'), &lang)
+        );
+    }
+    push_paragraphs(&mut out, &text);
+    out
+}
+
+fn push_paragraphs(out: &mut String, text: &str) {
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let _ = write!(out, "<p>{}</p>", escape(paragraph).replace('\n', "<br>"));
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "class", "const", "continue", "def", "else", "enum", "false",
+    "fn", "for", "from", "function", "if", "impl", "import", "in", "let", "match", "mod", "mut",
+    "null", "pub", "return", "self", "static", "struct", "trait", "true", "type", "use", "var",
+    "where", "while", "None", "True", "False",
+];
+
+/// Lightweight syntax highlighting for keywords, strings, numbers, and comments
+fn highlight(source: &str, lang: &str) -> String {
+    let hash_comments = matches!(
+        lang,
+        "python" | "py" | "sh" | "bash" | "shell" | "ruby" | "rb" | "toml" | "yaml" | "yml"
+    );
+    let single_quote_strings = !matches!(lang, "rust" | "rs");
+
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::with_capacity(source.len());
+    let mut i = 0;
+
+    let span = |out: &mut String, class: &str, text: &str| {
+        let _ = write!(out, "<span class=\"{}\">{}</span>", class, escape(text));
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let starts_comment =
+            (c == '/' && chars.get(i + 1) == Some(&'/')) || (c == '#' && hash_comments);
+
+        if starts_comment {
+            let end = chars[i..]
+                .iter()
+                .position(|&ch| ch == '\n')
+                .map_or(chars.len(), |p| i + p);
+            span(&mut out, "com", &chars[i..end].iter().collect::<String>());
+            i = end;
+        } else if c == '"' || (c == '\'' && single_quote_strings) {
+            let mut end = i + 1;
+            while end < chars.len() && chars[end] != c && chars[end] != '\n' {
+                if chars[end] == '\\' {
+                    end += 1;
+                }
+                end += 1;
+            }
+            let end = (end + 1).min(chars.len());
+            span(&mut out, "str", &chars[i..end].iter().collect::<String>());
+            i = end;
+        } else if c.is_ascii_digit() && (i == 0 || !is_ident(chars[i - 1])) {
+            let end = chars[i..]
+                .iter()
+                .position(|&ch| !(ch.is_ascii_alphanumeric() || ch == '.' || ch == '_'))
+                .map_or(chars.len(), |p| i + p);
+            span(&mut out, "num", &chars[i..end].iter().collect::<String>());
+            i = end;
+        } else if is_ident(c) {
+            let end = chars[i..]
+                .iter()
+                .position(|&ch| !is_ident(ch))
+                .map_or(chars.len(), |p| i + p);
+            let word: String = chars[i..end].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                span(&mut out, "kw", &word);
+            } else {
+                out.push_str(&escape(&word));
+            }
+            i = end;
+        } else {
+            out.push_str(&escape(&c.to_string()));
+            i += 1;
+        }
+    }
+
+    out
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use serde_json::json;
+
+    #[test]
+    fn test_html_is_self_contained() {
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::user("Hello"));
+
+        let html = conversation.to_html(None);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<script src"));
+        assert!(!html.contains("<link"));
+    }
+
+    #[test]
+    fn test_html_escapes_content() {
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::user("<script>alert('x')</script>"));
+
+        let html = conversation.to_html(None);
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+        assert!(!html.contains("<script>alert"));
+    }
+
+    #[test]
+    fn test_tool_calls_are_collapsible() {
+        let mut conversation = Conversation::new();
+        conversation.push_tool_call(ToolCallRecord::success(
+            "calculator",
+            json!({"expression": "2+2"}),
+            "4",
+        ));
+        conversation.push_tool_call(ToolCallRecord::failure("file_reader", json!({}), "denied"));
+
+        let html = conversation.to_html(None);
+        assert_eq!(html.matches("<details>").count(), 2);
+        assert!(html.contains("<code>calculator</code>"));
+        assert!(html.contains("<span class=\"str\">&quot;expression&quot;</span>"));
+        assert!(html.contains("tool failed"));
+        assert!(html.contains("2 (1 failed)"));
+    }
+
+    #[test]
+    fn test_code_blocks_are_highlighted() {
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::assistant(
+            "Try this:\n\n```rust\nlet x = 42; // answer\n```\n\nDone.",
+        ));
+
+        let html = conversation.to_html(None);
+        assert!(html.contains("<p>Try this:</p><pre><code>"));
+        assert!(html.contains("<span class=\"kw\">let</span>"));
+        assert!(html.contains("<span class=\"num\">42</span>"));
+        assert!(html.contains("<span class=\"com\">// answer</span>"));
+        assert!(html.contains("<p>Done.</p>"));
+    }
+
+    #[test]
+    fn test_cost_summary() {
+        let conversation = Conversation::from(vec![Message::user("a"), Message::assistant("b")]);
+        let cost = CostSummary {
+            input_tokens: 120,
+            output_tokens: 45,
+            total_cost_usd: Some(0.0123),
+        };
+
+        let html = conversation.to_html(Some(&cost));
+        assert!(html.contains("<span>Messages</span><span>2</span>"));
+        assert!(html.contains("<span>Input tokens</span><span>120</span>"));
+        assert!(html.contains("$0.0123"));
+    }
+
+    #[test]
+    fn test_highlight_identifiers_with_digits() {
+        assert_eq!(highlight("x1", "rust"), "x1");
+        assert_eq!(
+            highlight("'a' # c", "python"),
+            "<span class=\"str\">&#39;a&#39;</span> <span class=\"com\"># c</span>"
+        );
+    }
+}
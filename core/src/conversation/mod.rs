@@ -0,0 +1,271 @@
+//! Conversation transcripts combining messages and tool calls.
+
+mod branch;
+mod html;
+mod render;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+pub use branch::{BranchDiff, MergeStrategy, MessageId};
+pub use html::CostSummary;
+
+/// Record of a tool invoked during a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    /// Name of the tool that was called
+    pub tool_name: String,
+    /// Parameters passed to the tool
+    pub parameters: serde_json::Value,
+    /// Output produced by the tool, or the error message on failure
+    pub output: String,
+    /// Whether the tool call succeeded
+    pub success: bool,
+    /// When the tool call completed
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ToolCallRecord {
+    /// Create a record of a successful tool call
+    pub fn success(
+        tool_name: impl Into<String>,
+        parameters: serde_json::Value,
+        output: impl Into<String>,
+    ) -> Self {
+        Self {
+            tool_name: tool_name.into(),
+            parameters,
+            output: output.into(),
+            success: true,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Create a record of a failed tool call
+    pub fn failure(
+        tool_name: impl Into<String>,
+        parameters: serde_json::Value,
+        error: impl Into<String>,
+    ) -> Self {
+        Self {
+            success: false,
+            ..Self::success(tool_name, parameters, error)
+        }
+    }
+}
+
+/// Short title and summary describing a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// A few words naming the topic, suitable for a chat list
+    pub title: String,
+    /// One paragraph describing what was discussed
+    pub summary: String,
+    /// When the summary was generated
+    pub generated_at: DateTime<Utc>,
+}
+
+impl ConversationSummary {
+    /// Create a summary generated now
+    pub fn new(title: impl Into<String>, summary: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            summary: summary.into(),
+            generated_at: Utc::now(),
+        }
+    }
+}
+
+/// A single entry in a conversation transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Turn {
+    /// A message from the system, user, or assistant
+    Message(Message),
+    /// A tool invocation and its result
+    ToolCall(ToolCallRecord),
+}
+
+/// An ordered transcript of messages and tool calls
+///
+/// Conversations can be rendered for debugging and CLI output with
+/// [`Conversation::to_markdown`] and [`Conversation::to_ansi`], or exported
+/// as a shareable HTML page with [`Conversation::to_html`]. A conversation
+/// can be forked at any turn with [`Conversation::fork_at`] to explore
+/// alternatives, and branches compared or merged back. The prompt versions
+/// used to produce a conversation can be recorded with
+/// [`Conversation::record_prompt`], and a generated title and summary kept
+/// with [`Conversation::set_summary`].
+///
+/// # Example
+///
+/// ```
+/// use agent_core::{Conversation, Message, ToolCallRecord};
+/// use serde_json::json;
+///
+/// let mut conversation = Conversation::new();
+/// conversation.push_message(Message::user("What is 2+2?"));
+/// conversation.push_tool_call(ToolCallRecord::success(
+///     "calculator",
+///     json!({"expression": "2+2"}),
+///     "4",
+/// ));
+/// conversation.push_message(Message::assistant("2+2 is 4."));
+///
+/// assert!(conversation.to_markdown().contains("calculator"));
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Conversation {
+    turns: Vec<Turn>,
+    /// References (`name@version`) of the prompts used in this conversation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    prompts: Vec<String>,
+    /// Generated title and summary, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<ConversationSummary>,
+}
+
+impl Conversation {
+    /// Create an empty conversation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a message, returning its ID
+    pub fn push_message(&mut self, message: Message) -> MessageId {
+        self.turns.push(Turn::Message(message));
+        MessageId(self.turns.len() - 1)
+    }
+
+    /// Append a tool call record, returning its ID
+    pub fn push_tool_call(&mut self, tool_call: ToolCallRecord) -> MessageId {
+        self.turns.push(Turn::ToolCall(tool_call));
+        MessageId(self.turns.len() - 1)
+    }
+
+    /// Record that a prompt version was used to produce this conversation
+    ///
+    /// Recording the same reference twice has no effect.
+    pub fn record_prompt(&mut self, reference: impl Into<String>) {
+        let reference = reference.into();
+        if !self.prompts.contains(&reference) {
+            self.prompts.push(reference);
+        }
+    }
+
+    /// References of the prompts used in this conversation, in the order
+    /// they were first recorded
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// Store a generated title and summary, replacing any previous one
+    pub fn set_summary(&mut self, summary: ConversationSummary) {
+        self.summary = Some(summary);
+    }
+
+    /// The stored title and summary, if one has been generated
+    pub fn summary(&self) -> Option<&ConversationSummary> {
+        self.summary.as_ref()
+    }
+
+    /// The stored title, if a summary has been generated
+    pub fn title(&self) -> Option<&str> {
+        self.summary.as_ref().map(|summary| summary.title.as_str())
+    }
+
+    /// All turns in order
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    /// Iterate over the messages only, skipping tool calls
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.turns.iter().filter_map(|turn| match turn {
+            Turn::Message(message) => Some(message),
+            Turn::ToolCall(_) => None,
+        })
+    }
+
+    /// Number of turns
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    /// Returns true if the conversation has no turns
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+}
+
+impl From<Vec<Message>> for Conversation {
+    fn from(messages: Vec<Message>) -> Self {
+        Self {
+            turns: messages.into_iter().map(Turn::Message).collect(),
+            prompts: Vec::new(),
+            summary: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_push_and_iterate() {
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::user("Hi"));
+        conversation.push_tool_call(ToolCallRecord::success("echo", json!({}), "Hi"));
+        conversation.push_message(Message::assistant("Hello"));
+
+        assert_eq!(conversation.len(), 3);
+        assert_eq!(conversation.messages().count(), 2);
+    }
+
+    #[test]
+    fn test_from_messages() {
+        let conversation = Conversation::from(vec![Message::user("a"), Message::assistant("b")]);
+        assert_eq!(conversation.len(), 2);
+        assert!(!conversation.is_empty());
+    }
+
+    #[test]
+    fn test_failed_tool_call() {
+        let record = ToolCallRecord::failure("file_reader", json!({"path": "x"}), "not found");
+        assert!(!record.success);
+        assert_eq!(record.output, "not found");
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::user("Hi"));
+        conversation.push_tool_call(ToolCallRecord::success("echo", json!({"x": 1}), "1"));
+
+        let json = serde_json::to_string(&conversation).unwrap();
+        let restored: Conversation = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(matches!(restored.turns()[1], Turn::ToolCall(_)));
+    }
+
+    #[test]
+    fn test_record_prompt() {
+        let mut conversation = Conversation::new();
+        conversation.record_prompt("planner@v3");
+        conversation.record_prompt("planner@v3");
+        conversation.record_prompt("summarizer@v1");
+        assert_eq!(conversation.prompts(), ["planner@v3", "summarizer@v1"]);
+
+        let json = serde_json::to_string(&conversation).unwrap();
+        let restored: Conversation = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.prompts(), conversation.prompts());
+
+        // Older transcripts without prompt references still load
+        let restored: Conversation = serde_json::from_str(r#"{"turns": []}"#).unwrap();
+        assert!(restored.prompts().is_empty());
+    }
+}
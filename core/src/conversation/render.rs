@@ -0,0 +1,185 @@
+//! Text renderers for conversations.
+
+use std::fmt::Write;
+
+use super::{Conversation, ToolCallRecord, Turn};
+use crate::Role;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+impl Conversation {
+    /// Render the conversation as Markdown
+    ///
+    /// Each message becomes a heading with its role and timestamp; tool calls
+    /// show their parameters as a JSON code block followed by the output.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for turn in &self.turns {
+            match turn {
+                Turn::Message(message) => {
+                    let _ = writeln!(
+                        out,
+                        "### {} · {}\n\n{}\n",
+                        role_title(&message.role),
+                        message.timestamp.format(TIMESTAMP_FORMAT),
+                        message.content
+                    );
+                }
+                Turn::ToolCall(call) => {
+                    let _ = writeln!(
+                        out,
+                        "### Tool call: `{}` {} · {}\n\n```json\n{}\n```\n\n{}\n\n```\n{}\n```\n",
+                        call.tool_name,
+                        if call.success { "✓" } else { "✗" },
+                        call.timestamp.format(TIMESTAMP_FORMAT),
+                        pretty_parameters(call),
+                        if call.success {
+                            "**Output:**"
+                        } else {
+                            "**Error:**"
+                        },
+                        call.output
+                    );
+                }
+            }
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Render the conversation with ANSI colors for terminal output
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for turn in &self.turns {
+            match turn {
+                Turn::Message(message) => {
+                    let _ = writeln!(
+                        out,
+                        "{}{}{}{} {}{}{}\n{}\n",
+                        BOLD,
+                        role_color(&message.role),
+                        role_title(&message.role),
+                        RESET,
+                        DIM,
+                        message.timestamp.format(TIMESTAMP_FORMAT),
+                        RESET,
+                        message.content
+                    );
+                }
+                Turn::ToolCall(call) => {
+                    let (status_color, status) = if call.success {
+                        (GREEN, "ok")
+                    } else {
+                        (RED, "failed")
+                    };
+                    let _ = writeln!(
+                        out,
+                        "{}{}⚙ {}{} {}{}{} {}{}{}\n{}{}{}\n{}\n",
+                        BOLD,
+                        YELLOW,
+                        call.tool_name,
+                        RESET,
+                        status_color,
+                        status,
+                        RESET,
+                        DIM,
+                        call.timestamp.format(TIMESTAMP_FORMAT),
+                        RESET,
+                        DIM,
+                        call.parameters,
+                        RESET,
+                        call.output
+                    );
+                }
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn role_title(role: &Role) -> &'static str {
+    match role {
+        Role::System => "System",
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+    }
+}
+
+fn role_color(role: &Role) -> &'static str {
+    match role {
+        Role::System => MAGENTA,
+        Role::User => CYAN,
+        Role::Assistant => GREEN,
+    }
+}
+
+fn pretty_parameters(call: &ToolCallRecord) -> String {
+    serde_json::to_string_pretty(&call.parameters).unwrap_or_else(|_| call.parameters.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn fixed(mut message: Message) -> Message {
+        message.timestamp = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        message
+    }
+
+    fn sample() -> Conversation {
+        let mut conversation = Conversation::new();
+        conversation.push_message(fixed(Message::user("What is 2+2?")));
+        conversation.push_tool_call(ToolCallRecord::success(
+            "calculator",
+            json!({"expression": "2+2"}),
+            "4",
+        ));
+        conversation.push_tool_call(ToolCallRecord::failure(
+            "file_reader",
+            json!({"path": "/missing"}),
+            "not found",
+        ));
+        conversation.push_message(fixed(Message::assistant("It is 4.")));
+        conversation
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = sample().to_markdown();
+
+        assert!(markdown.starts_with("### User · 2024-01-02 03:04:05 UTC\n\nWhat is 2+2?"));
+        assert!(markdown.contains("### Tool call: `calculator` ✓"));
+        assert!(markdown.contains("\"expression\": \"2+2\""));
+        assert!(markdown.contains("**Output:**\n\n```\n4\n```"));
+        assert!(markdown.contains("### Tool call: `file_reader` ✗"));
+        assert!(markdown.contains("**Error:**"));
+        assert!(markdown.ends_with("It is 4."));
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        let ansi = sample().to_ansi();
+
+        assert!(ansi.contains(&format!("{}{}User{}", BOLD, CYAN, RESET)));
+        assert!(ansi.contains(&format!("{}{}Assistant{}", BOLD, GREEN, RESET)));
+        assert!(ansi.contains("calculator"));
+        assert!(ansi.contains(&format!("{}failed{}", RED, RESET)));
+    }
+
+    #[test]
+    fn test_empty_conversation() {
+        assert_eq!(Conversation::new().to_markdown(), "");
+        assert_eq!(Conversation::new().to_ansi(), "");
+    }
+}
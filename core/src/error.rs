@@ -1,5 +1,8 @@
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::ErrorContext;
+
 /// Common error type for the AI agent framework
 #[derive(Error, Debug)]
 pub enum AgentError {
@@ -11,6 +14,31 @@ pub enum AgentError {
     #[error("LLM provider error: {0}")]
     LLMProvider(String),
 
+    /// The provider rejected the request for exceeding a rate limit
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        /// What the provider reported
+        message: String,
+        /// How long the provider asked to wait before retrying, if it said
+        retry_after: Option<Duration>,
+    },
+
+    /// The provider rejected the credentials
+    #[error("Authentication failed: {0}")]
+    AuthFailed(String),
+
+    /// The request does not fit in the model's context window
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+
+    /// The provider's content filter blocked the request or the response
+    #[error("Content filtered: {0}")]
+    ContentFiltered(String),
+
+    /// An upstream request timed out
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
     /// Tool execution failed
     #[error("Tool execution failed: {tool_name} - {reason}")]
     ToolExecution {
@@ -20,6 +48,19 @@ pub enum AgentError {
         reason: String,
     },
 
+    /// A tool failed because of an underlying error
+    #[error("Tool '{tool}' failed: {source}")]
+    ToolFailed {
+        /// Name of the tool that failed
+        tool: String,
+        /// The error the tool failed with
+        source: Box<AgentError>,
+    },
+
+    /// The model service is down and no fallback could answer
+    #[error("Service degraded: {0}")]
+    ServiceDegraded(String),
+
     /// Guardrail violation
     #[error("Guardrail violation: {0}")]
     GuardrailViolation(String),
@@ -36,6 +77,14 @@ pub enum AgentError {
     #[error("Execution error: {0}")]
     Execution(String),
 
+    /// The operation was cancelled through a cancellation token
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    /// A run used up its token or cost budget
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -43,11 +92,62 @@ pub enum AgentError {
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    /// Error wrapped with structured operation context
+    #[error("{context}: {source}")]
+    WithContext {
+        /// Context describing where the error occurred
+        context: Box<ErrorContext>,
+        /// The underlying error
+        source: Box<AgentError>,
+    },
 }
 
 /// Result type alias for the AI agent framework
 pub type Result<T> = std::result::Result<T, AgentError>;
 
+impl AgentError {
+    /// Whether retrying the same operation may succeed
+    ///
+    /// Rate limits, timeouts, connection errors and 5xx responses are
+    /// transient; authentication failures, oversized requests, filtered
+    /// content and other client errors are not. Context layers are looked
+    /// through and a [`ToolFailed`](Self::ToolFailed) error is retryable if
+    /// its source is. Untyped `LLMProvider` errors are classified by their
+    /// message.
+    pub fn is_retryable(&self) -> bool {
+        match self.root_cause() {
+            AgentError::RateLimited { .. } | AgentError::Timeout(_) => true,
+            AgentError::ToolFailed { source, .. } => source.is_retryable(),
+            AgentError::Io(error) => matches!(
+                error.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+            ),
+            AgentError::LLMProvider(message) => {
+                let message = message.to_lowercase();
+                message.contains("timeout")
+                    || message.contains("connection error")
+                    || message.contains("rate limit")
+                    || message.contains("http 429")
+                    || message.contains("http 5")
+            }
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retrying, if the provider said
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.root_cause() {
+            AgentError::RateLimited { retry_after, .. } => *retry_after,
+            AgentError::ToolFailed { source, .. } => source.retry_after(),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +171,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_retryability() {
+        let rate_limited = AgentError::RateLimited {
+            message: "slow down".to_string(),
+            retry_after: Some(Duration::from_secs(7)),
+        };
+        assert!(rate_limited.is_retryable());
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(7)));
+        assert!(AgentError::Timeout("read timed out".to_string()).is_retryable());
+        assert!(AgentError::LLMProvider("HTTP 503 error".to_string()).is_retryable());
+
+        assert!(!AgentError::AuthFailed("invalid key".to_string()).is_retryable());
+        assert!(!AgentError::ContextLengthExceeded("too long".to_string()).is_retryable());
+        assert!(!AgentError::ContentFiltered("blocked".to_string()).is_retryable());
+        assert!(!AgentError::LLMProvider("HTTP 400 error".to_string()).is_retryable());
+        assert_eq!(AgentError::Timeout("slow".to_string()).retry_after(), None);
+    }
+
+    #[test]
+    fn test_tool_failed_follows_source() {
+        let err = AgentError::ToolFailed {
+            tool: "web_search".to_string(),
+            source: Box::new(AgentError::Timeout("search API".to_string())),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Tool 'web_search' failed: Timeout: search API"
+        );
+        assert!(err.is_retryable());
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err = AgentError::ToolFailed {
+            tool: "web_search".to_string(),
+            source: Box::new(AgentError::AuthFailed("bad key".to_string())),
+        };
+        assert!(!err.is_retryable());
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_err = io::Error::new(io::ErrorKind::NotFound, "file not found");
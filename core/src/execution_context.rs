@@ -0,0 +1,318 @@
+//! Context shared by every step of a plan run.
+//!
+//! An [`ExecutionContext`] carries facts about the caller (user profile,
+//! locale, references to credentials, arbitrary typed values) that steps
+//! and tools need but that the planner should not have to bake into every
+//! prompt. Step text and tool parameters refer to it with
+//! `{{context.<path>}}` placeholders, for example `{{context.locale}}` or
+//! `{{context.user.name}}`, which the executor fills in before running the
+//! step. Tools that need more than placeholders receive the whole context.
+
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{AgentError, Result};
+
+/// The user a plan is run for
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserProfile {
+    /// Stable user identifier
+    pub id: String,
+    /// Display name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Contact email address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Free-form profile attributes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl UserProfile {
+    /// Create a profile for the given user ID
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the display name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the email address
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Add a profile attribute
+    pub fn with_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Where a credential can be obtained, never the secret itself
+///
+/// The reference (for example `env:GITHUB_TOKEN` or `vault:ci/deploy-key`)
+/// is safe to show to the model and to log; resolving it is up to the tool
+/// that needs the credential.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CredentialRef(String);
+
+impl CredentialRef {
+    /// Create a reference
+    pub fn new(reference: impl Into<String>) -> Self {
+        Self(reference.into())
+    }
+
+    /// The reference string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Typed context bag injected into step templates and tool executions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionContext {
+    /// The user the plan runs for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserProfile>,
+    /// BCP 47 language tag, e.g. `en-GB`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Named credential references
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub credentials: BTreeMap<String, CredentialRef>,
+    /// Additional values, addressed by key in templates
+    #[serde(flatten)]
+    values: BTreeMap<String, Value>,
+}
+
+impl ExecutionContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the user profile
+    pub fn with_user(mut self, user: UserProfile) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Set the locale
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Add a named credential reference
+    pub fn with_credential(mut self, name: impl Into<String>, reference: CredentialRef) -> Self {
+        self.credentials.insert(name.into(), reference);
+        self
+    }
+
+    /// Add a value under `key`
+    ///
+    /// # Errors
+    /// Returns an error if `key` collides with a built-in field or `value`
+    /// cannot be serialized.
+    pub fn with_value<T: Serialize>(mut self, key: impl Into<String>, value: T) -> Result<Self> {
+        self.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// Insert or replace the value under `key`
+    ///
+    /// # Errors
+    /// Returns an error if `key` collides with a built-in field or `value`
+    /// cannot be serialized.
+    pub fn insert<T: Serialize>(&mut self, key: impl Into<String>, value: T) -> Result<()> {
+        let key = key.into();
+        if matches!(key.as_str(), "user" | "locale" | "credentials") {
+            return Err(AgentError::Config(format!(
+                "Context key '{}' is reserved",
+                key
+            )));
+        }
+        self.values.insert(key, serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    /// Get the value under `key` as `T`
+    ///
+    /// Returns `None` if the key is absent or holds a different type.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.values
+            .get(key)
+            .and_then(|value| T::deserialize(value).ok())
+    }
+
+    /// Whether the context holds no information
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Look up a dotted path such as `user.name` or `credentials.github`
+    pub fn lookup(&self, path: &str) -> Option<Value> {
+        let root = serde_json::to_value(self).ok()?;
+        path.split('.')
+            .try_fold(&root, |value, segment| value.get(segment))
+            .cloned()
+    }
+
+    /// Replace `{{context.<path>}}` placeholders in `template`
+    ///
+    /// Strings are inserted as-is; other values as JSON.
+    ///
+    /// # Errors
+    /// Returns an error naming the first placeholder whose path is not set.
+    pub fn render(&self, template: &str) -> Result<String> {
+        const OPEN: &str = "{{context.";
+
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find(OPEN) {
+            let after = &rest[start + OPEN.len()..];
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            let path = after[..end].trim();
+            let value = self.lookup(path).ok_or_else(|| {
+                AgentError::Execution(format!("Context value '{}' is not set", path))
+            })?;
+            rendered.push_str(&rest[..start]);
+            match value {
+                Value::String(text) => rendered.push_str(&text),
+                other => rendered.push_str(&other.to_string()),
+            }
+            rest = &after[end + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+
+    /// Render every string inside a JSON value, e.g. tool parameters
+    ///
+    /// A string that consists of a single placeholder is replaced by the
+    /// referenced value itself, so numbers and objects keep their type.
+    ///
+    /// # Errors
+    /// Returns an error if a placeholder's path is not set.
+    pub fn render_value(&self, value: &Value) -> Result<Value> {
+        Ok(match value {
+            Value::String(text) => {
+                let trimmed = text.trim();
+                let whole = trimmed
+                    .strip_prefix("{{context.")
+                    .and_then(|inner| inner.strip_suffix("}}"))
+                    .filter(|path| !path.contains("}}"));
+                match whole {
+                    Some(path) => self.lookup(path.trim()).ok_or_else(|| {
+                        AgentError::Execution(format!("Context value '{}' is not set", path.trim()))
+                    })?,
+                    None => Value::String(self.render(text)?),
+                }
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.render_value(item))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, item)| Ok((key.clone(), self.render_value(item)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context() -> ExecutionContext {
+        ExecutionContext::new()
+            .with_user(
+                UserProfile::new("u-1")
+                    .with_name("Ada")
+                    .with_attribute("plan", "pro"),
+            )
+            .with_locale("en-GB")
+            .with_credential("github", CredentialRef::new("env:GITHUB_TOKEN"))
+            .with_value("max_results", 5)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let context = context();
+        assert_eq!(context.get::<u32>("max_results"), Some(5));
+        assert_eq!(context.get::<String>("max_results"), None);
+        assert_eq!(context.get::<u32>("missing"), None);
+        assert!(ExecutionContext::new().is_empty());
+        assert!(!context.is_empty());
+    }
+
+    #[test]
+    fn test_reserved_keys_rejected() {
+        let result = ExecutionContext::new().with_value("locale", "fr");
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+
+    #[test]
+    fn test_lookup_paths() {
+        let context = context();
+        assert_eq!(context.lookup("user.name"), Some(json!("Ada")));
+        assert_eq!(context.lookup("user.attributes.plan"), Some(json!("pro")));
+        assert_eq!(
+            context.lookup("credentials.github"),
+            Some(json!("env:GITHUB_TOKEN"))
+        );
+        assert_eq!(context.lookup("max_results"), Some(json!(5)));
+        assert_eq!(context.lookup("user.email"), None);
+    }
+
+    #[test]
+    fn test_render_template() {
+        let context = context();
+        let rendered = context
+            .render("Hi {{context.user.name}}, answer in {{context.locale}} ({{context.max_results}} items)")
+            .unwrap();
+        assert_eq!(rendered, "Hi Ada, answer in en-GB (5 items)");
+        assert_eq!(
+            context.render("no placeholders {{x}}").unwrap(),
+            "no placeholders {{x}}"
+        );
+
+        let result = context.render("{{context.user.email}}");
+        assert!(matches!(result, Err(AgentError::Execution(msg)) if msg.contains("user.email")));
+    }
+
+    #[test]
+    fn test_render_value_keeps_types() {
+        let params = json!({
+            "query": "news for {{context.locale}}",
+            "limit": "{{context.max_results}}",
+            "tags": ["{{context.user.id}}", 3]
+        });
+        assert_eq!(
+            context().render_value(&params).unwrap(),
+            json!({"query": "news for en-GB", "limit": 5, "tags": ["u-1", 3]})
+        );
+    }
+}
@@ -1,9 +1,19 @@
 //! Core types and traits for the AI agent framework.
 //!
 //! This crate provides fundamental types used throughout the framework:
+//! - [`Agent`], the interface of anything that answers a user's input
 //! - [`Message`] and [`Role`] for representing conversation turns
+//! - [`Conversation`] transcripts with Markdown, terminal, and HTML renderers
 //! - [`AgentError`] for error handling across all components
 //! - [`Result`] type alias for convenient error propagation
+//! - [`ResultExt`] for attaching structured [`ErrorContext`] to errors
+//! - [`ErrorSanitizer`] for turning errors into safe [`UserFacingError`]s
+//! - [`ExecutionContext`] for user, locale and credential context shared by
+//!   plan steps and tools
+//! - [`RequestContext`] for trace, session and user IDs that follow a request
+//!   through providers, tools and executor steps
+//! - [`ToolDefinition`] and [`ToolUse`] for native tool calling
+//! - [`Shutdown`] for stopping servers, schedulers and executors gracefully
 //!
 //! # Example
 //!
@@ -14,8 +24,29 @@
 //! assert_eq!(msg.role, Role::User);
 //! ```
 
+mod agent;
+mod context;
+mod conversation;
 mod error;
+mod execution_context;
 mod message;
+mod request_context;
+mod shutdown;
+mod tool_definition;
+mod user_error;
 
+pub use agent::{Agent, AgentOutput};
+pub use context::{ErrorContext, ResultExt};
+pub use conversation::{
+    BranchDiff, Conversation, ConversationSummary, CostSummary, MergeStrategy, MessageId,
+    ToolCallRecord, Turn,
+};
 pub use error::{AgentError, Result};
-pub use message::{Message, Role};
+pub use execution_context::{CredentialRef, ExecutionContext, UserProfile};
+pub use message::{FileRef, ImageContent, Message, Role};
+pub use request_context::{REQUEST_ID_HEADER, RequestContext, TRACEPARENT_HEADER};
+pub use shutdown::{CANCEL_GRACE, Shutdown, ShutdownReport, WorkGuard, shutdown_signal};
+pub use tool_definition::{ToolDefinition, ToolUse, ToolUseResponse};
+pub use user_error::{
+    AuditSink, ErrorAuditRecord, ErrorCategory, ErrorReference, ErrorSanitizer, UserFacingError,
+};
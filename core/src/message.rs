@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Represents the role of a message in a conversation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -12,7 +13,75 @@ pub enum Role {
     Assistant,
 }
 
+/// Reference to a file uploaded to a provider's file API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileRef {
+    /// Provider-assigned file ID
+    pub id: String,
+    /// MIME type of the file, used to decide how it is attached
+    pub mime_type: String,
+}
+
+impl FileRef {
+    /// Create a reference to an uploaded file
+    pub fn new(id: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    /// Whether the file is an image
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+/// An image included in a message, for vision-capable models
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageContent {
+    /// An image the provider downloads from a URL
+    Url {
+        /// HTTP(S) URL of the image
+        url: String,
+    },
+    /// An image sent inline
+    Base64 {
+        /// MIME type, e.g. `image/png`
+        media_type: String,
+        /// Base64-encoded image bytes
+        data: String,
+    },
+}
+
+impl ImageContent {
+    /// An image at a URL
+    pub fn url(url: impl Into<String>) -> Self {
+        Self::Url { url: url.into() }
+    }
+
+    /// An inline image from base64-encoded bytes
+    pub fn base64(media_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self::Base64 {
+            media_type: media_type.into(),
+            data: data.into(),
+        }
+    }
+
+    /// The image as a URL, with inline images as `data:` URLs
+    pub fn to_url(&self) -> String {
+        match self {
+            Self::Url { url } => url.clone(),
+            Self::Base64 { media_type, data } => format!("data:{};base64,{}", media_type, data),
+        }
+    }
+}
+
 /// Represents a single message in a conversation
+///
+/// A message's content is made of its text and, for vision-capable models,
+/// any images and uploaded files it carries.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     /// The role of the message sender
@@ -21,6 +90,12 @@ pub struct Message {
     pub content: String,
     /// When the message was created
     pub timestamp: DateTime<Utc>,
+    /// Uploaded files referenced by this message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<FileRef>,
+    /// Images sent along with the text
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageContent>,
 }
 
 impl Message {
@@ -30,6 +105,8 @@ impl Message {
             role: Role::System,
             content: content.into(),
             timestamp: Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         }
     }
 
@@ -39,6 +116,8 @@ impl Message {
             role: Role::User,
             content: content.into(),
             timestamp: Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         }
     }
 
@@ -48,8 +127,51 @@ impl Message {
             role: Role::Assistant,
             content: content.into(),
             timestamp: Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         }
     }
+
+    /// Attach an uploaded file to this message
+    pub fn with_attachment(mut self, file: FileRef) -> Self {
+        self.attachments.push(file);
+        self
+    }
+
+    /// Add an image to this message
+    pub fn with_image(mut self, image: ImageContent) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    /// Whether the message has anything besides its text
+    pub fn is_multimodal(&self) -> bool {
+        !self.attachments.is_empty() || !self.images.is_empty()
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Formats as `[HH:MM:SS] role: content`
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.timestamp.format("%H:%M:%S"),
+            self.role,
+            self.content
+        )
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +199,50 @@ mod tests {
         assert_eq!(msg.content, "Hello! How can I help you?");
     }
 
+    #[test]
+    fn test_message_display() {
+        let msg = Message::user("Hello");
+        assert!(msg.to_string().ends_with("] user: Hello"));
+        assert_eq!(Role::Assistant.to_string(), "assistant");
+    }
+
+    #[test]
+    fn test_message_with_attachment() {
+        let msg = Message::user("Summarize this")
+            .with_attachment(FileRef::new("file-1", "application/pdf"));
+        assert_eq!(msg.attachments.len(), 1);
+        assert!(!msg.attachments[0].is_image());
+
+        let json = serde_json::to_string(&msg).unwrap();
+        let deserialized: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.attachments, msg.attachments);
+    }
+
+    #[test]
+    fn test_message_with_images() {
+        let msg = Message::user("What is in these pictures?")
+            .with_image(ImageContent::url("https://example.com/cat.png"))
+            .with_image(ImageContent::base64("image/png", "iVBORw0KGgo="));
+        assert!(msg.is_multimodal());
+        assert!(!Message::user("hi").is_multimodal());
+        assert_eq!(msg.images[1].to_url(), "data:image/png;base64,iVBORw0KGgo=");
+
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(
+            json["images"][0],
+            serde_json::json!({"type": "url", "url": "https://example.com/cat.png"})
+        );
+        let deserialized: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.images, msg.images);
+    }
+
+    #[test]
+    fn test_message_without_attachments_deserializes() {
+        let json = r#"{"role":"User","content":"hi","timestamp":"2024-01-01T00:00:00Z"}"#;
+        let msg: Message = serde_json::from_str(json).unwrap();
+        assert!(msg.attachments.is_empty());
+    }
+
     #[test]
     fn test_message_serialization() {
         let msg = Message::user("test");
@@ -0,0 +1,182 @@
+//! Identifiers that follow one user request through the stack.
+//!
+//! A [`RequestContext`] carries the trace ID of a request together with the
+//! session and user it belongs to. Instead of being passed to every
+//! function, it is installed for the duration of a future with
+//! [`RequestContext::scope`]; providers, tools and executor steps running
+//! inside that future read it with [`RequestContext::current`] and stamp the
+//! trace ID on outgoing HTTP headers, error context and audit records.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+tokio::task_local! {
+    static CURRENT: RequestContext;
+}
+
+/// Header carrying the trace ID on outgoing requests
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// W3C Trace Context header, sent when the trace ID is in W3C format
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Trace, session and user identifiers for one request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestContext {
+    /// Identifier shared by everything done for the request
+    pub trace_id: String,
+    /// Session the request belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// User who made the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+}
+
+impl RequestContext {
+    /// Create a context with a new random trace ID
+    ///
+    /// Generated IDs are 32 lowercase hex digits, so they can be used as a
+    /// W3C trace ID.
+    pub fn new() -> Self {
+        Self::with_trace_id(format!("{:016x}{:016x}", random_u64(), random_u64()))
+    }
+
+    /// Create a context that continues an existing trace, e.g. one received
+    /// from an upstream service
+    pub fn with_trace_id(trace_id: impl Into<String>) -> Self {
+        Self {
+            trace_id: trace_id.into(),
+            session_id: None,
+            user_id: None,
+        }
+    }
+
+    /// Set the session ID
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Set the user ID
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// The context installed for the running task, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` with this context installed
+    ///
+    /// Work spawned onto other tasks does not inherit the context; wrap it
+    /// in its own `scope` with a clone.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Headers identifying the request to downstream services
+    ///
+    /// Only the trace ID is sent; session and user IDs stay in-process.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![(REQUEST_ID_HEADER, self.trace_id.clone())];
+        if is_w3c_trace_id(&self.trace_id) {
+            headers.push((
+                TRACEPARENT_HEADER,
+                format!("00-{}-{:016x}-01", self.trace_id, random_u64()),
+            ));
+        }
+        headers
+    }
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "trace={}", self.trace_id)?;
+        if let Some(session_id) = &self.session_id {
+            write!(f, " session={}", session_id)?;
+        }
+        if let Some(user_id) = &self.user_id {
+            write!(f, " user={}", user_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `id` is 32 lowercase hex digits and not all zero
+fn is_w3c_trace_id(id: &str) -> bool {
+    id.len() == 32
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        && id.bytes().any(|b| b != b'0')
+}
+
+/// A practically unique, non-zero 64-bit value
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = DefaultHasher::new();
+    Utc::now().timestamp_nanos_opt().hash(&mut hasher);
+    COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+    std::process::id().hash(&mut hasher);
+    hasher.finish().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_installs_context() {
+        assert_eq!(RequestContext::current(), None);
+
+        let context = RequestContext::new()
+            .with_session_id("s-1")
+            .with_user_id("u-1");
+        let seen = context
+            .clone()
+            .scope(async { RequestContext::current() })
+            .await;
+        assert_eq!(seen, Some(context));
+        assert_eq!(RequestContext::current(), None);
+    }
+
+    #[test]
+    fn test_generated_ids_are_w3c_compatible() {
+        let a = RequestContext::new();
+        let b = RequestContext::new();
+        assert_ne!(a.trace_id, b.trace_id);
+        assert!(is_w3c_trace_id(&a.trace_id));
+
+        let headers = a.headers();
+        assert_eq!(headers[0], (REQUEST_ID_HEADER, a.trace_id.clone()));
+        let traceparent = &headers[1].1;
+        assert!(traceparent.starts_with(&format!("00-{}-", a.trace_id)));
+        assert!(traceparent.ends_with("-01"));
+    }
+
+    #[test]
+    fn test_custom_trace_id_skips_traceparent() {
+        let context = RequestContext::with_trace_id("req-42").with_session_id("s-1");
+        assert_eq!(
+            context.headers(),
+            vec![(REQUEST_ID_HEADER, "req-42".to_string())]
+        );
+        assert_eq!(context.to_string(), "trace=req-42 session=s-1");
+    }
+}
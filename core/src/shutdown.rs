@@ -0,0 +1,314 @@
+//! Graceful shutdown of long-running services.
+//!
+//! A [`Shutdown`] is shared by everything that takes on work: servers,
+//! schedulers and executors. Shutting down happens in phases:
+//!
+//! 1. New work is refused: [`Shutdown::start_work`] fails and
+//!    [`Shutdown::draining`] resolves, so servers stop accepting
+//!    connections.
+//! 2. Work in flight is given until the deadline to finish.
+//! 3. Work still running at the deadline is cancelled through
+//!    [`Shutdown::cancellation_token`]. Executors return the results of the
+//!    steps that finished, so callers can checkpoint the run and resume it
+//!    later, and are given [`CANCEL_GRACE`] to do so.
+//! 4. Hooks registered with [`Shutdown::on_shutdown`] run in order, e.g.
+//!    to flush telemetry and audit logs.
+
+use crate::{AgentError, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+/// How long cancelled work is given to wind down after the deadline
+pub const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// A hook run once work has stopped
+type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send>;
+
+/// Coordinates a graceful shutdown across the parts of a service
+///
+/// Clones share the same state, so one clone can be handed to each
+/// server, scheduler and executor while another triggers the shutdown.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::{Shutdown, shutdown_signal};
+/// use std::time::Duration;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let shutdown = Shutdown::new();
+/// shutdown.on_shutdown("audit log", || async {
+///     // write out buffered records
+///     Ok(())
+/// });
+///
+/// // Each unit of work holds a guard while it runs
+/// let worker = shutdown.clone();
+/// tokio::spawn(async move {
+///     while let Ok(_work) = worker.start_work() {
+///         // take and run one job
+///     }
+/// });
+///
+/// shutdown_signal().await;
+/// let report = shutdown.shutdown(Duration::from_secs(30)).await;
+/// println!("drained cleanly: {}", report.drained);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    draining: CancellationToken,
+    cancel: CancellationToken,
+    in_flight: AtomicUsize,
+    idle: Notify,
+    hooks: Mutex<Vec<(String, ShutdownHook)>>,
+}
+
+impl Shutdown {
+    /// Create a coordinator that is accepting work
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether shutdown has begun and new work is refused
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.is_cancelled()
+    }
+
+    /// Resolves once shutdown begins
+    ///
+    /// Servers pass this as their graceful shutdown signal to stop
+    /// accepting connections.
+    pub fn draining(&self) -> WaitForCancellationFutureOwned {
+        self.inner.draining.clone().cancelled_owned()
+    }
+
+    /// Token cancelled when the deadline passes with work still running
+    ///
+    /// Each call returns a child token, so cancelling it stops only the
+    /// work it was given to.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.inner.cancel.child_token()
+    }
+
+    /// Register a unit of work, which shutdown waits for until the guard is
+    /// dropped
+    ///
+    /// # Errors
+    /// Returns [`AgentError::Cancelled`] once shutdown has begun.
+    pub fn start_work(&self) -> Result<WorkGuard> {
+        // Count first, so shutdown cannot miss work that passed the check
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = WorkGuard {
+            inner: self.inner.clone(),
+        };
+        if self.is_draining() {
+            return Err(AgentError::Cancelled(
+                "service is shutting down".to_string(),
+            ));
+        }
+        Ok(guard)
+    }
+
+    /// Number of units of work currently running
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Run `hook` during shutdown, once work has stopped
+    ///
+    /// Hooks run in registration order. A failing hook is reported in the
+    /// [`ShutdownReport`] and does not stop the hooks after it.
+    pub fn on_shutdown<F, Fut>(&self, name: impl Into<String>, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let hook: ShutdownHook = Box::new(move || Box::pin(hook()));
+        self.inner.hooks.lock().unwrap().push((name.into(), hook));
+    }
+
+    /// Shut down: refuse new work, wait up to `deadline` for work in flight,
+    /// cancel what is left, then run the shutdown hooks
+    ///
+    /// Calling this again, e.g. on a second signal, runs no hooks twice.
+    pub async fn shutdown(&self, deadline: Duration) -> ShutdownReport {
+        let started = Instant::now();
+        self.inner.draining.cancel();
+
+        let drained = tokio::time::timeout(deadline, self.idle()).await.is_ok();
+        let mut cancelled = 0;
+        let mut abandoned = 0;
+        if !drained {
+            cancelled = self.in_flight();
+            self.inner.cancel.cancel();
+            if tokio::time::timeout(CANCEL_GRACE, self.idle())
+                .await
+                .is_err()
+            {
+                abandoned = self.in_flight();
+            }
+        }
+
+        let hooks = std::mem::take(&mut *self.inner.hooks.lock().unwrap());
+        let mut hook_errors = Vec::new();
+        for (name, hook) in hooks {
+            if let Err(e) = hook().await {
+                hook_errors.push(format!("{}: {}", name, e));
+            }
+        }
+
+        ShutdownReport {
+            drained,
+            cancelled,
+            abandoned,
+            hook_errors,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// Wait until no work is in flight
+    async fn idle(&self) {
+        loop {
+            let notified = self.inner.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Marks a unit of work as in flight until dropped
+pub struct WorkGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        if self.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+/// What happened during a shutdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Whether all work finished before the deadline
+    pub drained: bool,
+    /// Units of work cancelled at the deadline
+    pub cancelled: usize,
+    /// Units of work still running after the cancellation grace period
+    pub abandoned: usize,
+    /// Errors of failed shutdown hooks, as `"<hook>: <error>"`
+    pub hook_errors: Vec<String>,
+    /// Time taken by the whole shutdown
+    pub elapsed: Duration,
+}
+
+/// Wait for Ctrl-C, or on Unix also for SIGTERM
+///
+/// # Panics
+/// Panics if signal handlers cannot be installed.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.expect("failed to install Ctrl-C handler"),
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("failed to install Ctrl-C handler");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_work_is_refused_once_draining() {
+        let shutdown = Shutdown::new();
+        let work = shutdown.start_work().unwrap();
+        assert_eq!(shutdown.in_flight(), 1);
+
+        let trigger = shutdown.clone();
+        let handle = tokio::spawn(async move { trigger.shutdown(Duration::from_secs(5)).await });
+        shutdown.draining().await;
+        assert!(shutdown.is_draining());
+        assert!(matches!(
+            shutdown.start_work(),
+            Err(AgentError::Cancelled(_))
+        ));
+        // A refused start leaves nothing in flight
+        assert_eq!(shutdown.in_flight(), 1);
+
+        drop(work);
+        let report = handle.await.unwrap();
+        assert!(report.drained);
+        assert_eq!(report.cancelled, 0);
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_work_past_the_deadline_is_cancelled() {
+        let shutdown = Shutdown::new();
+        let work = shutdown.start_work().unwrap();
+        let cancel = shutdown.cancellation_token();
+        tokio::spawn(async move {
+            cancel.cancelled().await;
+            drop(work);
+        });
+
+        let report = shutdown.shutdown(Duration::from_millis(10)).await;
+        assert!(!report.drained);
+        assert_eq!(report.cancelled, 1);
+        assert_eq!(report.abandoned, 0);
+        assert!(report.elapsed < CANCEL_GRACE);
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_order_after_work_stops() {
+        let shutdown = Shutdown::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for name in ["telemetry", "audit"] {
+            let order = order.clone();
+            shutdown.on_shutdown(name, move || async move {
+                order.lock().unwrap().push(name);
+                Ok(())
+            });
+        }
+        shutdown.on_shutdown("broken", || async {
+            Err(AgentError::Io(std::io::Error::other("disk full")))
+        });
+
+        let report = shutdown.shutdown(Duration::from_secs(1)).await;
+        assert!(report.drained);
+        assert_eq!(*order.lock().unwrap(), ["telemetry", "audit"]);
+        assert_eq!(report.hook_errors.len(), 1);
+        assert!(report.hook_errors[0].starts_with("broken: "));
+
+        // Hooks only run once
+        let report = shutdown.shutdown(Duration::from_secs(1)).await;
+        assert!(report.hook_errors.is_empty());
+        assert_eq!(order.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,67 @@
+//! Provider-neutral types for native tool calling.
+//!
+//! A [`ToolDefinition`] describes a tool the model may call. Providers turn
+//! the definitions into their own request format (Anthropic `tools`, OpenAI
+//! function definitions) and turn the model's reply into a
+//! [`ToolUseResponse`]: the text it wrote plus any [`ToolUse`] requests,
+//! with arguments already parsed as JSON.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A tool the model may call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Name the model uses to call the tool
+    pub name: String,
+    /// What the tool does, shown to the model
+    pub description: String,
+    /// JSON Schema of the tool arguments
+    pub parameters: Value,
+}
+
+impl ToolDefinition {
+    /// Define a tool
+    pub fn new(name: impl Into<String>, description: impl Into<String>, parameters: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+        }
+    }
+}
+
+/// A tool call requested by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUse {
+    /// Provider-assigned ID linking the call to its result
+    pub id: String,
+    /// Name of the tool to call
+    pub name: String,
+    /// Arguments for the tool
+    pub arguments: Value,
+}
+
+/// A model reply to a request that offered tools
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolUseResponse {
+    /// Text the model wrote, if any
+    pub text: String,
+    /// Tool calls the model requested, in order
+    pub tool_uses: Vec<ToolUse>,
+}
+
+impl ToolUseResponse {
+    /// A reply with text and no tool calls
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            tool_uses: Vec::new(),
+        }
+    }
+
+    /// Whether the model asked for at least one tool call
+    pub fn has_tool_uses(&self) -> bool {
+        !self.tool_uses.is_empty()
+    }
+}
@@ -0,0 +1,377 @@
+//! End-user presentation of errors.
+//!
+//! Internal error messages can contain API keys, hostnames, file paths, or
+//! raw provider responses, none of which should be shown to end users.
+//! [`ErrorSanitizer`] maps an [`AgentError`] to a [`UserFacingError`] with a
+//! fixed, safe message and a reference ID. The full error is passed to an
+//! audit sink under the same reference, so a user quoting the ID can be
+//! matched to the detailed record.
+
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{AgentError, RequestContext};
+
+/// Broad class of failure shown to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The deployment is misconfigured
+    Configuration,
+    /// The model provider rejected the request for rate limiting
+    RateLimited,
+    /// An upstream request timed out
+    Timeout,
+    /// The model provider failed or could not be reached
+    ServiceUnavailable,
+    /// A tool needed for the request failed
+    ToolFailure,
+    /// The request or response was blocked by a guardrail
+    PolicyViolation,
+    /// The request used up its token or cost budget
+    BudgetExceeded,
+    /// Any other internal failure
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Classify an error, looking through any attached context
+    pub fn of(error: &AgentError) -> Self {
+        match error.root_cause() {
+            AgentError::Config(_) => Self::Configuration,
+            AgentError::LLMProvider(message) => {
+                let message = message.to_lowercase();
+                if message.contains("rate limit") || message.contains("429") {
+                    Self::RateLimited
+                } else if message.contains("timeout") || message.contains("timed out") {
+                    Self::Timeout
+                } else {
+                    Self::ServiceUnavailable
+                }
+            }
+            AgentError::AuthFailed(_) => Self::Configuration,
+            AgentError::RateLimited { .. } => Self::RateLimited,
+            AgentError::Timeout(_) => Self::Timeout,
+            AgentError::ServiceDegraded(_) => Self::ServiceUnavailable,
+            AgentError::ToolExecution { .. } | AgentError::ToolFailed { .. } => Self::ToolFailure,
+            AgentError::GuardrailViolation(_) | AgentError::ContentFiltered(_) => {
+                Self::PolicyViolation
+            }
+            AgentError::BudgetExceeded(_) => Self::BudgetExceeded,
+            _ => Self::Internal,
+        }
+    }
+
+    /// Safe message describing this category to an end user
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            Self::Configuration => {
+                "The service is not configured correctly. Please contact support."
+            }
+            Self::RateLimited => "The service is busy right now. Please try again in a moment.",
+            Self::Timeout => "The request took too long to complete. Please try again.",
+            Self::ServiceUnavailable => {
+                "The AI service is temporarily unavailable. Please try again later."
+            }
+            Self::ToolFailure => "An action needed for your request could not be completed.",
+            Self::PolicyViolation => "This request was blocked by a content policy.",
+            Self::BudgetExceeded => "This request reached its usage limit before finishing.",
+            Self::Internal => "Something went wrong while processing your request.",
+        }
+    }
+
+    /// Whether retrying the same request may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited | Self::Timeout | Self::ServiceUnavailable
+        )
+    }
+}
+
+/// Identifier linking a user-facing error to its audit record
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorReference(String);
+
+impl ErrorReference {
+    /// Generate a new, practically unique reference such as `ERR-3F2A-91C0`
+    pub fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let mut hasher = DefaultHasher::new();
+        Utc::now().timestamp_nanos_opt().hash(&mut hasher);
+        COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+        std::process::id().hash(&mut hasher);
+        let value = hasher.finish();
+
+        Self(format!(
+            "ERR-{:04X}-{:04X}",
+            (value >> 48) & 0xFFFF,
+            (value >> 32) & 0xFFFF
+        ))
+    }
+
+    /// The reference as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ErrorReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An error that is safe to show to end users
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserFacingError {
+    /// Reference to quote when reporting the problem
+    pub reference: ErrorReference,
+    /// Class of failure
+    pub category: ErrorCategory,
+    /// Safe description of the failure
+    pub message: String,
+}
+
+impl fmt::Display for UserFacingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (reference: {})", self.message, self.reference)
+    }
+}
+
+/// Full details of a sanitized error, for the audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorAuditRecord {
+    /// Reference shown to the user
+    pub reference: ErrorReference,
+    /// When the error was sanitized
+    pub timestamp: DateTime<Utc>,
+    /// Class of failure
+    pub category: ErrorCategory,
+    /// The internal error message, with credentials redacted
+    pub detail: String,
+    /// The request being handled when the error was sanitized, if known
+    pub request: Option<RequestContext>,
+}
+
+/// Callback that receives audit records
+pub type AuditSink = Box<dyn Fn(&ErrorAuditRecord) + Send + Sync>;
+
+/// Converts internal errors into [`UserFacingError`]s
+///
+/// # Example
+///
+/// ```
+/// use agent_core::{AgentError, ErrorSanitizer};
+/// use std::sync::{Arc, Mutex};
+///
+/// let audit_log = Arc::new(Mutex::new(Vec::new()));
+/// let log = audit_log.clone();
+/// let sanitizer = ErrorSanitizer::new()
+///     .with_audit_sink(move |record| log.lock().unwrap().push(record.clone()));
+///
+/// let error = AgentError::LLMProvider(
+///     "OpenAI API HTTP 401 error: invalid key sk-abc123 at api.internal:8443".to_string(),
+/// );
+/// let user_error = sanitizer.sanitize(&error);
+///
+/// assert!(!user_error.to_string().contains("sk-abc123"));
+/// assert!(!user_error.to_string().contains("api.internal"));
+/// assert_eq!(audit_log.lock().unwrap()[0].reference, user_error.reference);
+/// ```
+#[derive(Default)]
+pub struct ErrorSanitizer {
+    audit_sink: Option<AuditSink>,
+}
+
+impl ErrorSanitizer {
+    /// Create a sanitizer without an audit sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send an audit record for every sanitized error to `sink`
+    pub fn with_audit_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&ErrorAuditRecord) + Send + Sync + 'static,
+    {
+        self.audit_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Convert an error for display, recording its details under a new reference
+    pub fn sanitize(&self, error: &AgentError) -> UserFacingError {
+        let category = ErrorCategory::of(error);
+        let reference = ErrorReference::generate();
+
+        if let Some(sink) = &self.audit_sink {
+            sink(&ErrorAuditRecord {
+                reference: reference.clone(),
+                timestamp: Utc::now(),
+                category,
+                detail: redact_credentials(&error.to_string()),
+                request: RequestContext::current(),
+            });
+        }
+
+        UserFacingError {
+            reference,
+            category,
+            message: category.user_message().to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for ErrorSanitizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorSanitizer")
+            .field("audit_sink", &self.audit_sink.is_some())
+            .finish()
+    }
+}
+
+/// Prefixes of common API key formats
+const KEY_PREFIXES: &[&str] = &["sk-", "sk_", "pk_", "rk_", "AIza", "xoxb-", "ghp_"];
+
+/// Mask API keys and bearer tokens so audit records never store credentials
+fn redact_credentials(text: &str) -> String {
+    let mut redact_next = false;
+    text.split(' ')
+        .map(|word| {
+            let bare = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_');
+            let is_key = KEY_PREFIXES
+                .iter()
+                .any(|prefix| bare.starts_with(prefix) && bare.len() > prefix.len() + 3);
+            let redact = redact_next || is_key;
+            redact_next = word.eq_ignore_ascii_case("bearer");
+            if redact && !bare.is_empty() {
+                word.replace(bare, "[REDACTED]")
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorContext, ResultExt};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_categories() {
+        let rate_limited = AgentError::LLMProvider("OpenAI API rate limit exceeded".to_string());
+        let timeout = AgentError::LLMProvider("Anthropic API request timeout: ...".to_string());
+        let tool = AgentError::ToolExecution {
+            tool_name: "file_reader".to_string(),
+            reason: "/etc/secrets not found".to_string(),
+        };
+
+        assert_eq!(ErrorCategory::of(&rate_limited), ErrorCategory::RateLimited);
+        assert_eq!(ErrorCategory::of(&timeout), ErrorCategory::Timeout);
+        assert_eq!(ErrorCategory::of(&tool), ErrorCategory::ToolFailure);
+        assert_eq!(
+            ErrorCategory::of(&AgentError::ServiceDegraded("all providers failed".to_string())),
+            ErrorCategory::ServiceUnavailable
+        );
+        assert_eq!(
+            ErrorCategory::of(&AgentError::BudgetExceeded("1200 of 1000 tokens".to_string())),
+            ErrorCategory::BudgetExceeded
+        );
+        assert_eq!(
+            ErrorCategory::of(&AgentError::RateLimited {
+                message: "too many requests".to_string(),
+                retry_after: None,
+            }),
+            ErrorCategory::RateLimited
+        );
+        assert_eq!(
+            ErrorCategory::of(&AgentError::ContentFiltered("flagged".to_string())),
+            ErrorCategory::PolicyViolation
+        );
+        assert_eq!(
+            ErrorCategory::of(&AgentError::AuthFailed("invalid key".to_string())),
+            ErrorCategory::Configuration
+        );
+        assert!(ErrorCategory::RateLimited.is_retryable());
+        assert!(!ErrorCategory::PolicyViolation.is_retryable());
+    }
+
+    #[test]
+    fn test_context_is_looked_through() {
+        let error: crate::Result<()> = Err(AgentError::GuardrailViolation("pii".to_string()));
+        let error = error
+            .context(ErrorContext::new("send").provider("openai"))
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::PolicyViolation);
+    }
+
+    #[test]
+    fn test_user_message_hides_details() {
+        let error = AgentError::ToolExecution {
+            tool_name: "db".to_string(),
+            reason: "connect to postgres://admin:pw@10.0.0.5:5432 failed".to_string(),
+        };
+        let user_error = ErrorSanitizer::new().sanitize(&error);
+        let shown = user_error.to_string();
+
+        assert!(!shown.contains("10.0.0.5"));
+        assert!(!shown.contains("admin"));
+        assert!(shown.contains(user_error.reference.as_str()));
+    }
+
+    #[test]
+    fn test_audit_record_matches_reference_and_redacts_keys() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let sanitizer = ErrorSanitizer::new()
+            .with_audit_sink(move |record| sink.lock().unwrap().push(record.clone()));
+
+        let error = AgentError::LLMProvider(
+            "request with Bearer abc.def.ghi failed, key sk-ant-api03-xyz invalid".to_string(),
+        );
+        let user_error = sanitizer.sanitize(&error);
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].reference, user_error.reference);
+        assert_eq!(
+            records[0].detail,
+            "LLM provider error: request with Bearer [REDACTED] failed, key [REDACTED] invalid"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_record_carries_request_context() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let sanitizer = ErrorSanitizer::new()
+            .with_audit_sink(move |record| sink.lock().unwrap().push(record.clone()));
+        let request = RequestContext::new().with_user_id("u-1");
+
+        let error = AgentError::Execution("step failed".to_string());
+        request
+            .clone()
+            .scope(async { sanitizer.sanitize(&error) })
+            .await;
+        sanitizer.sanitize(&error);
+
+        let records = records.lock().unwrap();
+        assert_eq!(records[0].request.as_ref(), Some(&request));
+        assert_eq!(records[1].request, None);
+    }
+
+    #[test]
+    fn test_references_are_unique() {
+        let a = ErrorReference::generate();
+        let b = ErrorReference::generate();
+        assert_ne!(a, b);
+        assert!(a.as_str().starts_with("ERR-"));
+        assert_eq!(a.as_str().len(), "ERR-0000-0000".len());
+    }
+}
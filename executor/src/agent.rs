@@ -0,0 +1,275 @@
+//! Agents assembled from a provider, memory and tools.
+
+use agent_core::{Agent, AgentError, AgentOutput, Result, Shutdown};
+use async_trait::async_trait;
+use llm::LLMProvider;
+use memory::{InMemoryStore, MemoryStore};
+use tokio::sync::Mutex;
+use tools::{Tool, ToolRegistry};
+
+use crate::executor::Executor;
+use crate::tool_loop::ToolLoopConfig;
+use crate::types::{ExecutionResult, StepKind};
+
+/// Builder for a [`ToolLoopAgent`]
+///
+/// Only the provider is required. Without a memory store the agent keeps
+/// its conversation in an [`InMemoryStore`]; without tools it answers from
+/// the model alone.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::Agent;
+/// use executor::AgentBuilder;
+/// use tools::Calculator;
+///
+/// # async fn example(provider: Box<dyn llm::LLMProvider>) -> agent_core::Result<()> {
+/// let agent = AgentBuilder::new()
+///     .provider(provider)
+///     .system_prompt("You are a careful bookkeeper.")
+///     .tool(Calculator::new())
+///     .build()?;
+///
+/// let output = agent.run("What is 17% of 2300?").await?;
+/// println!("{}", output.response);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct AgentBuilder {
+    provider: Option<Box<dyn LLMProvider>>,
+    system_prompt: Option<String>,
+    memory: Option<Box<dyn MemoryStore>>,
+    tools: ToolRegistry,
+    config: ToolLoopConfig,
+    shutdown: Option<Shutdown>,
+}
+
+impl AgentBuilder {
+    /// Create a builder with no provider, memory or tools
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the model that drives the agent (required)
+    pub fn provider(mut self, provider: Box<dyn LLMProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Set the instructions given to the model ahead of the tool protocol
+    pub fn system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    /// Set where the conversation is kept between runs
+    pub fn memory(mut self, memory: Box<dyn MemoryStore>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Add a tool the model may call
+    pub fn tool(mut self, tool: impl Tool + 'static) -> Self {
+        self.tools.register(Box::new(tool));
+        self
+    }
+
+    /// Replace the tools with those of `registry`
+    pub fn tools(mut self, registry: ToolRegistry) -> Self {
+        self.tools = registry;
+        self
+    }
+
+    /// Set the iteration, tool call and token limits of each run
+    ///
+    /// A system prompt set on the builder takes precedence over one in
+    /// `config`.
+    pub fn limits(mut self, config: ToolLoopConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register runs with `shutdown`, which refuses new runs once it begins
+    /// and cancels those still going at its deadline
+    pub fn shutdown(mut self, shutdown: &Shutdown) -> Self {
+        self.shutdown = Some(shutdown.clone());
+        self
+    }
+
+    /// Build the agent
+    ///
+    /// # Errors
+    /// Returns an error if no provider was set.
+    pub fn build(self) -> Result<ToolLoopAgent> {
+        let provider = self
+            .provider
+            .ok_or_else(|| AgentError::Config("An agent needs an LLM provider".to_string()))?;
+        let memory = self
+            .memory
+            .unwrap_or_else(|| Box::new(InMemoryStore::new()));
+        let mut config = self.config;
+        if let Some(system_prompt) = self.system_prompt {
+            config.system_prompt = Some(system_prompt);
+        }
+        let mut executor = Executor::new(self.tools, memory);
+        if let Some(shutdown) = &self.shutdown {
+            executor = executor.with_shutdown(shutdown);
+        }
+        Ok(ToolLoopAgent {
+            provider,
+            executor: Mutex::new(executor),
+            config,
+        })
+    }
+}
+
+/// Agent that answers each input with a [tool loop](Executor::run_tool_loop)
+///
+/// Runs share the agent's memory, so later inputs see the earlier
+/// conversation. Concurrent runs on one agent take turns.
+pub struct ToolLoopAgent {
+    provider: Box<dyn LLMProvider>,
+    executor: Mutex<Executor>,
+    config: ToolLoopConfig,
+}
+
+impl ToolLoopAgent {
+    /// Start building an agent
+    pub fn builder() -> AgentBuilder {
+        AgentBuilder::new()
+    }
+
+    /// Run `input` and return the full execution result, with a step result
+    /// per model turn and tool call
+    ///
+    /// # Errors
+    /// Returns an error if the model fails or the run exceeds its budget.
+    pub async fn execute(&self, input: &str) -> Result<ExecutionResult> {
+        let mut executor = self.executor.lock().await;
+        executor
+            .run_tool_loop(self.provider.as_ref(), input, &self.config)
+            .await
+    }
+}
+
+#[async_trait]
+impl Agent for ToolLoopAgent {
+    async fn run(&self, input: &str) -> Result<AgentOutput> {
+        Ok(self.execute(input).await?.into())
+    }
+}
+
+impl From<ExecutionResult> for AgentOutput {
+    fn from(result: ExecutionResult) -> Self {
+        let tools_used = result
+            .step_results
+            .iter()
+            .filter_map(|step| match &step.step_type {
+                StepKind::ToolCall { tool_name } => Some(tool_name.clone()),
+                _ => None,
+            })
+            .collect();
+        Self {
+            response: result.final_response,
+            success: result.success,
+            tools_used,
+            trace_id: result.trace_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::Message;
+    use std::sync::{Arc, Mutex as StdMutex};
+    use tools::Calculator;
+
+    /// Every request a provider was sent
+    type Requests = Arc<StdMutex<Vec<Vec<Message>>>>;
+
+    /// Replies with canned turns and records what it was sent
+    struct ScriptedProvider {
+        turns: StdMutex<Vec<&'static str>>,
+        requests: Requests,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.turns.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    fn scripted(turns: Vec<&'static str>) -> (Box<dyn LLMProvider>, Requests) {
+        let requests = Arc::new(StdMutex::new(Vec::new()));
+        let provider = ScriptedProvider {
+            turns: StdMutex::new(turns),
+            requests: requests.clone(),
+        };
+        (Box::new(provider), requests)
+    }
+
+    #[test]
+    fn test_build_requires_a_provider() {
+        let error = AgentBuilder::new()
+            .system_prompt("Be brief.")
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(error, AgentError::Config(_)));
+    }
+
+    #[tokio::test]
+    async fn test_agent_calls_tools_and_answers() {
+        let call = r#"{"tool_calls": [{"tool_name": "calculator",
+            "parameters": {"operation": "multiply", "a": 6, "b": 7}}]}"#;
+        let (provider, requests) = scripted(vec![
+            call,
+            r#"{"final_answer": "It is 42."}"#,
+        ]);
+        let agent = ToolLoopAgent::builder()
+            .provider(provider)
+            .system_prompt("You are a careful bookkeeper.")
+            .tool(Calculator::new())
+            .build()
+            .unwrap();
+
+        let output = agent.run("What is 6 times 7?").await.unwrap();
+        assert!(output.success);
+        assert_eq!(output.response, "It is 42.");
+        assert_eq!(output.tools_used, vec!["calculator".to_string()]);
+
+        // The structured output instructions come first, then the agent's
+        // system prompt
+        let requests = requests.lock().unwrap();
+        let system = &requests[0][1].content;
+        assert!(system.starts_with("You are a careful bookkeeper."));
+        assert!(system.contains("- calculator:"));
+        assert!(requests[1].last().unwrap().content.contains("42"));
+    }
+
+    #[tokio::test]
+    async fn test_runs_share_memory() {
+        let (provider, requests) = scripted(vec![
+            r#"{"final_answer": "Hello, Sam."}"#,
+            r#"{"final_answer": "Your name is Sam."}"#,
+        ]);
+        let agent = AgentBuilder::new().provider(provider).build().unwrap();
+
+        agent.run("I'm Sam.").await.unwrap();
+        let output = agent.run("What's my name?").await.unwrap();
+        assert_eq!(output.response, "Your name is Sam.");
+        assert!(output.tools_used.is_empty());
+
+        let requests = requests.lock().unwrap();
+        let contents: Vec<_> = requests[1].iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            &contents[2..],
+            ["I'm Sam.", "Hello, Sam.", "What's my name?"]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Token and cost limits on a run.
+//!
+//! A [`Budget`] caps the tokens, and with prices the dollars, that the
+//! model calls of one plan or tool loop may use. The executor adds up the
+//! usage each response reports and stops the run with
+//! [`AgentError::BudgetExceeded`] once a limit is reached.
+
+use agent_core::{AgentError, Result};
+use llm::TokenUsage;
+use std::sync::Mutex;
+
+/// Limits on the model usage of one run
+///
+/// Only usage reported by the provider counts, so responses from providers
+/// that don't report usage are free. The cost limit needs prices; without
+/// them every call costs nothing.
+///
+/// # Example
+///
+/// ```
+/// use executor::Budget;
+/// use llm::TokenUsage;
+///
+/// let budget = Budget::new()
+///     .with_max_tokens(50_000)
+///     .with_max_cost_usd(0.25)
+///     .with_prices(3.0, 15.0);
+///
+/// let usage = TokenUsage { input_tokens: 10_000, output_tokens: 2_000 };
+/// assert_eq!(budget.cost_usd(&usage), 0.06);
+/// assert!(budget.check(&usage).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    /// Maximum prompt and generated tokens combined, if limited
+    pub max_tokens: Option<usize>,
+    /// Maximum cost in US dollars, if limited
+    pub max_cost_usd: Option<f64>,
+    /// US dollars per million prompt tokens
+    pub input_usd_per_million: f64,
+    /// US dollars per million generated tokens
+    pub output_usd_per_million: f64,
+}
+
+impl Budget {
+    /// Create a budget without limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop once this many tokens have been used
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Stop once this many US dollars have been spent
+    pub fn with_max_cost_usd(mut self, max_cost_usd: f64) -> Self {
+        self.max_cost_usd = Some(max_cost_usd);
+        self
+    }
+
+    /// Set the prices used to cost usage, in US dollars per million tokens
+    pub fn with_prices(mut self, input_usd_per_million: f64, output_usd_per_million: f64) -> Self {
+        self.input_usd_per_million = input_usd_per_million;
+        self.output_usd_per_million = output_usd_per_million;
+        self
+    }
+
+    /// Cost in US dollars of `usage`
+    pub fn cost_usd(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input_usd_per_million
+            + usage.output_tokens as f64 * self.output_usd_per_million)
+            / 1_000_000.0
+    }
+
+    /// Check whether `usage` leaves room for more model calls
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AgentError::BudgetExceeded`] if `usage` has reached the
+    /// token or cost limit.
+    pub fn check(&self, usage: &TokenUsage) -> Result<()> {
+        if let Some(max_tokens) = self.max_tokens
+            && usage.total() >= max_tokens
+        {
+            return Err(AgentError::BudgetExceeded(format!(
+                "used {} of {} tokens",
+                usage.total(),
+                max_tokens
+            )));
+        }
+        if let Some(max_cost_usd) = self.max_cost_usd {
+            let cost = self.cost_usd(usage);
+            if cost >= max_cost_usd {
+                return Err(AgentError::BudgetExceeded(format!(
+                    "spent ${:.4} of ${:.4}",
+                    cost, max_cost_usd
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A budget and the usage counted against it during the current run
+#[derive(Debug)]
+pub(crate) struct BudgetTracker {
+    pub budget: Budget,
+    spent: Mutex<TokenUsage>,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            spent: Mutex::new(TokenUsage::default()),
+        }
+    }
+
+    /// Usage counted since the run started
+    pub fn spent(&self) -> TokenUsage {
+        *self.spent.lock().unwrap()
+    }
+
+    /// Start counting a new run
+    pub fn reset(&self) {
+        *self.spent.lock().unwrap() = TokenUsage::default();
+    }
+
+    /// Fail if the budget is used up, before making a model call
+    pub fn check(&self) -> Result<()> {
+        self.budget.check(&self.spent())
+    }
+
+    /// Count the usage of a model response, failing if it used up the
+    /// budget
+    pub fn spend(&self, usage: Option<TokenUsage>) -> Result<()> {
+        let spent = {
+            let mut spent = self.spent.lock().unwrap();
+            *spent = *spent + usage.unwrap_or_default();
+            *spent
+        };
+        self.budget.check(&spent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: usize, output_tokens: usize) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    #[test]
+    fn test_token_limit() {
+        let budget = Budget::new().with_max_tokens(1000);
+        assert!(budget.check(&usage(600, 399)).is_ok());
+        let err = budget.check(&usage(600, 400)).unwrap_err();
+        assert!(matches!(err, AgentError::BudgetExceeded(_)));
+        assert_eq!(err.to_string(), "Budget exceeded: used 1000 of 1000 tokens");
+    }
+
+    #[test]
+    fn test_cost_limit_uses_prices() {
+        let budget = Budget::new().with_max_cost_usd(0.01);
+        assert!(budget.check(&usage(1_000_000, 1_000_000)).is_ok());
+
+        let budget = budget.with_prices(1.0, 4.0);
+        assert!(budget.check(&usage(1000, 1000)).is_ok());
+        let err = budget.check(&usage(2000, 2000)).unwrap_err();
+        assert_eq!(err.to_string(), "Budget exceeded: spent $0.0100 of $0.0100");
+    }
+
+    #[test]
+    fn test_tracker_accumulates_until_reset() {
+        let tracker = BudgetTracker::new(Budget::new().with_max_tokens(100));
+        assert!(tracker.spend(Some(usage(40, 10))).is_ok());
+        assert!(tracker.spend(None).is_ok());
+        assert!(tracker.check().is_ok());
+        assert!(tracker.spend(Some(usage(40, 10))).is_err());
+        assert!(tracker.check().is_err());
+        assert_eq!(tracker.spent(), usage(80, 20));
+
+        tracker.reset();
+        assert!(tracker.check().is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+//! Comparison of two runs of the same plan.
+//!
+//! [`ExecutionResult::diff`] lines up step results by position and reports
+//! what changed between a baseline run and a candidate run, for example
+//! before and after a model or prompt upgrade. Timing differences are
+//! reported but do not count as behavioural changes.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::types::{ExecutionResult, StepKind, StepResult};
+
+/// A value that differs between two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change<T> {
+    /// Value in the baseline run
+    pub before: T,
+    /// Value in the candidate run
+    pub after: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    /// A change if the values differ
+    fn between(before: T, after: T) -> Option<Self> {
+        (before != after).then_some(Self { before, after })
+    }
+}
+
+/// Differences in one step present in both runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepDiff {
+    /// Position of the step in the plan
+    pub index: usize,
+    /// Step type, if it changed
+    pub step_type: Option<Change<StepKind>>,
+    /// Step output, if it changed
+    pub output: Option<Change<String>>,
+    /// Success flag, if it changed
+    pub success: Option<Change<bool>>,
+    /// Duration in milliseconds, if both runs timed the step and it changed
+    pub duration_ms: Option<Change<u64>>,
+}
+
+impl StepDiff {
+    /// Whether anything other than timing changed
+    pub fn has_changes(&self) -> bool {
+        self.step_type.is_some() || self.output.is_some() || self.success.is_some()
+    }
+}
+
+/// Structured delta between two [`ExecutionResult`]s
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutionDiff {
+    /// Overall success flag, if it changed
+    pub success: Option<Change<bool>>,
+    /// Final response, if it changed
+    pub final_response: Option<Change<String>>,
+    /// Total step time in milliseconds, if both runs were timed and it changed
+    pub total_duration_ms: Option<Change<u64>>,
+    /// Steps present in both runs that differ in any way, including timing
+    pub steps: Vec<StepDiff>,
+    /// Steps only in the candidate run
+    pub added: Vec<StepResult>,
+    /// Steps only in the baseline run
+    pub removed: Vec<StepResult>,
+}
+
+impl ExecutionDiff {
+    /// Whether the runs behaved the same, ignoring timing
+    pub fn is_identical(&self) -> bool {
+        self.success.is_none()
+            && self.final_response.is_none()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && !self.steps.iter().any(StepDiff::has_changes)
+    }
+}
+
+impl ExecutionResult {
+    /// Compare this (baseline) run with another run of the same plan
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use executor::{ExecutionResult, StepKind, StepResult};
+    ///
+    /// let before = ExecutionResult {
+    ///     success: true,
+    ///     final_response: "42".to_string(),
+    ///     step_results: vec![StepResult::success(StepKind::Response, "42")],
+    ///     trace_id: None,
+    /// };
+    /// let mut after = before.clone();
+    /// after.final_response = "forty-two".to_string();
+    /// after.step_results[0].output = "forty-two".to_string();
+    ///
+    /// let diff = before.diff(&after);
+    /// assert!(!diff.is_identical());
+    /// assert_eq!(diff.steps[0].index, 0);
+    /// ```
+    pub fn diff(&self, other: &ExecutionResult) -> ExecutionDiff {
+        let steps = self
+            .step_results
+            .iter()
+            .zip(&other.step_results)
+            .enumerate()
+            .map(|(index, (before, after))| StepDiff {
+                index,
+                step_type: Change::between(before.step_type.clone(), after.step_type.clone()),
+                output: Change::between(before.output.clone(), after.output.clone()),
+                success: Change::between(before.success, after.success),
+                duration_ms: before
+                    .duration_ms
+                    .zip(after.duration_ms)
+                    .and_then(|(before, after)| Change::between(before, after)),
+            })
+            .filter(|step| step.has_changes() || step.duration_ms.is_some())
+            .collect();
+
+        let shared = self.step_results.len().min(other.step_results.len());
+        ExecutionDiff {
+            success: Change::between(self.success, other.success),
+            final_response: Change::between(
+                self.final_response.clone(),
+                other.final_response.clone(),
+            ),
+            total_duration_ms: self
+                .total_duration_ms()
+                .zip(other.total_duration_ms())
+                .and_then(|(before, after)| Change::between(before, after)),
+            steps,
+            added: other.step_results[shared..].to_vec(),
+            removed: self.step_results[shared..].to_vec(),
+        }
+    }
+}
+
+/// Write a before/after pair of multi-line text as `-`/`+` lines
+fn write_text_change(f: &mut fmt::Formatter<'_>, change: &Change<String>) -> fmt::Result {
+    for line in change.before.lines() {
+        writeln!(f, "  - {}", line)?;
+    }
+    for line in change.after.lines() {
+        writeln!(f, "  + {}", line)?;
+    }
+    Ok(())
+}
+
+fn write_duration(f: &mut fmt::Formatter<'_>, label: &str, change: &Change<u64>) -> fmt::Result {
+    let delta = change.after as i128 - change.before as i128;
+    writeln!(
+        f,
+        "{}: {}ms -> {}ms ({:+}ms)",
+        label, change.before, change.after, delta
+    )
+}
+
+impl fmt::Display for ExecutionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() && self.steps.is_empty() && self.total_duration_ms.is_none() {
+            return writeln!(f, "No differences");
+        }
+
+        if let Some(change) = &self.success {
+            writeln!(f, "success: {} -> {}", change.before, change.after)?;
+        }
+        if let Some(change) = &self.final_response {
+            writeln!(f, "final response:")?;
+            write_text_change(f, change)?;
+        }
+        if let Some(change) = &self.total_duration_ms {
+            write_duration(f, "total time", change)?;
+        }
+
+        for step in &self.steps {
+            let label = format!("step {}", step.index + 1);
+            if let Some(change) = &step.step_type {
+                writeln!(f, "{} type: {} -> {}", label, change.before, change.after)?;
+            }
+            if let Some(change) = &step.success {
+                writeln!(
+                    f,
+                    "{} success: {} -> {}",
+                    label, change.before, change.after
+                )?;
+            }
+            if let Some(change) = &step.output {
+                writeln!(f, "{} output:", label)?;
+                write_text_change(f, change)?;
+            }
+            if let Some(change) = &step.duration_ms {
+                write_duration(f, &format!("{} time", label), change)?;
+            }
+        }
+
+        for step in &self.added {
+            writeln!(f, "added step ({}): {}", step.step_type, step.output)?;
+        }
+        for step in &self.removed {
+            writeln!(f, "removed step ({}): {}", step.step_type, step.output)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(steps: Vec<StepResult>) -> ExecutionResult {
+        let success = steps.iter().all(|s| s.success);
+        let final_response = steps.last().map(|s| s.output.clone()).unwrap_or_default();
+        ExecutionResult {
+            success,
+            final_response,
+            step_results: steps,
+            trace_id: None,
+        }
+    }
+
+    fn timed(step: StepResult, ms: u64) -> StepResult {
+        step.with_duration(std::time::Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn test_identical_runs_with_timing_changes() {
+        let before = result(vec![
+            timed(StepResult::success(StepKind::tool_call("search"), "3 results"), 120),
+            timed(StepResult::success(StepKind::Response, "Done"), 10),
+        ]);
+        let after = result(vec![
+            timed(StepResult::success(StepKind::tool_call("search"), "3 results"), 80),
+            timed(StepResult::success(StepKind::Response, "Done"), 10),
+        ]);
+
+        let diff = before.diff(&after);
+        assert!(diff.is_identical());
+        assert_eq!(diff.steps.len(), 1);
+        assert_eq!(
+            diff.steps[0].duration_ms,
+            Some(Change {
+                before: 120,
+                after: 80
+            })
+        );
+        assert_eq!(
+            diff.total_duration_ms,
+            Some(Change {
+                before: 130,
+                after: 90
+            })
+        );
+        assert!(
+            diff.to_string()
+                .contains("step 1 time: 120ms -> 80ms (-40ms)")
+        );
+    }
+
+    #[test]
+    fn test_output_and_success_changes() {
+        let before = result(vec![
+            StepResult::success(StepKind::tool_call("search"), "3 results"),
+            StepResult::success(StepKind::Response, "Paris"),
+        ]);
+        let after = result(vec![
+            StepResult::failure(StepKind::tool_call("search"), "timeout"),
+            StepResult::success(StepKind::Response, "I don't know"),
+        ]);
+
+        let diff = before.diff(&after);
+        assert!(!diff.is_identical());
+        assert_eq!(
+            diff.success,
+            Some(Change {
+                before: true,
+                after: false
+            })
+        );
+        assert_eq!(diff.steps.len(), 2);
+        assert_eq!(
+            diff.steps[0].success,
+            Some(Change {
+                before: true,
+                after: false
+            })
+        );
+        assert!(diff.total_duration_ms.is_none());
+
+        let text = diff.to_string();
+        assert!(text.contains("success: true -> false"));
+        assert!(text.contains("step 1 output:\n  - 3 results\n  + timeout\n"));
+        assert!(text.contains("final response:\n  - Paris\n  + I don't know\n"));
+    }
+
+    #[test]
+    fn test_added_and_removed_steps() {
+        let before = result(vec![StepResult::success(StepKind::Reasoning, "Think")]);
+        let after = result(vec![
+            StepResult::success(StepKind::Reasoning, "Think"),
+            StepResult::success(StepKind::Response, "Answer"),
+        ]);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added.len(), 1);
+        assert!(diff.removed.is_empty());
+        assert!(diff.to_string().contains("added step (response): Answer"));
+
+        let reverse = after.diff(&before);
+        assert_eq!(reverse.removed.len(), 1);
+    }
+
+    #[test]
+    fn test_no_differences() {
+        let run = result(vec![StepResult::success(StepKind::Response, "Hi")]);
+        let diff = run.diff(&run.clone());
+        assert!(diff.is_identical());
+        assert_eq!(diff.to_string(), "No differences\n");
+    }
+}
@@ -1,9 +1,69 @@
-use agent_core::{Message, Result};
+use agent_core::{
+    AgentError, ErrorContext, ExecutionContext, Message, RequestContext, Result, ResultExt,
+    Shutdown, WorkGuard,
+};
+use futures::StreamExt;
+use llm::{
+    CancellationToken, CompletionResponse, ImageProvider, LLMProvider, TokenUsage, cancellable,
+};
 use memory::MemoryStore;
-use planner::{Plan, Step};
+use planner::{Condition, Plan, PlanEvent, PlanStream, Step};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::pin;
+use std::time::{Duration, Instant};
 use tools::ToolRegistry;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+use crate::budget::{Budget, BudgetTracker};
+use crate::tool_loop::{self, ToolLoopConfig, Turn};
+use crate::trace::{self, TraceEvent, TraceRecorder};
+use crate::types::{ExecutionResult, StepKind, StepResult, StreamExecution};
+use crate::workflow::{WorkflowEngine, WorkflowInput, WorkflowRun};
+
+/// What a conditional step tests
+enum Tested<'a> {
+    /// The result of its check step, which has yet to run
+    Check(&'a Step),
+    /// The result of an earlier plan step
+    Earlier(&'a StepResult),
+}
+
+/// Progress of a plan that is being executed
+#[derive(Default)]
+struct PlanRun {
+    step_results: Vec<StepResult>,
+    final_response: String,
+    failed: bool,
+    /// Set when a step used up the budget, which fails the whole run
+    budget_exceeded: Option<AgentError>,
+}
+
+impl PlanRun {
+    fn finish(mut self, request: Option<RequestContext>) -> Result<ExecutionResult> {
+        if let Some(error) = self.budget_exceeded {
+            return Err(error);
+        }
+
+        // If no explicit response step was found, build a response from the results
+        if self.final_response.is_empty() && !self.step_results.is_empty() {
+            self.final_response = self.step_results
+                .iter()
+                .filter(|r| r.success)
+                .map(|r| r.output.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
 
-use crate::types::{ExecutionResult, StepResult};
+        Ok(ExecutionResult {
+            success: !self.failed,
+            final_response: self.final_response,
+            step_results: self.step_results,
+            trace_id: request.map(|request| request.trace_id),
+        })
+    }
+}
 
 /// The Executor is responsible for running plans generated by the planner.
 /// 
@@ -14,6 +74,24 @@ pub struct Executor {
     tools: ToolRegistry,
     /// Memory store for conversation context
     memory: Box<dyn MemoryStore>,
+    /// Provider used for image generation steps, if configured
+    image_provider: Option<Box<dyn ImageProvider>>,
+    /// Provider used for text generation steps without a profile
+    llm_provider: Option<Box<dyn LLMProvider>>,
+    /// Providers for text generation steps, by model profile name
+    profiles: HashMap<String, Box<dyn LLMProvider>>,
+    /// Context injected into step templates and tool executions
+    context: ExecutionContext,
+    /// Maximum number of plan steps run at the same time
+    max_concurrency: usize,
+    /// Token that aborts running plans and tool loops when cancelled
+    cancel: CancellationToken,
+    /// Limits on the model usage of each run, if any
+    budget: Option<BudgetTracker>,
+    /// Collects a trace of every run, if set
+    recorder: Option<TraceRecorder>,
+    /// Coordinator that runs are registered with, if any
+    shutdown: Option<Shutdown>,
 }
 
 impl Executor {
@@ -26,7 +104,184 @@ impl Executor {
     /// # Returns
     /// A new Executor instance
     pub fn new(tools: ToolRegistry, memory: Box<dyn MemoryStore>) -> Self {
-        Self { tools, memory }
+        Self {
+            tools,
+            memory,
+            image_provider: None,
+            llm_provider: None,
+            profiles: HashMap::new(),
+            context: ExecutionContext::new(),
+            max_concurrency: 1,
+            cancel: CancellationToken::new(),
+            budget: None,
+            recorder: None,
+            shutdown: None,
+        }
+    }
+
+    /// Sets the provider used to run image generation steps.
+    /// 
+    /// # Arguments
+    /// * `provider` - The image provider to use
+    pub fn with_image_provider(mut self, provider: Box<dyn ImageProvider>) -> Self {
+        self.image_provider = Some(provider);
+        self
+    }
+
+    /// Sets the provider used to run text generation steps that do not
+    /// name a model profile.
+    /// 
+    /// # Arguments
+    /// * `provider` - The LLM provider to use
+    pub fn with_llm_provider(mut self, provider: Box<dyn LLMProvider>) -> Self {
+        self.llm_provider = Some(provider);
+        self
+    }
+
+    /// Adds a model profile that text generation steps can select by name.
+    /// 
+    /// Each step of a plan can run on a different provider and model, e.g.
+    /// a cheap `summarizer` for condensing search results and a strong
+    /// `coder` for writing code.
+    /// 
+    /// # Arguments
+    /// * `name` - Profile name used in plan steps
+    /// * `provider` - The LLM provider for the profile
+    pub fn with_profile(mut self, name: impl Into<String>, provider: Box<dyn LLMProvider>) -> Self {
+        self.profiles.insert(name.into(), provider);
+        self
+    }
+
+    /// Returns the names of the configured model profiles, sorted.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.profiles.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Sets the context injected into steps and tools.
+    /// 
+    /// `{{context.<path>}}` placeholders in step text, image prompts and tool
+    /// parameters are filled in from `context` before the step runs, and
+    /// tools receive it through [`tools::Tool::execute_with_context`].
+    /// 
+    /// # Arguments
+    /// * `context` - User, locale, credential and custom context
+    pub fn with_context(mut self, context: ExecutionContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Sets how many steps of a parallel group may run at the same time.
+    /// 
+    /// Only the steps a plan groups in a [`Step::Parallel`] run
+    /// concurrently, since the plan declares them independent; every other
+    /// step waits for the one before it, so a step can rely on the side
+    /// effects of earlier ones. Results of a group keep its order whatever
+    /// order the steps finish in. The default of 1 runs grouped steps one
+    /// after another too; 0 is treated as 1.
+    /// 
+    /// # Arguments
+    /// * `max_concurrency` - Maximum number of grouped steps in flight
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Returns the maximum number of grouped steps run at the same time.
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Sets the token that aborts execution when cancelled.
+    /// 
+    /// Cancelling stops a running plan or tool loop: steps in flight are
+    /// dropped along with their tool calls and model requests, and the run
+    /// returns the results of the steps that had finished, followed by a
+    /// failed `cancelled` step. Runs started after cancellation stop before
+    /// their first step, so pass a fresh token to run again.
+    /// 
+    /// # Arguments
+    /// * `cancel` - Token shared with whoever may abort execution
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Returns the token that aborts execution when cancelled.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Takes part in a graceful shutdown.
+    /// 
+    /// Every run is registered with `shutdown`, which waits for it before
+    /// exiting. Once shutdown begins, new runs fail with
+    /// [`AgentError::Cancelled`]; runs still going at the shutdown deadline
+    /// are cancelled as with [`with_cancellation`](Self::with_cancellation),
+    /// whose token this replaces. A cancelled plan's step results can be
+    /// kept and passed to [`execute_plan_from`](Self::execute_plan_from)
+    /// after a restart.
+    /// 
+    /// # Arguments
+    /// * `shutdown` - Coordinator shared with the rest of the service
+    pub fn with_shutdown(mut self, shutdown: &Shutdown) -> Self {
+        self.cancel = shutdown.cancellation_token();
+        self.shutdown = Some(shutdown.clone());
+        self
+    }
+
+    /// Sets limits on the tokens and cost of each run.
+    /// 
+    /// The usage reported by text generation steps and tool loop turns is
+    /// added up from the start of every plan or tool loop. Once a limit is
+    /// reached, no further model calls are made and the run returns
+    /// [`AgentError::BudgetExceeded`] instead of a result; steps already
+    /// running when the limit is reached still finish.
+    /// 
+    /// # Arguments
+    /// * `budget` - Token and cost limits applied to each run
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(BudgetTracker::new(budget));
+        self
+    }
+
+    /// Returns the budget applied to each run, if any.
+    pub fn budget(&self) -> Option<&Budget> {
+        self.budget.as_ref().map(|tracker| &tracker.budget)
+    }
+
+    /// Returns the usage counted against the budget in the current or last
+    /// run; zero without a budget.
+    pub fn budget_spent(&self) -> TokenUsage {
+        self.budget
+            .as_ref()
+            .map(BudgetTracker::spent)
+            .unwrap_or_default()
+    }
+
+    /// Records every run into `recorder`.
+    /// 
+    /// Each plan execution and tool loop becomes a
+    /// [`RunTrace`](crate::RunTrace) with its steps, model requests and
+    /// responses, tool calls and memory changes, which a
+    /// [`RunDebugger`](crate::RunDebugger) can step through and re-execute.
+    /// 
+    /// # Arguments
+    /// * `recorder` - Recorder shared with whoever reads the traces
+    pub fn with_recorder(mut self, recorder: TraceRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Returns the context injected into steps and tools.
+    pub fn context(&self) -> &ExecutionContext {
+        &self.context
+    }
+
+    /// Returns the context for modification between runs.
+    pub fn context_mut(&mut self) -> &mut ExecutionContext {
+        &mut self.context
     }
 
     /// Lists all available tools in the registry.
@@ -37,11 +292,14 @@ impl Executor {
         self.tools.list_tools()
     }
 
-    /// Executes a complete plan by running each step sequentially.
+    /// Executes a complete plan.
     /// 
-    /// This method iterates through all steps in the plan, executing each one
-    /// and collecting the results. After each step, the result is added to memory
-    /// to provide context for subsequent steps.
+    /// Steps run in order and their results are collected in plan order,
+    /// each with its own wall time; the steps of a [`Step::Parallel`] group
+    /// run up to [`max_concurrency`](Self::max_concurrency) at a time. Each
+    /// result is added to memory in plan order. Execution stops at the first
+    /// failed step. Tool calls with a [`RetryPolicy`](planner::RetryPolicy)
+    /// only fail once their retries and fallback step have failed too.
     /// 
     /// # Arguments
     /// * `plan` - The plan to execute
@@ -49,169 +307,1152 @@ impl Executor {
     /// # Returns
     /// An ExecutionResult containing the success status, final response, and all step results
     pub async fn execute_plan(&mut self, plan: Plan) -> Result<ExecutionResult> {
-        let mut step_results = Vec::new();
-        let mut final_response = String::new();
-        let mut overall_success = true;
-
-        // Execute each step in sequence
-        for step in plan.steps {
-            match self.execute_step(&step).await {
-                Ok(step_result) => {
-                    // Add result to memory for context
-                    let message = Message::assistant(step_result.output.clone());
-                    self.memory.add_message(message);
-
-                    // If this is a Response step, use it as the final response
-                    if step_result.step_type == "response" {
-                        final_response = step_result.output.clone();
-                    }
+        self.execute_plan_from(plan, Vec::new()).await
+    }
+
+    /// Hands a plan to a workflow engine instead of executing it here.
+    /// 
+    /// The workflow is started with the plan and this executor's context;
+    /// its workers run the steps. Read the run back with
+    /// [`WorkflowEngine::status`] or [`wait_for_workflow`](crate::wait_for_workflow).
+    /// 
+    /// # Arguments
+    /// * `engine` - The engine to run the plan on
+    /// * `workflow_id` - ID to start the workflow under
+    /// * `plan` - The plan to execute
+    /// 
+    /// # Returns
+    /// The started workflow run
+    pub async fn delegate_plan(
+        &self,
+        engine: &dyn WorkflowEngine,
+        workflow_id: &str,
+        plan: Plan,
+    ) -> Result<WorkflowRun> {
+        let input = WorkflowInput::new(plan).with_context(self.context.clone());
+        engine.start(workflow_id, &input).await
+    }
+
+    /// Executes the rest of a plan whose first steps have already run.
+    /// 
+    /// `completed` holds the results of the first `completed.len()` steps,
+    /// which are reused as they are; the remaining steps run as in
+    /// [`execute_plan`](Self::execute_plan). The completed results are not
+    /// added to memory again, so memory should already reflect them.
+    /// 
+    /// # Arguments
+    /// * `plan` - The whole plan, including the completed steps
+    /// * `completed` - Results of the steps that already ran, in plan order
+    /// 
+    /// # Returns
+    /// An ExecutionResult for the whole plan
+    #[tracing::instrument(
+        name = "executor.plan",
+        skip_all,
+        fields(
+            trace_id = RequestContext::current().map(|request| request.trace_id),
+            steps = plan.steps.len(),
+            success = Empty,
+            input_tokens = Empty,
+            output_tokens = Empty,
+        )
+    )]
+    pub async fn execute_plan_from(
+        &mut self,
+        plan: Plan,
+        completed: Vec<StepResult>,
+    ) -> Result<ExecutionResult> {
+        let _work = self.start_work()?;
+        let request = RequestContext::current();
+        let mut run = PlanRun::default();
+        self.start_budget();
+        self.begin_trace(Some(&plan), None);
+        for step_result in completed {
+            self.trace(|| TraceEvent::StepFinished {
+                step: run.step_results.len(),
+                result: step_result.clone(),
+            });
+            if let Some(response) = step_result.response() {
+                run.final_response = response.to_string();
+            }
+            run.step_results.push(step_result);
+        }
+        let remaining = plan.steps.into_iter().skip(run.step_results.len()).collect();
+        self.run_plan_steps(remaining, &request, &mut run).await;
+        let result = run.finish(request);
+        record_run(&result);
+        self.finish_trace(&result);
+        result
+    }
+
+    /// Executes a plan while it is still being generated.
+    /// 
+    /// Every step is passed to `validate` as soon as it arrives, so a plan
+    /// with a rejected step fails before the model has finished writing it.
+    /// With [`StreamExecution::ValidateFirst`] nothing runs until the whole
+    /// plan has arrived and passed validation. With [`StreamExecution::Eager`]
+    /// each step runs as soon as it is validated: steps only depend on their
+    /// own parameters and the execution context, never on later steps, so
+    /// the first action starts while the rest of the plan is streaming.
+    /// 
+    /// In eager mode a step rejected by `validate` fails the run after the
+    /// steps before it have already executed, and a failed step ends the
+    /// run without reading the rest of the stream.
+    /// 
+    /// # Arguments
+    /// * `plan` - Plan events from [`planner::Planner::stream_plan`]
+    /// * `mode` - Whether steps may run before the plan is complete
+    /// * `validate` - Check applied to each step, e.g. guardrails
+    /// 
+    /// # Returns
+    /// An ExecutionResult for the complete plan, or the first validation,
+    /// planning or stream error
+    #[tracing::instrument(
+        name = "executor.plan_stream",
+        skip_all,
+        fields(
+            trace_id = RequestContext::current().map(|request| request.trace_id),
+            ?mode,
+            success = Empty,
+            input_tokens = Empty,
+            output_tokens = Empty,
+        )
+    )]
+    pub async fn execute_plan_stream<F>(
+        &mut self,
+        plan: PlanStream,
+        mode: StreamExecution,
+        validate: F,
+    ) -> Result<ExecutionResult>
+    where
+        F: FnMut(&Step) -> Result<()>,
+    {
+        let _work = self.start_work()?;
+        self.begin_trace(None, None);
+        let result = self.run_plan_stream(plan, mode, validate).await;
+        record_run(&result);
+        self.finish_trace(&result);
+        result
+    }
 
-                    step_results.push(step_result);
+    /// Executes a streamed plan; see
+    /// [`execute_plan_stream`](Self::execute_plan_stream).
+    async fn run_plan_stream<F>(
+        &mut self,
+        mut plan: PlanStream,
+        mode: StreamExecution,
+        mut validate: F,
+    ) -> Result<ExecutionResult>
+    where
+        F: FnMut(&Step) -> Result<()>,
+    {
+        let request = RequestContext::current();
+        let mut run = PlanRun::default();
+        let mut validated = 0;
+        self.start_budget();
+
+        while let Some(event) = self.next_plan_event(&mut plan, &mut run).await {
+            match event? {
+                PlanEvent::Step(step) => {
+                    validate(&step)?;
+                    validated += 1;
+                    if mode == StreamExecution::Eager
+                        && !self.run_plan_step(step, &request, &mut run).await
+                    {
+                        return run.finish(request);
+                    }
                 }
-                Err(e) => {
-                    // Step failed - record the failure and stop execution
-                    let step_result = StepResult::failure(
-                        "error",
-                        format!("Step execution failed: {}", e),
-                    );
-                    step_results.push(step_result);
-                    overall_success = false;
-                    break;
+                PlanEvent::Complete(plan) => {
+                    if let Some(recorder) = &self.recorder {
+                        recorder.set_plan(&plan);
+                    }
+                    for step in plan.steps.iter().skip(validated) {
+                        validate(step)?;
+                    }
+                    let remaining = plan.steps.into_iter().skip(run.step_results.len()).collect();
+                    self.run_plan_steps(remaining, &request, &mut run).await;
+                    return run.finish(request);
                 }
             }
         }
 
-        // If no explicit response step was found, build a response from the results
-        if final_response.is_empty() && !step_results.is_empty() {
-            final_response = step_results
-                .iter()
-                .filter(|r| r.success)
-                .map(|r| r.output.as_str())
-                .collect::<Vec<_>>()
-                .join("\n");
+        if run.failed {
+            return run.finish(request);
         }
+        Err(AgentError::Planning(
+            "Plan stream ended without a complete plan".to_string(),
+        ))
+    }
 
-        Ok(ExecutionResult {
-            success: overall_success,
-            final_response,
+    /// Waits for the next event of a streamed plan.
+    /// 
+    /// Returns `None` when the stream ends or, after recording the
+    /// cancellation in `run`, when execution is cancelled.
+    async fn next_plan_event(
+        &self,
+        plan: &mut PlanStream,
+        run: &mut PlanRun,
+    ) -> Option<Result<PlanEvent>> {
+        match cancellable(&self.cancel, async { Ok(plan.next().await) }).await {
+            Ok(event) => event,
+            Err(e) => {
+                run.step_results.push(StepResult::failure(StepKind::Cancelled, e.to_string()));
+                run.failed = true;
+                None
+            }
+        }
+    }
+
+    /// Runs the remaining steps of a plan in order and records their
+    /// results in `run`.
+    /// 
+    /// Stops at the first failed step.
+    async fn run_plan_steps(
+        &mut self,
+        steps: Vec<Step>,
+        request: &Option<RequestContext>,
+        run: &mut PlanRun,
+    ) {
+        for step in steps {
+            if !self.run_plan_step(step, request, run).await {
+                break;
+            }
+        }
+    }
+
+    /// Runs the next step of a plan and records its result in `run`.
+    /// 
+    /// Returns whether the plan should continue.
+    async fn run_plan_step(
+        &mut self,
+        step: Step,
+        request: &Option<RequestContext>,
+        run: &mut PlanRun,
+    ) -> bool {
+        let index = run.step_results.len();
+        let outcome = self.timed_step(index, &step, &run.step_results, request).await;
+        self.record_step(outcome, run)
+    }
+
+    /// Executes the plan step at `index`, measuring how long it took.
+    /// 
+    /// `earlier` holds the results of the plan steps before it that have
+    /// finished.
+    #[tracing::instrument(
+        name = "executor.step",
+        skip_all,
+        fields(
+            step = index,
+            kind = %StepKind::of(step),
+            success = Empty,
+            model = Empty,
+            input_tokens = Empty,
+            output_tokens = Empty,
+        )
+    )]
+    async fn timed_step(
+        &self,
+        index: usize,
+        step: &Step,
+        earlier: &[StepResult],
+        request: &Option<RequestContext>,
+    ) -> (Result<StepResult>, Duration) {
+        self.trace(|| TraceEvent::StepStarted {
+            step: index,
+            definition: step.clone(),
+        });
+        let started = Instant::now();
+        let execution = trace::in_step(index, self.execute_step(step, earlier));
+        let outcome = cancellable(&self.cancel, execution).await.with_context(|| {
+            let context = ErrorContext::new("execute step").step_id((index + 1).to_string());
+            match request {
+                Some(request) => context.request(request),
+                None => context,
+            }
+        });
+        record_step(&outcome);
+        (outcome, started.elapsed())
+    }
+
+    /// Records the outcome of a plan step in `run` and memory.
+    /// 
+    /// Returns whether the plan should continue.
+    fn record_step(
+        &mut self,
+        (outcome, elapsed): (Result<StepResult>, Duration),
+        run: &mut PlanRun,
+    ) -> bool {
+        match outcome {
+            Ok(step_result) => {
+                let step_result = step_result.with_duration(elapsed);
+                self.trace(|| TraceEvent::StepFinished {
+                    step: run.step_results.len(),
+                    result: step_result.clone(),
+                });
+                // Add result to memory for context
+                self.remember(Message::assistant(step_result.output.clone()));
+
+                // If this is a Response step, or a subgoal ending in one,
+                // use it as the final response
+                if let Some(response) = step_result.response() {
+                    run.final_response = response.to_string();
+                }
+
+                // A loop that used up its iterations fails the plan, but its
+                // result is kept with every iteration
+                let success = step_result.success;
+                run.step_results.push(step_result);
+                run.failed |= !success;
+                success
+            }
+            Err(e) if matches!(e.root_cause(), AgentError::BudgetExceeded(_)) => {
+                self.trace(|| TraceEvent::StepFinished {
+                    step: run.step_results.len(),
+                    result: StepResult::failure(StepKind::BudgetExceeded, e.to_string())
+                        .with_duration(elapsed),
+                });
+                run.budget_exceeded = Some(e);
+                run.failed = true;
+                false
+            }
+            Err(e) => {
+                // Step failed or was cancelled - record it and stop execution
+                let step_type = match e.root_cause() {
+                    AgentError::Cancelled(_) => StepKind::Cancelled,
+                    _ => StepKind::Error,
+                };
+                let step_result = StepResult::failure(
+                    step_type,
+                    format!("Step execution failed: {}", e),
+                )
+                .with_duration(elapsed);
+                self.trace(|| TraceEvent::StepFinished {
+                    step: run.step_results.len(),
+                    result: step_result.clone(),
+                });
+                run.step_results.push(step_result);
+                run.failed = true;
+                false
+            }
+        }
+    }
+
+    /// Answers a query by letting the model call tools until it is done.
+    /// 
+    /// Each turn the model either requests tool calls, which are executed
+    /// via the registry with their results appended to the conversation, or
+    /// gives a final answer. Failed tool calls are reported back to the model
+    /// so it can recover. The loop stops with `success: false` when a limit
+    /// in `config` is reached before a final answer.
+    /// 
+    /// Every model turn is recorded as a `model` step result carrying the
+    /// turn's token usage and model name, so the cost of the loop can be
+    /// read from [`ExecutionResult::total_usage`].
+    /// 
+    /// The query and the final answer are added to memory, and the existing
+    /// memory contents (within the token budget, if any) are sent as
+    /// conversation context. If `config` has a compactor, repeated content
+    /// is collapsed before every turn.
+    /// 
+    /// Cancelling the executor's [cancellation token](Self::with_cancellation)
+    /// drops the model request or tool call in flight and stops the loop
+    /// with the step results recorded so far. Reaching the executor's
+    /// [budget](Self::with_budget) stops the loop with
+    /// [`AgentError::BudgetExceeded`].
+    /// 
+    /// # Arguments
+    /// * `provider` - The LLM that drives the loop
+    /// * `query` - The user's request
+    /// * `config` - Iteration, tool call and token limits
+    /// 
+    /// # Returns
+    /// An ExecutionResult with the final answer and one step result per model
+    /// turn and per tool call
+    #[tracing::instrument(
+        name = "executor.tool_loop",
+        skip_all,
+        fields(
+            trace_id = RequestContext::current().map(|request| request.trace_id),
+            max_iterations = config.max_iterations,
+            success = Empty,
+            input_tokens = Empty,
+            output_tokens = Empty,
+        )
+    )]
+    pub async fn run_tool_loop(
+        &mut self,
+        provider: &dyn LLMProvider,
+        query: &str,
+        config: &ToolLoopConfig,
+    ) -> Result<ExecutionResult> {
+        let _work = self.start_work()?;
+        self.begin_trace(None, Some(query));
+        let result = self.drive_tool_loop(provider, query, config).await;
+        record_run(&result);
+        self.finish_trace(&result);
+        result
+    }
+
+    /// Runs the turns of a tool loop; see
+    /// [`run_tool_loop`](Self::run_tool_loop).
+    async fn drive_tool_loop(
+        &mut self,
+        provider: &dyn LLMProvider,
+        query: &str,
+        config: &ToolLoopConfig,
+    ) -> Result<ExecutionResult> {
+        let output = tool_loop::turn_output();
+        let system_prompt =
+            tool_loop::system_prompt(config.system_prompt.as_deref(), &self.list_tools());
+        let mut conversation = vec![Message::system(system_prompt)];
+        conversation.extend(
+            self.memory
+                .get_within_budget(config.token_budget.unwrap_or(usize::MAX)),
+        );
+        conversation.push(Message::user(query));
+        self.remember(Message::user(query));
+
+        let mut step_results = Vec::new();
+        let mut tool_calls_made = 0;
+        self.start_budget();
+
+        for _ in 0..config.max_iterations {
+            let request = match &config.compaction {
+                Some(compactor) => Cow::Owned(compactor.compact(&conversation).0),
+                None => Cow::Borrowed(conversation.as_slice()),
+            };
+
+            if let Some(budget) = config.token_budget {
+                let used: usize = request.iter().map(memory::count_tokens).sum();
+                if used > budget {
+                    return Ok(Self::stopped(
+                        step_results,
+                        StepKind::Error,
+                        format!("exceeded token budget ({} > {})", used, budget),
+                    ));
+                }
+            }
+
+            self.check_budget()?;
+            let turn_index = step_results.len();
+            self.trace(|| TraceEvent::LlmRequest {
+                step: turn_index,
+                profile: None,
+                messages: request.to_vec(),
+            });
+            let started = Instant::now();
+            let completion = provider
+                .send_structured_completion(&request, &output)
+                .instrument(tracing::info_span!("executor.model_turn", turn = turn_index));
+            let (value, response) = match cancellable(&self.cancel, completion).await {
+                Err(AgentError::Cancelled(_)) => {
+                    let reason = "cancelled".to_string();
+                    return Ok(Self::stopped(step_results, StepKind::Cancelled, reason));
+                }
+                outcome => outcome?,
+            };
+            self.trace(|| TraceEvent::LlmResponse {
+                step: turn_index,
+                text: response.text.clone(),
+                usage: response.usage,
+                model: response.model.clone(),
+            });
+            self.spend_budget(&response)?;
+            conversation.push(Message::assistant(value.to_string()));
+            let model_result = StepResult::success(StepKind::Model, value.to_string())
+                .with_duration(started.elapsed())
+                .with_completion(&response);
+            self.trace(|| TraceEvent::StepFinished {
+                step: turn_index,
+                result: model_result.clone(),
+            });
+            step_results.push(model_result);
+            let turn = Turn::from_value(value)?;
+
+            if turn.tool_calls.is_empty() {
+                let answer = turn.final_answer.unwrap_or_default();
+                self.remember(Message::assistant(answer.clone()));
+                return Ok(ExecutionResult {
+                    success: true,
+                    final_response: answer,
+                    step_results,
+                    trace_id: RequestContext::current().map(|request| request.trace_id),
+                });
+            }
+
+            if tool_calls_made + turn.tool_calls.len() > config.max_tool_calls {
+                return Ok(Self::stopped(
+                    step_results,
+                    StepKind::Error,
+                    format!("exceeded max tool calls ({})", config.max_tool_calls),
+                ));
+            }
+            tool_calls_made += turn.tool_calls.len();
+
+            let mut results = String::from("Tool results:");
+            for tool_call in &turn.tool_calls {
+                let started = Instant::now();
+                let call = trace::in_step(step_results.len(), self.handle_tool_call(tool_call));
+                let call = cancellable(&self.cancel, call);
+                let step_result = match call.await {
+                    Ok(step_result) => step_result,
+                    Err(AgentError::Cancelled(_)) => {
+                        let reason = "cancelled".to_string();
+                        return Ok(Self::stopped(step_results, StepKind::Cancelled, reason));
+                    }
+                    Err(e) => {
+                        StepResult::failure(StepKind::tool_call(&tool_call.tool_name), e.to_string())
+                    }
+                }
+                .with_duration(started.elapsed());
+                let status = if step_result.success { "ok" } else { "error" };
+                results.push_str(&format!(
+                    "\n[{}] {} ({}): {}",
+                    tool_call.tool_name, tool_call.parameters, status, step_result.output
+                ));
+                self.trace(|| TraceEvent::StepFinished {
+                    step: step_results.len(),
+                    result: step_result.clone(),
+                });
+                step_results.push(step_result);
+            }
+            conversation.push(Message::user(results));
+        }
+
+        Ok(Self::stopped(
             step_results,
-        })
+            StepKind::Error,
+            format!("exceeded max iterations ({})", config.max_iterations),
+        ))
+    }
+
+    /// Builds the result of a tool loop that hit a limit or was cancelled.
+    fn stopped(
+        mut step_results: Vec<StepResult>,
+        step_type: StepKind,
+        reason: String,
+    ) -> ExecutionResult {
+        let message = format!("Tool loop stopped: {}", reason);
+        step_results.push(StepResult::failure(step_type, message.clone()));
+        ExecutionResult {
+            success: false,
+            final_response: message,
+            step_results,
+            trace_id: RequestContext::current().map(|request| request.trace_id),
+        }
     }
 
     /// Executes a single step from the plan.
     /// 
     /// This method pattern matches on the step type and delegates to the
-    /// appropriate handler. For ToolCall steps, it calls handle_tool_call.
+    /// appropriate handler. For ToolCall steps, it calls run_tool_call,
+    /// for ImageGeneration steps, handle_image_generation, and for
+    /// TextGeneration steps, handle_text_generation, and for Conditional,
+    /// Loop, Subgoal and Parallel steps, run_conditional, run_loop,
+    /// run_subgoal and run_parallel, which run their nested steps.
     /// For Reasoning and Response steps, it returns the text as the result.
+    /// Context placeholders in text and prompts are filled in first.
     /// 
     /// # Arguments
     /// * `step` - The step to execute
+    /// * `earlier` - Results of the plan steps that finished before the plan
+    ///   step this one is in, for conditionals that test them
     /// 
     /// # Returns
     /// A StepResult containing the step type, output, and success status
-    async fn execute_step(&mut self, step: &Step) -> Result<StepResult> {
+    async fn execute_step(&self, step: &Step, earlier: &[StepResult]) -> Result<StepResult> {
         match step {
             Step::ToolCall(tool_call) => {
-                self.handle_tool_call(tool_call).await
+                self.run_tool_call(tool_call, earlier).await
             }
             Step::Reasoning { text } => {
-                Ok(StepResult::success("reasoning", text.clone()))
+                Ok(StepResult::success(StepKind::Reasoning, self.context.render(text)?))
             }
             Step::Response { text } => {
-                Ok(StepResult::success("response", text.clone()))
+                Ok(StepResult::success(StepKind::Response, self.context.render(text)?))
+            }
+            Step::ImageGeneration { prompt } => {
+                let prompt = self.context.render(prompt)?;
+                self.handle_image_generation(&prompt).await
+            }
+            Step::TextGeneration { prompt, profile } => {
+                let prompt = self.context.render(prompt)?;
+                self.handle_text_generation(&prompt, profile.as_deref()).await
             }
+            Step::Conditional { check, step, condition, then, otherwise } => {
+                let tested = match check {
+                    Some(check) => Tested::Check(check),
+                    None => Tested::Earlier(Self::earlier_result(earlier, *step)?),
+                };
+                self.run_conditional(tested, condition, then, otherwise, earlier).await
+            }
+            Step::Loop { body, until, max_iterations } => {
+                self.run_loop(body, until, *max_iterations, earlier).await
+            }
+            Step::Subgoal { goal, profile, steps } => {
+                self.run_subgoal(goal, profile.as_deref(), steps).await
+            }
+            Step::Parallel { steps } => self.run_parallel(steps, earlier).await,
         }
     }
 
-    /// Handles the execution of a tool call.
+    /// Runs a conditional step.
     /// 
-    /// This method looks up the tool in the registry, executes it with the
-    /// provided parameters, and wraps the result in a StepResult. If the tool
-    /// is not found or execution fails, an error is returned.
-    /// 
-    /// # Arguments
-    /// * `tool_call` - The tool call to execute
+    /// A check step runs first. Its failure is not an error, since the
+    /// condition may test for it, unless the run was cancelled or used up
+    /// its budget. Without a check the condition tests the result of an
+    /// earlier plan step. The branch chosen by the condition then runs in
+    /// order and stops at the first failed step.
     /// 
     /// # Returns
-    /// A StepResult containing the tool output or an error
-    async fn handle_tool_call(&mut self, tool_call: &planner::ToolCall) -> Result<StepResult> {
-        // Look up the tool in the registry
-        let tool = self.tools.get(&tool_call.tool_name).ok_or_else(|| {
-            agent_core::AgentError::ToolExecution {
-                tool_name: tool_call.tool_name.clone(),
-                reason: "Tool not found in registry".to_string(),
+    /// A conditional StepResult with the output of the last step that ran
+    /// and the usage of every step, or the error of a failed branch step
+    async fn run_conditional(
+        &self,
+        tested: Tested<'_>,
+        condition: &Condition,
+        then: &[Step],
+        otherwise: &[Step],
+        earlier: &[StepResult],
+    ) -> Result<StepResult> {
+        let mut result = StepResult::success(StepKind::Conditional, String::new());
+        let holds = match tested {
+            Tested::Check(check) => {
+                let checked = self.run_nested_check(check, earlier).await?;
+                let holds = condition.holds(checked.success, &checked.output);
+                result.absorb(checked);
+                result.success = true;
+                holds
             }
-        })?;
+            Tested::Earlier(earlier) => condition.holds(earlier.success, &earlier.output),
+        };
 
-        // Execute the tool with the provided parameters
-        match tool.execute(tool_call.parameters.clone()).await {
-            Ok(result) => {
-                // Convert the JSON result to a string for the step result
-                let output = serde_json::to_string_pretty(&result)
-                    .unwrap_or_else(|_| result.to_string());
-                
-                Ok(StepResult::success(
-                    format!("tool_call:{}", tool_call.tool_name),
-                    output,
-                ))
-            }
-            Err(e) => {
-                Err(agent_core::AgentError::ToolExecution {
-                    tool_name: tool_call.tool_name.clone(),
-                    reason: e.to_string(),
-                })
+        let branch = if holds { then } else { otherwise };
+        for step in branch {
+            let nested = Box::pin(self.execute_step(step, earlier)).await?;
+            let failed = !nested.success;
+            result.absorb(nested);
+            if failed {
+                break;
             }
         }
+        Ok(result)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use agent_core::Message;
-    use async_trait::async_trait;
-    use planner::{Plan, Step, ToolCall};
-    use serde_json::{json, Value};
-    use std::sync::{Arc, Mutex};
 
-    // Mock MemoryStore for testing
-    #[derive(Clone)]
-    struct MockMemoryStore {
-        messages: Arc<Mutex<Vec<Message>>>,
+    /// Returns the result of the earlier plan step at `index`, or of the
+    /// last one to finish if `index` is unset.
+    fn earlier_result(earlier: &[StepResult], index: Option<usize>) -> Result<&StepResult> {
+        let found = match index {
+            Some(index) => earlier.get(index),
+            None => earlier.last(),
+        };
+        found.ok_or_else(|| {
+            AgentError::Execution(match index {
+                Some(index) => format!("Conditional tests step {}, which has not run", index),
+                None => "Conditional has no earlier step to test".to_string(),
+            })
+        })
     }
 
-    impl MockMemoryStore {
-        fn new() -> Self {
-            Self {
-                messages: Arc::new(Mutex::new(Vec::new())),
+    /// Runs a loop step.
+    /// 
+    /// Each iteration runs the body in order; a failed step ends the
+    /// iteration, and `until` is tested against the last step that ran.
+    /// Failures end the loop only if the run was cancelled or used up its
+    /// budget.
+    /// 
+    /// # Returns
+    /// A loop StepResult with the output of the final iteration, the usage
+    /// of every step and a result per iteration. It is a failure if `until`
+    /// still does not hold after `max_iterations` iterations, which fails
+    /// the plan with every iteration recorded.
+    #[tracing::instrument(
+        name = "executor.loop",
+        skip_all,
+        fields(max_iterations, iterations = Empty)
+    )]
+    async fn run_loop(
+        &self,
+        body: &[Step],
+        until: &Condition,
+        max_iterations: u32,
+        earlier: &[StepResult],
+    ) -> Result<StepResult> {
+        let mut result = StepResult::success(StepKind::Loop, String::new());
+        for _ in 0..max_iterations {
+            let started = Instant::now();
+            let mut iteration = StepResult::success(StepKind::Loop, String::new());
+            for step in body {
+                let outcome = self.run_nested_check(step, earlier).await?;
+                let failed = !outcome.success;
+                iteration.absorb(outcome);
+                if failed {
+                    break;
+                }
+            }
+            let iteration = iteration.with_duration(started.elapsed());
+            let done = until.holds(iteration.success, &iteration.output);
+            result.absorb(iteration.clone());
+            result.iterations.push(iteration);
+            Span::current().record("iterations", result.iterations.len());
+            if done {
+                result.success = true;
+                return Ok(result);
             }
         }
-
-        fn get_messages(&self) -> Vec<Message> {
-            self.messages.lock().unwrap().clone()
-        }
+        result.output = format!(
+            "Loop condition not met after {} iterations; last output: {}",
+            max_iterations, result.output
+        );
+        result.success = false;
+        Ok(result)
     }
 
-    impl MemoryStore for MockMemoryStore {
-        fn add_message(&mut self, message: Message) {
-            self.messages.lock().unwrap().push(message);
-        }
-
-        fn get_recent(&self, limit: usize) -> Vec<Message> {
-            let messages = self.messages.lock().unwrap();
-            messages.iter().rev().take(limit).rev().cloned().collect()
+    /// Runs a subgoal step.
+    /// 
+    /// Its steps run in order, like a plan of their own: conditionals in
+    /// them test the results of the subgoal's earlier steps, and the first
+    /// failed step fails the subgoal. Text generation steps that name no
+    /// profile run with the subgoal's profile, if it has one.
+    /// 
+    /// # Returns
+    /// A subgoal StepResult with the output of its last step, the usage of
+    /// every step and a timed result per step, or the error of a failed step
+    #[tracing::instrument(name = "executor.subgoal", skip_all, fields(goal, profile))]
+    async fn run_subgoal(
+        &self,
+        goal: &str,
+        profile: Option<&str>,
+        steps: &[Step],
+    ) -> Result<StepResult> {
+        let mut result = StepResult::success(
+            StepKind::Subgoal { goal: goal.to_string() },
+            String::new(),
+        );
+        for step in steps {
+            let step = match profile {
+                Some(profile) => step.with_default_profile(profile),
+                None => step.clone(),
+            };
+            let started = Instant::now();
+            let substep = Box::pin(self.execute_step(&step, &result.substeps))
+                .await?
+                .with_duration(started.elapsed());
+            let failed = !substep.success;
+            result.absorb(substep.clone());
+            result.substeps.push(substep);
+            if failed {
+                break;
+            }
         }
+        Ok(result)
+    }
 
-        fn get_within_budget(&self, _token_budget: usize) -> Vec<Message> {
-            self.messages.lock().unwrap().clone()
+    /// Runs the steps of a parallel group, up to `max_concurrency` at a
+    /// time.
+    /// 
+    /// The plan declares the steps independent, so they may start and
+    /// finish in any order; conditionals among them test the plan steps
+    /// before the group. The first failed step, in the group's order, fails
+    /// the group, and steps still running are dropped.
+    /// 
+    /// # Returns
+    /// A parallel StepResult with the output of its last step, the usage of
+    /// every step and a timed result per step in the group's order, or the
+    /// error of a failed step
+    #[tracing::instrument(name = "executor.parallel", skip_all, fields(steps = steps.len()))]
+    async fn run_parallel(&self, steps: &[Step], earlier: &[StepResult]) -> Result<StepResult> {
+        let mut result = StepResult::success(StepKind::Parallel, String::new());
+        let mut running = pin!(
+            futures::stream::iter(steps)
+                .map(|step| async move {
+                    let started = Instant::now();
+                    let outcome = Box::pin(self.execute_step(step, earlier)).await;
+                    outcome.map(|substep| substep.with_duration(started.elapsed()))
+                })
+                .buffered(self.max_concurrency)
+        );
+        while let Some(substep) = running.next().await {
+            let substep = substep?;
+            let failed = !substep.success;
+            result.absorb(substep.clone());
+            result.substeps.push(substep);
+            if failed {
+                break;
+            }
         }
+        Ok(result)
+    }
 
-        fn clear(&mut self) {
-            self.messages.lock().unwrap().clear();
+    /// Runs a step nested in a conditional or loop, returning a failure
+    /// as a failed StepResult so a condition can test it.
+    /// 
+    /// Cancellation and budget errors are returned as errors, since they
+    /// end the whole run.
+    async fn run_nested_check(&self, step: &Step, earlier: &[StepResult]) -> Result<StepResult> {
+        match Box::pin(self.execute_step(step, earlier)).await {
+            Ok(step_result) => Ok(step_result),
+            Err(e) if matches!(
+                e.root_cause(),
+                AgentError::Cancelled(_) | AgentError::BudgetExceeded(_)
+            ) => Err(e),
+            Err(e) => Ok(StepResult::failure(StepKind::of(step), e.to_string())),
         }
     }
 
-    // Mock Tool that always succeeds
+    /// Runs a tool call step, applying its retry policy.
+    /// 
+    /// A call that fails with a retryable error (see
+    /// [`AgentError::is_retryable`]) is retried up to `max_retries` times,
+    /// waiting `delay_ms` before each retry. If it still fails, or fails
+    /// with an error a retry cannot fix, the policy's fallback step runs in
+    /// its place; without a fallback the last error is returned.
+    /// 
+    /// # Arguments
+    /// * `tool_call` - The tool call to execute
+    /// * `earlier` - Results of earlier plan steps, passed to the fallback
+    /// 
+    /// # Returns
+    /// A StepResult from the tool or the fallback step, or the last error
+    async fn run_tool_call(
+        &self,
+        tool_call: &planner::ToolCall,
+        earlier: &[StepResult],
+    ) -> Result<StepResult> {
+        let Some(policy) = &tool_call.retry else {
+            return self.handle_tool_call(tool_call).await;
+        };
+
+        let mut retries = 0;
+        loop {
+            let error = match self.handle_tool_call(tool_call).await {
+                Ok(step_result) => return Ok(step_result),
+                Err(e) => e,
+            };
+            if retries == policy.max_retries || !error.is_retryable() {
+                return match &policy.fallback {
+                    Some(fallback) => Box::pin(self.execute_step(fallback, earlier)).await,
+                    None => Err(error),
+                };
+            }
+            retries += 1;
+            if policy.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(policy.delay_ms)).await;
+            }
+        }
+    }
+
+    /// Handles an image generation step.
+    /// 
+    /// The output contains one URL per generated image; inline image bytes
+    /// are returned as `data:` URLs.
+    /// 
+    /// # Arguments
+    /// * `prompt` - Description of the image to generate
+    /// 
+    /// # Returns
+    /// A StepResult containing the image URLs or an error
+    #[tracing::instrument(name = "executor.image_generation", skip_all)]
+    async fn handle_image_generation(&self, prompt: &str) -> Result<StepResult> {
+        let provider = self.image_provider.as_ref().ok_or_else(|| {
+            agent_core::AgentError::Execution(
+                "Image generation requested but no image provider is configured".to_string(),
+            )
+        })?;
+
+        let images = provider.generate(prompt).await?;
+        let output = images
+            .iter()
+            .map(|image| image.to_url())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(StepResult::success(StepKind::ImageGeneration, output))
+    }
+
+    /// Handles a text generation step.
+    /// 
+    /// The step runs on the provider of its model profile, or on the default
+    /// LLM provider if it names none, and records the response's usage and
+    /// model.
+    /// 
+    /// # Arguments
+    /// * `prompt` - Instructions for the model
+    /// * `profile` - Name of the model profile to use, if any
+    /// 
+    /// # Returns
+    /// A StepResult containing the generated text or an error
+    #[tracing::instrument(
+        name = "executor.text_generation",
+        skip_all,
+        fields(profile, model = Empty, input_tokens = Empty, output_tokens = Empty)
+    )]
+    async fn handle_text_generation(
+        &self,
+        prompt: &str,
+        profile: Option<&str>,
+    ) -> Result<StepResult> {
+        let provider = match profile {
+            Some(name) => self.profiles.get(name).ok_or_else(|| {
+                AgentError::Execution(format!("Unknown model profile '{}'", name))
+            })?,
+            None => self.llm_provider.as_ref().ok_or_else(|| {
+                AgentError::Execution(
+                    "Text generation requested but no LLM provider is configured".to_string(),
+                )
+            })?,
+        };
+
+        self.check_budget()?;
+        let messages = [Message::user(prompt)];
+        self.trace(|| TraceEvent::LlmRequest {
+            step: trace::current_step(),
+            profile: profile.map(str::to_string),
+            messages: messages.to_vec(),
+        });
+        let response = provider.send_completion(&messages).await?;
+        self.trace(|| TraceEvent::LlmResponse {
+            step: trace::current_step(),
+            text: response.text.clone(),
+            usage: response.usage,
+            model: response.model.clone(),
+        });
+        record_completion(&Span::current(), response.model.as_deref(), response.usage);
+        self.spend_budget(&response)?;
+        Ok(StepResult::success(StepKind::TextGeneration, response.text.clone())
+            .with_completion(&response))
+    }
+
+    /// Adds a message to memory, recording it in the trace.
+    fn remember(&mut self, message: Message) {
+        self.trace(|| TraceEvent::MemoryAdded {
+            message: message.clone(),
+        });
+        self.memory.add_message(message);
+    }
+
+    /// Replaces the contents of memory, e.g. to rerun a recorded run from
+    /// the middle.
+    pub(crate) fn restore_memory(&mut self, messages: Vec<Message>) {
+        self.memory.clear();
+        for message in messages {
+            self.memory.add_message(message);
+        }
+    }
+
+    /// Registers a run with the shutdown coordinator, if any.
+    fn start_work(&self) -> Result<Option<WorkGuard>> {
+        self.shutdown.as_ref().map(Shutdown::start_work).transpose()
+    }
+
+    /// Starts the trace of a new run, if recording.
+    fn begin_trace(&self, plan: Option<&Plan>, query: Option<&str>) {
+        if let Some(recorder) = &self.recorder {
+            recorder.begin(
+                plan.cloned(),
+                query.map(str::to_string),
+                &self.context,
+                self.memory.get_recent(usize::MAX),
+            );
+        }
+    }
+
+    /// Adds an event to the current trace, if recording.
+    fn trace(&self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(event());
+        }
+    }
+
+    /// Ends the current trace with the outcome of the run, if recording.
+    fn finish_trace(&self, result: &Result<ExecutionResult>) {
+        self.trace(|| match result {
+            Ok(result) => TraceEvent::RunFinished {
+                success: result.success,
+                output: result.final_response.clone(),
+            },
+            Err(e) => TraceEvent::RunFinished {
+                success: false,
+                output: e.to_string(),
+            },
+        });
+    }
+
+    /// Starts counting the budget of a new run.
+    fn start_budget(&self) {
+        if let Some(tracker) = &self.budget {
+            tracker.reset();
+        }
+    }
+
+    /// Fails with [`AgentError::BudgetExceeded`] if the run has used up
+    /// its budget.
+    fn check_budget(&self) -> Result<()> {
+        match &self.budget {
+            Some(tracker) => tracker.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts the usage of a model response against the budget, failing
+    /// if the run has now used it up.
+    fn spend_budget(&self, response: &CompletionResponse) -> Result<()> {
+        match &self.budget {
+            Some(tracker) => tracker.spend(response.usage),
+            None => Ok(()),
+        }
+    }
+
+    /// Handles the execution of a tool call.
+    /// 
+    /// This method fills context placeholders into the parameters,
+    /// dispatches the call by name through the registry with the executor's
+    /// context, and wraps the result in a StepResult. If the tool is not
+    /// found or execution fails, an error is returned.
+    /// 
+    /// # Arguments
+    /// * `tool_call` - The tool call to execute
+    /// 
+    /// # Returns
+    /// A StepResult containing the tool output or an error
+    #[tracing::instrument(
+        name = "executor.tool",
+        skip_all,
+        fields(tool = %tool_call.tool_name, success = Empty)
+    )]
+    async fn handle_tool_call(&self, tool_call: &planner::ToolCall) -> Result<StepResult> {
+        let parameters = self.context.render_value(&tool_call.parameters)?;
+        self.trace(|| TraceEvent::ToolCall {
+            step: trace::current_step(),
+            tool_name: tool_call.tool_name.clone(),
+            parameters: parameters.clone(),
+        });
+
+        // Execute the tool with the provided parameters
+        let outcome = self.tools.execute(&tool_call.tool_name, parameters, &self.context).await;
+        Span::current().record("success", outcome.is_ok());
+        self.trace(|| TraceEvent::ToolResult {
+            step: trace::current_step(),
+            tool_name: tool_call.tool_name.clone(),
+            success: outcome.is_ok(),
+            output: match &outcome {
+                Ok(result) => result.to_string(),
+                Err(e) => e.to_string(),
+            },
+        });
+        match outcome {
+            Ok(result) => {
+                // Convert the JSON result to a string for the step result
+                let output = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| result.to_string());
+                
+                Ok(StepResult::success(
+                    StepKind::tool_call(&tool_call.tool_name),
+                    output,
+                ))
+            }
+            Err(
+                e @ (agent_core::AgentError::ToolExecution { .. }
+                | agent_core::AgentError::ToolFailed { .. }),
+            ) => Err(e),
+            // Keep the underlying error so callers can tell whether a retry may help
+            Err(e) => Err(agent_core::AgentError::ToolFailed {
+                tool: tool_call.tool_name.clone(),
+                source: Box::new(e),
+            }),
+        }
+    }
+}
+
+/// Records the model and token usage of a model response on `span`.
+fn record_completion(span: &Span, model: Option<&str>, usage: Option<TokenUsage>) {
+    if let Some(model) = model {
+        span.record("model", model);
+    }
+    if let Some(usage) = usage {
+        span.record("input_tokens", usage.input_tokens);
+        span.record("output_tokens", usage.output_tokens);
+    }
+}
+
+/// Records the outcome of a plan step on the current span.
+fn record_step(outcome: &Result<StepResult>) {
+    let span = Span::current();
+    match outcome {
+        Ok(step_result) => {
+            span.record("success", step_result.success);
+            record_completion(&span, step_result.model.as_deref(), step_result.usage);
+        }
+        Err(_) => {
+            span.record("success", false);
+        }
+    }
+}
+
+/// Records the outcome and total token usage of a run on the current span.
+fn record_run(result: &Result<ExecutionResult>) {
+    let span = Span::current();
+    match result {
+        Ok(result) => {
+            span.record("success", result.success);
+            record_completion(&span, None, Some(result.total_usage()));
+        }
+        Err(_) => {
+            span.record("success", false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RunDebugger;
+    use agent_core::Message;
+    use async_trait::async_trait;
+    use llm::{CompletionResponse, FinishReason, StructuredOutput, TokenUsage};
+    use planner::{Plan, RetryPolicy, Step, ToolCall};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    // Mock MemoryStore for testing
+    #[derive(Clone)]
+    struct MockMemoryStore {
+        messages: Arc<Mutex<Vec<Message>>>,
+    }
+
+    impl MockMemoryStore {
+        fn new() -> Self {
+            Self {
+                messages: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn get_messages(&self) -> Vec<Message> {
+            self.messages.lock().unwrap().clone()
+        }
+    }
+
+    impl MemoryStore for MockMemoryStore {
+        fn add_message(&mut self, message: Message) {
+            self.messages.lock().unwrap().push(message);
+        }
+
+        fn get_recent(&self, limit: usize) -> Vec<Message> {
+            let messages = self.messages.lock().unwrap();
+            messages.iter().rev().take(limit).rev().cloned().collect()
+        }
+
+        fn get_within_budget(&self, _token_budget: usize) -> Vec<Message> {
+            self.messages.lock().unwrap().clone()
+        }
+
+        fn clear(&mut self) {
+            self.messages.lock().unwrap().clear();
+        }
+    }
+
+    // Mock Tool that always succeeds
     struct MockSuccessTool {
         name: String,
         result: Value,
@@ -278,317 +1519,1527 @@ mod tests {
             })
         }
 
-        async fn execute(&self, _params: Value) -> Result<Value> {
-            Err(agent_core::AgentError::ToolExecution {
-                tool_name: self.name.clone(),
-                reason: "Mock tool failure".to_string(),
-            })
+        async fn execute(&self, _params: Value) -> Result<Value> {
+            Err(agent_core::AgentError::ToolExecution {
+                tool_name: self.name.clone(),
+                reason: "Mock tool failure".to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_step_reasoning() {
+        let registry = ToolRegistry::new();
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let step = Step::Reasoning {
+            text: "This is a reasoning step".to_string(),
+        };
+
+        let result = executor.execute_step(&step, &[]).await.unwrap();
+        assert_eq!(result.step_type, StepKind::Reasoning);
+        assert_eq!(result.output, "This is a reasoning step");
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_step_response() {
+        let registry = ToolRegistry::new();
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let step = Step::Response {
+            text: "This is a response".to_string(),
+        };
+
+        let result = executor.execute_step(&step, &[]).await.unwrap();
+        assert_eq!(result.step_type, StepKind::Response);
+        assert_eq!(result.output, "This is a response");
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_step_tool_call_success() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "test_tool",
+            json!({"result": "success"}),
+        )));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let tool_call = ToolCall::new("test_tool".to_string(), json!({}));
+        let step = Step::ToolCall(tool_call);
+
+        let result = executor.execute_step(&step, &[]).await.unwrap();
+        assert_eq!(result.step_type, StepKind::tool_call("test_tool"));
+        assert!(result.output.contains("success"));
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_handle_tool_call_valid_tool() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "calculator",
+            json!({"result": 42}),
+        )));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let tool_call = ToolCall::new("calculator".to_string(), json!({"a": 2, "b": 3}));
+
+        let result = executor.handle_tool_call(&tool_call).await.unwrap();
+        assert_eq!(result.step_type, StepKind::tool_call("calculator"));
+        assert!(result.output.contains("42"));
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_handle_tool_call_invalid_tool() {
+        let registry = ToolRegistry::new();
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let tool_call = ToolCall::new("nonexistent_tool".to_string(), json!({}));
+
+        let result = executor.handle_tool_call(&tool_call).await;
+        assert!(result.is_err());
+
+        match result {
+            Err(agent_core::AgentError::ToolExecution { tool_name, reason }) => {
+                assert_eq!(tool_name, "nonexistent_tool");
+                assert!(reason.contains("not found"));
+            }
+            _ => panic!("Expected ToolExecution error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_tool_call_tool_execution_failure() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockFailureTool::new("failing_tool")));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let tool_call = ToolCall::new("failing_tool".to_string(), json!({}));
+
+        let result = executor.handle_tool_call(&tool_call).await;
+        assert!(result.is_err());
+
+        match result {
+            Err(agent_core::AgentError::ToolExecution { tool_name, reason }) => {
+                assert_eq!(tool_name, "failing_tool");
+                assert!(reason.contains("Mock tool failure"));
+            }
+            _ => panic!("Expected ToolExecution error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_single_step() {
+        let registry = ToolRegistry::new();
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(registry, memory);
+
+        let plan = Plan::new(
+            vec![Step::Response {
+                text: "Hello, world!".to_string(),
+            }],
+            "Simple single-step plan".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_response, "Hello, world!");
+        assert_eq!(result.step_results.len(), 1);
+        assert!(result.step_results[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_multi_step() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "tool1",
+            json!({"data": "result1"}),
+        )));
+        registry.register(Box::new(MockSuccessTool::new(
+            "tool2",
+            json!({"data": "result2"}),
+        )));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(registry, memory);
+
+        let plan = Plan::new(
+            vec![
+                Step::Reasoning {
+                    text: "First, I'll use tool1".to_string(),
+                },
+                Step::ToolCall(ToolCall::new("tool1".to_string(), json!({}))),
+                Step::Reasoning {
+                    text: "Now I'll use tool2".to_string(),
+                },
+                Step::ToolCall(ToolCall::new("tool2".to_string(), json!({}))),
+                Step::Response {
+                    text: "All done!".to_string(),
+                },
+            ],
+            "Multi-step plan".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_response, "All done!");
+        assert_eq!(result.step_results.len(), 5);
+        assert!(result.step_results.iter().all(|r| r.success));
+    }
+
+    /// Tool that records how many of its calls overlap, finishing calls
+    /// with a lower `n` parameter later
+    struct OverlapProbe {
+        name: String,
+        active: Arc<std::sync::atomic::AtomicUsize>,
+        max_active: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl tools::Tool for OverlapProbe {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn description(&self) -> &str {
+            "Mock tool that measures concurrency"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            use std::sync::atomic::Ordering;
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            let n = params["n"].as_u64().unwrap_or(0);
+            for _ in 0..(10 - n) {
+                tokio::task::yield_now().await;
+            }
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(json!(n))
+        }
+    }
+
+    async fn run_probe_plan(max_concurrency: usize) -> (ExecutionResult, usize) {
+        let max_active = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(OverlapProbe {
+            name: "probe".to_string(),
+            active: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            max_active: max_active.clone(),
+        }));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()))
+            .with_max_concurrency(max_concurrency);
+
+        let steps = (0..4)
+            .map(|n| Step::ToolCall(ToolCall::new("probe".to_string(), json!({"n": n}))))
+            .collect();
+        let result = executor
+            .execute_plan(Plan::new(vec![Step::Parallel { steps }], "Probe".to_string()))
+            .await
+            .unwrap();
+        (result, max_active.load(std::sync::atomic::Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_runs_parallel_steps_concurrently() {
+        let (result, max_active) = run_probe_plan(3).await;
+        assert!(result.success);
+        assert_eq!(max_active, 3);
+
+        // Results keep plan order although later steps finished first
+        let group = &result.step_results[0];
+        assert_eq!(group.step_type, StepKind::Parallel);
+        let outputs: Vec<_> = group.substeps.iter().map(|r| r.output.as_str()).collect();
+        assert_eq!(outputs, vec!["0", "1", "2", "3"]);
+        assert!(group.substeps.iter().all(|r| r.duration_ms.is_some()));
+
+        let (_, max_active) = run_probe_plan(1).await;
+        assert_eq!(max_active, 1);
+    }
+
+    /// Tool backed by one shared value: `write` stores `value` after a
+    /// delay and `read` returns what is stored
+    struct SlowStore {
+        name: &'static str,
+        value: Arc<Mutex<Option<String>>>,
+    }
+
+    #[async_trait]
+    impl tools::Tool for SlowStore {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Mock tool with a side effect"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            if self.name == "write" {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                *self.value.lock().unwrap() = params["value"].as_str().map(str::to_string);
+            }
+            Ok(json!(self.value.lock().unwrap().clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ungrouped_steps_wait_for_earlier_side_effects() {
+        let value = Arc::new(Mutex::new(None));
+        let mut registry = ToolRegistry::new();
+        for name in ["write", "read"] {
+            registry.register(Box::new(SlowStore {
+                name,
+                value: value.clone(),
+            }));
+        }
+        let mut executor =
+            Executor::new(registry, Box::new(MockMemoryStore::new())).with_max_concurrency(4);
+
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new("write".to_string(), json!({"value": "draft"}))),
+                Step::ToolCall(ToolCall::new("read".to_string(), json!({}))),
+            ],
+            "Write, then read back".to_string(),
+        );
+        let result = executor.execute_plan(plan).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.step_results[1].output, "\"draft\"");
+    }
+
+    /// Tool that never finishes within a test
+    struct HangingTool;
+
+    #[async_trait]
+    impl tools::Tool for HangingTool {
+        fn name(&self) -> &str {
+            "hang"
+        }
+
+        fn description(&self) -> &str {
+            "Mock tool that hangs"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, _params: Value) -> Result<Value> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(json!("too late"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_plan_returns_partial_results() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new("ok", json!("done"))));
+        registry.register(Box::new(HangingTool));
+        let cancel = CancellationToken::new();
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()))
+            .with_cancellation(cancel.clone());
+
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.cancel();
+        });
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new("ok".to_string(), json!({}))),
+                Step::ToolCall(ToolCall::new("hang".to_string(), json!({}))),
+                Step::Response {
+                    text: "Never reached".to_string(),
+                },
+            ],
+            "Cancelled plan".to_string(),
+        );
+        let result = executor.execute_plan(plan).await.unwrap();
+
+        assert!(!result.success);
+        let step_types: Vec<_> = result.step_results.iter().map(|r| r.step_type.clone()).collect();
+        assert_eq!(step_types, vec![StepKind::tool_call("ok"), StepKind::Cancelled]);
+        assert!(result.step_results[1].output.contains("Cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_runs_past_the_deadline_and_refuses_new_ones() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new("ok", json!("done"))));
+        registry.register(Box::new(HangingTool));
+        let shutdown = Shutdown::new();
+        let mut executor =
+            Executor::new(registry, Box::new(MockMemoryStore::new())).with_shutdown(&shutdown);
+
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new("ok".to_string(), json!({}))),
+                Step::ToolCall(ToolCall::new("hang".to_string(), json!({}))),
+            ],
+            "Interrupted plan".to_string(),
+        );
+        let (result, report) = tokio::join!(
+            executor.execute_plan(plan.clone()),
+            shutdown.shutdown(Duration::from_millis(20))
+        );
+
+        // The finished step is kept so the run can be resumed
+        let result = result.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.step_results[0].step_type, StepKind::tool_call("ok"));
+        assert_eq!(result.step_results[1].step_type, StepKind::Cancelled);
+        assert!(!report.drained);
+        assert_eq!(report.cancelled, 1);
+        assert_eq!(report.abandoned, 0);
+
+        let refused = executor.execute_plan(plan).await;
+        assert!(matches!(refused, Err(AgentError::Cancelled(_))));
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_plan_stops_at_failure() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new("ok", json!("done"))));
+        registry.register(Box::new(MockFailureTool::new("broken")));
+        let memory = MockMemoryStore::new();
+        let mut executor =
+            Executor::new(registry, Box::new(memory.clone())).with_max_concurrency(4);
+
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new("ok".to_string(), json!({}))),
+                Step::Parallel {
+                    steps: vec![
+                        Step::ToolCall(ToolCall::new("broken".to_string(), json!({}))),
+                        Step::ToolCall(ToolCall::new("ok".to_string(), json!({}))),
+                    ],
+                },
+                Step::ToolCall(ToolCall::new("ok".to_string(), json!({}))),
+            ],
+            "Fails halfway".to_string(),
+        );
+        let result = executor.execute_plan(plan).await.unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.step_results.len(), 2);
+        assert_eq!(result.step_results[1].step_type, StepKind::Error);
+        assert_eq!(memory.get_messages().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_with_failure() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "good_tool",
+            json!({"result": "ok"}),
+        )));
+        registry.register(Box::new(MockFailureTool::new("bad_tool")));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(registry, memory);
+
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new("good_tool".to_string(), json!({}))),
+                Step::ToolCall(ToolCall::new("bad_tool".to_string(), json!({}))),
+                Step::Response {
+                    text: "This should not execute".to_string(),
+                },
+            ],
+            "Plan with failure".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(!result.success);
+        // Should have 2 step results: one success, one failure
+        assert_eq!(result.step_results.len(), 2);
+        assert!(result.step_results[0].success);
+        assert!(!result.step_results[1].success);
+    }
+
+    // Mock Tool that fails a number of times before succeeding
+    struct FlakyTool {
+        failures: usize,
+        error: fn() -> AgentError,
+        calls: Arc<Mutex<usize>>,
+    }
+
+    #[async_trait]
+    impl tools::Tool for FlakyTool {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn description(&self) -> &str {
+            "Fails before succeeding"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, _params: Value) -> Result<Value> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            if *calls <= self.failures {
+                return Err((self.error)());
+            }
+            Ok(json!({"attempt": *calls}))
+        }
+    }
+
+    async fn run_flaky_step(failures: usize, retry: RetryPolicy) -> (ExecutionResult, usize) {
+        let timeout = || AgentError::Timeout("temporarily unavailable".to_string());
+        run_failing_step(failures, timeout, retry).await
+    }
+
+    async fn run_failing_step(
+        failures: usize,
+        error: fn() -> AgentError,
+        retry: RetryPolicy,
+    ) -> (ExecutionResult, usize) {
+        let calls = Arc::new(Mutex::new(0));
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(FlakyTool {
+            failures,
+            error,
+            calls: calls.clone(),
+        }));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
+
+        let plan = Plan::new(
+            vec![Step::ToolCall(
+                ToolCall::new("flaky".to_string(), json!({})).with_retry(retry),
+            )],
+            "Flaky plan".to_string(),
+        );
+        let result = executor.execute_plan(plan).await.unwrap();
+        let calls = *calls.lock().unwrap();
+        (result, calls)
+    }
+
+    #[tokio::test]
+    async fn test_failed_tool_call_is_retried() {
+        let (result, calls) = run_flaky_step(2, RetryPolicy::new(2)).await;
+        assert!(result.success);
+        assert_eq!(calls, 3);
+        assert_eq!(result.step_results.len(), 1);
+        assert!(result.step_results[0].output.contains("\"attempt\": 3"));
+
+        let (result, calls) = run_flaky_step(3, RetryPolicy::new(2)).await;
+        assert!(!result.success);
+        assert_eq!(calls, 3);
+        assert!(result.step_results[0].output.contains("temporarily unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_runs_after_retries_are_exhausted() {
+        let fallback = Step::Response {
+            text: "The service is unavailable, try again later".to_string(),
+        };
+        let (result, calls) = run_flaky_step(5, RetryPolicy::new(1).with_fallback(fallback)).await;
+        assert!(result.success);
+        assert_eq!(calls, 2);
+        assert_eq!(result.final_response, "The service is unavailable, try again later");
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_goes_straight_to_fallback() {
+        let fallback = Step::Response {
+            text: "The tool rejected the request".to_string(),
+        };
+        let invalid = || AgentError::Execution("invalid parameters".to_string());
+        let retry = RetryPolicy::new(3).with_fallback(fallback);
+        let (result, calls) = run_failing_step(5, invalid, retry).await;
+        assert!(result.success);
+        assert_eq!(calls, 1);
+        assert_eq!(result.final_response, "The tool rejected the request");
+
+        let (result, calls) = run_failing_step(5, invalid, RetryPolicy::new(3)).await;
+        assert!(!result.success);
+        assert_eq!(calls, 1);
+        assert!(result.step_results[0].output.contains("invalid parameters"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_stores_results_in_memory() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "test_tool",
+            json!({"result": "data"}),
+        )));
+
+        let memory_store = MockMemoryStore::new();
+        let memory_clone = memory_store.clone();
+        let mut executor = Executor::new(registry, Box::new(memory_store));
+
+        let plan = Plan::new(
+            vec![
+                Step::Reasoning {
+                    text: "Thinking...".to_string(),
+                },
+                Step::ToolCall(ToolCall::new("test_tool".to_string(), json!({}))),
+                Step::Response {
+                    text: "Done!".to_string(),
+                },
+            ],
+            "Plan to test memory".to_string(),
+        );
+
+        executor.execute_plan(plan).await.unwrap();
+
+        // Check that results were stored in memory
+        let messages = memory_clone.get_messages();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].content.contains("Thinking"));
+        assert!(messages[1].content.contains("data"));
+        assert!(messages[2].content.contains("Done"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_empty_plan() {
+        let registry = ToolRegistry::new();
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(registry, memory);
+
+        let plan = Plan::new(vec![], "Empty plan".to_string());
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_response, "");
+        assert_eq!(result.step_results.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_no_explicit_response() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "tool1",
+            json!({"result": "output1"}),
+        )));
+        registry.register(Box::new(MockSuccessTool::new(
+            "tool2",
+            json!({"result": "output2"}),
+        )));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(registry, memory);
+
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new("tool1".to_string(), json!({}))),
+                Step::ToolCall(ToolCall::new("tool2".to_string(), json!({}))),
+            ],
+            "Plan without explicit response".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        // Should build response from step outputs
+        assert!(result.final_response.contains("output1"));
+        assert!(result.final_response.contains("output2"));
+        assert_eq!(result.step_results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_tools() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new(
+            "tool1",
+            json!({"result": "ok"}),
+        )));
+        registry.register(Box::new(MockSuccessTool::new(
+            "tool2",
+            json!({"result": "ok"}),
+        )));
+
+        let memory = Box::new(MockMemoryStore::new());
+        let executor = Executor::new(registry, memory);
+
+        let tools = executor.list_tools();
+        assert_eq!(tools.len(), 2);
+
+        let tool_names: Vec<String> = tools.iter().map(|t| t.name.clone()).collect();
+        assert!(tool_names.contains(&"tool1".to_string()));
+        assert!(tool_names.contains(&"tool2".to_string()));
+    }
+
+    struct MockImageProvider;
+
+    #[async_trait]
+    impl ImageProvider for MockImageProvider {
+        async fn generate(&self, prompt: &str) -> Result<Vec<llm::GeneratedImage>> {
+            Ok(vec![llm::GeneratedImage::Url(format!(
+                "https://images.example.com/{}.png",
+                prompt.replace(' ', "-")
+            ))])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_image_generation_step() {
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(ToolRegistry::new(), memory)
+            .with_image_provider(Box::new(MockImageProvider));
+
+        let plan = Plan::new(
+            vec![Step::ImageGeneration {
+                prompt: "red fox".to_string(),
+            }],
+            "Draw a fox".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.step_results[0].step_type, StepKind::ImageGeneration);
+        assert_eq!(result.final_response, "https://images.example.com/red-fox.png");
+    }
+
+    #[tokio::test]
+    async fn test_image_generation_without_provider() {
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(ToolRegistry::new(), memory);
+
+        let plan = Plan::new(
+            vec![Step::ImageGeneration {
+                prompt: "red fox".to_string(),
+            }],
+            "Draw a fox".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(!result.success);
+        assert!(result.step_results[0].output.contains("no image provider"));
+    }
+
+    /// Provider that answers with its name and the prompt
+    struct NamedProvider(&'static str);
+
+    #[async_trait]
+    impl LLMProvider for NamedProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(format!("{}: {}", self.0, messages[0].content))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_text_generation_steps_use_their_profiles() {
+        let memory = Box::new(MockMemoryStore::new());
+        let mut executor = Executor::new(ToolRegistry::new(), memory)
+            .with_llm_provider(Box::new(NamedProvider("default")))
+            .with_profile("summarizer", Box::new(NamedProvider("summarizer")))
+            .with_profile("coder", Box::new(NamedProvider("coder")));
+        assert_eq!(executor.profile_names(), vec!["coder", "summarizer"]);
+
+        let step = |prompt: &str, profile: Option<&str>| Step::TextGeneration {
+            prompt: prompt.to_string(),
+            profile: profile.map(str::to_string),
+        };
+        let plan = Plan::new(
+            vec![
+                step("Summarize the docs", Some("summarizer")),
+                step("Write the parser", Some("coder")),
+                step("Say hi", None),
+                step("Translate", Some("translator")),
+            ],
+            "Mixed models".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        let outputs: Vec<_> = result.step_results.iter().map(|r| r.output.as_str()).collect();
+        assert_eq!(
+            &outputs[..3],
+            ["summarizer: Summarize the docs", "coder: Write the parser", "default: Say hi"]
+        );
+        assert!(!result.success);
+        assert!(outputs[3].contains("Unknown model profile 'translator'"));
+    }
+
+    // Mock Tool that echoes its parameters and the context locale
+    struct ContextEchoTool;
+
+    #[async_trait]
+    impl tools::Tool for ContextEchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echoes parameters and locale"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            Ok(params)
+        }
+
+        async fn execute_with_context(
+            &self,
+            params: Value,
+            context: &ExecutionContext,
+        ) -> Result<Value> {
+            Ok(json!({"params": params, "locale": context.locale}))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_injected_into_steps_and_tools() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(ContextEchoTool));
+        let context = ExecutionContext::new()
+            .with_user(agent_core::UserProfile::new("u-1").with_name("Ada"))
+            .with_locale("de-DE")
+            .with_value("limit", 3)
+            .unwrap();
+        let mut executor =
+            Executor::new(registry, Box::new(MockMemoryStore::new())).with_context(context);
+
+        let plan = Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new(
+                    "echo".to_string(),
+                    json!({"user": "{{context.user.name}}", "limit": "{{context.limit}}"}),
+                )),
+                Step::Response {
+                    text: "Done, {{context.user.name}}".to_string(),
+                },
+            ],
+            "Greet".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        let output: Value = serde_json::from_str(&result.step_results[0].output).unwrap();
+        assert_eq!(
+            output,
+            json!({"params": {"user": "Ada", "limit": 3}, "locale": "de-DE"})
+        );
+        assert_eq!(result.final_response, "Done, Ada");
+    }
+
+    #[tokio::test]
+    async fn test_missing_context_value_fails_step() {
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(MockMemoryStore::new()));
+        let plan = Plan::new(
+            vec![Step::Response {
+                text: "Hello {{context.user.name}}".to_string(),
+            }],
+            "Greet".to_string(),
+        );
+
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(!result.success);
+        assert!(result.step_results[0].output.contains("user.name"));
+    }
+
+    #[tokio::test]
+    async fn test_request_context_stamped_on_results() {
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(MockMemoryStore::new()));
+        let plan = Plan::new(
+            vec![
+                Step::Response {
+                    text: "Hi".to_string(),
+                },
+                Step::Response {
+                    text: "{{context.missing}}".to_string(),
+                },
+            ],
+            "Greet".to_string(),
+        );
+
+        let request = RequestContext::with_trace_id("trace-9").with_session_id("s-1");
+        let result = request.scope(executor.execute_plan(plan)).await.unwrap();
+        assert_eq!(result.trace_id.as_deref(), Some("trace-9"));
+        let failure = &result.step_results[1].output;
+        assert!(failure.contains("step=2"));
+        assert!(failure.contains("trace=trace-9"));
+
+        let untraced = executor
+            .execute_plan(Plan::new(Vec::new(), "Nothing".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(untraced.trace_id, None);
+    }
+
+    /// Streams the steps of `steps` one by one, then the complete plan
+    fn plan_stream(steps: Vec<Step>) -> PlanStream {
+        let plan = Plan::new(steps.clone(), "Streamed".to_string());
+        let events: Vec<Result<PlanEvent>> = steps
+            .into_iter()
+            .map(PlanEvent::Step)
+            .chain([PlanEvent::Complete(plan)])
+            .map(Ok)
+            .collect();
+        Box::pin(futures::stream::iter(events))
+    }
+
+    fn reject_forbidden(step: &Step) -> Result<()> {
+        match step {
+            Step::ToolCall(call) if call.tool_name == "forbidden" => Err(
+                AgentError::GuardrailViolation("forbidden tool".to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_stream_validates_before_running() {
+        let memory = MockMemoryStore::new();
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(memory.clone()));
+        let steps = vec![
+            Step::Reasoning {
+                text: "Thinking...".to_string(),
+            },
+            Step::ToolCall(ToolCall::new("forbidden".to_string(), json!({}))),
+        ];
+
+        let result = executor
+            .execute_plan_stream(
+                plan_stream(steps),
+                StreamExecution::ValidateFirst,
+                reject_forbidden,
+            )
+            .await;
+        assert!(matches!(result, Err(AgentError::GuardrailViolation(_))));
+        assert!(memory.get_messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_stream_eager_runs_steps_on_arrival() {
+        let memory = MockMemoryStore::new();
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(memory.clone()));
+        let steps = vec![
+            Step::Reasoning {
+                text: "Thinking...".to_string(),
+            },
+            Step::ToolCall(ToolCall::new("forbidden".to_string(), json!({}))),
+        ];
+
+        // The first step ran before the second one was rejected
+        let result = executor
+            .execute_plan_stream(plan_stream(steps), StreamExecution::Eager, reject_forbidden)
+            .await;
+        assert!(matches!(result, Err(AgentError::GuardrailViolation(_))));
+        assert_eq!(memory.get_messages().len(), 1);
+
+        let steps = vec![
+            Step::Reasoning {
+                text: "Thinking...".to_string(),
+            },
+            Step::Response {
+                text: "Done!".to_string(),
+            },
+        ];
+        let result = executor
+            .execute_plan_stream(plan_stream(steps), StreamExecution::Eager, reject_forbidden)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_response, "Done!");
+        assert_eq!(result.step_results.len(), 2);
+        assert_eq!(memory.get_messages().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_stream_requires_complete_plan() {
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(MockMemoryStore::new()));
+        let truncated: PlanStream = Box::pin(futures::stream::iter(vec![Ok(PlanEvent::Step(
+            Step::Response {
+                text: "Hi".to_string(),
+            },
+        ))]));
+
+        let result = executor
+            .execute_plan_stream(truncated, StreamExecution::ValidateFirst, |_| Ok(()))
+            .await;
+        assert!(matches!(result, Err(AgentError::Planning(_))));
+    }
+
+    /// Provider that replays canned turns and counts requests, reporting
+    /// 100 input and 10 output tokens per turn
+    struct ScriptedProvider {
+        turns: Mutex<Vec<&'static str>>,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(turns: Vec<&'static str>) -> Self {
+            Self {
+                turns: Mutex::new(turns),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.turns.lock().unwrap().remove(0).to_string())
+        }
+
+        async fn send_structured_completion(
+            &self,
+            messages: &[Message],
+            output: &StructuredOutput,
+        ) -> Result<(Value, CompletionResponse)> {
+            let value = self.send_structured(messages, output).await?;
+            let usage = TokenUsage {
+                input_tokens: 100,
+                output_tokens: 10,
+            };
+            let response = CompletionResponse::new(value.to_string(), FinishReason::Stop)
+                .with_usage(usage)
+                .with_model("scripted");
+            Ok((value, response))
         }
     }
 
+    const CALL_LOOKUP: &str =
+        r#"{"tool_calls": [{"tool_name": "lookup", "parameters": {"key": "x"}}]}"#;
+
     #[tokio::test]
-    async fn test_execute_step_reasoning() {
-        let registry = ToolRegistry::new();
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+    async fn test_tool_loop_runs_tools_until_final_answer() {
+        let memory = MockMemoryStore::new();
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockSuccessTool::new("lookup", json!({"value": 42}))));
+        let mut executor = Executor::new(registry, Box::new(memory.clone()));
 
-        let step = Step::Reasoning {
-            text: "This is a reasoning step".to_string(),
-        };
+        let provider = ScriptedProvider::new(vec![CALL_LOOKUP, r#"{"final_answer": "x is 42"}"#]);
+        let result = executor
+            .run_tool_loop(&provider, "What is x?", &ToolLoopConfig::default())
+            .await
+            .unwrap();
 
-        let result = executor.execute_step(&step).await.unwrap();
-        assert_eq!(result.step_type, "reasoning");
-        assert_eq!(result.output, "This is a reasoning step");
         assert!(result.success);
+        assert_eq!(result.final_response, "x is 42");
+        let step_types: Vec<_> = result.step_results.iter().map(|r| r.step_type.clone()).collect();
+        assert_eq!(
+            step_types,
+            vec![StepKind::Model, StepKind::tool_call("lookup"), StepKind::Model]
+        );
+        assert_eq!(result.step_results[0].model.as_deref(), Some("scripted"));
+        assert_eq!(
+            result.total_usage(),
+            TokenUsage {
+                input_tokens: 200,
+                output_tokens: 20,
+            }
+        );
+
+        // The second turn sees the tool result
+        let requests = provider.requests.lock().unwrap();
+        let last = requests[1].last().unwrap();
+        assert!(last.content.contains("[lookup]"));
+        assert!(last.content.contains("42"));
+
+        let stored = memory.get_messages();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].content, "x is 42");
     }
 
     #[tokio::test]
-    async fn test_execute_step_response() {
-        let registry = ToolRegistry::new();
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+    async fn test_tool_loop_reports_tool_errors_to_model() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockFailureTool::new("lookup")));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
 
-        let step = Step::Response {
-            text: "This is a response".to_string(),
-        };
+        let provider =
+            ScriptedProvider::new(vec![CALL_LOOKUP, r#"{"final_answer": "Lookup failed"}"#]);
+        let result = executor
+            .run_tool_loop(&provider, "What is x?", &ToolLoopConfig::default())
+            .await
+            .unwrap();
 
-        let result = executor.execute_step(&step).await.unwrap();
-        assert_eq!(result.step_type, "response");
-        assert_eq!(result.output, "This is a response");
         assert!(result.success);
+        assert!(!result.step_results[1].success);
+        let requests = provider.requests.lock().unwrap();
+        assert!(requests[1].last().unwrap().content.contains("(error)"));
     }
 
     #[tokio::test]
-    async fn test_execute_step_tool_call_success() {
+    async fn test_tool_loop_stops_at_max_iterations() {
         let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockSuccessTool::new(
-            "test_tool",
-            json!({"result": "success"}),
-        )));
-
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+        registry.register(Box::new(MockSuccessTool::new("lookup", json!(1))));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
 
-        let tool_call = ToolCall::new("test_tool".to_string(), json!({}));
-        let step = Step::ToolCall(tool_call);
+        let provider = ScriptedProvider::new(vec![CALL_LOOKUP, CALL_LOOKUP]);
+        let config = ToolLoopConfig::default().with_max_iterations(2);
+        let result = executor
+            .run_tool_loop(&provider, "Loop forever", &config)
+            .await
+            .unwrap();
 
-        let result = executor.execute_step(&step).await.unwrap();
-        assert_eq!(result.step_type, "tool_call:test_tool");
-        assert!(result.output.contains("success"));
-        assert!(result.success);
+        assert!(!result.success);
+        assert!(result.final_response.contains("max iterations (2)"));
+        assert_eq!(provider.requests.lock().unwrap().len(), 2);
     }
 
     #[tokio::test]
-    async fn test_handle_tool_call_valid_tool() {
+    async fn test_tool_loop_enforces_tool_call_budget() {
         let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockSuccessTool::new(
-            "calculator",
-            json!({"result": 42}),
-        )));
+        registry.register(Box::new(MockSuccessTool::new("lookup", json!(1))));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
 
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+        let provider = ScriptedProvider::new(vec![CALL_LOOKUP, CALL_LOOKUP]);
+        let config = ToolLoopConfig::default().with_max_tool_calls(1);
+        let result = executor
+            .run_tool_loop(&provider, "Look up twice", &config)
+            .await
+            .unwrap();
 
-        let tool_call = ToolCall::new("calculator".to_string(), json!({"a": 2, "b": 3}));
+        assert!(!result.success);
+        assert!(result.final_response.contains("max tool calls (1)"));
+        assert_eq!(result.step_results.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_compacts_repeated_tool_output() {
+        let mut registry = ToolRegistry::new();
+        let article = "The quarterly report shows revenue grew twelve percent, driven by strong subscription renewals in Europe and Asia.";
+        registry.register(Box::new(MockSuccessTool::new("lookup", json!(article))));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
+
+        let provider = ScriptedProvider::new(vec![
+            CALL_LOOKUP,
+            CALL_LOOKUP,
+            r#"{"final_answer": "Revenue grew 12%"}"#,
+        ]);
+        let config = ToolLoopConfig::default().with_compaction(memory::Compactor::new());
+        let result = executor
+            .run_tool_loop(&provider, "How did revenue do?", &config)
+            .await
+            .unwrap();
 
-        let result = executor.handle_tool_call(&tool_call).await.unwrap();
-        assert_eq!(result.step_type, "tool_call:calculator");
-        assert!(result.output.contains("42"));
         assert!(result.success);
+        let requests = provider.requests.lock().unwrap();
+        let last = &requests[2];
+        let results: Vec<_> = last
+            .iter()
+            .filter(|m| m.content.starts_with("Tool results:") || m.content == memory::OMITTED_PLACEHOLDER)
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].content.contains("twelve percent"));
+        assert_eq!(results[1].content, memory::OMITTED_PLACEHOLDER);
     }
 
     #[tokio::test]
-    async fn test_handle_tool_call_invalid_tool() {
-        let registry = ToolRegistry::new();
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
-
-        let tool_call = ToolCall::new("nonexistent_tool".to_string(), json!({}));
+    async fn test_tool_loop_enforces_token_budget() {
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(MockMemoryStore::new()));
 
-        let result = executor.handle_tool_call(&tool_call).await;
-        assert!(result.is_err());
+        let provider = ScriptedProvider::new(vec![]);
+        let config = ToolLoopConfig::default().with_token_budget(5);
+        let result = executor
+            .run_tool_loop(&provider, "Hello", &config)
+            .await
+            .unwrap();
 
-        match result {
-            Err(agent_core::AgentError::ToolExecution { tool_name, reason }) => {
-                assert_eq!(tool_name, "nonexistent_tool");
-                assert!(reason.contains("not found"));
-            }
-            _ => panic!("Expected ToolExecution error"),
-        }
+        assert!(!result.success);
+        assert!(result.final_response.contains("token budget"));
+        assert!(provider.requests.lock().unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_handle_tool_call_tool_execution_failure() {
+    async fn test_tool_loop_stops_when_budget_is_used_up() {
         let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockFailureTool::new("failing_tool")));
-
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+        registry.register(Box::new(MockSuccessTool::new("lookup", json!(1))));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()))
+            .with_budget(Budget::new().with_max_tokens(200));
+
+        let provider = ScriptedProvider::new(vec![CALL_LOOKUP, CALL_LOOKUP, CALL_LOOKUP]);
+        let result = executor
+            .run_tool_loop(&provider, "Look up forever", &ToolLoopConfig::default())
+            .await;
+
+        assert!(matches!(result, Err(AgentError::BudgetExceeded(_))));
+        assert_eq!(provider.requests.lock().unwrap().len(), 2);
+        assert_eq!(executor.budget_spent().total(), 220);
+    }
 
-        let tool_call = ToolCall::new("failing_tool".to_string(), json!({}));
+    /// Provider that reports 100 input and 10 output tokens per completion
+    struct MeteredProvider {
+        calls: AtomicUsize,
+    }
 
-        let result = executor.handle_tool_call(&tool_call).await;
-        assert!(result.is_err());
+    #[async_trait]
+    impl LLMProvider for MeteredProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(self.send_completion(messages).await?.text)
+        }
 
-        match result {
-            Err(agent_core::AgentError::ToolExecution { tool_name, reason }) => {
-                assert_eq!(tool_name, "failing_tool");
-                assert!(reason.contains("Mock tool failure"));
-            }
-            _ => panic!("Expected ToolExecution error"),
+        async fn send_completion(&self, _messages: &[Message]) -> Result<CompletionResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(CompletionResponse::new("text", FinishReason::Stop).with_usage(TokenUsage {
+                input_tokens: 100,
+                output_tokens: 10,
+            }))
         }
     }
 
     #[tokio::test]
-    async fn test_execute_plan_single_step() {
-        let registry = ToolRegistry::new();
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+    async fn test_plan_stops_when_budget_is_used_up() {
+        let provider = Arc::new(MeteredProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let budget = Budget::new()
+            .with_max_cost_usd(0.001)
+            .with_prices(5.0, 20.0);
+        let mut executor = Executor::new(ToolRegistry::new(), Box::new(MockMemoryStore::new()))
+            .with_llm_provider(Box::new(provider.clone()))
+            .with_budget(budget);
+
+        let step = Step::TextGeneration {
+            prompt: "Write".to_string(),
+            profile: None,
+        };
+        let plan = Plan::new(vec![step.clone(), step.clone(), step], "Write".to_string());
+        let err = executor.execute_plan(plan.clone()).await.unwrap_err();
+        assert!(matches!(err.root_cause(), AgentError::BudgetExceeded(_)));
+        assert!(err.to_string().contains("spent $0.0014 of $0.0010"));
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+
+        // Each run starts with the whole budget
+        assert!(executor.execute_plan(plan).await.is_err());
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 4);
+        assert_eq!(executor.budget_spent().total(), 220);
+    }
 
-        let plan = Plan::new(
-            vec![Step::Response {
-                text: "Hello, world!".to_string(),
-            }],
-            "Simple single-step plan".to_string(),
-        );
+    /// Tool that counts its calls
+    struct CountingTool(Arc<AtomicUsize>);
 
-        let result = executor.execute_plan(plan).await.unwrap();
-        assert!(result.success);
-        assert_eq!(result.final_response, "Hello, world!");
-        assert_eq!(result.step_results.len(), 1);
-        assert!(result.step_results[0].success);
+    #[async_trait]
+    impl tools::Tool for CountingTool {
+        fn name(&self) -> &str {
+            "search"
+        }
+
+        fn description(&self) -> &str {
+            "Counts calls"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(json!({"hits": params["query"]}))
+        }
     }
 
     #[tokio::test]
-    async fn test_execute_plan_multi_step() {
+    async fn test_recorded_run_can_be_stepped_through_and_rerun() {
+        let searches = Arc::new(AtomicUsize::new(0));
         let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockSuccessTool::new(
-            "tool1",
-            json!({"data": "result1"}),
-        )));
-        registry.register(Box::new(MockSuccessTool::new(
-            "tool2",
-            json!({"data": "result2"}),
-        )));
-
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+        registry.register(Box::new(CountingTool(searches.clone())));
+        let provider = Arc::new(MeteredProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let recorder = TraceRecorder::new();
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()))
+            .with_llm_provider(Box::new(provider.clone()))
+            .with_recorder(recorder.clone());
 
         let plan = Plan::new(
             vec![
-                Step::Reasoning {
-                    text: "First, I'll use tool1".to_string(),
-                },
-                Step::ToolCall(ToolCall::new("tool1".to_string(), json!({}))),
-                Step::Reasoning {
-                    text: "Now I'll use tool2".to_string(),
+                Step::ToolCall(ToolCall::new("search".to_string(), json!({"query": "rust"}))),
+                Step::TextGeneration {
+                    prompt: "Summarize".to_string(),
+                    profile: None,
                 },
-                Step::ToolCall(ToolCall::new("tool2".to_string(), json!({}))),
                 Step::Response {
-                    text: "All done!".to_string(),
+                    text: "Done".to_string(),
                 },
             ],
-            "Multi-step plan".to_string(),
+            "Search and summarize".to_string(),
         );
-
-        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(executor.execute_plan(plan).await.unwrap().success);
+
+        let trace = recorder.last().unwrap();
+        assert!(trace.plan.is_some());
+        assert_eq!(trace.step_results().len(), 3);
+
+        let mut debugger = RunDebugger::new(trace);
+        assert!(debugger.current().is_none());
+        assert!(matches!(
+            debugger.step_forward(),
+            Some(TraceEvent::StepStarted { step: 0, .. })
+        ));
+        assert!(matches!(
+            debugger.step_forward(),
+            Some(TraceEvent::ToolCall { step: 0, tool_name, parameters })
+                if tool_name == "search" && parameters["query"] == "rust"
+        ));
+        assert!(matches!(
+            debugger.step_forward(),
+            Some(TraceEvent::ToolResult { step: 0, success: true, .. })
+        ));
+        assert!(debugger.step_back().is_some());
+        assert_eq!(debugger.position(), 2);
+
+        assert!(matches!(
+            debugger.seek_step(1),
+            Some(TraceEvent::StepStarted { step: 1, .. })
+        ));
+        // The search was recorded, and added to memory, before the model step
+        let state = debugger.inspect();
+        assert_eq!(state.step_results.len(), 1);
+        assert_eq!(state.memory.len(), 1);
+        debugger.step_forward();
+        assert!(matches!(
+            debugger.step_forward(),
+            Some(TraceEvent::LlmResponse { step: 1, text, .. }) if text == "text"
+        ));
+
+        debugger.seek(debugger.len());
+        assert!(matches!(
+            debugger.current(),
+            Some(TraceEvent::RunFinished { success: true, output }) if output == "Done"
+        ));
+        assert_eq!(debugger.inspect().memory.len(), 3);
+
+        // Rerunning from the response step repeats no tool or model calls
+        let replacement = Step::Response {
+            text: "Done again".to_string(),
+        };
+        let result = debugger.rerun_from(&mut executor, 2, Some(replacement)).await.unwrap();
         assert!(result.success);
-        assert_eq!(result.final_response, "All done!");
-        assert_eq!(result.step_results.len(), 5);
-        assert!(result.step_results.iter().all(|r| r.success));
+        assert_eq!(result.final_response, "Done again");
+        assert_eq!(result.step_results.len(), 3);
+        assert_eq!(searches.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(executor.memory.get_recent(usize::MAX).len(), 3);
+
+        // Rerunning from the model step calls the model again
+        debugger.rerun_from(&mut executor, 1, None).await.unwrap();
+        assert_eq!(searches.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(recorder.traces().len(), 3);
     }
 
     #[tokio::test]
-    async fn test_execute_plan_with_failure() {
+    async fn test_conditional_runs_branch_chosen_by_check() {
         let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockSuccessTool::new(
-            "good_tool",
-            json!({"result": "ok"}),
-        )));
-        registry.register(Box::new(MockFailureTool::new("bad_tool")));
-
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
+        registry.register(Box::new(MockSuccessTool::new("status", json!({"state": "ready"}))));
+        registry.register(Box::new(MockFailureTool::new("broken")));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
 
+        let reason = |text: &str| Step::Reasoning {
+            text: text.to_string(),
+        };
+        let conditional = |tool: &str, condition: Condition| Step::Conditional {
+            check: Some(Box::new(Step::ToolCall(ToolCall::new(tool.to_string(), json!({}))))),
+            step: None,
+            condition,
+            then: vec![reason("then"), reason("then again")],
+            otherwise: vec![reason("otherwise")],
+        };
         let plan = Plan::new(
             vec![
-                Step::ToolCall(ToolCall::new("good_tool".to_string(), json!({}))),
-                Step::ToolCall(ToolCall::new("bad_tool".to_string(), json!({}))),
-                Step::Response {
-                    text: "This should not execute".to_string(),
-                },
+                conditional("broken", Condition::Failed),
+                conditional("status", Condition::Contains { text: "READY".to_string() }),
+                conditional("status", Condition::Equals { text: "busy".to_string() }),
             ],
-            "Plan with failure".to_string(),
+            "Branch".to_string(),
         );
 
         let result = executor.execute_plan(plan).await.unwrap();
-        assert!(!result.success);
-        // Should have 2 step results: one success, one failure
-        assert_eq!(result.step_results.len(), 2);
-        assert!(result.step_results[0].success);
-        assert!(!result.step_results[1].success);
+        assert!(result.success);
+        let outputs: Vec<_> = result.step_results.iter().map(|r| r.output.as_str()).collect();
+        assert_eq!(outputs, ["then again", "then again", "otherwise"]);
+        assert!(result.step_results.iter().all(|r| r.step_type == StepKind::Conditional));
     }
 
     #[tokio::test]
-    async fn test_execute_plan_stores_results_in_memory() {
+    async fn test_conditional_branches_on_earlier_step_results() {
         let mut registry = ToolRegistry::new();
         registry.register(Box::new(MockSuccessTool::new(
-            "test_tool",
-            json!({"result": "data"}),
+            "ticket",
+            json!({"status": "open", "owner": null}),
         )));
+        registry.register(Box::new(MockSuccessTool::new("search", json!({"hits": 3}))));
+        let mut executor = Executor::new(registry, Box::new(MockMemoryStore::new()));
 
-        let memory_store = MockMemoryStore::new();
-        let memory_clone = memory_store.clone();
-        let mut executor = Executor::new(registry, Box::new(memory_store));
-
+        let reason = |text: &str| Step::Reasoning {
+            text: text.to_string(),
+        };
+        let on_earlier = |step: Option<usize>, condition: Condition| Step::Conditional {
+            check: None,
+            step,
+            condition,
+            then: vec![reason("then")],
+            otherwise: vec![reason("otherwise")],
+        };
+        let path = |path: &str, equals: Option<serde_json::Value>| Condition::JsonPath {
+            path: path.to_string(),
+            equals,
+        };
         let plan = Plan::new(
             vec![
-                Step::Reasoning {
-                    text: "Thinking...".to_string(),
-                },
-                Step::ToolCall(ToolCall::new("test_tool".to_string(), json!({}))),
-                Step::Response {
-                    text: "Done!".to_string(),
-                },
+                Step::ToolCall(ToolCall::new("ticket".to_string(), json!({}))),
+                Step::ToolCall(ToolCall::new("search".to_string(), json!({}))),
+                on_earlier(Some(0), path("$.status", Some(json!("open")))),
+                on_earlier(Some(0), path("$.owner", None)),
+                on_earlier(Some(1), Condition::Matches { pattern: r#""hits":\s*\d+"#.to_string() }),
+                on_earlier(None, Condition::Succeeded),
             ],
-            "Plan to test memory".to_string(),
+            "Triage".to_string(),
         );
 
-        executor.execute_plan(plan).await.unwrap();
-
-        // Check that results were stored in memory
-        let messages = memory_clone.get_messages();
-        assert_eq!(messages.len(), 3);
-        assert!(messages[0].content.contains("Thinking"));
-        assert!(messages[1].content.contains("data"));
-        assert!(messages[2].content.contains("Done"));
+        let result = executor.execute_plan(plan).await.unwrap();
+        assert!(result.success);
+        let outputs: Vec<_> = result.step_results[2..].iter().map(|r| r.output.as_str()).collect();
+        assert_eq!(outputs, ["then", "otherwise", "then", "then"]);
     }
 
     #[tokio::test]
-    async fn test_execute_plan_empty_plan() {
-        let registry = ToolRegistry::new();
-        let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
-
-        let plan = Plan::new(vec![], "Empty plan".to_string());
+    async fn test_loop_repeats_until_condition_holds() {
+        let looped = |max_iterations| {
+            Plan::new(
+                vec![
+                    Step::Loop {
+                        body: vec![Step::ToolCall(ToolCall::new("flaky".to_string(), json!({})))],
+                        until: Condition::Succeeded,
+                        max_iterations,
+                    },
+                    Step::Response {
+                        text: "Fixed".to_string(),
+                    },
+                ],
+                "Retry until it works".to_string(),
+            )
+        };
+        let executor = || {
+            let mut registry = ToolRegistry::new();
+            registry.register(Box::new(FlakyTool {
+                failures: 2,
+                error: || AgentError::Execution("temporarily unavailable".to_string()),
+                calls: Arc::new(Mutex::new(0)),
+            }));
+            Executor::new(registry, Box::new(MockMemoryStore::new()))
+        };
 
-        let result = executor.execute_plan(plan).await.unwrap();
+        let result = executor().execute_plan(looped(5)).await.unwrap();
         assert!(result.success);
-        assert_eq!(result.final_response, "");
-        assert_eq!(result.step_results.len(), 0);
+        assert_eq!(result.step_results[0].step_type, StepKind::Loop);
+        assert!(result.step_results[0].output.contains("\"attempt\": 3"));
+        // Each iteration is recorded, including the failed attempts
+        let iterations = &result.step_results[0].iterations;
+        let succeeded: Vec<_> = iterations.iter().map(|iteration| iteration.success).collect();
+        assert_eq!(succeeded, [false, false, true]);
+        assert!(iterations.iter().all(|iteration| iteration.duration_ms.is_some()));
+
+        let result = executor()
+            .execute_plan(looped(2))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.step_results.len(), 1);
+        let capped = &result.step_results[0];
+        assert_eq!(capped.step_type, StepKind::Loop);
+        assert!(!capped.success);
+        assert!(capped.output.contains("Loop condition not met after 2 iterations"));
+        // Both failed attempts are kept
+        let succeeded: Vec<_> = capped.iterations.iter().map(|iteration| iteration.success).collect();
+        assert_eq!(succeeded, [false, false]);
+        assert!(capped.iterations[1].output.contains("temporarily unavailable"));
     }
 
     #[tokio::test]
-    async fn test_execute_plan_no_explicit_response() {
-        let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockSuccessTool::new(
-            "tool1",
-            json!({"result": "output1"}),
-        )));
-        registry.register(Box::new(MockSuccessTool::new(
-            "tool2",
-            json!({"result": "output2"}),
-        )));
-
+    async fn test_subgoals_run_with_their_profiles_and_record_hierarchy() {
         let memory = Box::new(MockMemoryStore::new());
-        let mut executor = Executor::new(registry, memory);
-
+        let mut executor = Executor::new(ToolRegistry::new(), memory)
+            .with_llm_provider(Box::new(NamedProvider("default")))
+            .with_profile("researcher", Box::new(NamedProvider("researcher")))
+            .with_profile("writer", Box::new(NamedProvider("writer")));
+
+        let generate = |prompt: &str| Step::TextGeneration {
+            prompt: prompt.to_string(),
+            profile: None,
+        };
         let plan = Plan::new(
             vec![
-                Step::ToolCall(ToolCall::new("tool1".to_string(), json!({}))),
-                Step::ToolCall(ToolCall::new("tool2".to_string(), json!({}))),
+                Step::Subgoal {
+                    goal: "Research".to_string(),
+                    profile: Some("researcher".to_string()),
+                    steps: vec![
+                        generate("Find sources"),
+                        Step::Subgoal {
+                            goal: "Draft".to_string(),
+                            profile: Some("writer".to_string()),
+                            steps: vec![generate("Write it up")],
+                        },
+                    ],
+                },
+                Step::Subgoal {
+                    goal: "Answer".to_string(),
+                    profile: None,
+                    steps: vec![
+                        generate("Check"),
+                        // Conditionals test the subgoal's own earlier steps
+                        Step::Conditional {
+                            check: None,
+                            step: Some(0),
+                            condition: Condition::Contains { text: "default".to_string() },
+                            then: vec![Step::Reasoning { text: "Checked".to_string() }],
+                            otherwise: Vec::new(),
+                        },
+                        Step::Response { text: "Done".to_string() },
+                    ],
+                },
             ],
-            "Plan without explicit response".to_string(),
+            "Research, then answer".to_string(),
         );
 
         let result = executor.execute_plan(plan).await.unwrap();
         assert!(result.success);
-        // Should build response from step outputs
-        assert!(result.final_response.contains("output1"));
-        assert!(result.final_response.contains("output2"));
-        assert_eq!(result.step_results.len(), 2);
+        assert_eq!(result.final_response, "Done");
+
+        let research = &result.step_results[0];
+        assert_eq!(research.step_type.to_string(), "subgoal:Research");
+        assert_eq!(research.output, "writer: Write it up");
+        let outputs: Vec<_> = research.substeps.iter().map(|r| r.output.as_str()).collect();
+        assert_eq!(outputs, ["researcher: Find sources", "writer: Write it up"]);
+        assert_eq!(
+            research.substeps[1].substeps[0].step_type,
+            StepKind::TextGeneration
+        );
+        assert!(research.substeps.iter().all(|r| r.duration_ms.is_some()));
+        assert_eq!(result.step_results[1].substeps[1].output, "Checked");
     }
 
-    #[tokio::test]
-    async fn test_list_tools() {
-        let mut registry = ToolRegistry::new();
-        registry.register(Box::new(MockSuccessTool::new(
-            "tool1",
-            json!({"result": "ok"}),
-        )));
-        registry.register(Box::new(MockSuccessTool::new(
-            "tool2",
-            json!({"result": "ok"}),
-        )));
-
-        let memory = Box::new(MockMemoryStore::new());
-        let executor = Executor::new(registry, memory);
-
-        let tools = executor.list_tools();
-        assert_eq!(tools.len(), 2);
-
-        let tool_names: Vec<String> = tools.iter().map(|t| t.name.clone()).collect();
-        assert!(tool_names.contains(&"tool1".to_string()));
-        assert!(tool_names.contains(&"tool2".to_string()));
+    #[test]
+    fn test_step_kind_serializes_with_tool_name() {
+        let kind = StepKind::tool_call("search");
+        assert_eq!(kind.to_string(), "tool_call:search");
+        let value = serde_json::to_value(&kind).unwrap();
+        assert_eq!(value, json!({"type": "tool_call", "tool_name": "search"}));
+        assert_eq!(serde_json::from_value::<StepKind>(value).unwrap(), kind);
+        assert_eq!(serde_json::to_value(StepKind::Response).unwrap(), json!({"type": "response"}));
     }
 }
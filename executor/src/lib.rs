@@ -9,6 +9,46 @@
 //! - **Executor**: The main component that executes plans step by step
 //! - **ExecutionResult**: The outcome of executing a complete plan
 //! - **StepResult**: The result of executing a single step
+//! - **Tool loop**: A mode where the model calls tools over several turns
+//!   until it produces a final answer (see [`Executor::run_tool_loop`])
+//! - **ExecutionDiff**: What changed between two runs of the same plan
+//!   (see [`ExecutionResult::diff`])
+//! - **Execution context**: User, locale and credential references filled
+//!   into `{{context.<path>}}` placeholders and passed to tools
+//!   (see [`Executor::with_context`])
+//! - **Streamed plans**: Steps validated, and optionally run, while the
+//!   planner is still generating the plan
+//!   (see [`Executor::execute_plan_stream`])
+//! - **Concurrent steps**: Steps a plan groups as independent run several
+//!   at a time, with results kept in plan order
+//!   (see [`Executor::with_max_concurrency`])
+//! - **Subgoals**: Steps of a hierarchical plan run under their subgoal's
+//!   model profile, and their results are kept in the subgoal's
+//!   [`StepResult::substeps`], mirroring the plan's tree
+//! - **Retries**: Tool calls with a [`planner::RetryPolicy`] are retried on
+//!   failure, optionally falling back to another step
+//! - **Cancellation**: A shared [`llm::CancellationToken`] aborts a running
+//!   plan or tool loop, returning the results of the steps that finished
+//!   (see [`Executor::with_cancellation`])
+//! - **Budget**: Token and dollar limits on a run's model calls; reaching
+//!   one stops the run with `AgentError::BudgetExceeded`
+//!   (see [`Executor::with_budget`])
+//! - **Run traces**: Every model request and response, tool call and memory
+//!   change of a run, recorded for a [`RunDebugger`] to step through and
+//!   re-execute from any step (see [`Executor::with_recorder`])
+//! - **Tracing spans**: Runs, steps, tool calls and model turns run in
+//!   `tracing` spans with the request's trace ID, the step index, the model
+//!   and token counts, for export with the `athena-ai` crate's
+//!   `opentelemetry` feature
+//! - **Agents**: [`AgentBuilder`] wires a provider, system prompt, memory
+//!   and tools into a [`ToolLoopAgent`] implementing [`agent_core::Agent`]
+//! - **Simulated users**: A [`SimulatedUser`] plays a [`Persona`] against
+//!   an agent for several turns, scripted or written by a model, and
+//!   returns a [`SimulationTranscript`] for conversation-level evaluation
+//! - **Workflow engines**: Long-running plans handed to a durable workflow
+//!   engine such as Temporal, with the run's status read back as an
+//!   [`ExecutionResult`] (see [`WorkflowEngine`] and
+//!   [`Executor::delegate_plan`])
 //! 
 //! # Example
 //! 
@@ -34,9 +74,26 @@
 //! # }
 //! ```
 
+mod agent;
 mod types;
 mod executor;
+mod tool_loop;
+mod diff;
+mod budget;
+mod trace;
+mod workflow;
+mod simulation;
 
 // Re-export public types
-pub use types::{ExecutionResult, StepResult};
+pub use types::{ExecutionResult, StepKind, StepResult, StreamExecution};
 pub use executor::Executor;
+pub use agent::{AgentBuilder, ToolLoopAgent};
+pub use tool_loop::ToolLoopConfig;
+pub use diff::{Change, ExecutionDiff, StepDiff};
+pub use budget::Budget;
+pub use trace::{DebugState, RunDebugger, RunTrace, TraceEvent, TraceRecord, TraceRecorder};
+pub use simulation::{DONE_MARKER, Persona, SimulatedUser, SimulationEnd, SimulationTranscript};
+pub use workflow::{
+    TemporalEngine, WorkflowEngine, WorkflowInput, WorkflowProgress, WorkflowRun, WorkflowState,
+    WorkflowStatus, wait_for_workflow,
+};
@@ -0,0 +1,380 @@
+//! Simulated users for end-to-end conversation tests.
+//!
+//! Single-turn checks miss problems that only show up over a conversation:
+//! forgotten context, repeated questions, answers that drift from the
+//! user's goal. A [`SimulatedUser`] role-plays a [`Persona`] against an
+//! [`Agent`] for several turns, either from a fixed script or with a model
+//! writing the user's messages, and returns a [`SimulationTranscript`] for
+//! an evaluation harness to score.
+
+use agent_core::{Agent, AgentError, AgentOutput, Conversation, Message, Result, Role};
+use llm::LLMProvider;
+use serde::{Deserialize, Serialize};
+
+/// Reply of a model-driven user that wants to end the conversation
+pub const DONE_MARKER: &str = "[DONE]";
+
+/// The user a [`SimulatedUser`] plays
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Persona {
+    /// Short name of the persona, e.g. `impatient-customer`
+    pub name: String,
+    /// Who the user is and how they write
+    pub description: String,
+    /// What the user wants from the conversation, if anything specific
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<String>,
+}
+
+impl Persona {
+    /// A persona without a specific goal
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            goal: None,
+        }
+    }
+
+    /// Set what the user wants from the conversation
+    pub fn with_goal(mut self, goal: impl Into<String>) -> Self {
+        self.goal = Some(goal.into());
+        self
+    }
+
+    /// System prompt for a model role-playing this persona
+    fn system_prompt(&self) -> String {
+        let mut prompt = format!(
+            "You are role-playing a user talking to an AI assistant. Stay in character.\n\n\
+             Who you are: {}",
+            self.description
+        );
+        if let Some(goal) = &self.goal {
+            prompt.push_str(&format!("\nWhat you want: {}", goal));
+        }
+        prompt.push_str(&format!(
+            "\n\nReply with only your next message to the assistant. Once your goal is met, \
+             or you would give up, reply with exactly {}.",
+            DONE_MARKER
+        ));
+        prompt
+    }
+}
+
+/// Why a simulated conversation ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", content = "detail", rename_all = "snake_case")]
+pub enum SimulationEnd {
+    /// Every scripted message was sent
+    ScriptFinished,
+    /// The model-driven user said it was done
+    UserDone,
+    /// The turn limit was reached
+    MaxTurns,
+    /// The agent failed, with its error
+    AgentFailed(String),
+}
+
+/// The result of one simulated conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationTranscript {
+    /// The persona that was played
+    pub persona: Persona,
+    /// Every user and agent message, in order
+    pub conversation: Conversation,
+    /// The agent's output for each user message that it answered
+    pub outputs: Vec<AgentOutput>,
+    /// Why the conversation ended
+    pub ended_by: SimulationEnd,
+}
+
+impl SimulationTranscript {
+    /// Number of user messages the agent answered
+    pub fn turns(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Whether every turn succeeded and the conversation ended on the
+    /// user's terms rather than at the turn limit or an error
+    pub fn completed(&self) -> bool {
+        matches!(
+            self.ended_by,
+            SimulationEnd::ScriptFinished | SimulationEnd::UserDone
+        ) && self.outputs.iter().all(|output| output.success)
+    }
+}
+
+/// Where the simulated user's messages come from
+enum UserScript {
+    Scripted(Vec<String>),
+    Model(Box<dyn LLMProvider>),
+}
+
+/// Plays a persona against an agent for several turns
+///
+/// # Example
+///
+/// ```no_run
+/// use executor::{Persona, SimulatedUser};
+///
+/// # async fn example(
+/// #     agent: &dyn agent_core::Agent,
+/// #     user_model: Box<dyn llm::LLMProvider>,
+/// # ) -> agent_core::Result<()> {
+/// let persona = Persona::new(
+///     "confused-traveller",
+///     "A first-time traveller who writes short, vague messages.",
+/// )
+/// .with_goal("Rebook a cancelled flight to Lisbon for tomorrow morning.");
+///
+/// let transcript = SimulatedUser::model(persona, user_model)
+///     .with_max_turns(8)
+///     .run(agent)
+///     .await?;
+///
+/// println!("{}", transcript.conversation.to_markdown());
+/// println!("{}", serde_json::to_string(&transcript)?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SimulatedUser {
+    persona: Persona,
+    script: UserScript,
+    max_turns: usize,
+}
+
+impl SimulatedUser {
+    /// A user that sends `messages` in order, whatever the agent answers
+    pub fn scripted(persona: Persona, messages: Vec<String>) -> Self {
+        let max_turns = messages.len();
+        Self {
+            persona,
+            script: UserScript::Scripted(messages),
+            max_turns,
+        }
+    }
+
+    /// A user whose messages are written by `provider` playing the persona
+    ///
+    /// The conversation runs until the model replies with [`DONE_MARKER`]
+    /// or the turn limit (10 by default) is reached.
+    pub fn model(persona: Persona, provider: Box<dyn LLMProvider>) -> Self {
+        Self {
+            persona,
+            script: UserScript::Model(provider),
+            max_turns: 10,
+        }
+    }
+
+    /// Set the most user messages to send
+    pub fn with_max_turns(mut self, max_turns: usize) -> Self {
+        self.max_turns = max_turns;
+        self
+    }
+
+    /// The persona being played
+    pub fn persona(&self) -> &Persona {
+        &self.persona
+    }
+
+    /// Hold a conversation with `agent`
+    ///
+    /// Agent errors end the conversation and are recorded in the
+    /// transcript rather than returned, so a failing agent still yields a
+    /// transcript to score.
+    ///
+    /// # Errors
+    /// Returns an error if the model playing the user fails
+    pub async fn run(&self, agent: &dyn Agent) -> Result<SimulationTranscript> {
+        let mut conversation = Conversation::new();
+        let mut outputs = Vec::new();
+
+        let ended_by = loop {
+            if outputs.len() >= self.max_turns {
+                break match &self.script {
+                    UserScript::Scripted(messages) if outputs.len() >= messages.len() => {
+                        SimulationEnd::ScriptFinished
+                    }
+                    _ => SimulationEnd::MaxTurns,
+                };
+            }
+
+            let input = match self.next_message(&conversation, outputs.len()).await? {
+                Some(input) => input,
+                None => {
+                    break match self.script {
+                        UserScript::Scripted(_) => SimulationEnd::ScriptFinished,
+                        UserScript::Model(_) => SimulationEnd::UserDone,
+                    };
+                }
+            };
+            conversation.push_message(Message::user(&input));
+
+            match agent.run(&input).await {
+                Ok(output) => {
+                    conversation.push_message(Message::assistant(&output.response));
+                    outputs.push(output);
+                }
+                Err(error) => break SimulationEnd::AgentFailed(error.to_string()),
+            }
+        };
+
+        Ok(SimulationTranscript {
+            persona: self.persona.clone(),
+            conversation,
+            outputs,
+            ended_by,
+        })
+    }
+
+    /// The user's next message, or `None` when the user is done
+    async fn next_message(
+        &self,
+        conversation: &Conversation,
+        turn: usize,
+    ) -> Result<Option<String>> {
+        match &self.script {
+            UserScript::Scripted(messages) => Ok(messages.get(turn).cloned()),
+            UserScript::Model(provider) => {
+                // The model plays the user, so the roles are swapped
+                let mut messages = vec![Message::system(self.persona.system_prompt())];
+                messages.extend(conversation.messages().map(|message| match message.role {
+                    Role::User => Message::assistant(&message.content),
+                    _ => Message::user(&message.content),
+                }));
+                if turn == 0 {
+                    messages.push(Message::user("Start the conversation."));
+                }
+
+                let reply = provider
+                    .send_message(&messages)
+                    .await
+                    .map_err(|e| AgentError::Execution(format!("Simulated user failed: {}", e)))?;
+                let reply = reply.trim();
+                if reply.is_empty() || reply.contains(DONE_MARKER) {
+                    Ok(None)
+                } else {
+                    Ok(Some(reply.to_string()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// Answers with the input in upper case, failing on "crash"
+    struct Shouter;
+
+    #[async_trait]
+    impl Agent for Shouter {
+        async fn run(&self, input: &str) -> Result<AgentOutput> {
+            if input == "crash" {
+                return Err(AgentError::Execution("agent crashed".to_string()));
+            }
+            Ok(AgentOutput::new(input.to_uppercase()))
+        }
+    }
+
+    /// Every request a provider was sent
+    type Requests = Arc<Mutex<Vec<Vec<Message>>>>;
+
+    /// Plays the user with canned replies
+    struct ScriptedUserModel {
+        replies: Mutex<Vec<&'static str>>,
+        requests: Requests,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedUserModel {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.replies.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    fn user_model(replies: Vec<&'static str>) -> (Box<dyn LLMProvider>, Requests) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedUserModel {
+            replies: Mutex::new(replies),
+            requests: requests.clone(),
+        };
+        (Box::new(provider), requests)
+    }
+
+    fn persona() -> Persona {
+        Persona::new("tester", "A terse tester.").with_goal("Hear something loud.")
+    }
+
+    #[tokio::test]
+    async fn test_scripted_user() {
+        let user = SimulatedUser::scripted(persona(), vec!["hi".to_string(), "bye".to_string()]);
+        let transcript = user.run(&Shouter).await.unwrap();
+
+        assert_eq!(transcript.ended_by, SimulationEnd::ScriptFinished);
+        assert_eq!(transcript.turns(), 2);
+        assert!(transcript.completed());
+        let contents: Vec<_> = transcript
+            .conversation
+            .messages()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["hi", "HI", "bye", "BYE"]);
+    }
+
+    #[tokio::test]
+    async fn test_model_driven_user_swaps_roles_and_stops_when_done() {
+        let (provider, requests) = user_model(vec!["hello", "louder", "[DONE]"]);
+        let transcript = SimulatedUser::model(persona(), provider)
+            .run(&Shouter)
+            .await
+            .unwrap();
+
+        assert_eq!(transcript.ended_by, SimulationEnd::UserDone);
+        assert_eq!(transcript.turns(), 2);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0][0].content.contains("Hear something loud."));
+        assert_eq!(
+            requests[0].last().unwrap().content,
+            "Start the conversation."
+        );
+        // The agent's answers are what the simulated user reads
+        let last = &requests[2];
+        assert_eq!(
+            (last[1].role.clone(), last[1].content.as_str()),
+            (Role::Assistant, "hello")
+        );
+        assert_eq!(
+            (last[2].role.clone(), last[2].content.as_str()),
+            (Role::User, "HELLO")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_turn_limit_and_agent_failures() {
+        let (provider, _) = user_model(vec!["one", "two", "three"]);
+        let transcript = SimulatedUser::model(persona(), provider)
+            .with_max_turns(2)
+            .run(&Shouter)
+            .await
+            .unwrap();
+        assert_eq!(transcript.ended_by, SimulationEnd::MaxTurns);
+        assert!(!transcript.completed());
+
+        let user = SimulatedUser::scripted(persona(), vec!["hi".to_string(), "crash".to_string()]);
+        let transcript = user.run(&Shouter).await.unwrap();
+        assert_eq!(
+            transcript.ended_by,
+            SimulationEnd::AgentFailed("Execution error: agent crashed".to_string())
+        );
+        assert_eq!(transcript.turns(), 1);
+
+        let json = serde_json::to_value(&transcript).unwrap();
+        assert_eq!(json["ended_by"]["reason"], "agent_failed");
+    }
+}
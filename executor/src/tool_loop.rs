@@ -0,0 +1,187 @@
+//! Multi-turn tool-use protocol.
+//!
+//! In tool loop mode the model is asked, turn after turn, to either request
+//! tool calls or give a final answer. Each turn is a structured response
+//! matching [`turn_schema`]; tool results are appended to the conversation
+//! and the model is asked again until it answers or a limit is reached.
+
+use agent_core::Result;
+use llm::StructuredOutput;
+use memory::Compactor;
+use planner::ToolCall;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tools::ToolInfo;
+
+/// Limits that bound a tool loop
+#[derive(Debug, Clone)]
+pub struct ToolLoopConfig {
+    /// Maximum number of model turns
+    pub max_iterations: usize,
+    /// Maximum number of tool calls across all turns
+    pub max_tool_calls: usize,
+    /// Maximum estimated conversation size in tokens, if limited
+    pub token_budget: Option<usize>,
+    /// Collapses repeated tool outputs and retrieved chunks before each turn
+    pub compaction: Option<Compactor>,
+    /// Instructions placed ahead of the tool protocol in the system prompt
+    pub system_prompt: Option<String>,
+}
+
+impl Default for ToolLoopConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 10,
+            max_tool_calls: 25,
+            token_budget: None,
+            compaction: None,
+            system_prompt: None,
+        }
+    }
+}
+
+impl ToolLoopConfig {
+    /// Set the maximum number of model turns
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Set the maximum number of tool calls
+    pub fn with_max_tool_calls(mut self, max_tool_calls: usize) -> Self {
+        self.max_tool_calls = max_tool_calls;
+        self
+    }
+
+    /// Stop once the conversation exceeds this many estimated tokens
+    pub fn with_token_budget(mut self, token_budget: usize) -> Self {
+        self.token_budget = Some(token_budget);
+        self
+    }
+
+    /// Compact the conversation before each turn
+    ///
+    /// The token budget is checked against the compacted conversation.
+    pub fn with_compaction(mut self, compactor: Compactor) -> Self {
+        self.compaction = Some(compactor);
+        self
+    }
+
+    /// Give the model instructions, e.g. a persona or task rules, ahead of
+    /// the description of the tools
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+}
+
+/// One model turn: tool calls to run, or the final answer
+#[derive(Debug, Deserialize)]
+pub(crate) struct Turn {
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default)]
+    pub final_answer: Option<String>,
+}
+
+impl Turn {
+    /// Parse a validated structured response
+    pub(crate) fn from_value(value: Value) -> Result<Self> {
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Schema every model turn must match
+pub(crate) fn turn_output() -> StructuredOutput {
+    StructuredOutput::new(json!({
+        "type": "object",
+        "properties": {
+            "tool_calls": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "tool_name": {"type": "string"},
+                        "parameters": {"type": "object"}
+                    },
+                    "required": ["tool_name", "parameters"]
+                }
+            },
+            "final_answer": {"type": "string"}
+        },
+        "additionalProperties": false
+    }))
+}
+
+/// System prompt describing the available tools and the turn protocol,
+/// after the caller's `instructions` if any
+pub(crate) fn system_prompt(instructions: Option<&str>, tools: &[ToolInfo]) -> String {
+    let mut prompt = match instructions {
+        Some(instructions) => format!("{}\n\n", instructions.trim_end()),
+        None => String::new(),
+    };
+    prompt.push_str(
+        "You can use tools to answer the user. On each turn, either request one or more \
+         tool calls with \"tool_calls\", or give your answer with \"final_answer\". \
+         Tool results will be sent back to you.\n\nAvailable tools:\n",
+    );
+    for tool in tools {
+        prompt.push_str(&format!(
+            "- {}: {}\n  Parameters: {}\n",
+            tool.name, tool.description, tool.parameters_schema
+        ));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_parsing() {
+        let turn = Turn::from_value(json!({
+            "tool_calls": [{"tool_name": "calculator", "parameters": {"expression": "2+2"}}]
+        }))
+        .unwrap();
+        assert_eq!(turn.tool_calls.len(), 1);
+        assert!(turn.final_answer.is_none());
+
+        let turn = Turn::from_value(json!({"final_answer": "4"})).unwrap();
+        assert!(turn.tool_calls.is_empty());
+        assert_eq!(turn.final_answer.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn test_turn_schema_rejects_unknown_fields() {
+        assert!(turn_output().parse(r#"{"answer": "4"}"#).is_err());
+        assert!(turn_output().parse(r#"{"final_answer": "4"}"#).is_ok());
+    }
+
+    #[test]
+    fn test_system_prompt_lists_tools() {
+        let tools = vec![ToolInfo {
+            name: "calculator".to_string(),
+            description: "Evaluates arithmetic".to_string(),
+            parameters_schema: json!({"type": "object"}),
+        }];
+        let prompt = system_prompt(None, &tools);
+        assert!(prompt.starts_with("You can use tools"));
+        assert!(prompt.contains("- calculator: Evaluates arithmetic"));
+        assert!(prompt.contains("final_answer"));
+
+        let prompt = system_prompt(Some("You are a terse accountant.\n"), &tools);
+        assert!(prompt.starts_with("You are a terse accountant.\n\nYou can use tools"));
+    }
+
+    #[test]
+    fn test_config_defaults_and_builders() {
+        let config = ToolLoopConfig::default()
+            .with_max_iterations(3)
+            .with_max_tool_calls(5)
+            .with_token_budget(1000);
+        assert_eq!(config.max_iterations, 3);
+        assert_eq!(config.max_tool_calls, 5);
+        assert_eq!(config.token_budget, Some(1000));
+    }
+}
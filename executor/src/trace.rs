@@ -0,0 +1,528 @@
+//! Recording and replaying agent runs.
+//!
+//! A [`TraceRecorder`] attached to an executor with
+//! [`Executor::with_recorder`] captures every run as a [`RunTrace`]: each
+//! step as it starts and finishes, the messages sent to the model and its
+//! responses, tool calls with their rendered parameters and results, and
+//! every message added to memory. Traces serialize to JSON, so a run from
+//! production can be saved and debugged elsewhere.
+//!
+//! A [`RunDebugger`] steps through a trace event by event, shows the state
+//! of the run at any point, and re-executes a plan from any step, with that
+//! step changed, without repeating the model and tool calls before it.
+
+use agent_core::{AgentError, ExecutionContext, Message, Result};
+use chrono::{DateTime, Utc};
+use llm::TokenUsage;
+use planner::{Plan, Step};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::executor::Executor;
+use crate::types::{ExecutionResult, StepResult};
+
+tokio::task_local! {
+    static STEP: usize;
+}
+
+/// Index of the step being executed on the current task, 0 outside steps
+pub(crate) fn current_step() -> usize {
+    STEP.try_with(|step| *step).unwrap_or(0)
+}
+
+/// Run `future` as the step at `index`, so the events it records are
+/// attributed to that step
+pub(crate) async fn in_step<F: Future>(index: usize, future: F) -> F::Output {
+    STEP.scope(index, future).await
+}
+
+/// Something that happened during a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// A step began executing
+    StepStarted {
+        /// Index of the step in the plan, or in the tool loop's results
+        step: usize,
+        /// The step as planned
+        definition: Step,
+    },
+    /// Messages were sent to a model
+    LlmRequest {
+        /// Index of the step that made the request
+        step: usize,
+        /// Model profile used, if not the default
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+        /// The messages sent
+        messages: Vec<Message>,
+    },
+    /// A model answered
+    LlmResponse {
+        /// Index of the step that made the request
+        step: usize,
+        /// The response text
+        text: String,
+        /// Tokens used, if reported
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<TokenUsage>,
+        /// The model that answered, if reported
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+    },
+    /// A tool was called
+    ToolCall {
+        /// Index of the step that made the call
+        step: usize,
+        /// Name of the tool
+        tool_name: String,
+        /// Parameters after context placeholders were filled in
+        parameters: Value,
+    },
+    /// A tool call returned
+    ToolResult {
+        /// Index of the step that made the call
+        step: usize,
+        /// Name of the tool
+        tool_name: String,
+        /// Whether the call succeeded
+        success: bool,
+        /// The tool's output, or the error
+        output: String,
+    },
+    /// A step finished, successfully or not
+    StepFinished {
+        /// Index of the step
+        step: usize,
+        /// The recorded result
+        result: StepResult,
+    },
+    /// A message was added to the executor's memory
+    MemoryAdded {
+        /// The message
+        message: Message,
+    },
+    /// The run ended
+    RunFinished {
+        /// Whether the run succeeded
+        success: bool,
+        /// The run's final response, or the error that ended it
+        output: String,
+    },
+}
+
+impl TraceEvent {
+    /// Index of the step the event belongs to, if any
+    pub fn step(&self) -> Option<usize> {
+        match self {
+            Self::StepStarted { step, .. }
+            | Self::LlmRequest { step, .. }
+            | Self::LlmResponse { step, .. }
+            | Self::ToolCall { step, .. }
+            | Self::ToolResult { step, .. }
+            | Self::StepFinished { step, .. } => Some(*step),
+            Self::MemoryAdded { .. } | Self::RunFinished { .. } => None,
+        }
+    }
+}
+
+/// A trace event and when it happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// When the event was recorded
+    pub at: DateTime<Utc>,
+    /// What happened
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+/// Everything that happened in one plan execution or tool loop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunTrace {
+    /// Trace ID of the request the run belonged to, if one was in scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// The plan that was executed; for streamed plans, set once the plan
+    /// was complete. `None` for tool loops.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+    /// The query a tool loop answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// The execution context when the run started
+    pub context: ExecutionContext,
+    /// The executor's memory when the run started
+    pub memory: Vec<Message>,
+    /// What happened, in order
+    pub events: Vec<TraceRecord>,
+}
+
+impl RunTrace {
+    /// Save the trace as JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Load a trace saved with [`save`](Self::save)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a trace.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Recorded result of every finished step, by step index
+    pub fn step_results(&self) -> Vec<(usize, &StepResult)> {
+        self.events
+            .iter()
+            .filter_map(|record| match &record.event {
+                TraceEvent::StepFinished { step, result } => Some((*step, result)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Shared handle that collects the traces of an executor's runs
+///
+/// Clones share the same traces, so keep one to read them while the
+/// executor owns another.
+#[derive(Debug, Clone, Default)]
+pub struct TraceRecorder {
+    traces: Arc<Mutex<Vec<RunTrace>>>,
+}
+
+impl TraceRecorder {
+    /// Create a recorder with no traces
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All recorded runs, oldest first
+    pub fn traces(&self) -> Vec<RunTrace> {
+        self.traces.lock().unwrap().clone()
+    }
+
+    /// The most recent run
+    pub fn last(&self) -> Option<RunTrace> {
+        self.traces.lock().unwrap().last().cloned()
+    }
+
+    /// Remove and return all recorded runs
+    pub fn take(&self) -> Vec<RunTrace> {
+        std::mem::take(&mut *self.traces.lock().unwrap())
+    }
+
+    /// Start recording a new run
+    pub(crate) fn begin(
+        &self,
+        plan: Option<Plan>,
+        query: Option<String>,
+        context: &ExecutionContext,
+        memory: Vec<Message>,
+    ) {
+        self.traces.lock().unwrap().push(RunTrace {
+            trace_id: agent_core::RequestContext::current().map(|request| request.trace_id),
+            plan,
+            query,
+            context: context.clone(),
+            memory,
+            events: Vec::new(),
+        });
+    }
+
+    /// Set the plan of the current run once it is known
+    pub(crate) fn set_plan(&self, plan: &Plan) {
+        if let Some(trace) = self.traces.lock().unwrap().last_mut() {
+            trace.plan = Some(plan.clone());
+        }
+    }
+
+    /// Add an event to the current run
+    pub(crate) fn record(&self, event: TraceEvent) {
+        if let Some(trace) = self.traces.lock().unwrap().last_mut() {
+            trace.events.push(TraceRecord {
+                at: Utc::now(),
+                event,
+            });
+        }
+    }
+}
+
+/// The state of a recorded run at one point
+#[derive(Debug, Clone)]
+pub struct DebugState<'a> {
+    /// Number of events that have happened
+    pub position: usize,
+    /// The last event that happened, if any
+    pub event: Option<&'a TraceEvent>,
+    /// Results of the steps finished so far, by step index
+    pub step_results: Vec<(usize, &'a StepResult)>,
+    /// The executor's memory at this point
+    pub memory: Vec<&'a Message>,
+    /// The execution context of the run
+    pub context: &'a ExecutionContext,
+}
+
+/// Steps through a recorded run and re-executes it from any step
+///
+/// The debugger's position is the number of events that have happened:
+/// 0 is before the run started and [`len`](Self::len) is after it ended.
+///
+/// # Example
+///
+/// ```no_run
+/// use executor::{Executor, RunDebugger, TraceRecorder};
+/// use memory::InMemoryStore;
+/// use planner::Step;
+/// use tools::ToolRegistry;
+///
+/// # async fn example(plan: planner::Plan) -> agent_core::Result<()> {
+/// let recorder = TraceRecorder::new();
+/// let mut executor = Executor::new(ToolRegistry::new(), Box::new(InMemoryStore::new()))
+///     .with_recorder(recorder.clone());
+/// executor.execute_plan(plan).await?;
+///
+/// let mut debugger = RunDebugger::new(recorder.last().unwrap());
+/// while let Some(event) = debugger.step_forward() {
+///     println!("{:?}", event);
+/// }
+///
+/// // Try the second step again with a different prompt
+/// let step = Step::TextGeneration { prompt: "Be brief".to_string(), profile: None };
+/// let result = debugger.rerun_from(&mut executor, 1, Some(step)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RunDebugger {
+    trace: RunTrace,
+    position: usize,
+}
+
+impl RunDebugger {
+    /// Start debugging a trace, before its first event
+    pub fn new(trace: RunTrace) -> Self {
+        Self { trace, position: 0 }
+    }
+
+    /// The trace being debugged
+    pub fn trace(&self) -> &RunTrace {
+        &self.trace
+    }
+
+    /// Number of events in the trace
+    pub fn len(&self) -> usize {
+        self.trace.events.len()
+    }
+
+    /// Check if the trace has no events
+    pub fn is_empty(&self) -> bool {
+        self.trace.events.is_empty()
+    }
+
+    /// Number of events that have happened at the current point
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The last event that happened, if any
+    pub fn current(&self) -> Option<&TraceEvent> {
+        self.position
+            .checked_sub(1)
+            .map(|index| &self.trace.events[index].event)
+    }
+
+    /// Move past the next event and return it, or `None` at the end
+    pub fn step_forward(&mut self) -> Option<&TraceEvent> {
+        if self.position == self.len() {
+            return None;
+        }
+        self.position += 1;
+        self.current()
+    }
+
+    /// Undo the last event and return the one before it, or `None` at the
+    /// start
+    pub fn step_back(&mut self) -> Option<&TraceEvent> {
+        self.position = self.position.saturating_sub(1);
+        self.current()
+    }
+
+    /// Move to a position, clamped to the end of the trace
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.len());
+    }
+
+    /// Move to just after `step` started and return its start event, if the
+    /// step ran
+    pub fn seek_step(&mut self, step: usize) -> Option<&TraceEvent> {
+        let index = self.trace.events.iter().position(|record| {
+            matches!(record.event, TraceEvent::StepStarted { step: started, .. } if started == step)
+        })?;
+        self.position = index + 1;
+        self.current()
+    }
+
+    /// The state of the run at the current point
+    pub fn inspect(&self) -> DebugState<'_> {
+        let happened = &self.trace.events[..self.position];
+        let mut step_results = Vec::new();
+        let mut memory: Vec<&Message> = self.trace.memory.iter().collect();
+        for record in happened {
+            match &record.event {
+                TraceEvent::StepFinished { step, result } => step_results.push((*step, result)),
+                TraceEvent::MemoryAdded { message } => memory.push(message),
+                _ => {}
+            }
+        }
+        DebugState {
+            position: self.position,
+            event: self.current(),
+            step_results,
+            memory,
+            context: &self.trace.context,
+        }
+    }
+
+    /// Re-execute the recorded plan from `step` on `executor`
+    ///
+    /// Steps before `step` are not run again: their recorded results are
+    /// reused and the executor's memory is restored to what it held when
+    /// `step` started. The executor's context is replaced by the recorded
+    /// one; change it through [`Executor::context_mut`] afterwards to
+    /// rerun with different context. `replacement`, if given, runs instead
+    /// of the recorded `step`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trace has no plan, `step` is past its end, or
+    /// a step before `step` did not finish successfully, and any error of
+    /// the re-executed run.
+    pub async fn rerun_from(
+        &self,
+        executor: &mut Executor,
+        step: usize,
+        replacement: Option<Step>,
+    ) -> Result<ExecutionResult> {
+        let plan = self.trace.plan.as_ref().ok_or_else(|| {
+            AgentError::Execution("Only plan runs can be re-executed".to_string())
+        })?;
+        if step >= plan.steps.len() {
+            return Err(AgentError::Execution(format!(
+                "Step {} is past the end of the {}-step plan",
+                step,
+                plan.steps.len()
+            )));
+        }
+
+        let mut completed = Vec::with_capacity(step);
+        for index in 0..step {
+            let result = self
+                .trace
+                .step_results()
+                .into_iter()
+                .find(|(finished, result)| *finished == index && result.success)
+                .map(|(_, result)| result.clone())
+                .ok_or_else(|| {
+                    AgentError::Execution(format!(
+                        "Step {} did not finish successfully in the recorded run",
+                        index
+                    ))
+                })?;
+            completed.push(result);
+        }
+
+        // Memory as it was when `step` started
+        let started = self
+            .trace
+            .events
+            .iter()
+            .position(|record| {
+                matches!(record.event, TraceEvent::StepFinished { step: finished, .. } if finished >= step)
+            })
+            .unwrap_or(self.len());
+        let memory = self.trace.memory.iter().cloned().chain(
+            self.trace.events[..started]
+                .iter()
+                .filter_map(|record| match &record.event {
+                    TraceEvent::MemoryAdded { message } => Some(message.clone()),
+                    _ => None,
+                }),
+        );
+        executor.restore_memory(memory.collect());
+        *executor.context_mut() = self.trace.context.clone();
+
+        let mut steps = plan.steps.clone();
+        if let Some(replacement) = replacement {
+            steps[step] = replacement;
+        }
+        executor
+            .execute_plan_from(Plan::new(steps, plan.reasoning.clone()), completed)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::StepKind;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_events_are_attributed_to_the_current_step() {
+        assert_eq!(current_step(), 0);
+        assert_eq!(in_step(3, async { current_step() }).await, 3);
+    }
+
+    #[test]
+    fn test_trace_round_trips_through_json() {
+        let recorder = TraceRecorder::new();
+        let plan = Plan::new(
+            vec![Step::Response {
+                text: "Hi".to_string(),
+            }],
+            "Greet".to_string(),
+        );
+        recorder.begin(
+            Some(plan),
+            None,
+            &ExecutionContext::default(),
+            vec![Message::user("Hello")],
+        );
+        recorder.record(TraceEvent::ToolCall {
+            step: 0,
+            tool_name: "search".to_string(),
+            parameters: json!({"query": "rust"}),
+        });
+        recorder.record(TraceEvent::StepFinished {
+            step: 0,
+            result: StepResult::success(StepKind::Response, "Hi"),
+        });
+
+        let path = std::env::temp_dir().join(format!("trace-{}.json", std::process::id()));
+        recorder.last().unwrap().save(&path).unwrap();
+        let loaded = RunTrace::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.plan.as_ref().unwrap().steps.len(), 1);
+        assert_eq!(loaded.memory[0].content, "Hello");
+        assert_eq!(loaded.events.len(), 2);
+        assert!(matches!(
+            &loaded.events[0].event,
+            TraceEvent::ToolCall { tool_name, .. } if tool_name == "search"
+        ));
+        assert_eq!(loaded.step_results()[0].1.output, "Hi");
+        assert_eq!(recorder.take().len(), 1);
+        assert!(recorder.last().is_none());
+    }
+}
@@ -1,4 +1,8 @@
+use llm::{CompletionResponse, TokenUsage};
+use planner::Step;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
 
 /// Result of executing a complete plan
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,35 +13,207 @@ pub struct ExecutionResult {
     pub final_response: String,
     /// Results from each step in the plan
     pub step_results: Vec<StepResult>,
+    /// Trace ID of the request the plan ran for, if one was in scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+impl ExecutionResult {
+    /// Total time spent in steps, if every step was timed
+    ///
+    /// This is the sum of the step wall times, so it exceeds the elapsed
+    /// time when steps ran concurrently.
+    pub fn total_duration_ms(&self) -> Option<u64> {
+        self.step_results.iter().map(|r| r.duration_ms).sum()
+    }
+
+    /// Tokens used by all steps that called a model, for cost tracking
+    pub fn total_usage(&self) -> TokenUsage {
+        self.step_results
+            .iter()
+            .filter_map(|r| r.usage)
+            .fold(TokenUsage::default(), |total, usage| total + usage)
+    }
+}
+
+/// When steps of a streamed plan may start running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamExecution {
+    /// Validate steps as they arrive, run them once the plan is complete
+    #[default]
+    ValidateFirst,
+    /// Run each step as soon as it arrives and passes validation
+    Eager,
+}
+
+/// Kind of step a result came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    /// A call to the named tool
+    ToolCall { tool_name: String },
+    /// A reasoning step
+    Reasoning,
+    /// A response to the user
+    Response,
+    /// An image generation step
+    ImageGeneration,
+    /// A text generation step
+    TextGeneration,
+    /// A conditional step, with the result of the branch that ran
+    Conditional,
+    /// A loop step, with the result of its last iteration
+    Loop,
+    /// A subgoal of a hierarchical plan, with the result of its last step
+    Subgoal { goal: String },
+    /// A group of independent steps run at the same time, with the result
+    /// of its last step
+    Parallel,
+    /// A model turn of a tool loop
+    Model,
+    /// A step cut short by cancellation
+    Cancelled,
+    /// A step stopped because the run used up its budget
+    BudgetExceeded,
+    /// A step that failed, or a tool loop that hit a limit
+    Error,
+}
+
+impl StepKind {
+    /// Kind of a call to `tool_name`
+    pub fn tool_call(tool_name: impl Into<String>) -> Self {
+        StepKind::ToolCall {
+            tool_name: tool_name.into(),
+        }
+    }
+
+    /// Kind of the result of running `step`
+    pub fn of(step: &Step) -> Self {
+        match step {
+            Step::ToolCall(tool_call) => StepKind::tool_call(&tool_call.tool_name),
+            Step::Reasoning { .. } => StepKind::Reasoning,
+            Step::Response { .. } => StepKind::Response,
+            Step::ImageGeneration { .. } => StepKind::ImageGeneration,
+            Step::TextGeneration { .. } => StepKind::TextGeneration,
+            Step::Conditional { .. } => StepKind::Conditional,
+            Step::Loop { .. } => StepKind::Loop,
+            Step::Subgoal { goal, .. } => StepKind::Subgoal { goal: goal.clone() },
+            Step::Parallel { .. } => StepKind::Parallel,
+        }
+    }
+}
+
+impl fmt::Display for StepKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StepKind::ToolCall { tool_name } => return write!(f, "tool_call:{}", tool_name),
+            StepKind::Subgoal { goal } => return write!(f, "subgoal:{}", goal),
+            StepKind::Reasoning => "reasoning",
+            StepKind::Response => "response",
+            StepKind::ImageGeneration => "image_generation",
+            StepKind::TextGeneration => "text_generation",
+            StepKind::Conditional => "conditional",
+            StepKind::Loop => "loop",
+            StepKind::Parallel => "parallel",
+            StepKind::Model => "model",
+            StepKind::Cancelled => "cancelled",
+            StepKind::BudgetExceeded => "budget_exceeded",
+            StepKind::Error => "error",
+        };
+        f.write_str(name)
+    }
 }
 
 /// Result of executing a single step
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StepResult {
-    /// Type of step that was executed
-    pub step_type: String,
+    /// Kind of step that was executed
+    pub step_type: StepKind,
     /// Output from the step execution
     pub output: String,
     /// Whether the step executed successfully
     pub success: bool,
+    /// How long the step took to run, if measured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Tokens used, if the step called a model that reports usage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// The model that ran the step, if it called one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// For a loop step, the result of each iteration in order: the output
+    /// and success of its last step, its duration and the usage of all its
+    /// steps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iterations: Vec<StepResult>,
+    /// For a subgoal or parallel step, the result of each of its steps in
+    /// order, so the results of a hierarchical plan form the same tree as
+    /// its steps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub substeps: Vec<StepResult>,
 }
 
 impl StepResult {
     /// Create a successful step result
-    pub fn success(step_type: impl Into<String>, output: impl Into<String>) -> Self {
+    pub fn success(step_type: StepKind, output: impl Into<String>) -> Self {
         Self {
-            step_type: step_type.into(),
+            step_type,
             output: output.into(),
             success: true,
+            duration_ms: None,
+            usage: None,
+            model: None,
+            iterations: Vec::new(),
+            substeps: Vec::new(),
         }
     }
 
     /// Create a failed step result
-    pub fn failure(step_type: impl Into<String>, output: impl Into<String>) -> Self {
+    pub fn failure(step_type: StepKind, output: impl Into<String>) -> Self {
         Self {
-            step_type: step_type.into(),
+            step_type,
             output: output.into(),
             success: false,
+            duration_ms: None,
+            usage: None,
+            model: None,
+            iterations: Vec::new(),
+            substeps: Vec::new(),
+        }
+    }
+
+    /// Record how long the step took
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration_ms = Some(duration.as_millis() as u64);
+        self
+    }
+
+    /// Record the usage and model of the model response behind the step
+    pub fn with_completion(mut self, response: &CompletionResponse) -> Self {
+        self.usage = response.usage;
+        self.model = response.model.clone();
+        self
+    }
+
+    /// The output of the last response step this result holds: its own
+    /// output if it is a response, or the last response among its substeps
+    pub fn response(&self) -> Option<&str> {
+        if self.step_type == StepKind::Response {
+            return Some(&self.output);
         }
+        self.substeps.iter().rev().find_map(StepResult::response)
+    }
+
+    /// Fold in the result of a step nested in this one: its output and
+    /// success replace this step's, and its usage is added
+    pub(crate) fn absorb(&mut self, nested: StepResult) {
+        self.output = nested.output;
+        self.success = nested.success;
+        self.usage = match (self.usage, nested.usage) {
+            (Some(total), Some(usage)) => Some(total + usage),
+            (total, usage) => total.or(usage),
+        };
+        self.model = nested.model.or(self.model.take());
     }
 }
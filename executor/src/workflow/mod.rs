@@ -0,0 +1,283 @@
+//! Delegating plan execution to external workflow engines.
+//!
+//! Teams that already run durable-workflow infrastructure can plan with
+//! athena-ai and let their engine execute the plan: it survives process
+//! restarts, retries steps on its own schedule and shows up in the
+//! engine's tooling. A [`WorkflowEngine`] starts a workflow from a
+//! [`WorkflowInput`] (the exported plan and its execution context) and
+//! reports the run's state and step results back as a [`WorkflowStatus`],
+//! which converts to the [`ExecutionResult`] a local run would return.
+//!
+//! [`TemporalEngine`] talks to Temporal. Other engines implement the trait;
+//! the workflow on the engine's side is written by the team, and only has
+//! to accept a [`WorkflowInput`] and report a [`WorkflowProgress`].
+
+mod temporal;
+
+pub use temporal::TemporalEngine;
+
+use agent_core::{AgentError, ExecutionContext, Result};
+use async_trait::async_trait;
+use planner::Plan;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::types::{ExecutionResult, StepResult};
+
+/// What a workflow is started with: the plan to run and its context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowInput {
+    /// The plan to execute
+    pub plan: Plan,
+    /// Context to fill into `{{context.<path>}}` placeholders and pass to
+    /// tools
+    #[serde(default)]
+    pub context: ExecutionContext,
+    /// Results of the plan steps that already ran, for resuming a plan
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed: Vec<StepResult>,
+}
+
+impl WorkflowInput {
+    /// Input that runs `plan` from the start with no context
+    pub fn new(plan: Plan) -> Self {
+        Self {
+            plan,
+            context: ExecutionContext::default(),
+            completed: Vec::new(),
+        }
+    }
+
+    /// Set the execution context
+    pub fn with_context(mut self, context: ExecutionContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Resume after the steps that already ran, as with
+    /// [`Executor::execute_plan_from`](crate::Executor::execute_plan_from)
+    pub fn with_completed(mut self, completed: Vec<StepResult>) -> Self {
+        self.completed = completed;
+        self
+    }
+}
+
+/// What a workflow reports about the plan it is running
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowProgress {
+    /// Results of the steps that finished, in plan order
+    #[serde(default)]
+    pub step_results: Vec<StepResult>,
+    /// The plan's final response, once a response step has run
+    #[serde(default)]
+    pub final_response: String,
+}
+
+/// Where a workflow run is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowState {
+    /// Still running, or waiting to be picked up by a worker
+    Running,
+    /// The workflow finished
+    Completed,
+    /// The workflow failed
+    Failed,
+    /// The workflow was cancelled or terminated
+    Cancelled,
+    /// The workflow ran past its timeout
+    TimedOut,
+}
+
+impl WorkflowState {
+    /// Whether the run has ended and its status will not change
+    pub fn is_finished(&self) -> bool {
+        *self != Self::Running
+    }
+}
+
+/// The state of a workflow run and the progress of its plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowStatus {
+    /// Where the run is in its life
+    pub state: WorkflowState,
+    /// The plan's progress as last reported by the workflow
+    pub progress: WorkflowProgress,
+}
+
+impl WorkflowStatus {
+    /// The run as the result of a plan execution
+    ///
+    /// The result is successful only if the workflow completed and every
+    /// reported step succeeded.
+    pub fn into_execution_result(self) -> ExecutionResult {
+        let success = self.state == WorkflowState::Completed
+            && self.progress.step_results.iter().all(|step| step.success);
+        ExecutionResult {
+            success,
+            final_response: self.progress.final_response,
+            step_results: self.progress.step_results,
+            trace_id: None,
+        }
+    }
+}
+
+/// A workflow run started on an engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    /// ID the workflow was started with
+    pub workflow_id: String,
+    /// ID the engine gave this run of the workflow, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+/// An external engine that runs plans as durable workflows
+///
+/// # Example
+///
+/// ```no_run
+/// use executor::{TemporalEngine, WorkflowEngine, WorkflowInput, wait_for_workflow};
+/// use std::time::Duration;
+///
+/// # async fn example(plan: planner::Plan) -> agent_core::Result<()> {
+/// let engine = TemporalEngine::new("http://localhost:7243", "default", "athena-plans");
+/// let run = engine.start("report-2024-06", &WorkflowInput::new(plan)).await?;
+///
+/// // Later, possibly from another process
+/// let result = wait_for_workflow(&engine, &run, Duration::from_secs(5)).await?;
+/// println!("{}", result.final_response);
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait WorkflowEngine: Send + Sync {
+    /// Name of the engine, for diagnostics
+    fn name(&self) -> &str;
+
+    /// Start a workflow running `input` under `workflow_id`
+    ///
+    /// Workflow IDs identify the run to the engine, so starting the same ID
+    /// twice fails while the first run is going rather than running the
+    /// plan twice.
+    async fn start(&self, workflow_id: &str, input: &WorkflowInput) -> Result<WorkflowRun>;
+
+    /// The state of `run` and the progress it reported
+    async fn status(&self, run: &WorkflowRun) -> Result<WorkflowStatus>;
+
+    /// Ask the engine to cancel `run`
+    async fn cancel(&self, run: &WorkflowRun) -> Result<()>;
+}
+
+/// Poll `run` every `poll_interval` until it finishes and return its result
+///
+/// # Errors
+/// Returns an error if the engine cannot report the run's status.
+pub async fn wait_for_workflow(
+    engine: &dyn WorkflowEngine,
+    run: &WorkflowRun,
+    poll_interval: Duration,
+) -> Result<ExecutionResult> {
+    loop {
+        let status = engine.status(run).await?;
+        if status.state.is_finished() {
+            return Ok(status.into_execution_result());
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Error for an engine request that failed
+fn engine_error(engine: &str, action: &str, reason: impl std::fmt::Display) -> AgentError {
+    AgentError::Execution(format!("{} could not {}: {}", engine, action, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::StepKind;
+    use planner::Step;
+    use std::sync::Mutex;
+
+    /// Engine that reports a canned sequence of states
+    struct Scripted {
+        states: Mutex<Vec<WorkflowState>>,
+    }
+
+    #[async_trait]
+    impl WorkflowEngine for Scripted {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        async fn start(&self, workflow_id: &str, _input: &WorkflowInput) -> Result<WorkflowRun> {
+            Ok(WorkflowRun {
+                workflow_id: workflow_id.to_string(),
+                run_id: None,
+            })
+        }
+
+        async fn status(&self, _run: &WorkflowRun) -> Result<WorkflowStatus> {
+            let state = self.states.lock().unwrap().remove(0);
+            let mut progress = WorkflowProgress::default();
+            if state == WorkflowState::Completed {
+                progress
+                    .step_results
+                    .push(StepResult::success(StepKind::Response, "Done"));
+                progress.final_response = "Done".to_string();
+            }
+            Ok(WorkflowStatus { state, progress })
+        }
+
+        async fn cancel(&self, _run: &WorkflowRun) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_polls_until_the_run_finishes() {
+        let engine = Scripted {
+            states: Mutex::new(vec![
+                WorkflowState::Running,
+                WorkflowState::Running,
+                WorkflowState::Completed,
+            ]),
+        };
+        let plan = Plan::new(
+            vec![Step::Response {
+                text: "Done".to_string(),
+            }],
+            "Answer".to_string(),
+        );
+        let run = engine
+            .start("answer-1", &WorkflowInput::new(plan))
+            .await
+            .unwrap();
+
+        let result = wait_for_workflow(&engine, &run, Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_response, "Done");
+        assert!(engine.states.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unfinished_or_failed_steps_are_not_successful() {
+        let failed_step = WorkflowStatus {
+            state: WorkflowState::Completed,
+            progress: WorkflowProgress {
+                step_results: vec![StepResult::failure(StepKind::Error, "boom")],
+                final_response: String::new(),
+            },
+        };
+        assert!(!failed_step.into_execution_result().success);
+
+        let timed_out = WorkflowStatus {
+            state: WorkflowState::TimedOut,
+            progress: WorkflowProgress::default(),
+        };
+        assert!(timed_out.state.is_finished());
+        assert!(!timed_out.into_execution_result().success);
+    }
+}
@@ -0,0 +1,355 @@
+//! Temporal workflows through the Temporal HTTP API.
+//!
+//! The plan is started as a workflow of a configurable type on a task
+//! queue, with the [`WorkflowInput`] as its single JSON argument. The
+//! workflow, run by the team's own workers, reports its progress by
+//! answering a query (`progress` by default) with a [`WorkflowProgress`];
+//! Temporal answers queries on finished workflows too, as long as a worker
+//! is polling the task queue.
+
+use agent_core::Result;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use super::{
+    WorkflowEngine, WorkflowInput, WorkflowProgress, WorkflowRun, WorkflowState, WorkflowStatus,
+    engine_error,
+};
+
+/// Encoding of payloads holding JSON, as set by the Temporal SDKs
+const JSON_ENCODING: &str = "json/plain";
+
+/// Runs plans as Temporal workflows
+///
+/// Requests go to the HTTP API of the Temporal frontend (port 7243 by
+/// default), under `/api/v1`.
+#[derive(Debug, Clone)]
+pub struct TemporalEngine {
+    client: Client,
+    base_url: String,
+    namespace: String,
+    task_queue: String,
+    workflow_type: String,
+    progress_query: String,
+    api_key: Option<String>,
+}
+
+impl TemporalEngine {
+    /// Engine that starts `AthenaPlan` workflows in `namespace` on
+    /// `task_queue`
+    ///
+    /// # Arguments
+    /// * `base_url` - Address of the Temporal HTTP API, e.g.
+    ///   `http://localhost:7243`
+    /// * `namespace` - Namespace to start workflows in
+    /// * `task_queue` - Task queue the team's workers poll
+    pub fn new(
+        base_url: impl Into<String>,
+        namespace: impl Into<String>,
+        task_queue: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            namespace: namespace.into(),
+            task_queue: task_queue.into(),
+            workflow_type: "AthenaPlan".to_string(),
+            progress_query: "progress".to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Set the workflow type plans are started as
+    pub fn with_workflow_type(mut self, workflow_type: impl Into<String>) -> Self {
+        self.workflow_type = workflow_type.into();
+        self
+    }
+
+    /// Set the query the workflow answers with its progress
+    pub fn with_progress_query(mut self, query: impl Into<String>) -> Self {
+        self.progress_query = query.into();
+        self
+    }
+
+    /// Authenticate with an API key, as Temporal Cloud requires
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Send requests with `client`, e.g. one configured with mTLS
+    /// certificates
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// URL of the workflow `workflow_id`, followed by `suffix`
+    fn workflow_url(&self, workflow_id: &str, suffix: &str) -> String {
+        format!(
+            "{}/api/v1/namespaces/{}/workflows/{}{}",
+            self.base_url, self.namespace, workflow_id, suffix
+        )
+    }
+
+    /// Send `request` and return the JSON response
+    async fn send(&self, request: RequestBuilder, action: &str) -> Result<Value> {
+        let request = match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| engine_error(self.name(), action, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error response".to_string());
+            return Err(engine_error(
+                self.name(),
+                action,
+                format!("HTTP {}: {}", status, error_text),
+            ));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| engine_error(self.name(), action, e))
+    }
+
+    /// Temporal's name for the execution of `run`
+    fn execution(run: &WorkflowRun) -> Value {
+        json!({"workflowId": run.workflow_id, "runId": run.run_id.clone().unwrap_or_default()})
+    }
+}
+
+/// A JSON value as a Temporal payload
+fn encode_payload<T: Serialize>(value: &T) -> Result<Value> {
+    Ok(json!({
+        "metadata": {"encoding": STANDARD.encode(JSON_ENCODING)},
+        "data": STANDARD.encode(serde_json::to_vec(value)?),
+    }))
+}
+
+/// The JSON value held by a Temporal payload
+fn decode_payload<T: DeserializeOwned>(payload: &Value) -> Result<T> {
+    let data = payload["data"].as_str().unwrap_or_default();
+    let bytes = STANDARD
+        .decode(data)
+        .map_err(|e| engine_error("Temporal", "decode a payload", e))?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Our state of a Temporal workflow execution status
+fn workflow_state(status: &str) -> Result<WorkflowState> {
+    Ok(match status {
+        // A workflow that continued as new keeps running as a new execution
+        "WORKFLOW_EXECUTION_STATUS_RUNNING" | "WORKFLOW_EXECUTION_STATUS_CONTINUED_AS_NEW" => {
+            WorkflowState::Running
+        }
+        "WORKFLOW_EXECUTION_STATUS_COMPLETED" => WorkflowState::Completed,
+        "WORKFLOW_EXECUTION_STATUS_FAILED" => WorkflowState::Failed,
+        "WORKFLOW_EXECUTION_STATUS_CANCELED" | "WORKFLOW_EXECUTION_STATUS_TERMINATED" => {
+            WorkflowState::Cancelled
+        }
+        "WORKFLOW_EXECUTION_STATUS_TIMED_OUT" => WorkflowState::TimedOut,
+        other => {
+            return Err(engine_error(
+                "Temporal",
+                "read the workflow status",
+                format!("unknown status '{}'", other),
+            ));
+        }
+    })
+}
+
+#[async_trait]
+impl WorkflowEngine for TemporalEngine {
+    fn name(&self) -> &str {
+        "Temporal"
+    }
+
+    async fn start(&self, workflow_id: &str, input: &WorkflowInput) -> Result<WorkflowRun> {
+        let body = json!({
+            "workflowId": workflow_id,
+            "workflowType": {"name": self.workflow_type},
+            "taskQueue": {"name": self.task_queue},
+            "input": {"payloads": [encode_payload(input)?]},
+        });
+        let request = self
+            .client
+            .post(self.workflow_url(workflow_id, ""))
+            .json(&body);
+        let response = self.send(request, "start the workflow").await?;
+        Ok(WorkflowRun {
+            workflow_id: workflow_id.to_string(),
+            run_id: response["runId"].as_str().map(str::to_string),
+        })
+    }
+
+    async fn status(&self, run: &WorkflowRun) -> Result<WorkflowStatus> {
+        let mut request = self.client.get(self.workflow_url(&run.workflow_id, ""));
+        if let Some(run_id) = &run.run_id {
+            request = request.query(&[("execution.runId", run_id)]);
+        }
+        let described = self.send(request, "describe the workflow").await?;
+        let state = workflow_state(
+            described["workflowExecutionInfo"]["status"]
+                .as_str()
+                .unwrap_or_default(),
+        )?;
+
+        let url = self.workflow_url(&run.workflow_id, &format!("/query/{}", self.progress_query));
+        let body = json!({
+            "execution": Self::execution(run),
+            "query": {"queryType": self.progress_query},
+        });
+        let answer = self
+            .send(
+                self.client.post(url).json(&body),
+                "query the workflow's progress",
+            )
+            .await?;
+        let progress = match answer["queryResult"]["payloads"].get(0) {
+            Some(payload) => decode_payload(payload)?,
+            None => WorkflowProgress::default(),
+        };
+        Ok(WorkflowStatus { state, progress })
+    }
+
+    async fn cancel(&self, run: &WorkflowRun) -> Result<()> {
+        let body = json!({
+            "workflowExecution": Self::execution(run),
+            "reason": "Cancelled by athena-ai",
+        });
+        let request = self
+            .client
+            .post(self.workflow_url(&run.workflow_id, "/cancel"))
+            .json(&body);
+        self.send(request, "cancel the workflow").await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{StepKind, StepResult};
+    use planner::{Plan, Step};
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const WORKFLOW: &str = "/api/v1/namespaces/default/workflows/report-1";
+
+    fn engine(server: &MockServer) -> TemporalEngine {
+        TemporalEngine::new(format!("{}/", server.uri()), "default", "plans").with_api_key("secret")
+    }
+
+    #[tokio::test]
+    async fn test_start_sends_the_plan_as_workflow_input() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(WORKFLOW))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({
+                "workflowType": {"name": "AthenaPlan"},
+                "taskQueue": {"name": "plans"},
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"runId": "r-1"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let plan = Plan::new(
+            vec![Step::Response {
+                text: "Done".to_string(),
+            }],
+            "Answer".to_string(),
+        );
+        let run = engine(&server)
+            .start("report-1", &WorkflowInput::new(plan))
+            .await
+            .unwrap();
+        assert_eq!(run.run_id.as_deref(), Some("r-1"));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let sent: WorkflowInput = decode_payload(&body["input"]["payloads"][0]).unwrap();
+        assert_eq!(sent.plan.reasoning, "Answer");
+    }
+
+    #[tokio::test]
+    async fn test_status_reads_state_and_progress() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(WORKFLOW))
+            .and(query_param("execution.runId", "r-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "workflowExecutionInfo": {"status": "WORKFLOW_EXECUTION_STATUS_COMPLETED"}
+            })))
+            .mount(&server)
+            .await;
+        let progress = WorkflowProgress {
+            step_results: vec![StepResult::success(StepKind::Response, "Done")],
+            final_response: "Done".to_string(),
+        };
+        Mock::given(method("POST"))
+            .and(path(format!("{}/query/progress", WORKFLOW)))
+            .and(body_partial_json(
+                json!({"query": {"queryType": "progress"}}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "queryResult": {"payloads": [encode_payload(&progress).unwrap()]}
+            })))
+            .mount(&server)
+            .await;
+
+        let run = WorkflowRun {
+            workflow_id: "report-1".to_string(),
+            run_id: Some("r-1".to_string()),
+        };
+        let status = engine(&server).status(&run).await.unwrap();
+        assert_eq!(status.state, WorkflowState::Completed);
+        assert_eq!(status.progress, progress);
+        assert!(status.into_execution_result().success);
+    }
+
+    #[tokio::test]
+    async fn test_errors_name_the_failed_action() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(format!("{}/cancel", WORKFLOW)))
+            .respond_with(ResponseTemplate::new(404).set_body_string("workflow not found"))
+            .mount(&server)
+            .await;
+
+        let run = WorkflowRun {
+            workflow_id: "report-1".to_string(),
+            run_id: None,
+        };
+        let error = engine(&server).cancel(&run).await.unwrap_err().to_string();
+        assert!(
+            error.contains("Temporal could not cancel the workflow"),
+            "{}",
+            error
+        );
+        assert!(error.contains("workflow not found"), "{}", error);
+    }
+
+    #[test]
+    fn test_workflow_states() {
+        let state = |status: &str| workflow_state(&format!("WORKFLOW_EXECUTION_STATUS_{}", status));
+        assert_eq!(state("CONTINUED_AS_NEW").unwrap(), WorkflowState::Running);
+        assert_eq!(state("TERMINATED").unwrap(), WorkflowState::Cancelled);
+        assert_eq!(state("TIMED_OUT").unwrap(), WorkflowState::TimedOut);
+        assert!(state("UNSPECIFIED").is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Gateway configuration: upstream routes and client keys.
+
+use agent_core::{AgentError, Result};
+use config::LLMConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Configuration of a gateway, usually loaded from YAML
+///
+/// ```yaml
+/// listen: 0.0.0.0:8080
+/// shutdown_timeout_secs: 30
+/// routes:
+///   fast:
+///     llm:
+///       provider: gemini
+///       model: gemini-2.5-flash
+///       api_key: ...
+///     pricing:
+///       input_usd_per_million: 0.30
+///       output_usd_per_million: 2.50
+///   smart:
+///     llm:
+///       provider: anthropic
+///       model: claude-sonnet-4-5
+///       api_key: ...
+/// keys:
+///   - key: gw-team-a-secret
+///     name: team-a
+///     routes: [fast, smart]
+///     default_route: fast
+///     quota:
+///       max_requests: 10000
+///       max_cost_usd: 25.0
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct GatewayConfig {
+    /// Address to listen on
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Upstream providers, by route name
+    pub routes: HashMap<String, RouteConfig>,
+    /// Keys clients authenticate with
+    pub keys: Vec<KeyConfig>,
+    /// Seconds requests in flight are given to finish on shutdown
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+/// An upstream provider clients can be routed to
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteConfig {
+    /// Provider, model and credentials for the upstream
+    pub llm: LLMConfig,
+    /// Prices used to track the cost of requests; without them requests
+    /// are tracked as free
+    #[serde(default)]
+    pub pricing: Option<Pricing>,
+}
+
+/// List prices of a model
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Pricing {
+    /// US dollars per million prompt tokens
+    pub input_usd_per_million: f64,
+    /// US dollars per million generated tokens
+    pub output_usd_per_million: f64,
+}
+
+impl Pricing {
+    /// Cost in US dollars of a request with the given token counts
+    pub fn cost_usd(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        (input_tokens as f64 * self.input_usd_per_million
+            + output_tokens as f64 * self.output_usd_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// A client key, the routes it may use and its quota
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyConfig {
+    /// Secret sent by the client as `Authorization: Bearer <key>`
+    pub key: String,
+    /// Name of the key's owner, used in usage reports
+    pub name: String,
+    /// Routes the key may use, requested by name in the `model` field
+    pub routes: Vec<String>,
+    /// Route used when a request names no model
+    #[serde(default)]
+    pub default_route: Option<String>,
+    /// Limits on the key's usage
+    #[serde(default)]
+    pub quota: QuotaConfig,
+}
+
+/// Limits on a key's usage within a time window
+///
+/// Unset limits are unlimited. Usage resets when the window ends.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// Maximum requests per window
+    #[serde(default)]
+    pub max_requests: Option<u64>,
+    /// Maximum prompt and generated tokens per window
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Maximum cost in US dollars per window
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// Length of the window in seconds
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_requests: None,
+            max_tokens: None,
+            max_cost_usd: None,
+            window_secs: default_window_secs(),
+        }
+    }
+}
+
+fn default_listen() -> String {
+    "127.0.0.1:8080".to_string()
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_window_secs() -> u64 {
+    24 * 60 * 60
+}
+
+impl GatewayConfig {
+    /// Load and validate a configuration file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or if it is
+    /// invalid (see [`validate`](Self::validate)).
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            AgentError::Config(format!(
+                "Failed to read gateway config '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let config: Self = serde_yaml::from_str(&contents).map_err(|e| {
+            AgentError::Config(format!(
+                "Failed to parse gateway config '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that keys are unique and only refer to configured routes
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - There are no keys, or a key or its name is empty
+    /// - Two keys share a secret or a name
+    /// - A key refers to an unknown route, or has no routes
+    /// - A quota window is zero
+    pub fn validate(&self) -> Result<()> {
+        if self.keys.is_empty() {
+            return Err(AgentError::Config(
+                "Gateway needs at least one key".to_string(),
+            ));
+        }
+
+        let mut secrets = HashSet::new();
+        let mut names = HashSet::new();
+        for key in &self.keys {
+            if key.key.is_empty() || key.name.is_empty() {
+                return Err(AgentError::Config(
+                    "Gateway keys need a key and a name".to_string(),
+                ));
+            }
+            if !secrets.insert(key.key.as_str()) {
+                return Err(AgentError::Config(format!(
+                    "Key of '{}' is used by another key",
+                    key.name
+                )));
+            }
+            // Usage is tracked by name, so keys sharing one would share a quota
+            if !names.insert(key.name.as_str()) {
+                return Err(AgentError::Config(format!(
+                    "Key name '{}' is used by another key",
+                    key.name
+                )));
+            }
+            if key.routes.is_empty() {
+                return Err(AgentError::Config(format!(
+                    "Key '{}' has no routes",
+                    key.name
+                )));
+            }
+            let unknown = key
+                .routes
+                .iter()
+                .chain(&key.default_route)
+                .find(|route| !self.routes.contains_key(*route));
+            if let Some(route) = unknown {
+                return Err(AgentError::Config(format!(
+                    "Key '{}' refers to unknown route '{}'",
+                    key.name, route
+                )));
+            }
+            if key.quota.window_secs == 0 {
+                return Err(AgentError::Config(format!(
+                    "Quota window of key '{}' must be greater than 0",
+                    key.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        routes:
+          fast:
+            llm:
+              provider: ollama
+              model: llama3.2
+              api_key: ""
+            pricing:
+              input_usd_per_million: 0.5
+              output_usd_per_million: 1.5
+        keys:
+          - key: secret-a
+            name: team-a
+            routes: [fast]
+            quota:
+              max_requests: 100
+    "#;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let mut config: GatewayConfig = serde_yaml::from_str(CONFIG).unwrap();
+        assert_eq!(config.listen, "127.0.0.1:8080");
+        assert_eq!(config.shutdown_timeout_secs, 30);
+        assert_eq!(config.keys[0].quota.max_requests, Some(100));
+        assert_eq!(config.keys[0].quota.window_secs, 86_400);
+        let pricing = config.routes["fast"].pricing.unwrap();
+        assert_eq!(pricing.cost_usd(1_000_000, 2_000_000), 3.5);
+        assert!(config.validate().is_ok());
+
+        config.keys[0].default_route = Some("slow".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("unknown route 'slow'"));
+
+        config.keys[0].default_route = None;
+        config.keys.push(config.keys[0].clone());
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("Key of 'team-a' is used by another key"));
+
+        config.keys[1].key = "secret-b".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("Key name 'team-a' is used by another key"));
+
+        config.keys[1].name = "team-b".to_string();
+        assert!(config.validate().is_ok());
+        config.keys[1].quota.window_secs = 0;
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("window of key 'team-b'"));
+    }
+}
@@ -0,0 +1,28 @@
+//! Self-hosted LLM gateway.
+//!
+//! This crate turns the framework's provider layer into a deployable LLM
+//! proxy. A [`Gateway`] serves many upstream providers, configured as named
+//! routes, behind one OpenAI-compatible endpoint:
+//!
+//! - Clients authenticate with a gateway key, never with provider
+//!   credentials
+//! - Each key may use a set of routes, chosen by the request's `model`
+//! - Each key has a [`QuotaConfig`] limiting requests, tokens and cost per
+//!   time window
+//! - Token usage and cost, from each route's [`Pricing`], are tracked per key
+//!   in a [`UsageLedger`]
+//!
+//! The `athena-gateway` binary loads a [`GatewayConfig`] from YAML and
+//! serves it:
+//!
+//! ```bash
+//! athena-gateway --config gateway.yaml
+//! ```
+
+mod config;
+mod quota;
+mod server;
+
+pub use config::{GatewayConfig, KeyConfig, Pricing, QuotaConfig, RouteConfig};
+pub use quota::{KeyUsage, QuotaReservation, UsageLedger};
+pub use server::{COST_HEADER, Gateway};
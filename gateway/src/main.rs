@@ -0,0 +1,57 @@
+//! Gateway binary: serves the routes and keys of a configuration file.
+
+use agent_core::{Shutdown, shutdown_signal};
+use clap::Parser;
+use gateway::{Gateway, GatewayConfig};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Command-line arguments for the gateway
+#[derive(Parser, Debug)]
+#[command(name = "athena-gateway")]
+#[command(about = "LLM gateway serving many providers behind one endpoint", long_about = None)]
+struct Args {
+    /// Path to the gateway configuration file (YAML format)
+    #[arg(short, long)]
+    config: PathBuf,
+
+    /// Address to listen on, overriding the configuration
+    #[arg(short, long)]
+    listen: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut config = GatewayConfig::load(&args.config)?;
+    if let Some(listen) = args.listen {
+        config.listen = listen;
+    }
+
+    let gateway = Gateway::from_config(&config)?;
+    let listener = tokio::net::TcpListener::bind(&config.listen).await?;
+    println!(
+        "Gateway listening on {} with {} routes and {} keys",
+        listener.local_addr()?,
+        config.routes.len(),
+        config.keys.len()
+    );
+
+    let shutdown = Shutdown::new();
+    let mut server = tokio::spawn(gateway.serve_with_shutdown(listener, shutdown.clone()));
+    tokio::select! {
+        result = &mut server => return Ok(result??),
+        _ = shutdown_signal() => {}
+    }
+
+    println!("Shutting down, waiting for requests in flight");
+    let report = shutdown
+        .shutdown(Duration::from_secs(config.shutdown_timeout_secs))
+        .await;
+    if !report.drained {
+        eprintln!("Dropped {} open requests at the deadline", report.cancelled);
+    }
+    server.await??;
+    Ok(())
+}
@@ -0,0 +1,249 @@
+//! Usage accounting and quota enforcement per key.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::QuotaConfig;
+
+/// Usage of one key in its current quota window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KeyUsage {
+    /// When the window started
+    pub window_start: DateTime<Utc>,
+    /// Requests answered
+    pub requests: u64,
+    /// Prompt tokens reported by upstream providers
+    pub input_tokens: u64,
+    /// Generated tokens reported by upstream providers
+    pub output_tokens: u64,
+    /// Cost in US dollars, from the prices of the routes used
+    pub cost_usd: f64,
+}
+
+impl KeyUsage {
+    fn new(window_start: DateTime<Utc>) -> Self {
+        Self {
+            window_start,
+            requests: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd: 0.0,
+        }
+    }
+
+    /// Prompt and generated tokens combined
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// The first limit of `quota` that has been reached, if any
+    pub fn exhausted(&self, quota: &QuotaConfig) -> Option<&'static str> {
+        if quota.max_requests.is_some_and(|max| self.requests >= max) {
+            Some("requests")
+        } else if quota
+            .max_tokens
+            .is_some_and(|max| self.total_tokens() >= max)
+        {
+            Some("tokens")
+        } else if quota.max_cost_usd.is_some_and(|max| self.cost_usd >= max) {
+            Some("cost")
+        } else {
+            None
+        }
+    }
+
+    /// Start a new window if the current one has ended at `now`
+    fn roll(&mut self, quota: &QuotaConfig, now: DateTime<Utc>) {
+        let window = Duration::seconds(quota.window_secs.try_into().unwrap_or(i64::MAX));
+        if now - self.window_start >= window {
+            *self = Self::new(now);
+        }
+    }
+}
+
+/// Usage of every key, by key name
+///
+/// Key names are unique, which [`GatewayConfig::validate`](crate::GatewayConfig::validate)
+/// enforces.
+///
+/// A request takes a slot with [`reserve`](Self::reserve) before it is
+/// forwarded, so concurrent requests from one key can't overshoot the
+/// request limit. Tokens and cost are only known once a request is
+/// answered, so those limits can still be overshot by the requests in
+/// flight when they are reached.
+#[derive(Debug, Default)]
+pub struct UsageLedger {
+    usage: Mutex<HashMap<String, KeyUsage>>,
+}
+
+impl UsageLedger {
+    /// Create an empty ledger
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Usage of a key in its current window at `now`
+    pub fn usage(&self, name: &str, quota: &QuotaConfig, now: DateTime<Utc>) -> KeyUsage {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry(name.to_string())
+            .or_insert_with(|| KeyUsage::new(now));
+        entry.roll(quota, now);
+        *entry
+    }
+
+    /// Check a key's quota at `now` and, if no limit has been reached, count
+    /// a request against it
+    ///
+    /// The request's tokens and cost are added by
+    /// [`QuotaReservation::settle`]; a reservation dropped without settling
+    /// gives its slot back, so failed requests aren't counted.
+    ///
+    /// # Errors
+    /// Returns the first limit that has been reached.
+    pub fn reserve<'a>(
+        &'a self,
+        name: &'a str,
+        quota: &'a QuotaConfig,
+        now: DateTime<Utc>,
+    ) -> Result<QuotaReservation<'a>, &'static str> {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry(name.to_string())
+            .or_insert_with(|| KeyUsage::new(now));
+        entry.roll(quota, now);
+        if let Some(limit) = entry.exhausted(quota) {
+            return Err(limit);
+        }
+        entry.requests += 1;
+        Ok(QuotaReservation {
+            ledger: self,
+            name,
+            quota,
+            window_start: Some(entry.window_start),
+        })
+    }
+
+    /// Record an answered request
+    pub fn record(
+        &self,
+        name: &str,
+        quota: &QuotaConfig,
+        now: DateTime<Utc>,
+        input_tokens: usize,
+        output_tokens: usize,
+        cost_usd: f64,
+    ) {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry(name.to_string())
+            .or_insert_with(|| KeyUsage::new(now));
+        entry.roll(quota, now);
+        entry.requests += 1;
+        entry.input_tokens += input_tokens as u64;
+        entry.output_tokens += output_tokens as u64;
+        entry.cost_usd += cost_usd;
+    }
+}
+
+/// A request counted against a key's quota by [`UsageLedger::reserve`]
+#[derive(Debug)]
+pub struct QuotaReservation<'a> {
+    ledger: &'a UsageLedger,
+    name: &'a str,
+    quota: &'a QuotaConfig,
+    /// Window the request was counted in; `None` once settled
+    window_start: Option<DateTime<Utc>>,
+}
+
+impl QuotaReservation<'_> {
+    /// Add the tokens and cost of the answered request at `now`
+    ///
+    /// If the window the request was counted in has ended, they go to the
+    /// current window.
+    pub fn settle(
+        mut self,
+        now: DateTime<Utc>,
+        input_tokens: usize,
+        output_tokens: usize,
+        cost_usd: f64,
+    ) {
+        self.window_start = None;
+        let mut usage = self.ledger.usage.lock().unwrap();
+        let entry = usage
+            .entry(self.name.to_string())
+            .or_insert_with(|| KeyUsage::new(now));
+        entry.roll(self.quota, now);
+        entry.input_tokens += input_tokens as u64;
+        entry.output_tokens += output_tokens as u64;
+        entry.cost_usd += cost_usd;
+    }
+}
+
+impl Drop for QuotaReservation<'_> {
+    fn drop(&mut self) {
+        let Some(window_start) = self.window_start else {
+            return;
+        };
+        let mut usage = self.ledger.usage.lock().unwrap();
+        if let Some(entry) = usage.get_mut(self.name)
+            && entry.window_start == window_start
+        {
+            entry.requests = entry.requests.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_apply_within_window() {
+        let quota = QuotaConfig {
+            max_requests: Some(2),
+            max_tokens: Some(1_000),
+            max_cost_usd: None,
+            window_secs: 60,
+        };
+        let ledger = UsageLedger::new();
+        let start = Utc::now();
+
+        ledger.record("a", &quota, start, 100, 50, 0.01);
+        assert_eq!(ledger.usage("a", &quota, start).exhausted(&quota), None);
+        ledger.record("a", &quota, start, 100, 50, 0.01);
+        let usage = ledger.usage("a", &quota, start);
+        assert_eq!(usage.exhausted(&quota), Some("requests"));
+        assert_eq!(usage.total_tokens(), 300);
+        assert!((usage.cost_usd - 0.02).abs() < 1e-9);
+
+        // Other keys and later windows start fresh
+        assert_eq!(ledger.usage("b", &quota, start).requests, 0);
+        let later = start + Duration::seconds(60);
+        assert_eq!(ledger.usage("a", &quota, later).requests, 0);
+    }
+
+    #[test]
+    fn test_reservations_hold_request_slots() {
+        let quota = QuotaConfig {
+            max_requests: Some(1),
+            ..QuotaConfig::default()
+        };
+        let ledger = UsageLedger::new();
+        let now = Utc::now();
+
+        let first = ledger.reserve("a", &quota, now).unwrap();
+        assert_eq!(ledger.reserve("a", &quota, now).unwrap_err(), "requests");
+
+        // A dropped reservation gives its slot back; a settled one keeps it
+        drop(first);
+        let second = ledger.reserve("a", &quota, now).unwrap();
+        second.settle(now, 100, 50, 0.01);
+        let usage = ledger.usage("a", &quota, now);
+        assert_eq!(usage.requests, 1);
+        assert_eq!(usage.total_tokens(), 150);
+        assert_eq!(ledger.reserve("a", &quota, now).unwrap_err(), "requests");
+    }
+}
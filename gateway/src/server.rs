@@ -0,0 +1,607 @@
+//! The HTTP server: authentication, routing, quotas and cost tracking.
+
+use agent_core::{AgentError, ErrorAuditRecord, ErrorSanitizer, Message, Result, Shutdown};
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use llm::{FinishReason, LLMProvider, create_provider};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::TcpListener;
+
+use crate::{GatewayConfig, KeyConfig, KeyUsage, Pricing, QuotaConfig, UsageLedger};
+
+/// Response header with the cost of the request in US dollars
+pub const COST_HEADER: &str = "x-gateway-cost-usd";
+
+/// An upstream provider and its prices
+struct Route {
+    provider: Box<dyn LLMProvider>,
+    pricing: Option<Pricing>,
+}
+
+/// An LLM proxy that serves many upstream providers behind one endpoint
+///
+/// Clients call the OpenAI-compatible `POST /v1/chat/completions` with
+/// `Authorization: Bearer <key>`. The `model` field names one of the key's
+/// routes; requests without one go to the key's default route. Each key has
+/// a [`QuotaConfig`], and the tokens and cost of every request are recorded
+/// in a [`UsageLedger`], which clients read from `GET /v1/usage`.
+/// `GET /v1/models` lists the routes a key may use.
+///
+/// Upstream failures are answered with a fixed message and a reference from
+/// an [`ErrorSanitizer`]; the details go to its audit sink, which by default
+/// writes them to stderr.
+///
+/// # Example
+///
+/// ```no_run
+/// use gateway::{Gateway, GatewayConfig};
+/// use std::path::Path;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let config = GatewayConfig::load(Path::new("gateway.yaml"))?;
+/// let listener = tokio::net::TcpListener::bind(&config.listen).await?;
+/// Gateway::from_config(&config)?.serve(listener).await
+/// # }
+/// ```
+pub struct Gateway {
+    routes: HashMap<String, Route>,
+    keys: HashMap<String, KeyConfig>,
+    ledger: UsageLedger,
+    sanitizer: ErrorSanitizer,
+    next_id: AtomicU64,
+}
+
+impl Gateway {
+    /// Create a gateway with no routes or keys
+    pub fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+            keys: HashMap::new(),
+            ledger: UsageLedger::new(),
+            sanitizer: ErrorSanitizer::new().with_audit_sink(log_upstream_error),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Create a gateway with a provider for every configured route
+    ///
+    /// # Errors
+    /// Returns an error if the configuration is invalid or a provider
+    /// cannot be created.
+    pub fn from_config(config: &GatewayConfig) -> Result<Self> {
+        config.validate()?;
+        let mut gateway = Self::new();
+        for (name, route) in &config.routes {
+            let provider = create_provider(&route.llm)
+                .map_err(|e| AgentError::Config(format!("Route '{}': {}", name, e)))?;
+            gateway.routes.insert(
+                name.clone(),
+                Route {
+                    provider,
+                    pricing: route.pricing,
+                },
+            );
+        }
+        for key in &config.keys {
+            gateway = gateway.with_key(key.clone());
+        }
+        Ok(gateway)
+    }
+
+    /// Add or replace a route
+    pub fn with_route(
+        mut self,
+        name: impl Into<String>,
+        provider: impl LLMProvider + 'static,
+        pricing: Option<Pricing>,
+    ) -> Self {
+        let provider = Box::new(provider);
+        self.routes.insert(name.into(), Route { provider, pricing });
+        self
+    }
+
+    /// Add or replace a client key
+    pub fn with_key(mut self, key: KeyConfig) -> Self {
+        self.keys.insert(key.key.clone(), key);
+        self
+    }
+
+    /// Set how upstream errors are hidden from clients and where their
+    /// details are recorded
+    pub fn with_error_sanitizer(mut self, sanitizer: ErrorSanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// Usage recorded so far
+    pub fn ledger(&self) -> &UsageLedger {
+        &self.ledger
+    }
+
+    /// The HTTP routes of the gateway
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/v1/chat/completions", post(chat_completions))
+            .route("/v1/models", get(models))
+            .route("/v1/usage", get(usage))
+            .with_state(Arc::new(self))
+    }
+
+    /// Serve requests on `listener` until the server fails
+    ///
+    /// # Errors
+    /// Returns an error if the server stops with an I/O error.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        axum::serve(listener, self.router())
+            .await
+            .map_err(|e| AgentError::Execution(format!("Gateway server failed: {}", e)))
+    }
+
+    /// Serve requests on `listener` until `shutdown` completes
+    ///
+    /// Once shutdown begins the gateway stops accepting connections and
+    /// returns when the requests in flight have been answered. Requests
+    /// still open at the shutdown deadline are dropped, along with their
+    /// upstream calls.
+    ///
+    /// # Errors
+    /// Returns an error if shutdown has already begun, or if the server
+    /// stops with an I/O error.
+    pub async fn serve_with_shutdown(
+        self,
+        listener: TcpListener,
+        shutdown: Shutdown,
+    ) -> Result<()> {
+        let _work = shutdown.start_work()?;
+        let cancel = shutdown.cancellation_token();
+        let server =
+            axum::serve(listener, self.router()).with_graceful_shutdown(shutdown.draining());
+        tokio::select! {
+            result = server => {
+                result.map_err(|e| AgentError::Execution(format!("Gateway server failed: {}", e)))
+            }
+            _ = cancel.cancelled() => Ok(()),
+        }
+    }
+
+    /// The key in the request's `Authorization` header
+    fn authenticate(&self, headers: &HeaderMap) -> std::result::Result<&KeyConfig, ApiError> {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|secret| self.keys.get(secret.trim()))
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "invalid_api_key",
+                    "Missing or unknown API key",
+                )
+            })
+    }
+}
+
+impl Default for Gateway {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// OpenAI-compatible chat completion request; other fields are ignored
+#[derive(Debug, Deserialize)]
+struct ChatRequest {
+    #[serde(default)]
+    model: Option<String>,
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+/// An error in the OpenAI error format
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, kind: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({"error": {"message": self.message, "type": self.kind}});
+        (self.status, Json(body)).into_response()
+    }
+}
+
+async fn chat_completions(
+    State(gateway): State<Arc<Gateway>>,
+    headers: HeaderMap,
+    Json(request): Json<ChatRequest>,
+) -> std::result::Result<Response, ApiError> {
+    let key = gateway.authenticate(&headers)?;
+
+    let route_name = match request.model.as_deref() {
+        Some(requested) if key.routes.iter().any(|route| route == requested) => requested,
+        Some(requested) => {
+            return Err(ApiError::new(
+                StatusCode::NOT_FOUND,
+                "model_not_found",
+                format!("Model '{}' is not available for this key", requested),
+            ));
+        }
+        None => key.default_route.as_deref().ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                "model_not_found",
+                "No model was given and this key has no default route",
+            )
+        })?,
+    };
+    let route = gateway.routes.get(route_name).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "model_not_found",
+            format!("Route '{}' is not configured", route_name),
+        )
+    })?;
+
+    let messages = request
+        .messages
+        .into_iter()
+        .map(|message| match message.role.as_str() {
+            "system" | "developer" => Ok(Message::system(message.content)),
+            "user" => Ok(Message::user(message.content)),
+            "assistant" => Ok(Message::assistant(message.content)),
+            other => Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                format!("Unsupported message role '{}'", other),
+            )),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    // The slot is given back if the upstream call fails or is dropped
+    let now = Utc::now();
+    let reservation = gateway
+        .ledger
+        .reserve(&key.name, &key.quota, now)
+        .map_err(|limit| {
+            ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "insufficient_quota",
+                format!("Quota exceeded: {} limit reached for this window", limit),
+            )
+        })?;
+
+    let completion = route
+        .provider
+        .send_completion(&messages)
+        .await
+        .map_err(|e| {
+            let error = gateway.sanitizer.sanitize(&e);
+            ApiError::new(StatusCode::BAD_GATEWAY, "upstream_error", error.to_string())
+        })?;
+
+    let usage = completion.usage.unwrap_or_default();
+    let cost_usd = route
+        .pricing
+        .map(|pricing| pricing.cost_usd(usage.input_tokens, usage.output_tokens))
+        .unwrap_or(0.0);
+    reservation.settle(Utc::now(), usage.input_tokens, usage.output_tokens, cost_usd);
+
+    let finish_reason = match &completion.finish_reason {
+        FinishReason::Stop => Some("stop"),
+        FinishReason::Length => Some("length"),
+        FinishReason::ToolUse => Some("tool_calls"),
+        FinishReason::ContentFilter => Some("content_filter"),
+        FinishReason::Other(reason) => Some(reason.as_str()),
+        FinishReason::Unknown => None,
+    };
+    let body = json!({
+        "id": format!("chatcmpl-gw-{}", gateway.next_id.fetch_add(1, Ordering::Relaxed)),
+        "object": "chat.completion",
+        "created": now.timestamp(),
+        "model": completion.model.as_deref().unwrap_or(route_name),
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": completion.text},
+            "finish_reason": finish_reason
+        }],
+        "usage": {
+            "prompt_tokens": usage.input_tokens,
+            "completion_tokens": usage.output_tokens,
+            "total_tokens": usage.total()
+        }
+    });
+
+    let mut response = Json(body).into_response();
+    if let Ok(value) = HeaderValue::from_str(&format!("{:.6}", cost_usd)) {
+        response.headers_mut().insert(COST_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// Default audit sink: upstream error details stay in the server's log
+fn log_upstream_error(record: &ErrorAuditRecord) {
+    eprintln!(
+        "Upstream request failed ({}): {}",
+        record.reference, record.detail
+    );
+}
+
+async fn models(
+    State(gateway): State<Arc<Gateway>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<serde_json::Value>, ApiError> {
+    let key = gateway.authenticate(&headers)?;
+    let data: Vec<_> = key
+        .routes
+        .iter()
+        .map(|route| json!({"id": route, "object": "model", "owned_by": "gateway"}))
+        .collect();
+    Ok(Json(json!({"object": "list", "data": data})))
+}
+
+/// A key's usage and quota, as returned by `GET /v1/usage`
+#[derive(Debug, Serialize)]
+struct UsageReport<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    usage: KeyUsage,
+    quota: &'a QuotaConfig,
+}
+
+async fn usage(
+    State(gateway): State<Arc<Gateway>>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, ApiError> {
+    let key = gateway.authenticate(&headers)?;
+    let report = UsageReport {
+        name: &key.name,
+        usage: gateway.ledger.usage(&key.name, &key.quota, Utc::now()),
+        quota: &key.quota,
+    };
+    Ok(Json(report).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use llm::{CompletionResponse, TokenUsage};
+    use serde_json::Value;
+
+    /// Replies with the route name and 1,000 input / 500 output tokens
+    struct Echo(&'static str);
+
+    #[async_trait]
+    impl LLMProvider for Echo {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(self.send_completion(messages).await?.text)
+        }
+
+        async fn send_completion(&self, _messages: &[Message]) -> Result<CompletionResponse> {
+            let usage = TokenUsage {
+                input_tokens: 1_000,
+                output_tokens: 500,
+            };
+            Ok(CompletionResponse::new(self.0, FinishReason::Stop).with_usage(usage))
+        }
+    }
+
+    /// Fails with an error quoting a credential and an internal host
+    struct Broken;
+
+    #[async_trait]
+    impl LLMProvider for Broken {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            Err(AgentError::LLMProvider(
+                "HTTP 500 from upstream.internal:8443 with key sk-live-1234".to_string(),
+            ))
+        }
+    }
+
+    /// Answers after a delay, signalling when a request arrives
+    struct Slow(Arc<tokio::sync::Notify>);
+
+    #[async_trait]
+    impl LLMProvider for Slow {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            self.0.notify_one();
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            Ok("slow".to_string())
+        }
+    }
+
+    fn key(secret: &str, routes: &[&str], max_requests: Option<u64>) -> KeyConfig {
+        KeyConfig {
+            key: secret.to_string(),
+            name: secret.trim_start_matches("secret-").to_string(),
+            routes: routes.iter().map(|route| route.to_string()).collect(),
+            default_route: routes.first().map(|route| route.to_string()),
+            quota: QuotaConfig {
+                max_requests,
+                ..QuotaConfig::default()
+            },
+        }
+    }
+
+    async fn start() -> String {
+        let pricing = Pricing {
+            input_usd_per_million: 2.0,
+            output_usd_per_million: 10.0,
+        };
+        let gateway = Gateway::new()
+            .with_route("fast", Echo("fast"), Some(pricing))
+            .with_route("smart", Echo("smart"), None)
+            .with_key(key("secret-a", &["fast", "smart"], Some(2)))
+            .with_key(key("secret-b", &["smart"], None));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(gateway.serve(listener));
+        url
+    }
+
+    async fn chat(url: &str, secret: &str, model: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/v1/chat/completions", url))
+            .bearer_auth(secret)
+            .json(&json!({"model": model, "messages": [{"role": "user", "content": "Hi"}]}))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_answers_requests_in_flight() {
+        let arrived = Arc::new(tokio::sync::Notify::new());
+        let gateway = Gateway::new()
+            .with_route("slow", Slow(arrived.clone()), None)
+            .with_key(key("secret-a", &["slow"], None));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let shutdown = Shutdown::new();
+        let server = tokio::spawn(gateway.serve_with_shutdown(listener, shutdown.clone()));
+
+        let request = tokio::spawn(async move { chat(&url, "secret-a", "slow").await.status() });
+        arrived.notified().await;
+        let report = shutdown.shutdown(std::time::Duration::from_secs(5)).await;
+
+        assert!(report.drained);
+        assert_eq!(request.await.unwrap(), 200);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_routed_per_key() {
+        let url = start().await;
+
+        let response = chat(&url, "secret-a", "smart").await;
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], "smart");
+        assert_eq!(body["usage"]["total_tokens"], 1_500);
+
+        // Requests without a model go to the key's default route; keys
+        // cannot use models they were not given
+        let body: Value = reqwest::Client::new()
+            .post(format!("{}/v1/chat/completions", url))
+            .bearer_auth("secret-a")
+            .json(&json!({"messages": [{"role": "user", "content": "Hi"}]}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], "fast");
+        for (secret, model) in [("secret-a", "gpt-4o"), ("secret-b", "fast")] {
+            let response = chat(&url, secret, model).await;
+            assert_eq!(response.status(), 404);
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["error"]["type"], "model_not_found");
+        }
+
+        assert_eq!(chat(&url, "secret-x", "fast").await.status(), 401);
+    }
+
+    #[tokio::test]
+    async fn test_quota_and_cost_are_tracked() {
+        let url = start().await;
+
+        let response = chat(&url, "secret-a", "fast").await;
+        // 1,000 input tokens at $2/M and 500 output tokens at $10/M
+        assert_eq!(response.headers()[COST_HEADER], "0.007000");
+        assert_eq!(chat(&url, "secret-a", "smart").await.status(), 200);
+
+        let response = chat(&url, "secret-a", "fast").await;
+        assert_eq!(response.status(), 429);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["type"], "insufficient_quota");
+
+        let usage: Value = reqwest::Client::new()
+            .get(format!("{}/v1/usage", url))
+            .bearer_auth("secret-a")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(usage["name"], "a");
+        assert_eq!(usage["requests"], 2);
+        assert_eq!(usage["input_tokens"], 2_000);
+        assert_eq!(usage["cost_usd"], 0.007);
+        assert_eq!(usage["quota"]["max_requests"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_quota_slots_are_taken_before_forwarding() {
+        let arrived = Arc::new(tokio::sync::Notify::new());
+        let gateway = Gateway::new()
+            .with_route("slow", Slow(arrived.clone()), None)
+            .with_key(key("secret-a", &["slow"], Some(1)));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(gateway.serve(listener));
+
+        let first = {
+            let url = url.clone();
+            tokio::spawn(async move { chat(&url, "secret-a", "slow").await.status() })
+        };
+        arrived.notified().await;
+        assert_eq!(chat(&url, "secret-a", "slow").await.status(), 429);
+        assert_eq!(first.await.unwrap(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_errors_are_sanitized() {
+        let audit_log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = audit_log.clone();
+        let sanitizer = ErrorSanitizer::new()
+            .with_audit_sink(move |record| log.lock().unwrap().push(record.clone()));
+        let gateway = Gateway::new()
+            .with_route("broken", Broken, None)
+            .with_key(key("secret-a", &["broken"], Some(1)))
+            .with_error_sanitizer(sanitizer);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(gateway.serve(listener));
+
+        let response = chat(&url, "secret-a", "broken").await;
+        assert_eq!(response.status(), 502);
+        let body: Value = response.json().await.unwrap();
+        let message = body["error"]["message"].as_str().unwrap();
+        assert!(!message.contains("upstream.internal"));
+        assert!(!message.contains("sk-live"));
+
+        let record = audit_log.lock().unwrap()[0].clone();
+        assert!(message.contains(record.reference.as_str()));
+        assert!(record.detail.contains("upstream.internal"));
+
+        // Failed requests don't use up the quota
+        assert_eq!(chat(&url, "secret-a", "broken").await.status(), 502);
+    }
+}
@@ -115,7 +115,7 @@ impl Guardrail for FilePathGuardrail {
     }
 
     fn validate(&self, plan: &Plan) -> Result<()> {
-        for step in &plan.steps {
+        for step in plan.all_steps() {
             if let Step::ToolCall(tool_call) = step {
                 // Only validate file_reader tool calls
                 if tool_call.tool_name == "file_reader" {
@@ -0,0 +1,208 @@
+use agent_core::{AgentError, Result};
+use planner::{Plan, Step};
+use whatlang::Lang;
+use crate::Guardrail;
+
+/// ISO 639-1 codes for the languages the detector supports.
+///
+/// Locales are usually written with two-letter language subtags (`de-AT`,
+/// `pt-BR`), while the detector reports ISO 639-3 languages.
+const ISO_639_1: &[(&str, Lang)] = &[
+    ("af", Lang::Afr), ("ak", Lang::Aka), ("am", Lang::Amh), ("ar", Lang::Ara),
+    ("az", Lang::Aze), ("be", Lang::Bel), ("bg", Lang::Bul), ("bn", Lang::Ben),
+    ("ca", Lang::Cat), ("cs", Lang::Ces), ("da", Lang::Dan), ("de", Lang::Deu),
+    ("el", Lang::Ell), ("en", Lang::Eng), ("eo", Lang::Epo), ("es", Lang::Spa),
+    ("et", Lang::Est), ("fa", Lang::Pes), ("fi", Lang::Fin), ("fr", Lang::Fra),
+    ("gu", Lang::Guj), ("he", Lang::Heb), ("hi", Lang::Hin), ("hr", Lang::Hrv),
+    ("hu", Lang::Hun), ("hy", Lang::Hye), ("id", Lang::Ind), ("it", Lang::Ita),
+    ("ja", Lang::Jpn), ("jv", Lang::Jav), ("ka", Lang::Kat), ("km", Lang::Khm),
+    ("kn", Lang::Kan), ("ko", Lang::Kor), ("la", Lang::Lat), ("lt", Lang::Lit),
+    ("lv", Lang::Lav), ("mk", Lang::Mkd), ("ml", Lang::Mal), ("mr", Lang::Mar),
+    ("my", Lang::Mya), ("nb", Lang::Nob), ("ne", Lang::Nep), ("nl", Lang::Nld),
+    ("no", Lang::Nob), ("or", Lang::Ori), ("pa", Lang::Pan), ("pl", Lang::Pol),
+    ("pt", Lang::Por), ("ro", Lang::Ron), ("ru", Lang::Rus), ("si", Lang::Sin),
+    ("sk", Lang::Slk), ("sl", Lang::Slv), ("sn", Lang::Sna), ("sr", Lang::Srp),
+    ("sv", Lang::Swe), ("ta", Lang::Tam), ("te", Lang::Tel), ("th", Lang::Tha),
+    ("tk", Lang::Tuk), ("tl", Lang::Tgl), ("tr", Lang::Tur), ("uk", Lang::Ukr),
+    ("ur", Lang::Urd), ("uz", Lang::Uzb), ("vi", Lang::Vie), ("yi", Lang::Yid),
+    ("zh", Lang::Cmn), ("zu", Lang::Zul),
+];
+
+/// Default minimum text length, in characters, worth checking
+const DEFAULT_MIN_CHARS: usize = 20;
+
+/// Default detector confidence needed to reject a text
+const DEFAULT_MIN_CONFIDENCE: f64 = 0.3;
+
+/// Guardrail that keeps responses in the user's language.
+///
+/// Detects the language of every response step and rejects the plan when
+/// the text is confidently in a different language than the configured
+/// locale. Short texts and texts the detector is unsure about (names,
+/// numbers, code) are let through.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use guardrails::LanguageGuardrail;
+///
+/// let guardrail = LanguageGuardrail::new("de-AT")?;
+///
+/// // This will fail if a response step is not written in German
+/// guardrail.validate(&plan)?;
+/// ```
+pub struct LanguageGuardrail {
+    locale: String,
+    expected: Lang,
+    min_chars: usize,
+    min_confidence: f64,
+}
+
+impl LanguageGuardrail {
+    /// Creates a new LanguageGuardrail for a BCP 47 locale such as `fr` or
+    /// `pt-BR`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Config` if the locale's language cannot be
+    /// detected.
+    pub fn new(locale: impl Into<String>) -> Result<Self> {
+        let locale = locale.into();
+        let expected = language_of(&locale).ok_or_else(|| {
+            AgentError::Config(format!(
+                "Language detection is not supported for locale '{}'",
+                locale
+            ))
+        })?;
+        Ok(Self {
+            locale,
+            expected,
+            min_chars: DEFAULT_MIN_CHARS,
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+        })
+    }
+
+    /// Only checks texts with at least `min_chars` characters.
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+
+    /// Only rejects texts detected with at least this confidence (0.0 to 1.0).
+    ///
+    /// Lower values catch more mismatches in short texts at the cost of
+    /// occasional false positives between closely related languages.
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// The locale responses must be written in.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Checks that a single text is written in the expected language.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::GuardrailViolation` if the text is confidently
+    /// detected as another language.
+    pub fn check_text(&self, text: &str) -> Result<()> {
+        if text.trim().chars().count() < self.min_chars {
+            return Ok(());
+        }
+        match whatlang::detect(text) {
+            Some(info)
+                if info.lang() != self.expected && info.confidence() >= self.min_confidence =>
+            {
+                Err(AgentError::GuardrailViolation(format!(
+                    "Response is in {} but the session locale is '{}' ({})",
+                    info.lang().eng_name(),
+                    self.locale,
+                    self.expected.eng_name()
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Guardrail for LanguageGuardrail {
+    fn name(&self) -> &str {
+        "language"
+    }
+
+    fn validate(&self, plan: &Plan) -> Result<()> {
+        plan.all_steps()
+            .into_iter()
+            .filter_map(|step| match step {
+                Step::Response { text } => Some(text),
+                _ => None,
+            })
+            .try_for_each(|text| self.check_text(text))
+    }
+}
+
+/// Detectable language of a locale's primary language subtag
+fn language_of(locale: &str) -> Option<Lang> {
+    let primary = locale
+        .split(['-', '_'])
+        .next()?
+        .to_ascii_lowercase();
+    ISO_639_1
+        .iter()
+        .find(|(code, _)| *code == primary)
+        .map(|(_, lang)| *lang)
+        .or_else(|| Lang::from_code(primary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(response: &str) -> Plan {
+        Plan::new(
+            vec![Step::Response {
+                text: response.to_string(),
+            }],
+            "Answer".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_locale_parsing() {
+        assert_eq!(LanguageGuardrail::new("de-AT").unwrap().expected, Lang::Deu);
+        assert_eq!(LanguageGuardrail::new("pt_BR").unwrap().expected, Lang::Por);
+        assert_eq!(LanguageGuardrail::new("zh-Hans").unwrap().expected, Lang::Cmn);
+        assert_eq!(LanguageGuardrail::new("spa").unwrap().expected, Lang::Spa);
+        assert!(matches!(
+            LanguageGuardrail::new("tlh"),
+            Err(AgentError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_matching_language_passes() {
+        let guardrail = LanguageGuardrail::new("fr-FR").unwrap();
+        let text = "Bonjour ! Le rendez-vous est confirmé pour demain matin à neuf heures.";
+        assert!(guardrail.validate(&plan(text)).is_ok());
+    }
+
+    #[test]
+    fn test_other_language_is_rejected() {
+        let guardrail = LanguageGuardrail::new("fr-FR").unwrap();
+        let text = "Hello! Your appointment is confirmed for tomorrow morning at nine o'clock.";
+        let result = guardrail.validate(&plan(text));
+        assert!(matches!(
+            result,
+            Err(AgentError::GuardrailViolation(msg)) if msg.contains("English")
+        ));
+    }
+
+    #[test]
+    fn test_short_text_is_not_checked() {
+        let guardrail = LanguageGuardrail::new("ja").unwrap();
+        assert!(guardrail.check_text("OK, 42").is_ok());
+    }
+}
@@ -10,6 +10,9 @@
 //! - **GuardrailRegistry**: Manages multiple guardrails and validates plans against all of them
 //! - **FilePathGuardrail**: Restricts file operations to allowed directories
 //! - **RateLimitGuardrail**: Enforces limits on API calls per time period
+//! - **LanguageGuardrail**: Keeps responses in the session's language
+//! - **TurnModerator**: Blocks user messages and model responses by category
+//!   score, with override tokens for human review of blocked turns
 //!
 //! # Architecture
 //!
@@ -49,6 +52,39 @@
 //! let guardrail = RateLimitGuardrail::new(100);
 //! ```
 //!
+//! ## LanguageGuardrail
+//!
+//! Detects the language of response steps and rejects plans that answer in
+//! a different language than the session locale, for multilingual
+//! deployments that must reply in the user's language.
+//!
+//! ```rust,ignore
+//! use guardrails::LanguageGuardrail;
+//!
+//! // Responses must be in Brazilian Portuguese
+//! let guardrail = LanguageGuardrail::new("pt-BR")?;
+//! ```
+//!
+//! # Moderation
+//!
+//! Guardrails check plans; a `TurnModerator` checks the messages themselves.
+//! A blocked turn comes back as a `BlockedTurn` carrying every category
+//! score and an override token rather than a bare error, so the hosting
+//! application can queue it for review. Once a reviewer approves the token,
+//! the same content can be resubmitted with it and is let through once.
+//!
+//! ```rust,ignore
+//! use guardrails::{ModerationDecision, TurnDirection, TurnModerator};
+//!
+//! let moderation = TurnModerator::new(my_moderator).with_threshold("violence", 0.8);
+//! if let ModerationDecision::Blocked(blocked) = moderation.check(TurnDirection::Input, text)? {
+//!     review_queue.push(blocked);
+//! }
+//! // Later, after review
+//! moderation.approve(&token)?;
+//! moderation.check_with_override(TurnDirection::Input, text, &token)?;
+//! ```
+//!
 //! # Complete Example
 //!
 //! ```rust,ignore
@@ -114,8 +150,15 @@ mod guardrail;
 mod registry;
 mod file_path;
 mod rate_limit;
+mod language;
+mod moderation;
 
 pub use guardrail::Guardrail;
 pub use registry::GuardrailRegistry;
 pub use file_path::FilePathGuardrail;
 pub use rate_limit::RateLimitGuardrail;
+pub use language::LanguageGuardrail;
+pub use moderation::{
+    AppealStatus, BlockedTurn, KeywordModerator, ModerationDecision, Moderator, OverrideToken,
+    TurnDirection, TurnModerator,
+};
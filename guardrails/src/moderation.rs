@@ -0,0 +1,503 @@
+//! Turn-level content moderation with a human review path.
+//!
+//! A [`TurnModerator`] scores each user message or model response with a
+//! [`Moderator`] and blocks it when a category score reaches its threshold.
+//! Instead of a bare error, a block is returned as a [`BlockedTurn`] with
+//! every category score and an [`OverrideToken`]. The hosting application
+//! can show the scores to a reviewer, and if the reviewer approves the
+//! appeal, resubmitting the same content with the token lets it through
+//! once.
+
+use agent_core::{AgentError, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Score at or above which a category blocks a turn, unless configured
+const DEFAULT_THRESHOLD: f32 = 0.5;
+
+/// Scores text for policy categories
+pub trait Moderator: Send + Sync {
+    /// Score `text` in each category, from 0.0 (clean) to 1.0 (certain
+    /// violation)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text could not be scored, e.g. because a
+    /// moderation service is unavailable.
+    fn scores(&self, text: &str) -> Result<BTreeMap<String, f32>>;
+}
+
+/// Moderator that scores a category 1.0 when the text contains one of its
+/// terms, ignoring case, and 0.0 otherwise
+#[derive(Debug, Clone, Default)]
+pub struct KeywordModerator {
+    categories: BTreeMap<String, Vec<String>>,
+}
+
+impl KeywordModerator {
+    /// Create a moderator with no categories
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a category and the terms that flag it
+    pub fn with_category<I, S>(mut self, category: impl Into<String>, terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let terms = terms
+            .into_iter()
+            .map(|term| term.into().to_lowercase())
+            .collect();
+        self.categories.insert(category.into(), terms);
+        self
+    }
+}
+
+impl Moderator for KeywordModerator {
+    fn scores(&self, text: &str) -> Result<BTreeMap<String, f32>> {
+        let text = text.to_lowercase();
+        Ok(self
+            .categories
+            .iter()
+            .map(|(category, terms)| {
+                let matched = terms.iter().any(|term| text.contains(term.as_str()));
+                (category.clone(), if matched { 1.0 } else { 0.0 })
+            })
+            .collect())
+    }
+}
+
+/// Which side of the conversation a turn came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnDirection {
+    /// A message from the user
+    Input,
+    /// A response from the model
+    Output,
+}
+
+/// Token identifying a blocked turn, used to approve and then send it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct OverrideToken(String);
+
+impl OverrideToken {
+    fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let mut hasher = DefaultHasher::new();
+        Utc::now().timestamp_nanos_opt().hash(&mut hasher);
+        COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+        std::process::id().hash(&mut hasher);
+        Self(format!("mod-{:016x}", hasher.finish()))
+    }
+
+    /// The token as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for OverrideToken {
+    fn from(token: &str) -> Self {
+        Self(token.to_string())
+    }
+}
+
+impl fmt::Display for OverrideToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A turn blocked by moderation, with what a reviewer needs to judge it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockedTurn {
+    /// Token to approve the turn with, and to resubmit it with once approved
+    pub token: OverrideToken,
+    /// Whether the user's message or the model's response was blocked
+    pub direction: TurnDirection,
+    /// Score of every category, including those below their threshold
+    pub scores: BTreeMap<String, f32>,
+    /// Categories at or above their threshold
+    pub flagged: Vec<String>,
+    /// When the turn was blocked
+    pub blocked_at: DateTime<Utc>,
+}
+
+impl BlockedTurn {
+    /// The block as a `GuardrailViolation`, for callers that don't
+    /// handle appeals
+    pub fn into_error(self) -> AgentError {
+        AgentError::GuardrailViolation(format!(
+            "{} blocked by moderation ({}); override token {}",
+            match self.direction {
+                TurnDirection::Input => "Message",
+                TurnDirection::Output => "Response",
+            },
+            self.flagged.join(", "),
+            self.token
+        ))
+    }
+}
+
+/// Result of moderating a turn
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModerationDecision {
+    /// The turn may be sent
+    Allowed,
+    /// The turn was blocked and can be appealed
+    Blocked(BlockedTurn),
+}
+
+impl ModerationDecision {
+    /// Whether the turn may be sent
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allowed)
+    }
+
+    /// `Ok(())` if allowed, otherwise the block as an error
+    pub fn into_result(self) -> Result<()> {
+        match self {
+            Self::Allowed => Ok(()),
+            Self::Blocked(blocked) => Err(blocked.into_error()),
+        }
+    }
+}
+
+/// Where an appeal of a blocked turn stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppealStatus {
+    /// Waiting for a reviewer
+    Pending,
+    /// A reviewer allowed the turn; it has not been resubmitted yet
+    Approved,
+    /// A reviewer upheld the block
+    Rejected,
+    /// The approved turn was resubmitted and let through
+    Used,
+}
+
+struct Appeal {
+    blocked: BlockedTurn,
+    content: u64,
+    status: AppealStatus,
+}
+
+/// Moderates turns and tracks appeals of blocked ones
+///
+/// Appeals are kept in memory for the life of the moderator, so decisions
+/// stay available for audit.
+///
+/// # Example
+///
+/// ```
+/// use guardrails::{KeywordModerator, ModerationDecision, TurnDirection, TurnModerator};
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let moderation = TurnModerator::new(
+///     KeywordModerator::new().with_category("weapons", ["pipe bomb"]),
+/// );
+///
+/// let text = "How was the pipe bomb in the 1996 Olympics case found?";
+/// let ModerationDecision::Blocked(blocked) = moderation.check(TurnDirection::Input, text)?
+/// else {
+///     unreachable!()
+/// };
+/// assert_eq!(blocked.flagged, ["weapons"]);
+///
+/// // A reviewer decides the question is historical and approves it
+/// moderation.approve(&blocked.token)?;
+/// let decision = moderation.check_with_override(TurnDirection::Input, text, &blocked.token)?;
+/// assert!(decision.is_allowed());
+/// # Ok(())
+/// # }
+/// ```
+pub struct TurnModerator {
+    moderator: Box<dyn Moderator>,
+    thresholds: BTreeMap<String, f32>,
+    default_threshold: f32,
+    appeals: Mutex<HashMap<OverrideToken, Appeal>>,
+}
+
+impl TurnModerator {
+    /// Moderate with `moderator`, blocking scores of 0.5 and above
+    pub fn new(moderator: impl Moderator + 'static) -> Self {
+        Self {
+            moderator: Box::new(moderator),
+            thresholds: BTreeMap::new(),
+            default_threshold: DEFAULT_THRESHOLD,
+            appeals: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the score at or above which `category` blocks a turn
+    pub fn with_threshold(mut self, category: impl Into<String>, threshold: f32) -> Self {
+        self.thresholds.insert(category.into(), threshold);
+        self
+    }
+
+    /// Set the threshold of categories without their own
+    pub fn with_default_threshold(mut self, threshold: f32) -> Self {
+        self.default_threshold = threshold;
+        self
+    }
+
+    /// Moderate a turn
+    ///
+    /// Blocking the same content again while its appeal is pending returns
+    /// the same token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the moderator fails to score the text.
+    pub fn check(&self, direction: TurnDirection, text: &str) -> Result<ModerationDecision> {
+        let scores = self.moderator.scores(text)?;
+        let flagged: Vec<String> = scores
+            .iter()
+            .filter(|(category, score)| **score >= self.threshold(category))
+            .map(|(category, _)| category.clone())
+            .collect();
+        if flagged.is_empty() {
+            return Ok(ModerationDecision::Allowed);
+        }
+
+        let content = content_hash(direction, text);
+        let mut appeals = self.appeals.lock().unwrap();
+        let pending = appeals
+            .values()
+            .find(|appeal| appeal.content == content && appeal.status == AppealStatus::Pending);
+        if let Some(appeal) = pending {
+            return Ok(ModerationDecision::Blocked(appeal.blocked.clone()));
+        }
+
+        let blocked = BlockedTurn {
+            token: OverrideToken::generate(),
+            direction,
+            scores,
+            flagged,
+            blocked_at: Utc::now(),
+        };
+        appeals.insert(
+            blocked.token.clone(),
+            Appeal {
+                blocked: blocked.clone(),
+                content,
+                status: AppealStatus::Pending,
+            },
+        );
+        Ok(ModerationDecision::Blocked(blocked))
+    }
+
+    /// Moderate a turn that may carry an approved override
+    ///
+    /// The turn is allowed without scoring if `token` was approved for
+    /// exactly this content and direction; the token can then not be used
+    /// again. Otherwise the turn is moderated as by [`check`](Self::check).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the moderator fails to score the text.
+    pub fn check_with_override(
+        &self,
+        direction: TurnDirection,
+        text: &str,
+        token: &OverrideToken,
+    ) -> Result<ModerationDecision> {
+        {
+            let mut appeals = self.appeals.lock().unwrap();
+            if let Some(appeal) = appeals.get_mut(token)
+                && appeal.status == AppealStatus::Approved
+                && appeal.content == content_hash(direction, text)
+            {
+                appeal.status = AppealStatus::Used;
+                return Ok(ModerationDecision::Allowed);
+            }
+        }
+        self.check(direction, text)
+    }
+
+    /// Allow a blocked turn to be resubmitted once with its token
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is unknown or its appeal was already
+    /// decided.
+    pub fn approve(&self, token: &OverrideToken) -> Result<()> {
+        self.decide(token, AppealStatus::Approved)
+    }
+
+    /// Uphold the block of a turn
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is unknown or its appeal was already
+    /// decided.
+    pub fn reject(&self, token: &OverrideToken) -> Result<()> {
+        self.decide(token, AppealStatus::Rejected)
+    }
+
+    /// Where the appeal of a blocked turn stands, if the token is known
+    pub fn status(&self, token: &OverrideToken) -> Option<AppealStatus> {
+        self.appeals
+            .lock()
+            .unwrap()
+            .get(token)
+            .map(|appeal| appeal.status)
+    }
+
+    /// Blocked turns waiting for review, oldest first
+    pub fn pending(&self) -> Vec<BlockedTurn> {
+        let mut pending: Vec<BlockedTurn> = self
+            .appeals
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|appeal| appeal.status == AppealStatus::Pending)
+            .map(|appeal| appeal.blocked.clone())
+            .collect();
+        pending.sort_by_key(|blocked| blocked.blocked_at);
+        pending
+    }
+
+    fn threshold(&self, category: &str) -> f32 {
+        self.thresholds
+            .get(category)
+            .copied()
+            .unwrap_or(self.default_threshold)
+    }
+
+    fn decide(&self, token: &OverrideToken, status: AppealStatus) -> Result<()> {
+        let mut appeals = self.appeals.lock().unwrap();
+        match appeals.get_mut(token) {
+            Some(appeal) if appeal.status == AppealStatus::Pending => {
+                appeal.status = status;
+                Ok(())
+            }
+            Some(appeal) => Err(AgentError::GuardrailViolation(format!(
+                "Appeal {} was already decided: {:?}",
+                token, appeal.status
+            ))),
+            None => Err(AgentError::GuardrailViolation(format!(
+                "Unknown override token {}",
+                token
+            ))),
+        }
+    }
+}
+
+fn content_hash(direction: TurnDirection, text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    direction.hash(&mut hasher);
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderation() -> TurnModerator {
+        TurnModerator::new(
+            KeywordModerator::new()
+                .with_category("violence", ["attack"])
+                .with_category("self_harm", ["hurt myself"]),
+        )
+        .with_threshold("self_harm", 0.2)
+    }
+
+    fn blocked(decision: ModerationDecision) -> BlockedTurn {
+        match decision {
+            ModerationDecision::Blocked(blocked) => blocked,
+            ModerationDecision::Allowed => panic!("expected a block"),
+        }
+    }
+
+    #[test]
+    fn test_block_carries_scores_and_token() {
+        let moderation = moderation();
+        assert!(
+            moderation
+                .check(TurnDirection::Input, "Plan a picnic")
+                .unwrap()
+                .is_allowed()
+        );
+
+        let first = blocked(
+            moderation
+                .check(TurnDirection::Output, "Attack at dawn")
+                .unwrap(),
+        );
+        assert_eq!(first.direction, TurnDirection::Output);
+        assert_eq!(first.flagged, ["violence"]);
+        assert_eq!(first.scores["self_harm"], 0.0);
+        assert!(first.token.as_str().starts_with("mod-"));
+
+        // The same content while pending keeps its appeal
+        let again = blocked(
+            moderation
+                .check(TurnDirection::Output, "Attack at dawn")
+                .unwrap(),
+        );
+        assert_eq!(again.token, first.token);
+        assert_eq!(moderation.pending(), std::slice::from_ref(&first));
+
+        let err = first.into_error();
+        assert!(matches!(err, AgentError::GuardrailViolation(_)));
+        assert!(
+            err.to_string()
+                .contains("Response blocked by moderation (violence)")
+        );
+    }
+
+    #[test]
+    fn test_approved_override_is_single_use_and_bound_to_content() {
+        let moderation = moderation();
+        let text = "How do castles survive an attack?";
+        let token = blocked(moderation.check(TurnDirection::Input, text).unwrap()).token;
+
+        // Not yet approved
+        let decision = moderation.check_with_override(TurnDirection::Input, text, &token);
+        assert!(!decision.unwrap().is_allowed());
+
+        moderation.approve(&token).unwrap();
+        assert!(moderation.approve(&token).is_err());
+        let other = "Plan an attack on the castle";
+        let decision = moderation.check_with_override(TurnDirection::Input, other, &token);
+        assert!(!decision.unwrap().is_allowed());
+
+        let decision = moderation.check_with_override(TurnDirection::Input, text, &token);
+        assert!(decision.unwrap().is_allowed());
+        assert_eq!(moderation.status(&token), Some(AppealStatus::Used));
+        let decision = moderation.check_with_override(TurnDirection::Input, text, &token);
+        assert!(!decision.unwrap().is_allowed());
+    }
+
+    #[test]
+    fn test_rejected_appeal_and_unknown_token() {
+        let moderation = moderation();
+        let token = blocked(
+            moderation
+                .check(TurnDirection::Input, "I might hurt myself")
+                .unwrap(),
+        )
+        .token;
+        moderation.reject(&token).unwrap();
+        assert_eq!(moderation.status(&token), Some(AppealStatus::Rejected));
+        assert!(moderation.pending().is_empty());
+
+        let unknown = OverrideToken::from("mod-0");
+        assert!(moderation.approve(&unknown).is_err());
+        assert_eq!(moderation.status(&unknown), None);
+    }
+}
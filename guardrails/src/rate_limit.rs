@@ -56,8 +56,8 @@ impl RateLimitGuardrail {
     ///
     /// The number of tool call steps in the plan.
     fn count_tool_calls(&self, plan: &Plan) -> usize {
-        plan.steps
-            .iter()
+        plan.all_steps()
+            .into_iter()
             .filter(|step| matches!(step, Step::ToolCall(_)))
             .count()
     }
@@ -75,6 +75,7 @@ impl RateLimitGuardrail {
     /// # Arguments
     ///
     /// * `count` - Number of tool calls to record
+    #[allow(dead_code)]
     fn record_calls(&self, count: usize) {
         let mut history = self.call_history.lock().unwrap();
         let now = Instant::now();
@@ -88,6 +89,7 @@ impl RateLimitGuardrail {
     /// # Returns
     ///
     /// The number of tool calls made in the last 60 seconds.
+    #[allow(dead_code)]
     fn current_call_count(&self) -> usize {
         let mut history = self.call_history.lock().unwrap();
         self.cleanup_old_calls(&mut history);
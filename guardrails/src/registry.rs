@@ -96,6 +96,11 @@ impl GuardrailRegistry {
         Ok(())
     }
 
+    /// Returns the names of the registered guardrails, in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.guardrails.iter().map(|guardrail| guardrail.name()).collect()
+    }
+
     /// Returns the number of registered guardrails.
     pub fn len(&self) -> usize {
         self.guardrails.len()
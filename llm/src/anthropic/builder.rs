@@ -0,0 +1,266 @@
+//! Fluent builder for [`AnthropicProvider`].
+
+use agent_core::{AgentError, Result};
+use communication::{ApiClient, RetryPolicy};
+use std::time::Duration;
+
+use crate::{MaxTokens, ModelId, ModelRules, Temperature, TopP};
+
+use super::AnthropicProvider;
+
+/// Default Anthropic API base URL
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+
+/// Builder for [`AnthropicProvider`]
+///
+/// Only the API key is required; everything else falls back to a sensible
+/// default (`claude-3-sonnet-20240229`, temperature 0.7, 2000 max tokens, 30 second
+/// timeout, 3 attempts with jittered backoff on rate limits and server errors).
+///
+/// # Example
+///
+/// ```
+/// use communication::RetryPolicy;
+/// use llm::{AnthropicProvider, ModelId};
+/// use std::time::Duration;
+///
+/// let provider = AnthropicProvider::builder()
+///     .api_key("sk-ant-...")
+///     .model(ModelId::CLAUDE_OPUS_4_1)
+///     .timeout(Duration::from_secs(60))
+///     .retry_policy(RetryPolicy::new(3))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnthropicProviderBuilder {
+    api_key: Option<String>,
+    model: ModelId,
+    temperature: Temperature,
+    top_p: Option<TopP>,
+    max_tokens: MaxTokens,
+    base_url: String,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    model_rules: Option<ModelRules>,
+    prompt_caching: bool,
+}
+
+impl AnthropicProviderBuilder {
+    /// Create a builder with default settings
+    pub fn new() -> Self {
+        Self {
+            api_key: None,
+            model: ModelId::CLAUDE_3_SONNET,
+            temperature: Temperature::default(),
+            top_p: None,
+            max_tokens: MaxTokens::default(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            headers: Vec::new(),
+            timeout: ApiClient::new().timeout(),
+            retry_policy: RetryPolicy::default(),
+            model_rules: None,
+            prompt_caching: false,
+        }
+    }
+
+    /// Set the API key (required)
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set the model
+    ///
+    /// Use a [`ModelId`] constant, or parse a name with `str::parse` to
+    /// catch typos before any request is sent.
+    pub fn model(mut self, model: ModelId) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Set the sampling temperature
+    pub fn temperature(mut self, temperature: Temperature) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Set nucleus sampling; unset by default, leaving the provider default
+    pub fn top_p(mut self, top_p: TopP) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the maximum number of tokens to generate
+    pub fn max_tokens(mut self, max_tokens: MaxTokens) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set the API base URL, e.g. for a proxy or gateway
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Add a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the request timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the retry policy for transient failures
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Override the request shaping rules, which are otherwise looked up
+    /// from the model name
+    pub fn model_rules(mut self, model_rules: ModelRules) -> Self {
+        self.model_rules = Some(model_rules);
+        self
+    }
+
+    /// Enable prompt caching
+    ///
+    /// Requests then mark the system prompt and the end of the conversation
+    /// as cache breakpoints, and completions report cache reads and writes
+    /// in [`CompletionResponse::cache`](crate::CompletionResponse::cache).
+    pub fn prompt_caching(mut self, enabled: bool) -> Self {
+        self.prompt_caching = enabled;
+        self
+    }
+
+    /// Build the provider
+    ///
+    /// The temperature is capped at [`Temperature::ANTHROPIC_MAX`].
+    ///
+    /// # Errors
+    /// Returns an error if no API key was set.
+    pub fn build(self) -> Result<AnthropicProvider> {
+        let api_key = self
+            .api_key
+            .ok_or_else(|| AgentError::Config("Anthropic API key is required".to_string()))?;
+
+        let model: String = self.model.into();
+        let rules = self
+            .model_rules
+            .unwrap_or_else(|| ModelRules::for_model(&model));
+        Ok(AnthropicProvider {
+            api_key,
+            model,
+            temperature: self.temperature.clamp_for("anthropic").get(),
+            top_p: self.top_p.map(TopP::get),
+            max_tokens: self.max_tokens.get(),
+            base_url: self.base_url,
+            headers: self.headers,
+            retry_policy: self.retry_policy,
+            rules,
+            prompt_caching: self.prompt_caching,
+            client: ApiClient::with_timeout(self.timeout),
+        })
+    }
+}
+
+impl Default for AnthropicProviderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LLMProvider;
+    use agent_core::Message;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_build_requires_api_key() {
+        let result = AnthropicProviderBuilder::new().build();
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+
+    #[test]
+    fn test_build_with_defaults() {
+        let provider = AnthropicProvider::builder().api_key("key").build().unwrap();
+        assert_eq!(provider.model, "claude-3-sonnet-20240229");
+        assert_eq!(provider.base_url, DEFAULT_BASE_URL);
+        assert_eq!(provider.retry_policy, RetryPolicy::default());
+        assert_eq!(provider.client.timeout(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_temperature_is_capped() {
+        let provider = AnthropicProvider::builder()
+            .api_key("key")
+            .temperature(Temperature::new(1.8).unwrap())
+            .top_p(TopP::new(0.9).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(provider.temperature, 1.0);
+        assert_eq!(provider.top_p, Some(0.9));
+    }
+
+    #[tokio::test]
+    async fn test_custom_base_url_and_headers() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "test-key"))
+            .and(header("X-Custom", "yes"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi!"}],
+                "model": "claude-3-sonnet-20240229",
+                "stop_reason": "end_turn"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = AnthropicProvider::builder()
+            .api_key("test-key")
+            .base_url(format!("{}/v1/", mock_server.uri()))
+            .header("X-Custom", "yes")
+            .build()
+            .unwrap();
+
+        let response = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap();
+        assert_eq!(response, "Hi!");
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_is_applied() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("Unavailable"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let provider = AnthropicProvider::builder()
+            .api_key("test-key")
+            .base_url(mock_server.uri())
+            .retry_policy(RetryPolicy::new(2).with_initial_delay(Duration::from_millis(1)))
+            .build()
+            .unwrap();
+
+        assert!(provider.send_message(&[Message::user("Hello")]).await.is_err());
+    }
+}
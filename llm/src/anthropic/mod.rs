@@ -1,20 +1,44 @@
+pub mod builder;
 pub mod types;
 
-use agent_core::{AgentError, Message, Result, Role};
+use agent_core::{AgentError, Message, Result, Role, ToolDefinition, ToolUse, ToolUseResponse};
 use async_trait::async_trait;
-use communication::ApiClient;
+use communication::{
+    ApiClient, RetryPolicy, check_status, decode_sse_stream, request_error, with_request_headers,
+    with_retry_policy,
+};
 use config::LLMConfig;
+use futures::StreamExt;
+use serde_json::Value;
 
-use crate::LLMProvider;
+use crate::citations::{
+    Citation, CitationDocument, CitationSource, CitationSpan, CitedResponse, CitedText,
+    DocumentSource,
+};
+use crate::structured::send_completion_with_repair;
+use crate::{
+    CacheUsage, CompletionResponse, FinishReason, LLMProvider, MaxTokens, ModelRules, StreamEvent,
+    StructuredOutput, Temperature, TokenStream, TokenUsage, ToolConfig,
+};
 
-pub use types::{AnthropicMessage, MessagesRequest, MessagesResponse};
+pub use builder::AnthropicProviderBuilder;
+pub use types::{AnthropicContent, AnthropicMessage, MessagesRequest, MessagesResponse};
+
+/// Beta header value required when referencing uploaded files
+pub(crate) const FILES_API_BETA: &str = "files-api-2025-04-14";
 
 /// Anthropic LLM provider implementation
 pub struct AnthropicProvider {
     api_key: String,
     model: String,
     temperature: f32,
+    top_p: Option<f32>,
     max_tokens: usize,
+    base_url: String,
+    headers: Vec<(String, String)>,
+    retry_policy: RetryPolicy,
+    rules: ModelRules,
+    prompt_caching: bool,
     client: ApiClient,
 }
 
@@ -26,13 +50,156 @@ impl AnthropicProvider {
     ///
     /// # Returns
     /// * `Result<Self>` - New provider instance or error
+    ///
+    /// # Errors
+    /// Returns an error if the model name looks like a typo of a known model,
+    /// or if the temperature or max tokens are out of range
     pub fn new(config: &LLMConfig) -> Result<Self> {
-        Ok(Self {
-            api_key: config.api_key.clone(),
-            model: config.model.clone(),
-            temperature: config.temperature,
-            max_tokens: config.max_tokens,
-            client: ApiClient::new(),
+        Self::builder()
+            .api_key(config.api_key.clone())
+            .model(config.model.parse()?)
+            .temperature(Temperature::new(config.temperature)?)
+            .max_tokens(MaxTokens::new(config.max_tokens)?)
+            .prompt_caching(config.prompt_caching)
+            .build()
+    }
+
+    /// Create a builder for configuring a provider without an `LLMConfig`
+    pub fn builder() -> AnthropicProviderBuilder {
+        AnthropicProviderBuilder::new()
+    }
+
+    /// Ask a question grounded in the given documents
+    ///
+    /// The documents are attached to the last user message with citations
+    /// enabled, and the answer is returned split into segments, each with
+    /// the passages that support it.
+    ///
+    /// # Errors
+    /// Returns an error if `messages` contains no user message, or if the
+    /// API request fails
+    pub async fn send_with_citations(
+        &self,
+        messages: &[Message],
+        documents: &[CitationDocument],
+    ) -> Result<CitedResponse> {
+        let mut request = self.build_request(messages, false);
+        let last_user = request
+            .messages
+            .iter_mut()
+            .rev()
+            .find(|message| message.role == "user")
+            .ok_or_else(|| {
+                AgentError::LLMProvider("Citations require at least one user message".to_string())
+            })?;
+        Self::attach_documents(&mut last_user.content, documents);
+
+        let response = with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+        Ok(Self::convert_cited_response(response))
+    }
+
+    /// Prepend citable document blocks to message content
+    fn attach_documents(content: &mut types::AnthropicContent, documents: &[CitationDocument]) {
+        let mut blocks: Vec<_> = documents
+            .iter()
+            .map(|document| types::AnthropicContentBlock::Document {
+                source: match &document.source {
+                    DocumentSource::Text(text) => types::AnthropicDocumentSource::text(text),
+                    DocumentSource::File(file) => types::AnthropicDocumentSource::file(&file.id),
+                },
+                title: document.title.clone(),
+                citations: Some(types::CitationsConfig { enabled: true }),
+            })
+            .collect();
+
+        match std::mem::replace(content, types::AnthropicContent::Blocks(Vec::new())) {
+            types::AnthropicContent::Text(text) => {
+                blocks.push(types::AnthropicContentBlock::text(text));
+            }
+            types::AnthropicContent::Blocks(existing) => blocks.extend(existing),
+        }
+        *content = types::AnthropicContent::Blocks(blocks);
+    }
+
+    /// Convert response text blocks and their citations into a [`CitedResponse`]
+    fn convert_cited_response(response: MessagesResponse) -> CitedResponse {
+        let segments = response
+            .content
+            .into_iter()
+            .filter(|block| block.content_type == "text")
+            .map(|block| CitedText {
+                text: block.text,
+                citations: block
+                    .citations
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(Self::convert_citation)
+                    .collect(),
+            })
+            .collect();
+        CitedResponse { segments }
+    }
+
+    /// Convert an Anthropic citation; unsupported citation types are dropped
+    fn convert_citation(citation: types::ResponseCitation) -> Option<Citation> {
+        use types::ResponseCitation::*;
+
+        let (quote, document_index, title, span) = match citation {
+            CharLocation {
+                cited_text,
+                document_index,
+                document_title,
+                start_char_index,
+                end_char_index,
+            } => (
+                cited_text,
+                document_index,
+                document_title,
+                CitationSpan::Chars {
+                    start: start_char_index,
+                    end: end_char_index,
+                },
+            ),
+            PageLocation {
+                cited_text,
+                document_index,
+                document_title,
+                start_page_number,
+                end_page_number,
+            } => (
+                cited_text,
+                document_index,
+                document_title,
+                CitationSpan::Pages {
+                    start: start_page_number,
+                    end: end_page_number,
+                },
+            ),
+            ContentBlockLocation {
+                cited_text,
+                document_index,
+                document_title,
+                start_block_index,
+                end_block_index,
+            } => (
+                cited_text,
+                document_index,
+                document_title,
+                CitationSpan::Blocks {
+                    start: start_block_index,
+                    end: end_block_index,
+                },
+            ),
+            Other => return None,
+        };
+
+        Some(Citation {
+            source: CitationSource {
+                document_index,
+                title,
+            },
+            quote,
+            span,
         })
     }
 
@@ -41,17 +208,49 @@ impl AnthropicProvider {
     /// Note: System messages are handled separately and should not be
     /// included in the messages array
     fn convert_message(message: &Message) -> Option<types::AnthropicMessage> {
-        match message.role {
-            Role::System => None, // System messages go in separate field
-            Role::User => Some(types::AnthropicMessage {
-                role: "user".to_string(),
-                content: message.content.clone(),
-            }),
-            Role::Assistant => Some(types::AnthropicMessage {
-                role: "assistant".to_string(),
-                content: message.content.clone(),
-            }),
+        let role = match message.role {
+            Role::System => return None, // System messages go in separate field
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        };
+
+        Some(types::AnthropicMessage {
+            role: role.to_string(),
+            content: Self::convert_content(message),
+        })
+    }
+
+    /// Convert message text, images and attached files to Anthropic content
+    ///
+    /// Images and image files become image blocks; every other file type is
+    /// attached as a document block.
+    fn convert_content(message: &Message) -> types::AnthropicContent {
+        if !message.is_multimodal() {
+            return types::AnthropicContent::Text(message.content.clone());
         }
+
+        let mut blocks: Vec<_> = message
+            .attachments
+            .iter()
+            .map(|file| {
+                if file.is_image() {
+                    types::AnthropicContentBlock::Image {
+                        source: types::AnthropicImageSource::file(&file.id),
+                    }
+                } else {
+                    types::AnthropicContentBlock::Document {
+                        source: types::AnthropicDocumentSource::file(&file.id),
+                        title: None,
+                        citations: None,
+                    }
+                }
+            })
+            .collect();
+        blocks.extend(message.images.iter().map(|image| types::AnthropicContentBlock::Image {
+            source: image.into(),
+        }));
+        blocks.push(types::AnthropicContentBlock::text(message.content.clone()));
+        types::AnthropicContent::Blocks(blocks)
     }
 
     /// Convert multiple framework messages to Anthropic format
@@ -83,69 +282,135 @@ impl AnthropicProvider {
 
         (system_message, anthropic_messages)
     }
-}
 
-#[async_trait]
-impl LLMProvider for AnthropicProvider {
-    async fn send_message(&self, messages: &[Message]) -> Result<String> {
-        // Convert framework messages to Anthropic format, separating system messages
-        let (system, anthropic_messages) = Self::convert_messages(messages);
+    /// The request shaping rules for this provider's model
+    pub fn model_rules(&self) -> &ModelRules {
+        &self.rules
+    }
 
-        // Build the request
-        let request = MessagesRequest {
+    /// Build a messages request for the given conversation, shaped for the
+    /// model
+    ///
+    /// With prompt caching, cache breakpoints are placed after the system
+    /// prompt and after the last message, so the next turn of the
+    /// conversation reads everything before it from the cache.
+    fn build_request(&self, messages: &[Message], stream: bool) -> MessagesRequest {
+        // Separate system messages, which Anthropic takes as a top-level field
+        let (system, mut anthropic_messages) =
+            Self::convert_messages(&self.rules.shape_messages(messages));
+        let mut system = system.map(types::AnthropicContent::Text);
+        if self.prompt_caching {
+            if let Some(system) = system.as_mut() {
+                system.cache_up_to_here();
+            }
+            if let Some(last) = anthropic_messages.last_mut() {
+                last.content.cache_up_to_here();
+            }
+        }
+        let (temperature, top_p) = self.rules.sampling(Some(self.temperature), self.top_p);
+
+        MessagesRequest {
             model: self.model.clone(),
             messages: anthropic_messages,
             system,
-            temperature: self.temperature,
-            max_tokens: self.max_tokens,
-        };
+            temperature,
+            top_p,
+            max_tokens: self.rules.cap_max_tokens(self.max_tokens),
+            stream,
+            tools: Vec::new(),
+            tool_choice: None,
+        }
+    }
+
+    /// Send a single messages request to the API
+    async fn send_request(&self, request: &MessagesRequest) -> Result<MessagesResponse> {
+        let response = self.post_request(request).await?;
+
+        // Deserialize the response
+        response.json().await.map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize Anthropic response: {}", e))
+        })
+    }
+
+    /// POST a messages request and check the response status
+    async fn post_request(&self, request: &MessagesRequest) -> Result<reqwest::Response> {
+        let url = format!("{}/messages", self.base_url);
 
-        // Call Anthropic API
-        let url = "https://api.anthropic.com/v1/messages";
-        
         // Create a custom client with required headers
         let client = reqwest::Client::new();
-        let response = client
-            .post(url)
+        let mut builder = with_request_headers(client.post(&url))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&request)
+            .header("Content-Type", "application/json");
+        if request.messages.iter().any(|m| m.content.has_files()) {
+            builder = builder.header("anthropic-beta", FILES_API_BETA);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder
+            .json(request)
             .timeout(self.client.timeout())
             .send()
             .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AgentError::LLMProvider(format!("Anthropic API request timeout: {}", e))
-                } else if e.is_connect() {
-                    AgentError::LLMProvider(format!("Anthropic API connection error: {}", e))
-                } else if e.status() == Some(reqwest::StatusCode::UNAUTHORIZED) {
-                    AgentError::LLMProvider("Anthropic API authentication failed: Invalid API key".to_string())
-                } else if e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
-                    AgentError::LLMProvider("Anthropic API rate limit exceeded".to_string())
-                } else {
-                    AgentError::LLMProvider(format!("Anthropic API request failed: {}", e))
-                }
-            })?;
+            .map_err(|e| request_error("Anthropic API", e))?;
+
+        check_status("Anthropic API", response).await
+    }
+}
 
-        // Check for HTTP errors
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read error response".to_string());
-            
-            return Err(AgentError::LLMProvider(format!(
-                "Anthropic API HTTP {} error: {}",
-                status, error_text
-            )));
+/// Convert one server-sent event from a streamed response into a stream event
+///
+/// `input_tokens` remembers the prompt size from `message_start` so the
+/// usage event emitted on `message_delta` can report both counts.
+fn parse_event(data: &str, input_tokens: &mut usize) -> Option<Result<StreamEvent>> {
+    match serde_json::from_str::<types::StreamPayload>(data) {
+        Ok(types::StreamPayload::MessageStart { message }) => {
+            if let Some(usage) = message.usage {
+                *input_tokens = usage.input_tokens;
+            }
+            None
         }
+        Ok(types::StreamPayload::MessageDelta { usage }) => usage.map(|usage| {
+            Ok(StreamEvent::Usage(TokenUsage {
+                input_tokens: usage.input_tokens.max(*input_tokens),
+                output_tokens: usage.output_tokens,
+            }))
+        }),
+        Ok(types::StreamPayload::ContentBlockDelta { delta }) => delta
+            .text
+            .filter(|text| !text.is_empty())
+            .map(|text| Ok(StreamEvent::Text(text))),
+        Ok(types::StreamPayload::Error { error }) => Some(Err(AgentError::LLMProvider(format!(
+            "Anthropic API stream error ({}): {}",
+            error.error_type, error.message
+        )))),
+        Ok(types::StreamPayload::MessageStop | types::StreamPayload::Other) => None,
+        Err(e) => Some(Err(AgentError::LLMProvider(format!(
+            "Failed to deserialize Anthropic stream event: {}",
+            e
+        )))),
+    }
+}
 
-        // Deserialize the response
-        let messages_response: MessagesResponse = response.json().await.map_err(|e| {
-            AgentError::LLMProvider(format!("Failed to deserialize Anthropic response: {}", e))
-        })?;
+/// Whether an event marks the end of a streamed response
+fn is_message_stop(data: &str) -> bool {
+    matches!(
+        serde_json::from_str::<types::StreamPayload>(data),
+        Ok(types::StreamPayload::MessageStop)
+    )
+}
+
+#[async_trait]
+impl LLMProvider for AnthropicProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        // Convert framework messages to Anthropic format and build the request
+        let request = self.build_request(messages, false);
+
+        // Call Anthropic API, retrying transient failures per the configured policy
+        let messages_response =
+            with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
 
         // Extract the response text from content[0].text
         messages_response
@@ -156,4 +421,539 @@ impl LLMProvider for AnthropicProvider {
                 AgentError::LLMProvider("Anthropic response contained no content".to_string())
             })
     }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let request = self.build_request(messages, false);
+        let messages_response =
+            with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+
+        let finish_reason = FinishReason::from_anthropic(messages_response.stop_reason.as_deref());
+        let content = messages_response.content.into_iter().next().ok_or_else(|| {
+            AgentError::LLMProvider("Anthropic response contained no content".to_string())
+        })?;
+
+        let mut response = CompletionResponse::new(content.text, finish_reason)
+            .with_model(messages_response.model);
+        if let Some(usage) = messages_response.usage {
+            response = response.with_usage(TokenUsage {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+            });
+            if self.prompt_caching {
+                response = response.with_cache_usage(CacheUsage {
+                    read_tokens: usage.cache_read_input_tokens,
+                    written_tokens: usage.cache_creation_input_tokens,
+                });
+            }
+        }
+        Ok(response)
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        send_completion_with_repair(self, messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let tools = tools
+            .iter()
+            .map(|tool| types::AnthropicTool {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                input_schema: tool.parameters.clone(),
+            })
+            .collect();
+        let request = self.build_request(messages, false).with_tools(tools, config);
+
+        let messages_response =
+            with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+
+        // Text blocks are joined; tool_use blocks become tool calls
+        let mut response = ToolUseResponse::default();
+        for block in messages_response.content {
+            match block.content_type.as_str() {
+                "text" => response.text.push_str(&block.text),
+                "tool_use" => response.tool_uses.push(ToolUse {
+                    id: block.id.unwrap_or_default(),
+                    name: block.name.unwrap_or_default(),
+                    arguments: block.input.unwrap_or_default(),
+                }),
+                _ => {}
+            }
+        }
+        Ok(response)
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let request = self.build_request(messages, true);
+
+        // Only the initial request is retried; a stream is not resumed mid-way
+        let response = with_retry_policy(|| self.post_request(&request), &self.retry_policy).await?;
+
+        let events = decode_sse_stream(response.bytes_stream())
+            .take_while(|event| {
+                let done = matches!(event, Ok(event) if is_message_stop(&event.data));
+                futures::future::ready(!done)
+            })
+            .scan(0, |input_tokens, event| {
+                futures::future::ready(Some(match event {
+                    Ok(event) => parse_event(&event.data, input_tokens),
+                    Err(e) => Some(Err(e)),
+                }))
+            })
+            .filter_map(futures::future::ready);
+        Ok(Box::pin(events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StopCondition, StopReason, ToolChoice, ToolConfig, with_stop_conditions};
+    use agent_core::{FileRef, ImageContent};
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sse_body(chunks: &[&str]) -> String {
+        let mut body = String::from(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n",
+        );
+        for text in chunks {
+            body.push_str(&format!(
+                "event: content_block_delta\ndata: {}\n\n",
+                serde_json::json!({
+                    "type": "content_block_delta",
+                    "index": 0,
+                    "delta": {"type": "text_delta", "text": text}
+                })
+            ));
+        }
+        body.push_str("event: ping\ndata: {\"type\":\"ping\"}\n\n");
+        body.push_str(
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":15}}\n\n",
+        );
+        body.push_str("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n");
+        body
+    }
+
+    async fn streaming_provider(server: &MockServer, body: String) -> AnthropicProvider {
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(serde_json::json!({"stream": true})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(server)
+            .await;
+
+        AnthropicProvider::builder()
+            .api_key("test-key")
+            .model("claude-3-opus-20240229".parse().unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap()
+    }
+
+    async fn drain(mut stream: TokenStream) -> Vec<Result<StreamEvent>> {
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_with_max_tokens() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, sse_body(&["One", " two", " three"])).await;
+
+        let stream = provider.stream_message(&[Message::user("Hi")]).await.unwrap();
+        let events = drain(with_stop_conditions(stream, vec![StopCondition::max_tokens(2)])).await;
+        let events: Vec<_> = events.into_iter().map(|e| e.unwrap()).collect();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("One".to_string()),
+                StreamEvent::Text(" two".to_string()),
+                StreamEvent::Stopped(StopReason::MaxTokens(2)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_message_stream_yields_tokens() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, sse_body(&["One", " two"])).await;
+
+        let tokens: Vec<String> = provider
+            .send_message_stream(&[Message::user("Hi")])
+            .await
+            .unwrap()
+            .map(|token| token.unwrap())
+            .collect()
+            .await;
+        assert_eq!(tokens, vec!["One", " two"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_ends_with_usage() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, sse_body(&["Hello"])).await;
+
+        let stream = provider.stream_message(&[Message::user("Hi")]).await.unwrap();
+        let events: Vec<_> = drain(stream).await.into_iter().map(|e| e.unwrap()).collect();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("Hello".to_string()),
+                StreamEvent::Usage(TokenUsage {
+                    input_tokens: 25,
+                    output_tokens: 15,
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_error_event() {
+        let server = MockServer::start().await;
+        let body = "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        let provider = streaming_provider(&server, body.to_string()).await;
+
+        let stream = provider.stream_message(&[Message::user("Hi")]).await.unwrap();
+        let events = drain(stream).await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], Err(AgentError::LLMProvider(msg)) if msg.contains("Overloaded")));
+    }
+
+    #[test]
+    fn test_convert_message_with_attachments() {
+        let message = Message::user("Compare these")
+            .with_attachment(FileRef::new("file_1", "image/png"))
+            .with_attachment(FileRef::new("file_2", "application/pdf"));
+        let converted = AnthropicProvider::convert_message(&message).unwrap();
+        assert!(converted.content.has_files());
+
+        let json = serde_json::to_value(&converted).unwrap();
+        assert_eq!(
+            json["content"],
+            serde_json::json!([
+                {"type": "image", "source": {"type": "file", "file_id": "file_1"}},
+                {"type": "document", "source": {"type": "file", "file_id": "file_2"}},
+                {"type": "text", "text": "Compare these"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_convert_message_with_images() {
+        let message = Message::user("What breed is this?")
+            .with_image(ImageContent::url("https://example.com/dog.jpg"))
+            .with_image(ImageContent::base64("image/png", "iVBORw0KGgo="));
+        let converted = AnthropicProvider::convert_message(&message).unwrap();
+        assert!(!converted.content.has_files());
+
+        let json = serde_json::to_value(&converted).unwrap();
+        assert_eq!(
+            json["content"],
+            serde_json::json!([
+                {"type": "image", "source": {"type": "url", "url": "https://example.com/dog.jpg"}},
+                {
+                    "type": "image",
+                    "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}
+                },
+                {"type": "text", "text": "What breed is this?"}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_with_citations() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(serde_json::json!({
+                "messages": [{
+                    "role": "user",
+                    "content": [
+                        {
+                            "type": "document",
+                            "source": {"type": "text", "media_type": "text/plain", "data": "The grass is green. The sky is blue."},
+                            "title": "Facts",
+                            "citations": {"enabled": true}
+                        },
+                        {"type": "text", "text": "What color is the sky?"}
+                    ]
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-opus-20240229",
+                "stop_reason": "end_turn",
+                "content": [
+                    {"type": "text", "text": "According to the document, "},
+                    {
+                        "type": "text",
+                        "text": "the sky is blue",
+                        "citations": [{
+                            "type": "char_location",
+                            "cited_text": "The sky is blue.",
+                            "document_index": 0,
+                            "document_title": "Facts",
+                            "start_char_index": 20,
+                            "end_char_index": 36
+                        }]
+                    },
+                    {"type": "text", "text": "."}
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::builder()
+            .api_key("test-key")
+            .model("claude-3-opus-20240229".parse().unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let document =
+            CitationDocument::text("The grass is green. The sky is blue.").with_title("Facts");
+        let response = provider
+            .send_with_citations(&[Message::user("What color is the sky?")], &[document])
+            .await
+            .unwrap();
+
+        assert_eq!(response.text(), "According to the document, the sky is blue.");
+        assert_eq!(response.segments.len(), 3);
+        let citations: Vec<_> = response.citations().collect();
+        assert_eq!(
+            citations,
+            vec![&Citation {
+                source: CitationSource {
+                    document_index: 0,
+                    title: Some("Facts".to_string()),
+                },
+                quote: "The sky is blue.".to_string(),
+                span: CitationSpan::Chars { start: 20, end: 36 },
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_with_citations_requires_user_message() {
+        let provider = AnthropicProvider::builder().api_key("test-key").build().unwrap();
+        let result = provider
+            .send_with_citations(&[Message::system("Be brief")], &[CitationDocument::text("Doc")])
+            .await;
+        assert!(matches!(result, Err(AgentError::LLMProvider(_))));
+    }
+
+    #[test]
+    fn test_convert_plain_message() {
+        let converted = AnthropicProvider::convert_message(&Message::assistant("Hi")).unwrap();
+        assert!(!converted.content.has_files());
+        assert_eq!(serde_json::to_value(&converted).unwrap()["content"], "Hi");
+    }
+
+    #[test]
+    fn test_tool_choice_serialization() {
+        let provider = AnthropicProvider::builder().api_key("test-key").build().unwrap();
+        let request = provider.build_request(&[Message::user("Hi")], false);
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json.get("tools").is_none());
+        assert!(json.get("tool_choice").is_none());
+
+        let tool = types::AnthropicTool {
+            name: "search".to_string(),
+            description: "Search the web".to_string(),
+            input_schema: serde_json::json!({"type": "object"}),
+        };
+        let config = ToolConfig::new(ToolChoice::tool("search")).sequential();
+        let json = serde_json::to_value(request.with_tools(vec![tool], &config)).unwrap();
+        assert_eq!(json["tools"][0]["name"], "search");
+        assert_eq!(
+            json["tool_choice"],
+            serde_json::json!({"type": "tool", "name": "search", "disable_parallel_tool_use": true})
+        );
+
+        let auto = types::AnthropicToolChoice::from(&ToolConfig::default());
+        assert_eq!(serde_json::to_value(auto).unwrap(), serde_json::json!({"type": "auto"}));
+        let config = ToolConfig::new(ToolChoice::None).sequential();
+        let none = types::AnthropicToolChoice::from(&config);
+        assert_eq!(serde_json::to_value(none).unwrap(), serde_json::json!({"type": "none"}));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_with_tools() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(serde_json::json!({
+                "tools": [{"name": "search", "input_schema": {"type": "object"}}],
+                "tool_choice": {"type": "auto"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-opus-20240229",
+                "stop_reason": "tool_use",
+                "content": [
+                    {"type": "text", "text": "Let me search."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "search", "input": {"q": "rust"}}
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::builder()
+            .api_key("test-key")
+            .model("claude-3-opus-20240229".parse().unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let tools = [ToolDefinition::new(
+            "search",
+            "Search the web",
+            serde_json::json!({"type": "object"}),
+        )];
+        let response = provider
+            .send_message_with_tools(&[Message::user("Find docs")], &tools, &ToolConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "Let me search.");
+        assert_eq!(
+            response.tool_uses,
+            vec![ToolUse {
+                id: "toolu_1".to_string(),
+                name: "search".to_string(),
+                arguments: serde_json::json!({"q": "rust"}),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_structured_completion_sums_usage() {
+        let server = MockServer::start().await;
+        let reply = |text: &str, output_tokens: usize| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-opus-20240229",
+                "stop_reason": "end_turn",
+                "content": [{"type": "text", "text": text}],
+                "usage": {"input_tokens": 50, "output_tokens": output_tokens}
+            }))
+        };
+        Mock::given(method("POST"))
+            .respond_with(reply("The answer is 42", 6))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(reply("{\"answer\": 42}", 8))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::builder()
+            .api_key("test-key")
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+        let output = StructuredOutput::new(serde_json::json!({
+            "type": "object",
+            "properties": {"answer": {"type": "integer"}},
+            "required": ["answer"]
+        }));
+        let (value, response) = provider
+            .send_structured_completion(&[Message::user("What is 6 x 7?")], &output)
+            .await
+            .unwrap();
+
+        assert_eq!(value, serde_json::json!({"answer": 42}));
+        assert_eq!(response.model.as_deref(), Some("claude-3-opus-20240229"));
+        assert_eq!(
+            response.usage,
+            Some(TokenUsage {
+                input_tokens: 100,
+                output_tokens: 14,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prompt_caching_marks_breakpoints_and_reports_hits() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "system": [{
+                    "type": "text",
+                    "text": "Long instructions",
+                    "cache_control": {"type": "ephemeral"}
+                }],
+                "messages": [
+                    {"role": "user", "content": "First"},
+                    {"role": "assistant", "content": "Reply"},
+                    {"role": "user", "content": [{
+                        "type": "text",
+                        "text": "Second",
+                        "cache_control": {"type": "ephemeral"}
+                    }]}
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-sonnet-4-5",
+                "stop_reason": "end_turn",
+                "content": [{"type": "text", "text": "Done"}],
+                "usage": {
+                    "input_tokens": 12,
+                    "output_tokens": 3,
+                    "cache_creation_input_tokens": 40,
+                    "cache_read_input_tokens": 2048
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::builder()
+            .api_key("test-key")
+            .base_url(server.uri())
+            .prompt_caching(true)
+            .build()
+            .unwrap();
+        let response = provider
+            .send_completion(&[
+                Message::system("Long instructions"),
+                Message::user("First"),
+                Message::assistant("Reply"),
+                Message::user("Second"),
+            ])
+            .await
+            .unwrap();
+
+        let cache = response.cache.unwrap();
+        assert!(cache.is_hit());
+        assert_eq!(cache.read_tokens, 2048);
+        assert_eq!(cache.written_tokens, 40);
+        assert_eq!(response.usage.unwrap().input_tokens, 12);
+    }
 }
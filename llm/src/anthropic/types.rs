@@ -1,6 +1,11 @@
 //! Type definitions for Anthropic API requests and responses.
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use agent_core::ImageContent;
+
+use crate::{ToolChoice, ToolConfig};
 
 /// Anthropic API message format.
 ///
@@ -10,7 +15,202 @@ pub struct AnthropicMessage {
     /// The role of the message sender ("user" or "assistant")
     pub role: String,
     /// The content of the message
-    pub content: String,
+    pub content: AnthropicContent,
+}
+
+/// Content of an Anthropic message.
+///
+/// Plain text is sent as a string; messages with attached files are sent
+/// as a list of content blocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AnthropicContent {
+    /// Plain text content
+    Text(String),
+    /// Text, image and document blocks
+    Blocks(Vec<AnthropicContentBlock>),
+}
+
+impl AnthropicContent {
+    /// Mark the end of this content as a prompt cache breakpoint
+    ///
+    /// Everything in the request up to and including the marked block is
+    /// cached. Plain text is turned into a single text block; content that
+    /// does not end in a non-empty text block is left unmarked.
+    pub fn cache_up_to_here(&mut self) {
+        if let AnthropicContent::Text(text) = self {
+            let text = std::mem::take(text);
+            *self = AnthropicContent::Blocks(vec![AnthropicContentBlock::text(text)]);
+        }
+        if let AnthropicContent::Blocks(blocks) = self
+            && let Some(AnthropicContentBlock::Text {
+                text,
+                cache_control,
+            }) = blocks.last_mut()
+            && !text.is_empty()
+        {
+            *cache_control = Some(CacheControl::ephemeral());
+        }
+    }
+
+    /// Whether any block references an uploaded file
+    pub fn has_files(&self) -> bool {
+        match self {
+            AnthropicContent::Text(_) => false,
+            AnthropicContent::Blocks(blocks) => blocks.iter().any(|block| match block {
+                AnthropicContentBlock::Text { .. } => false,
+                AnthropicContentBlock::Image { source } => {
+                    matches!(source, AnthropicImageSource::File { .. })
+                }
+                AnthropicContentBlock::Document { source, .. } => {
+                    matches!(source, AnthropicDocumentSource::File { .. })
+                }
+            }),
+        }
+    }
+}
+
+/// A single block of multi-block message content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicContentBlock {
+    /// A text block
+    Text {
+        /// The text
+        text: String,
+        /// Prompt cache breakpoint ending at this block
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    /// An image
+    Image {
+        /// Where the image comes from
+        source: AnthropicImageSource,
+    },
+    /// A document such as a PDF or text file
+    Document {
+        /// Where the document comes from
+        source: AnthropicDocumentSource,
+        /// Document title, echoed back in citations
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        /// Whether the model should cite this document
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<CitationsConfig>,
+    },
+}
+
+impl AnthropicContentBlock {
+    /// A text block without a cache breakpoint
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text {
+            text: text.into(),
+            cache_control: None,
+        }
+    }
+}
+
+/// A prompt cache breakpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheControl {
+    /// Cache type (always "ephemeral")
+    #[serde(rename = "type")]
+    pub cache_type: String,
+}
+
+impl CacheControl {
+    /// The default five-minute cache
+    pub fn ephemeral() -> Self {
+        Self {
+            cache_type: "ephemeral".to_string(),
+        }
+    }
+}
+
+/// Source of a document block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicDocumentSource {
+    /// A file uploaded through the Files API
+    File {
+        /// ID returned by the Files API
+        file_id: String,
+    },
+    /// Inline plain text
+    Text {
+        /// MIME type (always "text/plain")
+        media_type: String,
+        /// The document text
+        data: String,
+    },
+}
+
+impl AnthropicDocumentSource {
+    /// Reference an uploaded file by ID
+    pub fn file(file_id: impl Into<String>) -> Self {
+        Self::File {
+            file_id: file_id.into(),
+        }
+    }
+
+    /// Inline plain-text document
+    pub fn text(data: impl Into<String>) -> Self {
+        Self::Text {
+            media_type: "text/plain".to_string(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Citation settings for a document block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CitationsConfig {
+    /// Whether citations are enabled
+    pub enabled: bool,
+}
+
+/// Source of an image block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicImageSource {
+    /// A file uploaded through the Files API
+    File {
+        /// ID returned by the Files API
+        file_id: String,
+    },
+    /// An image the API downloads
+    Url {
+        /// HTTP(S) URL of the image
+        url: String,
+    },
+    /// Inline image bytes
+    Base64 {
+        /// MIME type, e.g. `image/png`
+        media_type: String,
+        /// Base64-encoded image bytes
+        data: String,
+    },
+}
+
+impl AnthropicImageSource {
+    /// Reference an uploaded file by ID
+    pub fn file(file_id: impl Into<String>) -> Self {
+        Self::File {
+            file_id: file_id.into(),
+        }
+    }
+}
+
+impl From<&ImageContent> for AnthropicImageSource {
+    fn from(image: &ImageContent) -> Self {
+        match image {
+            ImageContent::Url { url } => Self::Url { url: url.clone() },
+            ImageContent::Base64 { media_type, data } => Self::Base64 {
+                media_type: media_type.clone(),
+                data: data.clone(),
+            },
+        }
+    }
 }
 
 /// Request structure for Anthropic Messages API.
@@ -25,13 +225,94 @@ pub struct MessagesRequest {
     pub model: String,
     /// The conversation messages (user and assistant only)
     pub messages: Vec<AnthropicMessage>,
-    /// Optional system message (sent separately from messages array)
+    /// Optional system message (sent separately from messages array);
+    /// sent as blocks when it carries a cache breakpoint
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub system: Option<String>,
+    pub system: Option<AnthropicContent>,
     /// Sampling temperature (0.0 to 1.0)
-    pub temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
     /// Maximum number of tokens to generate
     pub max_tokens: usize,
+    /// Stream the response as server-sent events
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    /// Tools the model may call
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<AnthropicTool>,
+    /// How the model should use the tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<AnthropicToolChoice>,
+}
+
+impl MessagesRequest {
+    /// Offer tools to the model with the given calling settings
+    pub fn with_tools(mut self, tools: Vec<AnthropicTool>, config: &ToolConfig) -> Self {
+        self.tools = tools;
+        self.tool_choice = Some(AnthropicToolChoice::from(config));
+        self
+    }
+}
+
+/// A tool definition in a Messages API request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnthropicTool {
+    /// Tool name
+    pub name: String,
+    /// What the tool does
+    pub description: String,
+    /// JSON Schema of the tool input
+    pub input_schema: Value,
+}
+
+/// The `tool_choice` field of a Messages API request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicToolChoice {
+    /// The model decides whether to use tools
+    Auto {
+        /// Allow at most one tool call per response
+        #[serde(skip_serializing_if = "Option::is_none")]
+        disable_parallel_tool_use: Option<bool>,
+    },
+    /// The model must use one of the tools
+    Any {
+        /// Allow at most one tool call per response
+        #[serde(skip_serializing_if = "Option::is_none")]
+        disable_parallel_tool_use: Option<bool>,
+    },
+    /// The model must use the named tool
+    Tool {
+        /// Name of the tool
+        name: String,
+        /// Allow at most one tool call per response
+        #[serde(skip_serializing_if = "Option::is_none")]
+        disable_parallel_tool_use: Option<bool>,
+    },
+    /// The model must not use tools
+    None,
+}
+
+impl From<&ToolConfig> for AnthropicToolChoice {
+    fn from(config: &ToolConfig) -> Self {
+        let disable_parallel_tool_use = (!config.parallel).then_some(true);
+        match &config.choice {
+            ToolChoice::Auto => Self::Auto {
+                disable_parallel_tool_use,
+            },
+            ToolChoice::Any => Self::Any {
+                disable_parallel_tool_use,
+            },
+            ToolChoice::Tool(name) => Self::Tool {
+                name: name.clone(),
+                disable_parallel_tool_use,
+            },
+            ToolChoice::None => Self::None,
+        }
+    }
 }
 
 /// Response structure from Anthropic Messages API.
@@ -52,6 +333,9 @@ pub struct MessagesResponse {
     pub model: String,
     /// Reason why the model stopped generating
     pub stop_reason: Option<String>,
+    /// Token usage for the request
+    #[serde(default)]
+    pub usage: Option<StreamUsage>,
 }
 
 /// Content block in the Anthropic response.
@@ -63,6 +347,148 @@ pub struct ContentBlock {
     /// Type of content block (e.g., "text")
     #[serde(rename = "type")]
     pub content_type: String,
-    /// The text content
+    /// The text content (empty for `tool_use` blocks)
+    #[serde(default)]
     pub text: String,
+    /// Passages supporting this text, when citations are enabled
+    #[serde(default)]
+    pub citations: Option<Vec<ResponseCitation>>,
+    /// Tool call ID, for `tool_use` blocks
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Name of the tool to call, for `tool_use` blocks
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Tool input, for `tool_use` blocks
+    #[serde(default)]
+    pub input: Option<Value>,
+}
+
+/// A citation attached to a response text block.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseCitation {
+    /// Character range in a plain-text document
+    CharLocation {
+        /// The cited text
+        cited_text: String,
+        /// Index of the cited document in the request
+        document_index: usize,
+        /// Title of the cited document
+        document_title: Option<String>,
+        /// Start character (inclusive)
+        start_char_index: usize,
+        /// End character (exclusive)
+        end_char_index: usize,
+    },
+    /// Page range in a PDF
+    PageLocation {
+        /// The cited text
+        cited_text: String,
+        /// Index of the cited document in the request
+        document_index: usize,
+        /// Title of the cited document
+        document_title: Option<String>,
+        /// Start page, starting at 1 (inclusive)
+        start_page_number: usize,
+        /// End page (exclusive)
+        end_page_number: usize,
+    },
+    /// Content block range in a custom-content document
+    ContentBlockLocation {
+        /// The cited text
+        cited_text: String,
+        /// Index of the cited document in the request
+        document_index: usize,
+        /// Title of the cited document
+        document_title: Option<String>,
+        /// Start block (inclusive)
+        start_block_index: usize,
+        /// End block (exclusive)
+        end_block_index: usize,
+    },
+    /// Any other citation type, such as web search results
+    #[serde(other)]
+    Other,
+}
+
+/// A server-sent event from a streamed Messages API response.
+///
+/// Only the events needed to reassemble the text are modelled; everything
+/// else (`message_start`, `ping`, ...) deserializes to `Other`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamPayload {
+    /// The response has started
+    MessageStart {
+        /// The (still empty) message
+        message: StreamMessage,
+    },
+    /// Incremental content for a content block
+    ContentBlockDelta {
+        /// The content added
+        delta: StreamDelta,
+    },
+    /// Top-level message changes, including cumulative usage
+    MessageDelta {
+        /// Token usage so far
+        #[serde(default)]
+        usage: Option<StreamUsage>,
+    },
+    /// The message is complete
+    MessageStop,
+    /// The server reported an error mid-stream
+    Error {
+        /// Error details
+        error: StreamError,
+    },
+    /// Any other event type
+    #[serde(other)]
+    Other,
+}
+
+/// Message metadata sent in a `message_start` event.
+#[derive(Debug, Deserialize)]
+pub struct StreamMessage {
+    /// Token usage at the start of the response
+    #[serde(default)]
+    pub usage: Option<StreamUsage>,
+}
+
+/// Token usage reported in a response.
+///
+/// In a streamed response, `message_start` carries the input tokens and
+/// `message_delta` carries the cumulative output tokens.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct StreamUsage {
+    /// Tokens in the prompt
+    #[serde(default)]
+    pub input_tokens: usize,
+    /// Tokens generated so far
+    #[serde(default)]
+    pub output_tokens: usize,
+    /// Prompt tokens written to the cache
+    #[serde(default)]
+    pub cache_creation_input_tokens: usize,
+    /// Prompt tokens read from the cache
+    #[serde(default)]
+    pub cache_read_input_tokens: usize,
+}
+
+/// Content added by a `content_block_delta` event.
+#[derive(Debug, Deserialize)]
+pub struct StreamDelta {
+    /// Text appended to the block; absent for non-text deltas
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Error reported in a streamed response.
+#[derive(Debug, Deserialize)]
+pub struct StreamError {
+    /// Error type (e.g., "overloaded_error")
+    #[serde(rename = "type")]
+    pub error_type: String,
+    /// Human-readable error message
+    pub message: String,
 }
@@ -0,0 +1,487 @@
+//! Delivery granularity and backpressure for streamed responses.
+//!
+//! Consumers of a [`TokenStream`] want text in different units: a chat UI
+//! renders chunks as they arrive, a typing animation wants one token at a
+//! time and a text-to-speech engine wants whole sentences. They also differ
+//! in what should happen when they read slower than the provider writes.
+//! [`StreamBuffering`] configures both, and [`with_buffering`] applies it
+//! to a stream.
+//!
+//! With a capacity of 0, the default, nothing is read ahead: the response
+//! is read from the provider only as fast as the consumer asks for it. A
+//! larger capacity reads ahead on a background task, and
+//! [`Backpressure`] decides what happens once that many items are waiting.
+
+use agent_core::{AgentError, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+
+use crate::streaming::{StreamEvent, TokenStream};
+
+/// The unit of text each [`StreamEvent::Text`] event carries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    /// Text chunks as the provider sends them
+    #[default]
+    Chunk,
+    /// One token at a time
+    ///
+    /// Providers do not stream token boundaries, so a token is approximated
+    /// as a word with the whitespace before it.
+    Token,
+    /// Whole sentences, ending after `.`, `!` or `?` and a whitespace
+    /// character, or after a line break
+    Sentence,
+}
+
+impl Granularity {
+    /// Byte offsets in `text` at which a complete unit ends
+    fn unit_ends(self, text: &str) -> Vec<usize> {
+        let mut ends = Vec::new();
+        let mut previous = None;
+        for (index, c) in text.char_indices() {
+            match self {
+                Self::Chunk => {}
+                Self::Token => {
+                    if c.is_whitespace() && previous.is_some_and(|p: char| !p.is_whitespace()) {
+                        ends.push(index);
+                    }
+                }
+                Self::Sentence => {
+                    if c == '\n' || (c.is_whitespace() && matches!(previous, Some('.' | '!' | '?')))
+                    {
+                        ends.push(index + c.len_utf8());
+                    }
+                }
+            }
+            previous = Some(c);
+        }
+        ends
+    }
+}
+
+/// What happens when the consumer falls `capacity` items behind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backpressure {
+    /// Stop reading from the provider until the consumer catches up
+    ///
+    /// A consumer that stalls for long enough may cause the provider to
+    /// time out the response.
+    #[default]
+    Block,
+    /// Keep reading, appending new text to the last waiting item
+    ///
+    /// Nothing is lost, but a slow consumer receives fewer, larger items.
+    Coalesce,
+    /// End the stream with an error
+    Fail,
+}
+
+/// How a streamed response is delivered to its consumer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamBuffering {
+    /// The unit of text in each event
+    pub granularity: Granularity,
+    /// Events read ahead of the consumer; 0 reads only on demand
+    pub capacity: usize,
+    /// What happens once `capacity` events are waiting
+    ///
+    /// Ignored when `capacity` is 0.
+    pub backpressure: Backpressure,
+}
+
+impl StreamBuffering {
+    /// Deliver chunks as they arrive, reading only on demand
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the unit of text in each event
+    pub fn with_granularity(mut self, granularity: Granularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    /// Read up to `capacity` events ahead of the consumer
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set what happens once `capacity` events are waiting
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+}
+
+/// Deliver `stream` as configured by `buffering`
+///
+/// Text is regrouped into units of the configured granularity; a partial
+/// unit is delivered as is before any other event and at the end of the
+/// stream. With a non-zero capacity the stream is read on a background task,
+/// which is aborted, along with the request, when the returned stream is
+/// dropped; this needs a Tokio runtime.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::Message;
+/// use futures::StreamExt;
+/// use llm::{Backpressure, Granularity, LLMProvider, StreamBuffering, StreamEvent, with_buffering};
+///
+/// # async fn example(provider: &dyn LLMProvider) -> agent_core::Result<()> {
+/// // Whole sentences for a speech engine, which may lag behind the model
+/// let buffering = StreamBuffering::new()
+///     .with_granularity(Granularity::Sentence)
+///     .with_capacity(16)
+///     .with_backpressure(Backpressure::Coalesce);
+/// let stream = provider.stream_message(&[Message::user("Tell me a story")]).await?;
+/// let mut sentences = with_buffering(stream, &buffering);
+/// while let Some(event) = sentences.next().await {
+///     if let StreamEvent::Text(sentence) = event? {
+///         println!("speak: {}", sentence);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn with_buffering(stream: TokenStream, buffering: &StreamBuffering) -> TokenStream {
+    let stream = match buffering.granularity {
+        Granularity::Chunk => stream,
+        granularity => regroup(stream, granularity),
+    };
+    if buffering.capacity == 0 {
+        return stream;
+    }
+    read_ahead(stream, buffering.capacity, buffering.backpressure)
+}
+
+/// Regroup the text of `stream` into units of `granularity`
+fn regroup(stream: TokenStream, granularity: Granularity) -> TokenStream {
+    struct State {
+        inner: Option<TokenStream>,
+        granularity: Granularity,
+        partial: String,
+        ready: VecDeque<Result<StreamEvent>>,
+    }
+
+    impl State {
+        /// Queue the partial unit, if any
+        fn flush(&mut self) {
+            if !self.partial.is_empty() {
+                let text = std::mem::take(&mut self.partial);
+                self.ready.push_back(Ok(StreamEvent::Text(text)));
+            }
+        }
+    }
+
+    let state = State {
+        inner: Some(stream),
+        granularity,
+        partial: String::new(),
+        ready: VecDeque::new(),
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.ready.pop_front() {
+                return Some((event, state));
+            }
+            let Some(event) = state.inner.as_mut()?.next().await else {
+                state.inner = None;
+                state.flush();
+                continue;
+            };
+            match event {
+                Ok(StreamEvent::Text(chunk)) => {
+                    state.partial.push_str(&chunk);
+                    let mut start = 0;
+                    for end in state.granularity.unit_ends(&state.partial) {
+                        let unit = state.partial[start..end].to_string();
+                        state.ready.push_back(Ok(StreamEvent::Text(unit)));
+                        start = end;
+                    }
+                    state.partial.drain(..start);
+                }
+                other => {
+                    state.flush();
+                    state.ready.push_back(other);
+                }
+            }
+        }
+    }))
+}
+
+/// Events read ahead of the consumer
+#[derive(Default)]
+struct Buffer {
+    queue: Mutex<Queue>,
+    /// Signalled when an event is queued or the stream ends
+    readable: Notify,
+    /// Signalled when the consumer takes an event
+    writable: Notify,
+}
+
+#[derive(Default)]
+struct Queue {
+    events: VecDeque<Result<StreamEvent>>,
+    done: bool,
+}
+
+/// Aborts the reading task when the consumer drops the stream
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Read `stream` on a background task, up to `capacity` events ahead
+fn read_ahead(mut stream: TokenStream, capacity: usize, backpressure: Backpressure) -> TokenStream {
+    let buffer = Arc::new(Buffer::default());
+
+    let writer = buffer.clone();
+    let task = tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            loop {
+                {
+                    let mut queue = writer.queue.lock().unwrap();
+                    if queue.events.len() < capacity {
+                        queue.events.push_back(event);
+                        break;
+                    }
+                    match backpressure {
+                        Backpressure::Block => {}
+                        Backpressure::Coalesce => {
+                            if let (
+                                Ok(StreamEvent::Text(text)),
+                                Some(Ok(StreamEvent::Text(last))),
+                            ) = (&event, queue.events.back_mut())
+                            {
+                                last.push_str(text);
+                            } else {
+                                // Only text is merged; other events are rare
+                                // enough to overfill the buffer
+                                queue.events.push_back(event);
+                            }
+                            break;
+                        }
+                        Backpressure::Fail => {
+                            queue.events.push_back(Err(AgentError::LLMProvider(format!(
+                                "Stream consumer fell {} events behind",
+                                capacity
+                            ))));
+                            queue.done = true;
+                            drop(queue);
+                            // Dropping the stream aborts the request
+                            writer.readable.notify_one();
+                            return;
+                        }
+                    }
+                }
+                writer.writable.notified().await;
+            }
+            writer.readable.notify_one();
+        }
+        writer.queue.lock().unwrap().done = true;
+        writer.readable.notify_one();
+    });
+
+    let state = (buffer, AbortOnDrop(task.abort_handle()));
+    Box::pin(stream::unfold(state, |(buffer, guard)| async move {
+        loop {
+            {
+                let mut queue = buffer.queue.lock().unwrap();
+                if let Some(event) = queue.events.pop_front() {
+                    drop(queue);
+                    buffer.writable.notify_one();
+                    return Some((event, (buffer, guard)));
+                }
+                if queue.done {
+                    return None;
+                }
+            }
+            buffer.readable.notified().await;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::{TokenUsage, collect_text};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn chunks(chunks: &[&str]) -> TokenStream {
+        let events: Vec<Result<StreamEvent>> = chunks
+            .iter()
+            .map(|chunk| Ok(StreamEvent::Text(chunk.to_string())))
+            .collect();
+        Box::pin(stream::iter(events))
+    }
+
+    async fn texts(stream: TokenStream) -> Vec<String> {
+        stream
+            .filter_map(|event| async move {
+                match event.unwrap() {
+                    StreamEvent::Text(text) => Some(text),
+                    _ => None,
+                }
+            })
+            .collect()
+            .await
+    }
+
+    /// Stream of `count` one-character chunks that counts how many were read
+    fn counted(count: usize, read: Arc<AtomicUsize>) -> TokenStream {
+        Box::pin(stream::iter(0..count).map(move |_| {
+            read.fetch_add(1, Ordering::SeqCst);
+            Ok(StreamEvent::Text("x".to_string()))
+        }))
+    }
+
+    /// Wait until the reading task has stopped making progress
+    async fn settle(read: &AtomicUsize) -> usize {
+        let mut last = usize::MAX;
+        loop {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let now = read.load(Ordering::SeqCst);
+            if now == last {
+                return now;
+            }
+            last = now;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_granularity_splits_at_words() {
+        let stream = chunks(&["Hel", "lo wor", "ld,  how", " are", " you?"]);
+        let buffering = StreamBuffering::new().with_granularity(Granularity::Token);
+        let tokens = texts(with_buffering(stream, &buffering)).await;
+        assert_eq!(tokens, ["Hello", " world,", "  how", " are", " you?"]);
+    }
+
+    #[tokio::test]
+    async fn test_sentence_granularity_waits_for_whole_sentences() {
+        let usage = TokenUsage {
+            input_tokens: 3,
+            output_tokens: 9,
+        };
+        let stream: TokenStream = Box::pin(stream::iter(vec![
+            Ok(StreamEvent::Text("It is 3.5 km. Turn".to_string())),
+            Ok(StreamEvent::Text(" left! Then\nwalk".to_string())),
+            Ok(StreamEvent::Text(" on".to_string())),
+            Ok(StreamEvent::Usage(usage)),
+        ]));
+        let buffering = StreamBuffering::new().with_granularity(Granularity::Sentence);
+        let events: Vec<_> = with_buffering(stream, &buffering)
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        let text = |text: &str| StreamEvent::Text(text.to_string());
+        assert_eq!(
+            events,
+            [
+                text("It is 3.5 km. "),
+                text("Turn left! "),
+                text("Then\n"),
+                // The partial sentence is delivered before other events
+                text("walk on"),
+                StreamEvent::Usage(usage),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unbuffered_stream_reads_on_demand() {
+        let read = Arc::new(AtomicUsize::new(0));
+        let mut stream = with_buffering(counted(10, read.clone()), &StreamBuffering::new());
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(settle(&read).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_reads_ahead_up_to_capacity() {
+        let read = Arc::new(AtomicUsize::new(0));
+        let buffering = StreamBuffering::new().with_capacity(3);
+        let mut stream = with_buffering(counted(10, read.clone()), &buffering);
+
+        // Three waiting, plus one held until there is room
+        assert_eq!(settle(&read).await, 4);
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(settle(&read).await, 5);
+        assert_eq!(collect_text(stream).await.unwrap().len(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_merges_text_for_slow_consumers() {
+        let read = Arc::new(AtomicUsize::new(0));
+        let buffering = StreamBuffering::new()
+            .with_capacity(2)
+            .with_backpressure(Backpressure::Coalesce);
+        let stream = with_buffering(counted(10, read.clone()), &buffering);
+
+        assert_eq!(settle(&read).await, 10);
+        let texts = texts(stream).await;
+        assert_eq!(texts, ["x", "xxxxxxxxx"]);
+    }
+
+    #[tokio::test]
+    async fn test_fail_ends_the_stream_when_the_consumer_falls_behind() {
+        let read = Arc::new(AtomicUsize::new(0));
+        let buffering = StreamBuffering::new()
+            .with_capacity(2)
+            .with_backpressure(Backpressure::Fail);
+        let stream = with_buffering(counted(10, read.clone()), &buffering);
+
+        assert_eq!(settle(&read).await, 3);
+        let events: Vec<_> = stream.collect().await;
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[2], Err(AgentError::LLMProvider(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_stream_stops_reading() {
+        let read = Arc::new(AtomicUsize::new(0));
+        // Two chunks, then a response that never continues
+        let pending: TokenStream = Box::pin(stream::unfold(read.clone(), |read| async move {
+            if read.load(Ordering::SeqCst) == 2 {
+                std::future::pending::<()>().await;
+            }
+            read.fetch_add(1, Ordering::SeqCst);
+            Some((Ok(StreamEvent::Text("x".to_string())), read))
+        }));
+        let buffering = StreamBuffering::new().with_capacity(4);
+        let stream = with_buffering(pending, &buffering);
+        assert_eq!(settle(&read).await, 2);
+
+        // The reading task holds the other reference, through its stream
+        assert_eq!(Arc::strong_count(&read), 2);
+        drop(stream);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(Arc::strong_count(&read), 1);
+    }
+
+    #[test]
+    fn test_configuration_deserializes_with_defaults() {
+        let buffering: StreamBuffering =
+            serde_json::from_str(r#"{"granularity": "sentence", "capacity": 8}"#).unwrap();
+        assert_eq!(
+            buffering,
+            StreamBuffering::new()
+                .with_granularity(Granularity::Sentence)
+                .with_capacity(8)
+        );
+        assert_eq!(buffering.backpressure, Backpressure::Block);
+    }
+}
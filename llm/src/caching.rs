@@ -0,0 +1,491 @@
+//! Response caching for repeated identical requests.
+//!
+//! Deterministic test suites and agents that re-plan with the same prompt
+//! send the same request over and over. [`CachingProvider`] answers repeats
+//! from a [`ResponseCache`] instead of the model: [`InMemoryResponseCache`]
+//! for one process, or [`DiskResponseCache`] to share answers between runs.
+//! Entries expire after an optional time-to-live.
+
+use agent_core::{Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::coalescing::CoalescingProvider;
+use crate::{
+    CompletionResponse, FinishReason, LLMProvider, StructuredOutput, TextStream, TokenStream,
+    ToolConfig,
+};
+
+/// Default maximum number of responses kept in memory
+const DEFAULT_MAX_ENTRIES: usize = 1_024;
+
+/// Counter making the temporary file of each disk cache write unique
+static TEMPORARY_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// A response as stored in a cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// The full request key, to tell apart requests whose hashes collide
+    pub key: String,
+    /// The response text
+    pub text: String,
+    /// Why the model stopped
+    pub finish_reason: FinishReason,
+    /// The model that generated the response, if reported
+    pub model: Option<String>,
+    /// When the response was stored
+    pub stored_at: DateTime<Utc>,
+}
+
+/// Storage for cached responses, addressed by a hash of the request
+#[async_trait]
+pub trait ResponseCache: Send + Sync {
+    /// The response stored under `hash`, if any
+    async fn get(&self, hash: &str) -> Result<Option<CachedResponse>>;
+
+    /// Store a response under `hash`, replacing any previous one
+    async fn put(&self, hash: &str, response: CachedResponse) -> Result<()>;
+}
+
+/// Response cache that lives as long as the process
+///
+/// When full, the oldest response is dropped to make room.
+#[derive(Debug)]
+pub struct InMemoryResponseCache {
+    max_entries: usize,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl InMemoryResponseCache {
+    /// Create a cache holding up to 1,024 responses
+    pub fn new() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limit the number of responses kept
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Number of stored responses, including expired ones
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Check if no responses are stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for InMemoryResponseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ResponseCache for InMemoryResponseCache {
+    async fn get(&self, hash: &str) -> Result<Option<CachedResponse>> {
+        Ok(self.entries.lock().unwrap().get(hash).cloned())
+    }
+
+    async fn put(&self, hash: &str, response: CachedResponse) -> Result<()> {
+        if self.max_entries == 0 {
+            return Ok(());
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(hash) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, response)| response.stored_at)
+                .map(|(hash, _)| hash.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(hash.to_string(), response);
+        Ok(())
+    }
+}
+
+/// Response cache that keeps one JSON file per response in a directory
+///
+/// Files outlive the process, so a test suite can replay the answers of a
+/// previous run. Expired files are replaced when the request is next sent,
+/// not deleted. File names are SHA-256 hashes of the request, so entries
+/// stay valid across toolchain upgrades and machines.
+#[derive(Debug, Clone)]
+pub struct DiskResponseCache {
+    dir: PathBuf,
+}
+
+impl DiskResponseCache {
+    /// Store responses in `dir`, which is created on first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", hash))
+    }
+}
+
+#[async_trait]
+impl ResponseCache for DiskResponseCache {
+    async fn get(&self, hash: &str) -> Result<Option<CachedResponse>> {
+        match tokio::fs::read(self.path(hash)).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, hash: &str, response: CachedResponse) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // Write then rename, so concurrent readers never see half a file.
+        // Each write gets its own temporary file, so concurrent writers of
+        // the same entry (in this or another process) never interleave.
+        let temporary = self.dir.join(format!(
+            "{}.json.{}-{}.tmp",
+            hash,
+            std::process::id(),
+            TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temporary, serde_json::to_vec(&response)?).await?;
+        tokio::fs::rename(&temporary, self.path(hash)).await?;
+        Ok(())
+    }
+}
+
+/// Provider wrapper that serves repeated identical requests from a cache
+///
+/// Requests are identical when they have the same roles, contents,
+/// attachments and images, in order, and the same namespace. The wrapped
+/// provider's model and parameters are not visible to the wrapper, so give
+/// providers that share a cache different namespaces, e.g.
+/// `"openai/gpt-4o/t=0"`. Message timestamps are ignored. Failed requests
+/// are never cached, and a cache that can't be read or written is treated
+/// as a miss.
+///
+/// Responses served from the cache report no token usage, since nothing
+/// was billed for them. Structured, tool-calling and streaming requests are
+/// passed through uncached. Caching only makes sense where a repeated
+/// answer is acceptable, typically at temperature 0.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{CachingProvider, DiskResponseCache, LLMProvider, OpenAIProvider};
+/// use agent_core::Message;
+/// use std::time::Duration;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = CachingProvider::new(
+///     OpenAIProvider::builder().api_key("your-api-key").build()?,
+///     DiskResponseCache::new("target/llm-cache"),
+/// )
+/// .with_namespace("openai/gpt-3.5-turbo")
+/// .with_ttl(Duration::from_secs(24 * 60 * 60));
+///
+/// let reply = provider.send_message(&[Message::user("Name a prime")]).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CachingProvider<P> {
+    inner: P,
+    cache: Box<dyn ResponseCache>,
+    namespace: String,
+    ttl: Option<Duration>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<P: LLMProvider> CachingProvider<P> {
+    /// Wrap a provider; cached responses never expire
+    pub fn new(inner: P, cache: impl ResponseCache + 'static) -> Self {
+        Self {
+            inner,
+            cache: Box::new(cache),
+            namespace: String::new(),
+            ttl: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the namespace that separates this provider's entries from those
+    /// of other providers sharing the cache
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Set how long responses are reused
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Number of requests answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of requests sent to the wrapped provider
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// The full key of a request
+    fn request_key(&self, messages: &[Message]) -> String {
+        format!(
+            "{}:{}\n{}",
+            self.namespace.len(),
+            self.namespace,
+            CoalescingProvider::<P>::request_key(messages)
+        )
+    }
+
+    fn is_fresh(&self, response: &CachedResponse) -> bool {
+        match self.ttl {
+            Some(ttl) => (Utc::now() - response.stored_at)
+                .to_std()
+                .is_ok_and(|age| age < ttl),
+            None => true,
+        }
+    }
+}
+
+/// SHA-256 of the key, which stays the same across builds and platforms
+fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for CachingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        Ok(self.send_completion(messages).await?.text)
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let key = self.request_key(messages);
+        let hash = hash(&key);
+
+        // A cache that can't be read is treated as empty, so a broken cache
+        // never fails a request
+        if let Ok(Some(cached)) = self.cache.get(&hash).await
+            && cached.key == key
+            && self.is_fresh(&cached)
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            let response = CompletionResponse::new(cached.text, cached.finish_reason);
+            return Ok(match cached.model {
+                Some(model) => response.with_model(model),
+                None => response,
+            });
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let response = self.inner.send_completion(messages).await?;
+        let cached = CachedResponse {
+            key,
+            text: response.text.clone(),
+            finish_reason: response.finish_reason.clone(),
+            model: response.model.clone(),
+            stored_at: Utc::now(),
+        };
+        // The response is still good if it can't be cached
+        let _ = self.cache.put(&hash, cached).await;
+        Ok(response)
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner.send_message_with_tools(messages, tools, config).await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.inner.stream_message(messages).await
+    }
+
+    async fn send_message_stream(&self, messages: &[Message]) -> Result<TextStream> {
+        self.inner.send_message_stream(messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenUsage;
+    use agent_core::{AgentError, ImageContent};
+    use std::sync::atomic::AtomicUsize;
+
+    struct Counter {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Counter {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(self.send_completion(messages).await?.text)
+        }
+
+        async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if messages[0].content == "fail" {
+                return Err(AgentError::LLMProvider("down".to_string()));
+            }
+            Ok(
+                CompletionResponse::new(format!("answer {}", call), FinishReason::Stop)
+                    .with_model("m-1")
+                    .with_usage(TokenUsage {
+                        input_tokens: 10,
+                        output_tokens: 2,
+                    }),
+            )
+        }
+    }
+
+    fn counter() -> Counter {
+        Counter {
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeats_are_served_from_cache() {
+        let provider = CachingProvider::new(counter(), InMemoryResponseCache::new());
+        let question = [Message::system("Be terse"), Message::user("Hi")];
+
+        let first = provider.send_completion(&question).await.unwrap();
+        let second = provider.send_completion(&question).await.unwrap();
+        assert_eq!(first.text, "answer 0");
+        assert_eq!(second.text, "answer 0");
+        assert_eq!(second.model.as_deref(), Some("m-1"));
+        assert_eq!(second.usage, None);
+
+        let other = provider.send_message(&[Message::user("Hi")]).await.unwrap();
+        assert_eq!(other, "answer 1");
+        assert!(
+            provider
+                .send_message(&[Message::user("fail")])
+                .await
+                .is_err()
+        );
+        assert!(
+            provider
+                .send_message(&[Message::user("fail")])
+                .await
+                .is_err()
+        );
+        assert_eq!((provider.hits(), provider.misses()), (1, 4));
+    }
+
+    #[tokio::test]
+    async fn test_ttl_and_namespace() {
+        let cache = InMemoryResponseCache::new();
+        let provider = CachingProvider::new(counter(), cache).with_ttl(Duration::ZERO);
+        provider.send_message(&[Message::user("Hi")]).await.unwrap();
+        let again = provider.send_message(&[Message::user("Hi")]).await.unwrap();
+        assert_eq!(again, "answer 1");
+
+        let a = CachingProvider::new(counter(), InMemoryResponseCache::new()).with_namespace("a");
+        let b = CachingProvider::new(counter(), InMemoryResponseCache::new()).with_namespace("b");
+        let messages = [Message::user("Hi")];
+        assert_ne!(a.request_key(&messages), b.request_key(&messages));
+    }
+
+    #[tokio::test]
+    async fn test_requests_differing_only_in_image_are_not_shared() {
+        let provider = CachingProvider::new(counter(), InMemoryResponseCache::new());
+        let cat = [Message::user("Describe this")
+            .with_image(ImageContent::url("https://example.com/cat.png"))];
+        let dog = [Message::user("Describe this")
+            .with_image(ImageContent::url("https://example.com/dog.png"))];
+
+        assert_eq!(provider.send_message(&cat).await.unwrap(), "answer 0");
+        assert_eq!(provider.send_message(&dog).await.unwrap(), "answer 1");
+        assert_eq!(provider.send_message(&cat).await.unwrap(), "answer 0");
+        assert_eq!((provider.hits(), provider.misses()), (1, 2));
+    }
+
+    #[test]
+    fn test_hash_is_stable_sha256() {
+        assert_eq!(
+            hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_survives_new_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = [Message::user("Hi")];
+
+        let first = CachingProvider::new(counter(), DiskResponseCache::new(dir.path()));
+        assert_eq!(first.send_message(&messages).await.unwrap(), "answer 0");
+
+        let second = CachingProvider::new(counter(), DiskResponseCache::new(dir.path()));
+        let response = second.send_completion(&messages).await.unwrap();
+        assert_eq!(response.text, "answer 0");
+        assert_eq!(response.finish_reason, FinishReason::Stop);
+        assert_eq!(second.inner().calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_evicts_oldest() {
+        let cache = InMemoryResponseCache::new().with_max_entries(1);
+        let response = |key: &str| CachedResponse {
+            key: key.to_string(),
+            text: String::new(),
+            finish_reason: FinishReason::Stop,
+            model: None,
+            stored_at: Utc::now(),
+        };
+        cache.put("a", response("a")).await.unwrap();
+        cache.put("b", response("b")).await.unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("a").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,69 @@
+//! Cancelling in-flight requests.
+//!
+//! A [`CancellationToken`] is shared between the code running a request and
+//! whoever may want to stop it, such as a UI stop button or a shutdown
+//! handler. [`cancellable`] races a future against the token; when the token
+//! is cancelled first, the future is dropped, which drops any HTTP request
+//! it was waiting on, and [`AgentError::Cancelled`] is returned.
+
+use agent_core::{AgentError, Result};
+use std::future::Future;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Run `future` until it completes or `cancel` is cancelled
+///
+/// A token that is already cancelled fails without polling the future.
+///
+/// # Errors
+/// Returns [`AgentError::Cancelled`] if the token is cancelled first, or
+/// the future's own error.
+///
+/// # Example
+///
+/// ```
+/// use llm::{CancellationToken, cancellable};
+///
+/// # async fn example() {
+/// let cancel = CancellationToken::new();
+/// cancel.cancel();
+/// let result = cancellable(&cancel, async { Ok(42) }).await;
+/// assert!(matches!(result, Err(agent_core::AgentError::Cancelled(_))));
+/// # }
+/// ```
+pub async fn cancellable<T>(
+    cancel: &CancellationToken,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(AgentError::Cancelled("request was cancelled".to_string())),
+        result = future => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_drops_pending_future() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            trigger.cancel();
+        });
+
+        let pending = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok("too late")
+        };
+        let result = cancellable(&cancel, pending).await;
+        assert!(matches!(result, Err(AgentError::Cancelled(_))));
+
+        let fresh = CancellationToken::new();
+        assert_eq!(cancellable(&fresh, async { Ok(1) }).await.unwrap(), 1);
+    }
+}
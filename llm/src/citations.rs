@@ -0,0 +1,165 @@
+//! Grounded answers with source citations.
+//!
+//! Documents passed to [`AnthropicProvider::send_with_citations`] are sent
+//! with citations enabled; the answer comes back as a [`CitedResponse`]
+//! whose segments each carry the passages that support them.
+//!
+//! [`AnthropicProvider::send_with_citations`]: crate::AnthropicProvider::send_with_citations
+
+use agent_core::FileRef;
+
+/// A document the model may cite
+#[derive(Debug, Clone, PartialEq)]
+pub struct CitationDocument {
+    /// Title shown to the model and echoed back in citations
+    pub title: Option<String>,
+    /// Document contents
+    pub source: DocumentSource,
+}
+
+/// Where a [`CitationDocument`]'s contents come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentSource {
+    /// Inline plain text
+    Text(String),
+    /// A file uploaded through a [`FileStore`](crate::FileStore)
+    File(FileRef),
+}
+
+impl CitationDocument {
+    /// A plain-text document
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            title: None,
+            source: DocumentSource::Text(content.into()),
+        }
+    }
+
+    /// A previously uploaded file, such as a PDF
+    pub fn file(file: FileRef) -> Self {
+        Self {
+            title: None,
+            source: DocumentSource::File(file),
+        }
+    }
+
+    /// Set the document title
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+/// The document a citation points into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CitationSource {
+    /// Position of the document in the list passed with the request
+    pub document_index: usize,
+    /// Title of the document, if it had one
+    pub title: Option<String>,
+}
+
+/// Location of a cited passage within its document
+///
+/// Ranges are half-open: `start` is inclusive and `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitationSpan {
+    /// Character offsets into a plain-text document
+    Chars { start: usize, end: usize },
+    /// Page numbers (starting at 1) in a PDF
+    Pages { start: usize, end: usize },
+    /// Content block indices in a custom-content document
+    Blocks { start: usize, end: usize },
+}
+
+/// A passage quoted from a source document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    /// The document the quote comes from
+    pub source: CitationSource,
+    /// The exact text being cited
+    pub quote: String,
+    /// Where the quote sits in the document
+    pub span: CitationSpan,
+}
+
+/// A piece of the answer together with the citations backing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CitedText {
+    /// Answer text
+    pub text: String,
+    /// Passages supporting this text; empty for uncited text
+    pub citations: Vec<Citation>,
+}
+
+/// A model answer grounded in source documents
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CitedResponse {
+    /// The answer, split into segments at citation boundaries
+    pub segments: Vec<CitedText>,
+}
+
+impl CitedResponse {
+    /// The full answer text
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect()
+    }
+
+    /// Every citation in the answer, in order
+    pub fn citations(&self) -> impl Iterator<Item = &Citation> {
+        self.segments.iter().flat_map(|segment| &segment.citations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn citation(quote: &str) -> Citation {
+        Citation {
+            source: CitationSource {
+                document_index: 0,
+                title: None,
+            },
+            quote: quote.to_string(),
+            span: CitationSpan::Chars {
+                start: 0,
+                end: quote.len(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_response_text_and_citations() {
+        let response = CitedResponse {
+            segments: vec![
+                CitedText {
+                    text: "The sky is blue".to_string(),
+                    citations: vec![citation("sky is blue")],
+                },
+                CitedText {
+                    text: " and ".to_string(),
+                    citations: Vec::new(),
+                },
+                CitedText {
+                    text: "grass is green.".to_string(),
+                    citations: vec![citation("grass is green")],
+                },
+            ],
+        };
+
+        assert_eq!(response.text(), "The sky is blue and grass is green.");
+        let quotes: Vec<_> = response.citations().map(|c| c.quote.as_str()).collect();
+        assert_eq!(quotes, vec!["sky is blue", "grass is green"]);
+    }
+
+    #[test]
+    fn test_document_builders() {
+        let doc = CitationDocument::text("Body").with_title("Notes");
+        assert_eq!(doc.title.as_deref(), Some("Notes"));
+        assert_eq!(doc.source, DocumentSource::Text("Body".to_string()));
+    }
+}
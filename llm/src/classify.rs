@@ -0,0 +1,230 @@
+//! Classification of text into a fixed set of labels.
+//!
+//! [`classify`] asks for structured output whose `label` is an `enum` of the
+//! allowed label names, so local backends constrain decoding to them with a
+//! grammar and other providers have any other answer sent back for repair.
+//! The result maps back to the caller's own label type, usually an enum.
+
+use agent_core::{AgentError, Message, Result};
+use serde_json::json;
+
+use crate::{LLMProvider, StructuredOutput};
+
+/// A label text can be classified as
+///
+/// # Example
+///
+/// ```
+/// use llm::ClassLabel;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Sentiment {
+///     Positive,
+///     Negative,
+///     Neutral,
+/// }
+///
+/// impl ClassLabel for Sentiment {
+///     fn name(&self) -> &str {
+///         match self {
+///             Sentiment::Positive => "positive",
+///             Sentiment::Negative => "negative",
+///             Sentiment::Neutral => "neutral",
+///         }
+///     }
+/// }
+/// ```
+pub trait ClassLabel: Clone {
+    /// Name the model answers with; must be unique among the labels
+    fn name(&self) -> &str;
+
+    /// What the label means, if the name alone is ambiguous
+    fn description(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl ClassLabel for &str {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+impl ClassLabel for String {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+/// The label chosen for a text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification<L> {
+    /// The chosen label
+    pub label: L,
+    /// The model's confidence in the label, from 0.0 to 1.0
+    ///
+    /// Self-reported by the model, so useful for thresholds and triage
+    /// rather than as a calibrated probability.
+    pub confidence: f32,
+}
+
+/// Classify `text` as one of `labels`
+///
+/// # Errors
+/// Returns an error if `labels` is empty, if the request fails, or if the
+/// model still answers with an unknown label after the repair attempts.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{OpenAIProvider, classify};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OpenAIProvider::builder().api_key("your-api-key").build()?;
+/// let labels = ["complaint", "praise", "question"];
+/// let result = classify(&provider, "The package arrived broken", &labels).await?;
+/// if result.label == "complaint" && result.confidence > 0.8 {
+///     println!("Escalating");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn classify<L, P>(provider: &P, text: &str, labels: &[L]) -> Result<Classification<L>>
+where
+    L: ClassLabel,
+    P: LLMProvider + ?Sized,
+{
+    if labels.is_empty() {
+        return Err(AgentError::Config(
+            "At least one label is required to classify text".to_string(),
+        ));
+    }
+
+    let names: Vec<&str> = labels.iter().map(ClassLabel::name).collect();
+    let output = StructuredOutput::new(json!({
+        "type": "object",
+        "properties": {
+            "label": {"type": "string", "enum": names},
+            "confidence": {"type": "number"}
+        },
+        "required": ["label", "confidence"],
+        "additionalProperties": false
+    }));
+
+    let mut prompt = String::from(
+        "Classify the text below as exactly one of these labels, and rate your confidence \
+         from 0.0 to 1.0.\n\nLabels:\n",
+    );
+    for label in labels {
+        match label.description() {
+            Some(description) => prompt.push_str(&format!("- {}: {}\n", label.name(), description)),
+            None => prompt.push_str(&format!("- {}\n", label.name())),
+        }
+    }
+    prompt.push_str(&format!("\n<text>\n{}\n</text>", text));
+
+    let value = provider
+        .send_structured(&[Message::user(prompt)], &output)
+        .await?;
+
+    let name = value["label"].as_str().unwrap_or_default();
+    let label = labels
+        .iter()
+        .find(|label| label.name() == name)
+        .cloned()
+        .ok_or_else(|| {
+            AgentError::LLMProvider(format!("Model answered unknown label '{}'", name))
+        })?;
+    let confidence = value["confidence"].as_f64().unwrap_or(0.0).clamp(0.0, 1.0) as f32;
+
+    Ok(Classification { label, confidence })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Intent {
+        Refund,
+        Shipping,
+    }
+
+    impl ClassLabel for Intent {
+        fn name(&self) -> &str {
+            match self {
+                Intent::Refund => "refund",
+                Intent::Shipping => "shipping",
+            }
+        }
+
+        fn description(&self) -> Option<&str> {
+            match self {
+                Intent::Refund => Some("The customer wants their money back"),
+                Intent::Shipping => None,
+            }
+        }
+    }
+
+    /// Replies with scripted responses in order and records the requests
+    struct Scripted {
+        replies: Mutex<Vec<&'static str>>,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl Scripted {
+        fn new(replies: Vec<&'static str>) -> Self {
+            Self {
+                replies: Mutex::new(replies),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.replies.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_classify_returns_typed_label() {
+        let provider = Scripted::new(vec![
+            r#"{"label": "returns", "confidence": 0.9}"#,
+            r#"{"label": "refund", "confidence": 1.4}"#,
+        ]);
+
+        let result = classify(
+            &provider,
+            "I want my money back",
+            &[Intent::Refund, Intent::Shipping],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            result,
+            Classification {
+                label: Intent::Refund,
+                confidence: 1.0,
+            }
+        );
+
+        // The label outside the enum was sent back for repair
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let prompt = &requests[0][1].content;
+        assert!(prompt.contains("- refund: The customer wants their money back\n- shipping\n"));
+    }
+
+    #[tokio::test]
+    async fn test_classify_requires_labels() {
+        let provider = Scripted::new(vec![]);
+        let labels: [&str; 0] = [];
+        let result = classify(&provider, "Hello", &labels).await;
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+}
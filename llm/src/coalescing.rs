@@ -0,0 +1,456 @@
+//! Single-flight request coalescing for LLM providers.
+//!
+//! Agent topologies that fan out sub-tasks frequently ask the same question
+//! several times at once. [`CoalescingProvider`] makes concurrent identical
+//! requests share a single upstream call: the first caller performs the
+//! request and every caller that arrives while it is in flight receives a copy
+//! of the same result.
+
+use agent_core::{AgentError, Message, Result, Role, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+use crate::{
+    CompletionResponse, FinishReason, LLMProvider, StructuredOutput, TokenStream, ToolConfig,
+};
+
+/// Result shared between coalesced callers.
+///
+/// `AgentError` is not `Clone`, so the error is shared behind an `Arc` and
+/// rebuilt, variant and all, for every waiting caller.
+type SharedResult = std::result::Result<CompletionResponse, Arc<AgentError>>;
+
+/// Provider wrapper that deduplicates concurrent identical requests.
+///
+/// Two requests are considered identical when they carry the same sequence of
+/// roles and contents, including attached files and images. Provider
+/// parameters (model, temperature, max tokens) are fixed by the wrapped
+/// provider, so they are implicitly part of the key. Message timestamps are
+/// ignored.
+///
+/// Plain messages and completions are coalesced. Structured, tool-calling
+/// and streaming requests are passed straight to the wrapped provider.
+///
+/// Only *concurrent* requests are coalesced: once the upstream call completes,
+/// the next identical request triggers a fresh call. Use a caching layer if
+/// results should be reused over time.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{CoalescingProvider, LLMProvider, OpenAIProvider};
+/// use agent_core::Message;
+/// # use config::LLMConfig;
+///
+/// # async fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// let provider = CoalescingProvider::new(OpenAIProvider::new(&config)?);
+/// let messages = vec![Message::user("What is the capital of France?")];
+///
+/// // Both calls share one upstream request
+/// let (a, b) = tokio::join!(
+///     provider.send_message(&messages),
+///     provider.send_message(&messages),
+/// );
+/// assert_eq!(a?, b?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CoalescingProvider<P: LLMProvider> {
+    inner: P,
+    in_flight: Mutex<HashMap<String, Arc<OnceCell<SharedResult>>>>,
+}
+
+impl<P: LLMProvider> CoalescingProvider<P> {
+    /// Wrap a provider so that concurrent identical requests are coalesced
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a reference to the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Number of distinct requests currently in flight
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    /// Join the in-flight request under `key`, or become its leader and
+    /// run `call`
+    async fn coalesce<F, Fut>(&self, key: String, call: F) -> Result<CompletionResponse>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CompletionResponse>>,
+    {
+        let cell = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            in_flight.entry(key.clone()).or_default().clone()
+        };
+
+        // OnceCell guarantees that only one initializer runs at a time; if the
+        // leader is cancelled, one of the waiters takes over the upstream call
+        let result = cell
+            .get_or_init(|| async {
+                let result = call().await.map_err(Arc::new);
+
+                // Later identical requests should go upstream again
+                self.in_flight.lock().unwrap().remove(&key);
+
+                result
+            })
+            .await
+            .clone();
+
+        result.map_err(|error| rebuild_error(&error))
+    }
+
+    /// Build the deduplication key for a message sequence
+    ///
+    /// The key covers each message's role, text, attached files and images.
+    pub(crate) fn request_key(messages: &[Message]) -> String {
+        let mut key = String::new();
+        for message in messages {
+            let role = match message.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            };
+            // Length-prefix every part so that different splits of the same
+            // text across messages or parts never produce the same key
+            key.push_str(&format!("{}:{}:{}\n", role, message.content.len(), message.content));
+            for file in &message.attachments {
+                key.push_str(&format!(
+                    "file:{}:{}:{}:{}\n",
+                    file.id.len(),
+                    file.id,
+                    file.mime_type.len(),
+                    file.mime_type
+                ));
+            }
+            for image in &message.images {
+                let url = image.to_url();
+                key.push_str(&format!("image:{}:{}\n", url.len(), url));
+            }
+        }
+        key
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for CoalescingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let response = self
+            .coalesce(Self::request_key(messages), || async {
+                let text = self.inner.send_message(messages).await?;
+                Ok(CompletionResponse::new(text, FinishReason::Unknown))
+            })
+            .await?;
+        Ok(response.text)
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        // Kept apart from plain messages, whose shared response has no usage
+        let key = format!("completion\n{}", Self::request_key(messages));
+        self.coalesce(key, || self.inner.send_completion(messages))
+            .await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner.send_message_with_tools(messages, tools, config).await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.inner.stream_message(messages).await
+    }
+}
+
+/// A copy of `error` with the same variant and fields
+///
+/// IO and serialization errors keep their kind and message but not their
+/// source, which cannot be cloned.
+pub(crate) fn rebuild_error(error: &AgentError) -> AgentError {
+    match error {
+        AgentError::Config(message) => AgentError::Config(message.clone()),
+        AgentError::LLMProvider(message) => AgentError::LLMProvider(message.clone()),
+        AgentError::RateLimited {
+            message,
+            retry_after,
+        } => AgentError::RateLimited {
+            message: message.clone(),
+            retry_after: *retry_after,
+        },
+        AgentError::AuthFailed(message) => AgentError::AuthFailed(message.clone()),
+        AgentError::ContextLengthExceeded(message) => {
+            AgentError::ContextLengthExceeded(message.clone())
+        }
+        AgentError::ContentFiltered(message) => AgentError::ContentFiltered(message.clone()),
+        AgentError::Timeout(message) => AgentError::Timeout(message.clone()),
+        AgentError::ToolExecution { tool_name, reason } => AgentError::ToolExecution {
+            tool_name: tool_name.clone(),
+            reason: reason.clone(),
+        },
+        AgentError::ToolFailed { tool, source } => AgentError::ToolFailed {
+            tool: tool.clone(),
+            source: Box::new(rebuild_error(source)),
+        },
+        AgentError::ServiceDegraded(message) => AgentError::ServiceDegraded(message.clone()),
+        AgentError::GuardrailViolation(message) => {
+            AgentError::GuardrailViolation(message.clone())
+        }
+        AgentError::Memory(message) => AgentError::Memory(message.clone()),
+        AgentError::Planning(message) => AgentError::Planning(message.clone()),
+        AgentError::Execution(message) => AgentError::Execution(message.clone()),
+        AgentError::Cancelled(message) => AgentError::Cancelled(message.clone()),
+        AgentError::BudgetExceeded(message) => AgentError::BudgetExceeded(message.clone()),
+        AgentError::Io(error) => {
+            AgentError::Io(std::io::Error::new(error.kind(), error.to_string()))
+        }
+        AgentError::Serialization(error) => {
+            AgentError::Serialization(serde::de::Error::custom(error.to_string()))
+        }
+        AgentError::WithContext { context, source } => AgentError::WithContext {
+            context: context.clone(),
+            source: Box::new(rebuild_error(source)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::{FileRef, ImageContent};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Mock provider that counts upstream calls and responds after a delay
+    struct SlowProvider {
+        calls: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    impl SlowProvider {
+        fn new(fail: bool) -> (Self, Arc<AtomicUsize>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            (
+                Self {
+                    calls: calls.clone(),
+                    fail,
+                },
+                calls,
+            )
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for SlowProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if self.fail {
+                return Err(AgentError::LLMProvider("upstream unavailable".to_string()));
+            }
+            Ok(format!("call {}: {}", call, messages.last().unwrap().content))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_identical_requests_share_one_call() {
+        let (inner, calls) = SlowProvider::new(false);
+        let provider = CoalescingProvider::new(inner);
+        let messages = vec![Message::user("same question")];
+
+        let (a, b, c) = tokio::join!(
+            provider.send_message(&messages),
+            provider.send_message(&messages),
+            provider.send_message(&messages),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(a.unwrap(), "call 0: same question");
+        assert_eq!(b.unwrap(), "call 0: same question");
+        assert_eq!(c.unwrap(), "call 0: same question");
+        assert_eq!(provider.in_flight_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_completions_are_coalesced_apart_from_messages() {
+        let (inner, calls) = SlowProvider::new(false);
+        let provider = CoalescingProvider::new(inner);
+        let messages = vec![Message::user("same question")];
+
+        let (a, b, c) = tokio::join!(
+            provider.send_completion(&messages),
+            provider.send_completion(&messages),
+            provider.send_message(&messages),
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(a.unwrap().text, b.unwrap().text);
+        assert!(c.unwrap().ends_with("same question"));
+        assert_eq!(provider.in_flight_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_different_requests_are_not_coalesced() {
+        let (inner, calls) = SlowProvider::new(false);
+        let provider = CoalescingProvider::new(inner);
+        let first = vec![Message::user("first")];
+        let second = vec![Message::user("second")];
+
+        let (a, b) = tokio::join!(provider.send_message(&first), provider.send_message(&second));
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(a.unwrap().ends_with("first"));
+        assert!(b.unwrap().ends_with("second"));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_requests_go_upstream_again() {
+        let (inner, calls) = SlowProvider::new(false);
+        let provider = CoalescingProvider::new(inner);
+        let messages = vec![Message::user("repeat")];
+
+        provider.send_message(&messages).await.unwrap();
+        provider.send_message(&messages).await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_shared_with_waiters() {
+        let (inner, calls) = SlowProvider::new(true);
+        let provider = CoalescingProvider::new(inner);
+        let messages = vec![Message::user("will fail")];
+
+        let (a, b) = tokio::join!(provider.send_message(&messages), provider.send_message(&messages));
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        for result in [a, b] {
+            match result {
+                Err(AgentError::LLMProvider(msg)) => assert_eq!(msg, "upstream unavailable"),
+                other => panic!("Expected LLMProvider error, got {:?}", other),
+            }
+        }
+    }
+
+    /// Fails with a rate limit after a delay, counting upstream calls
+    struct RateLimitedProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for RateLimitedProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Err(AgentError::RateLimited {
+                message: "slow down".to_string(),
+                retry_after: Some(Duration::from_secs(3)),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waiters_receive_the_typed_error() {
+        let provider = CoalescingProvider::new(RateLimitedProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let messages = vec![Message::user("busy")];
+
+        let (a, b) = tokio::join!(provider.send_message(&messages), provider.send_message(&messages));
+
+        assert_eq!(provider.inner().calls.load(Ordering::SeqCst), 1);
+        for result in [a, b] {
+            let error = result.unwrap_err();
+            assert!(matches!(error, AgentError::RateLimited { .. }));
+            assert_eq!(error.retry_after(), Some(Duration::from_secs(3)));
+        }
+    }
+
+    #[test]
+    fn test_rebuilt_errors_keep_variant_and_context() {
+        let error = AgentError::Cancelled("stopped".to_string())
+            .wrap(agent_core::ErrorContext::new("agent.run"));
+        let rebuilt = rebuild_error(&error);
+        assert_eq!(rebuilt.to_string(), error.to_string());
+        assert!(matches!(rebuilt.root_cause(), AgentError::Cancelled(_)));
+
+        let io = AgentError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, "slow"));
+        assert!(rebuild_error(&io).is_retryable());
+    }
+
+    #[test]
+    fn test_request_key_distinguishes_roles_and_boundaries() {
+        let a = CoalescingProvider::<SlowProvider>::request_key(&[
+            Message::system("ab"),
+            Message::user("c"),
+        ]);
+        let b = CoalescingProvider::<SlowProvider>::request_key(&[
+            Message::system("a"),
+            Message::user("bc"),
+        ]);
+        let c = CoalescingProvider::<SlowProvider>::request_key(&[
+            Message::user("ab"),
+            Message::user("c"),
+        ]);
+
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_request_key_distinguishes_images_and_attachments() {
+        let key = |message: Message| CoalescingProvider::<SlowProvider>::request_key(&[message]);
+        let image = |url: &str| Message::user("What is this?").with_image(ImageContent::url(url));
+        let file = |id: &str| {
+            Message::user("Summarize").with_attachment(FileRef::new(id, "application/pdf"))
+        };
+
+        let cat = key(image("https://x/cat.png"));
+        assert_ne!(cat, key(image("https://x/dog.png")));
+        assert_ne!(cat, key(Message::user("What is this?")));
+        assert_ne!(key(file("file-1")), key(file("file-2")));
+    }
+
+    #[tokio::test]
+    async fn test_requests_differing_only_in_image_are_not_coalesced() {
+        let (inner, calls) = SlowProvider::new(false);
+        let provider = CoalescingProvider::new(inner);
+        let image = |url: &str| vec![Message::user("What is this?").with_image(ImageContent::url(url))];
+        let (cat, dog) = (image("https://x/cat.png"), image("https://x/dog.png"));
+
+        let (a, b) = tokio::join!(provider.send_message(&cat), provider.send_message(&dog));
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
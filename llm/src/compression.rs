@@ -0,0 +1,512 @@
+//! Prompt compression for long contexts.
+//!
+//! Retrieved documents, logs and earlier turns often carry far more text
+//! than a model needs to answer. [`PromptCompressor`] shortens them before
+//! they reach an expensive model, in the spirit of LLMLingua: each sentence
+//! is scored by the self-information of its words, estimated from how often
+//! they occur in the prompt, and the least informative sentences are dropped
+//! until the prompt fits a target share of its original size. Repeated
+//! boilerplate and filler go first; sentences with rare names, figures and
+//! terms stay.
+//!
+//! [`CompressingProvider`] runs a compressor in front of any provider and
+//! reports the tokens it saved on each [`CompletionResponse`].
+
+use agent_core::{Message, Result, Role, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::CompressionConfig;
+use memory::TokenEstimator;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig};
+
+/// Default share of a message's tokens to keep
+const DEFAULT_TARGET_RATIO: f32 = 0.5;
+
+/// Default size below which messages are left alone
+const DEFAULT_MIN_TOKENS: usize = 500;
+
+/// Words too common to carry information on their own
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "been", "but", "by", "for", "from", "had", "has",
+    "have", "he", "her", "his", "i", "in", "is", "it", "its", "of", "on", "or", "our", "she", "so",
+    "that", "the", "their", "them", "there", "they", "this", "to", "was", "we", "were", "which",
+    "will", "with", "would", "you", "your",
+];
+
+/// Prompt tokens before and after compression
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionUsage {
+    /// Estimated prompt tokens before compression
+    pub original_tokens: usize,
+    /// Estimated prompt tokens sent
+    pub compressed_tokens: usize,
+}
+
+impl CompressionUsage {
+    /// Prompt tokens removed by compression
+    pub fn saved_tokens(&self) -> usize {
+        self.original_tokens.saturating_sub(self.compressed_tokens)
+    }
+
+    /// Share of the original prompt that was sent, from 0.0 to 1.0
+    pub fn ratio(&self) -> f32 {
+        if self.original_tokens == 0 {
+            1.0
+        } else {
+            self.compressed_tokens as f32 / self.original_tokens as f32
+        }
+    }
+}
+
+/// Drops low-information sentences from long messages
+///
+/// System messages are never compressed, nor are messages shorter than
+/// `min_tokens`. In longer messages the first and last sentences, which
+/// usually hold the instruction or question, and fenced code blocks are
+/// always kept.
+///
+/// # Example
+///
+/// ```
+/// use llm::PromptCompressor;
+/// use agent_core::Message;
+///
+/// let compressor = PromptCompressor::new()
+///     .with_target_ratio(0.6)
+///     .with_min_tokens(0);
+///
+/// let context = "Summarize the incident. Disk /dev/sdb1 on db-7 reached 100% at 03:12. \
+///     The service was restarted. The service was restarted. \
+///     The service was restarted. The service was restarted. What failed?";
+/// let (messages, usage) = compressor.compress(&[Message::user(context)]);
+/// assert!(messages[0].content.contains("db-7"));
+/// assert!(usage.saved_tokens() > 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PromptCompressor {
+    target_ratio: f32,
+    min_tokens: usize,
+    estimator: TokenEstimator,
+}
+
+impl PromptCompressor {
+    /// Create a compressor that keeps about half of each message over 500
+    /// tokens
+    pub fn new() -> Self {
+        Self {
+            target_ratio: DEFAULT_TARGET_RATIO,
+            min_tokens: DEFAULT_MIN_TOKENS,
+            estimator: TokenEstimator::default(),
+        }
+    }
+
+    /// Build a compressor from its configuration
+    pub fn from_config(config: &CompressionConfig) -> Self {
+        Self::new()
+            .with_target_ratio(config.target_ratio)
+            .with_min_tokens(config.min_tokens)
+    }
+
+    /// Set the share of each compressed message's tokens to keep, clamped
+    /// to 0.0..=1.0
+    pub fn with_target_ratio(mut self, target_ratio: f32) -> Self {
+        self.target_ratio = target_ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the size in tokens below which messages are left alone
+    pub fn with_min_tokens(mut self, min_tokens: usize) -> Self {
+        self.min_tokens = min_tokens;
+        self
+    }
+
+    /// Set how tokens are counted; use the estimator of the target model
+    pub fn with_estimator(mut self, estimator: TokenEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Compress a copy of `messages`, returning the tokens saved
+    pub fn compress(&self, messages: &[Message]) -> (Vec<Message>, CompressionUsage) {
+        let compressed: Vec<Message> = messages
+            .iter()
+            .map(|message| {
+                let mut message = message.clone();
+                if message.role != Role::System
+                    && self.estimator.count_text(&message.content) > self.min_tokens
+                {
+                    message.content = self.compress_text(&message.content);
+                }
+                message
+            })
+            .collect();
+
+        let usage = CompressionUsage {
+            original_tokens: self.estimator.count_all(messages),
+            compressed_tokens: self.estimator.count_all(&compressed),
+        };
+        (compressed, usage)
+    }
+
+    /// Drop the least informative sentences of `text` until it fits the
+    /// target ratio, keeping the rest in order
+    pub fn compress_text(&self, text: &str) -> String {
+        let segments = split_segments(text);
+        let prose: Vec<usize> = segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| !segment.is_code && !segment.text.trim().is_empty())
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut keep: Vec<bool> = segments
+            .iter()
+            .map(|segment| segment.is_code || segment.text.trim().is_empty())
+            .collect();
+        for index in prose.first().into_iter().chain(prose.last()) {
+            keep[*index] = true;
+        }
+
+        let tokens: Vec<usize> = segments
+            .iter()
+            .map(|segment| self.estimator.count_text(segment.text))
+            .collect();
+        let budget = (tokens.iter().sum::<usize>() as f32 * self.target_ratio).ceil() as usize;
+        let mut kept: usize = (0..segments.len())
+            .filter(|&index| keep[index])
+            .map(|index| tokens[index])
+            .sum();
+
+        let information = WordInformation::new(text);
+        let mut candidates: Vec<(usize, f32)> = prose
+            .iter()
+            .filter(|&&index| !keep[index])
+            .map(|&index| (index, information.score(segments[index].text)))
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (index, _) in candidates {
+            if kept + tokens[index] <= budget {
+                keep[index] = true;
+                kept += tokens[index];
+            }
+        }
+
+        segments
+            .iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(segment, _)| segment.text)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+}
+
+impl Default for PromptCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A sentence, or a whole fenced code block, with its trailing whitespace
+struct Segment<'a> {
+    text: &'a str,
+    is_code: bool,
+}
+
+/// Split text into sentences and code blocks that join back into it
+fn split_segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut in_code = false;
+    let mut line_start = true;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
+        if line_start
+            && text[index..]
+                .trim_start_matches([' ', '\t'])
+                .starts_with("```")
+        {
+            if !in_code && index > start {
+                segments.push(Segment {
+                    text: &text[start..index],
+                    is_code: false,
+                });
+                start = index;
+            }
+            in_code = !in_code;
+            if !in_code {
+                // Close the block at the end of its closing fence line
+                let end = text[index..]
+                    .find('\n')
+                    .map_or(text.len(), |offset| index + offset + 1);
+                segments.push(Segment {
+                    text: &text[start..end],
+                    is_code: true,
+                });
+                start = end;
+                while chars.peek().is_some_and(|(next, _)| *next < end) {
+                    chars.next();
+                }
+                line_start = true;
+                continue;
+            }
+        }
+        line_start = ch == '\n';
+
+        let ends_sentence = matches!(ch, '.' | '!' | '?' | '\n')
+            && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if !in_code && ends_sentence {
+            let mut end = index + ch.len_utf8();
+            while let Some(&(next_index, next)) = chars.peek() {
+                if !next.is_whitespace() {
+                    break;
+                }
+                end = next_index + next.len_utf8();
+                line_start = next == '\n';
+                chars.next();
+            }
+            segments.push(Segment {
+                text: &text[start..end],
+                is_code: false,
+            });
+            start = end;
+        }
+    }
+
+    if start < text.len() {
+        segments.push(Segment {
+            text: &text[start..],
+            is_code: in_code,
+        });
+    }
+    segments
+}
+
+/// Self-information of words, estimated from their frequency in a text
+struct WordInformation {
+    counts: HashMap<String, usize>,
+    total: usize,
+}
+
+impl WordInformation {
+    fn new(text: &str) -> Self {
+        let mut counts = HashMap::new();
+        let mut total = 0;
+        for word in words(text) {
+            *counts.entry(word).or_insert(0) += 1;
+            total += 1;
+        }
+        Self { counts, total }
+    }
+
+    /// Average self-information of the words of a sentence; stop words
+    /// count as carrying none
+    fn score(&self, sentence: &str) -> f32 {
+        let information: Vec<f32> = words(sentence)
+            .map(|word| {
+                if STOP_WORDS.contains(&word.as_str()) {
+                    return 0.0;
+                }
+                let count = self.counts.get(&word).copied().unwrap_or(1);
+                (self.total as f32 / count as f32).ln()
+            })
+            .collect();
+        if information.is_empty() {
+            0.0
+        } else {
+            information.iter().sum::<f32>() / information.len() as f32
+        }
+    }
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Provider wrapper that compresses every request with a [`PromptCompressor`]
+///
+/// Completions report the estimated tokens saved in
+/// [`CompletionResponse::compression`].
+pub struct CompressingProvider<P> {
+    inner: P,
+    compressor: PromptCompressor,
+}
+
+impl<P: LLMProvider> CompressingProvider<P> {
+    /// Wrap a provider
+    pub fn new(inner: P, compressor: PromptCompressor) -> Self {
+        Self { inner, compressor }
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The compressor applied to each request
+    pub fn compressor(&self) -> &PromptCompressor {
+        &self.compressor
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for CompressingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let (messages, _) = self.compressor.compress(messages);
+        self.inner.send_message(&messages).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let (messages, usage) = self.compressor.compress(messages);
+        let response = self.inner.send_completion(&messages).await?;
+        Ok(response.with_compression_usage(usage))
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        let (messages, _) = self.compressor.compress(messages);
+        self.inner.send_structured(&messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        let (messages, usage) = self.compressor.compress(messages);
+        let (value, response) = self
+            .inner
+            .send_structured_completion(&messages, output)
+            .await?;
+        Ok((value, response.with_compression_usage(usage)))
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let (messages, _) = self.compressor.compress(messages);
+        self.inner
+            .send_message_with_tools(&messages, tools, config)
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let (messages, _) = self.compressor.compress(messages);
+        self.inner.stream_message(&messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FinishReason;
+    use std::sync::Mutex;
+
+    fn compressor(target_ratio: f32) -> PromptCompressor {
+        PromptCompressor::new()
+            .with_target_ratio(target_ratio)
+            .with_min_tokens(0)
+            .with_estimator(TokenEstimator::Approximate {
+                chars_per_token: 4.0,
+            })
+    }
+
+    #[test]
+    fn test_split_segments_round_trips() {
+        let text = "First one. Second!\nThird?\n```rs\nlet a = 1. b\n```\nAfter code. Tail";
+        let segments = split_segments(text);
+        assert_eq!(segments.iter().map(|s| s.text).collect::<String>(), text);
+        let code: Vec<_> = segments
+            .iter()
+            .filter(|s| s.is_code)
+            .map(|s| s.text)
+            .collect();
+        assert_eq!(code, ["```rs\nlet a = 1. b\n```\n"]);
+        assert_eq!(segments[0].text, "First one. ");
+        assert_eq!(segments.last().unwrap().text, "Tail");
+    }
+
+    #[test]
+    fn test_drops_repetitive_sentences_first() {
+        let filler = "We are looking into it and will update you. ".repeat(6);
+        let text = format!(
+            "Read the status updates below. {}Root cause was an expired TLS certificate \
+             on api-gw-3. Which host failed?",
+            filler
+        );
+
+        let compressed = compressor(0.5).compress_text(&text);
+        assert!(compressed.starts_with("Read the status updates below."));
+        assert!(compressed.contains("expired TLS certificate"));
+        assert!(compressed.ends_with("Which host failed?"));
+        assert!(compressed.len() <= text.len() / 2 + 40);
+    }
+
+    #[test]
+    fn test_keeps_system_short_messages_and_code() {
+        let long = "Intro. ".repeat(20) + "```\ncode();\n```\nEnd.";
+        let messages = [
+            Message::system("Be brief. ".repeat(50)),
+            Message::user(long.clone()),
+            Message::user("Short."),
+        ];
+
+        let (compressed, usage) = compressor(0.3).with_min_tokens(10).compress(&messages);
+        assert_eq!(compressed[0].content, messages[0].content);
+        assert_eq!(compressed[2].content, "Short.");
+        assert!(compressed[1].content.contains("```\ncode();\n```"));
+        assert!(compressed[1].content.len() < long.len());
+        assert!(usage.saved_tokens() > 0);
+        assert!(usage.ratio() < 1.0);
+    }
+
+    struct Recording {
+        seen: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Recording {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.seen.lock().unwrap().push(messages[0].content.clone());
+            Ok("ok".to_string())
+        }
+
+        async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+            let text = self.send_message(messages).await?;
+            Ok(CompletionResponse::new(text, FinishReason::Stop))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_reports_savings() {
+        let provider = CompressingProvider::new(
+            Recording {
+                seen: Mutex::new(Vec::new()),
+            },
+            compressor(0.5),
+        );
+        let text = "Question first. ".to_string() + &"Same old line. ".repeat(10) + "Done?";
+
+        let response = provider
+            .send_completion(&[Message::user(text.clone())])
+            .await
+            .unwrap();
+        let usage = response.compression.unwrap();
+        assert!(usage.saved_tokens() > 0);
+        assert_eq!(usage.original_tokens, 4 + text.len().div_ceil(4));
+        assert!(provider.inner().seen.lock().unwrap()[0].len() < text.len());
+    }
+}
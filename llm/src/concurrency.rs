@@ -0,0 +1,481 @@
+//! Global and per-provider limits on simultaneous requests.
+//!
+//! Multi-agent runs that fan out widely can open far more connections than
+//! a provider (or the local network stack) will tolerate. A
+//! [`ConcurrencyGovernor`] holds one semaphore for all requests and one per
+//! provider name; [`GovernedProvider`] waits for a permit from both before
+//! each request, so the caps hold across every provider sharing the governor.
+//!
+//! Requests wait with a [`RequestPriority`]. When slots are scarce,
+//! waiting interactive requests are always served before background ones,
+//! so batch and evaluation traffic cannot starve user-facing requests.
+
+use agent_core::{Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::ConcurrencyConfig;
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::{CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig};
+
+/// How urgently a request needs a slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RequestPriority {
+    /// A user is waiting for the response
+    #[default]
+    Interactive,
+    /// Batch, evaluation or other traffic nobody is waiting on; only gets
+    /// a slot when no interactive request is waiting for one
+    Background,
+}
+
+/// Shared request limits
+///
+/// Providers without a configured per-provider limit are only bound by
+/// the global limit; with neither configured, requests are not limited.
+#[derive(Debug, Default)]
+pub struct ConcurrencyGovernor {
+    global: Option<Arc<Slots>>,
+    per_provider: HashMap<String, Arc<Slots>>,
+}
+
+/// Permits held for the duration of one request
+#[derive(Debug)]
+pub struct RequestPermit {
+    _provider: Option<SlotPermit>,
+    _global: Option<SlotPermit>,
+}
+
+impl ConcurrencyGovernor {
+    /// Create a governor without any limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a governor from configuration
+    pub fn from_config(config: &ConcurrencyConfig) -> Self {
+        let mut governor = Self::new();
+        if let Some(limit) = config.max_concurrent_requests {
+            governor = governor.with_global_limit(limit);
+        }
+        for (provider, limit) in &config.per_provider {
+            governor = governor.with_provider_limit(provider.clone(), *limit);
+        }
+        governor
+    }
+
+    /// Cap simultaneous requests across all providers
+    pub fn with_global_limit(mut self, limit: usize) -> Self {
+        self.global = Some(Arc::new(Slots::new(limit)));
+        self
+    }
+
+    /// Cap simultaneous requests to one provider
+    pub fn with_provider_limit(mut self, provider: impl Into<String>, limit: usize) -> Self {
+        self.per_provider
+            .insert(provider.into(), Arc::new(Slots::new(limit)));
+        self
+    }
+
+    /// Wait until an interactive request to `provider` is allowed
+    pub async fn acquire(&self, provider: &str) -> RequestPermit {
+        self.acquire_with_priority(provider, RequestPriority::Interactive)
+            .await
+    }
+
+    /// Wait until a request to `provider` with `priority` is allowed
+    ///
+    /// The provider permit is taken first so that a request queued behind
+    /// its own provider's limit does not hold a global slot other
+    /// providers could use.
+    pub async fn acquire_with_priority(
+        &self,
+        provider: &str,
+        priority: RequestPriority,
+    ) -> RequestPermit {
+        let provider_permit = match self.per_provider.get(provider) {
+            Some(slots) => Some(slots.acquire(priority).await),
+            None => None,
+        };
+        let global_permit = match &self.global {
+            Some(slots) => Some(slots.acquire(priority).await),
+            None => None,
+        };
+        RequestPermit {
+            _provider: provider_permit,
+            _global: global_permit,
+        }
+    }
+
+    /// Permits currently free for `provider`, taking both limits into
+    /// account; `None` if the provider is unlimited
+    pub fn available(&self, provider: &str) -> Option<usize> {
+        let provider = self.per_provider.get(provider).map(|s| s.available());
+        let global = self.global.as_ref().map(|s| s.available());
+        match (provider, global) {
+            (Some(p), Some(g)) => Some(p.min(g)),
+            (p, g) => p.or(g),
+        }
+    }
+}
+
+/// A counting semaphore that serves interactive waiters first
+///
+/// Waiters are woken on every release and re-check the counts, so there is
+/// no ordering among requests of the same priority.
+#[derive(Debug)]
+struct Slots {
+    state: Mutex<SlotState>,
+    changed: Notify,
+}
+
+#[derive(Debug)]
+struct SlotState {
+    available: usize,
+    interactive_waiting: usize,
+}
+
+impl Slots {
+    fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(SlotState {
+                available: limit,
+                interactive_waiting: 0,
+            }),
+            changed: Notify::new(),
+        }
+    }
+
+    fn available(&self) -> usize {
+        self.state.lock().unwrap().available
+    }
+
+    async fn acquire(self: &Arc<Self>, priority: RequestPriority) -> SlotPermit {
+        let mut waiting = None;
+        loop {
+            // Register for wakeups before checking, so a release between
+            // the check and the wait is not missed
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            {
+                let mut state = self.state.lock().unwrap();
+                let allowed =
+                    priority == RequestPriority::Interactive || state.interactive_waiting == 0;
+                if allowed && state.available > 0 {
+                    state.available -= 1;
+                    return SlotPermit {
+                        slots: self.clone(),
+                    };
+                }
+                if priority == RequestPriority::Interactive && waiting.is_none() {
+                    state.interactive_waiting += 1;
+                    waiting = Some(InteractiveWaiter {
+                        slots: self.clone(),
+                    });
+                }
+            }
+            changed.await;
+        }
+    }
+}
+
+/// A slot taken from [`Slots`], returned when dropped
+#[derive(Debug)]
+struct SlotPermit {
+    slots: Arc<Slots>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        self.slots.state.lock().unwrap().available += 1;
+        self.slots.changed.notify_waiters();
+    }
+}
+
+/// Counts an interactive request as waiting until it gets a slot or gives up
+struct InteractiveWaiter {
+    slots: Arc<Slots>,
+}
+
+impl Drop for InteractiveWaiter {
+    fn drop(&mut self) {
+        let mut state = self.slots.state.lock().unwrap();
+        state.interactive_waiting -= 1;
+        if state.interactive_waiting == 0 {
+            // Background requests may now take any slots left over
+            drop(state);
+            self.slots.changed.notify_waiters();
+        }
+    }
+}
+
+/// Provider wrapper that enforces a [`ConcurrencyGovernor`]'s limits
+///
+/// Requests are interactive unless set otherwise with
+/// [`with_priority`](Self::with_priority); wrap the same provider twice to
+/// send user-facing and batch traffic through one governor.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{ConcurrencyGovernor, GovernedProvider, OpenAIProvider, RequestPriority};
+/// use std::sync::Arc;
+/// # use config::LLMConfig;
+///
+/// # fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// let governor = Arc::new(
+///     ConcurrencyGovernor::new()
+///         .with_global_limit(16)
+///         .with_provider_limit("openai", 8),
+/// );
+/// let provider = GovernedProvider::new(OpenAIProvider::new(&config)?, "openai", governor.clone());
+/// let batch = GovernedProvider::new(OpenAIProvider::new(&config)?, "openai", governor)
+///     .with_priority(RequestPriority::Background);
+/// # Ok(())
+/// # }
+/// ```
+pub struct GovernedProvider<P: LLMProvider> {
+    inner: P,
+    provider: String,
+    governor: Arc<ConcurrencyGovernor>,
+    priority: RequestPriority,
+}
+
+impl<P: LLMProvider> GovernedProvider<P> {
+    /// Wrap a provider; `provider` selects the per-provider limit
+    pub fn new(inner: P, provider: impl Into<String>, governor: Arc<ConcurrencyGovernor>) -> Self {
+        Self {
+            inner,
+            provider: provider.into(),
+            governor,
+            priority: RequestPriority::default(),
+        }
+    }
+
+    /// Set the priority the wrapped provider's requests wait with
+    pub fn with_priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    async fn acquire(&self) -> RequestPermit {
+        self.governor
+            .acquire_with_priority(&self.provider, self.priority)
+            .await
+    }
+
+    /// Get a reference to the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for GovernedProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let _permit = self.acquire().await;
+        self.inner.send_message(messages).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let _permit = self.acquire().await;
+        self.inner.send_completion(messages).await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        let _permit = self.acquire().await;
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        let _permit = self.acquire().await;
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let _permit = self.acquire().await;
+        self.inner.send_message_with_tools(messages, tools, config).await
+    }
+
+    /// The permit is held until the stream is dropped
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let permit = self.acquire().await;
+        let stream = self.inner.stream_message(messages).await?;
+        Ok(Box::pin(stream.map(move |event| {
+            let _held = &permit;
+            event
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Provider that records the peak number of concurrent calls
+    struct TrackingProvider {
+        active: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl TrackingProvider {
+        fn new(active: Arc<AtomicUsize>, peak: Arc<AtomicUsize>) -> Self {
+            Self { active, peak }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for TrackingProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok("ok".to_string())
+        }
+    }
+
+    async fn run_concurrently(provider: &dyn LLMProvider, count: usize) {
+        let messages = vec![Message::user("hi")];
+        let calls = (0..count).map(|_| provider.send_message(&messages));
+        for result in futures::future::join_all(calls).await {
+            result.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_limit() {
+        let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let governor = Arc::new(ConcurrencyGovernor::new().with_provider_limit("openai", 2));
+        let provider = GovernedProvider::new(
+            TrackingProvider::new(active, peak.clone()),
+            "openai",
+            governor.clone(),
+        );
+
+        run_concurrently(&provider, 6).await;
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(governor.available("openai"), Some(2));
+        assert_eq!(governor.available("anthropic"), None);
+    }
+
+    #[tokio::test]
+    async fn test_global_limit_spans_providers() {
+        let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let governor = Arc::new(
+            ConcurrencyGovernor::new()
+                .with_global_limit(3)
+                .with_provider_limit("openai", 2),
+        );
+        let openai = GovernedProvider::new(
+            TrackingProvider::new(active.clone(), peak.clone()),
+            "openai",
+            governor.clone(),
+        );
+        let anthropic = GovernedProvider::new(
+            TrackingProvider::new(active, peak.clone()),
+            "anthropic",
+            governor,
+        );
+
+        tokio::join!(
+            run_concurrently(&openai, 4),
+            run_concurrently(&anthropic, 4)
+        );
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_holds_permit_until_dropped() {
+        let governor = Arc::new(ConcurrencyGovernor::new().with_global_limit(1));
+        let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let provider = GovernedProvider::new(
+            TrackingProvider::new(active, peak),
+            "openai",
+            governor.clone(),
+        );
+
+        let stream = provider
+            .stream_message(&[Message::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(governor.available("openai"), Some(0));
+        drop(stream);
+        assert_eq!(governor.available("openai"), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_interactive_requests_are_served_first() {
+        let governor = Arc::new(ConcurrencyGovernor::new().with_global_limit(1));
+        let held = governor.acquire("openai").await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let spawn = |priority, label| {
+            let (governor, order) = (governor.clone(), order.clone());
+            tokio::spawn(async move {
+                let _permit = governor.acquire_with_priority("openai", priority).await;
+                order.lock().unwrap().push(label);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            })
+        };
+        let background = spawn(RequestPriority::Background, "background");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let interactive = spawn(RequestPriority::Interactive, "interactive");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        drop(held);
+        background.await.unwrap();
+        interactive.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["interactive", "background"]);
+        assert_eq!(governor.available("openai"), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_interactive_wait_releases_background() {
+        let governor = Arc::new(ConcurrencyGovernor::new().with_global_limit(1));
+        let held = governor.acquire("openai").await;
+
+        let waiting = tokio::time::timeout(Duration::from_millis(10), governor.acquire("openai"));
+        assert!(waiting.await.is_err());
+        drop(held);
+
+        let background = governor.acquire_with_priority("openai", RequestPriority::Background);
+        tokio::time::timeout(Duration::from_millis(100), background)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_from_config() {
+        let mut config = ConcurrencyConfig {
+            max_concurrent_requests: Some(10),
+            ..ConcurrencyConfig::default()
+        };
+        config.per_provider.insert("ollama".to_string(), 1);
+
+        let governor = ConcurrencyGovernor::from_config(&config);
+        assert_eq!(governor.available("ollama"), Some(1));
+        assert_eq!(governor.available("openai"), Some(10));
+        assert_eq!(ConcurrencyGovernor::new().available("openai"), None);
+    }
+}
@@ -0,0 +1,392 @@
+//! Completions with their metadata, and automatic continuation.
+//!
+//! A response cut off by the output token limit looks like any other
+//! response to [`LLMProvider::send_message`] callers. [`CompletionResponse`]
+//! carries the provider's stop reason, token usage and model name alongside
+//! the text, and a [`ContinuingProvider`] uses the stop reason to ask the
+//! model to continue a truncated response, stitching the parts together and
+//! removing text the model repeated at the seams.
+
+use agent_core::{Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    CacheUsage, CompressionUsage, LLMProvider, StructuredOutput, TokenStream, TokenUsage,
+    ToolConfig,
+};
+
+/// Default maximum number of continuation requests per response
+const DEFAULT_MAX_CONTINUATIONS: usize = 3;
+
+/// Longest overlap, in characters, removed between stitched parts
+const MAX_OVERLAP_CHARS: usize = 500;
+
+/// Shortest repeated text treated as overlap rather than coincidence
+const MIN_OVERLAP_CHARS: usize = 8;
+
+/// Instruction sent after a truncated response
+const CONTINUE_PROMPT: &str = "Your previous response was cut off. Continue exactly where it \
+stopped, without repeating any text and without any preamble.";
+
+/// Why the model stopped generating
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FinishReason {
+    /// The model finished its response or hit a stop sequence
+    Stop,
+    /// The output token limit was reached (`length` / `max_tokens`)
+    Length,
+    /// The model stopped to call tools
+    ToolUse,
+    /// The response was withheld or cut by a content filter
+    ContentFilter,
+    /// A reason this crate does not know, as reported by the provider
+    Other(String),
+    /// The provider does not report stop reasons
+    Unknown,
+}
+
+impl FinishReason {
+    /// Map an OpenAI `finish_reason`
+    pub fn from_openai(reason: Option<&str>) -> Self {
+        match reason {
+            Some("stop") => Self::Stop,
+            Some("length") => Self::Length,
+            Some("tool_calls") | Some("function_call") => Self::ToolUse,
+            Some("content_filter") => Self::ContentFilter,
+            Some(other) => Self::Other(other.to_string()),
+            None => Self::Unknown,
+        }
+    }
+
+    /// Map an Anthropic `stop_reason`
+    pub fn from_anthropic(reason: Option<&str>) -> Self {
+        match reason {
+            Some("end_turn") | Some("stop_sequence") => Self::Stop,
+            Some("max_tokens") => Self::Length,
+            Some("tool_use") => Self::ToolUse,
+            Some("refusal") => Self::ContentFilter,
+            Some(other) => Self::Other(other.to_string()),
+            None => Self::Unknown,
+        }
+    }
+
+    /// Map a Gemini `finishReason`
+    pub fn from_gemini(reason: Option<&str>) -> Self {
+        match reason {
+            Some("STOP") => Self::Stop,
+            Some("MAX_TOKENS") => Self::Length,
+            Some("SAFETY") | Some("RECITATION") | Some("BLOCKLIST")
+            | Some("PROHIBITED_CONTENT") | Some("SPII") => Self::ContentFilter,
+            Some(other) => Self::Other(other.to_string()),
+            None => Self::Unknown,
+        }
+    }
+
+    /// Whether the response was cut off by the output token limit
+    pub fn is_truncated(&self) -> bool {
+        *self == Self::Length
+    }
+}
+
+/// A response with the reason generation stopped and what it cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionResponse {
+    /// The response text
+    pub text: String,
+    /// Why the model stopped
+    pub finish_reason: FinishReason,
+    /// Tokens billed for the response, if the provider reports them
+    pub usage: Option<TokenUsage>,
+    /// The model that generated the response, as reported by the provider
+    pub model: Option<String>,
+    /// Prompt cache reads and writes, if the provider reports them
+    pub cache: Option<CacheUsage>,
+    /// Prompt tokens saved by compression, if the prompt was compressed
+    pub compression: Option<CompressionUsage>,
+}
+
+impl CompletionResponse {
+    /// A completion with the given text and stop reason, and no usage
+    pub fn new(text: impl Into<String>, finish_reason: FinishReason) -> Self {
+        Self {
+            text: text.into(),
+            finish_reason,
+            usage: None,
+            model: None,
+            cache: None,
+            compression: None,
+        }
+    }
+
+    /// Set the token usage
+    pub fn with_usage(mut self, usage: TokenUsage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Set the model name
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set the prompt cache usage
+    pub fn with_cache_usage(mut self, cache: CacheUsage) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Set the prompt compression savings
+    pub fn with_compression_usage(mut self, compression: CompressionUsage) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Add the usage of another request made for this response, such as a
+    /// continuation or a repair attempt
+    pub fn add_usage(&mut self, usage: Option<TokenUsage>) {
+        self.usage = match (self.usage, usage) {
+            (Some(total), Some(usage)) => Some(total + usage),
+            (total, usage) => total.or(usage),
+        };
+    }
+}
+
+/// Provider wrapper that continues responses cut off by the token limit
+///
+/// When a response stops with [`FinishReason::Length`], the partial
+/// response is sent back as an assistant message with a request to
+/// continue, up to `max_continuations` times. The parts are joined with any
+/// text the model repeated at the start of a continuation removed.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{ContinuingProvider, LLMProvider, OpenAIProvider};
+/// use agent_core::Message;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = ContinuingProvider::new(
+///     OpenAIProvider::builder().api_key("your-api-key").build()?,
+/// )
+/// .with_max_continuations(5);
+///
+/// let essay = provider
+///     .send_message(&[Message::user("Write a 5000 word essay on tides")])
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ContinuingProvider<P> {
+    inner: P,
+    max_continuations: usize,
+}
+
+impl<P: LLMProvider> ContinuingProvider<P> {
+    /// Wrap a provider, allowing 3 continuations per response
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            max_continuations: DEFAULT_MAX_CONTINUATIONS,
+        }
+    }
+
+    /// Set the maximum number of continuation requests per response
+    pub fn with_max_continuations(mut self, max_continuations: usize) -> Self {
+        self.max_continuations = max_continuations;
+        self
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Maximum number of continuation requests per response
+    pub fn max_continuations(&self) -> usize {
+        self.max_continuations
+    }
+}
+
+/// Append `part` to `text`, dropping the longest prefix of `part` that
+/// repeats the end of `text`
+fn stitch(text: &mut String, part: &str) {
+    let limit = MAX_OVERLAP_CHARS.min(text.len()).min(part.len());
+    let overlap = (MIN_OVERLAP_CHARS..=limit)
+        .rev()
+        .filter(|&len| part.is_char_boundary(len) && text.is_char_boundary(text.len() - len))
+        .find(|&len| text.ends_with(&part[..len]))
+        .unwrap_or(0);
+    text.push_str(&part[overlap..]);
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for ContinuingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        Ok(self.send_completion(messages).await?.text)
+    }
+
+    /// The stop reason is that of the last part, and the usage is summed
+    /// over all parts
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let mut completion = self.inner.send_completion(messages).await?;
+        let mut continuations = 0;
+        while completion.finish_reason.is_truncated() && continuations < self.max_continuations {
+            let mut request = messages.to_vec();
+            request.push(Message::assistant(completion.text.clone()));
+            request.push(Message::user(CONTINUE_PROMPT));
+
+            let part = self.inner.send_completion(&request).await?;
+            stitch(&mut completion.text, &part.text);
+            completion.finish_reason = part.finish_reason;
+            completion.add_usage(part.usage);
+            continuations += 1;
+        }
+        Ok(completion)
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner
+            .send_message_with_tools(messages, tools, config)
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.inner.stream_message(messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Returns scripted completions in order and records the requests
+    struct Scripted {
+        parts: Mutex<Vec<CompletionResponse>>,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl Scripted {
+        fn new(parts: Vec<CompletionResponse>) -> Self {
+            Self {
+                parts: Mutex::new(parts),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(self.send_completion(messages).await?.text)
+        }
+
+        async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.parts.lock().unwrap().remove(0))
+        }
+    }
+
+    #[test]
+    fn test_stitch_removes_overlap() {
+        let mut text = "The tide rises twice a day because".to_string();
+        stitch(&mut text, "twice a day because the moon pulls");
+        assert_eq!(text, "The tide rises twice a day because the moon pulls");
+
+        let mut text = "short".to_string();
+        stitch(&mut text, " and more");
+        assert_eq!(text, "short and more");
+
+        let mut text = "Café crème".to_string();
+        stitch(&mut text, "é crème brûlée");
+        assert_eq!(text, "Café crème brûlée");
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response_is_continued() {
+        let usage = |input_tokens, output_tokens| TokenUsage {
+            input_tokens,
+            output_tokens,
+        };
+        let provider = ContinuingProvider::new(Scripted::new(vec![
+            CompletionResponse::new("Tides are caused by", FinishReason::Length)
+                .with_usage(usage(10, 4)),
+            CompletionResponse::new(" the moon's gravity.", FinishReason::Stop)
+                .with_usage(usage(30, 5)),
+        ]));
+
+        let completion = provider
+            .send_completion(&[Message::user("Why are there tides?")])
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "Tides are caused by the moon's gravity.");
+        assert_eq!(completion.finish_reason, FinishReason::Stop);
+        assert_eq!(completion.usage, Some(usage(40, 9)));
+
+        let requests = provider.inner().requests.lock().unwrap();
+        assert_eq!(requests[1].len(), 3);
+        assert_eq!(requests[1][1].content, "Tides are caused by");
+    }
+
+    #[tokio::test]
+    async fn test_continuations_are_bounded() {
+        let provider = ContinuingProvider::new(Scripted::new(vec![
+            CompletionResponse::new("one", FinishReason::Length),
+            CompletionResponse::new(" two", FinishReason::Length),
+            CompletionResponse::new(" three", FinishReason::Length),
+        ]))
+        .with_max_continuations(1);
+
+        let completion = provider
+            .send_completion(&[Message::user("Count")])
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "one two");
+        assert!(completion.finish_reason.is_truncated());
+    }
+
+    #[test]
+    fn test_finish_reason_mapping() {
+        assert_eq!(
+            FinishReason::from_openai(Some("length")),
+            FinishReason::Length
+        );
+        assert_eq!(
+            FinishReason::from_anthropic(Some("max_tokens")),
+            FinishReason::Length
+        );
+        assert_eq!(
+            FinishReason::from_anthropic(Some("end_turn")),
+            FinishReason::Stop
+        );
+        assert_eq!(
+            FinishReason::from_openai(Some("eos")),
+            FinishReason::Other("eos".to_string())
+        );
+        assert_eq!(FinishReason::from_openai(None), FinishReason::Unknown);
+    }
+}
@@ -0,0 +1,416 @@
+//! Graceful degradation when the model service is down.
+//!
+//! Provider errors are not something end users should see. A
+//! [`DegradingProvider`] sits outside any retry or fallback wrappers and
+//! turns an outage they could not recover from into a typed
+//! [`AgentError::ServiceDegraded`], answering repeated requests from recent
+//! responses where it can. A [`DegradationPolicy`] then decides, at the edge
+//! of the application, whether that state becomes a fixed reply or is
+//! returned to the caller.
+
+use agent_core::{AgentError, ErrorCategory, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::DegradationConfig;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::{
+    CoalescingProvider, CompletionResponse, FinishReason, LLMProvider, StructuredOutput,
+    TokenStream, ToolConfig,
+};
+
+/// What to do once the model service is unavailable
+///
+/// The default policy caches nothing and has no fallback reply, so callers
+/// receive [`AgentError::ServiceDegraded`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DegradationPolicy {
+    cache_capacity: usize,
+    fallback_response: Option<String>,
+}
+
+impl DegradationPolicy {
+    /// A policy without cache or fallback reply
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a policy from the agent configuration
+    pub fn from_config(config: &DegradationConfig) -> Self {
+        Self {
+            cache_capacity: config.cache_responses,
+            fallback_response: config.fallback_response.clone(),
+        }
+    }
+
+    /// Keep up to `capacity` recent responses for replay during an outage
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// Reply with `text` instead of returning a degraded error
+    pub fn with_fallback_response(mut self, text: impl Into<String>) -> Self {
+        self.fallback_response = Some(text.into());
+        self
+    }
+
+    /// Number of responses kept for replay
+    pub fn cache_capacity(&self) -> usize {
+        self.cache_capacity
+    }
+
+    /// The fixed reply used during an outage, if any
+    pub fn fallback_response(&self) -> Option<&str> {
+        self.fallback_response.as_deref()
+    }
+
+    /// Turn a degraded result into the fallback reply
+    ///
+    /// Other errors, and degraded errors without a configured reply, are
+    /// returned unchanged.
+    pub fn recover(&self, result: Result<String>) -> Result<String> {
+        match (result, &self.fallback_response) {
+            (Err(error), Some(reply)) if is_degraded(&error) => Ok(reply.clone()),
+            (result, _) => result,
+        }
+    }
+}
+
+/// Whether `error` reports a degraded service
+fn is_degraded(error: &AgentError) -> bool {
+    matches!(error.root_cause(), AgentError::ServiceDegraded(_))
+}
+
+/// Whether `error` means the model service is unavailable: provider
+/// failures, rate limits and timeouts
+fn is_outage(error: &AgentError) -> bool {
+    ErrorCategory::of(error).is_retryable()
+}
+
+/// Recent responses, evicted oldest first
+#[derive(Default)]
+struct ResponseCache {
+    entries: HashMap<String, String>,
+    order: VecDeque<String>,
+}
+
+impl ResponseCache {
+    fn insert(&mut self, key: String, response: String, capacity: usize) {
+        if self.entries.insert(key.clone(), response).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Provider wrapper that reports outages as [`AgentError::ServiceDegraded`].
+///
+/// Successful message and completion responses are remembered (up to the
+/// policy's cache capacity) and replayed for identical requests while the
+/// wrapped provider fails. Structured, tool-calling and streaming requests
+/// are never replayed, but their outages are reported the same way.
+/// Provider failures, rate limits and timeouts count as an outage;
+/// authentication, configuration, guardrail and other errors pass through
+/// unchanged.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{DegradationPolicy, DegradingProvider, LLMProvider, OpenAIProvider};
+/// use agent_core::Message;
+/// # use config::LLMConfig;
+///
+/// # async fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// let policy = DegradationPolicy::new()
+///     .with_cache(100)
+///     .with_fallback_response("The assistant is temporarily unavailable.");
+/// let provider = DegradingProvider::new(OpenAIProvider::new(&config)?, policy.clone());
+///
+/// let result = provider
+///     .send_message(&[Message::user("What are your opening hours?")])
+///     .await;
+/// let reply = policy.recover(result)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DegradingProvider<P: LLMProvider> {
+    inner: P,
+    policy: DegradationPolicy,
+    cache: Mutex<ResponseCache>,
+    outage: Mutex<Option<String>>,
+}
+
+impl<P: LLMProvider> DegradingProvider<P> {
+    /// Wrap a provider with the given degradation policy
+    pub fn new(inner: P, policy: DegradationPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            cache: Mutex::new(ResponseCache::default()),
+            outage: Mutex::new(None),
+        }
+    }
+
+    /// Get a reference to the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get the degradation policy
+    pub fn policy(&self) -> &DegradationPolicy {
+        &self.policy
+    }
+
+    /// Whether the most recent request found the service down
+    pub fn is_degraded(&self) -> bool {
+        self.outage.lock().unwrap().is_some()
+    }
+
+    /// The error that put the service into the degraded state, if any
+    pub fn outage_reason(&self) -> Option<String> {
+        self.outage.lock().unwrap().clone()
+    }
+
+    /// Keep a successful response for replay during an outage
+    fn remember(&self, key: &str, response: &str) {
+        if self.policy.cache_capacity > 0 {
+            self.cache.lock().unwrap().insert(
+                key.to_string(),
+                response.to_string(),
+                self.policy.cache_capacity,
+            );
+        }
+    }
+
+    /// The response remembered for `key`, if any
+    fn cached(&self, key: &str) -> Option<String> {
+        self.cache.lock().unwrap().entries.get(key).cloned()
+    }
+
+    /// Track the service state from `result`, turning an outage into
+    /// [`AgentError::ServiceDegraded`] unless `replay` has a response
+    fn observe<T>(&self, result: Result<T>, replay: impl FnOnce() -> Option<T>) -> Result<T> {
+        match result {
+            Ok(response) => {
+                *self.outage.lock().unwrap() = None;
+                Ok(response)
+            }
+            Err(error) if is_outage(&error) => {
+                let reason = error.to_string();
+                *self.outage.lock().unwrap() = Some(reason.clone());
+                replay().ok_or(AgentError::ServiceDegraded(reason))
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for DegradingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let key = CoalescingProvider::<P>::request_key(messages);
+        let result = self.inner.send_message(messages).await;
+        if let Ok(response) = &result {
+            self.remember(&key, response);
+        }
+        self.observe(result, || self.cached(&key))
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let key = CoalescingProvider::<P>::request_key(messages);
+        let result = self.inner.send_completion(messages).await;
+        if let Ok(response) = &result {
+            self.remember(&key, &response.text);
+        }
+        // A replayed response was not billed and its stop reason is not kept
+        self.observe(result, || {
+            self.cached(&key)
+                .map(|text| CompletionResponse::new(text, FinishReason::Unknown))
+        })
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        let result = self.inner.send_structured(messages, output).await;
+        self.observe(result, || None)
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        let result = self.inner.send_structured_completion(messages, output).await;
+        self.observe(result, || None)
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let result = self
+            .inner
+            .send_message_with_tools(messages, tools, config)
+            .await;
+        self.observe(result, || None)
+    }
+
+    /// Only a stream that fails to start counts as an outage; errors in the
+    /// middle of a stream are passed through
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let result = self.inner.stream_message(messages).await;
+        self.observe(result, || None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Echoes the last message until switched off, then fails with `outage`
+    struct FlakyProvider {
+        up: AtomicBool,
+        outage: fn() -> AgentError,
+    }
+
+    impl FlakyProvider {
+        fn new() -> Self {
+            Self::failing_with(|| AgentError::LLMProvider("connection refused".to_string()))
+        }
+
+        fn failing_with(outage: fn() -> AgentError) -> Self {
+            Self {
+                up: AtomicBool::new(true),
+                outage,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for FlakyProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            if !self.up.load(Ordering::SeqCst) {
+                return Err((self.outage)());
+            }
+            let last = messages.last().map(|m| m.content.as_str()).unwrap_or("");
+            if last == "bad" {
+                return Err(AgentError::Config("invalid request".to_string()));
+            }
+            Ok(format!("echo: {}", last))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outage_is_reported_as_degraded() {
+        let provider = DegradingProvider::new(FlakyProvider::new(), DegradationPolicy::new());
+        provider.inner().up.store(false, Ordering::SeqCst);
+
+        let result = provider.send_message(&[Message::user("hi")]).await;
+        assert!(matches!(result, Err(AgentError::ServiceDegraded(msg)) if msg.contains("refused")));
+        assert!(provider.is_degraded());
+
+        provider.inner().up.store(true, Ordering::SeqCst);
+        provider.send_message(&[Message::user("hi")]).await.unwrap();
+        assert!(!provider.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_cached_responses_replayed_during_outage() {
+        let policy = DegradationPolicy::new().with_cache(1);
+        let provider = DegradingProvider::new(FlakyProvider::new(), policy);
+        provider.send_message(&[Message::user("a")]).await.unwrap();
+        provider.send_message(&[Message::user("b")]).await.unwrap();
+        provider.inner().up.store(false, Ordering::SeqCst);
+
+        let replayed = provider.send_message(&[Message::user("b")]).await.unwrap();
+        assert_eq!(replayed, "echo: b");
+        assert!(provider.is_degraded());
+
+        // "a" was evicted by the capacity of one
+        let evicted = provider.send_message(&[Message::user("a")]).await;
+        assert!(matches!(evicted, Err(AgentError::ServiceDegraded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_completions_replayed_and_streams_degraded() {
+        let policy = DegradationPolicy::new().with_cache(4);
+        let provider = DegradingProvider::new(FlakyProvider::new(), policy);
+        provider.send_completion(&[Message::user("a")]).await.unwrap();
+        provider.inner().up.store(false, Ordering::SeqCst);
+
+        let replayed = provider.send_completion(&[Message::user("a")]).await.unwrap();
+        assert_eq!(replayed.text, "echo: a");
+        assert_eq!(replayed.usage, None);
+
+        let stream = provider.stream_message(&[Message::user("a")]).await;
+        assert!(matches!(stream, Err(AgentError::ServiceDegraded(_))));
+        assert!(provider.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_and_rate_limits_are_outages() {
+        let outages: [fn() -> AgentError; 2] = [
+            || AgentError::Timeout("read timed out".to_string()),
+            || AgentError::RateLimited {
+                message: "slow down".to_string(),
+                retry_after: None,
+            },
+        ];
+        for outage in outages {
+            let policy = DegradationPolicy::new().with_cache(4);
+            let provider = DegradingProvider::new(FlakyProvider::failing_with(outage), policy);
+            provider.send_message(&[Message::user("a")]).await.unwrap();
+            provider.inner().up.store(false, Ordering::SeqCst);
+
+            let replayed = provider.send_message(&[Message::user("a")]).await.unwrap();
+            assert_eq!(replayed, "echo: a");
+            let uncached = provider.send_message(&[Message::user("b")]).await;
+            assert!(matches!(uncached, Err(AgentError::ServiceDegraded(_))));
+            assert!(provider.is_degraded());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_failures_are_not_outages() {
+        let provider = DegradingProvider::new(
+            FlakyProvider::failing_with(|| AgentError::AuthFailed("invalid key".to_string())),
+            DegradationPolicy::new().with_cache(4),
+        );
+        provider.inner().up.store(false, Ordering::SeqCst);
+        let result = provider.send_message(&[Message::user("a")]).await;
+        assert!(matches!(result, Err(AgentError::AuthFailed(_))));
+        assert!(!provider.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_pass_through() {
+        let provider = DegradingProvider::new(FlakyProvider::new(), DegradationPolicy::new());
+        let result = provider.send_message(&[Message::user("bad")]).await;
+        assert!(matches!(result, Err(AgentError::Config(_))));
+        assert!(!provider.is_degraded());
+    }
+
+    #[test]
+    fn test_policy_recover() {
+        let degraded = || Err(AgentError::ServiceDegraded("down".to_string()));
+        assert!(DegradationPolicy::new().recover(degraded()).is_err());
+
+        let policy = DegradationPolicy::new().with_fallback_response("Back soon");
+        assert_eq!(policy.recover(degraded()).unwrap(), "Back soon");
+        assert_eq!(policy.recover(Ok("live".to_string())).unwrap(), "live");
+        let other = policy.recover(Err(AgentError::Planning("bad plan".to_string())));
+        assert!(matches!(other, Err(AgentError::Planning(_))));
+    }
+}
@@ -0,0 +1,112 @@
+//! Text embedding providers.
+//!
+//! An [`EmbeddingProvider`] turns text into vectors for semantic search and
+//! retrieval. Providers embed many texts per request;
+//! [`EmbeddingProvider::embed_all`] splits larger inputs into batches the
+//! provider accepts.
+//!
+//! # Implementations
+//!
+//! - [`OpenAIEmbeddingProvider`]: OpenAI's embeddings API
+//! - [`OllamaEmbeddingProvider`]: embedding models served by a local Ollama
+//!   instance
+
+pub mod ollama;
+pub mod openai;
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+
+pub use ollama::OllamaEmbeddingProvider;
+pub use openai::OpenAIEmbeddingProvider;
+
+/// Trait for text embedding providers
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed texts in a single request
+    ///
+    /// # Arguments
+    /// * `texts` - At most [`max_batch_size`](Self::max_batch_size) texts
+    ///
+    /// # Returns
+    /// * `Result<Vec<Vec<f32>>>` - One embedding per text, in order, or an error
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    /// Most texts [`embed_batch`](Self::embed_batch) accepts per request
+    fn max_batch_size(&self) -> usize;
+
+    /// Embed a single text
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut embeddings = self.embed_batch(&[text.to_string()]).await?;
+        if embeddings.len() != 1 {
+            return Err(AgentError::LLMProvider(format!(
+                "Expected 1 embedding but received {}",
+                embeddings.len()
+            )));
+        }
+        Ok(embeddings.remove(0))
+    }
+
+    /// Embed any number of texts, in batches of at most
+    /// [`max_batch_size`](Self::max_batch_size)
+    ///
+    /// # Errors
+    /// Returns an error if a request fails or a batch does not return one
+    /// embedding per text.
+    async fn embed_all(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.max_batch_size().max(1)) {
+            let batch_embeddings = self.embed_batch(batch).await?;
+            if batch_embeddings.len() != batch.len() {
+                return Err(AgentError::LLMProvider(format!(
+                    "Expected {} embeddings but received {}",
+                    batch.len(),
+                    batch_embeddings.len()
+                )));
+            }
+            embeddings.extend(batch_embeddings);
+        }
+        Ok(embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Embeds each text as its length, recording batch sizes
+    #[derive(Default)]
+    struct Lengths {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for Lengths {
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.batches.lock().unwrap().push(texts.len());
+            Ok(texts.iter().map(|text| vec![text.len() as f32]).collect())
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_all_batches_in_order() {
+        let provider = Lengths::default();
+        let texts: Vec<String> = ["a", "bb", "ccc", "dddd", "eeeee"]
+            .iter()
+            .map(|text| text.to_string())
+            .collect();
+
+        let embeddings = provider.embed_all(&texts).await.unwrap();
+        assert_eq!(
+            embeddings,
+            vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]
+        );
+        assert_eq!(*provider.batches.lock().unwrap(), vec![2, 2, 1]);
+        assert_eq!(provider.embed("hello").await.unwrap(), vec![5.0]);
+    }
+}
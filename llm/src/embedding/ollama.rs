@@ -0,0 +1,140 @@
+//! Ollama local embeddings provider.
+
+use agent_core::Result;
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::EmbeddingProvider;
+
+/// Default Ollama server URL
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Default number of texts per request
+const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Request body for the Ollama embed endpoint
+#[derive(Debug, Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Embedding provider for models served by a local Ollama instance
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{EmbeddingProvider, OllamaEmbeddingProvider};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OllamaEmbeddingProvider::new("nomic-embed-text");
+/// let texts = vec!["first document".to_string(), "second document".to_string()];
+/// let embeddings = provider.embed_all(&texts).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OllamaEmbeddingProvider {
+    model: String,
+    batch_size: usize,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OllamaEmbeddingProvider {
+    /// Create a provider for an embedding model pulled into Ollama
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the number of texts sent per request
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set the Ollama server URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = EmbedRequest {
+            model: &self.model,
+            input: texts,
+        };
+
+        let url = format!("{}/api/embed", self.base_url);
+        let response: EmbedResponse = self.client.post_json(&url, &request).await?;
+        Ok(response.embeddings)
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.batch_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_embed_all_in_batches() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .and(body_partial_json(json!({"model": "nomic-embed-text", "input": ["a", "b"]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "nomic-embed-text",
+                "embeddings": [[0.1, 0.2], [0.3, 0.4]]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embed"))
+            .and(body_partial_json(json!({"input": ["c"]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "nomic-embed-text",
+                "embeddings": [[0.5, 0.6]]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = OllamaEmbeddingProvider::new("nomic-embed-text")
+            .with_batch_size(2)
+            .with_base_url(mock_server.uri());
+        let texts: Vec<String> = ["a", "b", "c"].iter().map(|t| t.to_string()).collect();
+        let embeddings = provider.embed_all(&texts).await.unwrap();
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4], vec![0.5, 0.6]]);
+    }
+}
@@ -0,0 +1,201 @@
+//! OpenAI embeddings provider.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::{ApiClient, check_status, request_error};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::EmbeddingProvider;
+
+/// Default OpenAI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Default number of texts per request; the API accepts up to 2048
+const DEFAULT_BATCH_SIZE: usize = 256;
+
+/// Request body for the OpenAI embeddings endpoint
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    encoding_format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embedding provider backed by OpenAI's embeddings API
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{EmbeddingProvider, OpenAIEmbeddingProvider};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OpenAIEmbeddingProvider::new("sk-...").with_dimensions(512);
+/// let embedding = provider.embed("How do I reset my password?").await?;
+/// assert_eq!(embedding.len(), 512);
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenAIEmbeddingProvider {
+    api_key: String,
+    model: String,
+    dimensions: Option<usize>,
+    batch_size: usize,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OpenAIEmbeddingProvider {
+    /// Create a provider using the `text-embedding-3-small` model
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "text-embedding-3-small".to_string(),
+            dimensions: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the embedding model (e.g. "text-embedding-3-large")
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Shorten embeddings to `dimensions` (`text-embedding-3` models only)
+    pub fn with_dimensions(mut self, dimensions: usize) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Set the number of texts sent per request, up to 2048
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, 2048);
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbeddingProvider {
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+            encoding_format: "float",
+            dimensions: self.dimensions,
+        };
+
+        let url = format!("{}/embeddings", self.base_url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("OpenAI embeddings", e))?;
+
+        let response = check_status("OpenAI embeddings", response).await?;
+
+        let mut response: EmbeddingResponse = response.json().await.map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize embeddings response: {}", e))
+        })?;
+        // Entries carry their input's index and are not guaranteed to be in order
+        response.data.sort_by_key(|data| data.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect())
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.batch_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_embed_batch() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .and(header("Authorization", "Bearer test-key"))
+            .and(body_partial_json(json!({
+                "model": "text-embedding-3-small",
+                "input": ["first", "second"],
+                "dimensions": 2
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    {"object": "embedding", "index": 1, "embedding": [0.0, 1.0]},
+                    {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]}
+                ],
+                "model": "text-embedding-3-small",
+                "usage": {"prompt_tokens": 2, "total_tokens": 2}
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIEmbeddingProvider::new("test-key")
+            .with_dimensions(2)
+            .with_base_url(mock_server.uri());
+        let embeddings = provider
+            .embed_batch(&["first".to_string(), "second".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_http_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Invalid input"))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIEmbeddingProvider::new("test-key").with_base_url(mock_server.uri());
+        let err = provider.embed("hello").await.unwrap_err();
+        assert!(err.to_string().contains("OpenAI embeddings HTTP 400"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Typed extraction of structured data from text.
+//!
+//! Pulling a typed record out of free text is the most common one-off task
+//! people write against a model by hand: describe the fields, ask for JSON,
+//! parse it, retry when it's malformed. [`extract`] does all of it for any
+//! type implementing [`Extractable`], using
+//! [`LLMProvider::send_structured`] so the reply is validated against the
+//! type's schema and repaired before it is deserialized.
+
+use agent_core::{AgentError, Message, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use crate::{LLMProvider, StructuredOutput};
+
+/// Instruction sent ahead of the text to extract from
+const EXTRACTION_PROMPT: &str = "Extract the requested information from the text below. \
+Use only information stated in the text; leave out optional fields it does not mention.";
+
+/// A type that can be extracted from text
+///
+/// The schema uses the subset of JSON Schema understood by
+/// [`StructuredOutput`], and must describe the JSON the type deserializes
+/// from.
+///
+/// # Example
+///
+/// ```
+/// use llm::Extractable;
+/// use serde::Deserialize;
+/// use serde_json::{Value, json};
+///
+/// #[derive(Deserialize)]
+/// struct Contact {
+///     name: String,
+///     email: Option<String>,
+/// }
+///
+/// impl Extractable for Contact {
+///     fn schema() -> Value {
+///         json!({
+///             "type": "object",
+///             "properties": {
+///                 "name": {"type": "string", "description": "Full name"},
+///                 "email": {"type": "string"}
+///             },
+///             "required": ["name"],
+///             "additionalProperties": false
+///         })
+///     }
+/// }
+/// ```
+pub trait Extractable: DeserializeOwned {
+    /// JSON Schema of the extracted value
+    fn schema() -> Value;
+
+    /// Build the value from a reply that matched [`schema`](Self::schema)
+    fn from_value(value: Value) -> serde_json::Result<Self> {
+        serde_json::from_value(value)
+    }
+}
+
+/// Every match in the text, e.g. `extract::<Vec<Contact>>`
+///
+/// The list is wrapped in an object, since some providers only accept
+/// objects as structured output.
+impl<T: Extractable> Extractable for Vec<T> {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "items": {"type": "array", "items": T::schema()}
+            },
+            "required": ["items"],
+            "additionalProperties": false
+        })
+    }
+
+    fn from_value(mut value: Value) -> serde_json::Result<Self> {
+        let items: Vec<Value> = serde_json::from_value(value["items"].take())?;
+        items.into_iter().map(T::from_value).collect()
+    }
+}
+
+/// Extract a `T` from `text`
+///
+/// # Errors
+/// Returns an error if the request fails, if the model's reply still does
+/// not match `T::schema()` after the repair attempts, or if the validated
+/// reply does not deserialize into `T`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use llm::Extractable;
+/// # use serde::Deserialize;
+/// # use serde_json::{Value, json};
+/// # #[derive(Deserialize)]
+/// # struct Contact { name: String }
+/// # impl Extractable for Contact {
+/// #     fn schema() -> Value { json!({"type": "object"}) }
+/// # }
+/// use llm::{OpenAIProvider, extract};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OpenAIProvider::builder().api_key("your-api-key").build()?;
+/// let contacts: Vec<Contact> =
+///     extract(&provider, "Reach Ada at ada@example.com or Alan on 555-0100").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn extract<T, P>(provider: &P, text: &str) -> Result<T>
+where
+    T: Extractable,
+    P: LLMProvider + ?Sized,
+{
+    extract_with(provider, text, &StructuredOutput::new(T::schema())).await
+}
+
+/// Extract a `T` from `text` with custom structured output settings, e.g.
+/// more repair attempts or a grammar for local models
+///
+/// The schema in `output` is used as given, so it should be `T::schema()`
+/// or a stricter version of it.
+///
+/// # Errors
+/// See [`extract`].
+pub async fn extract_with<T, P>(provider: &P, text: &str, output: &StructuredOutput) -> Result<T>
+where
+    T: Extractable,
+    P: LLMProvider + ?Sized,
+{
+    let messages = [Message::user(format!(
+        "{}\n\n<text>\n{}\n</text>",
+        EXTRACTION_PROMPT, text
+    ))];
+    let value = provider.send_structured(&messages, output).await?;
+    T::from_value(value)
+        .map_err(|e| AgentError::LLMProvider(format!("Extracted value has the wrong shape: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Contact {
+        name: String,
+        email: Option<String>,
+    }
+
+    impl Extractable for Contact {
+        fn schema() -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "email": {"type": "string"}
+                },
+                "required": ["name"]
+            })
+        }
+    }
+
+    /// Replies with scripted responses in order and records the requests
+    struct Scripted {
+        replies: Mutex<Vec<&'static str>>,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl Scripted {
+        fn new(replies: Vec<&'static str>) -> Self {
+            Self {
+                replies: Mutex::new(replies),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.replies.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_typed_value() {
+        let provider = Scripted::new(vec![
+            "Sure! {\"email\": \"ada@example.com\"}",
+            r#"{"name": "Ada Lovelace", "email": "ada@example.com"}"#,
+        ]);
+
+        let contact: Contact = extract(&provider, "Write to Ada Lovelace at ada@example.com")
+            .await
+            .unwrap();
+        assert_eq!(
+            contact,
+            Contact {
+                name: "Ada Lovelace".to_string(),
+                email: Some("ada@example.com".to_string()),
+            }
+        );
+
+        // The missing name was sent back for repair
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0][1].content.contains("<text>\nWrite to Ada"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_list() {
+        let provider = Scripted::new(vec![
+            r#"{"items": [{"name": "Ada"}, {"name": "Alan", "email": "alan@example.com"}]}"#,
+        ]);
+
+        let contacts: Vec<Contact> = extract(&provider, "Ada and Alan").await.unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[1].email.as_deref(), Some("alan@example.com"));
+    }
+}
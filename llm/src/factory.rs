@@ -1,7 +1,10 @@
 use agent_core::{AgentError, Result};
 use config::LLMConfig;
 
-use crate::{anthropic::AnthropicProvider, openai::OpenAIProvider, LLMProvider};
+use crate::{
+    anthropic::AnthropicProvider, openai::OpenAIProvider, GeminiProvider, LLMProvider,
+    LlamaCppProvider, OllamaProvider, OpenRouterProvider, TracedProvider,
+};
 
 /// Create an LLM provider instance from configuration
 ///
@@ -9,7 +12,8 @@ use crate::{anthropic::AnthropicProvider, openai::OpenAIProvider, LLMProvider};
 /// * `config` - LLM configuration specifying provider type and parameters
 ///
 /// # Returns
-/// * `Result<Box<dyn LLMProvider>>` - Provider instance or error
+/// * `Result<Box<dyn LLMProvider>>` - Provider instance or error, wrapped
+///   in a [`TracedProvider`] that names the configured provider and model
 ///
 /// # Errors
 /// Returns an error if:
@@ -18,27 +22,55 @@ use crate::{anthropic::AnthropicProvider, openai::OpenAIProvider, LLMProvider};
 ///
 /// # Supported Providers
 /// - "openai" - OpenAI GPT models
+/// - "azure" - OpenAI models deployed on Azure OpenAI
 /// - "anthropic" - Anthropic Claude models
+/// - "gemini" - Google Gemini models
+/// - "openrouter" - Models of many vendors routed by OpenRouter
+/// - "ollama" - Local models served by Ollama
+/// - "llamacpp" - Local models served by the llama.cpp server
 pub fn create_provider(config: &LLMConfig) -> Result<Box<dyn LLMProvider>> {
-    match config.provider.as_str() {
-        "openai" => {
+    let provider: Box<dyn LLMProvider> = match config.provider.as_str() {
+        "openai" | "azure" => {
             let provider = OpenAIProvider::new(config)?;
-            Ok(Box::new(provider))
+            Box::new(provider)
         }
         "anthropic" => {
             let provider = AnthropicProvider::new(config)?;
-            Ok(Box::new(provider))
+            Box::new(provider)
         }
-        _ => Err(AgentError::Config(format!(
-            "Unknown LLM provider: '{}'. Supported providers: openai, anthropic",
-            config.provider
-        ))),
-    }
+        "gemini" => {
+            let provider = GeminiProvider::new(config)?;
+            Box::new(provider)
+        }
+        "openrouter" => {
+            let provider = OpenRouterProvider::new(config)?;
+            Box::new(provider)
+        }
+        "ollama" => {
+            let provider = OllamaProvider::new(config)?;
+            Box::new(provider)
+        }
+        "llamacpp" => {
+            let provider = LlamaCppProvider::new(config)?;
+            Box::new(provider)
+        }
+        _ => {
+            return Err(AgentError::Config(format!(
+                "Unknown LLM provider: '{}'. Supported providers: openai, azure, anthropic, \
+                 gemini, openrouter, ollama, llamacpp",
+                config.provider
+            )));
+        }
+    };
+    Ok(Box::new(
+        TracedProvider::new(provider, &config.provider).with_model(&config.model),
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use config::{AzureConfig, OpenRouterConfig};
 
     #[test]
     fn test_create_openai_provider() {
@@ -48,6 +80,12 @@ mod tests {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
         };
 
         let result = create_provider(&config);
@@ -62,12 +100,98 @@ mod tests {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
         };
 
         let result = create_provider(&config);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_create_azure_provider() {
+        let mut config = LLMConfig {
+            provider: "azure".to_string(),
+            model: "gpt-4o".to_string(),
+            api_key: "test-key".to_string(),
+            temperature: 0.7,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+        assert!(matches!(create_provider(&config), Err(AgentError::Config(_))));
+
+        config.azure = Some(AzureConfig::new("https://my-resource.openai.azure.com"));
+        assert!(create_provider(&config).is_ok());
+    }
+
+    #[test]
+    fn test_create_gemini_provider() {
+        let config = LLMConfig {
+            provider: "gemini".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            api_key: "test-key".to_string(),
+            temperature: 0.7,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+
+        assert!(create_provider(&config).is_ok());
+    }
+
+    #[test]
+    fn test_create_openrouter_provider() {
+        let config = LLMConfig {
+            provider: "openrouter".to_string(),
+            model: "anthropic/claude-sonnet-4".to_string(),
+            api_key: "test-key".to_string(),
+            temperature: 0.7,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: Some(OpenRouterConfig::default()),
+            prompt_caching: false,
+        };
+
+        assert!(create_provider(&config).is_ok());
+    }
+
+    #[test]
+    fn test_create_local_providers() {
+        for provider in ["ollama", "llamacpp"] {
+            let config = LLMConfig {
+                provider: provider.to_string(),
+                model: "llama3.2".to_string(),
+                api_key: String::new(),
+                temperature: 0.7,
+                max_tokens: 2000,
+                organization: None,
+                project: None,
+                safety_settings: Default::default(),
+                azure: None,
+                openrouter: None,
+                prompt_caching: false,
+            };
+
+            assert!(create_provider(&config).is_ok());
+        }
+    }
+
     #[test]
     fn test_create_unknown_provider() {
         let config = LLMConfig {
@@ -76,6 +200,12 @@ mod tests {
             api_key: "test-key".to_string(),
             temperature: 0.7,
             max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
         };
 
         let result = create_provider(&config);
@@ -86,4 +216,44 @@ mod tests {
             assert!(err.to_string().contains("unknown"));
         }
     }
+
+    #[test]
+    fn test_create_provider_with_model_typo() {
+        let config = LLMConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4-o".to_string(),
+            api_key: "test-key".to_string(),
+            temperature: 0.7,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+
+        let result = create_provider(&config);
+        assert!(matches!(result, Err(AgentError::Config(msg)) if msg.contains("gpt-4o")));
+    }
+
+    #[test]
+    fn test_create_provider_with_invalid_temperature() {
+        let config = LLMConfig {
+            provider: "anthropic".to_string(),
+            model: "claude-3-sonnet-20240229".to_string(),
+            api_key: "test-key".to_string(),
+            temperature: 2.5,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+
+        let result = create_provider(&config);
+        assert!(matches!(result, Err(AgentError::Config(msg)) if msg.contains("Temperature")));
+    }
 }
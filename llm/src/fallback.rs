@@ -0,0 +1,364 @@
+//! Failover across an ordered list of providers.
+//!
+//! A [`FallbackProvider`] sends each request to its first provider and moves
+//! on to the next one only when the failure looks like an outage: a timeout,
+//! a connection error, a rate limit or a 5xx response. Other errors, such as
+//! an invalid API key or a rejected request, are returned at once, since the
+//! next provider would most likely reject the request too.
+
+use agent_core::{AgentError, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use communication::should_retry_error;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig};
+
+/// Provider that fails over to the next provider when one is unavailable.
+///
+/// Providers are tried in the order they were added. Each request records
+/// the name of the provider that served it, see
+/// [`FallbackProvider::last_served_by`] and [`FallbackProvider::served_count`].
+///
+/// Streams fail over only while the stream is being opened; once tokens
+/// arrive, an error ends the stream.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{AnthropicProvider, FallbackProvider, LLMProvider, OpenAIProvider};
+/// use agent_core::Message;
+/// use std::time::Duration;
+/// # use config::LLMConfig;
+///
+/// # async fn example(openai: LLMConfig, anthropic: LLMConfig) -> agent_core::Result<()> {
+/// let provider = FallbackProvider::new()
+///     .with_provider("openai", OpenAIProvider::new(&openai)?)
+///     .with_provider("anthropic", AnthropicProvider::new(&anthropic)?)
+///     .with_timeout(Duration::from_secs(30));
+///
+/// let reply = provider.send_message(&[Message::user("Hello")]).await?;
+/// println!("{} answered: {}", provider.last_served_by().unwrap(), reply);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct FallbackProvider {
+    providers: Vec<(String, Box<dyn LLMProvider>)>,
+    timeout: Option<Duration>,
+    last_served_by: Mutex<Option<String>>,
+    served: Mutex<HashMap<String, u64>>,
+}
+
+impl FallbackProvider {
+    /// Create a fallback provider without providers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a provider, tried after those added before it
+    pub fn with_provider(
+        mut self,
+        name: impl Into<String>,
+        provider: impl LLMProvider + 'static,
+    ) -> Self {
+        self.providers.push((name.into(), Box::new(provider)));
+        self
+    }
+
+    /// Give up on a provider after `timeout` and fail over to the next one
+    ///
+    /// Without a timeout a provider is waited on until its own request
+    /// timeout expires.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Names of the providers, in the order they are tried
+    pub fn provider_names(&self) -> Vec<&str> {
+        self.providers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Number of providers
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Returns true if no providers are configured
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Name of the provider that served the most recent successful request
+    pub fn last_served_by(&self) -> Option<String> {
+        self.last_served_by.lock().unwrap().clone()
+    }
+
+    /// Number of requests the named provider has served
+    pub fn served_count(&self, name: &str) -> u64 {
+        self.served.lock().unwrap().get(name).copied().unwrap_or(0)
+    }
+
+    /// Run `call` against each provider in turn until one succeeds or fails
+    /// with an error that is not worth failing over for
+    async fn try_in_order<'a, T, F, Fut>(&'a self, call: F) -> Result<T>
+    where
+        F: Fn(&'a dyn LLMProvider) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if self.providers.is_empty() {
+            return Err(AgentError::Config(
+                "FallbackProvider requires at least one provider".to_string(),
+            ));
+        }
+
+        let mut errors = Vec::new();
+        for (name, provider) in &self.providers {
+            let result = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, call(provider.as_ref()))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(AgentError::Timeout(format!(
+                            "Request timed out after {}ms",
+                            timeout.as_millis()
+                        )))
+                    }),
+                None => call(provider.as_ref()).await,
+            };
+            match result {
+                Ok(response) => {
+                    *self.last_served_by.lock().unwrap() = Some(name.clone());
+                    *self.served.lock().unwrap().entry(name.clone()).or_insert(0) += 1;
+                    return Ok(response);
+                }
+                Err(error) if should_retry_error(&error) => {
+                    errors.push(format!("{}: {}", name, error));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(AgentError::LLMProvider(format!(
+            "All {} providers failed: {}",
+            errors.len(),
+            errors.join("; ")
+        )))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for FallbackProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.try_in_order(|provider| provider.send_message(messages))
+            .await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.try_in_order(|provider| provider.send_completion(messages))
+            .await
+    }
+
+    async fn send_structured(&self, messages: &[Message], output: &StructuredOutput) -> Result<Value> {
+        self.try_in_order(|provider| provider.send_structured(messages, output))
+            .await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.try_in_order(|provider| provider.send_structured_completion(messages, output))
+            .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.try_in_order(|provider| provider.send_message_with_tools(messages, tools, config))
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.try_in_order(|provider| provider.stream_message(messages))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers with a fixed result, counting its calls
+    struct FixedProvider {
+        result: std::result::Result<String, String>,
+        delay: Duration,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl FixedProvider {
+        fn ok(text: &str) -> Self {
+            Self::new(Ok(text.to_string()))
+        }
+
+        fn failing(error: &str) -> Self {
+            Self::new(Err(error.to_string()))
+        }
+
+        fn new(result: std::result::Result<String, String>) -> Self {
+            Self {
+                result,
+                delay: Duration::ZERO,
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for FixedProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.result.clone().map_err(AgentError::LLMProvider)
+        }
+    }
+
+    /// Only answers structured requests, the way a provider with a native
+    /// JSON mode would
+    struct StructuredOnly;
+
+    #[async_trait]
+    impl LLMProvider for StructuredOnly {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            Err(AgentError::LLMProvider("not used".to_string()))
+        }
+
+        async fn send_structured(
+            &self,
+            _messages: &[Message],
+            _output: &StructuredOutput,
+        ) -> Result<Value> {
+            Ok(serde_json::json!({"name": "Ada"}))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_on_outages() {
+        let provider = FallbackProvider::new()
+            .with_provider("primary", FixedProvider::failing("HTTP 503: overloaded"))
+            .with_provider("secondary", FixedProvider::failing("Rate limit exceeded"))
+            .with_provider("tertiary", FixedProvider::ok("Hi!"));
+
+        let reply = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hi!");
+        assert_eq!(provider.last_served_by().as_deref(), Some("tertiary"));
+        assert_eq!(provider.served_count("tertiary"), 1);
+        assert_eq!(provider.served_count("primary"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_do_not_fail_over() {
+        let secondary = FixedProvider::ok("Hi!");
+        let calls = secondary.calls.clone();
+        let provider = FallbackProvider::new()
+            .with_provider(
+                "primary",
+                FixedProvider::failing("HTTP 401: invalid api key"),
+            )
+            .with_provider("secondary", secondary);
+
+        let error = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("401"));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(provider.last_served_by(), None);
+    }
+
+    #[tokio::test]
+    async fn test_slow_provider_times_out() {
+        let provider = FallbackProvider::new()
+            .with_provider(
+                "slow",
+                FixedProvider::ok("late").with_delay(Duration::from_secs(60)),
+            )
+            .with_provider("fast", FixedProvider::ok("Hi!"))
+            .with_timeout(Duration::from_millis(20));
+
+        let reply = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hi!");
+        assert_eq!(provider.last_served_by().as_deref(), Some("fast"));
+
+        let only_slow = FallbackProvider::new()
+            .with_provider(
+                "slow",
+                FixedProvider::ok("late").with_delay(Duration::from_secs(60)),
+            )
+            .with_timeout(Duration::from_millis(20));
+        let error = only_slow
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("slow: Timeout: Request timed out after 20ms"));
+    }
+
+    #[tokio::test]
+    async fn test_all_providers_failing() {
+        let provider = FallbackProvider::new()
+            .with_provider("a", FixedProvider::failing("HTTP 500: boom"))
+            .with_provider("b", FixedProvider::failing("Connection error: refused"));
+
+        let error = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("All 2 providers failed"));
+        assert!(message.contains("a: ") && message.contains("b: "));
+
+        let empty = FallbackProvider::new();
+        assert!(matches!(
+            empty.send_message(&[Message::user("Hello")]).await,
+            Err(AgentError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_structured_requests_use_each_providers_own_implementation() {
+        let provider = FallbackProvider::new()
+            .with_provider("primary", FixedProvider::failing("HTTP 503: overloaded"))
+            .with_provider("native", StructuredOnly);
+
+        let output = StructuredOutput::new(serde_json::json!({"type": "object"}));
+        let value = provider
+            .send_structured(&[Message::user("Who wrote the first program?")], &output)
+            .await
+            .unwrap();
+        assert_eq!(value["name"], "Ada");
+        assert_eq!(provider.last_served_by().as_deref(), Some("native"));
+    }
+}
@@ -0,0 +1,552 @@
+//! Parallel fan-out across multiple providers.
+//!
+//! These wrappers send the same prompt to several providers concurrently,
+//! trading extra cost for lower latency or higher robustness:
+//!
+//! - [`RaceProvider`] returns the first successful response and cancels the rest
+//! - [`ConsensusProvider`] returns the answer agreed on by a majority of providers
+
+use agent_core::{AgentError, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use futures::stream::{self, FuturesUnordered, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+
+use crate::{
+    CompletionResponse, LLMProvider, StreamEvent, StructuredOutput, TokenStream, ToolConfig,
+};
+
+/// Provider that races several providers and returns the first success.
+///
+/// All providers receive the request at the same time. As soon as one of them
+/// returns successfully, the remaining in-flight requests are dropped. The
+/// request only fails if every provider fails.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{AnthropicProvider, LLMProvider, OpenAIProvider, RaceProvider};
+/// # use config::LLMConfig;
+///
+/// # fn example(openai: LLMConfig, anthropic: LLMConfig) -> agent_core::Result<()> {
+/// let provider = RaceProvider::new(vec![
+///     Box::new(OpenAIProvider::new(&openai)?),
+///     Box::new(AnthropicProvider::new(&anthropic)?),
+/// ]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RaceProvider {
+    providers: Vec<Box<dyn LLMProvider>>,
+}
+
+impl RaceProvider {
+    /// Create a new race over the given providers
+    pub fn new(providers: Vec<Box<dyn LLMProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// Number of providers participating in the race
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Returns true if no providers are configured
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Run `call` against every provider at once and return the first success
+    async fn race<'a, T, F, Fut>(&'a self, call: F) -> Result<T>
+    where
+        F: Fn(&'a dyn LLMProvider) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if self.providers.is_empty() {
+            return Err(AgentError::Config(
+                "RaceProvider requires at least one provider".to_string(),
+            ));
+        }
+
+        let mut pending: FuturesUnordered<_> = self
+            .providers
+            .iter()
+            .map(|provider| call(provider.as_ref()))
+            .collect();
+
+        let mut errors = Vec::new();
+        while let Some(result) = pending.next().await {
+            match result {
+                // Returning drops the remaining futures, cancelling their requests
+                Ok(response) => return Ok(response),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        Err(AgentError::LLMProvider(format!(
+            "All {} providers failed: {}",
+            errors.len(),
+            errors.join("; ")
+        )))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for RaceProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.race(|provider| provider.send_message(messages)).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.race(|provider| provider.send_completion(messages)).await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.race(|provider| provider.send_structured(messages, output))
+            .await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.race(|provider| provider.send_structured_completion(messages, output))
+            .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.race(|provider| provider.send_message_with_tools(messages, tools, config))
+            .await
+    }
+
+    /// Races to the first provider that starts a stream; the stream itself
+    /// is not raced
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.race(|provider| provider.stream_message(messages)).await
+    }
+}
+
+/// Provider that asks several providers and returns the majority answer.
+///
+/// Responses are grouped by a normalized form (trimmed, lowercased, whitespace
+/// collapsed) so that trivial formatting differences don't split the vote. As
+/// soon as one answer reaches the required number of votes it is returned and
+/// the remaining requests are dropped.
+///
+/// Structured responses agree when their values are equal, and tool-calling
+/// responses when they call the same tools with the same arguments.
+/// Streaming requests wait for consensus and yield the agreed answer as a
+/// single event.
+///
+/// By default a strict majority of all providers must agree; use
+/// [`ConsensusProvider::with_min_agreement`] to change the threshold.
+pub struct ConsensusProvider {
+    providers: Vec<Box<dyn LLMProvider>>,
+    min_agreement: usize,
+}
+
+impl ConsensusProvider {
+    /// Create a new consensus provider requiring a strict majority
+    pub fn new(providers: Vec<Box<dyn LLMProvider>>) -> Self {
+        let min_agreement = providers.len() / 2 + 1;
+        Self {
+            providers,
+            min_agreement,
+        }
+    }
+
+    /// Set how many providers must return the same answer
+    ///
+    /// # Arguments
+    /// * `min_agreement` - Number of matching votes required (at least 1)
+    pub fn with_min_agreement(mut self, min_agreement: usize) -> Self {
+        self.min_agreement = min_agreement.max(1);
+        self
+    }
+
+    /// Number of matching votes required for consensus
+    pub fn min_agreement(&self) -> usize {
+        self.min_agreement
+    }
+
+    /// Normalize a response for vote counting
+    fn normalize(response: &str) -> String {
+        response
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    /// Run `call` against every provider at once and return the first
+    /// response whose `answer` reaches the required number of votes
+    async fn vote<'a, T, F, Fut>(&'a self, call: F, answer: impl Fn(&T) -> String) -> Result<T>
+    where
+        F: Fn(&'a dyn LLMProvider) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if self.providers.is_empty() {
+            return Err(AgentError::Config(
+                "ConsensusProvider requires at least one provider".to_string(),
+            ));
+        }
+
+        let mut pending: FuturesUnordered<_> = self
+            .providers
+            .iter()
+            .map(|provider| call(provider.as_ref()))
+            .collect();
+
+        // answer -> (votes, first response with that answer)
+        let mut votes: HashMap<String, (usize, T)> = HashMap::new();
+        let mut errors = Vec::new();
+
+        while let Some(result) = pending.next().await {
+            match result {
+                Ok(response) => {
+                    let key = answer(&response);
+                    let entry = votes.entry(key.clone()).or_insert((0, response));
+                    entry.0 += 1;
+                    if entry.0 >= self.min_agreement
+                        && let Some((_, response)) = votes.remove(&key)
+                    {
+                        return Ok(response);
+                    }
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        let best = votes.values().map(|(count, _)| *count).max().unwrap_or(0);
+        let mut message = format!(
+            "No consensus reached: best answer had {} of {} required votes ({} providers responded, {} failed)",
+            best,
+            self.min_agreement,
+            self.providers.len() - errors.len(),
+            errors.len()
+        );
+        if !errors.is_empty() {
+            message.push_str(&format!(". Errors: {}", errors.join("; ")));
+        }
+
+        Err(AgentError::LLMProvider(message))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ConsensusProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.vote(
+            |provider| provider.send_message(messages),
+            |response| Self::normalize(response),
+        )
+        .await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.vote(
+            |provider| provider.send_completion(messages),
+            |response| Self::normalize(&response.text),
+        )
+        .await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.vote(
+            |provider| provider.send_structured(messages, output),
+            |value| value.to_string(),
+        )
+        .await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.vote(
+            |provider| provider.send_structured_completion(messages, output),
+            |(value, _)| value.to_string(),
+        )
+        .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.vote(
+            |provider| provider.send_message_with_tools(messages, tools, config),
+            |response| {
+                // Call IDs are assigned by each provider, so only the tools
+                // and their arguments take part in the vote
+                let mut answer = Self::normalize(&response.text);
+                for tool_use in &response.tool_uses {
+                    answer.push_str(&format!("\n{}({})", tool_use.name, tool_use.arguments));
+                }
+                answer
+            },
+        )
+        .await
+    }
+
+    /// Waits for consensus and streams the agreed answer as one event
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let response = self.send_completion(messages).await?;
+        let mut events = vec![Ok(StreamEvent::Text(response.text))];
+        if let Some(usage) = response.usage {
+            events.push(Ok(StreamEvent::Usage(usage)));
+        }
+        Ok(Box::pin(stream::iter(events)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::ToolUse;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    /// Mock provider that answers after a delay
+    struct DelayedProvider {
+        response: std::result::Result<String, String>,
+        delay: Duration,
+        finished: Arc<AtomicBool>,
+    }
+
+    impl DelayedProvider {
+        fn ok(response: &str, delay_ms: u64) -> Box<dyn LLMProvider> {
+            Box::new(Self {
+                response: Ok(response.to_string()),
+                delay: Duration::from_millis(delay_ms),
+                finished: Arc::new(AtomicBool::new(false)),
+            })
+        }
+
+        fn err(message: &str, delay_ms: u64) -> Box<dyn LLMProvider> {
+            Box::new(Self {
+                response: Err(message.to_string()),
+                delay: Duration::from_millis(delay_ms),
+                finished: Arc::new(AtomicBool::new(false)),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for DelayedProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            tokio::time::sleep(self.delay).await;
+            self.finished.store(true, Ordering::SeqCst);
+            self.response.clone().map_err(AgentError::LLMProvider)
+        }
+    }
+
+    /// Mock provider that calls a tool under its own call ID
+    struct ToolCallingProvider {
+        id: &'static str,
+        city: &'static str,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ToolCallingProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            Ok(String::new())
+        }
+
+        async fn send_message_with_tools(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _config: &ToolConfig,
+        ) -> Result<ToolUseResponse> {
+            Ok(ToolUseResponse {
+                text: String::new(),
+                tool_uses: vec![ToolUse {
+                    id: self.id.to_string(),
+                    name: "weather".to_string(),
+                    arguments: json!({ "city": self.city }),
+                }],
+            })
+        }
+    }
+
+    fn tool_caller(id: &'static str, city: &'static str) -> Box<dyn LLMProvider> {
+        Box::new(ToolCallingProvider { id, city })
+    }
+
+    fn messages() -> Vec<Message> {
+        vec![Message::user("What is 2+2?")]
+    }
+
+    #[tokio::test]
+    async fn test_race_returns_fastest_success() {
+        let provider = RaceProvider::new(vec![
+            DelayedProvider::ok("slow", 200),
+            DelayedProvider::ok("fast", 10),
+        ]);
+
+        assert_eq!(provider.send_message(&messages()).await.unwrap(), "fast");
+    }
+
+    #[tokio::test]
+    async fn test_race_skips_failures() {
+        let provider = RaceProvider::new(vec![
+            DelayedProvider::err("boom", 5),
+            DelayedProvider::ok("eventually", 30),
+        ]);
+
+        assert_eq!(
+            provider.send_message(&messages()).await.unwrap(),
+            "eventually"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_race_fails_when_all_fail() {
+        let provider = RaceProvider::new(vec![
+            DelayedProvider::err("first down", 5),
+            DelayedProvider::err("second down", 5),
+        ]);
+
+        let err = provider.send_message(&messages()).await.unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("All 2 providers failed"));
+        assert!(msg.contains("first down"));
+        assert!(msg.contains("second down"));
+    }
+
+    #[tokio::test]
+    async fn test_race_empty_is_config_error() {
+        let provider = RaceProvider::new(vec![]);
+        let result = provider.send_message(&messages()).await;
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_consensus_returns_majority_answer() {
+        let provider = ConsensusProvider::new(vec![
+            DelayedProvider::ok("4", 10),
+            DelayedProvider::ok("5", 5),
+            DelayedProvider::ok(" 4 ", 20),
+        ]);
+
+        assert_eq!(provider.min_agreement(), 2);
+        assert_eq!(provider.send_message(&messages()).await.unwrap(), "4");
+    }
+
+    #[tokio::test]
+    async fn test_consensus_stops_once_majority_reached() {
+        let straggler = Arc::new(AtomicBool::new(false));
+        let provider = ConsensusProvider::new(vec![
+            DelayedProvider::ok("Paris", 5),
+            DelayedProvider::ok("paris", 10),
+            Box::new(DelayedProvider {
+                response: Ok("Lyon".to_string()),
+                delay: Duration::from_millis(500),
+                finished: straggler.clone(),
+            }),
+        ]);
+
+        assert_eq!(provider.send_message(&messages()).await.unwrap(), "Paris");
+        assert!(!straggler.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_consensus_fails_without_agreement() {
+        let provider = ConsensusProvider::new(vec![
+            DelayedProvider::ok("a", 5),
+            DelayedProvider::ok("b", 5),
+            DelayedProvider::err("timeout", 5),
+        ]);
+
+        let err = provider.send_message(&messages()).await.unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("No consensus reached"));
+        assert!(msg.contains("timeout"));
+    }
+
+    #[tokio::test]
+    async fn test_consensus_custom_threshold() {
+        let provider = ConsensusProvider::new(vec![
+            DelayedProvider::ok("a", 5),
+            DelayedProvider::ok("b", 10),
+        ])
+        .with_min_agreement(1);
+
+        assert_eq!(provider.send_message(&messages()).await.unwrap(), "a");
+    }
+
+    #[tokio::test]
+    async fn test_race_forwards_completions_and_streams() {
+        let provider = RaceProvider::new(vec![
+            DelayedProvider::ok("slow", 200),
+            DelayedProvider::ok("fast", 10),
+        ]);
+
+        let response = provider.send_completion(&messages()).await.unwrap();
+        assert_eq!(response.text, "fast");
+
+        let events: Vec<_> = provider
+            .stream_message(&messages())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].as_ref().unwrap(), &StreamEvent::Text("fast".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_consensus_on_tool_calls_ignores_call_ids() {
+        let provider = ConsensusProvider::new(vec![
+            tool_caller("call_1", "Paris"),
+            tool_caller("call_2", "Lyon"),
+            tool_caller("call_3", "Paris"),
+        ]);
+
+        let response = provider
+            .send_message_with_tools(&messages(), &[], &ToolConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.tool_uses[0].arguments, json!({ "city": "Paris" }));
+        assert_eq!(response.tool_uses[0].id, "call_1");
+    }
+
+    #[tokio::test]
+    async fn test_consensus_streams_agreed_answer() {
+        let provider = ConsensusProvider::new(vec![
+            DelayedProvider::ok("4", 5),
+            DelayedProvider::ok("5", 10),
+            DelayedProvider::ok("4", 15),
+        ]);
+
+        let events: Vec<_> = provider
+            .stream_message(&messages())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].as_ref().unwrap(), &StreamEvent::Text("4".to_string()));
+    }
+}
@@ -0,0 +1,209 @@
+//! Anthropic Files API store.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::{FileStore, FileUpload, StoredFile, check_status, request_error};
+use crate::anthropic::FILES_API_BETA;
+
+/// Default Anthropic API base URL
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+
+/// Service name used in error messages
+const SERVICE: &str = "Anthropic Files API";
+
+/// File metadata returned by the Files API
+#[derive(Debug, Deserialize)]
+struct FileMetadata {
+    id: String,
+    filename: String,
+    size_bytes: u64,
+    mime_type: String,
+}
+
+impl From<FileMetadata> for StoredFile {
+    fn from(file: FileMetadata) -> Self {
+        StoredFile {
+            id: file.id,
+            file_name: file.filename,
+            size_bytes: file.size_bytes,
+            mime_type: file.mime_type,
+        }
+    }
+}
+
+/// Response body of the list endpoint
+#[derive(Debug, Deserialize)]
+struct FileList {
+    data: Vec<FileMetadata>,
+}
+
+/// File store backed by Anthropic's Files API
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::Message;
+/// use llm::{AnthropicFileStore, FileStore, FileUpload};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let store = AnthropicFileStore::new("sk-ant-...");
+/// let file = store.upload(&FileUpload::from_file("diagram.png").await?).await?;
+/// let message = Message::user("What does this diagram show?").with_attachment(file.file_ref());
+/// # Ok(())
+/// # }
+/// ```
+pub struct AnthropicFileStore {
+    api_key: String,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl AnthropicFileStore {
+    /// Create a store for the given API key
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, format!("{}/files{}", self.base_url, path))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("anthropic-beta", FILES_API_BETA)
+            .timeout(self.client.timeout())
+    }
+}
+
+#[async_trait]
+impl FileStore for AnthropicFileStore {
+    async fn upload(&self, file: &FileUpload) -> Result<StoredFile> {
+        let form = reqwest::multipart::Form::new().part("file", file.to_part()?);
+
+        let response = self
+            .request(reqwest::Method::POST, "")
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        let metadata: FileMetadata = check_status(SERVICE, response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| AgentError::LLMProvider(format!("Failed to deserialize file: {}", e)))?;
+        Ok(metadata.into())
+    }
+
+    async fn list(&self) -> Result<Vec<StoredFile>> {
+        let response = self
+            .request(reqwest::Method::GET, "")
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        let list: FileList = check_status(SERVICE, response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| {
+                AgentError::LLMProvider(format!("Failed to deserialize file list: {}", e))
+            })?;
+        Ok(list.data.into_iter().map(StoredFile::from).collect())
+    }
+
+    async fn delete(&self, file_id: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("/{}", file_id))
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        check_status(SERVICE, response).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_upload_sends_beta_header() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/files"))
+            .and(header("x-api-key", "test-key"))
+            .and(header("anthropic-beta", FILES_API_BETA))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "file_011",
+                "type": "file",
+                "filename": "report.pdf",
+                "size_bytes": 1024,
+                "mime_type": "application/pdf",
+                "created_at": "2025-01-01T00:00:00Z"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let store = AnthropicFileStore::new("test-key").with_base_url(mock_server.uri());
+        let stored = store
+            .upload(&FileUpload::new(vec![0; 16], "report.pdf"))
+            .await
+            .unwrap();
+        assert_eq!(stored.id, "file_011");
+        assert_eq!(stored.mime_type, "application/pdf");
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "id": "file_1",
+                    "filename": "cat.png",
+                    "size_bytes": 3,
+                    "mime_type": "image/png"
+                }],
+                "has_more": false
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/files/file_1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "file_1"})))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let store = AnthropicFileStore::new("test-key").with_base_url(mock_server.uri());
+        let files = store.list().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name, "cat.png");
+
+        store.delete("file_1").await.unwrap();
+    }
+}
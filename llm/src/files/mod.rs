@@ -0,0 +1,169 @@
+//! Provider file APIs.
+//!
+//! A [`FileStore`] uploads files to a provider once so later messages can
+//! reference them by ID instead of resending their contents. Uploaded files
+//! are attached to a message with [`Message::with_attachment`], using the
+//! [`FileRef`] returned by [`StoredFile::file_ref`].
+//!
+//! [`Message::with_attachment`]: agent_core::Message::with_attachment
+
+pub mod anthropic;
+pub mod openai;
+
+use agent_core::{AgentError, FileRef, Result};
+use async_trait::async_trait;
+use communication::{check_status, request_error};
+use std::path::Path;
+
+pub use anthropic::AnthropicFileStore;
+pub use openai::OpenAIFileStore;
+
+/// A file to be uploaded
+#[derive(Debug, Clone)]
+pub struct FileUpload {
+    /// Raw file contents
+    pub data: Vec<u8>,
+    /// File name sent with the upload
+    pub file_name: String,
+    /// MIME type of the file
+    pub mime_type: String,
+}
+
+impl FileUpload {
+    /// Create an upload from bytes, inferring the MIME type from the file name
+    pub fn new(data: Vec<u8>, file_name: impl Into<String>) -> Self {
+        let file_name = file_name.into();
+        let mime_type = mime_type_for(&file_name).to_string();
+        Self {
+            data,
+            file_name,
+            mime_type,
+        }
+    }
+
+    /// Read an upload from a file on disk
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = tokio::fs::read(path).await?;
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| AgentError::Config(format!("Invalid file path: {}", path.display())))?;
+        Ok(Self::new(data, file_name))
+    }
+
+    /// Override the inferred MIME type
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = mime_type.into();
+        self
+    }
+
+    /// Build a multipart form part for this file
+    fn to_part(&self) -> Result<reqwest::multipart::Part> {
+        reqwest::multipart::Part::bytes(self.data.clone())
+            .file_name(self.file_name.clone())
+            .mime_str(&self.mime_type)
+            .map_err(|e| AgentError::Config(format!("Invalid file MIME type: {}", e)))
+    }
+}
+
+/// A file stored with a provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredFile {
+    /// Provider-assigned file ID
+    pub id: String,
+    /// Original file name
+    pub file_name: String,
+    /// Size in bytes
+    pub size_bytes: u64,
+    /// MIME type of the file
+    pub mime_type: String,
+}
+
+impl StoredFile {
+    /// Reference for attaching this file to a message
+    pub fn file_ref(&self) -> FileRef {
+        FileRef::new(&self.id, &self.mime_type)
+    }
+}
+
+/// Trait for provider file APIs
+///
+/// Covers the lifecycle of uploaded files: upload, list and delete.
+#[async_trait]
+pub trait FileStore: Send + Sync {
+    /// Upload a file, returning its stored metadata
+    async fn upload(&self, file: &FileUpload) -> Result<StoredFile>;
+
+    /// List all stored files
+    async fn list(&self) -> Result<Vec<StoredFile>>;
+
+    /// Delete a stored file by ID
+    async fn delete(&self, file_id: &str) -> Result<()>;
+}
+
+fn mime_type_for(file_name: &str) -> &'static str {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "jsonl" => "application/jsonl",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_type_inference() {
+        assert_eq!(
+            FileUpload::new(vec![], "report.PDF").mime_type,
+            "application/pdf"
+        );
+        assert_eq!(
+            FileUpload::new(vec![], "photo.jpeg").mime_type,
+            "image/jpeg"
+        );
+        assert_eq!(
+            FileUpload::new(vec![], "data.bin").mime_type,
+            "application/octet-stream"
+        );
+        assert_eq!(
+            FileUpload::new(vec![], "data.bin")
+                .with_mime_type("text/plain")
+                .mime_type,
+            "text/plain"
+        );
+    }
+
+    #[test]
+    fn test_file_ref() {
+        let stored = StoredFile {
+            id: "file-1".to_string(),
+            file_name: "cat.png".to_string(),
+            size_bytes: 10,
+            mime_type: "image/png".to_string(),
+        };
+        let file_ref = stored.file_ref();
+        assert_eq!(file_ref.id, "file-1");
+        assert!(file_ref.is_image());
+    }
+
+    #[tokio::test]
+    async fn test_from_missing_file() {
+        let result = FileUpload::from_file("/nonexistent/report.pdf").await;
+        assert!(result.is_err());
+    }
+}
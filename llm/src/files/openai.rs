@@ -0,0 +1,235 @@
+//! OpenAI Files API store.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::{FileStore, FileUpload, StoredFile, check_status, mime_type_for, request_error};
+
+/// Default OpenAI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Service name used in error messages
+const SERVICE: &str = "OpenAI Files API";
+
+/// File object returned by the Files API
+#[derive(Debug, Deserialize)]
+struct FileObject {
+    id: String,
+    filename: String,
+    bytes: u64,
+}
+
+impl From<FileObject> for StoredFile {
+    fn from(file: FileObject) -> Self {
+        // The API does not report MIME types, so infer one from the name
+        let mime_type = mime_type_for(&file.filename).to_string();
+        StoredFile {
+            id: file.id,
+            file_name: file.filename,
+            size_bytes: file.bytes,
+            mime_type,
+        }
+    }
+}
+
+/// Response body of the list endpoint
+#[derive(Debug, Deserialize)]
+struct FileList {
+    data: Vec<FileObject>,
+}
+
+/// File store backed by OpenAI's Files API
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::Message;
+/// use llm::{FileStore, FileUpload, OpenAIFileStore};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let store = OpenAIFileStore::new("sk-...");
+/// let file = store.upload(&FileUpload::from_file("report.pdf").await?).await?;
+/// let message = Message::user("Summarize this report").with_attachment(file.file_ref());
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenAIFileStore {
+    api_key: String,
+    purpose: String,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OpenAIFileStore {
+    /// Create a store that uploads files with the `user_data` purpose
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            purpose: "user_data".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the purpose sent with uploads (e.g. `assistants`, `fine-tune`)
+    pub fn with_purpose(mut self, purpose: impl Into<String>) -> Self {
+        self.purpose = purpose.into();
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, format!("{}/files{}", self.base_url, path))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .timeout(self.client.timeout())
+    }
+}
+
+#[async_trait]
+impl FileStore for OpenAIFileStore {
+    async fn upload(&self, file: &FileUpload) -> Result<StoredFile> {
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", self.purpose.clone())
+            .part("file", file.to_part()?);
+
+        let response = self
+            .request(reqwest::Method::POST, "")
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        let object: FileObject = check_status(SERVICE, response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| AgentError::LLMProvider(format!("Failed to deserialize file: {}", e)))?;
+
+        // Keep the MIME type the caller uploaded with
+        Ok(StoredFile {
+            mime_type: file.mime_type.clone(),
+            ..object.into()
+        })
+    }
+
+    async fn list(&self) -> Result<Vec<StoredFile>> {
+        let response = self
+            .request(reqwest::Method::GET, "")
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        let list: FileList = check_status(SERVICE, response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| {
+                AgentError::LLMProvider(format!("Failed to deserialize file list: {}", e))
+            })?;
+        Ok(list.data.into_iter().map(StoredFile::from).collect())
+    }
+
+    async fn delete(&self, file_id: &str) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("/{}", file_id))
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        check_status(SERVICE, response).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_upload() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/files"))
+            .and(header("Authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "file-abc",
+                "filename": "notes.txt",
+                "bytes": 5,
+                "purpose": "user_data"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let store = OpenAIFileStore::new("test-key").with_base_url(mock_server.uri());
+        let stored = store
+            .upload(&FileUpload::new(b"hello".to_vec(), "notes.txt"))
+            .await
+            .unwrap();
+        assert_eq!(stored.id, "file-abc");
+        assert_eq!(stored.size_bytes, 5);
+        assert_eq!(stored.mime_type, "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/files"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    {"id": "file-1", "filename": "a.pdf", "bytes": 10},
+                    {"id": "file-2", "filename": "b.png", "bytes": 20}
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/files/file-1"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"id": "file-1", "deleted": true})),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let store = OpenAIFileStore::new("test-key").with_base_url(mock_server.uri());
+        let files = store.list().await.unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].mime_type, "application/pdf");
+        assert!(files[1].file_ref().is_image());
+
+        store.delete("file-1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_missing_file() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("No such file"))
+            .mount(&mock_server)
+            .await;
+
+        let store = OpenAIFileStore::new("test-key").with_base_url(mock_server.uri());
+        let err = store.delete("file-x").await.unwrap_err();
+        assert!(err.to_string().contains("OpenAI Files API HTTP 404"));
+    }
+}
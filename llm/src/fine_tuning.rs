@@ -0,0 +1,582 @@
+//! OpenAI fine-tuning job management.
+//!
+//! [`FineTuningClient`] uploads training data, creates and monitors
+//! fine-tuning jobs, and registers the resulting model in a
+//! [`ModelRegistry`] so it can be served through the same providers.
+
+use agent_core::{AgentError, Message, Result, Role};
+use communication::{ApiClient, check_status, request_error};
+use memory::FeedbackDataset;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::files::{FileStore, FileUpload, OpenAIFileStore, StoredFile};
+use crate::{ModelId, ModelRegistry, RegisteredModel};
+
+/// Default OpenAI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Service name used in error messages
+const SERVICE: &str = "OpenAI fine-tuning API";
+
+/// Training data for a chat fine-tune
+///
+/// Each example is a conversation; the assistant messages are what the
+/// model learns to produce.
+#[derive(Debug, Clone, Default)]
+pub struct FineTuneDataset {
+    examples: Vec<Vec<Message>>,
+}
+
+impl FineTuneDataset {
+    /// Create an empty dataset
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a dataset from user feedback
+    ///
+    /// Corrected responses are trained on with the correction in their
+    /// place, and responses rated up as they are; other feedback is skipped.
+    pub fn from_feedback(feedback: &FeedbackDataset) -> Self {
+        Self {
+            examples: feedback.training_conversations(),
+        }
+    }
+
+    /// Add a training conversation
+    pub fn add_example(&mut self, messages: Vec<Message>) {
+        self.examples.push(messages);
+    }
+
+    /// Number of training examples
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    /// Returns true if the dataset has no examples
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// Serialize as JSONL, one `{"messages": [...]}` object per line
+    pub fn to_jsonl(&self) -> Result<String> {
+        let mut jsonl = String::new();
+        for example in &self.examples {
+            let messages: Vec<TrainingMessage> = example
+                .iter()
+                .map(|message| TrainingMessage {
+                    role: match message.role {
+                        Role::System => "system",
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                    },
+                    content: &message.content,
+                })
+                .collect();
+            jsonl.push_str(&serde_json::to_string(&TrainingExample { messages })?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+}
+
+/// One line of a chat fine-tuning JSONL file
+#[derive(Serialize)]
+struct TrainingExample<'a> {
+    messages: Vec<TrainingMessage<'a>>,
+}
+
+#[derive(Serialize)]
+struct TrainingMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+/// Parameters for creating a fine-tuning job
+#[derive(Debug, Clone, Serialize)]
+pub struct FineTuneRequest {
+    /// Base model to fine-tune
+    pub model: String,
+    /// ID of the uploaded training file
+    pub training_file: String,
+    /// ID of an uploaded validation file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_file: Option<String>,
+    /// Suffix included in the fine-tuned model name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+}
+
+impl FineTuneRequest {
+    /// Fine-tune `model` on an uploaded training file
+    pub fn new(model: ModelId, training_file: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            training_file: training_file.into(),
+            validation_file: None,
+            suffix: None,
+        }
+    }
+
+    /// Set the validation file
+    pub fn with_validation_file(mut self, file_id: impl Into<String>) -> Self {
+        self.validation_file = Some(file_id.into());
+        self
+    }
+
+    /// Set the model name suffix
+    pub fn with_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+}
+
+/// Status of a fine-tuning job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FineTuneStatus {
+    /// Training files are being validated
+    ValidatingFiles,
+    /// Waiting to start
+    Queued,
+    /// Training in progress
+    Running,
+    /// Training finished and the model is available
+    Succeeded,
+    /// Training failed
+    Failed,
+    /// The job was cancelled
+    Cancelled,
+}
+
+impl FineTuneStatus {
+    /// Returns true once the job will no longer change
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Error reported for a failed job
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FineTuneError {
+    /// Machine-readable error code
+    pub code: Option<String>,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// A fine-tuning job
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FineTuneJob {
+    /// Job ID
+    pub id: String,
+    /// Base model being fine-tuned
+    pub model: String,
+    /// Current status
+    pub status: FineTuneStatus,
+    /// Name of the resulting model, once the job succeeds
+    pub fine_tuned_model: Option<String>,
+    /// Number of tokens trained on, once known
+    pub trained_tokens: Option<u64>,
+    /// Failure details, if the job failed
+    pub error: Option<FineTuneError>,
+}
+
+/// Response body of the list endpoint
+#[derive(Debug, Deserialize)]
+struct JobList {
+    data: Vec<FineTuneJob>,
+}
+
+/// Client for OpenAI fine-tuning jobs
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::Message;
+/// use llm::{FineTuneDataset, FineTuneRequest, FineTuningClient, ModelId, ModelRegistry};
+/// use std::time::Duration;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let client = FineTuningClient::new("sk-...");
+///
+/// let mut dataset = FineTuneDataset::new();
+/// dataset.add_example(vec![Message::user("Hi"), Message::assistant("Ahoy!")]);
+/// let file = client.upload_dataset(&dataset, "pirate.jsonl").await?;
+///
+/// let job = client
+///     .create_job(&FineTuneRequest::new(ModelId::GPT_4O_MINI, file.id).with_suffix("pirate"))
+///     .await?;
+/// let job = client.wait_for_completion(&job.id, Duration::from_secs(30)).await?;
+///
+/// let registry = ModelRegistry::new();
+/// let model = client.register_model(&job, &registry)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct FineTuningClient {
+    api_key: String,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl FineTuningClient {
+    /// Create a client for the given API key
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+
+    /// Upload a dataset as a JSONL training file
+    pub async fn upload_dataset(
+        &self,
+        dataset: &FineTuneDataset,
+        file_name: &str,
+    ) -> Result<StoredFile> {
+        if dataset.is_empty() {
+            return Err(AgentError::Config(
+                "Fine-tuning dataset cannot be empty".to_string(),
+            ));
+        }
+
+        let upload = FileUpload::new(dataset.to_jsonl()?.into_bytes(), file_name)
+            .with_mime_type("application/jsonl");
+        OpenAIFileStore::new(&self.api_key)
+            .with_purpose("fine-tune")
+            .with_base_url(&self.base_url)
+            .with_timeout(self.client.timeout())
+            .upload(&upload)
+            .await
+    }
+
+    /// Create a fine-tuning job
+    pub async fn create_job(&self, request: &FineTuneRequest) -> Result<FineTuneJob> {
+        let builder = self.request(reqwest::Method::POST, "").json(request);
+        self.send(builder).await
+    }
+
+    /// Fetch the current state of a job
+    pub async fn get_job(&self, job_id: &str) -> Result<FineTuneJob> {
+        let builder = self.request(reqwest::Method::GET, &format!("/{}", job_id));
+        self.send(builder).await
+    }
+
+    /// List recent jobs
+    pub async fn list_jobs(&self) -> Result<Vec<FineTuneJob>> {
+        let builder = self.request(reqwest::Method::GET, "");
+        let list: JobList = self.send(builder).await?;
+        Ok(list.data)
+    }
+
+    /// Cancel a running job
+    pub async fn cancel_job(&self, job_id: &str) -> Result<FineTuneJob> {
+        let builder = self.request(reqwest::Method::POST, &format!("/{}/cancel", job_id));
+        self.send(builder).await
+    }
+
+    /// Poll a job until it reaches a terminal status
+    ///
+    /// # Arguments
+    /// * `job_id` - The job to wait for
+    /// * `poll_interval` - Delay between status checks
+    pub async fn wait_for_completion(
+        &self,
+        job_id: &str,
+        poll_interval: Duration,
+    ) -> Result<FineTuneJob> {
+        loop {
+            let job = self.get_job(job_id).await?;
+            if job.status.is_terminal() {
+                return Ok(job);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Register the model produced by a succeeded job
+    ///
+    /// # Errors
+    /// Returns an error if the job has not succeeded or reported no model.
+    pub fn register_model(&self, job: &FineTuneJob, registry: &ModelRegistry) -> Result<ModelId> {
+        let name = match (&job.status, &job.fine_tuned_model) {
+            (FineTuneStatus::Succeeded, Some(name)) => name,
+            (FineTuneStatus::Failed, _) => {
+                let reason = job
+                    .error
+                    .as_ref()
+                    .map(|error| error.message.as_str())
+                    .unwrap_or("unknown error");
+                return Err(AgentError::LLMProvider(format!(
+                    "Fine-tuning job {} failed: {}",
+                    job.id, reason
+                )));
+            }
+            (status, _) => {
+                return Err(AgentError::LLMProvider(format!(
+                    "Fine-tuning job {} has no model (status: {:?})",
+                    job.id, status
+                )));
+            }
+        };
+
+        let id = ModelId::custom(name.clone());
+        registry.register(
+            RegisteredModel::new(id.clone(), "openai")
+                .with_base_model(ModelId::custom(job.model.clone())),
+        );
+        Ok(id)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(
+                method,
+                format!("{}/fine_tuning/jobs{}", self.base_url, path),
+            )
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .timeout(self.client.timeout())
+    }
+
+    async fn send<R: serde::de::DeserializeOwned>(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<R> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| request_error(SERVICE, e))?;
+        check_status(SERVICE, response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| {
+                AgentError::LLMProvider(format!("Failed to deserialize fine-tuning job: {}", e))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn job_json(status: &str, fine_tuned_model: Value) -> Value {
+        json!({
+            "id": "ftjob-1",
+            "object": "fine_tuning.job",
+            "model": "gpt-4o-mini",
+            "status": status,
+            "fine_tuned_model": fine_tuned_model,
+            "trained_tokens": null,
+            "error": null
+        })
+    }
+
+    #[test]
+    fn test_dataset_to_jsonl() {
+        let mut dataset = FineTuneDataset::new();
+        dataset.add_example(vec![
+            Message::system("Talk like a pirate"),
+            Message::user("Hi"),
+            Message::assistant("Ahoy!"),
+        ]);
+        dataset.add_example(vec![Message::user("Bye"), Message::assistant("Farewell!")]);
+
+        let jsonl = dataset.to_jsonl().unwrap();
+        let lines: Vec<Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["messages"][0]["role"], "system");
+        assert_eq!(lines[1]["messages"][1]["content"], "Farewell!");
+    }
+
+    #[test]
+    fn test_dataset_from_feedback() {
+        use memory::{ConversationIndex, Feedback, MessageId};
+
+        let mut index = ConversationIndex::in_memory().unwrap();
+        let session = [Message::user("2 + 2?"), Message::assistant("5")];
+        index.index_session("math", &session).unwrap();
+        let message = MessageId::new("math", 1);
+        index
+            .record_feedback(&message, &Feedback::correction("4"))
+            .unwrap();
+        index
+            .record_feedback(&message, &Feedback::thumbs_down())
+            .unwrap();
+
+        let dataset = FineTuneDataset::from_feedback(&index.export_feedback().unwrap());
+        assert_eq!(dataset.len(), 1);
+        assert!(dataset.to_jsonl().unwrap().contains(r#""content":"4""#));
+    }
+
+    #[tokio::test]
+    async fn test_upload_empty_dataset_fails() {
+        let client = FineTuningClient::new("key");
+        let result = client
+            .upload_dataset(&FineTuneDataset::new(), "empty.jsonl")
+            .await;
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_upload_dataset_uses_fine_tune_purpose() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/files"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "file-train",
+                "filename": "train.jsonl",
+                "bytes": 42
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = FineTuningClient::new("key").with_base_url(mock_server.uri());
+        let mut dataset = FineTuneDataset::new();
+        dataset.add_example(vec![Message::user("Hi"), Message::assistant("Hello")]);
+        let file = client
+            .upload_dataset(&dataset, "train.jsonl")
+            .await
+            .unwrap();
+        assert_eq!(file.id, "file-train");
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("fine-tune"));
+        assert!(body.contains(r#"{"messages":[{"role":"user","content":"Hi"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_create_and_cancel_job() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/fine_tuning/jobs"))
+            .and(body_partial_json(json!({
+                "model": "gpt-4o-mini",
+                "training_file": "file-train",
+                "suffix": "pirate"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(job_json("queued", Value::Null)))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/fine_tuning/jobs/ftjob-1/cancel"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(job_json("cancelled", Value::Null)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = FineTuningClient::new("key").with_base_url(mock_server.uri());
+        let request =
+            FineTuneRequest::new(ModelId::GPT_4O_MINI, "file-train").with_suffix("pirate");
+        let job = client.create_job(&request).await.unwrap();
+        assert_eq!(job.status, FineTuneStatus::Queued);
+
+        let job = client.cancel_job(&job.id).await.unwrap();
+        assert_eq!(job.status, FineTuneStatus::Cancelled);
+        assert!(job.status.is_terminal());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_completion_and_register() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/fine_tuning/jobs/ftjob-1"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(job_json("running", Value::Null)),
+            )
+            .up_to_n_times(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fine_tuning/jobs/ftjob-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(job_json(
+                "succeeded",
+                json!("ft:gpt-4o-mini:acme:pirate:abc"),
+            )))
+            .mount(&mock_server)
+            .await;
+
+        let client = FineTuningClient::new("key").with_base_url(mock_server.uri());
+        let job = client
+            .wait_for_completion("ftjob-1", Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(job.status, FineTuneStatus::Succeeded);
+
+        let registry = ModelRegistry::new();
+        let model = client.register_model(&job, &registry).unwrap();
+        assert_eq!(model.as_str(), "ft:gpt-4o-mini:acme:pirate:abc");
+
+        let entry = registry.get(model.as_str()).unwrap();
+        assert_eq!(entry.provider, "openai");
+        assert_eq!(entry.base_model, Some(ModelId::GPT_4O_MINI));
+    }
+
+    #[test]
+    fn test_register_failed_job() {
+        let job = FineTuneJob {
+            id: "ftjob-2".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            status: FineTuneStatus::Failed,
+            fine_tuned_model: None,
+            trained_tokens: None,
+            error: Some(FineTuneError {
+                code: Some("invalid_training_file".to_string()),
+                message: "Line 3 is not valid JSON".to_string(),
+            }),
+        };
+
+        let registry = ModelRegistry::new();
+        let err = FineTuningClient::new("key")
+            .register_model(&job, &registry)
+            .unwrap_err();
+        assert!(err.to_string().contains("Line 3 is not valid JSON"));
+        assert!(registry.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_jobs() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/fine_tuning/jobs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [job_json("running", Value::Null)],
+                "has_more": false
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = FineTuningClient::new("key").with_base_url(mock_server.uri());
+        let jobs = client.list_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, FineTuneStatus::Running);
+    }
+}
@@ -0,0 +1,718 @@
+//! Google Gemini provider for the Generative Language API.
+
+use agent_core::{AgentError, Message, Result, Role, ToolDefinition, ToolUse, ToolUseResponse};
+use async_trait::async_trait;
+use communication::{
+    ApiClient, RetryPolicy, check_status, decode_sse_stream, request_error, with_request_headers,
+    with_retry_policy,
+};
+use config::{HarmBlockThreshold, HarmCategory, LLMConfig};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::structured::send_completion_with_repair;
+use crate::{
+    CompletionResponse, FinishReason, LLMProvider, MaxTokens, StreamEvent, StructuredOutput,
+    Temperature, TokenStream, TokenUsage, ToolChoice, ToolConfig,
+};
+
+/// Default Gemini API base URL
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Request body for the `generateContent` endpoint
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentRequest {
+    contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    safety_settings: Vec<SafetySetting>,
+    generation_config: GenerationConfig,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_config: Option<GeminiToolConfig>,
+}
+
+/// A turn of the conversation, made of parts
+#[derive(Debug, Serialize, Deserialize)]
+struct Content {
+    /// `user` or `model`; absent for the system instruction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    /// Absent for non-text parts such as function calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_call: Option<FunctionCall>,
+}
+
+impl Part {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            function_call: None,
+        }
+    }
+}
+
+/// A function call requested by the model
+#[derive(Debug, Serialize, Deserialize)]
+struct FunctionCall {
+    name: String,
+    #[serde(default)]
+    args: Value,
+    /// Call ID, only returned by some models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Tool {
+    function_declarations: Vec<FunctionDeclaration>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionDeclaration {
+    name: String,
+    description: String,
+    parameters_json_schema: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiToolConfig {
+    function_calling_config: FunctionCallingConfig,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionCallingConfig {
+    /// `AUTO`, `ANY` or `NONE`
+    mode: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allowed_function_names: Vec<String>,
+}
+
+impl GeminiToolConfig {
+    fn new(config: &ToolConfig) -> Self {
+        let (mode, allowed_function_names) = match &config.choice {
+            ToolChoice::Auto => ("AUTO", Vec::new()),
+            ToolChoice::Any => ("ANY", Vec::new()),
+            ToolChoice::None => ("NONE", Vec::new()),
+            ToolChoice::Tool(name) => ("ANY", vec![name.clone()]),
+        };
+        Self {
+            function_calling_config: FunctionCallingConfig {
+                mode,
+                allowed_function_names,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SafetySetting {
+    category: &'static str,
+    threshold: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    temperature: f32,
+    max_output_tokens: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    prompt_feedback: Option<PromptFeedback>,
+    usage_metadata: Option<UsageMetadata>,
+    model_version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<Content>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: usize,
+    #[serde(default)]
+    candidates_token_count: usize,
+}
+
+/// Google Gemini LLM provider
+///
+/// Messages are sent as Gemini `contents`: system messages become the
+/// `systemInstruction`, assistant messages use the `model` role, and
+/// consecutive messages from the same side are merged into one turn with a
+/// part per message. The [`safety_settings`](LLMConfig::safety_settings)
+/// from the configuration set the blocking threshold per harm category.
+/// Tools are offered as `functionDeclarations`, and responses are streamed
+/// from `streamGenerateContent`.
+///
+/// # Example
+///
+/// ```no_run
+/// use config::{HarmBlockThreshold, HarmCategory, LLMConfig};
+/// use llm::{GeminiProvider, LLMProvider};
+/// use agent_core::Message;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let mut config = LLMConfig {
+///     provider: "gemini".to_string(),
+///     model: "gemini-2.5-flash".to_string(),
+///     api_key: "your-api-key".to_string(),
+///     temperature: 0.7,
+///     max_tokens: 2000,
+///     organization: None,
+///     project: None,
+///     safety_settings: Default::default(),
+///     azure: None,
+///     openrouter: None,
+///     prompt_caching: false,
+/// };
+/// config
+///     .safety_settings
+///     .insert(HarmCategory::Harassment, HarmBlockThreshold::BlockOnlyHigh);
+///
+/// let provider = GeminiProvider::new(&config)?;
+/// let reply = provider.send_message(&[Message::user("Hello!")]).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct GeminiProvider {
+    api_key: String,
+    model: String,
+    temperature: f32,
+    max_tokens: usize,
+    safety_settings: BTreeMap<HarmCategory, HarmBlockThreshold>,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    client: ApiClient,
+}
+
+impl GeminiProvider {
+    /// Create a new Gemini provider from configuration
+    ///
+    /// # Errors
+    /// Returns an error if the API key is empty, or if the temperature or
+    /// max tokens are out of range
+    pub fn new(config: &LLMConfig) -> Result<Self> {
+        if config.api_key.is_empty() {
+            return Err(AgentError::Config(
+                "Gemini API key is required but not provided".to_string(),
+            ));
+        }
+        Ok(Self {
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            temperature: Temperature::new(config.temperature)?.get(),
+            max_tokens: MaxTokens::new(config.max_tokens)?.get(),
+            safety_settings: config.safety_settings.clone(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry_policy: RetryPolicy::default(),
+            client: ApiClient::new(),
+        })
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set how transient failures are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Convert messages into Gemini contents and a system instruction
+    fn build_request(&self, messages: &[Message]) -> GenerateContentRequest {
+        let mut system: Vec<Part> = Vec::new();
+        let mut contents: Vec<Content> = Vec::new();
+        for message in messages {
+            let part = Part::text(message.content.clone());
+            let role = match message.role {
+                Role::System => {
+                    system.push(part);
+                    continue;
+                }
+                Role::User => "user",
+                Role::Assistant => "model",
+            };
+            match contents.last_mut() {
+                Some(last) if last.role.as_deref() == Some(role) => last.parts.push(part),
+                _ => contents.push(Content {
+                    role: Some(role.to_string()),
+                    parts: vec![part],
+                }),
+            }
+        }
+
+        GenerateContentRequest {
+            contents,
+            system_instruction: (!system.is_empty()).then_some(Content {
+                role: None,
+                parts: system,
+            }),
+            safety_settings: self
+                .safety_settings
+                .iter()
+                .map(|(category, threshold)| SafetySetting {
+                    category: category_name(*category),
+                    threshold: threshold_name(*threshold),
+                })
+                .collect(),
+            generation_config: GenerationConfig {
+                temperature: self.temperature,
+                max_output_tokens: self.max_tokens,
+            },
+            tools: Vec::new(),
+            tool_config: None,
+        }
+    }
+
+    /// Post a request to a model method such as `generateContent`
+    async fn post_request(
+        &self,
+        method: &str,
+        request: &GenerateContentRequest,
+    ) -> Result<reqwest::Response> {
+        let url = format!("{}/models/{}:{}", self.base_url, self.model, method);
+        let response = with_request_headers(reqwest::Client::new().post(&url))
+            .header("x-goog-api-key", &self.api_key)
+            .json(request)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("Gemini API", e))?;
+
+        check_status("Gemini API", response).await
+    }
+
+    async fn send_request(
+        &self,
+        request: &GenerateContentRequest,
+    ) -> Result<GenerateContentResponse> {
+        let response = self.post_request("generateContent", request).await?;
+
+        response.json().await.map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize Gemini response: {}", e))
+        })
+    }
+
+    /// Extract the text, stop reason and usage of the first candidate
+    fn convert_response(response: GenerateContentResponse) -> Result<CompletionResponse> {
+        let candidate = first_candidate(response.candidates, response.prompt_feedback)?;
+        let text = candidate
+            .content
+            .map(|content| content_text(&content.parts))
+            .unwrap_or_default();
+        let mut completion = CompletionResponse::new(
+            text,
+            FinishReason::from_gemini(candidate.finish_reason.as_deref()),
+        );
+        if let Some(usage) = response.usage_metadata {
+            completion = completion.with_usage(TokenUsage {
+                input_tokens: usage.prompt_token_count,
+                output_tokens: usage.candidates_token_count,
+            });
+        }
+        if let Some(model) = response.model_version {
+            completion = completion.with_model(model);
+        }
+        Ok(completion)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for GeminiProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        Ok(self.send_completion(messages).await?.text)
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let request = self.build_request(messages);
+        let response =
+            with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+        Self::convert_response(response)
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        send_completion_with_repair(self, messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let mut request = self.build_request(messages);
+        if !tools.is_empty() {
+            request.tools = vec![Tool {
+                function_declarations: tools
+                    .iter()
+                    .map(|tool| FunctionDeclaration {
+                        name: tool.name.clone(),
+                        description: tool.description.clone(),
+                        parameters_json_schema: tool.parameters.clone(),
+                    })
+                    .collect(),
+            }];
+            request.tool_config = Some(GeminiToolConfig::new(config));
+        }
+
+        let response =
+            with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+        let candidate = first_candidate(response.candidates, response.prompt_feedback)?;
+        let parts = candidate.content.map(|content| content.parts).unwrap_or_default();
+
+        let text = content_text(&parts);
+        let mut tool_uses: Vec<ToolUse> = parts
+            .into_iter()
+            .filter_map(|part| part.function_call)
+            .enumerate()
+            .map(|(index, call)| ToolUse {
+                id: call.id.unwrap_or_else(|| format!("call_{}", index)),
+                name: call.name,
+                arguments: call.args,
+            })
+            .collect();
+        // Gemini has no switch for parallel calls, so extra calls are dropped
+        if !config.parallel {
+            tool_uses.truncate(1);
+        }
+
+        Ok(ToolUseResponse { text, tool_uses })
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let request = self.build_request(messages);
+
+        // Only the initial request is retried; a stream is not resumed mid-way
+        let response = with_retry_policy(
+            || self.post_request("streamGenerateContent?alt=sse", &request),
+            &self.retry_policy,
+        )
+        .await?;
+
+        let events = decode_sse_stream(response.bytes_stream()).flat_map(|event| {
+            futures::stream::iter(match event {
+                Ok(event) => parse_chunk(&event.data),
+                Err(e) => vec![Err(e)],
+            })
+        });
+        Ok(Box::pin(events))
+    }
+}
+
+/// Take the first candidate, or report why the prompt was blocked
+fn first_candidate(
+    candidates: Vec<Candidate>,
+    feedback: Option<PromptFeedback>,
+) -> Result<Candidate> {
+    candidates.into_iter().next().ok_or_else(|| {
+        let reason = feedback
+            .and_then(|feedback| feedback.block_reason)
+            .unwrap_or_else(|| "no candidates returned".to_string());
+        AgentError::LLMProvider(format!("Gemini blocked the request: {}", reason))
+    })
+}
+
+/// Concatenate the text parts, skipping function calls
+fn content_text(parts: &[Part]) -> String {
+    parts.iter().filter_map(|part| part.text.as_deref()).collect()
+}
+
+/// Convert one server-sent event from `streamGenerateContent` into stream
+/// events
+///
+/// Every chunk is a partial response; usage is reported once, with the chunk
+/// that carries the finish reason.
+fn parse_chunk(data: &str) -> Vec<Result<StreamEvent>> {
+    let chunk = match serde_json::from_str::<GenerateContentResponse>(data) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            return vec![Err(AgentError::LLMProvider(format!(
+                "Failed to deserialize Gemini stream chunk: {}",
+                e
+            )))];
+        }
+    };
+    let candidate = match first_candidate(chunk.candidates, chunk.prompt_feedback) {
+        Ok(candidate) => candidate,
+        Err(e) => return vec![Err(e)],
+    };
+
+    let text = candidate
+        .content
+        .map(|content| content_text(&content.parts))
+        .filter(|text| !text.is_empty())
+        .map(StreamEvent::Text);
+    let usage = chunk
+        .usage_metadata
+        .filter(|_| candidate.finish_reason.is_some())
+        .map(|usage| {
+            StreamEvent::Usage(TokenUsage {
+                input_tokens: usage.prompt_token_count,
+                output_tokens: usage.candidates_token_count,
+            })
+        });
+    text.into_iter().chain(usage).map(Ok).collect()
+}
+
+fn category_name(category: HarmCategory) -> &'static str {
+    match category {
+        HarmCategory::Harassment => "HARM_CATEGORY_HARASSMENT",
+        HarmCategory::HateSpeech => "HARM_CATEGORY_HATE_SPEECH",
+        HarmCategory::SexuallyExplicit => "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+        HarmCategory::DangerousContent => "HARM_CATEGORY_DANGEROUS_CONTENT",
+        HarmCategory::CivicIntegrity => "HARM_CATEGORY_CIVIC_INTEGRITY",
+    }
+}
+
+fn threshold_name(threshold: HarmBlockThreshold) -> &'static str {
+    match threshold {
+        HarmBlockThreshold::BlockNone => "BLOCK_NONE",
+        HarmBlockThreshold::BlockOnlyHigh => "BLOCK_ONLY_HIGH",
+        HarmBlockThreshold::BlockMediumAndAbove => "BLOCK_MEDIUM_AND_ABOVE",
+        HarmBlockThreshold::BlockLowAndAbove => "BLOCK_LOW_AND_ABOVE",
+        HarmBlockThreshold::Off => "OFF",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(base_url: String) -> GeminiProvider {
+        let mut config = LLMConfig {
+            provider: "gemini".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            api_key: "test-key".to_string(),
+            temperature: 0.5,
+            max_tokens: 256,
+            organization: None,
+            project: None,
+            safety_settings: BTreeMap::new(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+        config.safety_settings.insert(
+            HarmCategory::DangerousContent,
+            HarmBlockThreshold::BlockLowAndAbove,
+        );
+        config
+            .safety_settings
+            .insert(HarmCategory::Harassment, HarmBlockThreshold::BlockOnlyHigh);
+        GeminiProvider::new(&config)
+            .unwrap()
+            .with_base_url(base_url)
+            .with_retry_policy(RetryPolicy::none())
+    }
+
+    #[tokio::test]
+    async fn test_send_completion_maps_contents_and_safety_settings() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-2.5-flash:generateContent"))
+            .and(header("x-goog-api-key", "test-key"))
+            .and(body_json(json!({
+                "contents": [
+                    {"role": "user", "parts": [{"text": "Hi"}, {"text": "Are you there?"}]},
+                    {"role": "model", "parts": [{"text": "Yes."}]},
+                    {"role": "user", "parts": [{"text": "Name a color"}]}
+                ],
+                "systemInstruction": {"parts": [{"text": "Be brief."}]},
+                "safetySettings": [
+                    {"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH"},
+                    {
+                        "category": "HARM_CATEGORY_DANGEROUS_CONTENT",
+                        "threshold": "BLOCK_LOW_AND_ABOVE"
+                    }
+                ],
+                "generationConfig": {"temperature": 0.5, "maxOutputTokens": 256}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [{"text": "Blue"}, {"text": "."}]},
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": {"promptTokenCount": 12, "candidatesTokenCount": 2},
+                "modelVersion": "gemini-2.5-flash"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let messages = [
+            Message::system("Be brief."),
+            Message::user("Hi"),
+            Message::user("Are you there?"),
+            Message::assistant("Yes."),
+            Message::user("Name a color"),
+        ];
+        let completion = provider(mock_server.uri())
+            .send_completion(&messages)
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "Blue.");
+        assert_eq!(completion.finish_reason, FinishReason::Stop);
+        assert_eq!(completion.usage.unwrap().input_tokens, 12);
+        assert_eq!(completion.model.as_deref(), Some("gemini-2.5-flash"));
+    }
+
+    #[tokio::test]
+    async fn test_blocked_prompt_is_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "promptFeedback": {"blockReason": "SAFETY"}
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let err = provider(mock_server.uri())
+            .send_message(&[Message::user("...")])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("blocked the request: SAFETY"));
+    }
+
+    #[tokio::test]
+    async fn test_tools_are_sent_as_function_declarations() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-2.5-flash:generateContent"))
+            .and(body_partial_json(json!({
+                "tools": [{"functionDeclarations": [{
+                    "name": "search",
+                    "description": "Search the web",
+                    "parametersJsonSchema": {"type": "object"}
+                }]}],
+                "toolConfig": {
+                    "functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["search"]}
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [
+                        {"text": "Searching"},
+                        {"functionCall": {"name": "search", "args": {"q": "rust"}}},
+                        {"functionCall": {"name": "search", "args": {"q": "tokio"}}}
+                    ]},
+                    "finishReason": "STOP"
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let tools = [ToolDefinition::new(
+            "search",
+            "Search the web",
+            json!({"type": "object"}),
+        )];
+        let config = ToolConfig::new(ToolChoice::tool("search")).sequential();
+        let response = provider(mock_server.uri())
+            .send_message_with_tools(&[Message::user("Find docs")], &tools, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "Searching");
+        assert_eq!(
+            response.tool_uses,
+            vec![ToolUse {
+                id: "call_0".to_string(),
+                name: "search".to_string(),
+                arguments: json!({"q": "rust"}),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_ends_with_usage() {
+        let mock_server = MockServer::start().await;
+
+        let body = [
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "Hel"}]}}]}),
+            json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [{"text": "lo"}]},
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": {"promptTokenCount": 4, "candidatesTokenCount": 2}
+            }),
+        ]
+        .iter()
+        .map(|chunk| format!("data: {}\r\n\r\n", chunk))
+        .collect::<String>();
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-2.5-flash:streamGenerateContent"))
+            .and(query_param("alt", "sse"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let stream = provider(mock_server.uri())
+            .stream_message(&[Message::user("Hi")])
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.map(|event| event.unwrap()).collect().await;
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("Hel".to_string()),
+                StreamEvent::Text("lo".to_string()),
+                StreamEvent::Usage(TokenUsage {
+                    input_tokens: 4,
+                    output_tokens: 2,
+                }),
+            ]
+        );
+    }
+}
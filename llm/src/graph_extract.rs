@@ -0,0 +1,263 @@
+//! Entity and relation extraction for knowledge-graph memory.
+//!
+//! [`GraphExtractor`] asks a model for the entities and typed relations
+//! stated in a document or conversation, as a [`GraphUpdate`] to apply to a
+//! [`KnowledgeGraph`].
+
+use agent_core::{AgentError, Conversation, Message, Result, Role};
+use memory::{GraphUpdate, KnowledgeGraph};
+use serde_json::{Value, json};
+
+use crate::{Extractable, LLMProvider, StructuredOutput};
+
+/// Most known entity names listed in the prompt by [`GraphExtractor::update`]
+const MAX_KNOWN_NAMES: usize = 200;
+
+const INSTRUCTIONS: &str = "You build a knowledge graph from text. List the people, \
+organisations, places, products, projects and other named things the text mentions as \
+entities, with a short lowercase kind such as \"person\" or \"service\" and a one-sentence \
+description of what the text says about them. List the facts that connect them as \
+relations from a source entity to a target entity, with a short snake_case relation type \
+such as \"works_at\" or \"depends_on\". Use only facts stated in the text, and use the same \
+name for an entity every time.";
+
+impl Extractable for GraphUpdate {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "entities": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "kind": {"type": "string"},
+                            "description": {"type": "string"}
+                        },
+                        "required": ["name", "kind"],
+                        "additionalProperties": false
+                    }
+                },
+                "relations": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "source": {"type": "string"},
+                            "relation": {"type": "string"},
+                            "target": {"type": "string"}
+                        },
+                        "required": ["source", "relation", "target"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["entities", "relations"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Extracts entities and relations from text with an LLM
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{AnthropicProvider, GraphExtractor, ModelId};
+/// use memory::KnowledgeGraph;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = AnthropicProvider::builder()
+///     .api_key("your-api-key")
+///     .model(ModelId::CLAUDE_HAIKU_4_5)
+///     .build()?;
+/// let extractor = GraphExtractor::new(provider);
+/// let mut graph = KnowledgeGraph::in_memory();
+///
+/// extractor
+///     .update(&mut graph, "Alice owns the billing API. She reports to Bob.")
+///     .await?;
+///
+/// let question = "Who owns the billing API?";
+/// if let Some(facts) = graph.context_message(question, 2)? {
+///     println!("{}", facts.content);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct GraphExtractor<P: LLMProvider> {
+    provider: P,
+}
+
+impl<P: LLMProvider> GraphExtractor<P> {
+    /// Create an extractor that uses `provider`
+    pub fn new(provider: P) -> Self {
+        Self { provider }
+    }
+
+    /// The entities and relations stated in `text`
+    ///
+    /// # Errors
+    /// Returns an error if the model call fails or its reply does not match
+    /// the schema of a [`GraphUpdate`].
+    pub async fn extract(&self, text: &str) -> Result<GraphUpdate> {
+        self.extract_with_known(text, &[]).await
+    }
+
+    /// The entities and relations stated in the user and assistant messages
+    /// of `conversation`
+    ///
+    /// # Errors
+    /// Returns an error if the conversation has no user or assistant
+    /// messages, or see [`extract`](Self::extract).
+    pub async fn extract_conversation(&self, conversation: &Conversation) -> Result<GraphUpdate> {
+        let transcript = transcript(conversation);
+        if transcript.is_empty() {
+            return Err(AgentError::Execution(
+                "Cannot extract a graph from a conversation without messages".to_string(),
+            ));
+        }
+        self.extract(&transcript).await
+    }
+
+    /// Extract the entities and relations in `text` and add them to `graph`
+    ///
+    /// The names of entities already in the graph are sent along, so the
+    /// model refers to them the same way.
+    ///
+    /// # Errors
+    /// Returns an error if the extraction fails or the graph cannot be
+    /// updated.
+    pub async fn update(&self, graph: &mut KnowledgeGraph, text: &str) -> Result<GraphUpdate> {
+        let known: Vec<String> = graph
+            .entities()?
+            .into_iter()
+            .take(MAX_KNOWN_NAMES)
+            .map(|entity| entity.name)
+            .collect();
+        let update = self.extract_with_known(text, &known).await?;
+        graph.apply(&update)?;
+        Ok(update)
+    }
+
+    async fn extract_with_known(&self, text: &str, known: &[String]) -> Result<GraphUpdate> {
+        let mut prompt = String::new();
+        if !known.is_empty() {
+            prompt.push_str(&format!("Known entities: {}\n\n", known.join(", ")));
+        }
+        prompt.push_str(&format!("<text>\n{}\n</text>", text));
+
+        let messages = [Message::system(INSTRUCTIONS), Message::user(prompt)];
+        let output = StructuredOutput::new(GraphUpdate::schema());
+        let value = self.provider.send_structured(&messages, &output).await?;
+        GraphUpdate::from_value(value).map_err(|e| {
+            AgentError::LLMProvider(format!("Extracted graph has the wrong shape: {}", e))
+        })
+    }
+}
+
+/// Render the user and assistant messages as plain text
+fn transcript(conversation: &Conversation) -> String {
+    conversation
+        .messages()
+        .filter_map(|message| match message.role {
+            Role::User => Some(format!("User: {}", message.content)),
+            Role::Assistant => Some(format!("Assistant: {}", message.content)),
+            Role::System => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use memory::Relation;
+    use std::sync::Mutex;
+
+    /// Replies with a scripted response and records the requests
+    struct Scripted {
+        reply: &'static str,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl Scripted {
+        fn new(reply: &'static str) -> Self {
+            Self {
+                reply,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.reply.to_string())
+        }
+    }
+
+    const REPLY: &str = r#"{
+        "entities": [
+            {"name": "Alice", "kind": "person", "description": "Owns the billing API"},
+            {"name": "Billing API", "kind": "service"}
+        ],
+        "relations": [
+            {"source": "Alice", "relation": "owns", "target": "Billing API"},
+            {"source": "Alice", "relation": "reports to", "target": "Bob"}
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn test_update_applies_extraction_and_sends_known_names() {
+        let extractor = GraphExtractor::new(Scripted::new(REPLY));
+        let mut graph = KnowledgeGraph::in_memory();
+        graph
+            .add_entity(&memory::Entity::new("Carol", "person"))
+            .unwrap();
+
+        let update = extractor
+            .update(&mut graph, "Alice owns the billing API and reports to Bob.")
+            .await
+            .unwrap();
+        assert_eq!(update.entities.len(), 2);
+
+        assert_eq!(graph.entities().unwrap().len(), 4);
+        assert!(graph.relations_of("bob").unwrap().contains(&Relation::new(
+            "Alice",
+            "reports_to",
+            "Bob"
+        )));
+
+        let requests = extractor.provider.requests.lock().unwrap();
+        let prompt = &requests[0].last().unwrap().content;
+        assert!(prompt.starts_with("Known entities: Carol\n\n<text>\nAlice owns"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_conversation() {
+        let extractor = GraphExtractor::new(Scripted::new(REPLY));
+        assert!(
+            extractor
+                .extract_conversation(&Conversation::new())
+                .await
+                .is_err()
+        );
+
+        let mut conversation = Conversation::new();
+        conversation.push_message(Message::system("Be helpful."));
+        conversation.push_message(Message::user("Who owns billing?"));
+        conversation.push_message(Message::assistant("Alice does."));
+        extractor.extract_conversation(&conversation).await.unwrap();
+
+        let requests = extractor.provider.requests.lock().unwrap();
+        let prompt = &requests[0].last().unwrap().content;
+        assert_eq!(
+            prompt,
+            "<text>\nUser: Who owns billing?\n\nAssistant: Alice does.\n</text>"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Google Gemini image generation provider.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::{GeneratedImage, ImageProvider, parse_response, request_error};
+
+/// Default Gemini API base URL
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+#[derive(Debug, Deserialize)]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Candidate {
+    content: Content,
+}
+
+#[derive(Debug, Deserialize)]
+struct Content {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    inline_data: Option<InlineData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InlineData {
+    mime_type: String,
+    data: String,
+}
+
+/// Image provider backed by Gemini image-capable models
+pub struct GeminiImageProvider {
+    api_key: String,
+    model: String,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl GeminiImageProvider {
+    /// Create a provider using the `gemini-2.5-flash-image` model
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "gemini-2.5-flash-image".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::with_timeout(Duration::from_secs(120)),
+        }
+    }
+
+    /// Set the model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl ImageProvider for GeminiImageProvider {
+    async fn generate(&self, prompt: &str) -> Result<Vec<GeneratedImage>> {
+        let request = serde_json::json!({
+            "contents": [{"parts": [{"text": prompt}]}],
+            "generationConfig": {"responseModalities": ["TEXT", "IMAGE"]}
+        });
+
+        let url = format!("{}/models/{}:generateContent", self.base_url, self.model);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("x-goog-api-key", &self.api_key)
+            .json(&request)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("Gemini", e))?;
+
+        let body: GenerateContentResponse = parse_response(response, "Gemini").await?;
+        let images = body
+            .candidates
+            .iter()
+            .flat_map(|candidate| &candidate.content.parts)
+            .filter_map(|part| part.inline_data.as_ref())
+            .map(|inline| GeneratedImage::from_base64(&inline.data, inline.mime_type.clone()))
+            .collect::<Result<Vec<_>>>()?;
+
+        if images.is_empty() {
+            return Err(AgentError::LLMProvider(
+                "Gemini response contained no images".to_string(),
+            ));
+        }
+        Ok(images)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_generate_extracts_inline_images() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-2.5-flash-image:generateContent"))
+            .and(header("x-goog-api-key", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "candidates": [{
+                    "content": {
+                        "parts": [
+                            {"text": "Here is your image"},
+                            {"inlineData": {"mimeType": "image/jpeg", "data": "AAEC"}}
+                        ]
+                    }
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = GeminiImageProvider::new("test-key").with_base_url(mock_server.uri());
+        let images = provider.generate("a boat").await.unwrap();
+
+        assert_eq!(
+            images,
+            vec![GeneratedImage::Bytes {
+                data: vec![0, 1, 2],
+                mime_type: "image/jpeg".to_string()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_text_only_response_is_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "candidates": [{"content": {"parts": [{"text": "I can't draw that"}]}}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = GeminiImageProvider::new("test-key").with_base_url(mock_server.uri());
+        let err = provider.generate("x").await.unwrap_err();
+        assert!(err.to_string().contains("no images"));
+    }
+}
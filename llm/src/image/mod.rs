@@ -0,0 +1,109 @@
+//! Image generation providers.
+//!
+//! An [`ImageProvider`] turns a text prompt into one or more images, returned
+//! either as hosted URLs or as raw bytes.
+//!
+//! # Implementations
+//!
+//! - [`OpenAIImageProvider`]: OpenAI DALL-E / GPT image models
+//! - [`StabilityImageProvider`]: Stability AI Stable Image
+//! - [`GeminiImageProvider`]: Google Gemini image models
+
+pub mod gemini;
+pub mod openai;
+pub mod stability;
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::{check_status, request_error};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+pub use gemini::GeminiImageProvider;
+pub use openai::OpenAIImageProvider;
+pub use stability::StabilityImageProvider;
+
+/// An image produced by an [`ImageProvider`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratedImage {
+    /// Image hosted by the provider (URLs usually expire after a while)
+    Url(String),
+    /// Encoded image bytes
+    Bytes {
+        /// Encoded image data (e.g. PNG)
+        data: Vec<u8>,
+        /// MIME type of the image
+        mime_type: String,
+    },
+}
+
+impl GeneratedImage {
+    /// A URL for the image, using a `data:` URL for inline bytes
+    pub fn to_url(&self) -> String {
+        match self {
+            GeneratedImage::Url(url) => url.clone(),
+            GeneratedImage::Bytes { data, mime_type } => {
+                format!("data:{};base64,{}", mime_type, STANDARD.encode(data))
+            }
+        }
+    }
+
+    /// Decode a base64 image returned by a provider
+    pub(crate) fn from_base64(encoded: &str, mime_type: impl Into<String>) -> Result<Self> {
+        let data = STANDARD
+            .decode(encoded)
+            .map_err(|e| AgentError::LLMProvider(format!("Failed to decode image data: {}", e)))?;
+        Ok(GeneratedImage::Bytes {
+            data,
+            mime_type: mime_type.into(),
+        })
+    }
+}
+
+/// Trait for image generation providers
+#[async_trait]
+pub trait ImageProvider: Send + Sync {
+    /// Generate images from a text prompt
+    ///
+    /// # Arguments
+    /// * `prompt` - Description of the image to generate
+    ///
+    /// # Returns
+    /// * `Result<Vec<GeneratedImage>>` - The generated images (at least one) or an error
+    async fn generate(&self, prompt: &str) -> Result<Vec<GeneratedImage>>;
+}
+
+/// Check the response status and deserialize the JSON body
+async fn parse_response<T: serde::de::DeserializeOwned>(
+    response: reqwest::Response,
+    service: &str,
+) -> Result<T> {
+    let response = check_status(service, response).await?;
+
+    response.json().await.map_err(|e| {
+        AgentError::LLMProvider(format!("Failed to deserialize {} response: {}", service, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_image() {
+        let image = GeneratedImage::Url("https://example.com/a.png".to_string());
+        assert_eq!(image.to_url(), "https://example.com/a.png");
+    }
+
+    #[test]
+    fn test_bytes_image_to_data_url() {
+        let image = GeneratedImage::from_base64("iVBORw==", "image/png").unwrap();
+        assert_eq!(image.to_url(), "data:image/png;base64,iVBORw==");
+        assert!(matches!(image, GeneratedImage::Bytes { ref data, .. } if data.len() == 4));
+    }
+
+    #[test]
+    fn test_invalid_base64() {
+        assert!(GeneratedImage::from_base64("not base64!", "image/png").is_err());
+    }
+}
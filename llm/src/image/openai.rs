@@ -0,0 +1,197 @@
+//! OpenAI image generation provider.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{GeneratedImage, ImageProvider, parse_response, request_error};
+
+/// Default OpenAI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+#[derive(Debug, Serialize)]
+struct ImageRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    n: u32,
+    size: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageResponse {
+    data: Vec<ImageData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageData {
+    url: Option<String>,
+    b64_json: Option<String>,
+}
+
+/// Image provider backed by OpenAI's image generation API (DALL-E)
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{ImageProvider, OpenAIImageProvider};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OpenAIImageProvider::new("sk-...").with_size("1792x1024");
+/// let images = provider.generate("A lighthouse at dawn").await?;
+/// println!("{}", images[0].to_url());
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenAIImageProvider {
+    api_key: String,
+    model: String,
+    size: String,
+    count: u32,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OpenAIImageProvider {
+    /// Create a provider using `dall-e-3` at 1024x1024
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "dall-e-3".to_string(),
+            size: "1024x1024".to_string(),
+            count: 1,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::with_timeout(Duration::from_secs(120)),
+        }
+    }
+
+    /// Set the image model (e.g. "dall-e-2", "gpt-image-1")
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the image size (e.g. "1024x1024", "1792x1024")
+    pub fn with_size(mut self, size: impl Into<String>) -> Self {
+        self.size = size.into();
+        self
+    }
+
+    /// Set how many images to generate per prompt (dall-e-3 only supports 1)
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = count.max(1);
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl ImageProvider for OpenAIImageProvider {
+    async fn generate(&self, prompt: &str) -> Result<Vec<GeneratedImage>> {
+        let request = ImageRequest {
+            model: &self.model,
+            prompt,
+            n: self.count,
+            size: &self.size,
+        };
+
+        let url = format!("{}/images/generations", self.base_url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("OpenAI Images", e))?;
+
+        let body: ImageResponse = parse_response(response, "OpenAI Images").await?;
+        let images = body
+            .data
+            .into_iter()
+            .filter_map(|image| match (image.url, image.b64_json) {
+                (Some(url), _) => Some(Ok(GeneratedImage::Url(url))),
+                (None, Some(encoded)) => Some(GeneratedImage::from_base64(&encoded, "image/png")),
+                (None, None) => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if images.is_empty() {
+            return Err(AgentError::LLMProvider(
+                "OpenAI Images response contained no images".to_string(),
+            ));
+        }
+        Ok(images)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_generate_returns_urls_and_bytes() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/images/generations"))
+            .and(header("Authorization", "Bearer test-key"))
+            .and(body_partial_json(
+                serde_json::json!({"prompt": "a cat", "n": 2}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 0,
+                "data": [
+                    {"url": "https://images.example.com/1.png"},
+                    {"b64_json": "AAEC"}
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIImageProvider::new("test-key")
+            .with_count(2)
+            .with_base_url(mock_server.uri());
+        let images = provider.generate("a cat").await.unwrap();
+
+        assert_eq!(
+            images[0],
+            GeneratedImage::Url("https://images.example.com/1.png".to_string())
+        );
+        assert_eq!(
+            images[1],
+            GeneratedImage::Bytes {
+                data: vec![0, 1, 2],
+                mime_type: "image/png".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_rejected_prompt() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("content_policy_violation"))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIImageProvider::new("test-key").with_base_url(mock_server.uri());
+        let err = provider.generate("bad").await.unwrap_err();
+        assert!(err.to_string().contains("content_policy_violation"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Stability AI image generation provider.
+
+use agent_core::Result;
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::{GeneratedImage, ImageProvider, parse_response, request_error};
+
+/// Default Stability AI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.stability.ai/v2beta";
+
+#[derive(Debug, Deserialize)]
+struct StabilityResponse {
+    image: String,
+}
+
+/// Image provider backed by Stability AI's Stable Image API
+pub struct StabilityImageProvider {
+    api_key: String,
+    endpoint: String,
+    aspect_ratio: Option<String>,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl StabilityImageProvider {
+    /// Create a provider using the `core` endpoint
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            endpoint: "core".to_string(),
+            aspect_ratio: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::with_timeout(Duration::from_secs(120)),
+        }
+    }
+
+    /// Set the generation endpoint ("core", "ultra", or "sd3")
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Set the aspect ratio (e.g. "16:9")
+    pub fn with_aspect_ratio(mut self, aspect_ratio: impl Into<String>) -> Self {
+        self.aspect_ratio = Some(aspect_ratio.into());
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl ImageProvider for StabilityImageProvider {
+    async fn generate(&self, prompt: &str) -> Result<Vec<GeneratedImage>> {
+        let mut form = reqwest::multipart::Form::new()
+            .text("prompt", prompt.to_string())
+            .text("output_format", "png");
+        if let Some(aspect_ratio) = &self.aspect_ratio {
+            form = form.text("aspect_ratio", aspect_ratio.clone());
+        }
+
+        let url = format!("{}/stable-image/generate/{}", self.base_url, self.endpoint);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Accept", "application/json")
+            .multipart(form)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("Stability", e))?;
+
+        let body: StabilityResponse = parse_response(response, "Stability").await?;
+        Ok(vec![GeneratedImage::from_base64(&body.image, "image/png")?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_generate() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/stable-image/generate/ultra"))
+            .and(header("Authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "image": "AAEC",
+                "finish_reason": "SUCCESS",
+                "seed": 42
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = StabilityImageProvider::new("test-key")
+            .with_endpoint("ultra")
+            .with_base_url(mock_server.uri());
+        let images = provider.generate("a mountain").await.unwrap();
+
+        assert_eq!(images.len(), 1);
+        assert!(images[0].to_url().starts_with("data:image/png;base64,"));
+    }
+}
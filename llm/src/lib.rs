@@ -5,8 +5,112 @@
 //!
 //! # Supported Providers
 //! 
-//! - **OpenAI**: GPT-3.5, GPT-4, and other OpenAI models
+//! - **OpenAI**: GPT-3.5, GPT-4, and other OpenAI models, from the OpenAI API
+//!   or an Azure OpenAI deployment
 //! - **Anthropic**: Claude models (Claude 3 Sonnet, Opus, etc.)
+//! - **Google Gemini**: Gemini models through the Generative Language API,
+//!   with per-category safety settings
+//! - **OpenRouter**: Models of many vendors through one API, with fallback
+//!   models and provider routing preferences
+//! - **Ollama** and **llama.cpp**: Local models, with grammar-constrained
+//!   structured output
+//!
+//! Speech-to-text is available through the [`TranscriptionProvider`] trait
+//! (see the [`transcription`] module), and text-to-speech through the
+//! [`SpeechProvider`] trait (see the [`speech`] module). Image generation is
+//! available through the [`ImageProvider`] trait (see the [`image`] module).
+//! Text embeddings for retrieval come from the [`EmbeddingProvider`] trait
+//! (see the [`embedding`] module), with batching across requests.
+//! Files can be uploaded once through a [`FileStore`] (see the [`files`]
+//! module) and referenced from messages by ID, and OpenAI fine-tuning jobs are
+//! managed with [`FineTuningClient`], with training data built from user
+//! feedback by [`FineTuneDataset::from_feedback`]. Bidirectional audio/text
+//! conversations with the OpenAI Realtime API use [`RealtimeSession`]. Answers grounded in
+//! source documents, with typed [`Citation`]s, come from
+//! [`AnthropicProvider::send_with_citations`]. Billed usage and costs are
+//! read from the Anthropic Admin API with [`AnthropicUsageClient`] and
+//! reconciled against local estimates.
+//!
+//! Named, versioned prompts are loaded from a directory into a
+//! [`PromptStore`] (see the [`prompt`] module); [`PromptTemplate`] compiles
+//! prompts with placeholders, conditionals and partials. Conversation titles
+//! and summaries for chat UIs are generated with [`ConversationSummarizer`].
+//! Documents longer than the context window are summarized with
+//! [`DocumentSummarizer`], which summarizes token-bounded chunks in parallel
+//! and combines the results hierarchically.
+//!
+//! [`LLMProvider::send_completion`] returns the response as a
+//! [`CompletionResponse`] with its stop reason, token usage and model name,
+//! for cost tracking.
+//!
+//! Responses can be streamed with [`LLMProvider::stream_message`].
+//! [`with_stop_conditions`] ends a stream early on a regex match, a
+//! client-side token limit, or a guardrail violation, aborting the request.
+//! [`with_buffering`] regroups a stream into tokens or sentences and reads
+//! ahead of slow consumers, as configured by a [`StreamBuffering`].
+//!
+//! [`LLMProvider::send_message_with_tools`] offers tools described by
+//! [`ToolDefinition`](agent_core::ToolDefinition)s and returns the tool calls
+//! the model requested, using Anthropic `tool_use` blocks, OpenAI function
+//! calling and Gemini function declarations natively and structured output
+//! for other providers.
+//!
+//! [`extract`] pulls a typed value out of text: it asks for structured output
+//! matching the [`Extractable`] type's schema, repairs malformed replies and
+//! deserializes the result. [`classify`] picks one of a fixed set of
+//! [`ClassLabel`]s for a text, with the model's confidence.
+//! [`GraphExtractor`] pulls entities and typed relations out of documents and
+//! conversations into a [`KnowledgeGraph`](memory::KnowledgeGraph).
+//!
+//! The OpenAI and Anthropic providers shape each request with the
+//! [`ModelRules`] of their model family: system messages are merged into the
+//! first user message for models that reject them, `max_tokens` is capped at
+//! the model's output limit, and sampling parameters the model does not
+//! accept are dropped.
+//!
+//! # Provider Wrappers
+//!
+//! - [`CoalescingProvider`]: Shares one upstream call between concurrent
+//!   identical requests
+//! - [`GovernedProvider`]: Caps simultaneous requests, overall and per
+//!   provider, through a shared [`ConcurrencyGovernor`] that serves
+//!   interactive requests before background ones
+//! - [`RateLimitedProvider`]: Draws from a per-provider token bucket in a
+//!   [`RateLimiter`], optionally shared across processes through Redis
+//! - [`PlainTextProvider`]: Strips code fences, XML wrappers and chatter from
+//!   completions according to an [`OutputPolicy`]
+//! - [`FallbackProvider`]: Fails over to the next of an ordered list of
+//!   providers on timeouts, rate limits and 5xx errors, recording which
+//!   provider served each request
+//! - [`PooledProvider`]: Balances requests round robin or to the least
+//!   loaded of several API keys or endpoints of one vendor, resting keys
+//!   that hit a rate limit
+//! - [`PostProcessingProvider`]: Runs completions through a
+//!   [`PostProcessingPipeline`] of [`PostProcessor`] stages (Markdown
+//!   normalization, citation linking, code block extraction, custom stages)
+//! - [`RaceProvider`]: Returns the first successful response from several providers
+//! - [`ConsensusProvider`]: Returns the majority answer across several providers
+//! - [`SelfConsistencyProvider`]: Samples one provider several times at a
+//!   high temperature and returns the answer most samples agree on
+//! - [`DegradingProvider`]: Reports outages as a typed `ServiceDegraded` error
+//!   and replays recent responses, with a [`DegradationPolicy`] deciding
+//!   whether users see a fallback reply
+//! - [`ContinuingProvider`]: Asks the model to continue responses cut off by
+//!   the token limit and stitches the parts together
+//! - [`TransformingProvider`]: Cleans up messages before they are sent with a
+//!   configured [`TransformPipeline`] (HTML stripping, whitespace, long code
+//!   blocks, current date and time)
+//! - [`CompressingProvider`]: Drops low-information sentences from long
+//!   prompts with a [`PromptCompressor`] and reports the tokens saved
+//! - [`LayeredProvider`]: Runs requests through a stack of [`LLMMiddleware`]
+//!   for logging, redaction, caching or rate limiting around any provider
+//! - [`CachingProvider`]: Answers repeated identical requests from an
+//!   in-memory or on-disk [`ResponseCache`], with an optional time-to-live
+//! - [`TelemetryProvider`]: Records latency, usage and errors of each request,
+//!   keeping prompts and completions verbatim, hashed, embedded or not at all
+//! - [`TracedProvider`]: Runs each request in a `tracing` span with the
+//!   provider, model, request trace ID and token counts; providers from
+//!   [`create_provider`] are wrapped in one
 //!
 //! # Usage
 //!
@@ -24,19 +128,160 @@
 //!     api_key: "your-api-key".to_string(),
 //!     temperature: 0.7,
 //!     max_tokens: 2000,
+//!     organization: None,
+//!     project: None,
+//!     safety_settings: Default::default(),
+//!     azure: None,
+//!     openrouter: None,
+//!     prompt_caching: false,
 //! };
 //!
 //! let provider = create_provider(&config)?;
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! Providers can also be constructed directly with a builder, which only
+//! requires an API key:
+//!
+//! ```no_run
+//! use llm::{AnthropicProvider, ModelId};
+//!
+//! # fn example() -> agent_core::Result<()> {
+//! let provider = AnthropicProvider::builder()
+//!     .api_key("your-api-key")
+//!     .model(ModelId::CLAUDE_SONNET_4_5)
+//!     .base_url("https://gateway.example.com/v1")
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
 
 mod provider;
 mod factory;
+mod buffering;
+mod caching;
+mod cancellation;
+mod citations;
+mod classify;
+mod coalescing;
+mod compression;
+mod concurrency;
+mod continuation;
+mod degradation;
+mod extract;
+mod fallback;
+mod fanout;
+pub mod files;
+mod fine_tuning;
+mod gemini;
+mod graph_extract;
+mod map_reduce;
+mod middleware;
+pub mod image;
+mod llama_cpp;
+mod model;
+mod ollama;
+mod openrouter;
+mod params;
+mod plain_text;
+mod pool;
+mod post_process;
+pub mod prompt;
+mod rate_limit;
+mod shaping;
+mod realtime;
+mod self_consistency;
+mod streaming;
+mod structured;
+mod summarize;
+mod telemetry;
+mod tool_choice;
+mod traced;
+mod tool_use;
+mod transform;
+pub mod speech;
+pub mod transcription;
+pub mod embedding;
+mod usage;
 pub mod openai;
 pub mod anthropic;
 
-pub use anthropic::AnthropicProvider;
+pub use anthropic::{AnthropicProvider, AnthropicProviderBuilder};
+pub use caching::{
+    CachedResponse, CachingProvider, DiskResponseCache, InMemoryResponseCache, ResponseCache,
+};
+pub use cancellation::{CancellationToken, cancellable};
+pub use citations::{
+    Citation, CitationDocument, CitationSource, CitationSpan, CitedResponse, CitedText,
+    DocumentSource,
+};
+pub use classify::{ClassLabel, Classification, classify};
+pub use buffering::{Backpressure, Granularity, StreamBuffering, with_buffering};
+pub use coalescing::CoalescingProvider;
+pub use compression::{CompressingProvider, CompressionUsage, PromptCompressor};
+pub use concurrency::{ConcurrencyGovernor, GovernedProvider, RequestPermit, RequestPriority};
+pub use continuation::{CompletionResponse, ContinuingProvider, FinishReason};
+pub use degradation::{DegradationPolicy, DegradingProvider};
+pub use extract::{Extractable, extract, extract_with};
 pub use factory::create_provider;
-pub use openai::OpenAIProvider;
+pub use fallback::FallbackProvider;
+pub use fanout::{ConsensusProvider, RaceProvider};
+pub use self_consistency::{Consensus, SelfConsistencyProvider};
+pub use fine_tuning::{
+    FineTuneDataset, FineTuneError, FineTuneJob, FineTuneRequest, FineTuneStatus, FineTuningClient,
+};
+pub use gemini::GeminiProvider;
+pub use graph_extract::GraphExtractor;
+pub use files::{AnthropicFileStore, FileStore, FileUpload, OpenAIFileStore, StoredFile};
+pub use image::{
+    GeminiImageProvider, GeneratedImage, ImageProvider, OpenAIImageProvider, StabilityImageProvider,
+};
+pub use llama_cpp::LlamaCppProvider;
+pub use map_reduce::{DocumentSummarizer, DocumentSummary};
+pub use middleware::{LLMMiddleware, LayeredProvider, Next};
+pub use model::{ModelId, ModelRegistry, RegisteredModel};
+pub use params::{MaxTokens, Temperature, TopP};
+pub use plain_text::{OutputPolicy, PlainTextProvider, XmlWrappers};
+pub use pool::{PoolMemberStats, PoolStrategy, PooledProvider};
+pub use post_process::{
+    CitationLinker, CodeBlockExtractor, MarkdownNormalizer, PostProcessingPipeline,
+    PostProcessingProvider, PostProcessor,
+};
+pub use prompt::{Prompt, PromptMetadata, PromptStore, PromptTemplate, PromptTemplateBuilder};
+pub use rate_limit::{
+    LocalRateLimitBackend, RateLimitBackend, RateLimitedProvider, RateLimiter,
+    RedisRateLimitBackend, TokenBucket,
+};
+pub use realtime::{
+    RealtimeConfig, RealtimeEvent, RealtimeReceiver, RealtimeSender, RealtimeSession, ServerVad,
+};
+pub use ollama::OllamaProvider;
+pub use openai::{OpenAIProvider, OpenAIProviderBuilder};
+pub use openrouter::{OPENROUTER_BASE_URL, OpenRouterProvider};
 pub use provider::LLMProvider;
+pub use shaping::ModelRules;
+pub use streaming::{
+    CacheUsage, ContentCheck, StopCondition, StopReason, StreamEvent, TextStream, TokenStream,
+    TokenUsage, collect_text, text_chunks, with_stop_conditions,
+};
+pub use structured::StructuredOutput;
+pub use summarize::ConversationSummarizer;
+pub use telemetry::{
+    ContentMode, Embedder, InMemoryTelemetry, RecordedContent, RecordedMessage, TelemetryEvent,
+    TelemetryProvider, TelemetrySink,
+};
+pub use tool_choice::{ToolChoice, ToolConfig};
+pub use traced::TracedProvider;
+pub use transform::{MessageTransform, TransformPipeline, TransformingProvider};
+pub use speech::{
+    AudioFormat, AudioStream, ElevenLabsSpeechProvider, OpenAISpeechProvider, SpeechProvider,
+};
+pub use transcription::{
+    AudioInput, OpenAIWhisperProvider, TranscriptionProvider, WhisperCppProvider,
+};
+pub use embedding::{EmbeddingProvider, OllamaEmbeddingProvider, OpenAIEmbeddingProvider};
+pub use usage::{
+    AnthropicUsageClient, BilledUsage, BucketWidth, CostLine, CostReconciliation, ModelUsage,
+    UsageQuery,
+};
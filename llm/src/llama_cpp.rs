@@ -0,0 +1,250 @@
+//! llama.cpp server provider.
+
+use agent_core::{AgentError, Message, Result, Role};
+use async_trait::async_trait;
+use communication::ApiClient;
+use config::LLMConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::structured::repair_loop;
+use crate::{LLMProvider, StructuredOutput};
+
+/// Default llama.cpp server URL
+const DEFAULT_BASE_URL: &str = "http://localhost:8080";
+
+/// Request body for the llama.cpp chat completions endpoint
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+    max_tokens: usize,
+    /// GBNF grammar the output is constrained to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<&'a str>,
+    /// JSON schema the output is constrained to, converted to a grammar by the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_schema: Option<&'a Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ChatMessage,
+}
+
+/// Output constraint applied to a request
+enum Constraint<'a> {
+    Grammar(&'a str),
+    Schema(&'a Value),
+}
+
+/// LLM provider for models served by the llama.cpp HTTP server
+///
+/// Structured requests are grammar-constrained: a GBNF grammar from
+/// [`StructuredOutput::grammar`] is used when set, otherwise the server
+/// derives a grammar from the schema.
+pub struct LlamaCppProvider {
+    model: String,
+    temperature: f32,
+    max_tokens: usize,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl LlamaCppProvider {
+    /// Create a new llama.cpp provider from configuration
+    ///
+    /// The API key is not used. The model name is informational; the server
+    /// answers with whichever model it has loaded.
+    pub fn new(config: &LLMConfig) -> Result<Self> {
+        Ok(Self {
+            model: config.model.clone(),
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        })
+    }
+
+    /// Set the llama.cpp server URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        constraint: Option<Constraint<'_>>,
+    ) -> Result<String> {
+        let (grammar, json_schema) = match constraint {
+            Some(Constraint::Grammar(grammar)) => (Some(grammar), None),
+            Some(Constraint::Schema(schema)) => (None, Some(schema)),
+            None => (None, None),
+        };
+        let request = ChatRequest {
+            model: &self.model,
+            messages: messages
+                .iter()
+                .map(|message| ChatMessage {
+                    role: match message.role {
+                        Role::System => "system",
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                    }
+                    .to_string(),
+                    content: message.content.clone(),
+                })
+                .collect(),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            grammar,
+            json_schema,
+        };
+
+        let url = format!("{}/v1/chat/completions", self.base_url);
+        let response: ChatResponse = self.client.post_json(&url, &request).await?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| {
+                AgentError::LLMProvider("llama.cpp response contained no choices".to_string())
+            })
+    }
+}
+
+#[async_trait]
+impl LLMProvider for LlamaCppProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.chat(messages, None).await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        repair_loop(messages, output, |conversation| async move {
+            let constraint = match &output.grammar {
+                Some(grammar) => Constraint::Grammar(grammar),
+                None => Constraint::Schema(&output.schema),
+            };
+            self.chat(&conversation, Some(constraint)).await
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(base_url: String) -> LlamaCppProvider {
+        let config = LLMConfig {
+            provider: "llamacpp".to_string(),
+            model: "local".to_string(),
+            api_key: String::new(),
+            temperature: 0.0,
+            max_tokens: 128,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+        LlamaCppProvider::new(&config)
+            .unwrap()
+            .with_base_url(base_url)
+    }
+
+    fn reply(content: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": content}}]
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_send_message() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(reply("Hello!"))
+            .mount(&mock_server)
+            .await;
+
+        let response = provider(mock_server.uri())
+            .send_message(&[Message::user("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(response, "Hello!");
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("grammar").is_none());
+        assert!(body.get("json_schema").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_uses_json_schema() {
+        let mock_server = MockServer::start().await;
+        let schema = json!({"type": "object", "properties": {"ok": {"type": "boolean"}}});
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"json_schema": schema})))
+            .respond_with(reply(r#"{"ok": true}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let value = provider(mock_server.uri())
+            .send_structured(&[Message::user("Ok?")], &StructuredOutput::new(schema))
+            .await
+            .unwrap();
+        assert_eq!(value["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_prefers_grammar() {
+        let mock_server = MockServer::start().await;
+        let grammar = r#"root ::= "[" ("\"yes\"" | "\"no\"") "]""#;
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"grammar": grammar})))
+            .respond_with(reply(r#"["yes"]"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let output =
+            StructuredOutput::new(json!({"type": "array", "items": {"enum": ["yes", "no"]}}))
+                .with_grammar(grammar);
+        let value = provider(mock_server.uri())
+            .send_structured(&[Message::user("Agree?")], &output)
+            .await
+            .unwrap();
+        assert_eq!(value, json!(["yes"]));
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("json_schema").is_none());
+    }
+}
@@ -0,0 +1,288 @@
+//! Summaries of documents longer than a model's context window.
+//!
+//! [`DocumentSummarizer`] splits a document into chunks with a
+//! [`TextChunker`], summarizes the chunks in parallel ("map"), then
+//! combines groups of summaries that fit the same token budget into
+//! summaries of summaries ("reduce") until one is left. Every request stays
+//! within the budget however long the document is.
+
+use agent_core::{AgentError, Message, Result};
+use futures::{StreamExt, TryStreamExt, stream};
+use memory::{TextChunker, TokenEstimator};
+
+use crate::{LLMProvider, TokenUsage};
+
+/// Default tokens per chunk, and per group of summaries combined at once
+const DEFAULT_CHUNK_TOKENS: usize = 3_000;
+
+/// Default number of summary requests in flight at once
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+const MAP_PROMPT: &str = "Summarize the following section of a longer document. Keep the key \
+facts, figures, names and conclusions, and leave out repetition and filler. Reply with the \
+summary only.";
+
+const REDUCE_PROMPT: &str = "The following are summaries of consecutive sections of one \
+document. Combine them into a single summary that keeps the key facts, figures, names and \
+conclusions in the order they appear. Reply with the summary only.";
+
+/// Separator between summaries combined in one request
+const SUMMARY_SEPARATOR: &str = "\n\n---\n\n";
+
+/// The summary of a document and what it took to produce
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentSummary {
+    /// The final summary
+    pub summary: String,
+    /// Number of chunks the document was split into
+    pub chunks: usize,
+    /// Number of reduce rounds needed to combine the chunk summaries
+    pub reduce_rounds: usize,
+    /// Tokens billed across all requests, if the provider reports them
+    pub usage: Option<TokenUsage>,
+}
+
+/// Summarizes long documents with map-reduce over token-bounded chunks
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{DocumentSummarizer, OpenAIProvider};
+/// use memory::TokenEstimator;
+///
+/// # async fn example(report: &str) -> agent_core::Result<()> {
+/// let provider = OpenAIProvider::builder().api_key("your-api-key").build()?;
+/// let summarizer = DocumentSummarizer::new(provider)
+///     .with_estimator(TokenEstimator::for_model("openai", "gpt-4o-mini"))
+///     .with_chunk_tokens(6_000)
+///     .with_focus("risks and open questions");
+///
+/// let result = summarizer.summarize(report).await?;
+/// println!("{} chunks: {}", result.chunks, result.summary);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DocumentSummarizer<P: LLMProvider> {
+    provider: P,
+    chunk_tokens: usize,
+    estimator: TokenEstimator,
+    max_concurrency: usize,
+    focus: Option<String>,
+}
+
+impl<P: LLMProvider> DocumentSummarizer<P> {
+    /// Create a summarizer that uses `provider`, with 3,000 token chunks
+    /// and up to 4 requests at once
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            chunk_tokens: DEFAULT_CHUNK_TOKENS,
+            estimator: TokenEstimator::default(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            focus: None,
+        }
+    }
+
+    /// Set the token budget for each chunk, and for each group of
+    /// summaries combined in one request
+    pub fn with_chunk_tokens(mut self, tokens: usize) -> Self {
+        self.chunk_tokens = tokens.max(1);
+        self
+    }
+
+    /// Set the estimator used to count tokens, usually the one for the
+    /// provider's model
+    pub fn with_estimator(mut self, estimator: TokenEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Set how many summary requests may be in flight at once
+    ///
+    /// Zero is treated as one.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Ask every summary to concentrate on a topic, e.g. "financial risks"
+    pub fn with_focus(mut self, focus: impl Into<String>) -> Self {
+        self.focus = Some(focus.into());
+        self
+    }
+
+    /// Summarize `text`
+    ///
+    /// # Errors
+    /// Returns an error if the text is blank or if any summary request
+    /// fails.
+    pub async fn summarize(&self, text: &str) -> Result<DocumentSummary> {
+        let chunks = TextChunker::new(self.chunk_tokens)
+            .with_estimator(self.estimator)
+            .split(text);
+        if chunks.is_empty() {
+            return Err(AgentError::Execution(
+                "Cannot summarize an empty document".to_string(),
+            ));
+        }
+
+        let mut usage = None;
+        let mut summaries = self.run_all(MAP_PROMPT, chunks.clone(), &mut usage).await?;
+
+        let mut reduce_rounds = 0;
+        while summaries.len() > 1 {
+            let groups = self.group(summaries);
+            summaries = self.run_all(REDUCE_PROMPT, groups, &mut usage).await?;
+            reduce_rounds += 1;
+        }
+
+        Ok(DocumentSummary {
+            summary: summaries.remove(0),
+            chunks: chunks.len(),
+            reduce_rounds,
+            usage,
+        })
+    }
+
+    /// Summarize each input with `instructions`, keeping their order
+    async fn run_all(
+        &self,
+        instructions: &str,
+        inputs: Vec<String>,
+        usage: &mut Option<TokenUsage>,
+    ) -> Result<Vec<String>> {
+        let completions: Vec<_> = stream::iter(inputs)
+            .map(|input| {
+                let mut system = instructions.to_string();
+                if let Some(focus) = &self.focus {
+                    system.push_str(&format!("\n\nFocus on: {}", focus));
+                }
+                async move {
+                    self.provider
+                        .send_completion(&[Message::system(system), Message::user(input)])
+                        .await
+                }
+            })
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await?;
+
+        Ok(completions
+            .into_iter()
+            .map(|completion| {
+                *usage = match (*usage, completion.usage) {
+                    (Some(total), Some(more)) => Some(total + more),
+                    (total, more) => total.or(more),
+                };
+                completion.text.trim().to_string()
+            })
+            .collect())
+    }
+
+    /// Join consecutive summaries into groups within the chunk budget
+    ///
+    /// Every group has at least two summaries, so each reduce round at
+    /// least halves the count even when single summaries are over budget.
+    fn group(&self, summaries: Vec<String>) -> Vec<String> {
+        let mut groups: Vec<(String, usize)> = Vec::new();
+        for summary in summaries {
+            match groups.last_mut() {
+                Some((group, len))
+                    if *len < 2
+                        || self.estimator.count_text(group)
+                            + self.estimator.count_text(&summary)
+                            <= self.chunk_tokens =>
+                {
+                    group.push_str(SUMMARY_SEPARATOR);
+                    group.push_str(&summary);
+                    *len += 1;
+                }
+                _ => groups.push((summary, 1)),
+            }
+        }
+        // A trailing summary on its own would not shrink; fold it into the
+        // previous group
+        if groups.len() > 1 && groups.last().is_some_and(|(_, len)| *len == 1) {
+            let (last, _) = groups.pop().expect("checked above");
+            let (group, _) = groups.last_mut().expect("checked above");
+            group.push_str(SUMMARY_SEPARATOR);
+            group.push_str(&last);
+        }
+        groups.into_iter().map(|(group, _)| group).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompletionResponse, FinishReason};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Replies with a numbered summary and records each request's input
+    struct Numbering {
+        inputs: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Numbering {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(self.send_completion(messages).await?.text)
+        }
+
+        async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+            let mut inputs = self.inputs.lock().unwrap();
+            inputs.push(messages[1].content.clone());
+            let usage = TokenUsage {
+                input_tokens: 10,
+                output_tokens: 2,
+            };
+            Ok(
+                CompletionResponse::new(format!("s{}", inputs.len()), FinishReason::Stop)
+                    .with_usage(usage),
+            )
+        }
+    }
+
+    fn summarizer(chunk_tokens: usize) -> DocumentSummarizer<Numbering> {
+        DocumentSummarizer::new(Numbering {
+            inputs: Mutex::new(Vec::new()),
+        })
+        .with_estimator(TokenEstimator::Approximate {
+            chars_per_token: 1.0,
+        })
+        .with_chunk_tokens(chunk_tokens)
+        .with_max_concurrency(2)
+    }
+
+    #[tokio::test]
+    async fn test_long_document_is_reduced_hierarchically() {
+        let summarizer = summarizer(12);
+        let text = ["aaaaaaaaaa"; 5].join("\n\n");
+
+        let result = summarizer.summarize(&text).await.unwrap();
+        assert_eq!(result.chunks, 5);
+        // 5 chunk summaries -> 2 groups -> 1
+        assert_eq!(result.reduce_rounds, 2);
+        assert_eq!(result.summary, "s8");
+        assert_eq!(result.usage.unwrap().input_tokens, 80);
+
+        let inputs = summarizer.provider.inputs.lock().unwrap();
+        assert_eq!(inputs.len(), 8);
+        assert_eq!(inputs[5], "s1\n\n---\n\ns2");
+        assert_eq!(inputs[6], "s3\n\n---\n\ns4\n\n---\n\ns5");
+        assert_eq!(inputs[7], "s6\n\n---\n\ns7");
+    }
+
+    #[tokio::test]
+    async fn test_short_document_needs_one_request() {
+        let summarizer = summarizer(100).with_focus("dates");
+        let result = summarizer.summarize("A short note.").await.unwrap();
+        assert_eq!(result.chunks, 1);
+        assert_eq!(result.reduce_rounds, 0);
+        assert_eq!(summarizer.provider.inputs.lock().unwrap().len(), 1);
+
+        let result = summarizer.summarize(" \n ").await;
+        assert!(matches!(result, Err(AgentError::Execution(_))));
+    }
+}
@@ -0,0 +1,339 @@
+//! Composable middleware around a provider.
+//!
+//! Logging, redaction, caching and rate limiting all want to run code
+//! before and after a request without knowing which provider serves it. An
+//! [`LLMMiddleware`] receives the messages of each completion request and a
+//! [`Next`] handle to the rest of the chain: it can change the messages,
+//! answer without calling the provider, or inspect and change the response.
+//! A [`LayeredProvider`] stacks middleware around any provider, much like
+//! tower layers around a service.
+
+use agent_core::{Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::{CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig};
+
+/// Code that runs around each completion request of a [`LayeredProvider`]
+///
+/// # Example
+///
+/// ```
+/// use agent_core::{Message, Result};
+/// use async_trait::async_trait;
+/// use llm::{CompletionResponse, LLMMiddleware, Next};
+///
+/// /// Replaces email addresses before they leave the process
+/// struct RedactEmails;
+///
+/// #[async_trait]
+/// impl LLMMiddleware for RedactEmails {
+///     async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse> {
+///         let redacted: Vec<Message> = messages
+///             .iter()
+///             .map(|message| {
+///                 let mut message = message.clone();
+///                 message.content = message
+///                     .content
+///                     .split(' ')
+///                     .map(|word| if word.contains('@') { "[email]" } else { word })
+///                     .collect::<Vec<_>>()
+///                     .join(" ");
+///                 message
+///             })
+///             .collect();
+///         next.run(&redacted).await
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait LLMMiddleware: Send + Sync {
+    /// Handle a request, usually by calling `next.run` with the same or
+    /// changed messages
+    async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse>;
+}
+
+/// The middleware after the current one, followed by the provider
+pub struct Next<'a> {
+    provider: &'a dyn LLMProvider,
+    layers: &'a [Arc<dyn LLMMiddleware>],
+}
+
+impl Next<'_> {
+    /// Pass the request on to the next middleware, or to the provider if
+    /// this is the last one
+    pub async fn run(self, messages: &[Message]) -> Result<CompletionResponse> {
+        match self.layers.split_first() {
+            Some((layer, rest)) => {
+                let next = Next {
+                    provider: self.provider,
+                    layers: rest,
+                };
+                layer.handle(messages, next).await
+            }
+            None => self.provider.send_completion(messages).await,
+        }
+    }
+}
+
+/// Provider wrapper that runs requests through a stack of [`LLMMiddleware`]
+///
+/// Middleware added first runs first, and sees the response last.
+/// `send_message` and `send_completion` go through the middleware.
+/// Structured output, tool calls and streams go straight to the provider,
+/// since middleware works on complete text responses; wrap the provider in
+/// a dedicated wrapper to intercept those.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{LayeredProvider, LLMProvider, OpenAIProvider};
+/// # use agent_core::{Message, Result};
+/// # use async_trait::async_trait;
+/// # use llm::{CompletionResponse, LLMMiddleware, Next};
+/// # struct RequestLog;
+/// # #[async_trait]
+/// # impl LLMMiddleware for RequestLog {
+/// #     async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse> {
+/// #         next.run(messages).await
+/// #     }
+/// # }
+/// # struct RedactEmails;
+/// # #[async_trait]
+/// # impl LLMMiddleware for RedactEmails {
+/// #     async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse> {
+/// #         next.run(messages).await
+/// #     }
+/// # }
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = LayeredProvider::new(OpenAIProvider::builder().api_key("your-api-key").build()?)
+///     .layer(RequestLog)
+///     .layer(RedactEmails);
+///
+/// let reply = provider.send_message(&[Message::user("Mail bob@example.com")]).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct LayeredProvider<P> {
+    inner: P,
+    layers: Vec<Arc<dyn LLMMiddleware>>,
+}
+
+impl<P: LLMProvider> LayeredProvider<P> {
+    /// Wrap a provider with no middleware
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Add a middleware inside those already added
+    pub fn layer(self, middleware: impl LLMMiddleware + 'static) -> Self {
+        self.layer_arc(Arc::new(middleware))
+    }
+
+    /// Add a shared middleware inside those already added
+    pub fn layer_arc(mut self, middleware: Arc<dyn LLMMiddleware>) -> Self {
+        self.layers.push(middleware);
+        self
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Number of middleware layers
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Check if there is no middleware
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for LayeredProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        Ok(self.send_completion(messages).await?.text)
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let next = Next {
+            provider: &self.inner,
+            layers: &self.layers,
+        };
+        next.run(messages).await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner
+            .send_structured_completion(messages, output)
+            .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner
+            .send_message_with_tools(messages, tools, config)
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.inner.stream_message(messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FinishReason;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Echo {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Echo {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(messages.last().unwrap().content.clone())
+        }
+    }
+
+    fn echo() -> Echo {
+        Echo {
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    struct Log {
+        name: &'static str,
+        entries: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl LLMMiddleware for Log {
+        async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse> {
+            self.entries
+                .lock()
+                .unwrap()
+                .push(format!("{} before", self.name));
+            let response = next.run(messages).await;
+            self.entries
+                .lock()
+                .unwrap()
+                .push(format!("{} after", self.name));
+            response
+        }
+    }
+
+    struct Uppercase;
+
+    #[async_trait]
+    impl LLMMiddleware for Uppercase {
+        async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse> {
+            let messages: Vec<Message> = messages
+                .iter()
+                .map(|message| Message::user(message.content.to_uppercase()))
+                .collect();
+            next.run(&messages).await
+        }
+    }
+
+    #[derive(Default)]
+    struct Cache {
+        responses: Mutex<HashMap<String, CompletionResponse>>,
+    }
+
+    #[async_trait]
+    impl LLMMiddleware for Cache {
+        async fn handle(&self, messages: &[Message], next: Next<'_>) -> Result<CompletionResponse> {
+            let key = messages.last().unwrap().content.clone();
+            if let Some(response) = self.responses.lock().unwrap().get(&key) {
+                return Ok(response.clone());
+            }
+            let response = next.run(messages).await?;
+            self.responses.lock().unwrap().insert(key, response.clone());
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layers_run_in_order() {
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let provider = LayeredProvider::new(echo())
+            .layer(Log {
+                name: "outer",
+                entries: entries.clone(),
+            })
+            .layer(Uppercase)
+            .layer(Log {
+                name: "inner",
+                entries: entries.clone(),
+            });
+
+        let response = provider
+            .send_completion(&[Message::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.text, "HI");
+        assert_eq!(response.finish_reason, FinishReason::Unknown);
+        assert_eq!(
+            *entries.lock().unwrap(),
+            ["outer before", "inner before", "inner after", "outer after"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middleware_can_answer_without_the_provider() {
+        let provider = LayeredProvider::new(echo()).layer(Cache::default());
+
+        for _ in 0..3 {
+            let reply = provider
+                .send_message(&[Message::user("same")])
+                .await
+                .unwrap();
+            assert_eq!(reply, "same");
+        }
+        provider
+            .send_message(&[Message::user("other")])
+            .await
+            .unwrap();
+        assert_eq!(provider.inner().calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_layers_calls_provider() {
+        let provider = LayeredProvider::new(echo());
+        assert!(provider.is_empty());
+        assert_eq!(
+            provider.send_message(&[Message::user("x")]).await.unwrap(),
+            "x"
+        );
+    }
+}
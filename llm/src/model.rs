@@ -0,0 +1,358 @@
+//! Typed model identifiers.
+//!
+//! [`ModelId`] wraps a model name and provides constants for common models.
+//! Parsing a model name with [`str::parse`] rejects near-misses of known
+//! models (e.g. `gpt-4-o` or `claude-sonet-4-5`) so typos fail locally with a
+//! suggestion instead of surfacing as an opaque 404 from the API.
+//!
+//! Models that are created at runtime, such as fine-tunes, can be recorded
+//! in a [`ModelRegistry`].
+
+use agent_core::{AgentError, Result};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Identifier of an LLM model
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModelId(Cow<'static, str>);
+
+impl ModelId {
+    /// Claude Opus 4.1
+    pub const CLAUDE_OPUS_4_1: ModelId = ModelId::known("claude-opus-4-1");
+    /// Claude Opus 4
+    pub const CLAUDE_OPUS_4_0: ModelId = ModelId::known("claude-opus-4-0");
+    /// Claude Sonnet 4.5
+    pub const CLAUDE_SONNET_4_5: ModelId = ModelId::known("claude-sonnet-4-5");
+    /// Claude Sonnet 4
+    pub const CLAUDE_SONNET_4_0: ModelId = ModelId::known("claude-sonnet-4-0");
+    /// Claude Haiku 4.5
+    pub const CLAUDE_HAIKU_4_5: ModelId = ModelId::known("claude-haiku-4-5");
+    /// Claude 3.7 Sonnet
+    pub const CLAUDE_3_7_SONNET: ModelId = ModelId::known("claude-3-7-sonnet-latest");
+    /// Claude 3.5 Haiku
+    pub const CLAUDE_3_5_HAIKU: ModelId = ModelId::known("claude-3-5-haiku-latest");
+    /// Claude 3 Opus
+    pub const CLAUDE_3_OPUS: ModelId = ModelId::known("claude-3-opus-20240229");
+    /// Claude 3 Sonnet
+    pub const CLAUDE_3_SONNET: ModelId = ModelId::known("claude-3-sonnet-20240229");
+    /// Claude 3 Haiku
+    pub const CLAUDE_3_HAIKU: ModelId = ModelId::known("claude-3-haiku-20240307");
+    /// GPT-4o
+    pub const GPT_4O: ModelId = ModelId::known("gpt-4o");
+    /// GPT-4o mini
+    pub const GPT_4O_MINI: ModelId = ModelId::known("gpt-4o-mini");
+    /// GPT-4.1
+    pub const GPT_4_1: ModelId = ModelId::known("gpt-4.1");
+    /// GPT-4.1 mini
+    pub const GPT_4_1_MINI: ModelId = ModelId::known("gpt-4.1-mini");
+    /// GPT-4 Turbo
+    pub const GPT_4_TURBO: ModelId = ModelId::known("gpt-4-turbo");
+    /// GPT-4
+    pub const GPT_4: ModelId = ModelId::known("gpt-4");
+    /// GPT-3.5 Turbo
+    pub const GPT_3_5_TURBO: ModelId = ModelId::known("gpt-3.5-turbo");
+    /// o1 reasoning model
+    pub const O1: ModelId = ModelId::known("o1");
+    /// o3-mini reasoning model
+    pub const O3_MINI: ModelId = ModelId::known("o3-mini");
+
+    /// All models with a constant, used for typo detection
+    pub const KNOWN: &'static [ModelId] = &[
+        Self::CLAUDE_OPUS_4_1,
+        Self::CLAUDE_OPUS_4_0,
+        Self::CLAUDE_SONNET_4_5,
+        Self::CLAUDE_SONNET_4_0,
+        Self::CLAUDE_HAIKU_4_5,
+        Self::CLAUDE_3_7_SONNET,
+        Self::CLAUDE_3_5_HAIKU,
+        Self::CLAUDE_3_OPUS,
+        Self::CLAUDE_3_SONNET,
+        Self::CLAUDE_3_HAIKU,
+        Self::GPT_4O,
+        Self::GPT_4O_MINI,
+        Self::GPT_4_1,
+        Self::GPT_4_1_MINI,
+        Self::GPT_4_TURBO,
+        Self::GPT_4,
+        Self::GPT_3_5_TURBO,
+        Self::O1,
+        Self::O3_MINI,
+    ];
+
+    const fn known(name: &'static str) -> Self {
+        Self(Cow::Borrowed(name))
+    }
+
+    /// Create a model ID without typo checking
+    ///
+    /// Use this for fine-tuned models, self-hosted models, or model names
+    /// that happen to look like a misspelling of a known model.
+    pub fn custom(name: impl Into<String>) -> Self {
+        Self(Cow::Owned(name.into()))
+    }
+
+    /// The model name as sent to the API
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns true if this is one of the [`ModelId::KNOWN`] models
+    pub fn is_known(&self) -> bool {
+        Self::KNOWN
+            .iter()
+            .any(|known| known.as_str() == self.as_str())
+    }
+
+    /// Name of the provider that serves this model, if recognizable
+    pub fn provider(&self) -> Option<&'static str> {
+        let name = self.as_str();
+        if name.starts_with("claude-") {
+            Some("anthropic")
+        } else if name.starts_with("gpt-")
+            || name.starts_with("o1")
+            || name.starts_with("o3")
+            || name.starts_with("ft:")
+        {
+            Some("openai")
+        } else {
+            None
+        }
+    }
+
+    /// Find the known model this name most likely misspells
+    fn suggest(name: &str) -> Option<&'static ModelId> {
+        Self::KNOWN
+            .iter()
+            .filter(|known| {
+                let known = known.as_str();
+                if known.eq_ignore_ascii_case(name) {
+                    return true;
+                }
+                // Names that only differ in digits are treated as a different
+                // version (e.g. a newer release), not a typo
+                edit_distance(known, name) <= 2 && strip_digits(known) != strip_digits(name)
+            })
+            .min_by_key(|known| edit_distance(known.as_str(), name))
+    }
+}
+
+impl FromStr for ModelId {
+    type Err = AgentError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AgentError::Config("Model name cannot be empty".to_string()));
+        }
+
+        if let Some(known) = Self::KNOWN.iter().find(|known| known.as_str() == name) {
+            return Ok(known.clone());
+        }
+
+        if let Some(suggestion) = Self::suggest(name) {
+            return Err(AgentError::Config(format!(
+                "Unknown model '{}'. Did you mean '{}'? Use ModelId::custom to skip this check",
+                name, suggestion
+            )));
+        }
+
+        Ok(Self::custom(name))
+    }
+}
+
+impl fmt::Display for ModelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ModelId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<ModelId> for String {
+    fn from(model: ModelId) -> Self {
+        model.0.into_owned()
+    }
+}
+
+/// A model recorded in a [`ModelRegistry`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredModel {
+    /// The model's identifier
+    pub id: ModelId,
+    /// Name of the provider that serves the model
+    pub provider: String,
+    /// Model this one was derived from, e.g. the base of a fine-tune
+    pub base_model: Option<ModelId>,
+}
+
+impl RegisteredModel {
+    /// Create an entry for a model served by the given provider
+    pub fn new(id: ModelId, provider: impl Into<String>) -> Self {
+        Self {
+            id,
+            provider: provider.into(),
+            base_model: None,
+        }
+    }
+
+    /// Record the model this one was derived from
+    pub fn with_base_model(mut self, base_model: ModelId) -> Self {
+        self.base_model = Some(base_model);
+        self
+    }
+}
+
+/// Registry of models created at runtime
+///
+/// Complements the built-in [`ModelId::KNOWN`] list with models that only
+/// exist for one account, such as fine-tunes. The registry is thread-safe
+/// and can be shared behind an `Arc`.
+#[derive(Debug, Default)]
+pub struct ModelRegistry {
+    models: RwLock<HashMap<String, RegisteredModel>>,
+}
+
+impl ModelRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a model entry
+    pub fn register(&self, model: RegisteredModel) {
+        self.models
+            .write()
+            .unwrap()
+            .insert(model.id.to_string(), model);
+    }
+
+    /// Look up a model by name
+    pub fn get(&self, name: &str) -> Option<RegisteredModel> {
+        self.models.read().unwrap().get(name).cloned()
+    }
+
+    /// Returns true if a model with this name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.models.read().unwrap().contains_key(name)
+    }
+
+    /// Remove a model, returning its entry if it was registered
+    pub fn remove(&self, name: &str) -> Option<RegisteredModel> {
+        self.models.write().unwrap().remove(name)
+    }
+
+    /// All registered models, sorted by name
+    pub fn list(&self) -> Vec<RegisteredModel> {
+        let mut models: Vec<_> = self.models.read().unwrap().values().cloned().collect();
+        models.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+        models
+    }
+}
+
+fn strip_digits(s: &str) -> String {
+    s.chars().filter(|c| !c.is_ascii_digit()).collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_model() {
+        let model: ModelId = "gpt-4o".parse().unwrap();
+        assert_eq!(model, ModelId::GPT_4O);
+        assert!(model.is_known());
+        assert_eq!(model.provider(), Some("openai"));
+    }
+
+    #[test]
+    fn test_typo_is_rejected_with_suggestion() {
+        let err = "claude-sonet-4-5".parse::<ModelId>().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Did you mean 'claude-sonnet-4-5'?")
+        );
+
+        let err = "gpt-4-o".parse::<ModelId>().unwrap_err();
+        assert!(err.to_string().contains("Did you mean 'gpt-4o'?"));
+
+        assert!("GPT-4o".parse::<ModelId>().is_err());
+    }
+
+    #[test]
+    fn test_new_versions_and_snapshots_are_accepted() {
+        let model: ModelId = "claude-sonnet-4-6".parse().unwrap();
+        assert!(!model.is_known());
+        assert_eq!(model.provider(), Some("anthropic"));
+
+        assert!("gpt-4o-2024-08-06".parse::<ModelId>().is_ok());
+        assert!("llama3:8b".parse::<ModelId>().is_ok());
+    }
+
+    #[test]
+    fn test_empty_name_is_rejected() {
+        assert!("  ".parse::<ModelId>().is_err());
+    }
+
+    #[test]
+    fn test_custom_skips_checks() {
+        let model = ModelId::custom("gpt-4-o");
+        assert_eq!(model.as_str(), "gpt-4-o");
+        assert_eq!(String::from(model), "gpt-4-o");
+    }
+
+    #[test]
+    fn test_fine_tuned_model_provider() {
+        let model: ModelId = "ft:gpt-4o-mini-2024-07-18:acme::abc123".parse().unwrap();
+        assert_eq!(model.provider(), Some("openai"));
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = ModelRegistry::new();
+        let id = ModelId::custom("ft:gpt-4o-mini:acme::abc");
+        registry.register(
+            RegisteredModel::new(id.clone(), "openai").with_base_model(ModelId::GPT_4O_MINI),
+        );
+
+        assert!(registry.contains("ft:gpt-4o-mini:acme::abc"));
+        let entry = registry.get(id.as_str()).unwrap();
+        assert_eq!(entry.base_model, Some(ModelId::GPT_4O_MINI));
+        assert_eq!(registry.list().len(), 1);
+
+        assert!(registry.remove(id.as_str()).is_some());
+        assert!(registry.list().is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("gpt-4o", "gpt-4o"), 0);
+        assert_eq!(edit_distance("gpt-4o", "gpt-4-o"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
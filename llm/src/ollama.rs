@@ -0,0 +1,343 @@
+//! Ollama local model provider.
+
+use agent_core::{AgentError, Message, Result, Role};
+use async_trait::async_trait;
+use communication::ApiClient;
+use futures::StreamExt;
+use config::LLMConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::structured::repair_loop;
+use crate::{LLMProvider, StreamEvent, StructuredOutput, TokenStream, TokenUsage};
+
+/// Default Ollama server URL
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Request body for the Ollama chat endpoint
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    /// JSON schema the output is constrained to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a Value>,
+    options: ChatOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatOptions {
+    temperature: f32,
+    num_predict: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+}
+
+/// One line of a streamed chat response
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    /// Prompt tokens, reported on the final line
+    #[serde(default)]
+    prompt_eval_count: Option<usize>,
+    /// Generated tokens, reported on the final line
+    #[serde(default)]
+    eval_count: Option<usize>,
+    /// Set instead of a message when generation fails mid-stream
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// LLM provider for models served by a local Ollama instance
+///
+/// Structured requests pass the schema as Ollama's `format`, so decoding is
+/// constrained to JSON matching the schema. Streaming requests decode
+/// Ollama's JSON lines as they arrive. Ollama does not accept GBNF
+/// grammars; [`StructuredOutput::grammar`] is ignored.
+pub struct OllamaProvider {
+    model: String,
+    temperature: f32,
+    max_tokens: usize,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OllamaProvider {
+    /// Create a new Ollama provider from configuration
+    ///
+    /// The API key is not used.
+    pub fn new(config: &LLMConfig) -> Result<Self> {
+        Ok(Self {
+            model: config.model.clone(),
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        })
+    }
+
+    /// Set the Ollama server URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn request<'a>(
+        &'a self,
+        messages: &[Message],
+        format: Option<&'a Value>,
+        stream: bool,
+    ) -> ChatRequest<'a> {
+        ChatRequest {
+            model: &self.model,
+            messages: messages
+                .iter()
+                .map(|message| ChatMessage {
+                    role: role_name(&message.role).to_string(),
+                    content: message.content.clone(),
+                })
+                .collect(),
+            stream,
+            format,
+            options: ChatOptions {
+                temperature: self.temperature,
+                num_predict: self.max_tokens,
+            },
+        }
+    }
+
+    async fn chat(&self, messages: &[Message], format: Option<&Value>) -> Result<String> {
+        let url = format!("{}/api/chat", self.base_url);
+        let request = self.request(messages, format, false);
+        let response: ChatResponse = self.client.post_json(&url, &request).await?;
+        Ok(response.message.content)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.chat(messages, None).await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        repair_loop(messages, output, |conversation| async move {
+            self.chat(&conversation, Some(&output.schema)).await
+        })
+        .await
+    }
+
+    /// Streams the JSON lines Ollama sends with `stream: true`
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let url = format!("{}/api/chat", self.base_url);
+        let request = self.request(messages, None, true);
+        let chunks = self
+            .client
+            .post_json_stream::<_, ChatChunk>(&url, &request)
+            .await?;
+
+        let events = chunks.flat_map(|chunk| {
+            let events = match chunk {
+                Ok(chunk) => chunk_events(chunk),
+                Err(e) => vec![Err(e)],
+            };
+            futures::stream::iter(events)
+        });
+        Ok(Box::pin(events))
+    }
+}
+
+/// The events carried by one streamed line
+fn chunk_events(chunk: ChatChunk) -> Vec<Result<StreamEvent>> {
+    if let Some(error) = chunk.error {
+        return vec![Err(AgentError::LLMProvider(error))];
+    }
+    let mut events = Vec::new();
+    if let Some(message) = chunk.message
+        && !message.content.is_empty()
+    {
+        events.push(Ok(StreamEvent::Text(message.content)));
+    }
+    if chunk.done
+        && let (Some(input_tokens), Some(output_tokens)) =
+            (chunk.prompt_eval_count, chunk.eval_count)
+    {
+        events.push(Ok(StreamEvent::Usage(TokenUsage {
+            input_tokens,
+            output_tokens,
+        })));
+    }
+    events
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(base_url: String) -> OllamaProvider {
+        let config = LLMConfig {
+            provider: "ollama".to_string(),
+            model: "llama3.2".to_string(),
+            api_key: String::new(),
+            temperature: 0.2,
+            max_tokens: 256,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+        OllamaProvider::new(&config)
+            .unwrap()
+            .with_base_url(base_url)
+    }
+
+    fn reply(content: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "model": "llama3.2",
+            "message": {"role": "assistant", "content": content},
+            "done": true
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_send_message() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "llama3.2",
+                "stream": false,
+                "messages": [{"role": "user", "content": "Hi"}],
+                "options": {"num_predict": 256}
+            })))
+            .respond_with(reply("Hello!"))
+            .mount(&mock_server)
+            .await;
+
+        let response = provider(mock_server.uri())
+            .send_message(&[Message::user("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(response, "Hello!");
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_sends_schema_as_format() {
+        let mock_server = MockServer::start().await;
+        let schema = json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city"]
+        });
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({"format": schema})))
+            .respond_with(reply(r#"{"city": "Oslo"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let value = provider(mock_server.uri())
+            .send_structured(
+                &[Message::user("Capital of Norway?")],
+                &StructuredOutput::new(schema),
+            )
+            .await
+            .unwrap();
+        assert_eq!(value["city"], "Oslo");
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_decodes_json_lines() {
+        let mock_server = MockServer::start().await;
+        let body = concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"lo!\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,",
+            "\"prompt_eval_count\":12,\"eval_count\":2}\n",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let events: Vec<StreamEvent> = provider(mock_server.uri())
+            .stream_message(&[Message::user("Hi")])
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("Hel".to_string()),
+                StreamEvent::Text("lo!".to_string()),
+                StreamEvent::Usage(TokenUsage {
+                    input_tokens: 12,
+                    output_tokens: 2,
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_reports_errors_after_text() {
+        let mock_server = MockServer::start().await;
+        let body = concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":false}\n",
+            "{\"error\":\"model unloaded\"}\n",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let events: Vec<Result<StreamEvent>> = provider(mock_server.uri())
+            .stream_message(&[Message::user("Hi")])
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap(), &StreamEvent::Text("Hi".to_string()));
+        assert!(events[1].as_ref().unwrap_err().to_string().contains("model unloaded"));
+    }
+}
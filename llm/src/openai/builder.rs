@@ -0,0 +1,353 @@
+//! Fluent builder for [`OpenAIProvider`].
+
+use agent_core::{AgentError, Result};
+use communication::{ApiClient, RetryPolicy};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+use crate::{MaxTokens, ModelId, ModelRules, Temperature, TopP};
+
+use super::{AzureDeployment, OpenAIProvider};
+
+/// Default OpenAI API base URL
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Builder for [`OpenAIProvider`]
+///
+/// Only the API key is required; everything else falls back to a sensible
+/// default (`gpt-3.5-turbo`, temperature 0.7, 2000 max tokens, 30 second
+/// timeout, 3 attempts with jittered backoff on rate limits and server errors).
+///
+/// # Example
+///
+/// ```
+/// use communication::RetryPolicy;
+/// use llm::{ModelId, OpenAIProvider};
+/// use std::time::Duration;
+///
+/// let provider = OpenAIProvider::builder()
+///     .api_key("sk-...")
+///     .model(ModelId::GPT_4O)
+///     .organization("org-123")
+///     .project("proj_abc")
+///     .timeout(Duration::from_secs(60))
+///     .retry_policy(RetryPolicy::new(3))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenAIProviderBuilder {
+    api_key: Option<String>,
+    model: ModelId,
+    temperature: Temperature,
+    top_p: Option<TopP>,
+    max_tokens: MaxTokens,
+    base_url: String,
+    organization: Option<String>,
+    project: Option<String>,
+    azure: Option<AzureDeployment>,
+    headers: Vec<(String, String)>,
+    body_fields: Map<String, Value>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    model_rules: Option<ModelRules>,
+}
+
+impl OpenAIProviderBuilder {
+    /// Create a builder with default settings
+    pub fn new() -> Self {
+        Self {
+            api_key: None,
+            model: ModelId::GPT_3_5_TURBO,
+            temperature: Temperature::default(),
+            top_p: None,
+            max_tokens: MaxTokens::default(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            organization: None,
+            project: None,
+            azure: None,
+            headers: Vec::new(),
+            body_fields: Map::new(),
+            timeout: ApiClient::new().timeout(),
+            retry_policy: RetryPolicy::default(),
+            model_rules: None,
+        }
+    }
+
+    /// Set the API key (required)
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set the model
+    ///
+    /// Use a [`ModelId`] constant, or parse a name with `str::parse` to
+    /// catch typos before any request is sent.
+    pub fn model(mut self, model: ModelId) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Set the sampling temperature
+    pub fn temperature(mut self, temperature: Temperature) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Set nucleus sampling; unset by default, leaving the provider default
+    pub fn top_p(mut self, top_p: TopP) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the maximum number of tokens to generate
+    pub fn max_tokens(mut self, max_tokens: MaxTokens) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set the API base URL, e.g. for a proxy or an OpenAI-compatible server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the organization ID sent in the `OpenAI-Organization` header
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    /// Set the project ID sent in the `OpenAI-Project` header
+    ///
+    /// Requests are then billed to and rate limited by this project, which
+    /// matters for keys that have access to several projects.
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Send requests to an Azure OpenAI deployment instead of the OpenAI API
+    ///
+    /// Requests go to
+    /// `{endpoint}/openai/deployments/{deployment}/chat/completions` with the
+    /// `api-version` query parameter, and the API key is sent in the
+    /// `api-key` header. The model still selects the request shaping rules,
+    /// so set it to the model the deployment serves.
+    pub fn azure(
+        mut self,
+        endpoint: impl Into<String>,
+        deployment: impl Into<String>,
+        api_version: impl Into<String>,
+    ) -> Self {
+        self.base_url = endpoint.into().trim_end_matches('/').to_string();
+        self.azure = Some(AzureDeployment {
+            deployment: deployment.into(),
+            api_version: api_version.into(),
+        });
+        self
+    }
+
+    /// Add a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add a field sent in the body of every chat completion request
+    ///
+    /// For OpenAI-compatible APIs that accept fields OpenAI does not, e.g.
+    /// OpenRouter's `provider` routing preferences. Don't use it for fields
+    /// the provider already sends, such as `model` or `messages`.
+    pub fn body_field(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.body_fields.insert(name.into(), value);
+        self
+    }
+
+    /// Set the request timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the retry policy for transient failures
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Override the request shaping rules, which are otherwise looked up
+    /// from the model name
+    ///
+    /// Useful for fine-tunes and OpenAI-compatible servers whose model names
+    /// don't match a known family.
+    pub fn model_rules(mut self, model_rules: ModelRules) -> Self {
+        self.model_rules = Some(model_rules);
+        self
+    }
+
+    /// Build the provider
+    ///
+    /// # Errors
+    /// Returns an error if no API key was set.
+    pub fn build(self) -> Result<OpenAIProvider> {
+        let api_key = self
+            .api_key
+            .ok_or_else(|| AgentError::Config("OpenAI API key is required".to_string()))?;
+
+        let model: String = self.model.into();
+        let rules = self
+            .model_rules
+            .unwrap_or_else(|| ModelRules::for_model(&model));
+        Ok(OpenAIProvider {
+            api_key,
+            model,
+            temperature: self.temperature.clamp_for("openai").get(),
+            top_p: self.top_p.map(TopP::get),
+            max_tokens: self.max_tokens.get(),
+            base_url: self.base_url,
+            organization: self.organization,
+            project: self.project,
+            azure: self.azure,
+            headers: self.headers,
+            body_fields: self.body_fields,
+            retry_policy: self.retry_policy,
+            rules,
+            client: ApiClient::with_timeout(self.timeout),
+        })
+    }
+}
+
+impl Default for OpenAIProviderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LLMProvider;
+    use agent_core::Message;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_build_requires_api_key() {
+        let result = OpenAIProviderBuilder::new().build();
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+
+    #[test]
+    fn test_build_with_defaults() {
+        let provider = OpenAIProvider::builder().api_key("key").build().unwrap();
+        assert_eq!(provider.model, "gpt-3.5-turbo");
+        assert_eq!(provider.base_url, DEFAULT_BASE_URL);
+        assert_eq!(provider.retry_policy, RetryPolicy::default());
+        assert_eq!(provider.client.timeout(), Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_custom_base_url_and_headers() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("Authorization", "Bearer test-key"))
+            .and(header("OpenAI-Organization", "org-123"))
+            .and(header("OpenAI-Project", "proj_abc"))
+            .and(header("X-Custom", "yes"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-3.5-turbo",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi!"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .base_url(format!("{}/v1/", mock_server.uri()))
+            .organization("org-123")
+            .project("proj_abc")
+            .header("X-Custom", "yes")
+            .build()
+            .unwrap();
+
+        let response = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap();
+        assert_eq!(response, "Hi!");
+    }
+
+    #[tokio::test]
+    async fn test_azure_deployment_url_and_auth() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/chat-prod/chat/completions"))
+            .and(query_param("api-version", "2024-10-21"))
+            .and(header("api-key", "azure-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi from Azure"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIProvider::builder()
+            .api_key("azure-key")
+            .model(ModelId::GPT_4O)
+            .azure(format!("{}/", mock_server.uri()), "chat-prod", "2024-10-21")
+            .build()
+            .unwrap();
+
+        let response = provider
+            .send_message(&[Message::user("Hello")])
+            .await
+            .unwrap();
+        assert_eq!(response, "Hi from Azure");
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(
+            !requests[0]
+                .headers
+                .keys()
+                .any(|name| name.as_str() == "authorization")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_is_applied() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("Unavailable"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .base_url(mock_server.uri())
+            .retry_policy(RetryPolicy::new(2).with_initial_delay(Duration::from_millis(1)))
+            .build()
+            .unwrap();
+
+        assert!(provider.send_message(&[Message::user("Hello")]).await.is_err());
+    }
+}
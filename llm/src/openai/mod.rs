@@ -1,20 +1,52 @@
+pub mod builder;
 pub mod types;
 
-use agent_core::{AgentError, Message, Result, Role};
+use agent_core::{AgentError, Message, Result, Role, ToolDefinition, ToolUse, ToolUseResponse};
 use async_trait::async_trait;
-use communication::ApiClient;
+use communication::{
+    ApiClient, RetryPolicy, check_status, decode_sse_stream, request_error, with_request_headers,
+    with_retry_policy,
+};
 use config::LLMConfig;
+use futures::StreamExt;
+use serde_json::{Value, json};
 
-use crate::LLMProvider;
+use crate::tool_use::parse_arguments;
+use crate::structured::send_completion_with_repair;
+use crate::{
+    CompletionResponse, FinishReason, LLMProvider, MaxTokens, ModelRules, StreamEvent,
+    StructuredOutput, Temperature, TokenStream, TokenUsage, ToolConfig,
+};
 
-pub use types::{ChatCompletionRequest, ChatCompletionResponse, OpenAIMessage};
+pub use builder::OpenAIProviderBuilder;
+pub use types::{ChatCompletionRequest, ChatCompletionResponse, OpenAIContent, OpenAIMessage};
+
+/// Azure OpenAI deployment requests are sent to instead of the OpenAI API
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AzureDeployment {
+    deployment: String,
+    api_version: String,
+}
 
 /// OpenAI LLM provider implementation
+///
+/// Also serves Azure OpenAI deployments, configured with
+/// [`OpenAIProviderBuilder::azure`] or the `azure` section of the
+/// configuration.
 pub struct OpenAIProvider {
     api_key: String,
     model: String,
     temperature: f32,
+    top_p: Option<f32>,
     max_tokens: usize,
+    base_url: String,
+    organization: Option<String>,
+    project: Option<String>,
+    azure: Option<AzureDeployment>,
+    headers: Vec<(String, String)>,
+    body_fields: serde_json::Map<String, Value>,
+    retry_policy: RetryPolicy,
+    rules: ModelRules,
     client: ApiClient,
 }
 
@@ -26,14 +58,44 @@ impl OpenAIProvider {
     ///
     /// # Returns
     /// * `Result<Self>` - New provider instance or error
+    ///
+    /// With `azure` settings, requests go to the Azure deployment, which
+    /// defaults to the model name.
+    ///
+    /// # Errors
+    /// Returns an error if the model name looks like a typo of a known model,
+    /// if the temperature or max tokens are out of range, or if the azure
+    /// provider has no Azure settings
     pub fn new(config: &LLMConfig) -> Result<Self> {
-        Ok(Self {
-            api_key: config.api_key.clone(),
-            model: config.model.clone(),
-            temperature: config.temperature,
-            max_tokens: config.max_tokens,
-            client: ApiClient::new(),
-        })
+        let mut builder = Self::builder()
+            .api_key(config.api_key.clone())
+            .model(config.model.parse()?)
+            .temperature(Temperature::new(config.temperature)?)
+            .max_tokens(MaxTokens::new(config.max_tokens)?);
+        if let Some(organization) = &config.organization {
+            builder = builder.organization(organization.clone());
+        }
+        if let Some(project) = &config.project {
+            builder = builder.project(project.clone());
+        }
+        match &config.azure {
+            Some(azure) => {
+                let deployment = azure.deployment.as_ref().unwrap_or(&config.model);
+                builder = builder.azure(&azure.endpoint, deployment, &azure.api_version);
+            }
+            None if config.provider == "azure" => {
+                return Err(AgentError::Config(
+                    "The azure provider requires an azure endpoint".to_string(),
+                ));
+            }
+            None => {}
+        }
+        builder.build()
+    }
+
+    /// Create a builder for configuring a provider without an `LLMConfig`
+    pub fn builder() -> OpenAIProviderBuilder {
+        OpenAIProviderBuilder::new()
     }
 
     /// Convert framework Message to OpenAI message format
@@ -44,9 +106,33 @@ impl OpenAIProvider {
             Role::Assistant => "assistant",
         };
 
+        let content = if !message.is_multimodal() {
+            types::OpenAIContent::Text(message.content.clone())
+        } else {
+            let mut parts = vec![types::OpenAIContentPart::Text {
+                text: message.content.clone(),
+            }];
+            parts.extend(message.images.iter().map(|image| {
+                types::OpenAIContentPart::ImageUrl {
+                    image_url: types::OpenAIImageUrl {
+                        url: image.to_url(),
+                    },
+                }
+            }));
+            parts.extend(message.attachments.iter().map(|file| {
+                types::OpenAIContentPart::File {
+                    file: types::OpenAIFileId {
+                        file_id: file.id.clone(),
+                    },
+                }
+            }));
+            types::OpenAIContent::Parts(parts)
+        };
+
         types::OpenAIMessage {
             role: role.to_string(),
-            content: message.content.clone(),
+            content,
+            tool_calls: Vec::new(),
         }
     }
 
@@ -54,76 +140,577 @@ impl OpenAIProvider {
     fn convert_messages(messages: &[Message]) -> Vec<types::OpenAIMessage> {
         messages.iter().map(Self::convert_message).collect()
     }
-}
-
 
-#[async_trait]
-impl LLMProvider for OpenAIProvider {
-    async fn send_message(&self, messages: &[Message]) -> Result<String> {
-        // Convert framework messages to OpenAI format
-        let openai_messages = Self::convert_messages(messages);
+    /// The request shaping rules for this provider's model
+    pub fn model_rules(&self) -> &ModelRules {
+        &self.rules
+    }
 
-        // Build the request
-        let request = ChatCompletionRequest {
+    /// Build a chat completion request for the given messages, shaped for
+    /// the model
+    fn build_request(&self, messages: &[Message], stream: bool) -> ChatCompletionRequest {
+        let (temperature, top_p) = self.rules.sampling(Some(self.temperature), self.top_p);
+        ChatCompletionRequest {
             model: self.model.clone(),
-            messages: openai_messages,
-            temperature: self.temperature,
-            max_tokens: self.max_tokens,
-        };
+            messages: Self::convert_messages(&self.rules.shape_messages(messages)),
+            temperature,
+            top_p,
+            max_tokens: self.rules.cap_max_tokens(self.max_tokens),
+            stream,
+            stream_options: stream.then_some(types::StreamOptions {
+                include_usage: true,
+            }),
+            tools: Vec::new(),
+            tool_choice: None,
+            parallel_tool_calls: None,
+            extra: self.body_fields.clone(),
+        }
+    }
+
+    /// Send a single chat completion request to the API
+    async fn send_request(&self, request: &ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        let response = self.post_request(request).await?;
 
-        // Call OpenAI API
-        let url = "https://api.openai.com/v1/chat/completions";
-        
+        // Deserialize the response
+        response.json().await.map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize OpenAI response: {}", e))
+        })
+    }
+
+    /// POST a chat completion request and check the response status
+    async fn post_request(&self, request: &ChatCompletionRequest) -> Result<reqwest::Response> {
         // Create a custom client with authorization header
         let client = reqwest::Client::new();
-        let response = client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
+        let mut builder = match &self.azure {
+            Some(azure) => {
+                let url = format!(
+                    "{}/openai/deployments/{}/chat/completions",
+                    self.base_url, azure.deployment
+                );
+                with_request_headers(client.post(&url))
+                    .query(&[("api-version", &azure.api_version)])
+                    .header("api-key", &self.api_key)
+            }
+            None => {
+                let url = format!("{}/chat/completions", self.base_url);
+                with_request_headers(client.post(&url))
+                    .header("Authorization", format!("Bearer {}", self.api_key))
+            }
+        }
+        .header("Content-Type", "application/json");
+        if let Some(organization) = &self.organization {
+            builder = builder.header("OpenAI-Organization", organization);
+        }
+        if let Some(project) = &self.project {
+            builder = builder.header("OpenAI-Project", project);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder
+            .json(request)
             .timeout(self.client.timeout())
             .send()
             .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AgentError::LLMProvider(format!("OpenAI API request timeout: {}", e))
-                } else if e.is_connect() {
-                    AgentError::LLMProvider(format!("OpenAI API connection error: {}", e))
-                } else if e.status() == Some(reqwest::StatusCode::UNAUTHORIZED) {
-                    AgentError::LLMProvider("OpenAI API authentication failed: Invalid API key".to_string())
-                } else if e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
-                    AgentError::LLMProvider("OpenAI API rate limit exceeded".to_string())
-                } else {
-                    AgentError::LLMProvider(format!("OpenAI API request failed: {}", e))
-                }
-            })?;
+            .map_err(|e| request_error("OpenAI API", e))?;
 
-        // Check for HTTP errors
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read error response".to_string());
-            
-            return Err(AgentError::LLMProvider(format!(
-                "OpenAI API HTTP {} error: {}",
-                status, error_text
-            )));
-        }
+        check_status("OpenAI API", response).await
+    }
 
-        // Deserialize the response
-        let completion: ChatCompletionResponse = response.json().await.map_err(|e| {
-            AgentError::LLMProvider(format!("Failed to deserialize OpenAI response: {}", e))
+    /// Send a chat completion request, retrying transient failures, and
+    /// return its first choice
+    async fn complete(&self, request: &ChatCompletionRequest) -> Result<CompletionResponse> {
+        let completion = with_retry_policy(|| self.send_request(request), &self.retry_policy).await?;
+        let choice = completion.choices.first().ok_or_else(|| {
+            AgentError::LLMProvider("OpenAI response contained no choices".to_string())
         })?;
 
+        let mut response = CompletionResponse::new(
+            choice.message.content.text(),
+            FinishReason::from_openai(choice.finish_reason.as_deref()),
+        )
+        .with_model(completion.model.clone());
+        response.usage = completion.usage.as_ref().map(|usage| TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        });
+        Ok(response)
+    }
+
+    /// Send a chat completion request whose output must match `schema`,
+    /// using the JSON schema `response_format` of OpenAI-compatible APIs
+    /// that support it
+    pub(crate) async fn send_completion_with_schema(
+        &self,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<CompletionResponse> {
+        let mut request = self.build_request(messages, false);
+        request.extra.insert(
+            "response_format".to_string(),
+            json!({
+                "type": "json_schema",
+                "json_schema": {"name": "response", "schema": schema}
+            }),
+        );
+        self.complete(&request).await
+    }
+}
+
+/// Convert one server-sent event from a streamed completion into stream events
+///
+/// Content chunks yield text; the final chunk carries usage and no choices.
+fn parse_chunk(data: &str) -> Vec<Result<StreamEvent>> {
+    let chunk = match serde_json::from_str::<types::ChatCompletionChunk>(data) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            return vec![Err(AgentError::LLMProvider(format!(
+                "Failed to deserialize OpenAI stream chunk: {}",
+                e
+            )))];
+        }
+    };
+
+    let text = chunk
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.delta.content)
+        .filter(|text| !text.is_empty())
+        .map(StreamEvent::Text);
+    let usage = chunk.usage.map(|usage| {
+        StreamEvent::Usage(TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        })
+    });
+    text.into_iter().chain(usage).map(Ok).collect()
+}
+
+
+#[async_trait]
+impl LLMProvider for OpenAIProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        // Convert framework messages to OpenAI format and build the request
+        let request = self.build_request(messages, false);
+
+        // Call OpenAI API, retrying transient failures per the configured policy
+        let completion = with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+
         // Extract the response text from choices[0].message.content
         completion
             .choices
             .first()
-            .map(|choice| choice.message.content.clone())
+            .map(|choice| choice.message.content.text())
             .ok_or_else(|| {
                 AgentError::LLMProvider("OpenAI response contained no choices".to_string())
             })
     }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let request = self.build_request(messages, false);
+        self.complete(&request).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        send_completion_with_repair(self, messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let functions = tools
+            .iter()
+            .map(|tool| {
+                types::OpenAITool::function(&tool.name, &tool.description, tool.parameters.clone())
+            })
+            .collect();
+        let request = self.build_request(messages, false).with_tools(functions, config);
+
+        let completion = with_retry_policy(|| self.send_request(&request), &self.retry_policy).await?;
+        let message = completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| {
+                AgentError::LLMProvider("OpenAI response contained no choices".to_string())
+            })?;
+
+        Ok(ToolUseResponse {
+            text: message.content.text(),
+            tool_uses: message
+                .tool_calls
+                .into_iter()
+                .map(|call| ToolUse {
+                    id: call.id,
+                    name: call.function.name,
+                    arguments: parse_arguments(&call.function.arguments),
+                })
+                .collect(),
+        })
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let request = self.build_request(messages, true);
+
+        // Only the initial request is retried; a stream is not resumed mid-way
+        let response = with_retry_policy(|| self.post_request(&request), &self.retry_policy).await?;
+
+        let events = decode_sse_stream(response.bytes_stream())
+            .take_while(|event| {
+                let done = matches!(event, Ok(event) if event.data == "[DONE]");
+                futures::future::ready(!done)
+            })
+            .flat_map(|event| {
+                futures::stream::iter(match event {
+                    Ok(event) => parse_chunk(&event.data),
+                    Err(e) => vec![Err(e)],
+                })
+            });
+        Ok(Box::pin(events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ModelId, StopCondition, StopReason, ToolChoice, ToolConfig, TopP, collect_text,
+        with_stop_conditions,
+    };
+    use agent_core::{FileRef, ImageContent};
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sse_body(chunks: &[&str]) -> String {
+        let mut body: String = chunks
+            .iter()
+            .map(|text| {
+                format!(
+                    "data: {}\n\n",
+                    serde_json::json!({"choices": [{"index": 0, "delta": {"content": text}}]})
+                )
+            })
+            .collect();
+        body.push_str(
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":3,\"total_tokens\":12}}\n\n",
+        );
+        body.push_str("data: [DONE]\n\n");
+        body
+    }
+
+    async fn streaming_provider(server: &MockServer, chunks: &[&str]) -> OpenAIProvider {
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "stream": true,
+                "stream_options": {"include_usage": true}
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(sse_body(chunks)),
+            )
+            .mount(server)
+            .await;
+
+        OpenAIProvider::builder()
+            .api_key("test-key")
+            .model("gpt-4".parse().unwrap())
+            .base_url(server.uri())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_message() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, &["Hel", "lo", "!"]).await;
+
+        let stream = provider.stream_message(&[Message::user("Hi")]).await.unwrap();
+        assert_eq!(collect_text(stream).await.unwrap(), "Hello!");
+    }
+
+    #[tokio::test]
+    async fn test_send_message_stream_yields_tokens() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, &["Hel", "lo", "!"]).await;
+
+        let tokens: Vec<String> = provider
+            .send_message_stream(&[Message::user("Hi")])
+            .await
+            .unwrap()
+            .map(|token| token.unwrap())
+            .collect()
+            .await;
+        assert_eq!(tokens, vec!["Hel", "lo", "!"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_ends_with_usage() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, &["Hi"]).await;
+
+        let stream = provider.stream_message(&[Message::user("Hi")]).await.unwrap();
+        let events: Vec<_> = stream.map(|event| event.unwrap()).collect().await;
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("Hi".to_string()),
+                StreamEvent::Usage(TokenUsage {
+                    input_tokens: 9,
+                    output_tokens: 3,
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_with_stop_pattern() {
+        let server = MockServer::start().await;
+        let provider = streaming_provider(&server, &["1, 2, ", "3, 4", ", 5"]).await;
+
+        let stream = provider.stream_message(&[Message::user("Count")]).await.unwrap();
+        let mut stream =
+            with_stop_conditions(stream, vec![StopCondition::pattern("3").unwrap()]);
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event.unwrap());
+        }
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("1, 2, ".to_string()),
+                StreamEvent::Stopped(StopReason::Pattern("3".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_plain_message() {
+        let converted = OpenAIProvider::convert_message(&Message::user("Hi"));
+        assert_eq!(converted.content, types::OpenAIContent::Text("Hi".to_string()));
+    }
+
+    #[test]
+    fn test_convert_message_with_attachment() {
+        let message =
+            Message::user("Summarize").with_attachment(FileRef::new("file-1", "application/pdf"));
+        let json = serde_json::to_value(OpenAIProvider::convert_message(&message)).unwrap();
+        assert_eq!(
+            json["content"],
+            serde_json::json!([
+                {"type": "text", "text": "Summarize"},
+                {"type": "file", "file": {"file_id": "file-1"}}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_convert_message_with_images() {
+        let message = Message::user("Describe both")
+            .with_image(ImageContent::url("https://example.com/a.jpg"))
+            .with_image(ImageContent::base64("image/jpeg", "/9j/4AAQ"));
+        let json = serde_json::to_value(OpenAIProvider::convert_message(&message)).unwrap();
+        assert_eq!(
+            json["content"],
+            serde_json::json!([
+                {"type": "text", "text": "Describe both"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/a.jpg"}},
+                {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,/9j/4AAQ"}}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_tool_choice_serialization() {
+        let provider = OpenAIProvider::builder().api_key("test-key").build().unwrap();
+        let request = || provider.build_request(&[Message::user("Hi")], false);
+        let json = serde_json::to_value(request()).unwrap();
+        assert!(json.get("tools").is_none());
+        assert!(json.get("tool_choice").is_none());
+        assert!(json.get("parallel_tool_calls").is_none());
+
+        let schema = serde_json::json!({"type": "object"});
+        let tool = types::OpenAITool::function("search", "Search the web", schema);
+        let config = ToolConfig::new(ToolChoice::tool("search")).sequential();
+        let json = serde_json::to_value(request().with_tools(vec![tool.clone()], &config)).unwrap();
+        assert_eq!(json["tools"][0]["type"], "function");
+        assert_eq!(
+            json["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "search"}})
+        );
+        assert_eq!(json["parallel_tool_calls"], false);
+
+        let config = ToolConfig::new(ToolChoice::Any);
+        let json = serde_json::to_value(request().with_tools(vec![tool.clone()], &config)).unwrap();
+        assert_eq!(json["tool_choice"], "required");
+        assert!(json.get("parallel_tool_calls").is_none());
+
+        let config = ToolConfig::new(ToolChoice::None).sequential();
+        let json = serde_json::to_value(request().with_tools(vec![tool], &config)).unwrap();
+        assert_eq!(json["tool_choice"], "none");
+        assert!(json.get("parallel_tool_calls").is_none());
+    }
+
+    #[test]
+    fn test_request_is_shaped_for_model() {
+        let messages = [Message::system("Be brief."), Message::user("Hi")];
+
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .model(ModelId::GPT_4)
+            .top_p(TopP::new(0.9).unwrap())
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(provider.build_request(&messages, false)).unwrap();
+        assert_eq!(json["messages"][0]["role"], "system");
+        assert!(json.get("temperature").is_some());
+        assert!(json.get("top_p").is_some());
+
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .model(ModelId::O1)
+            .top_p(TopP::new(0.9).unwrap())
+            .model_rules(ModelRules {
+                system_messages: false,
+                ..ModelRules::for_model("o1")
+            })
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(provider.build_request(&messages, false)).unwrap();
+        assert_eq!(json["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(json["messages"][0]["content"], "Be brief.\n\nHi");
+        assert!(json.get("temperature").is_none());
+        assert!(json.get("top_p").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_completion_reports_truncation() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Once upon a"},
+                    "finish_reason": "length"
+                }],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15}
+            })))
+            .mount(&server)
+            .await;
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let completion = provider.send_completion(&[Message::user("Story")]).await.unwrap();
+        assert_eq!(
+            completion,
+            CompletionResponse::new("Once upon a", FinishReason::Length)
+                .with_usage(TokenUsage {
+                    input_tokens: 12,
+                    output_tokens: 3,
+                })
+                .with_model("gpt-4")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_errors_are_typed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "3")
+                    .set_body_string("Rate limit reached"),
+            )
+            .mount(&server)
+            .await;
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .base_url(server.uri())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+
+        let error = provider.send_message(&[Message::user("Hi")]).await.unwrap_err();
+        assert!(matches!(error, AgentError::RateLimited { .. }));
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(std::time::Duration::from_secs(3)));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_with_tools() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "tools": [{"type": "function", "function": {"name": "search"}}],
+                "tool_choice": "auto"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_abc",
+                            "type": "function",
+                            "function": {"name": "search", "arguments": "{\"q\": \"rust\"}"}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        let provider = OpenAIProvider::builder()
+            .api_key("test-key")
+            .base_url(server.uri())
+            .build()
+            .unwrap();
+
+        let tools = [ToolDefinition::new(
+            "search",
+            "Search the web",
+            serde_json::json!({"type": "object"}),
+        )];
+        let response = provider
+            .send_message_with_tools(&[Message::user("Find docs")], &tools, &ToolConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "");
+        assert_eq!(
+            response.tool_uses,
+            vec![ToolUse {
+                id: "call_abc".to_string(),
+                name: "search".to_string(),
+                arguments: serde_json::json!({"q": "rust"}),
+            }]
+        );
+    }
 }
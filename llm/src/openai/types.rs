@@ -1,6 +1,9 @@
 //! Type definitions for OpenAI API requests and responses.
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ToolChoice, ToolConfig};
 
 /// OpenAI API message format.
 ///
@@ -9,8 +12,112 @@ use serde::{Deserialize, Serialize};
 pub struct OpenAIMessage {
     /// The role of the message sender ("system", "user", or "assistant")
     pub role: String,
-    /// The content of the message
-    pub content: String,
+    /// The content of the message; empty when the model only calls tools
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub content: OpenAIContent,
+    /// Function calls requested by the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<OpenAIToolCall>,
+}
+
+/// A function call in an assistant message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAIToolCall {
+    /// Call ID, referenced by the tool result
+    pub id: String,
+    /// Call type (always "function")
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// The function and its arguments
+    pub function: OpenAIFunctionCall,
+}
+
+/// Function name and arguments inside an [`OpenAIToolCall`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAIFunctionCall {
+    /// Function name
+    pub name: String,
+    /// Arguments as a JSON-encoded string
+    pub arguments: String,
+}
+
+/// Deserialize `null` as the type's default value
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Content of an OpenAI message.
+///
+/// Plain text is sent as a string; messages with images or attached files
+/// are sent as a list of content parts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OpenAIContent {
+    /// Plain text content
+    Text(String),
+    /// Text, image and file parts
+    Parts(Vec<OpenAIContentPart>),
+}
+
+impl Default for OpenAIContent {
+    fn default() -> Self {
+        OpenAIContent::Text(String::new())
+    }
+}
+
+impl OpenAIContent {
+    /// Concatenated text of the content, ignoring file and image parts
+    pub fn text(&self) -> String {
+        match self {
+            OpenAIContent::Text(text) => text.clone(),
+            OpenAIContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    OpenAIContentPart::Text { text } => Some(text.as_str()),
+                    OpenAIContentPart::File { .. } | OpenAIContentPart::ImageUrl { .. } => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A single part of multi-part message content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpenAIContentPart {
+    /// A text part
+    Text {
+        /// The text
+        text: String,
+    },
+    /// A reference to a file uploaded through the Files API
+    File {
+        /// The referenced file
+        file: OpenAIFileId,
+    },
+    /// An image by URL, with inline images as `data:` URLs
+    ImageUrl {
+        /// The image
+        image_url: OpenAIImageUrl,
+    },
+}
+
+/// File reference inside a content part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAIFileId {
+    /// ID returned by the Files API
+    pub file_id: String,
+}
+
+/// Image reference inside a content part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAIImageUrl {
+    /// HTTP(S) or `data:` URL of the image
+    pub url: String,
 }
 
 /// Request structure for OpenAI Chat Completions API.
@@ -22,10 +129,128 @@ pub struct ChatCompletionRequest {
     pub model: String,
     /// The conversation messages
     pub messages: Vec<OpenAIMessage>,
-    /// Sampling temperature (0.0 to 2.0)
-    pub temperature: f32,
+    /// Sampling temperature (0.0 to 2.0), omitted for models without sampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
     /// Maximum number of tokens to generate
     pub max_tokens: usize,
+    /// Stream the response as server-sent events
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    /// Options for streamed responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    /// Functions the model may call
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<OpenAITool>,
+    /// How the model should use the tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<OpenAIToolChoice>,
+    /// Whether several tools may be called in one response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    /// Fields of OpenAI-compatible APIs that OpenAI does not have, such as
+    /// OpenRouter's `models` and `provider`
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+impl ChatCompletionRequest {
+    /// Offer tools to the model with the given calling settings
+    pub fn with_tools(mut self, tools: Vec<OpenAITool>, config: &ToolConfig) -> Self {
+        self.tools = tools;
+        self.tool_choice = Some(OpenAIToolChoice::from(&config.choice));
+        // OpenAI rejects parallel_tool_calls when tools are disabled
+        self.parallel_tool_calls =
+            (config.choice != ToolChoice::None && !config.parallel).then_some(false);
+        self
+    }
+}
+
+/// A function tool in a Chat Completions request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenAITool {
+    /// Tool type (always "function")
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    /// The function definition
+    pub function: OpenAIFunction,
+}
+
+impl OpenAITool {
+    /// Define a function tool
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+    ) -> Self {
+        Self {
+            tool_type: "function".to_string(),
+            function: OpenAIFunction {
+                name: name.into(),
+                description: description.into(),
+                parameters,
+            },
+        }
+    }
+}
+
+/// Function definition inside an [`OpenAITool`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenAIFunction {
+    /// Function name
+    pub name: String,
+    /// What the function does
+    pub description: String,
+    /// JSON Schema of the function arguments
+    pub parameters: Value,
+}
+
+/// The `tool_choice` field of a Chat Completions request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum OpenAIToolChoice {
+    /// "auto", "none", or "required"
+    Mode(String),
+    /// Force a call to one function
+    Function {
+        /// Choice type (always "function")
+        #[serde(rename = "type")]
+        choice_type: String,
+        /// The function to call
+        function: OpenAIFunctionName,
+    },
+}
+
+/// Function name inside a forced [`OpenAIToolChoice`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenAIFunctionName {
+    /// Function name
+    pub name: String,
+}
+
+impl From<&ToolChoice> for OpenAIToolChoice {
+    fn from(choice: &ToolChoice) -> Self {
+        match choice {
+            ToolChoice::Auto => Self::Mode("auto".to_string()),
+            ToolChoice::Any => Self::Mode("required".to_string()),
+            ToolChoice::None => Self::Mode("none".to_string()),
+            ToolChoice::Tool(name) => Self::Function {
+                choice_type: "function".to_string(),
+                function: OpenAIFunctionName { name: name.clone() },
+            },
+        }
+    }
+}
+
+/// Options for a streamed chat completion.
+#[derive(Debug, Serialize)]
+pub struct StreamOptions {
+    /// Send a final chunk with token usage for the whole request
+    pub include_usage: bool,
 }
 
 /// Response structure from OpenAI Chat Completions API.
@@ -43,6 +268,9 @@ pub struct ChatCompletionResponse {
     pub model: String,
     /// Array of completion choices (usually contains one element)
     pub choices: Vec<Choice>,
+    /// Token usage for the request
+    #[serde(default)]
+    pub usage: Option<CompletionUsage>,
 }
 
 /// Individual choice in the response.
@@ -57,3 +285,38 @@ pub struct Choice {
     /// Reason why the model stopped generating (e.g., "stop", "length")
     pub finish_reason: Option<String>,
 }
+
+/// A single chunk of a streamed chat completion.
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionChunk {
+    /// Choices updated by this chunk
+    #[serde(default)]
+    pub choices: Vec<ChunkChoice>,
+    /// Token usage, present only on the final chunk when requested
+    #[serde(default)]
+    pub usage: Option<CompletionUsage>,
+}
+
+/// Token usage for a chat completion.
+#[derive(Debug, Deserialize)]
+pub struct CompletionUsage {
+    /// Tokens in the prompt
+    pub prompt_tokens: usize,
+    /// Tokens in the generated completion
+    pub completion_tokens: usize,
+}
+
+/// Incremental update to one choice in a streamed completion.
+#[derive(Debug, Deserialize)]
+pub struct ChunkChoice {
+    /// The new content for this choice
+    pub delta: ChunkDelta,
+}
+
+/// Content added to a choice by a stream chunk.
+#[derive(Debug, Deserialize)]
+pub struct ChunkDelta {
+    /// Text appended to the message, if any
+    #[serde(default)]
+    pub content: Option<String>,
+}
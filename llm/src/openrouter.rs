@@ -0,0 +1,353 @@
+//! OpenRouter provider, routing requests across many model vendors.
+
+use agent_core::{AgentError, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::{DataCollection, LLMConfig, ProviderRouting, ProviderSort};
+use serde_json::{Map, Value, json};
+
+use crate::structured::completion_repair_loop;
+use crate::{
+    CompletionResponse, LLMProvider, MaxTokens, ModelId, ModelRules, OpenAIProvider,
+    OpenAIProviderBuilder, StructuredOutput, Temperature, TokenStream, ToolConfig,
+};
+
+/// OpenRouter API base URL
+pub const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1";
+
+/// LLM provider for models served through OpenRouter
+///
+/// OpenRouter speaks the OpenAI Chat Completions API, so requests are sent
+/// by an [`OpenAIProvider`] with OpenRouter's additions:
+///
+/// - The `HTTP-Referer` and `X-Title` headers attribute usage to the calling
+///   app
+/// - The `models` field lists fallback models OpenRouter tries in order when
+///   the main model is unavailable, rate limited or refuses the request
+/// - The `provider` field holds the [`ProviderRouting`] preferences that
+///   choose which upstream providers serve the model
+/// - Structured requests pass their schema as a JSON schema
+///   `response_format`, which OpenRouter hands to upstream providers that
+///   support it; replies are still validated and repaired
+///
+/// Model names carry their vendor, e.g. `anthropic/claude-sonnet-4`; the
+/// request shaping rules are looked up from the name after the vendor.
+pub struct OpenRouterProvider {
+    inner: OpenAIProvider,
+}
+
+impl OpenRouterProvider {
+    /// Create a provider from configuration, using the `openrouter` settings
+    /// if there are any
+    ///
+    /// # Errors
+    /// Returns an error if the model name is empty, or if the temperature or
+    /// max tokens are out of range
+    pub fn new(config: &LLMConfig) -> Result<Self> {
+        Self::with_builder(config, OpenAIProvider::builder().base_url(OPENROUTER_BASE_URL))
+    }
+
+    /// Create a provider from configuration, starting from `builder` for the
+    /// connection settings, e.g. the base URL of a proxy, a timeout or a
+    /// retry policy
+    ///
+    /// # Errors
+    /// Returns an error if the model name is empty, or if the temperature or
+    /// max tokens are out of range
+    pub fn with_builder(config: &LLMConfig, builder: OpenAIProviderBuilder) -> Result<Self> {
+        let model = config.model.trim();
+        if model.is_empty() {
+            return Err(AgentError::Config("Model name cannot be empty".to_string()));
+        }
+        let family = model.split_once('/').map_or(model, |(_, name)| name);
+
+        // Vendor-prefixed names are never known models, so skip the typo check
+        let mut builder = builder
+            .api_key(config.api_key.clone())
+            .model(ModelId::custom(model))
+            .model_rules(ModelRules::for_model(family))
+            .temperature(Temperature::new(config.temperature)?)
+            .max_tokens(MaxTokens::new(config.max_tokens)?);
+
+        if let Some(openrouter) = &config.openrouter {
+            if let Some(site_url) = &openrouter.site_url {
+                builder = builder.header("HTTP-Referer", site_url);
+            }
+            if let Some(app_name) = &openrouter.app_name {
+                builder = builder.header("X-Title", app_name);
+            }
+            if !openrouter.fallback_models.is_empty() {
+                let models = std::iter::once(model)
+                    .chain(openrouter.fallback_models.iter().map(String::as_str))
+                    .collect::<Vec<_>>();
+                builder = builder.body_field("models", json!(models));
+            }
+            if !openrouter.routing.is_default() {
+                builder = builder.body_field("provider", routing_preferences(&openrouter.routing));
+            }
+        }
+
+        Ok(Self {
+            inner: builder.build()?,
+        })
+    }
+
+    /// The request shaping rules for this provider's model
+    pub fn model_rules(&self) -> &ModelRules {
+        self.inner.model_rules()
+    }
+}
+
+/// The `provider` field of a request, holding the routing preferences that
+/// are set
+fn routing_preferences(routing: &ProviderRouting) -> Value {
+    let mut preferences = Map::new();
+    let lists = [
+        ("order", &routing.order),
+        ("only", &routing.only),
+        ("ignore", &routing.ignore),
+    ];
+    for (name, providers) in lists {
+        if !providers.is_empty() {
+            preferences.insert(name.to_string(), json!(providers));
+        }
+    }
+    if let Some(allow_fallbacks) = routing.allow_fallbacks {
+        preferences.insert("allow_fallbacks".to_string(), json!(allow_fallbacks));
+    }
+    if let Some(require_parameters) = routing.require_parameters {
+        preferences.insert("require_parameters".to_string(), json!(require_parameters));
+    }
+    if let Some(data_collection) = routing.data_collection {
+        let value = match data_collection {
+            DataCollection::Allow => "allow",
+            DataCollection::Deny => "deny",
+        };
+        preferences.insert("data_collection".to_string(), json!(value));
+    }
+    if let Some(sort) = routing.sort {
+        let value = match sort {
+            ProviderSort::Price => "price",
+            ProviderSort::Throughput => "throughput",
+            ProviderSort::Latency => "latency",
+        };
+        preferences.insert("sort".to_string(), json!(value));
+    }
+    Value::Object(preferences)
+}
+
+#[async_trait]
+impl LLMProvider for OpenRouterProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.inner.send_message(messages).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.inner.send_completion(messages).await
+    }
+
+    async fn send_structured(&self, messages: &[Message], output: &StructuredOutput) -> Result<Value> {
+        Ok(self.send_structured_completion(messages, output).await?.0)
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        completion_repair_loop(messages, output, |conversation| async move {
+            self.inner
+                .send_completion_with_schema(&conversation, &output.schema)
+                .await
+        })
+        .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner
+            .send_message_with_tools(messages, tools, config)
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.inner.stream_message(messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::OpenRouterConfig;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(model: &str, openrouter: Option<OpenRouterConfig>) -> LLMConfig {
+        LLMConfig {
+            provider: "openrouter".to_string(),
+            model: model.to_string(),
+            api_key: "or-key".to_string(),
+            temperature: 0.7,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter,
+            prompt_caching: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_carry_headers_fallbacks_and_routing() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/chat/completions"))
+            .and(header("Authorization", "Bearer or-key"))
+            .and(header("HTTP-Referer", "https://athena.example"))
+            .and(header("X-Title", "Athena"))
+            .and(body_partial_json(json!({
+                "model": "anthropic/claude-sonnet-4",
+                "models": ["anthropic/claude-sonnet-4", "openai/gpt-4o"],
+                "provider": {
+                    "order": ["anthropic", "amazon-bedrock"],
+                    "allow_fallbacks": false,
+                    "data_collection": "deny",
+                    "sort": "latency",
+                },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "gen-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "openai/gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi!"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let openrouter = OpenRouterConfig {
+            fallback_models: vec!["openai/gpt-4o".to_string()],
+            site_url: Some("https://athena.example".to_string()),
+            app_name: Some("Athena".to_string()),
+            routing: ProviderRouting {
+                order: vec!["anthropic".to_string(), "amazon-bedrock".to_string()],
+                allow_fallbacks: Some(false),
+                data_collection: Some(DataCollection::Deny),
+                sort: Some(ProviderSort::Latency),
+                ..Default::default()
+            },
+        };
+        let provider = OpenRouterProvider::with_builder(
+            &config("anthropic/claude-sonnet-4", Some(openrouter)),
+            OpenAIProvider::builder().base_url(format!("{}/api/v1", server.uri())),
+        )
+        .unwrap();
+
+        // The fallback model answered
+        let response = provider
+            .send_completion(&[Message::user("Hello")])
+            .await
+            .unwrap();
+        assert_eq!(response.text, "Hi!");
+        assert_eq!(response.model.as_deref(), Some("openai/gpt-4o"));
+    }
+
+    #[tokio::test]
+    async fn test_defaults_send_plain_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "gen-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "openai/gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi!"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenRouterProvider::with_builder(
+            &config("openai/gpt-4o", Some(OpenRouterConfig::default())),
+            OpenAIProvider::builder().base_url(server.uri()),
+        )
+        .unwrap();
+        provider.send_message(&[Message::user("Hello")]).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("models").is_none());
+        assert!(body.get("provider").is_none());
+        assert!(requests[0].headers.iter().all(|(name, _)| name.as_str() != "x-title"));
+    }
+
+    #[tokio::test]
+    async fn test_structured_requests_send_the_schema_as_response_format() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city"]
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": {"name": "response", "schema": schema}
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "gen-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "openai/gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "{\"city\": \"Lyon\"}"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 30, "completion_tokens": 6, "total_tokens": 36}
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let provider = OpenRouterProvider::with_builder(
+            &config("openai/gpt-4o", None),
+            OpenAIProvider::builder().base_url(server.uri()),
+        )
+        .unwrap();
+        let output = StructuredOutput::new(schema.clone());
+        let messages = [Message::user("Where does the TGV from Paris go?")];
+
+        let value = provider.send_structured(&messages, &output).await.unwrap();
+        assert_eq!(value["city"], "Lyon");
+        let (_, response) = provider
+            .send_structured_completion(&messages, &output)
+            .await
+            .unwrap();
+        assert_eq!(response.usage.unwrap().output_tokens, 6);
+    }
+
+    #[test]
+    fn test_model_rules_follow_the_vendor_model() {
+        let provider = OpenRouterProvider::new(&config("openai/o1", None)).unwrap();
+        assert_eq!(provider.model_rules(), &ModelRules::for_model("o1"));
+
+        // Vendor-prefixed names are not checked for typos
+        assert!(OpenRouterProvider::new(&config("openai/gpt-4-o", None)).is_ok());
+        assert!(OpenRouterProvider::new(&config(" ", None)).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Validated sampling parameters.
+//!
+//! [`Temperature`], [`TopP`], and [`MaxTokens`] check their ranges on
+//! construction, so invalid values fail locally with a clear error instead
+//! of as a 400 from the provider.
+
+use agent_core::{AgentError, Result};
+use std::num::NonZeroUsize;
+
+/// Sampling temperature between 0.0 and 2.0
+///
+/// Providers accept different maximums; use [`Temperature::clamp_for`] to
+/// cap a value to what a given provider supports.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Temperature(f32);
+
+impl Temperature {
+    /// Highest temperature accepted by any provider
+    pub const MAX: f32 = 2.0;
+    /// Highest temperature accepted by Anthropic
+    pub const ANTHROPIC_MAX: f32 = 1.0;
+    /// Highest temperature accepted by OpenAI
+    pub const OPENAI_MAX: f32 = 2.0;
+
+    /// Create a temperature, checking it is within 0.0 to 2.0
+    pub fn new(value: f32) -> Result<Self> {
+        if !value.is_finite() || !(0.0..=Self::MAX).contains(&value) {
+            return Err(AgentError::Config(format!(
+                "Temperature must be between 0.0 and {}, got {}",
+                Self::MAX,
+                value
+            )));
+        }
+        Ok(Self(value))
+    }
+
+    /// The temperature value
+    pub fn get(self) -> f32 {
+        self.0
+    }
+
+    /// Cap the temperature to the maximum supported by a provider
+    ///
+    /// # Arguments
+    /// * `provider` - Provider name as used in `LLMConfig` ("openai", "anthropic")
+    pub fn clamp_for(self, provider: &str) -> Self {
+        let max = match provider {
+            "anthropic" => Self::ANTHROPIC_MAX,
+            "openai" => Self::OPENAI_MAX,
+            _ => Self::MAX,
+        };
+        Self(self.0.min(max))
+    }
+}
+
+impl Default for Temperature {
+    fn default() -> Self {
+        Self(0.7)
+    }
+}
+
+impl TryFrom<f32> for Temperature {
+    type Error = AgentError;
+
+    fn try_from(value: f32) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+/// Nucleus sampling probability mass, greater than 0.0 and at most 1.0
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct TopP(f32);
+
+impl TopP {
+    /// Create a top-p value, checking it is within (0.0, 1.0]
+    pub fn new(value: f32) -> Result<Self> {
+        if !value.is_finite() || value <= 0.0 || value > 1.0 {
+            return Err(AgentError::Config(format!(
+                "Top-p must be greater than 0.0 and at most 1.0, got {}",
+                value
+            )));
+        }
+        Ok(Self(value))
+    }
+
+    /// The top-p value
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl TryFrom<f32> for TopP {
+    type Error = AgentError;
+
+    fn try_from(value: f32) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+/// Maximum number of tokens to generate, always greater than zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MaxTokens(NonZeroUsize);
+
+impl MaxTokens {
+    /// Create a token limit, checking it is greater than zero
+    pub fn new(value: usize) -> Result<Self> {
+        NonZeroUsize::new(value)
+            .map(Self)
+            .ok_or_else(|| AgentError::Config("Max tokens must be greater than 0".to_string()))
+    }
+
+    /// The token limit
+    pub fn get(self) -> usize {
+        self.0.get()
+    }
+}
+
+impl Default for MaxTokens {
+    fn default() -> Self {
+        Self(NonZeroUsize::new(2000).expect("2000 is non-zero"))
+    }
+}
+
+impl TryFrom<usize> for MaxTokens {
+    type Error = AgentError;
+
+    fn try_from(value: usize) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temperature_range() {
+        assert_eq!(Temperature::new(0.0).unwrap().get(), 0.0);
+        assert_eq!(Temperature::new(2.0).unwrap().get(), 2.0);
+        assert!(Temperature::new(-0.1).is_err());
+        assert!(Temperature::new(2.1).is_err());
+        assert!(Temperature::new(f32::NAN).is_err());
+
+        let err = Temperature::new(3.0).unwrap_err();
+        assert!(err.to_string().contains("between 0.0 and 2, got 3"));
+    }
+
+    #[test]
+    fn test_temperature_clamping() {
+        let temperature = Temperature::new(1.5).unwrap();
+        assert_eq!(temperature.clamp_for("anthropic").get(), 1.0);
+        assert_eq!(temperature.clamp_for("openai").get(), 1.5);
+
+        let low = Temperature::new(0.3).unwrap();
+        assert_eq!(low.clamp_for("anthropic").get(), 0.3);
+    }
+
+    #[test]
+    fn test_top_p_range() {
+        assert!(TopP::new(1.0).is_ok());
+        assert!(TopP::new(0.1).is_ok());
+        assert!(TopP::new(0.0).is_err());
+        assert!(TopP::new(1.01).is_err());
+        assert!(TopP::try_from(0.9).is_ok());
+    }
+
+    #[test]
+    fn test_max_tokens() {
+        assert_eq!(MaxTokens::new(100).unwrap().get(), 100);
+        assert!(MaxTokens::new(0).is_err());
+        assert_eq!(MaxTokens::default().get(), 2000);
+    }
+}
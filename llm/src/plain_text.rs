@@ -0,0 +1,434 @@
+//! Plain-text enforcement for model output.
+//!
+//! Models often decorate an answer that was requested as bare text: they wrap
+//! it in a Markdown code fence, enclose it in an XML tag such as `<answer>`,
+//! or surround it with chatter ("Sure! Here is the result:", "Let me know if
+//! you need anything else."). Callers that feed completions straight into a
+//! downstream parser can describe what to remove with an [`OutputPolicy`] and
+//! wrap their provider in a [`PlainTextProvider`].
+
+use agent_core::{Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::{
+    CompletionResponse, LLMProvider, StreamEvent, StructuredOutput, TokenStream, ToolConfig,
+};
+
+/// Openers that mark a leading line as chatter rather than content
+const PREAMBLE_OPENERS: &[&str] = &[
+    "sure",
+    "certainly",
+    "of course",
+    "absolutely",
+    "okay",
+    "ok,",
+    "ok!",
+    "here is",
+    "here's",
+    "here are",
+    "below is",
+    "the following",
+];
+
+/// Openers that mark a trailing line as chatter rather than content
+const POSTAMBLE_OPENERS: &[&str] = &[
+    "let me know",
+    "i hope this",
+    "hope this helps",
+    "feel free to",
+    "if you need",
+    "if you have any",
+];
+
+/// Which XML wrappers are removed from around the output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum XmlWrappers {
+    /// Leave XML tags untouched
+    #[default]
+    Keep,
+    /// Unwrap any single tag that encloses the whole output
+    Any,
+    /// Unwrap only the named tags
+    Named(Vec<String>),
+}
+
+impl XmlWrappers {
+    fn allows(&self, tag: &str) -> bool {
+        match self {
+            Self::Keep => false,
+            Self::Any => true,
+            Self::Named(names) => names.iter().any(|name| name == tag),
+        }
+    }
+}
+
+/// What to strip from a completion before it is returned
+///
+/// The default policy only trims surrounding whitespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputPolicy {
+    /// Replace the output with the contents of its first Markdown code fence
+    pub strip_code_fences: bool,
+    /// XML tags to unwrap when they enclose the whole output
+    pub xml_wrappers: XmlWrappers,
+    /// Drop conversational lines before and after the content
+    pub strip_chatter: bool,
+}
+
+impl OutputPolicy {
+    /// A policy that only trims whitespace
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that strips fences, any enclosing XML tag and chatter
+    pub fn strict() -> Self {
+        Self {
+            strip_code_fences: true,
+            xml_wrappers: XmlWrappers::Any,
+            strip_chatter: true,
+        }
+    }
+
+    /// Replace the output with the contents of its first code fence
+    pub fn strip_code_fences(mut self) -> Self {
+        self.strip_code_fences = true;
+        self
+    }
+
+    /// Unwrap the named XML tag when it encloses the whole output
+    pub fn unwrap_tag(mut self, tag: impl Into<String>) -> Self {
+        match &mut self.xml_wrappers {
+            XmlWrappers::Named(names) => names.push(tag.into()),
+            XmlWrappers::Any => {}
+            XmlWrappers::Keep => self.xml_wrappers = XmlWrappers::Named(vec![tag.into()]),
+        }
+        self
+    }
+
+    /// Unwrap any XML tag that encloses the whole output
+    pub fn unwrap_any_tag(mut self) -> Self {
+        self.xml_wrappers = XmlWrappers::Any;
+        self
+    }
+
+    /// Drop conversational lines before and after the content
+    pub fn strip_chatter(mut self) -> Self {
+        self.strip_chatter = true;
+        self
+    }
+
+    /// Apply the policy to a completion
+    ///
+    /// Wrappers are peeled repeatedly, so a fence inside an `<answer>` tag
+    /// (or the reverse) is removed in one call.
+    pub fn apply(&self, output: &str) -> String {
+        let mut text = output.trim();
+        loop {
+            let before = text;
+            if self.strip_chatter {
+                text = strip_chatter(text);
+            }
+            if self.strip_code_fences
+                && let Some(inner) = fenced_block(text)
+            {
+                text = inner.trim();
+            }
+            if let Some((tag, inner)) = enclosing_tag(text)
+                && self.xml_wrappers.allows(tag)
+            {
+                text = inner.trim();
+            }
+            if text == before {
+                return text.to_string();
+            }
+        }
+    }
+}
+
+/// Drop chatter lines from the start and end of `text`
+fn strip_chatter(text: &str) -> &str {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut start = 0;
+    let mut end = lines.len();
+
+    // Keep at least one line; a lone "Sure, 42" is the whole answer
+    while end - start > 1 && is_chatter(lines[start], PREAMBLE_OPENERS, true) {
+        start += 1;
+        while start < end && lines[start].trim().is_empty() {
+            start += 1;
+        }
+    }
+    while end - start > 1 && is_chatter(lines[end - 1], POSTAMBLE_OPENERS, false) {
+        end -= 1;
+        while end > start && lines[end - 1].trim().is_empty() {
+            end -= 1;
+        }
+    }
+
+    if start == 0 && end == lines.len() {
+        return text;
+    }
+    let first = lines[start];
+    let last = lines[end - 1];
+    // Both lines are slices of `text`, so their offsets delimit the content
+    let from = first.as_ptr() as usize - text.as_ptr() as usize;
+    let to = last.as_ptr() as usize - text.as_ptr() as usize + last.len();
+    text[from..to].trim()
+}
+
+/// Whether `line` is a conversational aside
+///
+/// Preamble lines must also be short or end with a colon or exclamation
+/// mark, so content that merely starts with "Here is" is kept.
+fn is_chatter(line: &str, openers: &[&str], preamble: bool) -> bool {
+    let line = line.trim().to_lowercase();
+    if !openers.iter().any(|opener| line.starts_with(opener)) {
+        return false;
+    }
+    !preamble || line.ends_with(':') || line.ends_with('!') || line.split_whitespace().count() <= 2
+}
+
+/// Contents of the first Markdown code fence in `text`
+fn fenced_block(text: &str) -> Option<&str> {
+    let open = text.find("```")?;
+    let after_open = &text[open + 3..];
+    // Skip the info string (language tag) on the opening line
+    let body_start = after_open.find('\n')? + 1;
+    let body = &after_open[body_start..];
+    let close = body.find("```")?;
+    Some(&body[..close])
+}
+
+/// Tag name and contents when a single XML element encloses all of `text`
+fn enclosing_tag(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix('<')?;
+    let open_end = rest.find('>')?;
+    let tag = rest[..open_end].split_whitespace().next()?;
+    if tag.is_empty()
+        || !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+    let closing = format!("</{}>", tag);
+    let inner = text[open_end + 2..].strip_suffix(closing.as_str())?;
+    // Sibling elements such as `<a>1</a> <a>2</a>` are not one wrapper
+    if inner.contains(closing.as_str()) {
+        return None;
+    }
+    Some((tag, inner))
+}
+
+/// Provider wrapper that post-processes every completion with an
+/// [`OutputPolicy`].
+///
+/// Streaming falls back to a single text event carrying the processed
+/// response, followed by the inner stream's usage and stop events, since
+/// wrappers can only be recognised once the output is complete. Tool-calling
+/// responses have their text cleaned; structured output is passed through.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{LLMProvider, OpenAIProvider, OutputPolicy, PlainTextProvider};
+/// use agent_core::Message;
+/// # use config::LLMConfig;
+///
+/// # async fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// let policy = OutputPolicy::new().strip_code_fences().strip_chatter();
+/// let provider = PlainTextProvider::new(OpenAIProvider::new(&config)?, policy);
+///
+/// let sql = provider
+///     .send_message(&[Message::user("Write a query counting users")])
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PlainTextProvider<P: LLMProvider> {
+    inner: P,
+    policy: OutputPolicy,
+}
+
+impl<P: LLMProvider> PlainTextProvider<P> {
+    /// Wrap a provider so its output is cleaned according to `policy`
+    pub fn new(inner: P, policy: OutputPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Get a reference to the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Get the output policy
+    pub fn policy(&self) -> &OutputPolicy {
+        &self.policy
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for PlainTextProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let response = self.inner.send_message(messages).await?;
+        Ok(self.policy.apply(&response))
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let mut response = self.inner.send_completion(messages).await?;
+        response.text = self.policy.apply(&response.text);
+        Ok(response)
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let mut response = self
+            .inner
+            .send_message_with_tools(messages, tools, config)
+            .await?;
+        response.text = self.policy.apply(&response.text);
+        Ok(response)
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let mut stream = self.inner.stream_message(messages).await?;
+        let mut text = String::new();
+        let mut trailing = Vec::new();
+        while let Some(event) = stream.next().await {
+            match event? {
+                StreamEvent::Text(chunk) => text.push_str(&chunk),
+                other => trailing.push(Ok(other)),
+            }
+        }
+        let mut events = vec![Ok(StreamEvent::Text(self.policy.apply(&text)))];
+        events.extend(trailing);
+        Ok(Box::pin(futures::stream::iter(events)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedProvider(&'static str);
+
+    #[async_trait]
+    impl LLMProvider for FixedProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn test_default_policy_only_trims() {
+        let output = "  ```sql\nSELECT 1;\n```  ";
+        assert_eq!(OutputPolicy::new().apply(output), "```sql\nSELECT 1;\n```");
+    }
+
+    #[test]
+    fn test_strip_code_fences() {
+        let policy = OutputPolicy::new().strip_code_fences();
+        assert_eq!(policy.apply("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+        assert_eq!(policy.apply("Result:\n```\nx = 1\n```\nDone."), "x = 1");
+        assert_eq!(policy.apply("no fence here"), "no fence here");
+    }
+
+    #[test]
+    fn test_unwrap_xml_wrappers() {
+        let named = OutputPolicy::new().unwrap_tag("answer");
+        assert_eq!(named.apply("<answer>\n42\n</answer>"), "42");
+        assert_eq!(named.apply("<result>42</result>"), "<result>42</result>");
+        assert_eq!(
+            named.apply("<answer>1</answer> and <answer>2</answer>"),
+            "<answer>1</answer> and <answer>2</answer>"
+        );
+
+        let any = OutputPolicy::new().unwrap_any_tag();
+        assert_eq!(any.apply("<output kind=\"text\">hi</output>"), "hi");
+    }
+
+    #[test]
+    fn test_strip_chatter() {
+        let policy = OutputPolicy::new().strip_chatter();
+        let output =
+            "Sure! Here is the summary:\n\nThe build passed.\n\nLet me know if you need more.";
+        assert_eq!(policy.apply(output), "The build passed.");
+        assert_eq!(
+            policy.apply("Here is the plan for today"),
+            "Here is the plan for today"
+        );
+        assert_eq!(
+            policy.apply("Here is what happened in detail\nThe build passed."),
+            "Here is what happened in detail\nThe build passed."
+        );
+    }
+
+    #[test]
+    fn test_strict_peels_nested_wrappers() {
+        let output = "Certainly! Here's the query:\n<answer>\n```sql\nSELECT 1;\n```\n</answer>\nHope this helps!";
+        assert_eq!(OutputPolicy::strict().apply(output), "SELECT 1;");
+    }
+
+    #[tokio::test]
+    async fn test_provider_applies_policy() {
+        let provider = PlainTextProvider::new(
+            FixedProvider("Here you go:\n```\nhello\n```"),
+            OutputPolicy::strict(),
+        );
+        let response = provider.send_message(&[Message::user("Hi")]).await.unwrap();
+        assert_eq!(response, "hello");
+
+        let stream = provider
+            .stream_message(&[Message::user("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(crate::collect_text(stream).await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_provider_cleans_completions_and_tool_replies() {
+        let provider = PlainTextProvider::new(
+            FixedProvider("<answer>42</answer>"),
+            OutputPolicy::new().unwrap_tag("answer"),
+        );
+        let response = provider
+            .send_completion(&[Message::user("Hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.text, "42");
+
+        let reply = provider
+            .send_message_with_tools(
+                &[Message::user("Hi")],
+                &[],
+                &ToolConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reply.text, "42");
+        assert!(reply.tool_uses.is_empty());
+    }
+}
@@ -0,0 +1,505 @@
+//! Load balancing across several API keys or endpoints of one vendor.
+//!
+//! Vendors rate limit each API key separately, so batch workloads can raise
+//! their throughput by spreading requests over several keys. A
+//! [`PooledProvider`] picks a member for each request by round robin or by
+//! the fewest requests in flight, and takes a member out of rotation for a
+//! cooldown when it reports a rate limit, retrying the request on another
+//! member.
+
+use agent_core::{AgentError, ErrorCategory, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::LLMConfig;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{
+    CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig, create_provider,
+};
+
+/// Cooldown used when none is configured
+const DEFAULT_RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// How a [`PooledProvider`] picks the member that serves a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoolStrategy {
+    /// Take turns, in the order the members were added
+    #[default]
+    RoundRobin,
+    /// Pick the member with the fewest requests in flight
+    LeastLoaded,
+}
+
+/// Counters of one pool member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolMemberStats {
+    /// Name of the member
+    pub name: String,
+    /// Requests currently in flight
+    pub in_flight: usize,
+    /// Requests the member answered successfully
+    pub served: u64,
+    /// Requests the member rejected with a rate limit
+    pub rate_limited: u64,
+    /// Whether the member is out of rotation after a rate limit
+    pub cooling_down: bool,
+}
+
+struct PoolMember {
+    name: String,
+    provider: Box<dyn LLMProvider>,
+    in_flight: AtomicUsize,
+    served: AtomicU64,
+    rate_limited: AtomicU64,
+    cooldown_until: Mutex<Option<Instant>>,
+}
+
+impl PoolMember {
+    /// The end of the member's cooldown, if it is cooling down at `now`
+    fn cooling_until(&self, now: Instant) -> Option<Instant> {
+        self.cooldown_until
+            .lock()
+            .unwrap()
+            .filter(|until| *until > now)
+    }
+}
+
+/// Decrements a member's in-flight count when the request finishes
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Provider that balances requests across several members of one vendor.
+///
+/// A member that fails with a rate limit is skipped for the cooldown (30
+/// seconds by default) and the request is retried on another member. When
+/// every member is cooling down, the request waits for the first cooldown to
+/// end. Other errors are returned unchanged.
+///
+/// Streams are balanced like other requests, but only rate limits reported
+/// while the stream is opened are retried.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{LLMProvider, PoolStrategy, PooledProvider};
+/// use agent_core::Message;
+/// # use config::LLMConfig;
+///
+/// # async fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// let keys = vec!["sk-batch-1".to_string(), "sk-batch-2".to_string()];
+/// let provider = PooledProvider::for_api_keys(&config, &keys)?
+///     .with_strategy(PoolStrategy::LeastLoaded);
+///
+/// let reply = provider.send_message(&[Message::user("Hello")]).await?;
+/// for member in provider.stats() {
+///     println!("{}: {} served", member.name, member.served);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct PooledProvider {
+    members: Vec<PoolMember>,
+    strategy: PoolStrategy,
+    cooldown: Duration,
+    next: AtomicUsize,
+}
+
+impl Default for PooledProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PooledProvider {
+    /// Create an empty round-robin pool
+    pub fn new() -> Self {
+        Self {
+            members: Vec::new(),
+            strategy: PoolStrategy::default(),
+            cooldown: DEFAULT_RATE_LIMIT_COOLDOWN,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Create a pool with one member per API key, each configured like
+    /// `config` otherwise
+    ///
+    /// Members are named `key-1`, `key-2` and so on, so keys never appear in
+    /// stats or logs.
+    ///
+    /// # Errors
+    /// Returns an error if `api_keys` is empty or a provider cannot be
+    /// created from the configuration
+    pub fn for_api_keys(config: &LLMConfig, api_keys: &[String]) -> Result<Self> {
+        if api_keys.is_empty() {
+            return Err(AgentError::Config(
+                "PooledProvider requires at least one API key".to_string(),
+            ));
+        }
+
+        let mut pool = Self::new();
+        for (index, api_key) in api_keys.iter().enumerate() {
+            let config = LLMConfig {
+                api_key: api_key.clone(),
+                ..config.clone()
+            };
+            pool = pool.with_member(format!("key-{}", index + 1), create_provider(&config)?);
+        }
+        Ok(pool)
+    }
+
+    /// Add a member, e.g. a provider for another API key or endpoint
+    pub fn with_member(
+        mut self,
+        name: impl Into<String>,
+        provider: impl LLMProvider + 'static,
+    ) -> Self {
+        self.members.push(PoolMember {
+            name: name.into(),
+            provider: Box::new(provider),
+            in_flight: AtomicUsize::new(0),
+            served: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+            cooldown_until: Mutex::new(None),
+        });
+        self
+    }
+
+    /// Set how members are picked
+    pub fn with_strategy(mut self, strategy: PoolStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set how long a rate-limited member is skipped
+    pub fn with_rate_limit_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// The strategy members are picked by
+    pub fn strategy(&self) -> PoolStrategy {
+        self.strategy
+    }
+
+    /// Number of members
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns true if the pool has no members
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Counters of every member, in the order they were added
+    pub fn stats(&self) -> Vec<PoolMemberStats> {
+        let now = Instant::now();
+        self.members
+            .iter()
+            .map(|member| PoolMemberStats {
+                name: member.name.clone(),
+                in_flight: member.in_flight.load(Ordering::SeqCst),
+                served: member.served.load(Ordering::SeqCst),
+                rate_limited: member.rate_limited.load(Ordering::SeqCst),
+                cooling_down: member.cooling_until(now).is_some(),
+            })
+            .collect()
+    }
+
+    /// Pick the member for the next request among those not cooling down,
+    /// or return when the first cooldown ends if all are
+    fn pick(&self) -> std::result::Result<&PoolMember, Instant> {
+        let now = Instant::now();
+        let start = self.next.fetch_add(1, Ordering::SeqCst);
+        let mut available = (0..self.members.len())
+            .map(|offset| &self.members[(start + offset) % self.members.len()])
+            .filter(|member| member.cooling_until(now).is_none());
+
+        let picked = match self.strategy {
+            PoolStrategy::RoundRobin => available.next(),
+            PoolStrategy::LeastLoaded => {
+                available.min_by_key(|member| member.in_flight.load(Ordering::SeqCst))
+            }
+        };
+        picked.ok_or_else(|| {
+            self.members
+                .iter()
+                .filter_map(|member| member.cooling_until(now))
+                .min()
+                .unwrap_or(now)
+        })
+    }
+
+    /// Run `call` on a picked member, retrying on other members while they
+    /// report rate limits
+    async fn dispatch<'a, T, F, Fut>(&'a self, call: F) -> Result<T>
+    where
+        F: Fn(&'a dyn LLMProvider) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if self.members.is_empty() {
+            return Err(AgentError::Config(
+                "PooledProvider requires at least one member".to_string(),
+            ));
+        }
+
+        let mut errors = Vec::new();
+        for _ in 0..self.members.len() {
+            let member = loop {
+                match self.pick() {
+                    Ok(member) => break member,
+                    Err(until) => tokio::time::sleep_until(until.into()).await,
+                }
+            };
+
+            member.in_flight.fetch_add(1, Ordering::SeqCst);
+            let result = {
+                let _in_flight = InFlight(&member.in_flight);
+                call(member.provider.as_ref()).await
+            };
+            match result {
+                Ok(response) => {
+                    member.served.fetch_add(1, Ordering::SeqCst);
+                    return Ok(response);
+                }
+                Err(error) if ErrorCategory::of(&error) == ErrorCategory::RateLimited => {
+                    member.rate_limited.fetch_add(1, Ordering::SeqCst);
+                    *member.cooldown_until.lock().unwrap() = Some(Instant::now() + self.cooldown);
+                    errors.push(format!("{}: {}", member.name, error));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(AgentError::LLMProvider(format!(
+            "Rate limit exceeded on {} pool members: {}",
+            errors.len(),
+            errors.join("; ")
+        )))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for PooledProvider {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.dispatch(|provider| provider.send_message(messages))
+            .await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.dispatch(|provider| provider.send_completion(messages))
+            .await
+    }
+
+    async fn send_structured(&self, messages: &[Message], output: &StructuredOutput) -> Result<Value> {
+        self.dispatch(|provider| provider.send_structured(messages, output))
+            .await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.dispatch(|provider| provider.send_structured_completion(messages, output))
+            .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.dispatch(|provider| provider.send_message_with_tools(messages, tools, config))
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.dispatch(|provider| provider.stream_message(messages))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+
+    /// Answers with its name, or reports a rate limit while `limited` is set
+    struct KeyProvider {
+        name: &'static str,
+        limited: Arc<AtomicBool>,
+        delay: Duration,
+    }
+
+    impl KeyProvider {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                limited: Arc::new(AtomicBool::new(false)),
+                delay: Duration::ZERO,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for KeyProvider {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            tokio::time::sleep(self.delay).await;
+            if self.limited.load(Ordering::SeqCst) {
+                return Err(AgentError::LLMProvider(
+                    "Rate limit exceeded (HTTP 429)".to_string(),
+                ));
+            }
+            Ok(self.name.to_string())
+        }
+
+        /// Answers natively with its name as JSON, which the plain text
+        /// reply above would not parse as
+        async fn send_structured(
+            &self,
+            messages: &[Message],
+            _output: &StructuredOutput,
+        ) -> Result<Value> {
+            let name = self.send_message(messages).await?;
+            Ok(serde_json::json!({ "member": name }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let pool = PooledProvider::new()
+            .with_member("a", KeyProvider::new("a"))
+            .with_member("b", KeyProvider::new("b"));
+        let messages = [Message::user("Hello")];
+
+        let mut replies = Vec::new();
+        for _ in 0..4 {
+            replies.push(pool.send_message(&messages).await.unwrap());
+        }
+        assert_eq!(replies, vec!["a", "b", "a", "b"]);
+        assert!(pool.stats().iter().all(|member| member.served == 2));
+    }
+
+    #[tokio::test]
+    async fn test_least_loaded_avoids_busy_members() {
+        let mut slow = KeyProvider::new("slow");
+        slow.delay = Duration::from_millis(200);
+        let pool = Arc::new(
+            PooledProvider::new()
+                .with_member("slow", slow)
+                .with_member("fast", KeyProvider::new("fast"))
+                .with_strategy(PoolStrategy::LeastLoaded),
+        );
+        let messages = [Message::user("Hello")];
+
+        let background = pool.clone();
+        let busy =
+            tokio::spawn(async move { background.send_message(&[Message::user("Hello")]).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(pool.stats()[0].in_flight, 1);
+
+        for _ in 0..3 {
+            assert_eq!(pool.send_message(&messages).await.unwrap(), "fast");
+        }
+        assert_eq!(busy.await.unwrap().unwrap(), "slow");
+        assert_eq!(pool.stats()[0].in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_member_cools_down() {
+        let limited = KeyProvider::new("a");
+        limited.limited.store(true, Ordering::SeqCst);
+        let pool = PooledProvider::new()
+            .with_member("a", limited)
+            .with_member("b", KeyProvider::new("b"));
+        let messages = [Message::user("Hello")];
+
+        for _ in 0..3 {
+            assert_eq!(pool.send_message(&messages).await.unwrap(), "b");
+        }
+        let stats = pool.stats();
+        assert_eq!(stats[0].rate_limited, 1);
+        assert!(stats[0].cooling_down);
+        assert_eq!(stats[1].served, 3);
+    }
+
+    #[tokio::test]
+    async fn test_structured_requests_go_to_the_selected_member() {
+        let limited = KeyProvider::new("a");
+        limited.limited.store(true, Ordering::SeqCst);
+        let pool = PooledProvider::new()
+            .with_member("a", limited)
+            .with_member("b", KeyProvider::new("b"));
+        let output = StructuredOutput::new(serde_json::json!({"type": "object"}));
+
+        for _ in 0..2 {
+            let value = pool
+                .send_structured(&[Message::user("Hello")], &output)
+                .await
+                .unwrap();
+            assert_eq!(value["member"], "b");
+        }
+        let stats = pool.stats();
+        assert_eq!(stats[0].rate_limited, 1);
+        assert!(stats[0].cooling_down);
+        assert_eq!(stats[1].served, 2);
+    }
+
+    #[tokio::test]
+    async fn test_waits_when_every_member_is_limited() {
+        let member = KeyProvider::new("a");
+        let limited = member.limited.clone();
+        limited.store(true, Ordering::SeqCst);
+        let pool = PooledProvider::new()
+            .with_member("a", member)
+            .with_rate_limit_cooldown(Duration::from_millis(50));
+        let messages = [Message::user("Hello")];
+
+        let error = pool.send_message(&messages).await.unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Rate limit exceeded on 1 pool members")
+        );
+
+        limited.store(false, Ordering::SeqCst);
+        let started = Instant::now();
+        assert_eq!(pool.send_message(&messages).await.unwrap(), "a");
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_for_api_keys() {
+        let config = LLMConfig {
+            provider: "openai".to_string(),
+            model: "gpt-4".to_string(),
+            api_key: String::new(),
+            temperature: 0.7,
+            max_tokens: 2000,
+            organization: None,
+            project: None,
+            safety_settings: Default::default(),
+            azure: None,
+            openrouter: None,
+            prompt_caching: false,
+        };
+
+        let keys = vec!["sk-1".to_string(), "sk-2".to_string()];
+        let pool = PooledProvider::for_api_keys(&config, &keys).unwrap();
+        let names: Vec<_> = pool.stats().into_iter().map(|member| member.name).collect();
+        assert_eq!(names, vec!["key-1", "key-2"]);
+        assert!(PooledProvider::for_api_keys(&config, &[]).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+//! Post-processing of generated text before it reaches callers.
+//!
+//! A [`PostProcessingPipeline`] runs a configured list of
+//! [`PostProcessor`] stages over each completion: built-in stages normalize
+//! Markdown, link citation markers to their sources and extract code
+//! blocks, and applications add their own stages by implementing the
+//! trait. A [`PostProcessingProvider`] applies the pipeline to every
+//! response of a provider.
+
+use agent_core::{Message, Result, ResultExt, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::PostProcessorConfig;
+use futures::TryStreamExt;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+
+use crate::{
+    CompletionResponse, LLMProvider, StreamEvent, StructuredOutput, TokenStream, ToolConfig,
+};
+
+static HEADING_WITHOUT_SPACE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(#{1,6})([[:alpha:]])").unwrap());
+static LIST_MARKER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\s*)[*+] ").unwrap());
+static CITATION_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\[(\d+)\]:?\s+<?(https?://[^\s>]+)>?").unwrap());
+static CITATION_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\[)?\[(\d+)\]([(:\]])?").unwrap());
+
+/// A stage that rewrites the text of a completion
+///
+/// # Example
+///
+/// ```
+/// use llm::{PostProcessingPipeline, PostProcessor};
+///
+/// struct Signature;
+///
+/// impl PostProcessor for Signature {
+///     fn name(&self) -> &str {
+///         "signature"
+///     }
+///
+///     fn process(&self, text: &str) -> agent_core::Result<String> {
+///         Ok(format!("{}\n\n-- Athena", text))
+///     }
+/// }
+///
+/// let pipeline = PostProcessingPipeline::new().with(Signature);
+/// assert_eq!(pipeline.apply("Hi").unwrap(), "Hi\n\n-- Athena");
+/// ```
+pub trait PostProcessor: Send + Sync {
+    /// Name of the stage, reported with its errors
+    fn name(&self) -> &str;
+
+    /// Rewrite `text`, or reject it with an error
+    fn process(&self, text: &str) -> Result<String>;
+}
+
+/// Tidies Markdown written by a model
+///
+/// Outside fenced code blocks, trailing whitespace is removed, runs of
+/// blank lines are collapsed, a space is added after the `#`s of headings
+/// such as `##Usage`, and `*` and `+` list markers become `-`. A code
+/// fence left open at the end of the text is closed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownNormalizer;
+
+impl PostProcessor for MarkdownNormalizer {
+    fn name(&self) -> &str {
+        "normalize_markdown"
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut in_code = false;
+        for line in text.lines() {
+            if is_fence(line) {
+                in_code = !in_code;
+                lines.push(line.trim_end().to_string());
+            } else if in_code {
+                lines.push(line.to_string());
+            } else {
+                let line = line.trim_end();
+                let previous_blank = lines.last().is_none_or(|last| last.is_empty());
+                if line.is_empty() && previous_blank {
+                    continue;
+                }
+                let line = HEADING_WITHOUT_SPACE.replace(line, "$1 $2");
+                let line = LIST_MARKER.replace(&line, "$1- ");
+                lines.push(line.into_owned());
+            }
+        }
+        if in_code {
+            lines.push("```".to_string());
+        }
+        while lines.last().is_some_and(|last| last.is_empty()) {
+            lines.pop();
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Links numbered citation markers to the sources listed with the answer
+///
+/// Models asked to cite sources usually write markers such as `[1]` and
+/// list the sources as `[1]: https://...` (or `[1] https://...`) lines.
+/// Each marker with a listed source becomes the Markdown link
+/// `[[1]](https://...)`; markers without one, existing links and the
+/// source lines themselves are left alone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CitationLinker;
+
+impl PostProcessor for CitationLinker {
+    fn name(&self) -> &str {
+        "link_citations"
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        let sources: HashMap<&str, &str> = text
+            .lines()
+            .filter_map(|line| CITATION_REFERENCE.captures(line))
+            .map(|captures| {
+                let (_, [number, url]) = captures.extract();
+                (number, url)
+            })
+            .collect();
+        if sources.is_empty() {
+            return Ok(text.to_string());
+        }
+
+        let mut in_code = false;
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| {
+                if is_fence(line) {
+                    in_code = !in_code;
+                }
+                if in_code || is_fence(line) || CITATION_REFERENCE.is_match(line) {
+                    return line.to_string();
+                }
+                CITATION_MARKER
+                    .replace_all(line, |captures: &regex::Captures| {
+                        let number = &captures[2];
+                        let linked = captures.get(1).is_some() || captures.get(3).is_some();
+                        match sources.get(number) {
+                            Some(url) if !linked => format!("[[{}]]({})", number, url),
+                            _ => captures[0].to_string(),
+                        }
+                    })
+                    .into_owned()
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Replaces a completion with the contents of its fenced code blocks
+///
+/// Blocks are joined with a blank line. With a language set, only blocks
+/// tagged with it (ignoring case) are kept. A completion without matching
+/// blocks is returned unchanged, as it may be bare code.
+#[derive(Debug, Clone, Default)]
+pub struct CodeBlockExtractor {
+    language: Option<String>,
+}
+
+impl CodeBlockExtractor {
+    /// Extract every code block
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Extract only the code blocks tagged with `language`
+    pub fn language(language: impl Into<String>) -> Self {
+        Self {
+            language: Some(language.into()),
+        }
+    }
+}
+
+impl PostProcessor for CodeBlockExtractor {
+    fn name(&self) -> &str {
+        "extract_code_blocks"
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        let mut blocks: Vec<String> = Vec::new();
+        let mut current: Option<(String, Vec<&str>)> = None;
+        for line in text.lines() {
+            match current.take() {
+                None if is_fence(line) => {
+                    let tag = line.trim_start().trim_start_matches('`').trim();
+                    let language = tag.split_whitespace().next().unwrap_or("");
+                    current = Some((language.to_lowercase(), Vec::new()));
+                }
+                None => {}
+                Some((language, body)) if is_fence(line) => {
+                    let wanted = self
+                        .language
+                        .as_ref()
+                        .is_none_or(|wanted| wanted.to_lowercase() == language);
+                    if wanted {
+                        blocks.push(body.join("\n"));
+                    }
+                }
+                Some((language, mut body)) => {
+                    body.push(line);
+                    current = Some((language, body));
+                }
+            }
+        }
+
+        if blocks.is_empty() {
+            Ok(text.to_string())
+        } else {
+            Ok(blocks.join("\n\n"))
+        }
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+/// An ordered list of post-processing stages
+///
+/// # Example
+///
+/// ```
+/// use llm::{CitationLinker, MarkdownNormalizer, PostProcessingPipeline};
+///
+/// let pipeline = PostProcessingPipeline::new()
+///     .with(MarkdownNormalizer)
+///     .with(CitationLinker);
+///
+/// let text = pipeline
+///     .apply("##Answer\nRust is memory safe [1].\n\n\n[1]: https://www.rust-lang.org")
+///     .unwrap();
+/// assert_eq!(
+///     text,
+///     "## Answer\nRust is memory safe [[1]](https://www.rust-lang.org).\n\n[1]: https://www.rust-lang.org"
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct PostProcessingPipeline {
+    stages: Vec<Arc<dyn PostProcessor>>,
+}
+
+impl PostProcessingPipeline {
+    /// An empty pipeline, which leaves text unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a pipeline of built-in stages from configuration, keeping their
+    /// order
+    pub fn from_config(config: &[PostProcessorConfig]) -> Self {
+        config
+            .iter()
+            .fold(Self::new(), |pipeline, stage| match stage {
+                PostProcessorConfig::NormalizeMarkdown => pipeline.with(MarkdownNormalizer),
+                PostProcessorConfig::LinkCitations => pipeline.with(CitationLinker),
+                PostProcessorConfig::ExtractCodeBlocks { language } => {
+                    pipeline.with(CodeBlockExtractor {
+                        language: language.clone(),
+                    })
+                }
+            })
+    }
+
+    /// Add a stage to the end of the pipeline
+    pub fn with(mut self, stage: impl PostProcessor + 'static) -> Self {
+        self.stages.push(Arc::new(stage));
+        self
+    }
+
+    /// Names of the stages, in the order they run
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Check if the pipeline has no stages
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run every stage over `text`
+    ///
+    /// # Errors
+    /// Returns the first error of a stage, with the stage's name attached
+    pub fn apply(&self, text: &str) -> Result<String> {
+        let mut text = text.to_string();
+        for stage in &self.stages {
+            text = stage
+                .process(&text)
+                .with_context(|| format!("post-processing stage '{}'", stage.name()))?;
+        }
+        Ok(text)
+    }
+}
+
+impl fmt::Debug for PostProcessingPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostProcessingPipeline")
+            .field("stages", &self.stage_names())
+            .finish()
+    }
+}
+
+/// Provider wrapper that runs a [`PostProcessingPipeline`] on every response
+///
+/// Structured output is returned unchanged, since rewriting it would break
+/// its schema. Streams are read to the end and sent on as one processed
+/// chunk, followed by the inner stream's other events, as stages need the
+/// whole text.
+pub struct PostProcessingProvider<P> {
+    inner: P,
+    pipeline: PostProcessingPipeline,
+}
+
+impl<P: LLMProvider> PostProcessingProvider<P> {
+    /// Wrap a provider
+    pub fn new(inner: P, pipeline: PostProcessingPipeline) -> Self {
+        Self { inner, pipeline }
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The stages applied to each response
+    pub fn pipeline(&self) -> &PostProcessingPipeline {
+        &self.pipeline
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for PostProcessingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let text = self.inner.send_message(messages).await?;
+        self.pipeline.apply(&text)
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let mut response = self.inner.send_completion(messages).await?;
+        response.text = self.pipeline.apply(&response.text)?;
+        Ok(response)
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner
+            .send_structured_completion(messages, output)
+            .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let mut response = self
+            .inner
+            .send_message_with_tools(messages, tools, config)
+            .await?;
+        if !response.text.is_empty() {
+            response.text = self.pipeline.apply(&response.text)?;
+        }
+        Ok(response)
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let stream = self.inner.stream_message(messages).await?;
+        if self.pipeline.is_empty() {
+            return Ok(stream);
+        }
+
+        let events: Vec<StreamEvent> = stream.try_collect().await?;
+        let text: String = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut processed = vec![Ok(StreamEvent::Text(self.pipeline.apply(&text)?))];
+        processed.extend(
+            events
+                .into_iter()
+                .filter(|event| !matches!(event, StreamEvent::Text(_)))
+                .map(Ok),
+        );
+        Ok(Box::pin(futures::stream::iter(processed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenUsage, collect_text};
+    use agent_core::AgentError;
+
+    #[test]
+    fn test_normalize_markdown() {
+        let text = "#Title  \n\n\n* one\n  + two\n```rust\nlet  x = 1;   \n\n\n";
+        assert_eq!(
+            MarkdownNormalizer.process(text).unwrap(),
+            "# Title\n\n- one\n  - two\n```rust\nlet  x = 1;   \n\n\n```"
+        );
+        assert_eq!(
+            MarkdownNormalizer.process("*emphasis* stays").unwrap(),
+            "*emphasis* stays"
+        );
+    }
+
+    #[test]
+    fn test_link_citations() {
+        let text = "Rust [1] and Go [2], see [[1]](https://a.example) and [3].\n\n\
+                    [1]: https://a.example\n[2] <https://b.example>";
+        assert_eq!(
+            CitationLinker.process(text).unwrap(),
+            "Rust [[1]](https://a.example) and Go [[2]](https://b.example), see \
+             [[1]](https://a.example) and [3].\n\n[1]: https://a.example\n[2] <https://b.example>"
+        );
+        assert_eq!(
+            CitationLinker.process("No sources [1]").unwrap(),
+            "No sources [1]"
+        );
+    }
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let text = "Here:\n```Python\nprint(1)\n```\nand\n```sh\nls\n```\nDone.";
+        assert_eq!(
+            CodeBlockExtractor::new().process(text).unwrap(),
+            "print(1)\n\nls"
+        );
+        assert_eq!(
+            CodeBlockExtractor::language("python")
+                .process(text)
+                .unwrap(),
+            "print(1)"
+        );
+        assert_eq!(
+            CodeBlockExtractor::language("rust").process(text).unwrap(),
+            text
+        );
+    }
+
+    /// Fails on text containing "forbidden"
+    struct Reject;
+
+    impl PostProcessor for Reject {
+        fn name(&self) -> &str {
+            "reject"
+        }
+
+        fn process(&self, text: &str) -> Result<String> {
+            if text.contains("forbidden") {
+                return Err(AgentError::GuardrailViolation("forbidden".to_string()));
+            }
+            Ok(text.to_string())
+        }
+    }
+
+    #[test]
+    fn test_pipeline_from_config_and_errors() {
+        let pipeline = PostProcessingPipeline::from_config(&[
+            PostProcessorConfig::NormalizeMarkdown,
+            PostProcessorConfig::ExtractCodeBlocks { language: None },
+        ])
+        .with(Reject);
+        assert_eq!(
+            pipeline.stage_names(),
+            vec!["normalize_markdown", "extract_code_blocks", "reject"]
+        );
+
+        let error = pipeline.apply("forbidden").unwrap_err();
+        assert_eq!(
+            error.context().unwrap().operation,
+            "post-processing stage 'reject'"
+        );
+        assert!(matches!(
+            error.root_cause(),
+            AgentError::GuardrailViolation(_)
+        ));
+    }
+
+    /// Streams a fixed answer in two chunks
+    struct Fixed;
+
+    #[async_trait]
+    impl LLMProvider for Fixed {
+        async fn send_message(&self, _messages: &[Message]) -> Result<String> {
+            Ok("See:\n```sh\nls\n```".to_string())
+        }
+
+        async fn stream_message(&self, _messages: &[Message]) -> Result<TokenStream> {
+            Ok(Box::pin(futures::stream::iter(vec![
+                Ok(StreamEvent::Text("See:\n```sh\n".to_string())),
+                Ok(StreamEvent::Text("ls\n```".to_string())),
+                Ok(StreamEvent::Usage(TokenUsage::default())),
+            ])))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_processes_responses() {
+        let provider = PostProcessingProvider::new(
+            Fixed,
+            PostProcessingPipeline::new().with(CodeBlockExtractor::new()),
+        );
+        let messages = [Message::user("List files")];
+
+        assert_eq!(provider.send_message(&messages).await.unwrap(), "ls");
+        assert_eq!(
+            provider.send_completion(&messages).await.unwrap().text,
+            "ls"
+        );
+
+        let events: Vec<StreamEvent> = provider
+            .stream_message(&messages)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("ls".to_string()),
+                StreamEvent::Usage(TokenUsage::default())
+            ]
+        );
+        let stream = provider.stream_message(&messages).await.unwrap();
+        assert_eq!(collect_text(stream).await.unwrap(), "ls");
+    }
+}
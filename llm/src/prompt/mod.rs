@@ -0,0 +1,17 @@
+//! Named, versioned prompts.
+//!
+//! Prompts live as files with YAML front matter in a directory and are
+//! loaded into a [`PromptStore`]. Code refers to them by name and version,
+//! e.g. `planner@v3`, and records the reference on the resulting
+//! [`Conversation`](agent_core::Conversation) so every transcript can be
+//! traced back to the exact prompt that produced it.
+//!
+//! A [`PromptTemplate`] compiles a prompt with placeholders, conditional
+//! sections and shared partials, so system prompts are written as templates
+//! checked once when they are loaded rather than assembled with `format!`.
+
+mod store;
+mod template;
+
+pub use store::{Prompt, PromptMetadata, PromptStore};
+pub use template::{PromptTemplate, PromptTemplateBuilder};
@@ -0,0 +1,423 @@
+use agent_core::{AgentError, Conversation, Result};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::PromptTemplate;
+
+/// File extensions recognised as prompt files
+const PROMPT_EXTENSIONS: &[&str] = &["md", "prompt", "txt"];
+
+/// Front-matter metadata of a prompt file
+///
+/// ```text
+/// ---
+/// name: planner
+/// version: v3
+/// model: gpt-4o
+/// variables: [tools, goal]
+/// description: Breaks a goal into tool calls
+/// ---
+/// You can use these tools:
+/// {{tools}}
+///
+/// Goal: {{goal}}
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PromptMetadata {
+    /// Prompt name; defaults to the file name without extension
+    #[serde(default)]
+    pub name: String,
+    /// Version label such as `v3`
+    #[serde(default = "default_version")]
+    pub version: String,
+    /// Model the prompt was written for, as a hint to callers
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Suggested sampling temperature
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Variables that must be supplied when rendering
+    #[serde(default)]
+    pub variables: Vec<String>,
+    /// What the prompt is for
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+fn default_version() -> String {
+    "v1".to_string()
+}
+
+/// A prompt body with its metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    /// Front-matter metadata
+    pub metadata: PromptMetadata,
+    /// Prompt text with `{{variable}}` placeholders
+    pub body: String,
+}
+
+impl Prompt {
+    /// Parse a prompt from file contents with optional front matter
+    ///
+    /// # Errors
+    /// Returns an error if the front matter is not valid YAML or the
+    /// prompt has no name.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        let (metadata, body) = match split_front_matter(contents) {
+            Some((front, body)) => {
+                let metadata: PromptMetadata = serde_yaml::from_str(front).map_err(|e| {
+                    AgentError::Config(format!("Invalid prompt front matter: {}", e))
+                })?;
+                (metadata, body)
+            }
+            None => (
+                PromptMetadata {
+                    name: String::new(),
+                    version: default_version(),
+                    model: None,
+                    temperature: None,
+                    variables: Vec::new(),
+                    description: None,
+                },
+                contents,
+            ),
+        };
+
+        Ok(Self {
+            metadata,
+            body: body.trim().to_string(),
+        })
+    }
+
+    /// The `name@version` reference for this prompt
+    pub fn reference(&self) -> String {
+        format!("{}@{}", self.metadata.name, self.metadata.version)
+    }
+
+    /// Substitute variables into the prompt body
+    ///
+    /// # Errors
+    /// Returns an error if a declared variable is not supplied.
+    pub fn render(&self, variables: &HashMap<&str, &str>) -> Result<String> {
+        let mut rendered = self.body.clone();
+        for name in &self.metadata.variables {
+            let value = variables.get(name.as_str()).ok_or_else(|| {
+                AgentError::Config(format!(
+                    "Prompt '{}' requires variable '{}'",
+                    self.reference(),
+                    name
+                ))
+            })?;
+            rendered = rendered.replace(&format!("{{{{{}}}}}", name), value);
+        }
+        Ok(rendered)
+    }
+
+    /// Compile the prompt body as a [`PromptTemplate`]
+    ///
+    /// # Errors
+    /// Returns an error if the body is not a valid template or reads a
+    /// variable the front matter does not declare.
+    pub fn template(&self) -> Result<PromptTemplate> {
+        let template = PromptTemplate::compile(&self.body).map_err(|e| {
+            AgentError::Config(format!("Prompt '{}': {}", self.reference(), e))
+        })?;
+        if let Some(undeclared) = template
+            .variables()
+            .into_iter()
+            .find(|name| !self.metadata.variables.contains(name))
+        {
+            return Err(AgentError::Config(format!(
+                "Prompt '{}' uses undeclared variable '{}'",
+                self.reference(),
+                undeclared
+            )));
+        }
+        Ok(template)
+    }
+
+    /// Render the prompt and record its reference on `conversation`
+    ///
+    /// # Errors
+    /// Returns an error if a declared variable is not supplied.
+    pub fn render_for(
+        &self,
+        conversation: &mut Conversation,
+        variables: &HashMap<&str, &str>,
+    ) -> Result<String> {
+        let rendered = self.render(variables)?;
+        conversation.record_prompt(self.reference());
+        Ok(rendered)
+    }
+}
+
+/// Split `---`-delimited front matter from the body
+fn split_front_matter(contents: &str) -> Option<(&str, &str)> {
+    let rest = contents
+        .strip_prefix("---\n")
+        .or_else(|| contents.strip_prefix("---\r\n"))?;
+    let end = rest.find("\n---")?;
+    let front = &rest[..end];
+    let body = &rest[end + 4..];
+    // The closing delimiter must be a line of its own
+    let body = match body.find('\n') {
+        Some(newline) if body[..newline].trim().is_empty() => &body[newline + 1..],
+        None if body.trim().is_empty() => "",
+        _ => return None,
+    };
+    Some((front, body))
+}
+
+/// Order version labels numerically when they look like `v<number>`
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let number = |v: &str| v.trim_start_matches('v').parse::<u64>().ok();
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        _ => a.cmp(b),
+    }
+}
+
+/// A library of named, versioned prompts
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::PromptStore;
+/// use std::collections::HashMap;
+/// use std::path::Path;
+///
+/// # fn example() -> agent_core::Result<()> {
+/// let prompts = PromptStore::load_dir(Path::new("prompts"))?;
+/// let planner = prompts.get("planner@v3").expect("prompt exists");
+/// let latest = prompts.get("planner").expect("prompt exists");
+///
+/// let system = planner.render(&HashMap::from([("tools", "calculator"), ("goal", "2+2")]))?;
+/// # let _ = (latest, system);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PromptStore {
+    prompts: HashMap<String, Vec<Prompt>>,
+}
+
+impl PromptStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every prompt file (`.md`, `.prompt`, `.txt`) in a directory
+    ///
+    /// # Errors
+    /// Returns an error if the directory or a file cannot be read, a file
+    /// cannot be parsed, or two files declare the same name and version.
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            AgentError::Config(format!(
+                "Failed to read prompt directory '{}': {}",
+                dir.display(),
+                e
+            ))
+        })?;
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| PROMPT_EXTENSIONS.contains(&ext))
+            })
+            .collect();
+        paths.sort();
+
+        let mut store = Self::new();
+        for path in paths {
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                AgentError::Config(format!(
+                    "Failed to read prompt file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            let mut prompt = Prompt::parse(&contents).map_err(|e| {
+                AgentError::Config(format!(
+                    "Failed to parse prompt file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            if prompt.metadata.name.is_empty()
+                && let Some(stem) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                prompt.metadata.name = stem.to_string();
+            }
+            store.insert(prompt)?;
+        }
+        Ok(store)
+    }
+
+    /// Add a prompt
+    ///
+    /// # Errors
+    /// Returns an error if the prompt has no name or the same name and
+    /// version is already present.
+    pub fn insert(&mut self, prompt: Prompt) -> Result<()> {
+        if prompt.metadata.name.is_empty() {
+            return Err(AgentError::Config("Prompt has no name".to_string()));
+        }
+        let versions = self
+            .prompts
+            .entry(prompt.metadata.name.clone())
+            .or_default();
+        if versions
+            .iter()
+            .any(|existing| existing.metadata.version == prompt.metadata.version)
+        {
+            return Err(AgentError::Config(format!(
+                "Duplicate prompt '{}'",
+                prompt.reference()
+            )));
+        }
+        versions.push(prompt);
+        versions.sort_by(|a, b| compare_versions(&a.metadata.version, &b.metadata.version));
+        Ok(())
+    }
+
+    /// Look up a prompt by `name@version`, or by `name` for the latest version
+    pub fn get(&self, reference: &str) -> Option<&Prompt> {
+        let (name, version) = match reference.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (reference, None),
+        };
+        let versions = self.prompts.get(name)?;
+        match version {
+            Some(version) => versions.iter().find(|p| p.metadata.version == version),
+            None => versions.last(),
+        }
+    }
+
+    /// Version labels available for a prompt, oldest first
+    pub fn versions(&self, name: &str) -> Vec<&str> {
+        self.prompts
+            .get(name)
+            .map(|versions| {
+                versions
+                    .iter()
+                    .map(|p| p.metadata.version.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Names of all prompts, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.prompts.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLANNER_V3: &str = "---
+name: planner
+version: v3
+model: gpt-4o
+variables: [goal]
+---
+Plan how to achieve: {{goal}}
+";
+
+    fn prompt(name: &str, version: &str) -> Prompt {
+        Prompt::parse(&format!(
+            "---\nname: {}\nversion: {}\n---\nBody {}",
+            name, version, version
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_front_matter() {
+        let prompt = Prompt::parse(PLANNER_V3).unwrap();
+        assert_eq!(prompt.reference(), "planner@v3");
+        assert_eq!(prompt.metadata.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(prompt.metadata.variables, vec!["goal"]);
+        assert_eq!(prompt.body, "Plan how to achieve: {{goal}}");
+    }
+
+    #[test]
+    fn test_parse_without_front_matter() {
+        let prompt = Prompt::parse("Just a prompt\n---\nwith a rule").unwrap();
+        assert_eq!(prompt.metadata.version, "v1");
+        assert_eq!(prompt.body, "Just a prompt\n---\nwith a rule");
+    }
+
+    #[test]
+    fn test_render_and_record() {
+        let prompt = Prompt::parse(PLANNER_V3).unwrap();
+        let mut conversation = Conversation::new();
+
+        let rendered = prompt
+            .render_for(&mut conversation, &HashMap::from([("goal", "ship it")]))
+            .unwrap();
+        assert_eq!(rendered, "Plan how to achieve: ship it");
+        assert_eq!(conversation.prompts(), ["planner@v3"]);
+
+        let missing = prompt.render(&HashMap::new());
+        assert!(missing.unwrap_err().to_string().contains("'goal'"));
+    }
+
+    #[test]
+    fn test_template_checks_declared_variables() {
+        let prompt = Prompt::parse(PLANNER_V3).unwrap();
+        let rendered = prompt
+            .template()
+            .unwrap()
+            .render(&HashMap::from([("goal", "ship it")]))
+            .unwrap();
+        assert_eq!(rendered, "Plan how to achieve: ship it");
+
+        let undeclared = Prompt::parse("---\nname: greeting\n---\nHi {{user}}").unwrap();
+        assert!(undeclared.template().unwrap_err().to_string().contains("'user'"));
+    }
+
+    #[test]
+    fn test_get_specific_and_latest_version() {
+        let mut store = PromptStore::new();
+        store.insert(prompt("planner", "v10")).unwrap();
+        store.insert(prompt("planner", "v2")).unwrap();
+        store.insert(prompt("planner", "v3")).unwrap();
+
+        assert_eq!(store.get("planner@v2").unwrap().body, "Body v2");
+        assert_eq!(store.get("planner").unwrap().metadata.version, "v10");
+        assert_eq!(store.versions("planner"), vec!["v2", "v3", "v10"]);
+        assert!(store.get("planner@v4").is_none());
+        assert!(store.get("unknown").is_none());
+        assert!(store.insert(prompt("planner", "v2")).is_err());
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("athena-prompts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("planner_v3.md"), PLANNER_V3).unwrap();
+        std::fs::write(dir.join("greeting.prompt"), "Say hello").unwrap();
+        std::fs::write(dir.join("notes.json"), "{}").unwrap();
+
+        let store = PromptStore::load_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(store.names(), vec!["greeting", "planner"]);
+        assert_eq!(store.get("greeting@v1").unwrap().body, "Say hello");
+        assert!(store.get("planner@v3").is_some());
+    }
+}
@@ -0,0 +1,580 @@
+use agent_core::{AgentError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// A compiled prompt with placeholders, conditionals and partials
+///
+/// Templates use a small subset of Handlebars:
+///
+/// - `{{name}}` and `{{user.name}}` insert a value from the render context;
+///   strings are inserted as they are, other values as JSON
+/// - `{{#if name}}...{{else}}...{{/if}}` and `{{#unless name}}...{{/unless}}`
+///   keep a section depending on a value; missing values, `null`, `false`,
+///   `0`, empty strings and empty arrays count as false
+/// - `{{> name}}` inserts a partial registered with
+///   [`PromptTemplateBuilder::partial`]
+/// - `{{! comment }}` is left out of the output
+///
+/// Block tags and comments alone on a line are removed with their line, so
+/// conditional sections can be written one tag per line.
+///
+/// Templates are parsed when they are built: unclosed or mismatched blocks,
+/// malformed tags, unknown partials and partials that include themselves
+/// are reported then rather than on the first render.
+///
+/// # Example
+///
+/// ```
+/// use llm::PromptTemplate;
+/// use serde_json::json;
+///
+/// # fn example() -> agent_core::Result<()> {
+/// let template = PromptTemplate::builder(
+///     "You are {{assistant}}.\n\
+///      {{#if tools}}\n\
+///      You can use: {{tools}}\n\
+///      {{/if}}\n\
+///      {{> rules}}",
+/// )
+/// .partial("rules", "Answer in {{language}}.")
+/// .build()?;
+///
+/// let prompt = template.render(&json!({
+///     "assistant": "a travel agent",
+///     "tools": "flights, hotels",
+///     "language": "French",
+/// }))?;
+/// assert_eq!(
+///     prompt,
+///     "You are a travel agent.\nYou can use: flights, hotels\nAnswer in French."
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    nodes: Vec<Node>,
+}
+
+impl PromptTemplate {
+    /// Compile a template that uses no partials
+    ///
+    /// # Errors
+    /// Returns a configuration error if the template is malformed or
+    /// includes a partial.
+    pub fn compile(source: &str) -> Result<Self> {
+        Self::builder(source).build()
+    }
+
+    /// Start a template that can include partials
+    pub fn builder(source: impl Into<String>) -> PromptTemplateBuilder {
+        PromptTemplateBuilder {
+            source: source.into(),
+            partials: HashMap::new(),
+        }
+    }
+
+    /// Render the template with the fields of `context`
+    ///
+    /// `context` is anything that serializes to a JSON object, such as a
+    /// `serde_json::Value`, a `HashMap<&str, &str>` or a struct deriving
+    /// `Serialize`.
+    ///
+    /// # Errors
+    /// Returns a configuration error if `context` cannot be serialized or
+    /// a placeholder's value is missing or `null`.
+    pub fn render<T: Serialize + ?Sized>(&self, context: &T) -> Result<String> {
+        let context = serde_json::to_value(context).map_err(|e| {
+            AgentError::Config(format!("Failed to serialize prompt template context: {}", e))
+        })?;
+        let mut rendered = String::new();
+        render_nodes(&self.nodes, &context, &mut rendered)?;
+        Ok(rendered)
+    }
+
+    /// Names of the top-level values the template reads, sorted
+    ///
+    /// Useful for checking a template against the variables declared in
+    /// its [`PromptMetadata`](crate::PromptMetadata).
+    pub fn variables(&self) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        collect_variables(&self.nodes, &mut names);
+        names.into_iter().collect()
+    }
+}
+
+/// Builder for a [`PromptTemplate`] that includes partials
+#[derive(Debug, Clone)]
+pub struct PromptTemplateBuilder {
+    source: String,
+    partials: HashMap<String, String>,
+}
+
+impl PromptTemplateBuilder {
+    /// Register a partial included with `{{> name}}`
+    ///
+    /// Partials may include other partials.
+    pub fn partial(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.partials.insert(name.into(), source.into());
+        self
+    }
+
+    /// Compile the template and its partials
+    ///
+    /// # Errors
+    /// Returns a configuration error if the template or a partial is
+    /// malformed, includes an unknown partial, or a partial includes itself.
+    pub fn build(self) -> Result<PromptTemplate> {
+        let mut partials = HashMap::new();
+        for (name, source) in &self.partials {
+            let nodes = parse(source)
+                .map_err(|e| AgentError::Config(format!("Invalid partial '{}': {}", name, e)))?;
+            partials.insert(name.as_str(), nodes);
+        }
+        let nodes = parse(&self.source)
+            .map_err(|e| AgentError::Config(format!("Invalid prompt template: {}", e)))?;
+        let nodes = expand(nodes, &partials, &mut Vec::new())?;
+        Ok(PromptTemplate { nodes })
+    }
+}
+
+/// A parsed piece of a template
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(Vec<String>),
+    Conditional {
+        path: Vec<String>,
+        negated: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Partial(String),
+}
+
+/// A `{{...}}` tag
+#[derive(Debug, Clone, PartialEq)]
+enum Tag {
+    Variable(Vec<String>),
+    Open { block: String, path: Vec<String> },
+    Else,
+    Close(String),
+    Partial(String),
+    Comment,
+}
+
+impl Tag {
+    /// Whether the tag is removed with its line when alone on it
+    fn is_standalone_kind(&self) -> bool {
+        matches!(
+            self,
+            Tag::Open { .. } | Tag::Else | Tag::Close(_) | Tag::Comment
+        )
+    }
+}
+
+/// Parse errors are plain descriptions, wrapped in an `AgentError` by
+/// the builder
+type ParseResult<T> = std::result::Result<T, String>;
+
+/// The `{{else}}` or closing tag that ends a section, with its line
+type Terminator = (Tag, usize);
+
+/// A `{{...}}` tag with the line it starts on, or the text between tags
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Tag(Tag, usize),
+}
+
+/// Parse template source into nodes
+fn parse(source: &str) -> ParseResult<Vec<Node>> {
+    let mut tokens = tokenize(source)?.into_iter();
+    let (nodes, end) = parse_nodes(&mut tokens)?;
+    match end {
+        None => Ok(nodes),
+        Some((Tag::Else, line)) => Err(format!("{{{{else}}}} outside a block at line {}", line)),
+        Some((Tag::Close(block), line)) => Err(format!(
+            "{{{{/{}}}}} without a matching {{{{#{}}}}} at line {}",
+            block, block, line
+        )),
+        Some((_, line)) => Err(format!("unexpected tag at line {}", line)),
+    }
+}
+
+/// Parse nodes up to the next `{{else}}` or closing tag, which is returned
+fn parse_nodes(
+    tokens: &mut impl Iterator<Item = Token>,
+) -> ParseResult<(Vec<Node>, Option<Terminator>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Tag(Tag::Variable(path), _) => nodes.push(Node::Variable(path)),
+            Token::Tag(Tag::Partial(name), _) => nodes.push(Node::Partial(name)),
+            Token::Tag(Tag::Comment, _) => {}
+            Token::Tag(Tag::Open { block, path }, line) => {
+                let (then, mut end) = parse_nodes(tokens)?;
+                let mut otherwise = Vec::new();
+                if let Some((Tag::Else, _)) = end {
+                    (otherwise, end) = parse_nodes(tokens)?;
+                }
+                match end {
+                    Some((Tag::Close(closed), _)) if closed == block => {}
+                    Some((Tag::Close(closed), close_line)) => {
+                        return Err(format!(
+                            "{{{{/{}}}}} at line {} closes {{{{#{}}}}} opened at line {}",
+                            closed, close_line, block, line
+                        ));
+                    }
+                    Some((Tag::Else, else_line)) => {
+                        return Err(format!("second {{{{else}}}} at line {}", else_line));
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{{{{#{}}}}} opened at line {} is never closed",
+                            block, line
+                        ));
+                    }
+                }
+                nodes.push(Node::Conditional {
+                    path,
+                    negated: block == "unless",
+                    then,
+                    otherwise,
+                });
+            }
+            Token::Tag(tag, line) => return Ok((nodes, Some((tag, line)))),
+        }
+    }
+    Ok((nodes, None))
+}
+
+/// Split source into text and tags, removing standalone block lines
+fn tokenize(source: &str) -> ParseResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    while let Some(start) = rest.find("{{") {
+        let text = &rest[..start];
+        line += text.matches('\n').count();
+        if !text.is_empty() {
+            tokens.push(Token::Text(text.to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("unclosed '{{{{' at line {}", line))?;
+        let content = &after[..end];
+        tokens.push(Token::Tag(parse_tag(content, line)?, line));
+        line += content.matches('\n').count();
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    strip_standalone(&mut tokens);
+    Ok(tokens)
+}
+
+fn parse_tag(content: &str, line: usize) -> ParseResult<Tag> {
+    if content.starts_with('!') {
+        return Ok(Tag::Comment);
+    }
+    let content = content.trim();
+    if content == "else" {
+        return Ok(Tag::Else);
+    }
+    if let Some(name) = content.strip_prefix('>') {
+        return Ok(Tag::Partial(parse_name(name.trim(), line)?));
+    }
+    if let Some(block) = content.strip_prefix('/') {
+        return match block.trim() {
+            block @ ("if" | "unless") => Ok(Tag::Close(block.to_string())),
+            block => Err(format!("unknown block '{}' at line {}", block, line)),
+        };
+    }
+    if let Some(open) = content.strip_prefix('#') {
+        let (block, path) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+        if !matches!(block, "if" | "unless") {
+            return Err(format!("unknown block '{}' at line {}", block, line));
+        }
+        return Ok(Tag::Open {
+            block: block.to_string(),
+            path: parse_path(path.trim(), line)?,
+        });
+    }
+    Ok(Tag::Variable(parse_path(content, line)?))
+}
+
+fn parse_path(path: &str, line: usize) -> ParseResult<Vec<String>> {
+    path.split('.')
+        .map(|segment| parse_name(segment, line))
+        .collect()
+}
+
+fn parse_name(name: &str, line: usize) -> ParseResult<String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("invalid name '{}' at line {}", name, line))
+    }
+}
+
+/// Remove block tags and comments that are alone on their line, along
+/// with the line's indentation and line break
+fn strip_standalone(tokens: &mut [Token]) {
+    let standalone: Vec<(usize, usize, usize)> = (0..tokens.len())
+        .filter_map(|index| {
+            let Token::Tag(tag, _) = &tokens[index] else {
+                return None;
+            };
+            if !tag.is_standalone_kind() {
+                return None;
+            }
+            // Indentation to remove before the tag
+            let before = match index.checked_sub(1).map(|i| &tokens[i]) {
+                None => 0,
+                Some(Token::Text(text)) => {
+                    let line_start = text.rfind('\n').map_or(0, |newline| newline + 1);
+                    let indentation = &text[line_start..];
+                    if (line_start == 0 && index > 1) || !indentation.trim().is_empty() {
+                        return None;
+                    }
+                    indentation.len()
+                }
+                Some(Token::Tag(..)) => return None,
+            };
+            // Trailing whitespace and line break to remove after the tag
+            let after = match tokens.get(index + 1) {
+                None => 0,
+                Some(Token::Text(text)) => {
+                    let line_end = text.find('\n').map_or(text.len(), |newline| newline + 1);
+                    if (line_end == text.len() && !text.ends_with('\n') && index + 2 < tokens.len())
+                        || !text[..line_end].trim().is_empty()
+                    {
+                        return None;
+                    }
+                    line_end
+                }
+                Some(Token::Tag(..)) => return None,
+            };
+            Some((index, before, after))
+        })
+        .collect();
+
+    for (index, before, after) in standalone {
+        if let Some(Token::Text(text)) = index.checked_sub(1).map(|i| &mut tokens[i]) {
+            text.truncate(text.len() - before);
+        }
+        if let Some(Token::Text(text)) = tokens.get_mut(index + 1) {
+            text.drain(..after);
+        }
+    }
+}
+
+/// Replace partial nodes with the partials' nodes
+fn expand<'a>(
+    nodes: Vec<Node>,
+    partials: &HashMap<&'a str, Vec<Node>>,
+    including: &mut Vec<&'a str>,
+) -> Result<Vec<Node>> {
+    let mut expanded = Vec::new();
+    for node in nodes {
+        match node {
+            Node::Partial(name) => {
+                let Some((&key, partial)) = partials.get_key_value(name.as_str()) else {
+                    return Err(AgentError::Config(format!(
+                        "Prompt template includes unknown partial '{}'",
+                        name
+                    )));
+                };
+                if including.contains(&key) {
+                    return Err(AgentError::Config(format!(
+                        "Partial '{}' includes itself",
+                        name
+                    )));
+                }
+                including.push(key);
+                expanded.extend(expand(partial.clone(), partials, including)?);
+                including.pop();
+            }
+            Node::Conditional {
+                path,
+                negated,
+                then,
+                otherwise,
+            } => expanded.push(Node::Conditional {
+                path,
+                negated,
+                then: expand(then, partials, including)?,
+                otherwise: expand(otherwise, partials, including)?,
+            }),
+            node => expanded.push(node),
+        }
+    }
+    Ok(expanded)
+}
+
+fn render_nodes(nodes: &[Node], context: &Value, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Variable(path) => match lookup(context, path) {
+                Some(Value::String(value)) => out.push_str(value),
+                Some(Value::Null) | None => {
+                    return Err(AgentError::Config(format!(
+                        "Prompt template variable '{}' is not set",
+                        path.join(".")
+                    )));
+                }
+                Some(value) => out.push_str(&value.to_string()),
+            },
+            Node::Conditional {
+                path,
+                negated,
+                then,
+                otherwise,
+            } => {
+                let section = if is_truthy(lookup(context, path)) != *negated {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(section, context, out)?;
+            }
+            // Partials are expanded when the template is built
+            Node::Partial(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn lookup<'a>(context: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(context, |value, segment| match value {
+        Value::Object(fields) => fields.get(segment),
+        Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(value)) => *value,
+        Some(Value::Number(number)) => number.as_f64() != Some(0.0),
+        Some(Value::String(value)) => !value.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(Value::Object(_)) => true,
+    }
+}
+
+fn collect_variables(nodes: &[Node], names: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            Node::Variable(path) => {
+                names.insert(path[0].clone());
+            }
+            Node::Conditional {
+                path,
+                then,
+                otherwise,
+                ..
+            } => {
+                names.insert(path[0].clone());
+                collect_variables(then, names);
+                collect_variables(otherwise, names);
+            }
+            Node::Text(_) | Node::Partial(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_placeholders() {
+        let template = PromptTemplate::compile("Hi {{ user.name }}, you have {{count}} {{items.0}}.")
+            .unwrap();
+        let rendered = template
+            .render(&json!({"user": {"name": "Ada"}, "count": 3, "items": ["tasks"]}))
+            .unwrap();
+        assert_eq!(rendered, "Hi Ada, you have 3 tasks.");
+        assert_eq!(template.variables(), ["count", "items", "user"]);
+
+        let rendered = PromptTemplate::compile("Goal: {{goal}}")
+            .unwrap()
+            .render(&HashMap::from([("goal", "ship it")]))
+            .unwrap();
+        assert_eq!(rendered, "Goal: ship it");
+
+        let error = template.render(&json!({"count": 3})).unwrap_err();
+        assert!(error.to_string().contains("'user.name'"));
+    }
+
+    #[test]
+    fn test_conditionals_and_standalone_lines() {
+        let template = PromptTemplate::compile(
+            "Rules:\n  {{#if strict}}\n- Never guess\n  {{else}}\n- Guess when unsure\n  {{/if}}\n{{! internal note }}\n{{#unless tools}}\nNo tools.\n{{/unless}}\nEnd: {{#if strict}}strict{{/if}}",
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&json!({"strict": true, "tools": []})).unwrap(),
+            "Rules:\n- Never guess\nNo tools.\nEnd: strict"
+        );
+        assert_eq!(
+            template.render(&json!({"tools": ["search"]})).unwrap(),
+            "Rules:\n- Guess when unsure\nEnd: "
+        );
+    }
+
+    #[test]
+    fn test_partials() {
+        let template = PromptTemplate::builder("{{> header}}\nAsk: {{question}}")
+            .partial("header", "You are {{role}}. {{> tone}}")
+            .partial("tone", "Be brief.")
+            .build()
+            .unwrap();
+        assert_eq!(
+            template
+                .render(&json!({"role": "a tutor", "question": "why?"}))
+                .unwrap(),
+            "You are a tutor. Be brief.\nAsk: why?"
+        );
+        assert_eq!(template.variables(), ["question", "role"]);
+
+        let recursive = PromptTemplate::builder("{{> a}}")
+            .partial("a", "{{> b}}")
+            .partial("b", "{{> a}}")
+            .build();
+        assert!(recursive.unwrap_err().to_string().contains("includes itself"));
+        let unknown = PromptTemplate::compile("{{> missing}}").unwrap_err();
+        assert!(unknown.to_string().contains("'missing'"));
+    }
+
+    #[test]
+    fn test_malformed_templates_are_rejected() {
+        let cases = [
+            ("{{#if a}}\nopen", "never closed"),
+            ("{{#if a}}{{/unless}}", "closes"),
+            ("{{/if}}", "without a matching"),
+            ("{{else}}", "outside a block"),
+            ("{{#each items}}{{/each}}", "unknown block 'each'"),
+            ("line\n{{name", "unclosed '{{' at line 2"),
+            ("{{bad name}}", "invalid name"),
+            ("{{}}", "invalid name"),
+        ];
+        for (source, message) in cases {
+            let error = PromptTemplate::compile(source).unwrap_err().to_string();
+            assert!(error.contains(message), "{}: {}", source, error);
+        }
+    }
+}
@@ -1,5 +1,14 @@
-use agent_core::{Message, Result};
+use agent_core::{Message, Result, ToolDefinition, ToolUseResponse};
 use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::cancellation::{CancellationToken, cancellable};
+use crate::continuation::{CompletionResponse, FinishReason};
+use crate::streaming::{StreamEvent, TextStream, TokenStream, text_chunks};
+use crate::structured::{StructuredOutput, send_with_repair};
+use crate::tool_choice::ToolConfig;
+use crate::tool_use::send_with_prompted_tools;
 
 /// Trait for LLM provider implementations
 /// 
@@ -15,4 +24,222 @@ pub trait LLMProvider: Send + Sync {
     /// # Returns
     /// * `Result<String>` - The LLM's response text or an error
     async fn send_message(&self, messages: &[Message]) -> Result<String>;
+
+    /// Send messages, giving up as soon as `cancel` is cancelled
+    ///
+    /// The in-flight request is dropped on cancellation, so the connection
+    /// is closed rather than left to finish in the background.
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    /// * `cancel` - Token that aborts the request when cancelled
+    ///
+    /// # Returns
+    /// * `Result<String>` - The LLM's response text, or
+    ///   [`AgentError::Cancelled`](agent_core::AgentError::Cancelled)
+    async fn send_message_cancellable(
+        &self,
+        messages: &[Message],
+        cancel: &CancellationToken,
+    ) -> Result<String> {
+        cancellable(cancel, self.send_message(messages)).await
+    }
+
+    /// Send messages and receive the response with its stop reason
+    ///
+    /// OpenAI and Anthropic report why generation stopped, so callers can
+    /// tell a finished response from one cut off by the token limit. Other
+    /// providers report [`FinishReason::Unknown`].
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    ///
+    /// # Returns
+    /// * `Result<CompletionResponse>` - The response text and stop reason or an error
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let text = self.send_message(messages).await?;
+        Ok(CompletionResponse::new(text, FinishReason::Unknown))
+    }
+
+    /// Send messages and receive a JSON value matching a schema
+    ///
+    /// Malformed or non-conforming responses are sent back to the model for
+    /// correction, up to `output.max_repair_attempts` times.
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    /// * `output` - The schema and repair settings
+    ///
+    /// # Returns
+    /// * `Result<Value>` - The validated JSON value or an error
+    async fn send_structured(&self, messages: &[Message], output: &StructuredOutput) -> Result<Value> {
+        send_with_repair(self, messages, output).await
+    }
+
+    /// Send messages and receive a JSON value matching a schema, along with
+    /// the metadata of the response
+    ///
+    /// OpenAI and Anthropic report the token usage summed over repair
+    /// attempts and the model name. Other providers return the value from
+    /// [`send_structured`](Self::send_structured) with no usage.
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    /// * `output` - The schema and repair settings
+    ///
+    /// # Returns
+    /// * `Result<(Value, CompletionResponse)>` - The validated JSON value and
+    ///   the final response or an error
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        let value = self.send_structured(messages, output).await?;
+        let response = CompletionResponse::new(value.to_string(), FinishReason::Unknown);
+        Ok((value, response))
+    }
+
+    /// Send messages and stream the response as it is generated
+    ///
+    /// Providers without streaming support return the whole response as a
+    /// single text event. Dropping the stream aborts the request.
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    ///
+    /// # Returns
+    /// * `Result<TokenStream>` - A stream of response events or an error
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let text = self.send_message(messages).await?;
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(StreamEvent::Text(text))
+        })))
+    }
+
+    /// Send messages and stream the response text as it is generated
+    ///
+    /// Yields the text chunks of [`stream_message`](Self::stream_message),
+    /// without usage or stop events. Providers with server-sent event
+    /// support (OpenAI, Anthropic) yield tokens as they arrive; others yield
+    /// the whole response as one chunk.
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    ///
+    /// # Returns
+    /// * `Result<TextStream>` - A stream of text chunks or an error
+    async fn send_message_stream(&self, messages: &[Message]) -> Result<TextStream> {
+        Ok(text_chunks(self.stream_message(messages).await?))
+    }
+
+    /// Send messages with tools the model may call
+    ///
+    /// Anthropic and OpenAI use their native tool calling (`tool_use`
+    /// blocks and function calling). Other providers describe the tools in a
+    /// system message and request the calls as structured output.
+    ///
+    /// # Arguments
+    /// * `messages` - A slice of messages representing the conversation history
+    /// * `tools` - The tools the model may call
+    /// * `config` - Whether and how the model may call them
+    ///
+    /// # Returns
+    /// * `Result<ToolUseResponse>` - The model's text and requested tool calls
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        send_with_prompted_tools(self, messages, tools, config).await
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider + ?Sized> LLMProvider for Box<P> {
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        (**self).stream_message(messages).await
+    }
+
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        (**self).send_message(messages).await
+    }
+
+    async fn send_message_cancellable(
+        &self,
+        messages: &[Message],
+        cancel: &CancellationToken,
+    ) -> Result<String> {
+        (**self).send_message_cancellable(messages, cancel).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        (**self).send_completion(messages).await
+    }
+
+    async fn send_structured(&self, messages: &[Message], output: &StructuredOutput) -> Result<Value> {
+        (**self).send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        (**self).send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        (**self).send_message_with_tools(messages, tools, config).await
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider + ?Sized> LLMProvider for Arc<P> {
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        (**self).stream_message(messages).await
+    }
+
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        (**self).send_message(messages).await
+    }
+
+    async fn send_message_cancellable(
+        &self,
+        messages: &[Message],
+        cancel: &CancellationToken,
+    ) -> Result<String> {
+        (**self).send_message_cancellable(messages, cancel).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        (**self).send_completion(messages).await
+    }
+
+    async fn send_structured(&self, messages: &[Message], output: &StructuredOutput) -> Result<Value> {
+        (**self).send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        (**self).send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        (**self).send_message_with_tools(messages, tools, config).await
+    }
 }
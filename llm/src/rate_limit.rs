@@ -0,0 +1,433 @@
+//! Token-bucket rate limits shared across processes.
+//!
+//! Provider rate limits apply to the whole organisation, but every instance
+//! of a service only sees its own traffic. A [`RateLimiter`] keeps one token
+//! bucket per provider in a [`RateLimitBackend`]: [`LocalRateLimitBackend`]
+//! for a single process, or [`RedisRateLimitBackend`] so that every instance
+//! pointing at the same Redis draws from one shared budget.
+//! [`RateLimitedProvider`] takes a token before each request and waits when
+//! the bucket is empty.
+
+use agent_core::{AgentError, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::{CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig};
+
+/// Atomically refill a bucket stored as a Redis hash and try to take `cost`
+/// tokens. Returns 0 on success, otherwise the milliseconds to wait.
+///
+/// The Redis server clock is used so that instances with skewed clocks
+/// agree on the refill.
+const REDIS_TAKE_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or capacity
+local ts = tonumber(state[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - ts) * rate)
+local wait = 0
+if tokens >= cost then
+  tokens = tokens - cost
+else
+  wait = math.ceil((cost - tokens) / rate)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate) + 1000)
+return wait
+"#;
+
+/// Size and refill rate of a token bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBucket {
+    /// Maximum number of tokens, i.e. the largest allowed burst
+    pub capacity: u32,
+    /// Tokens added per second
+    pub refill_per_second: f64,
+}
+
+impl TokenBucket {
+    /// Create a bucket
+    pub fn new(capacity: u32, refill_per_second: f64) -> Self {
+        Self {
+            capacity,
+            refill_per_second,
+        }
+    }
+
+    /// A bucket allowing `requests` per minute, with bursts of the same size
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, f64::from(requests) / 60.0)
+    }
+
+    /// Time needed to refill `missing` tokens
+    fn refill_time(&self, missing: f64) -> Duration {
+        Duration::from_secs_f64(missing / self.refill_per_second)
+    }
+}
+
+/// Storage for token buckets
+#[async_trait]
+pub trait RateLimitBackend: Send + Sync {
+    /// Try to take `cost` tokens from the bucket under `key`
+    ///
+    /// Returns `Duration::ZERO` if the tokens were taken, or how long to
+    /// wait before enough tokens are available. Nothing is taken on a
+    /// non-zero result.
+    async fn try_acquire(&self, key: &str, bucket: &TokenBucket, cost: u32) -> Result<Duration>;
+}
+
+/// In-process bucket storage, for services running as a single instance
+#[derive(Debug, Default)]
+pub struct LocalRateLimitBackend {
+    /// Tokens left and time of the last refill, per key
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl LocalRateLimitBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimitBackend for LocalRateLimitBackend {
+    async fn try_acquire(&self, key: &str, bucket: &TokenBucket, cost: u32) -> Result<Duration> {
+        let capacity = f64::from(bucket.capacity);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let (tokens, refilled_at) = buckets.entry(key.to_string()).or_insert((capacity, now));
+
+        let elapsed = now.duration_since(*refilled_at).as_secs_f64();
+        *tokens = (*tokens + elapsed * bucket.refill_per_second).min(capacity);
+        *refilled_at = now;
+
+        let cost = f64::from(cost);
+        if *tokens >= cost {
+            *tokens -= cost;
+            Ok(Duration::ZERO)
+        } else {
+            Ok(bucket.refill_time(cost - *tokens))
+        }
+    }
+}
+
+/// Bucket storage in Redis, shared by every process using the same server
+///
+/// Each bucket is a hash under `<prefix><provider>` that expires once it
+/// would have refilled completely, so idle providers leave no keys behind.
+pub struct RedisRateLimitBackend {
+    client: redis::Client,
+    connection: OnceCell<redis::aio::MultiplexedConnection>,
+    script: redis::Script,
+    prefix: String,
+}
+
+impl RedisRateLimitBackend {
+    /// Create a backend for the Redis server at `url`
+    ///
+    /// The connection is opened on first use.
+    ///
+    /// # Errors
+    /// Returns an error if `url` is not a valid Redis URL.
+    pub fn new(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| AgentError::Config(format!("Invalid Redis URL: {}", e)))?;
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+            script: redis::Script::new(REDIS_TAKE_SCRIPT),
+            prefix: "athena:ratelimit:".to_string(),
+        })
+    }
+
+    /// Set the prefix of the bucket keys, e.g. to separate environments
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Redis key holding the bucket for `key`
+    fn bucket_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        self.connection
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await
+            .cloned()
+            .map_err(|e| AgentError::Execution(format!("Redis connection failed: {}", e)))
+    }
+}
+
+#[async_trait]
+impl RateLimitBackend for RedisRateLimitBackend {
+    async fn try_acquire(&self, key: &str, bucket: &TokenBucket, cost: u32) -> Result<Duration> {
+        let mut connection = self.connection().await?;
+        let wait_ms: u64 = self
+            .script
+            .key(self.bucket_key(key))
+            .arg(bucket.capacity)
+            .arg(bucket.refill_per_second / 1000.0)
+            .arg(cost)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(|e| AgentError::Execution(format!("Redis rate limit failed: {}", e)))?;
+        Ok(Duration::from_millis(wait_ms))
+    }
+}
+
+/// Per-provider token buckets in a shared backend
+///
+/// Providers without a configured bucket are not limited.
+pub struct RateLimiter {
+    backend: Arc<dyn RateLimitBackend>,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Create a limiter storing its buckets in `backend`
+    pub fn new(backend: Arc<dyn RateLimitBackend>) -> Self {
+        Self {
+            backend,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Create a limiter with in-process buckets
+    pub fn local() -> Self {
+        Self::new(Arc::new(LocalRateLimitBackend::new()))
+    }
+
+    /// Limit requests to `provider` with `bucket`
+    pub fn with_bucket(mut self, provider: impl Into<String>, bucket: TokenBucket) -> Self {
+        self.buckets.insert(provider.into(), bucket);
+        self
+    }
+
+    /// Wait until `cost` tokens can be taken from `provider`'s bucket
+    ///
+    /// # Errors
+    /// Returns an error if `cost` exceeds the bucket capacity, since it
+    /// could never be satisfied, or if the backend fails.
+    pub async fn acquire(&self, provider: &str, cost: u32) -> Result<()> {
+        let Some(bucket) = self.buckets.get(provider) else {
+            return Ok(());
+        };
+        if cost > bucket.capacity {
+            return Err(AgentError::Config(format!(
+                "Rate limit cost {} exceeds the capacity {} of the '{}' bucket",
+                cost, bucket.capacity, provider
+            )));
+        }
+        loop {
+            let wait = self.backend.try_acquire(provider, bucket, cost).await?;
+            if wait.is_zero() {
+                return Ok(());
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Provider wrapper that takes one token from a [`RateLimiter`] per request
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{OpenAIProvider, RateLimitedProvider, RateLimiter, RedisRateLimitBackend, TokenBucket};
+/// use std::sync::Arc;
+/// # use config::LLMConfig;
+///
+/// # fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// // Every instance connected to this Redis shares 500 requests per minute
+/// let backend = Arc::new(RedisRateLimitBackend::new("redis://127.0.0.1/")?);
+/// let limiter = Arc::new(
+///     RateLimiter::new(backend).with_bucket("openai", TokenBucket::per_minute(500)),
+/// );
+/// let provider = RateLimitedProvider::new(OpenAIProvider::new(&config)?, "openai", limiter);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RateLimitedProvider<P: LLMProvider> {
+    inner: P,
+    provider: String,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<P: LLMProvider> RateLimitedProvider<P> {
+    /// Wrap a provider; `provider` selects the bucket
+    pub fn new(inner: P, provider: impl Into<String>, limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            inner,
+            provider: provider.into(),
+            limiter,
+        }
+    }
+
+    /// Get a reference to the wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for RateLimitedProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.limiter.acquire(&self.provider, 1).await?;
+        self.inner.send_message(messages).await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.limiter.acquire(&self.provider, 1).await?;
+        self.inner.send_completion(messages).await
+    }
+
+    /// Repair rounds of the inner provider are not counted separately
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.limiter.acquire(&self.provider, 1).await?;
+        self.inner.send_structured(messages, output).await
+    }
+
+    /// Repair rounds of the inner provider are not counted separately
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.limiter.acquire(&self.provider, 1).await?;
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.limiter.acquire(&self.provider, 1).await?;
+        self.inner.send_message_with_tools(messages, tools, config).await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.limiter.acquire(&self.provider, 1).await?;
+        self.inner.stream_message(messages).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoProvider;
+
+    #[async_trait]
+    impl LLMProvider for EchoProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(messages
+                .last()
+                .map(|m| m.content.clone())
+                .unwrap_or_default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_bucket_drains_and_refills() {
+        let backend = LocalRateLimitBackend::new();
+        let bucket = TokenBucket::new(2, 20.0);
+
+        assert!(
+            backend
+                .try_acquire("openai", &bucket, 1)
+                .await
+                .unwrap()
+                .is_zero()
+        );
+        assert!(
+            backend
+                .try_acquire("openai", &bucket, 1)
+                .await
+                .unwrap()
+                .is_zero()
+        );
+        let wait = backend.try_acquire("openai", &bucket, 1).await.unwrap();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(50));
+
+        // Buckets are independent per key
+        assert!(
+            backend
+                .try_acquire("anthropic", &bucket, 2)
+                .await
+                .unwrap()
+                .is_zero()
+        );
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(
+            backend
+                .try_acquire("openai", &bucket, 1)
+                .await
+                .unwrap()
+                .is_zero()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limiter_waits_for_tokens() {
+        let limiter = RateLimiter::local().with_bucket("openai", TokenBucket::new(1, 20.0));
+        let started = Instant::now();
+        limiter.acquire("openai", 1).await.unwrap();
+        limiter.acquire("openai", 1).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        // Unconfigured providers pass straight through
+        limiter.acquire("ollama", 100).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cost_above_capacity_is_rejected() {
+        let limiter = RateLimiter::local().with_bucket("openai", TokenBucket::per_minute(10));
+        let result = limiter.acquire("openai", 11).await;
+        assert!(matches!(result, Err(AgentError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_limited_provider_shares_bucket() {
+        let limiter = Arc::new(RateLimiter::local().with_bucket("echo", TokenBucket::new(1, 20.0)));
+        let first = RateLimitedProvider::new(EchoProvider, "echo", limiter.clone());
+        let second = RateLimitedProvider::new(EchoProvider, "echo", limiter);
+
+        let started = Instant::now();
+        assert_eq!(
+            first.send_message(&[Message::user("a")]).await.unwrap(),
+            "a"
+        );
+        assert_eq!(
+            second.send_message(&[Message::user("b")]).await.unwrap(),
+            "b"
+        );
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_redis_backend_keys() {
+        let backend = RedisRateLimitBackend::new("redis://127.0.0.1/")
+            .unwrap()
+            .with_prefix("staging:rl:");
+        assert_eq!(backend.bucket_key("openai"), "staging:rl:openai");
+        assert!(RedisRateLimitBackend::new("not a url").is_err());
+    }
+}
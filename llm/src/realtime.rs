@@ -0,0 +1,451 @@
+//! OpenAI Realtime API sessions.
+//!
+//! A [`RealtimeSession`] holds a WebSocket connection to the Realtime API and
+//! exchanges events in both directions: the client streams user audio or text
+//! in, and the server streams text, audio and turn-detection events back.
+//! With server-side voice activity detection (VAD) enabled the server decides
+//! when the user has finished speaking and starts a response on its own.
+
+use agent_core::{AgentError, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use communication::{WebSocketConnection, WebSocketReceiver, WebSocketSender};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// Default Realtime API endpoint
+const DEFAULT_URL: &str = "wss://api.openai.com/v1/realtime";
+
+/// Default realtime model
+const DEFAULT_MODEL: &str = "gpt-4o-realtime-preview";
+
+/// Server-side voice activity detection settings
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerVad {
+    /// Activation threshold between 0 and 1; higher requires louder speech
+    pub threshold: f32,
+    /// Audio kept before detected speech, in milliseconds
+    pub prefix_padding_ms: u32,
+    /// Silence that ends a turn, in milliseconds
+    pub silence_duration_ms: u32,
+}
+
+impl Default for ServerVad {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            prefix_padding_ms: 300,
+            silence_duration_ms: 500,
+        }
+    }
+}
+
+/// Configuration for a realtime session
+#[derive(Debug, Clone)]
+pub struct RealtimeConfig {
+    /// Realtime model name
+    pub model: String,
+    /// System instructions for the session
+    pub instructions: Option<String>,
+    /// Voice used for audio output
+    pub voice: String,
+    /// Whether the server should produce audio in addition to text
+    pub audio_output: bool,
+    /// Server VAD settings; `None` disables automatic turn detection
+    pub turn_detection: Option<ServerVad>,
+    /// WebSocket endpoint, overridable for proxies and tests
+    pub url: String,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            model: DEFAULT_MODEL.to_string(),
+            instructions: None,
+            voice: "alloy".to_string(),
+            audio_output: true,
+            turn_detection: Some(ServerVad::default()),
+            url: DEFAULT_URL.to_string(),
+        }
+    }
+}
+
+impl RealtimeConfig {
+    /// Build the `session.update` event describing this configuration
+    fn session_update(&self) -> Value {
+        let modalities: &[&str] = if self.audio_output {
+            &["text", "audio"]
+        } else {
+            &["text"]
+        };
+        let turn_detection = match &self.turn_detection {
+            Some(vad) => json!({
+                "type": "server_vad",
+                "threshold": vad.threshold,
+                "prefix_padding_ms": vad.prefix_padding_ms,
+                "silence_duration_ms": vad.silence_duration_ms,
+            }),
+            None => Value::Null,
+        };
+
+        let mut session = json!({
+            "modalities": modalities,
+            "voice": self.voice,
+            "input_audio_format": "pcm16",
+            "output_audio_format": "pcm16",
+            "turn_detection": turn_detection,
+        });
+        if let Some(instructions) = &self.instructions {
+            session["instructions"] = json!(instructions);
+        }
+
+        json!({ "type": "session.update", "session": session })
+    }
+}
+
+/// An event received from the Realtime API
+#[derive(Debug, Clone, PartialEq)]
+pub enum RealtimeEvent {
+    /// The session was created or its configuration updated
+    SessionUpdated,
+    /// Server VAD detected the start of user speech
+    SpeechStarted,
+    /// Server VAD detected the end of user speech
+    SpeechStopped,
+    /// Transcript of the user's spoken input
+    InputTranscript(String),
+    /// A chunk of response text
+    TextDelta(String),
+    /// A chunk of the transcript of response audio
+    TranscriptDelta(String),
+    /// A chunk of response audio as raw PCM16 bytes
+    AudioDelta(Vec<u8>),
+    /// The current response finished
+    ResponseDone,
+    /// The server reported an error
+    Error(String),
+    /// Any other event, passed through unparsed
+    Other(Value),
+}
+
+impl RealtimeEvent {
+    /// Parse a raw server event
+    fn parse(event: Value) -> Result<Self> {
+        let text = |field: &str| event[field].as_str().unwrap_or_default().to_string();
+
+        let parsed = match event["type"].as_str().unwrap_or_default() {
+            "session.created" | "session.updated" => Self::SessionUpdated,
+            "input_audio_buffer.speech_started" => Self::SpeechStarted,
+            "input_audio_buffer.speech_stopped" => Self::SpeechStopped,
+            "conversation.item.input_audio_transcription.completed" => {
+                Self::InputTranscript(text("transcript"))
+            }
+            "response.text.delta" => Self::TextDelta(text("delta")),
+            "response.audio_transcript.delta" => Self::TranscriptDelta(text("delta")),
+            "response.audio.delta" => {
+                let audio = STANDARD.decode(text("delta")).map_err(|e| {
+                    AgentError::LLMProvider(format!("Invalid realtime audio chunk: {}", e))
+                })?;
+                Self::AudioDelta(audio)
+            }
+            "response.done" => Self::ResponseDone,
+            "error" => Self::Error(
+                event["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string(),
+            ),
+            _ => Self::Other(event),
+        };
+        Ok(parsed)
+    }
+}
+
+/// A bidirectional session with the OpenAI Realtime API
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{RealtimeConfig, RealtimeEvent, RealtimeSession};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let mut session = RealtimeSession::connect("sk-...", RealtimeConfig::default()).await?;
+/// session.send_text("Hello!").await?;
+/// while let Some(event) = session.next_event().await? {
+///     match event {
+///         RealtimeEvent::TextDelta(text) => print!("{}", text),
+///         RealtimeEvent::ResponseDone => break,
+///         _ => {}
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RealtimeSession {
+    sender: RealtimeSender,
+    receiver: RealtimeReceiver,
+}
+
+impl RealtimeSession {
+    /// Connect and configure a session
+    ///
+    /// Sends a `session.update` with the given configuration immediately
+    /// after the connection is established.
+    pub async fn connect(api_key: &str, config: RealtimeConfig) -> Result<Self> {
+        let url = format!("{}?model={}", config.url, config.model);
+        let headers = [
+            ("Authorization".to_string(), format!("Bearer {}", api_key)),
+            ("OpenAI-Beta".to_string(), "realtime=v1".to_string()),
+        ];
+        let (sender, receiver) = WebSocketConnection::connect(&url, &headers).await?.split();
+
+        let mut session = Self {
+            sender: RealtimeSender { inner: sender },
+            receiver: RealtimeReceiver { inner: receiver },
+        };
+        session.sender.send(&config.session_update()).await?;
+        Ok(session)
+    }
+
+    /// Send a user text message and request a response
+    pub async fn send_text(&mut self, text: &str) -> Result<()> {
+        self.sender.send_text(text).await
+    }
+
+    /// Append raw PCM16 audio to the input buffer
+    pub async fn append_audio(&mut self, pcm: &[u8]) -> Result<()> {
+        self.sender.append_audio(pcm).await
+    }
+
+    /// Receive the next server event, or `None` once the session is closed
+    pub async fn next_event(&mut self) -> Result<Option<RealtimeEvent>> {
+        self.receiver.next_event().await
+    }
+
+    /// Split into halves so audio can be streamed while events are read
+    pub fn split(self) -> (RealtimeSender, RealtimeReceiver) {
+        (self.sender, self.receiver)
+    }
+}
+
+/// Sending half of a [`RealtimeSession`]
+pub struct RealtimeSender {
+    inner: WebSocketSender,
+}
+
+impl RealtimeSender {
+    async fn send(&mut self, event: &Value) -> Result<()> {
+        self.inner.send_json(event).await
+    }
+
+    /// Send a user text message and request a response
+    pub async fn send_text(&mut self, text: &str) -> Result<()> {
+        self.send(&json!({
+            "type": "conversation.item.create",
+            "item": {
+                "type": "message",
+                "role": "user",
+                "content": [{ "type": "input_text", "text": text }],
+            },
+        }))
+        .await?;
+        self.send(&json!({ "type": "response.create" })).await
+    }
+
+    /// Append raw PCM16 audio to the input buffer
+    ///
+    /// With server VAD enabled the server commits the buffer and responds
+    /// once it detects the end of speech; otherwise call
+    /// [`commit_audio`](Self::commit_audio).
+    pub async fn append_audio(&mut self, pcm: &[u8]) -> Result<()> {
+        self.send(&json!({
+            "type": "input_audio_buffer.append",
+            "audio": STANDARD.encode(pcm),
+        }))
+        .await
+    }
+
+    /// Commit buffered audio as a user turn and request a response
+    pub async fn commit_audio(&mut self) -> Result<()> {
+        self.send(&json!({ "type": "input_audio_buffer.commit" }))
+            .await?;
+        self.send(&json!({ "type": "response.create" })).await
+    }
+
+    /// Cancel the response currently being generated
+    pub async fn cancel_response(&mut self) -> Result<()> {
+        self.send(&json!({ "type": "response.cancel" })).await
+    }
+
+    /// Close the session
+    pub async fn close(self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+/// Receiving half of a [`RealtimeSession`]
+pub struct RealtimeReceiver {
+    inner: WebSocketReceiver,
+}
+
+impl RealtimeReceiver {
+    /// Receive the next server event, or `None` once the session is closed
+    pub async fn next_event(&mut self) -> Result<Option<RealtimeEvent>> {
+        match self.inner.recv_json::<Value>().await? {
+            Some(event) => RealtimeEvent::parse(event).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::Message as Frame;
+
+    /// Start a fake realtime server that records client events and replies
+    /// to each `response.create` with a canned response
+    async fn fake_server() -> (String, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            while let Some(Ok(Frame::Text(text))) = ws.next().await {
+                let event: Value = serde_json::from_str(&text).unwrap();
+                let kind = event["type"].as_str().unwrap().to_string();
+                tx.send(event).unwrap();
+
+                let replies = match kind.as_str() {
+                    "session.update" => vec![json!({"type": "session.updated"})],
+                    "input_audio_buffer.append" => vec![
+                        json!({"type": "input_audio_buffer.speech_started"}),
+                        json!({"type": "input_audio_buffer.speech_stopped"}),
+                    ],
+                    "response.create" => vec![
+                        json!({"type": "response.text.delta", "delta": "Hel"}),
+                        json!({"type": "response.text.delta", "delta": "lo"}),
+                        json!({"type": "response.audio.delta", "delta": STANDARD.encode([1u8, 2, 3])}),
+                        json!({"type": "response.done"}),
+                    ],
+                    _ => vec![],
+                };
+                for reply in replies {
+                    ws.send(Frame::Text(reply.to_string())).await.unwrap();
+                }
+            }
+        });
+
+        (format!("ws://{}/v1/realtime", addr), rx)
+    }
+
+    fn test_config(url: String) -> RealtimeConfig {
+        RealtimeConfig {
+            url,
+            instructions: Some("Be brief".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_sends_session_update() {
+        let (url, mut events) = fake_server().await;
+        let mut session = RealtimeSession::connect("key", test_config(url))
+            .await
+            .unwrap();
+
+        let update = events.recv().await.unwrap();
+        assert_eq!(update["type"], "session.update");
+        assert_eq!(update["session"]["turn_detection"]["type"], "server_vad");
+        assert_eq!(update["session"]["instructions"], "Be brief");
+        assert_eq!(update["session"]["modalities"], json!(["text", "audio"]));
+
+        assert_eq!(
+            session.next_event().await.unwrap(),
+            Some(RealtimeEvent::SessionUpdated)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_text_round_trip() {
+        let (url, mut events) = fake_server().await;
+        let mut session = RealtimeSession::connect("key", test_config(url))
+            .await
+            .unwrap();
+        session.send_text("Hi").await.unwrap();
+
+        let mut text = String::new();
+        let mut audio = Vec::new();
+        while let Some(event) = session.next_event().await.unwrap() {
+            match event {
+                RealtimeEvent::TextDelta(delta) => text.push_str(&delta),
+                RealtimeEvent::AudioDelta(bytes) => audio.extend(bytes),
+                RealtimeEvent::ResponseDone => break,
+                _ => {}
+            }
+        }
+        assert_eq!(text, "Hello");
+        assert_eq!(audio, vec![1, 2, 3]);
+
+        events.recv().await.unwrap(); // session.update
+        let item = events.recv().await.unwrap();
+        assert_eq!(item["type"], "conversation.item.create");
+        assert_eq!(item["item"]["content"][0]["text"], "Hi");
+        assert_eq!(events.recv().await.unwrap()["type"], "response.create");
+    }
+
+    #[tokio::test]
+    async fn test_split_audio_with_server_vad() {
+        let (url, mut events) = fake_server().await;
+        let session = RealtimeSession::connect("key", test_config(url))
+            .await
+            .unwrap();
+        let (mut sender, mut receiver) = session.split();
+
+        sender.append_audio(&[0, 0, 1, 0]).await.unwrap();
+
+        assert_eq!(
+            receiver.next_event().await.unwrap(),
+            Some(RealtimeEvent::SessionUpdated)
+        );
+        assert_eq!(
+            receiver.next_event().await.unwrap(),
+            Some(RealtimeEvent::SpeechStarted)
+        );
+        assert_eq!(
+            receiver.next_event().await.unwrap(),
+            Some(RealtimeEvent::SpeechStopped)
+        );
+
+        events.recv().await.unwrap(); // session.update
+        let append = events.recv().await.unwrap();
+        assert_eq!(append["audio"], STANDARD.encode([0u8, 0, 1, 0]));
+    }
+
+    #[test]
+    fn test_parse_error_and_unknown_events() {
+        let error = RealtimeEvent::parse(json!({"type": "error", "error": {"message": "bad"}}));
+        assert_eq!(error.unwrap(), RealtimeEvent::Error("bad".to_string()));
+
+        let other = RealtimeEvent::parse(json!({"type": "rate_limits.updated"})).unwrap();
+        assert!(matches!(other, RealtimeEvent::Other(_)));
+    }
+
+    #[test]
+    fn test_session_update_without_vad_or_audio() {
+        let config = RealtimeConfig {
+            audio_output: false,
+            turn_detection: None,
+            ..Default::default()
+        };
+        let update = config.session_update();
+        assert_eq!(update["session"]["modalities"], json!(["text"]));
+        assert!(update["session"]["turn_detection"].is_null());
+        assert!(update["session"].get("instructions").is_none());
+    }
+}
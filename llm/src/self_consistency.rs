@@ -0,0 +1,497 @@
+//! Self-consistency sampling.
+//!
+//! [`SelfConsistencyProvider`] asks one model the same question several
+//! times at a high temperature, groups the sampled answers by similarity,
+//! and returns the answer most samples agree on. Reasoning-heavy queries
+//! tend to reach the right answer along more paths than any single wrong
+//! one, so the consensus is more accurate than one sample, at the cost of
+//! one request per sample.
+
+use agent_core::{AgentError, Message, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use config::LLMConfig;
+use futures::future::join_all;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::{
+    CompletionResponse, LLMProvider, StreamEvent, StructuredOutput, Temperature, TokenStream,
+    TokenUsage, ToolConfig, create_provider,
+};
+
+/// Pulls the answer to compare out of a sampled response
+type AnswerExtractor = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// The consensus of a set of samples
+#[derive(Debug, Clone)]
+pub struct Consensus {
+    /// The full sampled response chosen as the consensus
+    pub response: CompletionResponse,
+    /// The answer extracted from the chosen response
+    pub answer: String,
+    /// Number of samples in the winning cluster
+    pub agreement: usize,
+    /// Number of samples that were answered
+    pub samples: usize,
+    /// Number of distinct answer clusters
+    pub clusters: usize,
+    /// Tokens billed for all samples, if the provider reports them
+    pub usage: Option<TokenUsage>,
+}
+
+impl Consensus {
+    /// Share of the answered samples that agree with the consensus
+    pub fn confidence(&self) -> f32 {
+        self.agreement as f32 / self.samples as f32
+    }
+}
+
+/// Provider wrapper that returns the consensus of several samples
+///
+/// Each request is sent to the inner provider [`samples`](Self::samples)
+/// times at once, so a request costs that many times as much; see
+/// [`cost_multiplier`](Self::cost_multiplier). The inner provider should
+/// sample at a high temperature so the samples differ; build it yourself,
+/// or use [`from_config`](Self::from_config) to override the temperature
+/// of a configured provider.
+///
+/// By default the answer of a response is its last non-empty line, with
+/// any `Answer:` prefix removed, so chains of thought leading to the same
+/// answer agree. Two answers are similar when the Jaccard similarity of
+/// their lowercased words is at least the
+/// [`similarity threshold`](Self::with_similarity_threshold). Each answer
+/// joins the first cluster whose first answer it is similar to, and the
+/// first response of the largest cluster is returned; ties go to the
+/// cluster formed first.
+///
+/// Failed samples are ignored; the request only fails if every sample
+/// fails. The usage of a completion adds up all samples. Streaming requests
+/// wait for the consensus and yield it as a single event. Structured and
+/// tool-calling requests have no text answer to compare, so they are sent
+/// to the inner provider once.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{LLMProvider, SelfConsistencyProvider, Temperature};
+/// use agent_core::Message;
+/// # use config::LLMConfig;
+///
+/// # async fn example(config: LLMConfig) -> agent_core::Result<()> {
+/// let provider = SelfConsistencyProvider::from_config(&config, Temperature::new(1.0)?)?
+///     .with_samples(7);
+///
+/// let consensus = provider
+///     .consensus(&[Message::user("A bat and a ball cost $1.10 ...")])
+///     .await?;
+/// println!("{} ({:.0}% agreement)", consensus.answer, consensus.confidence() * 100.0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SelfConsistencyProvider<P> {
+    inner: P,
+    samples: usize,
+    similarity_threshold: f32,
+    extractor: AnswerExtractor,
+}
+
+impl SelfConsistencyProvider<Box<dyn LLMProvider>> {
+    /// Build the configured provider, sampling at `temperature` instead of
+    /// the configured temperature
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider cannot be created from `config`.
+    pub fn from_config(config: &LLMConfig, temperature: Temperature) -> Result<Self> {
+        let config = LLMConfig {
+            temperature: temperature.clamp_for(&config.provider).get(),
+            ..config.clone()
+        };
+        Ok(Self::new(create_provider(&config)?))
+    }
+}
+
+impl<P: LLMProvider> SelfConsistencyProvider<P> {
+    /// Default number of samples per request
+    pub const DEFAULT_SAMPLES: usize = 5;
+    /// Default similarity above which two answers agree
+    pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.8;
+
+    /// Wrap `inner`, taking [`DEFAULT_SAMPLES`](Self::DEFAULT_SAMPLES)
+    /// samples per request
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            samples: Self::DEFAULT_SAMPLES,
+            similarity_threshold: Self::DEFAULT_SIMILARITY_THRESHOLD,
+            extractor: Arc::new(default_answer),
+        }
+    }
+
+    /// Set the number of samples per request (at least 1)
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Set how similar two answers must be to agree, from 0.0 to 1.0
+    ///
+    /// 1.0 only groups answers with the same words.
+    pub fn with_similarity_threshold(mut self, threshold: f32) -> Self {
+        self.similarity_threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how the answer to compare is pulled out of a response
+    pub fn with_answer_extractor(
+        mut self,
+        extractor: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.extractor = Arc::new(extractor);
+        self
+    }
+
+    /// Number of samples per request
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// How many requests to the inner provider each request makes
+    pub fn cost_multiplier(&self) -> usize {
+        self.samples
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Sample the inner provider and return the consensus with its agreement
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AgentError::LLMProvider`] if every sample fails.
+    pub async fn consensus(&self, messages: &[Message]) -> Result<Consensus> {
+        let outcomes =
+            join_all((0..self.samples).map(|_| self.inner.send_completion(messages))).await;
+
+        let mut responses = Vec::new();
+        let mut errors = Vec::new();
+        for outcome in outcomes {
+            match outcome {
+                Ok(response) => responses.push(response),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        if responses.is_empty() {
+            return Err(AgentError::LLMProvider(format!(
+                "All {} samples failed: {}",
+                errors.len(),
+                errors.join("; ")
+            )));
+        }
+
+        // (words of the cluster's first answer, indices of its responses)
+        let mut clusters: Vec<(BTreeSet<String>, Vec<usize>)> = Vec::new();
+        let answers: Vec<String> = responses
+            .iter()
+            .map(|r| (self.extractor)(&r.text))
+            .collect();
+        for (index, answer) in answers.iter().enumerate() {
+            let words = words(answer);
+            match clusters
+                .iter_mut()
+                .find(|(first, _)| jaccard(first, &words) >= self.similarity_threshold)
+            {
+                Some((_, members)) => members.push(index),
+                None => clusters.push((words, vec![index])),
+            }
+        }
+
+        let mut usage = None;
+        for response in &responses {
+            usage = match (usage, response.usage) {
+                (Some(total), Some(sample)) => Some(total + sample),
+                (total, sample) => total.or(sample),
+            };
+        }
+
+        let mut best = &clusters[0].1;
+        for (_, members) in &clusters[1..] {
+            if members.len() > best.len() {
+                best = members;
+            }
+        }
+        let chosen = best[0];
+        Ok(Consensus {
+            answer: answers[chosen].clone(),
+            agreement: best.len(),
+            samples: responses.len(),
+            clusters: clusters.len(),
+            usage,
+            response: responses.swap_remove(chosen),
+        })
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for SelfConsistencyProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        Ok(self.consensus(messages).await?.response.text)
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let consensus = self.consensus(messages).await?;
+        let mut response = consensus.response;
+        response.usage = consensus.usage;
+        Ok(response)
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner.send_structured(messages, output).await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner.send_structured_completion(messages, output).await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner.send_message_with_tools(messages, tools, config).await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let response = self.send_completion(messages).await?;
+        let mut events = vec![Ok(StreamEvent::Text(response.text))];
+        if let Some(usage) = response.usage {
+            events.push(Ok(StreamEvent::Usage(usage)));
+        }
+        Ok(Box::pin(futures::stream::iter(events)))
+    }
+}
+
+/// The last non-empty line of a response, without an `Answer:` prefix
+fn default_answer(response: &str) -> String {
+    let line = response
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .unwrap_or_default();
+    let prefix = line
+        .get(..7)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("answer:"));
+    match prefix {
+        Some(prefix) => line[prefix.len()..].trim().to_string(),
+        None => line.to_string(),
+    }
+}
+
+/// Lowercased words of an answer, ignoring punctuation around them
+fn words(answer: &str) -> BTreeSet<String> {
+    answer
+        .split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Jaccard similarity of two word sets; two empty answers are identical
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FinishReason;
+    use std::sync::Mutex;
+
+    /// Provider that returns scripted responses in order
+    struct Scripted {
+        responses: Mutex<Vec<Result<String>>>,
+    }
+
+    impl Scripted {
+        fn new(responses: Vec<Result<&str>>) -> Self {
+            let mut responses: Vec<_> = responses
+                .into_iter()
+                .map(|r| r.map(str::to_string))
+                .collect();
+            responses.reverse();
+            Self {
+                responses: Mutex::new(responses),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            Ok(self.send_completion(messages).await?.text)
+        }
+
+        async fn send_completion(&self, _messages: &[Message]) -> Result<CompletionResponse> {
+            let text = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop()
+                .expect("no scripted response")?;
+            Ok(
+                CompletionResponse::new(text, FinishReason::Stop).with_usage(TokenUsage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                }),
+            )
+        }
+    }
+
+    fn question() -> Vec<Message> {
+        vec![Message::user("What is 17 * 3?")]
+    }
+
+    #[tokio::test]
+    async fn test_returns_majority_answer_after_reasoning() {
+        let provider = SelfConsistencyProvider::new(Scripted::new(vec![
+            Ok("17 * 3 = 41\nAnswer: 41"),
+            Ok("17 * 3 is 10 * 3 + 7 * 3 = 51\nAnswer: 51"),
+            Ok("Three 17s make 51.\n\n51."),
+            Ok("Answer: 54"),
+            Ok("answer: 51"),
+        ]));
+        assert_eq!(provider.cost_multiplier(), 5);
+
+        let consensus = provider.consensus(&question()).await.unwrap();
+        assert_eq!(consensus.answer, "51");
+        assert_eq!(consensus.agreement, 3);
+        assert_eq!(consensus.samples, 5);
+        assert_eq!(consensus.clusters, 3);
+        assert_eq!(consensus.confidence(), 0.6);
+        assert!(consensus.response.text.starts_with("17 * 3 is"));
+        assert_eq!(consensus.usage.unwrap().total(), 75);
+    }
+
+    #[tokio::test]
+    async fn test_similar_answers_agree() {
+        let provider = SelfConsistencyProvider::new(Scripted::new(vec![
+            Ok("The capital of France is Paris"),
+            Ok("Lyon"),
+            Ok("the capital of France is Paris."),
+        ]))
+        .with_samples(3);
+        assert_eq!(
+            provider.send_message(&question()).await.unwrap(),
+            "The capital of France is Paris"
+        );
+
+        let strict = SelfConsistencyProvider::new(Scripted::new(vec![
+            Ok("It is Paris"),
+            Ok("Paris"),
+            Ok("Lyon"),
+        ]))
+        .with_samples(3)
+        .with_similarity_threshold(1.0);
+        let consensus = strict.consensus(&question()).await.unwrap();
+        assert_eq!(consensus.clusters, 3);
+        assert_eq!(consensus.answer, "It is Paris");
+
+        let loose = SelfConsistencyProvider::new(Scripted::new(vec![
+            Ok("Lyon"),
+            Ok("It is Paris"),
+            Ok("Paris"),
+        ]))
+        .with_samples(3)
+        .with_similarity_threshold(0.3);
+        assert_eq!(
+            loose.consensus(&question()).await.unwrap().answer,
+            "It is Paris"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_extractor_and_failed_samples() {
+        let provider = SelfConsistencyProvider::new(Scripted::new(vec![
+            Err(AgentError::LLMProvider("timeout".to_string())),
+            Ok("<answer>7</answer> because..."),
+            Ok("<answer>7</answer> since..."),
+        ]))
+        .with_samples(3)
+        .with_answer_extractor(|response| {
+            response
+                .split("<answer>")
+                .nth(1)
+                .and_then(|rest| rest.split("</answer>").next())
+                .unwrap_or_default()
+                .to_string()
+        });
+
+        let response = provider.send_completion(&question()).await.unwrap();
+        assert_eq!(response.text, "<answer>7</answer> because...");
+        assert_eq!(response.usage.unwrap().total(), 30);
+    }
+
+    #[tokio::test]
+    async fn test_fails_when_every_sample_fails() {
+        let provider = SelfConsistencyProvider::new(Scripted::new(vec![
+            Err(AgentError::LLMProvider("down".to_string())),
+            Err(AgentError::LLMProvider("down".to_string())),
+        ]))
+        .with_samples(2);
+
+        let err = provider.send_message(&question()).await.unwrap_err();
+        assert!(err.to_string().contains("All 2 samples failed"));
+    }
+
+    #[test]
+    fn test_default_answer_is_last_line() {
+        assert_eq!(default_answer("Let me think.\nANSWER: 42\n\n"), "42");
+        assert_eq!(default_answer("just this"), "just this");
+        assert_eq!(default_answer(""), "");
+    }
+
+    #[tokio::test]
+    async fn test_streams_consensus_with_total_usage() {
+        let provider = SelfConsistencyProvider::new(Scripted::new(vec![
+            Ok("Answer: 51"),
+            Ok("Answer: 41"),
+            Ok("Answer: 51"),
+        ]))
+        .with_samples(3);
+
+        let events: Vec<_> = futures::StreamExt::collect(
+            provider.stream_message(&question()).await.unwrap(),
+        )
+        .await;
+        let events: Vec<_> = events.into_iter().map(|event| event.unwrap()).collect();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("Answer: 51".to_string()),
+                StreamEvent::Usage(TokenUsage {
+                    input_tokens: 30,
+                    output_tokens: 15,
+                }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Per-model request shaping.
+//!
+//! Model families differ in what a request may contain: early reasoning
+//! models reject system messages and sampling parameters, every model has
+//! its own output token limit, and recent Claude models accept either
+//! `temperature` or `top_p` but not both. [`ModelRules`] records these
+//! differences in one table, and providers apply them while building each
+//! request, so callers can switch models without special-casing them.
+
+use agent_core::{Message, Role};
+
+/// What a model family accepts in a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelRules {
+    /// Name of the model family, for diagnostics
+    pub family: &'static str,
+    /// Whether the model accepts system messages
+    ///
+    /// If not, system messages are merged into the first user message.
+    pub system_messages: bool,
+    /// Whether the model accepts `temperature` and `top_p`
+    pub sampling_params: bool,
+    /// Whether `temperature` and `top_p` may be sent together
+    ///
+    /// If not, `top_p` is dropped when both are set.
+    pub combined_sampling: bool,
+    /// Largest `max_tokens` the model accepts, if known
+    pub max_output_tokens: Option<usize>,
+}
+
+/// Known model families, matched by model name prefix; the first match wins
+const FAMILIES: &[(&str, ModelRules)] = &[
+    ("o1-mini", ModelRules::reasoning("o1-mini", false, 65_536)),
+    (
+        "o1-preview",
+        ModelRules::reasoning("o1-preview", false, 32_768),
+    ),
+    ("o1", ModelRules::reasoning("o1", true, 100_000)),
+    ("o3", ModelRules::reasoning("o3", true, 100_000)),
+    ("o4", ModelRules::reasoning("o4", true, 100_000)),
+    ("gpt-4o", ModelRules::chat("gpt-4o", 16_384)),
+    ("gpt-4.1", ModelRules::chat("gpt-4.1", 32_768)),
+    ("gpt-4-turbo", ModelRules::chat("gpt-4-turbo", 4_096)),
+    ("gpt-4", ModelRules::chat("gpt-4", 8_192)),
+    ("gpt-3.5-turbo", ModelRules::chat("gpt-3.5-turbo", 4_096)),
+    ("claude-3-5", ModelRules::chat("claude-3.5", 8_192)),
+    ("claude-3-7", ModelRules::chat("claude-3.7", 64_000)),
+    ("claude-3", ModelRules::chat("claude-3", 4_096)),
+    ("claude-opus-4-0", ModelRules::chat("claude-opus-4", 32_000)),
+    (
+        "claude-opus-4",
+        ModelRules::claude_4("claude-opus-4", 32_000),
+    ),
+    (
+        "claude-sonnet-4-0",
+        ModelRules::chat("claude-sonnet-4", 64_000),
+    ),
+    (
+        "claude-sonnet-4",
+        ModelRules::claude_4("claude-sonnet-4", 64_000),
+    ),
+    (
+        "claude-haiku-4",
+        ModelRules::claude_4("claude-haiku-4", 64_000),
+    ),
+];
+
+impl ModelRules {
+    /// Rules for a model that accepts everything and has no known limit
+    pub const fn permissive() -> Self {
+        Self {
+            family: "unknown",
+            system_messages: true,
+            sampling_params: true,
+            combined_sampling: true,
+            max_output_tokens: None,
+        }
+    }
+
+    const fn chat(family: &'static str, max_output_tokens: usize) -> Self {
+        Self {
+            family,
+            max_output_tokens: Some(max_output_tokens),
+            ..Self::permissive()
+        }
+    }
+
+    const fn reasoning(family: &'static str, system_messages: bool, max: usize) -> Self {
+        Self {
+            family,
+            system_messages,
+            sampling_params: false,
+            combined_sampling: false,
+            max_output_tokens: Some(max),
+        }
+    }
+
+    const fn claude_4(family: &'static str, max_output_tokens: usize) -> Self {
+        Self {
+            combined_sampling: false,
+            ..Self::chat(family, max_output_tokens)
+        }
+    }
+
+    /// Look up the rules for a model name
+    ///
+    /// Unknown models, including fine-tunes with custom names, get
+    /// [`permissive`](Self::permissive) rules.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use llm::ModelRules;
+    ///
+    /// let rules = ModelRules::for_model("o1-mini");
+    /// assert!(!rules.system_messages);
+    /// assert_eq!(rules.cap_max_tokens(200_000), 65_536);
+    /// ```
+    pub fn for_model(model: &str) -> Self {
+        FAMILIES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, rules)| rules.clone())
+            .unwrap_or_else(Self::permissive)
+    }
+
+    /// Limit `max_tokens` to what the model accepts
+    pub fn cap_max_tokens(&self, max_tokens: usize) -> usize {
+        self.max_output_tokens
+            .map_or(max_tokens, |limit| max_tokens.min(limit))
+    }
+
+    /// The `temperature` and `top_p` to send, dropping unsupported ones
+    pub fn sampling(
+        &self,
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+    ) -> (Option<f32>, Option<f32>) {
+        if !self.sampling_params {
+            (None, None)
+        } else if !self.combined_sampling && temperature.is_some() {
+            (temperature, None)
+        } else {
+            (temperature, top_p)
+        }
+    }
+
+    /// Merge system messages into the first user message if the model does
+    /// not accept them
+    ///
+    /// If there is no user message, the system text becomes one.
+    pub fn shape_messages(&self, messages: &[Message]) -> Vec<Message> {
+        if self.system_messages || messages.iter().all(|m| m.role != Role::System) {
+            return messages.to_vec();
+        }
+
+        let system = messages
+            .iter()
+            .filter(|message| message.role == Role::System)
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let mut shaped: Vec<Message> = messages
+            .iter()
+            .filter(|message| message.role != Role::System)
+            .cloned()
+            .collect();
+        match shaped.iter_mut().find(|message| message.role == Role::User) {
+            Some(user) => user.content = format!("{}\n\n{}", system, user.content),
+            None => shaped.insert(0, Message::user(system)),
+        }
+        shaped
+    }
+}
+
+impl Default for ModelRules {
+    fn default() -> Self {
+        Self::permissive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_family_lookup() {
+        assert_eq!(
+            ModelRules::for_model("o1-mini-2024-09-12").family,
+            "o1-mini"
+        );
+        assert_eq!(ModelRules::for_model("gpt-4o-mini").family, "gpt-4o");
+        assert_eq!(ModelRules::for_model("gpt-4-0613").family, "gpt-4");
+        assert_eq!(
+            ModelRules::for_model("claude-3-5-haiku-latest").family,
+            "claude-3.5"
+        );
+        assert_eq!(
+            ModelRules::for_model("claude-sonnet-4-5").family,
+            "claude-sonnet-4"
+        );
+        assert_eq!(
+            ModelRules::for_model("ft:my-model"),
+            ModelRules::permissive()
+        );
+    }
+
+    #[test]
+    fn test_sampling_params() {
+        let o1 = ModelRules::for_model("o1");
+        assert_eq!(o1.sampling(Some(0.7), Some(0.9)), (None, None));
+
+        let sonnet = ModelRules::for_model("claude-sonnet-4-5");
+        assert_eq!(sonnet.sampling(Some(0.7), Some(0.9)), (Some(0.7), None));
+        assert_eq!(sonnet.sampling(None, Some(0.9)), (None, Some(0.9)));
+
+        let gpt = ModelRules::for_model("gpt-4o");
+        assert_eq!(gpt.sampling(Some(0.7), Some(0.9)), (Some(0.7), Some(0.9)));
+        assert_eq!(gpt.cap_max_tokens(100_000), 16_384);
+        assert_eq!(gpt.cap_max_tokens(2_000), 2_000);
+    }
+
+    #[test]
+    fn test_system_messages_are_collapsed() {
+        let rules = ModelRules::for_model("o1-preview");
+        let messages = [
+            Message::system("Be brief."),
+            Message::system("Answer in French."),
+            Message::user("Hi"),
+            Message::assistant("Salut"),
+            Message::user("Bye"),
+        ];
+
+        let shaped = rules.shape_messages(&messages);
+        assert_eq!(shaped.len(), 3);
+        assert_eq!(shaped[0].role, Role::User);
+        assert_eq!(shaped[0].content, "Be brief.\n\nAnswer in French.\n\nHi");
+        assert_eq!(shaped[2].content, "Bye");
+
+        let shaped = rules.shape_messages(&[Message::system("Only instructions")]);
+        assert_eq!(shaped.len(), 1);
+        assert_eq!(shaped[0].role, Role::User);
+        assert_eq!(shaped[0].content, "Only instructions");
+    }
+}
@@ -0,0 +1,133 @@
+//! ElevenLabs text-to-speech provider.
+
+use agent_core::Result;
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::{AudioFormat, AudioStream, SpeechProvider, into_audio_stream, request_error};
+
+/// Default ElevenLabs API base URL
+const DEFAULT_BASE_URL: &str = "https://api.elevenlabs.io/v1";
+
+/// Request body for the ElevenLabs streaming endpoint
+#[derive(Debug, Serialize)]
+struct TextToSpeechRequest<'a> {
+    text: &'a str,
+    model_id: &'a str,
+}
+
+/// Speech provider backed by ElevenLabs' streaming TTS API
+///
+/// Audio is always returned as MP3.
+pub struct ElevenLabsSpeechProvider {
+    api_key: String,
+    voice_id: String,
+    model_id: String,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl ElevenLabsSpeechProvider {
+    /// Create a provider for the given voice using the `eleven_multilingual_v2` model
+    pub fn new(api_key: impl Into<String>, voice_id: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            voice_id: voice_id.into(),
+            model_id: "eleven_multilingual_v2".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the synthesis model (e.g. "eleven_turbo_v2_5" for lower latency)
+    pub fn with_model(mut self, model_id: impl Into<String>) -> Self {
+        self.model_id = model_id.into();
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for ElevenLabsSpeechProvider {
+    async fn synthesize(&self, text: &str) -> Result<AudioStream> {
+        let request = TextToSpeechRequest {
+            text,
+            model_id: &self.model_id,
+        };
+
+        let url = format!("{}/text-to-speech/{}/stream", self.base_url, self.voice_id);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("xi-api-key", &self.api_key)
+            .header("Accept", AudioFormat::Mp3.mime_type())
+            .json(&request)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("ElevenLabs", e))?;
+
+        into_audio_stream(response, "ElevenLabs").await
+    }
+
+    fn format(&self) -> AudioFormat {
+        AudioFormat::Mp3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_synthesize_streams_audio() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/text-to-speech/voice-1/stream"))
+            .and(header("xi-api-key", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 128]))
+            .mount(&mock_server)
+            .await;
+
+        let provider =
+            ElevenLabsSpeechProvider::new("test-key", "voice-1").with_base_url(mock_server.uri());
+
+        let mut stream = provider.synthesize("Hi").await.unwrap();
+        let mut total = 0;
+        while let Some(chunk) = stream.next().await {
+            total += chunk.unwrap().len();
+        }
+        assert_eq!(total, 128);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_voice() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("voice_not_found"))
+            .mount(&mock_server)
+            .await;
+
+        let provider =
+            ElevenLabsSpeechProvider::new("test-key", "missing").with_base_url(mock_server.uri());
+        let result = provider.synthesize_to_vec("Hi").await;
+        assert!(result.unwrap_err().to_string().contains("voice_not_found"));
+    }
+}
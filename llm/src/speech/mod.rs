@@ -0,0 +1,138 @@
+//! Text-to-speech providers.
+//!
+//! A [`SpeechProvider`] converts agent responses into audio, streamed back
+//! chunk by chunk so playback can start before synthesis finishes.
+//!
+//! # Implementations
+//!
+//! - [`OpenAISpeechProvider`]: OpenAI's TTS API
+//! - [`ElevenLabsSpeechProvider`]: ElevenLabs streaming TTS
+
+pub mod elevenlabs;
+pub mod openai;
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::{check_status, request_error};
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
+
+pub use elevenlabs::ElevenLabsSpeechProvider;
+pub use openai::OpenAISpeechProvider;
+
+/// Stream of encoded audio chunks
+pub type AudioStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>>;
+
+/// Encoding of synthesized audio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AudioFormat {
+    /// MPEG layer 3
+    #[default]
+    Mp3,
+    /// Opus in an Ogg container
+    Opus,
+    /// AAC
+    Aac,
+    /// FLAC
+    Flac,
+    /// WAV
+    Wav,
+    /// Raw 16-bit PCM
+    Pcm,
+}
+
+impl AudioFormat {
+    /// Format name as used by provider APIs
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Pcm => "pcm",
+        }
+    }
+
+    /// MIME type of the encoded audio
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Opus => "audio/ogg",
+            AudioFormat::Aac => "audio/aac",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Pcm => "audio/pcm",
+        }
+    }
+}
+
+/// Trait for text-to-speech providers
+#[async_trait]
+pub trait SpeechProvider: Send + Sync {
+    /// Synthesize speech, returning audio as it is generated
+    ///
+    /// # Arguments
+    /// * `text` - The text to speak
+    ///
+    /// # Returns
+    /// * `Result<AudioStream>` - Stream of encoded audio chunks or an error
+    async fn synthesize(&self, text: &str) -> Result<AudioStream>;
+
+    /// Encoding of the audio produced by [`SpeechProvider::synthesize`]
+    fn format(&self) -> AudioFormat;
+
+    /// Synthesize speech and collect the complete audio
+    async fn synthesize_to_vec(&self, text: &str) -> Result<Vec<u8>> {
+        let mut stream = self.synthesize(text).await?;
+        let mut audio = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            audio.extend_from_slice(&chunk?);
+        }
+        Ok(audio)
+    }
+}
+
+/// Check a TTS response status and turn its body into an [`AudioStream`]
+async fn into_audio_stream(response: reqwest::Response, service: &str) -> Result<AudioStream> {
+    let response = check_status(service, response).await?;
+
+    let service = service.to_string();
+    Ok(Box::pin(response.bytes_stream().map(move |chunk| {
+        chunk
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| AgentError::LLMProvider(format!("{} audio stream failed: {}", service, e)))
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    struct ChunkedSpeech;
+
+    #[async_trait]
+    impl SpeechProvider for ChunkedSpeech {
+        async fn synthesize(&self, _text: &str) -> Result<AudioStream> {
+            Ok(Box::pin(stream::iter(vec![Ok(vec![1, 2]), Ok(vec![3])])))
+        }
+
+        fn format(&self) -> AudioFormat {
+            AudioFormat::Wav
+        }
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_to_vec_collects_chunks() {
+        let audio = ChunkedSpeech.synthesize_to_vec("hi").await.unwrap();
+        assert_eq!(audio, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_audio_format() {
+        assert_eq!(AudioFormat::default(), AudioFormat::Mp3);
+        assert_eq!(AudioFormat::Opus.as_str(), "opus");
+        assert_eq!(AudioFormat::Wav.mime_type(), "audio/wav");
+    }
+}
@@ -0,0 +1,176 @@
+//! OpenAI text-to-speech provider.
+
+use agent_core::Result;
+use async_trait::async_trait;
+use communication::ApiClient;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::{AudioFormat, AudioStream, SpeechProvider, into_audio_stream, request_error};
+
+/// Default OpenAI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Request body for the OpenAI speech endpoint
+#[derive(Debug, Serialize)]
+struct SpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speed: Option<f32>,
+}
+
+/// Speech provider backed by OpenAI's TTS API
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{OpenAISpeechProvider, SpeechProvider};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OpenAISpeechProvider::new("sk-...").with_voice("nova");
+/// let audio = provider.synthesize_to_vec("Hello!").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenAISpeechProvider {
+    api_key: String,
+    model: String,
+    voice: String,
+    speed: Option<f32>,
+    format: AudioFormat,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OpenAISpeechProvider {
+    /// Create a provider using the `tts-1` model and `alloy` voice
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "tts-1".to_string(),
+            voice: "alloy".to_string(),
+            speed: None,
+            format: AudioFormat::default(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the TTS model (e.g. "tts-1-hd")
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the voice (e.g. "alloy", "nova", "shimmer")
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = voice.into();
+        self
+    }
+
+    /// Set the playback speed, from 0.25 to 4.0
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = Some(speed.clamp(0.25, 4.0));
+        self
+    }
+
+    /// Set the output audio format
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for OpenAISpeechProvider {
+    async fn synthesize(&self, text: &str) -> Result<AudioStream> {
+        let request = SpeechRequest {
+            model: &self.model,
+            input: text,
+            voice: &self.voice,
+            response_format: self.format.as_str(),
+            speed: self.speed,
+        };
+
+        let url = format!("{}/audio/speech", self.base_url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("OpenAI TTS", e))?;
+
+        into_audio_stream(response, "OpenAI TTS").await
+    }
+
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_synthesize() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/audio/speech"))
+            .and(header("Authorization", "Bearer test-key"))
+            .and(body_partial_json(serde_json::json!({
+                "input": "Hello",
+                "voice": "nova",
+                "response_format": "opus"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0xAB; 64]))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAISpeechProvider::new("test-key")
+            .with_voice("nova")
+            .with_format(AudioFormat::Opus)
+            .with_base_url(mock_server.uri());
+
+        let audio = provider.synthesize_to_vec("Hello").await.unwrap();
+        assert_eq!(audio, vec![0xAB; 64]);
+        assert_eq!(provider.format(), AudioFormat::Opus);
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_http_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Invalid API key"))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAISpeechProvider::new("bad").with_base_url(mock_server.uri());
+        let err = match provider.synthesize("Hello").await {
+            Err(e) => e,
+            Ok(_) => panic!("Expected error"),
+        };
+        assert!(err.to_string().contains("OpenAI TTS HTTP 401"));
+    }
+}
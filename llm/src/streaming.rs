@@ -0,0 +1,398 @@
+//! Streaming responses and client-side stop conditions.
+//!
+//! [`LLMProvider::stream_message`] returns a [`TokenStream`] of text deltas.
+//! [`with_stop_conditions`] wraps such a stream and ends it as soon as a
+//! [`StopCondition`] triggers. Ending the stream drops the underlying HTTP
+//! response, which closes the connection so the provider stops generating
+//! (and billing for) tokens nobody will read.
+//!
+//! Callers that only display or forward tokens can use
+//! [`LLMProvider::send_message_stream`], which yields the text chunks alone.
+//!
+//! [`LLMProvider::stream_message`]: crate::LLMProvider::stream_message
+//! [`LLMProvider::send_message_stream`]: crate::LLMProvider::send_message_stream
+
+use agent_core::{AgentError, Result};
+use futures::stream::{self, Stream, StreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Add;
+use std::pin::Pin;
+
+/// Boxed stream of response events
+pub type TokenStream = Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>;
+
+/// Boxed stream of response text chunks
+pub type TextStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
+/// An event in a streamed response
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// A chunk of response text
+    Text(String),
+    /// The stream was ended early by a client-side stop condition
+    Stopped(StopReason),
+    /// Token accounting for the whole response, sent as the last event
+    ///
+    /// Not sent when the stream is stopped early, since the provider never
+    /// gets to report usage for an aborted request.
+    Usage(TokenUsage),
+}
+
+/// Token counts reported by the provider for one response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Prompt tokens
+    pub input_tokens: usize,
+    /// Generated tokens
+    pub output_tokens: usize,
+}
+
+impl TokenUsage {
+    /// Prompt and generated tokens combined
+    pub fn total(&self) -> usize {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Prompt cache activity reported by the provider for one response
+///
+/// Cached tokens are billed separately from, and are not included in,
+/// [`TokenUsage::input_tokens`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheUsage {
+    /// Prompt tokens read from the cache
+    pub read_tokens: usize,
+    /// Prompt tokens written to the cache for later requests
+    pub written_tokens: usize,
+}
+
+impl CacheUsage {
+    /// Whether any of the prompt was served from the cache
+    pub fn is_hit(&self) -> bool {
+        self.read_tokens > 0
+    }
+}
+
+impl Add for TokenUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            input_tokens: self.input_tokens + other.input_tokens,
+            output_tokens: self.output_tokens + other.output_tokens,
+        }
+    }
+}
+
+/// Why a stream was stopped on the client
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    /// The response matched a stop pattern; holds the matched text
+    Pattern(String),
+    /// The client-side token limit was reached
+    MaxTokens(usize),
+}
+
+/// Content check used by [`StopCondition::Guardrail`]
+pub type ContentCheck = Box<dyn Fn(&str) -> Result<()> + Send + Sync>;
+
+/// A predicate that ends a stream early
+pub enum StopCondition {
+    /// Stop when the response text matches a regex. Text from the start of
+    /// the match onwards is not emitted.
+    Pattern(Regex),
+    /// Stop after this many text chunks. Providers stream roughly one token
+    /// per chunk, so this approximates a token limit.
+    MaxTokens(usize),
+    /// Run a check on the response text so far; stop with its error as
+    /// soon as it fails
+    Guardrail(ContentCheck),
+}
+
+impl StopCondition {
+    /// Stop when the response matches `pattern`
+    ///
+    /// # Errors
+    /// Returns an error if the pattern is not a valid regex.
+    pub fn pattern(pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(Self::Pattern)
+            .map_err(|e| AgentError::Config(format!("Invalid stop pattern '{}': {}", pattern, e)))
+    }
+
+    /// Stop after `max_tokens` text chunks
+    pub fn max_tokens(max_tokens: usize) -> Self {
+        Self::MaxTokens(max_tokens)
+    }
+
+    /// Stop with an error when `check` rejects the response text so far
+    ///
+    /// The check should return [`AgentError::GuardrailViolation`] on failure.
+    pub fn guardrail<F>(check: F) -> Self
+    where
+        F: Fn(&str) -> Result<()> + Send + Sync + 'static,
+    {
+        Self::Guardrail(Box::new(check))
+    }
+}
+
+/// What happens to the next chunk after evaluating the stop conditions
+enum Verdict {
+    Continue,
+    /// Emit this prefix of the chunk, then stop
+    Stop(String, StopReason),
+    Fail(AgentError),
+}
+
+/// Evaluate every condition against the response with `chunk` appended
+fn evaluate(conditions: &[StopCondition], text: &str, chunk: &str, chunks: usize) -> Verdict {
+    let combined = format!("{}{}", text, chunk);
+    for condition in conditions {
+        match condition {
+            StopCondition::Pattern(regex) => {
+                if let Some(found) = regex.find(&combined) {
+                    let keep = found.start().saturating_sub(text.len());
+                    return Verdict::Stop(
+                        chunk[..keep].to_string(),
+                        StopReason::Pattern(found.as_str().to_string()),
+                    );
+                }
+            }
+            StopCondition::MaxTokens(max) => {
+                if chunks > *max {
+                    return Verdict::Stop(String::new(), StopReason::MaxTokens(*max));
+                }
+            }
+            StopCondition::Guardrail(check) => {
+                if let Err(e) = check(&combined) {
+                    return Verdict::Fail(e);
+                }
+            }
+        }
+    }
+    Verdict::Continue
+}
+
+/// End `stream` as soon as any of `conditions` triggers
+///
+/// When a pattern or token limit triggers, the remaining allowed text is
+/// emitted followed by a [`StreamEvent::Stopped`] event. When a guardrail
+/// check fails, its error is emitted instead. In both cases the stream then
+/// ends and the underlying request is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::Message;
+/// use futures::StreamExt;
+/// use llm::{LLMProvider, StopCondition, StreamEvent, with_stop_conditions};
+///
+/// # async fn example(provider: &dyn LLMProvider) -> agent_core::Result<()> {
+/// let stream = provider.stream_message(&[Message::user("Count to 100")]).await?;
+/// let conditions = vec![StopCondition::pattern(r"\b10\b")?, StopCondition::max_tokens(200)];
+/// let mut stream = with_stop_conditions(stream, conditions);
+/// while let Some(event) = stream.next().await {
+///     if let StreamEvent::Text(text) = event? {
+///         print!("{}", text);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn with_stop_conditions(stream: TokenStream, conditions: Vec<StopCondition>) -> TokenStream {
+    struct State {
+        inner: Option<TokenStream>,
+        conditions: Vec<StopCondition>,
+        text: String,
+        chunks: usize,
+        pending: Option<StreamEvent>,
+    }
+
+    let state = State {
+        inner: Some(stream),
+        conditions,
+        text: String::new(),
+        chunks: 0,
+        pending: None,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        if let Some(event) = state.pending.take() {
+            return Some((Ok(event), state));
+        }
+
+        let event = state.inner.as_mut()?.next().await?;
+        let chunk = match event {
+            Ok(StreamEvent::Text(chunk)) => chunk,
+            other => return Some((other, state)),
+        };
+
+        state.chunks += 1;
+        match evaluate(&state.conditions, &state.text, &chunk, state.chunks) {
+            Verdict::Continue => {
+                state.text.push_str(&chunk);
+                Some((Ok(StreamEvent::Text(chunk)), state))
+            }
+            Verdict::Stop(prefix, reason) => {
+                // Dropping the inner stream aborts the request
+                state.inner = None;
+                let stopped = StreamEvent::Stopped(reason);
+                if prefix.is_empty() {
+                    Some((Ok(stopped), state))
+                } else {
+                    state.pending = Some(stopped);
+                    Some((Ok(StreamEvent::Text(prefix)), state))
+                }
+            }
+            Verdict::Fail(e) => {
+                state.inner = None;
+                Some((Err(e), state))
+            }
+        }
+    }))
+}
+
+/// Collect the text of a stream into a single string, discarding other events
+pub async fn collect_text(mut stream: TokenStream) -> Result<String> {
+    let mut text = String::new();
+    while let Some(event) = stream.next().await {
+        if let StreamEvent::Text(chunk) = event? {
+            text.push_str(&chunk);
+        }
+    }
+    Ok(text)
+}
+
+/// Keep only the text chunks of a stream, discarding other events
+pub fn text_chunks(stream: TokenStream) -> TextStream {
+    Box::pin(stream.filter_map(|event| {
+        futures::future::ready(match event {
+            Ok(StreamEvent::Text(text)) => Some(Ok(text)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stream of text chunks that counts how many were pulled
+    fn counted(chunks: &[&str], pulled: Arc<AtomicUsize>) -> TokenStream {
+        let chunks: Vec<String> = chunks.iter().map(|c| c.to_string()).collect();
+        Box::pin(stream::iter(chunks).map(move |chunk| {
+            pulled.fetch_add(1, Ordering::SeqCst);
+            Ok(StreamEvent::Text(chunk))
+        }))
+    }
+
+    async fn events(stream: TokenStream) -> Vec<Result<StreamEvent>> {
+        stream.collect().await
+    }
+
+    #[tokio::test]
+    async fn test_pattern_stops_mid_chunk() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let stream = counted(&["Hello ", "wor", "ld. STOP here", "never"], pulled.clone());
+        let stopped = with_stop_conditions(stream, vec![StopCondition::pattern("STOP").unwrap()]);
+
+        let events: Vec<_> = events(stopped)
+            .await
+            .into_iter()
+            .map(|e| e.unwrap())
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Text("Hello ".to_string()),
+                StreamEvent::Text("wor".to_string()),
+                StreamEvent::Text("ld. ".to_string()),
+                StreamEvent::Stopped(StopReason::Pattern("STOP".to_string())),
+            ]
+        );
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pattern_spanning_chunks() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let stream = counted(&["ab", "c", "d"], pulled);
+        let stopped = with_stop_conditions(stream, vec![StopCondition::pattern("bc").unwrap()]);
+
+        let text = collect_text(stopped).await.unwrap();
+        // "b" was already emitted before the match could be seen
+        assert_eq!(text, "ab");
+    }
+
+    #[tokio::test]
+    async fn test_max_tokens() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let stream = counted(&["a", "b", "c", "d", "e"], pulled.clone());
+        let stopped = with_stop_conditions(stream, vec![StopCondition::max_tokens(2)]);
+
+        let events: Vec<_> = events(stopped)
+            .await
+            .into_iter()
+            .map(|e| e.unwrap())
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2], StreamEvent::Stopped(StopReason::MaxTokens(2)));
+        assert_eq!(pulled.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_guardrail_violation() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let stream = counted(&["my password", " is hunter2", " ok"], pulled.clone());
+        let check = StopCondition::guardrail(|text| {
+            if text.contains("hunter2") {
+                Err(AgentError::GuardrailViolation(
+                    "Secret in output".to_string(),
+                ))
+            } else {
+                Ok(())
+            }
+        });
+
+        let events = events(with_stop_conditions(stream, vec![check])).await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], Err(AgentError::GuardrailViolation(_))));
+        assert_eq!(pulled.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_usage_passes_through() {
+        let usage = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 2,
+        };
+        let inner: TokenStream = Box::pin(stream::iter(vec![
+            Ok(StreamEvent::Text("a".to_string())),
+            Ok(StreamEvent::Usage(usage)),
+        ]));
+        let events = events(with_stop_conditions(inner, vec![StopCondition::max_tokens(5)])).await;
+        assert_eq!(events.last().unwrap().as_ref().unwrap(), &StreamEvent::Usage(usage));
+        assert_eq!(usage.total(), 12);
+    }
+
+    #[tokio::test]
+    async fn test_no_conditions_passes_through() {
+        let stream = counted(&["a", "b"], Arc::new(AtomicUsize::new(0)));
+        let text = collect_text(with_stop_conditions(stream, Vec::new()))
+            .await
+            .unwrap();
+        assert_eq!(text, "ab");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(matches!(
+            StopCondition::pattern("("),
+            Err(AgentError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,410 @@
+//! Structured (JSON) output with automatic repair.
+//!
+//! [`LLMProvider::send_structured`] asks the model for JSON matching a
+//! schema. When the reply is not valid JSON or does not match the schema,
+//! the malformed output and the validation errors are sent back to the
+//! model, which is asked to correct it. This repeats up to
+//! [`StructuredOutput::max_repair_attempts`] times before the error is
+//! surfaced.
+//!
+//! Local providers (Ollama, llama.cpp) additionally constrain decoding with
+//! the schema or a GBNF grammar, so their output parses on the first try.
+//! OpenRouter sends the schema as a JSON schema `response_format` for the
+//! upstream provider to enforce.
+//!
+//! Schemas use a practical subset of JSON Schema: `type`, `properties`,
+//! `required`, `additionalProperties: false`, `items` and `enum`.
+
+use agent_core::{AgentError, Message, Result};
+use serde_json::Value;
+use std::sync::Mutex;
+
+use crate::{CompletionResponse, LLMProvider};
+
+/// Default number of repair rounds after the initial attempt
+const DEFAULT_MAX_REPAIR_ATTEMPTS: usize = 2;
+
+/// Schema and retry settings for a structured request
+#[derive(Debug, Clone)]
+pub struct StructuredOutput {
+    /// JSON Schema the response must match
+    pub schema: Value,
+    /// How many times a malformed response is sent back for correction
+    pub max_repair_attempts: usize,
+    /// GBNF grammar for providers that support grammar-constrained decoding
+    ///
+    /// When unset, such providers constrain output with `schema` instead.
+    /// Providers without constrained decoding ignore this.
+    pub grammar: Option<String>,
+}
+
+impl StructuredOutput {
+    /// Require responses to match `schema`
+    pub fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            max_repair_attempts: DEFAULT_MAX_REPAIR_ATTEMPTS,
+            grammar: None,
+        }
+    }
+
+    /// Constrain local model output with a GBNF grammar
+    pub fn with_grammar(mut self, grammar: impl Into<String>) -> Self {
+        self.grammar = Some(grammar.into());
+        self
+    }
+
+    /// Set how many repair rounds are attempted; 0 disables repair
+    pub fn with_max_repair_attempts(mut self, attempts: usize) -> Self {
+        self.max_repair_attempts = attempts;
+        self
+    }
+
+    /// Parse and validate a raw model response
+    ///
+    /// Tolerates Markdown code fences and text around the JSON value.
+    /// Returns a description of the problem on failure.
+    pub fn parse(&self, raw: &str) -> std::result::Result<Value, String> {
+        let json = extract_json(raw);
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| format!("Response is not valid JSON: {}", e))?;
+
+        let mut errors = Vec::new();
+        validate(&self.schema, &value, "$", &mut errors);
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(format!(
+                "Response does not match the schema: {}",
+                errors.join("; ")
+            ))
+        }
+    }
+
+    /// System instruction describing the required output format
+    fn instructions(&self) -> String {
+        format!(
+            "Respond with only a JSON value matching this JSON Schema, with no other text:\n{}",
+            serde_json::to_string_pretty(&self.schema).unwrap_or_else(|_| self.schema.to_string())
+        )
+    }
+}
+
+/// Request structured output from `provider`, repairing malformed replies
+///
+/// This is the default implementation of [`LLMProvider::send_structured`].
+pub(crate) async fn send_with_repair<P: LLMProvider + ?Sized>(
+    provider: &P,
+    messages: &[Message],
+    output: &StructuredOutput,
+) -> Result<Value> {
+    repair_loop(messages, output, |conversation| async move {
+        provider.send_message(&conversation).await
+    })
+    .await
+}
+
+/// Request structured output with [`LLMProvider::send_completion`],
+/// returning the final response with its usage summed over all attempts
+///
+/// Providers that report usage use this for
+/// [`LLMProvider::send_structured_completion`].
+pub(crate) async fn send_completion_with_repair<P: LLMProvider + ?Sized>(
+    provider: &P,
+    messages: &[Message],
+    output: &StructuredOutput,
+) -> Result<(Value, CompletionResponse)> {
+    completion_repair_loop(messages, output, |conversation| async move {
+        provider.send_completion(&conversation).await
+    })
+    .await
+}
+
+/// Run the request/validate/repair cycle with a `send` that returns whole
+/// responses, summing their usage into the final one
+pub(crate) async fn completion_repair_loop<F, Fut>(
+    messages: &[Message],
+    output: &StructuredOutput,
+    mut send: F,
+) -> Result<(Value, CompletionResponse)>
+where
+    F: FnMut(Vec<Message>) -> Fut,
+    Fut: Future<Output = Result<CompletionResponse>>,
+{
+    let last: Mutex<Option<CompletionResponse>> = Mutex::new(None);
+    let value = repair_loop(messages, output, |conversation| {
+        let last = &last;
+        let response = send(conversation);
+        async move {
+            let mut response = response.await?;
+            let mut last = last.lock().unwrap();
+            if let Some(previous) = last.take() {
+                response.add_usage(previous.usage);
+            }
+            let text = response.text.clone();
+            *last = Some(response);
+            Ok(text)
+        }
+    })
+    .await?;
+
+    // repair_loop only succeeds after at least one response
+    let response = last.into_inner().unwrap().unwrap();
+    Ok((value, response))
+}
+
+/// Run the request/validate/repair cycle using `send` for each attempt
+///
+/// Providers that can constrain decoding (e.g. with a grammar) pass a
+/// `send` that applies the constraint, and still get validation and repair.
+pub(crate) async fn repair_loop<F, Fut>(
+    messages: &[Message],
+    output: &StructuredOutput,
+    mut send: F,
+) -> Result<Value>
+where
+    F: FnMut(Vec<Message>) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let mut conversation = Vec::with_capacity(messages.len() + 1);
+    conversation.push(Message::system(output.instructions()));
+    conversation.extend_from_slice(messages);
+
+    let mut attempt = 0;
+    loop {
+        let raw = send(conversation.clone()).await?;
+        let error = match output.parse(&raw) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        if attempt >= output.max_repair_attempts {
+            return Err(AgentError::LLMProvider(format!(
+                "Structured output still invalid after {} attempts: {}",
+                attempt + 1,
+                error
+            )));
+        }
+        attempt += 1;
+
+        conversation.push(Message::assistant(raw));
+        conversation.push(Message::user(format!(
+            "{}\nRespond again with only the corrected JSON.",
+            error
+        )));
+    }
+}
+
+/// Return the JSON portion of a response
+///
+/// Strips Markdown code fences and any prose before the first `{` or `[`
+/// and after the last matching `}` or `]`.
+fn extract_json(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(trimmed);
+
+    let start = unfenced.find(['{', '[']);
+    let end = unfenced.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &unfenced[start..=end],
+        _ => unfenced,
+    }
+}
+
+/// Validate `value` against `schema`, collecting errors with JSON paths
+fn validate(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        errors.push(format!(
+            "{}: must be one of {}",
+            path,
+            Value::from(allowed.clone())
+        ));
+    }
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str)
+        && !matches_type(expected, value)
+    {
+        errors.push(format!("{}: expected {}", path, expected));
+        return;
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}: missing required property '{}'", path, name));
+                }
+            }
+        }
+
+        for (name, field) in object {
+            let field_path = format!("{}.{}", path, name);
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(field_schema) => validate(field_schema, field, &field_path, errors),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected property", field_path));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            validate(items, item, &format!("{}[{}]", path, index), errors);
+        }
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Provider that replays canned responses and records each request
+    struct ScriptedProvider {
+        responses: Mutex<Vec<&'static str>>,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: Vec<&'static str>) -> Self {
+            Self {
+                responses: Mutex::new(responses),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.responses.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "role": {"enum": ["admin", "user"]}
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false
+        })
+    }
+
+    #[tokio::test]
+    async fn test_valid_response_on_first_attempt() {
+        let provider = ScriptedProvider::new(vec![r#"{"name": "Ada", "age": 36}"#]);
+        let output = StructuredOutput::new(person_schema());
+
+        let value = provider
+            .send_structured(&[Message::user("Who?")], &output)
+            .await
+            .unwrap();
+        assert_eq!(value["name"], "Ada");
+
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0][0].content.contains("JSON Schema"));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_response_is_repaired() {
+        let provider = ScriptedProvider::new(vec![
+            r#"{"name": "Ada", "age": "36"}"#,
+            "```json\n{\"name\": \"Ada\", \"age\": 36}\n```",
+        ]);
+        let output = StructuredOutput::new(person_schema());
+
+        let value = provider
+            .send_structured(&[Message::user("Who?")], &output)
+            .await
+            .unwrap();
+        assert_eq!(value["age"], 36);
+
+        // The repair request carries the bad output and the validation error
+        let requests = provider.requests.lock().unwrap();
+        let repair = &requests[1];
+        assert_eq!(
+            repair[repair.len() - 2].content,
+            r#"{"name": "Ada", "age": "36"}"#
+        );
+        assert!(
+            repair[repair.len() - 1]
+                .content
+                .contains("$.age: expected integer")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let provider = ScriptedProvider::new(vec!["not json", "still not json"]);
+        let output = StructuredOutput::new(person_schema()).with_max_repair_attempts(1);
+
+        let err = provider
+            .send_structured(&[Message::user("Who?")], &output)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("after 2 attempts"));
+        assert_eq!(provider.requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let output = StructuredOutput::new(person_schema());
+        let err = output
+            .parse(r#"{"age": 1.5, "role": "root", "extra": true}"#)
+            .unwrap_err();
+        assert!(err.contains("missing required property 'name'"));
+        assert!(err.contains("$.age: expected integer"));
+        assert!(err.contains("$.role: must be one of"));
+        assert!(err.contains("$.extra: unexpected property"));
+    }
+
+    #[test]
+    fn test_array_items_are_validated() {
+        let output = StructuredOutput::new(json!({"type": "array", "items": {"type": "string"}}));
+        assert!(output.parse(r#"["a", "b"]"#).is_ok());
+        let err = output.parse(r#"["a", 2]"#).unwrap_err();
+        assert!(err.contains("$[1]: expected string"));
+    }
+
+    #[test]
+    fn test_extract_json() {
+        assert_eq!(
+            extract_json("Sure! {\"a\": 1} Hope that helps."),
+            "{\"a\": 1}"
+        );
+        assert_eq!(extract_json("```\n[1, 2]\n```"), "[1, 2]");
+        assert_eq!(extract_json("plain"), "plain");
+    }
+}
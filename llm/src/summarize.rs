@@ -0,0 +1,215 @@
+//! Conversation titles and summaries.
+//!
+//! Chat interfaces list conversations by a short title and often show a
+//! one-paragraph summary. [`ConversationSummarizer`] asks a model (usually a
+//! small, cheap one) for both and stores the result on the
+//! [`Conversation`](agent_core::Conversation).
+
+use agent_core::{AgentError, Conversation, ConversationSummary, Message, Result, Role};
+use serde_json::json;
+
+use crate::{LLMProvider, StructuredOutput};
+
+/// Default number of transcript characters sent to the model
+const DEFAULT_MAX_TRANSCRIPT_CHARS: usize = 12_000;
+
+/// Marker inserted where the middle of a long transcript was dropped
+const ELISION: &str = "\n[...]\n";
+
+const INSTRUCTIONS: &str = "You write titles and summaries for chat conversations. \
+Reply with JSON containing a \"title\" of at most six words, without quotes or \
+trailing punctuation, and a \"summary\" of one short paragraph describing what \
+the user wanted and what was concluded. Write both in the language of the conversation.";
+
+/// Generates a title and summary for a conversation with an LLM.
+///
+/// Only messages are sent; tool calls and system messages are left out.
+/// Transcripts longer than the configured limit keep their beginning and
+/// end, where the topic and the outcome usually are.
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::{Conversation, Message};
+/// use llm::{AnthropicProvider, ConversationSummarizer, ModelId};
+///
+/// # async fn example(mut conversation: Conversation) -> agent_core::Result<()> {
+/// let cheap = AnthropicProvider::builder()
+///     .api_key("your-api-key")
+///     .model(ModelId::CLAUDE_HAIKU_4_5)
+///     .build()?;
+/// let summarizer = ConversationSummarizer::new(cheap);
+///
+/// let summary = summarizer.summarize_into(&mut conversation).await?;
+/// println!("{}: {}", summary.title, summary.summary);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ConversationSummarizer<P: LLMProvider> {
+    provider: P,
+    max_transcript_chars: usize,
+}
+
+impl<P: LLMProvider> ConversationSummarizer<P> {
+    /// Create a summarizer that uses `provider`
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            max_transcript_chars: DEFAULT_MAX_TRANSCRIPT_CHARS,
+        }
+    }
+
+    /// Set how many transcript characters are sent to the model
+    pub fn with_max_transcript_chars(mut self, max_chars: usize) -> Self {
+        self.max_transcript_chars = max_chars;
+        self
+    }
+
+    /// Generate a title and summary for `conversation`
+    ///
+    /// # Errors
+    /// Returns an error if the conversation has no user or assistant
+    /// messages, or if the model call fails.
+    pub async fn summarize(&self, conversation: &Conversation) -> Result<ConversationSummary> {
+        let transcript = self.transcript(conversation);
+        if transcript.is_empty() {
+            return Err(AgentError::Execution(
+                "Cannot summarize a conversation without messages".to_string(),
+            ));
+        }
+
+        let output = StructuredOutput::new(json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string"},
+                "summary": {"type": "string"}
+            },
+            "required": ["title", "summary"],
+            "additionalProperties": false
+        }));
+        let messages = [
+            Message::system(INSTRUCTIONS),
+            Message::user(format!("Conversation:\n\n{}", transcript)),
+        ];
+        let value = self.provider.send_structured(&messages, &output).await?;
+
+        let field = |name: &str| value[name].as_str().unwrap_or_default().trim().to_string();
+        let title = field("title")
+            .trim_matches(|c: char| c == '"' || c == '.')
+            .to_string();
+        Ok(ConversationSummary::new(title, field("summary")))
+    }
+
+    /// Generate a title and summary and store them on `conversation`
+    ///
+    /// # Errors
+    /// Returns an error if the conversation has no user or assistant
+    /// messages, or if the model call fails.
+    pub async fn summarize_into<'a>(
+        &self,
+        conversation: &'a mut Conversation,
+    ) -> Result<&'a ConversationSummary> {
+        let summary = self.summarize(conversation).await?;
+        conversation.set_summary(summary);
+        Ok(conversation.summary().expect("summary was just set"))
+    }
+
+    /// Render the conversation's messages as plain text within the limit
+    fn transcript(&self, conversation: &Conversation) -> String {
+        let transcript = conversation
+            .messages()
+            .filter_map(|message| match message.role {
+                Role::User => Some(format!("User: {}", message.content)),
+                Role::Assistant => Some(format!("Assistant: {}", message.content)),
+                Role::System => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let chars = transcript.chars().count();
+        if chars <= self.max_transcript_chars {
+            return transcript;
+        }
+        let keep = self.max_transcript_chars.saturating_sub(ELISION.len()) / 2;
+        let head: String = transcript.chars().take(keep).collect();
+        let tail: String = transcript.chars().skip(chars - keep).collect();
+        format!("{}{}{}", head, ELISION, tail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct RecordingProvider {
+        reply: &'static str,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl RecordingProvider {
+        fn new(reply: &'static str) -> Self {
+            Self {
+                reply,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for RecordingProvider {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.reply.to_string())
+        }
+    }
+
+    fn conversation() -> Conversation {
+        Conversation::from(vec![
+            Message::system("Be helpful"),
+            Message::user("How do I reverse a Vec in Rust?"),
+            Message::assistant("Call `v.reverse()`."),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_summarize_into_stores_summary() {
+        let summarizer = ConversationSummarizer::new(RecordingProvider::new(
+            r#"{"title": "Reversing a Vec.", "summary": "The user asked how to reverse a Vec."}"#,
+        ));
+        let mut conversation = conversation();
+
+        let summary = summarizer.summarize_into(&mut conversation).await.unwrap();
+        assert_eq!(summary.title, "Reversing a Vec");
+        assert_eq!(conversation.title(), Some("Reversing a Vec"));
+
+        let requests = summarizer.provider.requests.lock().unwrap();
+        let prompt = &requests[0].last().unwrap().content;
+        assert!(prompt.contains("User: How do I reverse a Vec in Rust?"));
+        assert!(!prompt.contains("Be helpful"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_conversation_fails() {
+        let summarizer = ConversationSummarizer::new(RecordingProvider::new("{}"));
+        let result = summarizer.summarize(&Conversation::new()).await;
+        assert!(matches!(result, Err(AgentError::Execution(_))));
+    }
+
+    #[test]
+    fn test_long_transcript_keeps_head_and_tail() {
+        let summarizer =
+            ConversationSummarizer::new(RecordingProvider::new("{}")).with_max_transcript_chars(60);
+        let conversation = Conversation::from(vec![
+            Message::user(format!("START {}", "a".repeat(100))),
+            Message::assistant(format!("{} END", "b".repeat(100))),
+        ]);
+
+        let transcript = summarizer.transcript(&conversation);
+        assert!(transcript.chars().count() <= 60);
+        assert!(transcript.starts_with("User: START"));
+        assert!(transcript.ends_with("END"));
+        assert!(transcript.contains("[...]"));
+    }
+}
@@ -0,0 +1,464 @@
+//! Request telemetry that need not retain user content.
+//!
+//! [`TelemetryProvider`] records one [`TelemetryEvent`] per request: which
+//! model answered, how long it took, the tokens it used and whether it
+//! failed. What is kept of the prompt and completion is chosen by a
+//! [`ContentMode`]. Operators who must not retain user content can record a
+//! keyed hash, so identical prompts can still be counted and grouped, or an
+//! embedding, so topics can be clustered, instead of the text itself.
+
+use agent_core::{Message, Result, Role, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::{
+    CompletionResponse, LLMProvider, StructuredOutput, TokenStream, TokenUsage, ToolConfig,
+};
+
+/// Computes an embedding of a text, e.g. with a small local model
+pub type Embedder = Arc<dyn Fn(&str) -> Vec<f32> + Send + Sync>;
+
+/// What telemetry keeps of prompts and completions
+#[derive(Clone, Default)]
+pub enum ContentMode {
+    /// The text as sent and received
+    #[default]
+    Verbatim,
+    /// A hash of the text keyed by a secret salt
+    ///
+    /// Without the salt, a hash can't be matched against hashes of guessed
+    /// prompts. Hashes are only comparable between processes built with the
+    /// same toolchain that use the same salt.
+    Hashed {
+        /// Secret mixed into every hash
+        salt: String,
+    },
+    /// An embedding of the text computed by the caller's model
+    Embedded(Embedder),
+    /// Only the length of the text
+    Omitted,
+}
+
+impl ContentMode {
+    /// Record keyed hashes of content
+    pub fn hashed(salt: impl Into<String>) -> Self {
+        Self::Hashed { salt: salt.into() }
+    }
+
+    /// Record embeddings of content computed by `embedder`
+    pub fn embedded(embedder: impl Fn(&str) -> Vec<f32> + Send + Sync + 'static) -> Self {
+        Self::Embedded(Arc::new(embedder))
+    }
+
+    /// What to record of `text`
+    pub fn record(&self, text: &str) -> RecordedContent {
+        let chars = text.chars().count();
+        match self {
+            Self::Verbatim => RecordedContent::Verbatim {
+                text: text.to_string(),
+            },
+            Self::Hashed { salt } => {
+                let mut hasher = DefaultHasher::new();
+                salt.hash(&mut hasher);
+                text.hash(&mut hasher);
+                RecordedContent::Hashed {
+                    hash: format!("{:016x}", hasher.finish()),
+                    chars,
+                }
+            }
+            Self::Embedded(embedder) => RecordedContent::Embedded {
+                embedding: embedder(text),
+                chars,
+            },
+            Self::Omitted => RecordedContent::Omitted { chars },
+        }
+    }
+}
+
+impl fmt::Debug for ContentMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The salt is a secret and the embedder is opaque
+        match self {
+            Self::Verbatim => f.write_str("Verbatim"),
+            Self::Hashed { .. } => f.write_str("Hashed"),
+            Self::Embedded(_) => f.write_str("Embedded"),
+            Self::Omitted => f.write_str("Omitted"),
+        }
+    }
+}
+
+/// What was kept of a prompt message or completion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedContent {
+    /// The text itself
+    Verbatim {
+        /// The text
+        text: String,
+    },
+    /// A keyed hash of the text, as 16 hex digits
+    Hashed {
+        /// The hash
+        hash: String,
+        /// Length of the text in characters
+        chars: usize,
+    },
+    /// An embedding of the text
+    Embedded {
+        /// The embedding
+        embedding: Vec<f32>,
+        /// Length of the text in characters
+        chars: usize,
+    },
+    /// Nothing but the length of the text
+    Omitted {
+        /// Length of the text in characters
+        chars: usize,
+    },
+}
+
+/// A prompt message as recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Who sent the message
+    pub role: Role,
+    /// What was kept of its content
+    pub content: RecordedContent,
+}
+
+/// One request to a provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryEvent {
+    /// When the request was sent
+    pub timestamp: DateTime<Utc>,
+    /// Name of the provider, as given to [`TelemetryProvider::new`]
+    pub provider: String,
+    /// The model that answered, if the provider reported it
+    pub model: Option<String>,
+    /// Time until the response, or the stream, was received
+    pub latency_ms: u64,
+    /// Tokens billed, if the provider reported them
+    pub usage: Option<TokenUsage>,
+    /// The prompt messages
+    pub prompt: Vec<RecordedMessage>,
+    /// The completion; absent for failed requests and streams
+    pub completion: Option<RecordedContent>,
+    /// The error message of a failed request
+    pub error: Option<String>,
+}
+
+/// Destination of telemetry events
+pub trait TelemetrySink: Send + Sync {
+    /// Store or forward an event
+    fn record(&self, event: TelemetryEvent);
+
+    /// Write out buffered events, e.g. from a
+    /// [shutdown hook](agent_core::Shutdown::on_shutdown)
+    ///
+    /// Sinks that do not buffer need not implement this.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Telemetry sink that keeps events in memory
+#[derive(Debug, Default)]
+pub struct InMemoryTelemetry {
+    events: Mutex<Vec<TelemetryEvent>>,
+}
+
+impl InMemoryTelemetry {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The events recorded so far, oldest first
+    pub fn events(&self) -> Vec<TelemetryEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl TelemetrySink for InMemoryTelemetry {
+    fn record(&self, event: TelemetryEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+/// Provider wrapper that records a [`TelemetryEvent`] for every request
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{ContentMode, InMemoryTelemetry, LLMProvider, OpenAIProvider, TelemetryProvider};
+/// use agent_core::Message;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let telemetry = Arc::new(InMemoryTelemetry::new());
+/// let provider = TelemetryProvider::new(
+///     OpenAIProvider::builder().api_key("your-api-key").build()?,
+///     "openai",
+///     telemetry.clone(),
+/// )
+/// .with_content_mode(ContentMode::hashed("telemetry-salt"));
+///
+/// provider.send_message(&[Message::user("Hello")]).await?;
+/// println!("{:?}", telemetry.events()[0].prompt);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TelemetryProvider<P> {
+    inner: P,
+    provider: String,
+    sink: Arc<dyn TelemetrySink>,
+    content_mode: ContentMode,
+}
+
+impl<P: LLMProvider> TelemetryProvider<P> {
+    /// Wrap a provider, recording content verbatim
+    pub fn new(inner: P, provider: impl Into<String>, sink: Arc<dyn TelemetrySink>) -> Self {
+        Self {
+            inner,
+            provider: provider.into(),
+            sink,
+            content_mode: ContentMode::default(),
+        }
+    }
+
+    /// Set what is kept of prompts and completions
+    pub fn with_content_mode(mut self, content_mode: ContentMode) -> Self {
+        self.content_mode = content_mode;
+        self
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn record<T>(
+        &self,
+        messages: &[Message],
+        started: (DateTime<Utc>, Instant),
+        result: &Result<T>,
+        describe: impl Fn(&T) -> (Option<&str>, Option<&CompletionResponse>),
+    ) {
+        let (completion, response) = match result {
+            Ok(value) => describe(value),
+            Err(_) => (None, None),
+        };
+        self.sink.record(TelemetryEvent {
+            timestamp: started.0,
+            provider: self.provider.clone(),
+            model: response.and_then(|response| response.model.clone()),
+            latency_ms: started.1.elapsed().as_millis() as u64,
+            usage: response.and_then(|response| response.usage),
+            prompt: messages
+                .iter()
+                .map(|message| RecordedMessage {
+                    role: message.role.clone(),
+                    content: self.content_mode.record(&message.content),
+                })
+                .collect(),
+            completion: completion.map(|text| self.content_mode.record(text)),
+            error: result.as_ref().err().map(ToString::to_string),
+        });
+    }
+}
+
+fn start() -> (DateTime<Utc>, Instant) {
+    (Utc::now(), Instant::now())
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for TelemetryProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let started = start();
+        let result = self.inner.send_message(messages).await;
+        self.record(messages, started, &result, |text| (Some(text), None));
+        result
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let started = start();
+        let result = self.inner.send_completion(messages).await;
+        self.record(messages, started, &result, |response| {
+            (Some(&response.text), Some(response))
+        });
+        result
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        Ok(self.send_structured_completion(messages, output).await?.0)
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        let started = start();
+        let result = self
+            .inner
+            .send_structured_completion(messages, output)
+            .await;
+        self.record(messages, started, &result, |(_, response)| {
+            (Some(&response.text), Some(response))
+        });
+        result
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let started = start();
+        let result = self
+            .inner
+            .send_message_with_tools(messages, tools, config)
+            .await;
+        self.record(messages, started, &result, |response| {
+            (Some(&response.text), None)
+        });
+        result
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let started = start();
+        let result = self.inner.stream_message(messages).await;
+        self.record(messages, started, &result, |_| (None, None));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FinishReason;
+    use agent_core::AgentError;
+
+    struct Echo;
+
+    #[async_trait]
+    impl LLMProvider for Echo {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            match messages.last() {
+                Some(message) if message.content == "fail" => {
+                    Err(AgentError::LLMProvider("upstream down".to_string()))
+                }
+                Some(message) => Ok(format!("echo: {}", message.content)),
+                None => Ok(String::new()),
+            }
+        }
+
+        async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+            let text = self.send_message(messages).await?;
+            Ok(CompletionResponse::new(text, FinishReason::Stop)
+                .with_model("echo-1")
+                .with_usage(TokenUsage {
+                    input_tokens: 5,
+                    output_tokens: 3,
+                }))
+        }
+    }
+
+    fn provider(mode: ContentMode) -> (TelemetryProvider<Echo>, Arc<InMemoryTelemetry>) {
+        let sink = Arc::new(InMemoryTelemetry::new());
+        let provider = TelemetryProvider::new(Echo, "echo", sink.clone()).with_content_mode(mode);
+        (provider, sink)
+    }
+
+    #[tokio::test]
+    async fn test_records_usage_and_verbatim_content() {
+        let (provider, sink) = provider(ContentMode::Verbatim);
+        provider
+            .send_completion(&[Message::system("Be nice"), Message::user("hi")])
+            .await
+            .unwrap();
+
+        let event = &sink.events()[0];
+        assert_eq!(event.provider, "echo");
+        assert_eq!(event.model.as_deref(), Some("echo-1"));
+        assert_eq!(event.usage.unwrap().total(), 8);
+        assert_eq!(event.prompt[0].role, Role::System);
+        assert_eq!(
+            event.completion,
+            Some(RecordedContent::Verbatim {
+                text: "echo: hi".to_string()
+            })
+        );
+        assert_eq!(event.error, None);
+    }
+
+    #[tokio::test]
+    async fn test_hashed_mode_keeps_no_text() {
+        let (provider, sink) = provider(ContentMode::hashed("salt-a"));
+        provider
+            .send_message(&[Message::user("my ssn is 123")])
+            .await
+            .unwrap();
+        provider
+            .send_message(&[Message::user("my ssn is 123")])
+            .await
+            .unwrap();
+        assert!(
+            provider
+                .send_message(&[Message::user("fail")])
+                .await
+                .is_err()
+        );
+
+        let events = sink.events();
+        let serialized = serde_json::to_string(&events).unwrap();
+        assert!(!serialized.contains("123"));
+        assert_eq!(events[0].prompt, events[1].prompt);
+        assert!(matches!(
+            events[0].prompt[0].content,
+            RecordedContent::Hashed { chars: 13, .. }
+        ));
+        assert_eq!(events[2].completion, None);
+        assert_eq!(
+            events[2].error.as_deref(),
+            Some("LLM provider error: upstream down")
+        );
+
+        // Another salt gives unrelated hashes
+        let other = ContentMode::hashed("salt-b").record("my ssn is 123");
+        assert_ne!(events[0].prompt[0].content, other);
+    }
+
+    #[tokio::test]
+    async fn test_embedded_and_omitted_modes() {
+        let (provider, sink) = provider(ContentMode::embedded(|text| vec![text.len() as f32, 1.0]));
+        provider
+            .send_message(&[Message::user("abc")])
+            .await
+            .unwrap();
+        assert_eq!(
+            sink.events()[0].prompt[0].content,
+            RecordedContent::Embedded {
+                embedding: vec![3.0, 1.0],
+                chars: 3
+            }
+        );
+
+        assert_eq!(
+            ContentMode::Omitted.record("héllo"),
+            RecordedContent::Omitted { chars: 5 }
+        );
+    }
+}
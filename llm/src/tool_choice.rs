@@ -0,0 +1,61 @@
+//! Controls over whether and how a model may call tools.
+//!
+//! [`ToolChoice`] is provider-neutral; each provider converts it, together
+//! with the parallel tool use setting, into its own request fields
+//! (`tool_choice` and `disable_parallel_tool_use` for Anthropic,
+//! `tool_choice` and `parallel_tool_calls` for OpenAI).
+
+/// Which tools the model may call for one request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool
+    #[default]
+    Auto,
+    /// The model must call at least one tool, of its choosing
+    Any,
+    /// The model must not call tools
+    None,
+    /// The model must call this tool
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// Force a call to the named tool
+    pub fn tool(name: impl Into<String>) -> Self {
+        Self::Tool(name.into())
+    }
+}
+
+/// Tool calling settings for one request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolConfig {
+    /// Which tools may be called
+    pub choice: ToolChoice,
+    /// Whether the model may call several tools in one response
+    pub parallel: bool,
+}
+
+impl Default for ToolConfig {
+    fn default() -> Self {
+        Self {
+            choice: ToolChoice::Auto,
+            parallel: true,
+        }
+    }
+}
+
+impl ToolConfig {
+    /// Settings with the given choice and parallel calls allowed
+    pub fn new(choice: ToolChoice) -> Self {
+        Self {
+            choice,
+            ..Self::default()
+        }
+    }
+
+    /// Allow at most one tool call per response
+    pub fn sequential(mut self) -> Self {
+        self.parallel = false;
+        self
+    }
+}
@@ -0,0 +1,183 @@
+//! Tool calling for providers without a native tool API.
+//!
+//! [`LLMProvider::send_message_with_tools`] maps to Anthropic `tool_use`
+//! blocks, OpenAI function calling and Gemini function declarations. Other
+//! providers fall back to [`send_with_prompted_tools`]: the tools are
+//! described in a system message and the reply is requested as structured
+//! output, so callers get the same [`ToolUseResponse`] either way.
+//!
+//! [`LLMProvider::send_message_with_tools`]: crate::LLMProvider::send_message_with_tools
+
+use agent_core::{Message, Result, ToolDefinition, ToolUse, ToolUseResponse};
+use serde_json::{Value, json};
+
+use crate::{LLMProvider, StructuredOutput, ToolChoice, ToolConfig};
+
+/// Ask for tool calls through a prompt and structured output
+pub(crate) async fn send_with_prompted_tools<P>(
+    provider: &P,
+    messages: &[Message],
+    tools: &[ToolDefinition],
+    config: &ToolConfig,
+) -> Result<ToolUseResponse>
+where
+    P: LLMProvider + ?Sized,
+{
+    if tools.is_empty() || config.choice == ToolChoice::None {
+        return Ok(ToolUseResponse::text(provider.send_message(messages).await?));
+    }
+
+    let names: Vec<&str> = match &config.choice {
+        ToolChoice::Tool(name) => vec![name.as_str()],
+        _ => tools.iter().map(|tool| tool.name.as_str()).collect(),
+    };
+    let output = StructuredOutput::new(json!({
+        "type": "object",
+        "properties": {
+            "text": {"type": "string"},
+            "tool_calls": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string", "enum": names},
+                        "arguments": {"type": "object"}
+                    },
+                    "required": ["name", "arguments"]
+                }
+            }
+        },
+        "additionalProperties": false
+    }));
+
+    let mut request = vec![Message::system(tool_prompt(tools, config))];
+    request.extend_from_slice(messages);
+    let value = provider.send_structured(&request, &output).await?;
+
+    let mut tool_uses: Vec<ToolUse> = value["tool_calls"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, call)| ToolUse {
+            id: format!("call_{}", index),
+            name: call["name"].as_str().unwrap_or_default().to_string(),
+            arguments: call["arguments"].clone(),
+        })
+        .collect();
+    if !config.parallel {
+        tool_uses.truncate(1);
+    }
+
+    Ok(ToolUseResponse {
+        text: value["text"].as_str().unwrap_or_default().to_string(),
+        tool_uses,
+    })
+}
+
+/// System prompt describing the tools and how to call them
+fn tool_prompt(tools: &[ToolDefinition], config: &ToolConfig) -> String {
+    let mut prompt = String::from(
+        "You can call tools. Reply with a JSON object: put any text for the user in \
+         \"text\" and the calls you want to make in \"tool_calls\", each with the tool \
+         \"name\" and its \"arguments\".\n\nAvailable tools:\n",
+    );
+    for tool in tools {
+        prompt.push_str(&format!(
+            "- {}: {}\n  Arguments: {}\n",
+            tool.name, tool.description, tool.parameters
+        ));
+    }
+    match &config.choice {
+        ToolChoice::Any => prompt.push_str("\nYou must call at least one tool.\n"),
+        ToolChoice::Tool(name) => prompt.push_str(&format!("\nYou must call {}.\n", name)),
+        ToolChoice::Auto | ToolChoice::None => {}
+    }
+    if !config.parallel {
+        prompt.push_str("Call at most one tool.\n");
+    }
+    prompt
+}
+
+/// Parse tool arguments sent as a JSON string, keeping invalid JSON as a
+/// string so the tool can report it
+pub(crate) fn parse_arguments(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Replies with a fixed response and records the requests
+    struct Scripted {
+        reply: &'static str,
+        requests: Mutex<Vec<Vec<Message>>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            self.requests.lock().unwrap().push(messages.to_vec());
+            Ok(self.reply.to_string())
+        }
+    }
+
+    fn scripted(reply: &'static str) -> Scripted {
+        Scripted {
+            reply,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn search() -> ToolDefinition {
+        ToolDefinition::new("search", "Search the web", json!({"type": "object"}))
+    }
+
+    #[tokio::test]
+    async fn test_prompted_tool_calls() {
+        let provider = scripted(
+            r#"{"text": "Searching", "tool_calls": [
+                {"name": "search", "arguments": {"q": "rust"}},
+                {"name": "search", "arguments": {"q": "tokio"}}
+            ]}"#,
+        );
+        let config = ToolConfig::new(ToolChoice::Any).sequential();
+        let response = provider
+            .send_message_with_tools(&[Message::user("Find docs")], &[search()], &config)
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "Searching");
+        assert_eq!(
+            response.tool_uses,
+            vec![ToolUse {
+                id: "call_0".to_string(),
+                name: "search".to_string(),
+                arguments: json!({"q": "rust"}),
+            }]
+        );
+        let requests = provider.requests.lock().unwrap();
+        let prompt = requests[0]
+            .iter()
+            .find(|message| message.content.contains("Available tools"))
+            .unwrap();
+        assert!(prompt.content.contains("- search: Search the web"));
+        assert!(prompt.content.contains("must call at least one tool"));
+    }
+
+    #[tokio::test]
+    async fn test_tools_disabled_sends_plain_message() {
+        let provider = scripted("Hello");
+        let config = ToolConfig::new(ToolChoice::None);
+        let response = provider
+            .send_message_with_tools(&[Message::user("Hi")], &[search()], &config)
+            .await
+            .unwrap();
+
+        assert_eq!(response, ToolUseResponse::text("Hello"));
+        assert_eq!(provider.requests.lock().unwrap()[0].len(), 1);
+    }
+}
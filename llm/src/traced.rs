@@ -0,0 +1,301 @@
+//! `tracing` spans around provider requests.
+//!
+//! [`TracedProvider`] runs every request in an `llm.request` span carrying
+//! the provider, the model, the trace ID of the current
+//! [`RequestContext`] and, once the response arrives, its token counts.
+//! Spans cost next to nothing without a subscriber; with one, such as the
+//! OpenTelemetry exporter of the `athena-ai` crate, they show where the
+//! latency of an agent run goes.
+
+use agent_core::{Message, RequestContext, Result, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::Value;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+use crate::{
+    CompletionResponse, LLMProvider, StreamEvent, StructuredOutput, TokenStream, TokenUsage,
+    ToolConfig,
+};
+
+/// Provider wrapper that runs each request in a `tracing` span
+///
+/// [`create_provider`](crate::create_provider) wraps every provider it
+/// creates in one, so other wrappers only need it for providers built
+/// directly.
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{LLMProvider, OpenAIProvider, TracedProvider};
+/// use agent_core::Message;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = TracedProvider::new(
+///     OpenAIProvider::builder().api_key("your-api-key").build()?,
+///     "openai",
+/// )
+/// .with_model("gpt-4o");
+///
+/// provider.send_message(&[Message::user("Hello")]).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TracedProvider<P> {
+    inner: P,
+    provider: String,
+    model: Option<String>,
+}
+
+impl<P: LLMProvider> TracedProvider<P> {
+    /// Wrap a provider, naming it `provider` in spans
+    pub fn new(inner: P, provider: impl Into<String>) -> Self {
+        Self {
+            inner,
+            provider: provider.into(),
+            model: None,
+        }
+    }
+
+    /// Set the model recorded for requests whose response does not name one
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn span(&self, operation: &'static str, messages: &[Message]) -> Span {
+        let trace_id = RequestContext::current().map(|request| request.trace_id);
+        tracing::info_span!(
+            "llm.request",
+            provider = %self.provider,
+            operation,
+            model = self.model.as_deref(),
+            trace_id = trace_id.as_deref(),
+            messages = messages.len(),
+            input_tokens = Empty,
+            output_tokens = Empty,
+            tools = Empty,
+            error = Empty,
+        )
+    }
+}
+
+/// Record a response's model and usage, or the error, on `span`
+fn record<T>(
+    span: &Span,
+    result: &Result<T>,
+    response: impl Fn(&T) -> Option<&CompletionResponse>,
+) {
+    match result {
+        Ok(value) => {
+            if let Some(response) = response(value) {
+                if let Some(model) = &response.model {
+                    span.record("model", model.as_str());
+                }
+                if let Some(usage) = response.usage {
+                    record_usage(span, usage);
+                }
+            }
+        }
+        Err(e) => {
+            span.record("error", tracing::field::display(e));
+        }
+    }
+}
+
+fn record_usage(span: &Span, usage: TokenUsage) {
+    span.record("input_tokens", usage.input_tokens);
+    span.record("output_tokens", usage.output_tokens);
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for TracedProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        let span = self.span("send_message", messages);
+        let result = self.inner.send_message(messages).instrument(span.clone()).await;
+        record(&span, &result, |_| None);
+        result
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        let span = self.span("send_completion", messages);
+        let result = self.inner.send_completion(messages).instrument(span.clone()).await;
+        record(&span, &result, |response| Some(response));
+        result
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        Ok(self.send_structured_completion(messages, output).await?.0)
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        let span = self.span("send_structured_completion", messages);
+        let result = self
+            .inner
+            .send_structured_completion(messages, output)
+            .instrument(span.clone())
+            .await;
+        record(&span, &result, |(_, response)| Some(response));
+        result
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        let span = self.span("send_message_with_tools", messages);
+        span.record("tools", tools.len());
+        let result = self
+            .inner
+            .send_message_with_tools(messages, tools, config)
+            .instrument(span.clone())
+            .await;
+        record(&span, &result, |_| None);
+        result
+    }
+
+    /// The span stays open until the stream is dropped, and records the
+    /// usage reported at its end
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        let span = self.span("stream_message", messages);
+        let result = self.inner.stream_message(messages).instrument(span.clone()).await;
+        record(&span, &result, |_| None);
+        let events = result?.inspect(move |event| {
+            if let Ok(StreamEvent::Usage(usage)) = event {
+                record_usage(&span, *usage);
+            }
+        });
+        Ok(Box::pin(events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FinishReason;
+    use agent_core::AgentError;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::Registry;
+
+    /// Field values recorded on spans, open and closed
+    ///
+    /// Open spans are kept by ID; IDs are reused once a span closes.
+    #[derive(Clone, Default)]
+    struct Recorded {
+        open: Arc<Mutex<HashMap<u64, HashMap<String, String>>>>,
+        closed: Arc<Mutex<Vec<HashMap<String, String>>>>,
+    }
+
+    impl Recorded {
+        fn fields(&self) -> Vec<HashMap<String, String>> {
+            let closed = self.closed.lock().unwrap().clone();
+            closed.into_iter().chain(self.open.lock().unwrap().values().cloned()).collect()
+        }
+    }
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for Recorded {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+            let mut fields = HashMap::new();
+            attrs.record(&mut Fields(&mut fields));
+            self.open.lock().unwrap().insert(id.into_u64(), fields);
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            if let Some(fields) = self.open.lock().unwrap().get_mut(&id.into_u64()) {
+                values.record(&mut Fields(fields));
+            }
+        }
+
+        fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+            if let Some(fields) = self.open.lock().unwrap().remove(&id.into_u64()) {
+                self.closed.lock().unwrap().push(fields);
+            }
+        }
+    }
+
+    struct Echo;
+
+    #[async_trait]
+    impl LLMProvider for Echo {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            match messages.last() {
+                Some(message) if message.content == "fail" => {
+                    Err(AgentError::LLMProvider("upstream down".to_string()))
+                }
+                _ => Ok("echo".to_string()),
+            }
+        }
+
+        async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+            let text = self.send_message(messages).await?;
+            Ok(CompletionResponse::new(text, FinishReason::Stop)
+                .with_model("echo-1")
+                .with_usage(TokenUsage {
+                    input_tokens: 5,
+                    output_tokens: 3,
+                }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spans_record_request_and_usage() {
+        let recorded = Recorded::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(recorded.clone()));
+        let provider = TracedProvider::new(Echo, "echo").with_model("echo-default");
+
+        RequestContext::with_trace_id("trace-1")
+            .scope(provider.send_completion(&[Message::user("hi")]))
+            .await
+            .unwrap();
+        let fields = recorded.fields();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0]["provider"], "echo");
+        assert_eq!(fields[0]["operation"], "send_completion");
+        assert_eq!(fields[0]["trace_id"], "trace-1");
+        assert_eq!(fields[0]["model"], "echo-1");
+        assert_eq!(fields[0]["input_tokens"], "5");
+        assert_eq!(fields[0]["output_tokens"], "3");
+
+        assert!(provider.send_message(&[Message::user("fail")]).await.is_err());
+        let failed = recorded
+            .fields()
+            .into_iter()
+            .find(|fields| fields.contains_key("error"))
+            .unwrap();
+        assert_eq!(failed["model"], "echo-default");
+        assert!(failed["error"].contains("upstream down"));
+        assert!(!failed.contains_key("trace_id"));
+    }
+}
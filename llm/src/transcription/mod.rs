@@ -0,0 +1,145 @@
+//! Speech-to-text transcription providers.
+//!
+//! A [`TranscriptionProvider`] turns recorded audio into text so voice input
+//! can be fed into the framework as `Message::user` content.
+//!
+//! # Implementations
+//!
+//! - [`OpenAIWhisperProvider`]: OpenAI's hosted Whisper API
+//! - [`WhisperCppProvider`]: A local whisper.cpp server
+
+pub mod openai;
+pub mod whisper_cpp;
+
+use agent_core::{AgentError, Message, Result};
+use async_trait::async_trait;
+use std::path::Path;
+
+pub use openai::OpenAIWhisperProvider;
+pub use whisper_cpp::WhisperCppProvider;
+
+/// Audio data to be transcribed
+#[derive(Debug, Clone)]
+pub struct AudioInput {
+    /// Raw encoded audio bytes (e.g. WAV, MP3)
+    pub data: Vec<u8>,
+    /// File name sent with the upload; its extension tells the server the format
+    pub file_name: String,
+    /// MIME type of the audio
+    pub mime_type: String,
+}
+
+impl AudioInput {
+    /// Create audio input from bytes, inferring the MIME type from the file name
+    pub fn new(data: Vec<u8>, file_name: impl Into<String>) -> Self {
+        let file_name = file_name.into();
+        let mime_type = mime_type_for(&file_name).to_string();
+        Self {
+            data,
+            file_name,
+            mime_type,
+        }
+    }
+
+    /// Read audio input from a file
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = tokio::fs::read(path).await?;
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                AgentError::Config(format!("Invalid audio file path: {}", path.display()))
+            })?;
+        Ok(Self::new(data, file_name))
+    }
+
+    /// Build a multipart form part for uploading this audio
+    pub(crate) fn to_part(&self) -> Result<reqwest::multipart::Part> {
+        reqwest::multipart::Part::bytes(self.data.clone())
+            .file_name(self.file_name.clone())
+            .mime_str(&self.mime_type)
+            .map_err(|e| AgentError::Config(format!("Invalid audio MIME type: {}", e)))
+    }
+}
+
+/// Trait for speech-to-text providers
+#[async_trait]
+pub trait TranscriptionProvider: Send + Sync {
+    /// Transcribe audio into text
+    ///
+    /// # Arguments
+    /// * `audio` - The audio to transcribe
+    ///
+    /// # Returns
+    /// * `Result<String>` - The transcribed text or an error
+    async fn transcribe(&self, audio: &AudioInput) -> Result<String>;
+
+    /// Transcribe audio into a user message
+    async fn transcribe_to_message(&self, audio: &AudioInput) -> Result<Message> {
+        let text = self.transcribe(audio).await?;
+        Ok(Message::user(text.trim()))
+    }
+}
+
+/// Response body returned by Whisper-compatible JSON endpoints
+#[derive(Debug, serde::Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+fn mime_type_for(file_name: &str) -> &'static str {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "wav" => "audio/wav",
+        "mp3" | "mpga" | "mpeg" => "audio/mpeg",
+        "m4a" | "mp4" => "audio/mp4",
+        "ogg" | "oga" => "audio/ogg",
+        "webm" => "audio/webm",
+        "flac" => "audio/flac",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedTranscriber;
+
+    #[async_trait]
+    impl TranscriptionProvider for FixedTranscriber {
+        async fn transcribe(&self, _audio: &AudioInput) -> Result<String> {
+            Ok("  what's the weather?\n".to_string())
+        }
+    }
+
+    #[test]
+    fn test_mime_type_inference() {
+        assert_eq!(AudioInput::new(vec![], "clip.WAV").mime_type, "audio/wav");
+        assert_eq!(AudioInput::new(vec![], "clip.mp3").mime_type, "audio/mpeg");
+        assert_eq!(
+            AudioInput::new(vec![], "clip").mime_type,
+            "application/octet-stream"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_to_message() {
+        let message = FixedTranscriber
+            .transcribe_to_message(&AudioInput::new(vec![1, 2, 3], "clip.wav"))
+            .await
+            .unwrap();
+        assert_eq!(message.role, agent_core::Role::User);
+        assert_eq!(message.content, "what's the weather?");
+    }
+
+    #[tokio::test]
+    async fn test_from_missing_file() {
+        let result = AudioInput::from_file("/nonexistent/clip.wav").await;
+        assert!(matches!(result, Err(AgentError::Io(_))));
+    }
+}
@@ -0,0 +1,146 @@
+//! OpenAI Whisper transcription provider.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::{ApiClient, check_status, request_error};
+use std::time::Duration;
+
+use super::{AudioInput, TranscriptionProvider, TranscriptionResponse};
+
+/// Default OpenAI API base URL
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Transcription provider backed by OpenAI's Whisper API
+///
+/// # Example
+///
+/// ```no_run
+/// use llm::{AudioInput, OpenAIWhisperProvider, TranscriptionProvider};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let provider = OpenAIWhisperProvider::new("sk-...").with_language("en");
+/// let audio = AudioInput::from_file("question.wav").await?;
+/// let message = provider.transcribe_to_message(&audio).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OpenAIWhisperProvider {
+    api_key: String,
+    model: String,
+    language: Option<String>,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl OpenAIWhisperProvider {
+    /// Create a provider using the `whisper-1` model
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            model: "whisper-1".to_string(),
+            language: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the transcription model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the spoken language as an ISO-639-1 code, improving accuracy and latency
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for OpenAIWhisperProvider {
+    async fn transcribe(&self, audio: &AudioInput) -> Result<String> {
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", audio.to_part()?)
+            .text("model", self.model.clone())
+            .text("response_format", "json");
+        if let Some(language) = &self.language {
+            form = form.text("language", language.clone());
+        }
+
+        let url = format!("{}/audio/transcriptions", self.base_url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .multipart(form)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| request_error("Whisper API", e))?;
+
+        let response = check_status("Whisper API", response).await?;
+
+        let transcription: TranscriptionResponse = response.json().await.map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize Whisper response: {}", e))
+        })?;
+        Ok(transcription.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_transcribe() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/audio/transcriptions"))
+            .and(header("Authorization", "Bearer test-key"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"text": "Hello there"})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIWhisperProvider::new("test-key").with_base_url(mock_server.uri());
+        let text = provider
+            .transcribe(&AudioInput::new(vec![0; 16], "clip.wav"))
+            .await
+            .unwrap();
+        assert_eq!(text, "Hello there");
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_http_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Invalid file format"))
+            .mount(&mock_server)
+            .await;
+
+        let provider = OpenAIWhisperProvider::new("test-key").with_base_url(mock_server.uri());
+        let err = provider
+            .transcribe(&AudioInput::new(vec![0; 16], "clip.xyz"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Whisper API HTTP 400"));
+    }
+}
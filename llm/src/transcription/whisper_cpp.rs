@@ -0,0 +1,131 @@
+//! Local whisper.cpp transcription provider.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::{ApiClient, check_status, request_error};
+use std::time::Duration;
+
+use super::{AudioInput, TranscriptionProvider, TranscriptionResponse};
+
+/// Default address of `whisper-server` from whisper.cpp
+const DEFAULT_BASE_URL: &str = "http://127.0.0.1:8080";
+
+/// Transcription provider backed by a local whisper.cpp server
+///
+/// Talks to the `/inference` endpoint exposed by whisper.cpp's
+/// `whisper-server` example, so audio never leaves the machine.
+pub struct WhisperCppProvider {
+    base_url: String,
+    language: Option<String>,
+    client: ApiClient,
+}
+
+impl WhisperCppProvider {
+    /// Create a provider for a server at the default address (`http://127.0.0.1:8080`)
+    pub fn new() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            language: None,
+            client: ApiClient::with_timeout(Duration::from_secs(120)),
+        }
+    }
+
+    /// Set the server base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the spoken language; the server auto-detects it by default
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Set the request timeout (defaults to 2 minutes since local inference can be slow)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+}
+
+impl Default for WhisperCppProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for WhisperCppProvider {
+    async fn transcribe(&self, audio: &AudioInput) -> Result<String> {
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", audio.to_part()?)
+            .text("response_format", "json");
+        if let Some(language) = &self.language {
+            form = form.text("language", language.clone());
+        }
+
+        let url = format!("{}/inference", self.base_url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .multipart(form)
+            .timeout(self.client.timeout())
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_connect() {
+                    AgentError::LLMProvider(format!(
+                        "whisper.cpp connection error (is whisper-server running at {}?): {}",
+                        self.base_url, e
+                    ))
+                } else {
+                    request_error("whisper.cpp", e)
+                }
+            })?;
+
+        let response = check_status("whisper.cpp", response).await?;
+
+        let transcription: TranscriptionResponse = response.json().await.map_err(|e| {
+            AgentError::LLMProvider(format!("Failed to deserialize whisper.cpp response: {}", e))
+        })?;
+        Ok(transcription.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_transcribe() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/inference"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"text": " Local transcript.\n"})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let provider = WhisperCppProvider::new().with_base_url(mock_server.uri());
+        let message = provider
+            .transcribe_to_message(&AudioInput::new(vec![0; 16], "clip.wav"))
+            .await
+            .unwrap();
+        assert_eq!(message.content, "Local transcript.");
+    }
+
+    #[tokio::test]
+    async fn test_server_not_running() {
+        let provider = WhisperCppProvider::new().with_base_url("http://localhost:1");
+        let err = provider
+            .transcribe(&AudioInput::new(vec![0; 16], "clip.wav"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("whisper.cpp"));
+    }
+}
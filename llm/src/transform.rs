@@ -0,0 +1,382 @@
+//! Declarative pre-send message transformations.
+//!
+//! Every consumer that sends scraped pages, pasted logs or long source files
+//! to a model ends up writing the same hygiene code. A [`TransformPipeline`]
+//! applies a configured list of [`MessageTransform`]s to the messages of each
+//! request, and a [`TransformingProvider`] runs it in front of any provider,
+//! so the transforms can be chosen per configuration profile instead of in
+//! code.
+
+use agent_core::{Message, Result, Role, ToolDefinition, ToolUseResponse};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use config::MessageTransformConfig;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+use crate::{CompletionResponse, LLMProvider, StructuredOutput, TokenStream, ToolConfig};
+
+/// Timestamp format used when none is configured
+const DEFAULT_DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+
+static SCRIPT_OR_STYLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<script\b.*?</script\s*>|<style\b.*?</style\s*>|<!--.*?-->").unwrap()
+});
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"</?[a-zA-Z][^<>]*>").unwrap());
+
+/// A transformation applied to messages before they are sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageTransform {
+    /// Remove HTML tags, scripts, styles and comments, and decode common
+    /// entities
+    StripHtml,
+    /// Trim lines, collapse runs of spaces and blank lines; fenced code
+    /// blocks are left alone
+    NormalizeWhitespace,
+    /// Keep the first `max_lines` lines of each fenced code block
+    TruncateCodeBlocks {
+        /// Lines of each code block to keep
+        max_lines: usize,
+    },
+    /// Add the current date and time to the system prompt
+    InjectDateTime {
+        /// `strftime` format of the timestamp, in UTC
+        format: String,
+    },
+}
+
+impl MessageTransform {
+    /// Build a transform from its configuration
+    pub fn from_config(config: &MessageTransformConfig) -> Self {
+        match config {
+            MessageTransformConfig::StripHtml => Self::StripHtml,
+            MessageTransformConfig::NormalizeWhitespace => Self::NormalizeWhitespace,
+            MessageTransformConfig::TruncateCodeBlocks { max_lines } => Self::TruncateCodeBlocks {
+                max_lines: *max_lines,
+            },
+            MessageTransformConfig::InjectDateTime { format } => Self::InjectDateTime {
+                format: format
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_DATE_TIME_FORMAT.to_string()),
+            },
+        }
+    }
+
+    fn apply(&self, messages: &mut Vec<Message>, now: DateTime<Utc>) {
+        match self {
+            Self::StripHtml => map_content(messages, strip_html),
+            Self::NormalizeWhitespace => map_content(messages, normalize_whitespace),
+            Self::TruncateCodeBlocks { max_lines } => {
+                map_content(messages, |text| truncate_code_blocks(text, *max_lines))
+            }
+            Self::InjectDateTime { format } => {
+                let note = format!("Current date and time: {}", now.format(format));
+                match messages.first_mut() {
+                    Some(first) if first.role == Role::System => {
+                        first.content = format!("{}\n\n{}", first.content, note);
+                    }
+                    _ => messages.insert(0, Message::system(note)),
+                }
+            }
+        }
+    }
+}
+
+/// An ordered list of message transforms
+///
+/// # Example
+///
+/// ```
+/// use llm::{MessageTransform, TransformPipeline};
+/// use agent_core::Message;
+///
+/// let pipeline = TransformPipeline::new()
+///     .with(MessageTransform::StripHtml)
+///     .with(MessageTransform::NormalizeWhitespace);
+///
+/// let messages = pipeline.apply(&[Message::user("<p>Hello,   <b>world</b></p>")]);
+/// assert_eq!(messages[0].content, "Hello, world");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransformPipeline {
+    transforms: Vec<MessageTransform>,
+}
+
+impl TransformPipeline {
+    /// An empty pipeline, which leaves messages unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a pipeline from the configured transforms, keeping their order
+    pub fn from_config(config: &[MessageTransformConfig]) -> Self {
+        Self {
+            transforms: config.iter().map(MessageTransform::from_config).collect(),
+        }
+    }
+
+    /// Add a transform to the end of the pipeline
+    pub fn with(mut self, transform: MessageTransform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// The transforms, in the order they are applied
+    pub fn transforms(&self) -> &[MessageTransform] {
+        &self.transforms
+    }
+
+    /// Check if the pipeline has no transforms
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Apply every transform to a copy of `messages`
+    pub fn apply(&self, messages: &[Message]) -> Vec<Message> {
+        self.apply_at(messages, Utc::now())
+    }
+
+    /// Apply every transform, using `now` as the current time
+    pub fn apply_at(&self, messages: &[Message], now: DateTime<Utc>) -> Vec<Message> {
+        let mut messages = messages.to_vec();
+        for transform in &self.transforms {
+            transform.apply(&mut messages, now);
+        }
+        messages
+    }
+}
+
+fn map_content(messages: &mut [Message], transform: impl Fn(&str) -> String) {
+    for message in messages {
+        message.content = transform(&message.content);
+    }
+}
+
+fn strip_html(text: &str) -> String {
+    let text = SCRIPT_OR_STYLE.replace_all(text, "");
+    let text = HTML_TAG.replace_all(&text, "");
+    // &amp; last, so "&amp;lt;" becomes "&lt;" rather than "<"
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        if is_fence(line) {
+            in_code = !in_code;
+            lines.push(line.trim().to_string());
+        } else if in_code {
+            lines.push(line.to_string());
+        } else {
+            let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            let previous_blank = lines.last().is_some_and(|last| last.is_empty());
+            if !collapsed.is_empty() || !previous_blank {
+                lines.push(collapsed);
+            }
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn truncate_code_blocks(text: &str, max_lines: usize) -> String {
+    let mut output: Vec<String> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+    for line in text.lines() {
+        match code.as_mut() {
+            None => {
+                output.push(line.to_string());
+                if is_fence(line) {
+                    code = Some(Vec::new());
+                }
+            }
+            Some(block) if is_fence(line) => {
+                let omitted = block.len().saturating_sub(max_lines);
+                output.extend(block.iter().take(max_lines).map(|line| line.to_string()));
+                if omitted > 0 {
+                    output.push(format!("... ({} more lines)", omitted));
+                }
+                output.push(line.to_string());
+                code = None;
+            }
+            Some(block) => block.push(line),
+        }
+    }
+    // An unclosed block is kept as it is
+    if let Some(block) = code {
+        output.extend(block.iter().map(|line| line.to_string()));
+    }
+    output.join("\n")
+}
+
+/// Provider wrapper that runs a [`TransformPipeline`] on every request
+pub struct TransformingProvider<P> {
+    inner: P,
+    pipeline: TransformPipeline,
+}
+
+impl<P: LLMProvider> TransformingProvider<P> {
+    /// Wrap a provider
+    pub fn new(inner: P, pipeline: TransformPipeline) -> Self {
+        Self { inner, pipeline }
+    }
+
+    /// The wrapped provider
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The transforms applied to each request
+    pub fn pipeline(&self) -> &TransformPipeline {
+        &self.pipeline
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for TransformingProvider<P> {
+    async fn send_message(&self, messages: &[Message]) -> Result<String> {
+        self.inner
+            .send_message(&self.pipeline.apply(messages))
+            .await
+    }
+
+    async fn send_completion(&self, messages: &[Message]) -> Result<CompletionResponse> {
+        self.inner
+            .send_completion(&self.pipeline.apply(messages))
+            .await
+    }
+
+    async fn send_structured(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<Value> {
+        self.inner
+            .send_structured(&self.pipeline.apply(messages), output)
+            .await
+    }
+
+    async fn send_structured_completion(
+        &self,
+        messages: &[Message],
+        output: &StructuredOutput,
+    ) -> Result<(Value, CompletionResponse)> {
+        self.inner
+            .send_structured_completion(&self.pipeline.apply(messages), output)
+            .await
+    }
+
+    async fn send_message_with_tools(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        config: &ToolConfig,
+    ) -> Result<ToolUseResponse> {
+        self.inner
+            .send_message_with_tools(&self.pipeline.apply(messages), tools, config)
+            .await
+    }
+
+    async fn stream_message(&self, messages: &[Message]) -> Result<TokenStream> {
+        self.inner
+            .stream_message(&self.pipeline.apply(messages))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_strip_html() {
+        let html = "<html><head><style>p { color: red; }</style></head><body>\
+                    <!-- nav --><p>Fish &amp; chips &lt;3</p><script>track()</script></body></html>";
+        assert_eq!(strip_html(html), "Fish & chips <3");
+        assert_eq!(strip_html("if a < b && b > c"), "if a < b && b > c");
+    }
+
+    #[test]
+    fn test_normalize_whitespace_keeps_code() {
+        let text = "  Hello   there  \n\n\n\nSee:\n```python\ndef f():\n    return  1\n```\n\n";
+        assert_eq!(
+            normalize_whitespace(text),
+            "Hello there\n\nSee:\n```python\ndef f():\n    return  1\n```"
+        );
+    }
+
+    #[test]
+    fn test_truncate_code_blocks() {
+        let text = "Log:\n```\n1\n2\n3\n4\n```\nShort:\n```\na\n```";
+        assert_eq!(
+            truncate_code_blocks(text, 2),
+            "Log:\n```\n1\n2\n... (2 more lines)\n```\nShort:\n```\na\n```"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_from_config() {
+        let pipeline = TransformPipeline::from_config(&[
+            MessageTransformConfig::StripHtml,
+            MessageTransformConfig::InjectDateTime { format: None },
+        ]);
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap();
+
+        let messages = pipeline.apply_at(&[Message::user("<b>Hi</b>")], now);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(
+            messages[0].content,
+            "Current date and time: 2024-03-01 09:30 UTC"
+        );
+        assert_eq!(messages[1].content, "Hi");
+
+        let messages = pipeline.apply_at(&[Message::system("Be brief."), Message::user("Hi")], now);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].content,
+            "Be brief.\n\nCurrent date and time: 2024-03-01 09:30 UTC"
+        );
+    }
+
+    /// Records the messages it receives
+    #[derive(Default)]
+    struct Recording {
+        received: Mutex<Vec<Message>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Recording {
+        async fn send_message(&self, messages: &[Message]) -> Result<String> {
+            *self.received.lock().unwrap() = messages.to_vec();
+            Ok("ok".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_transforms_requests() {
+        let pipeline = TransformPipeline::new().with(MessageTransform::NormalizeWhitespace);
+        let provider = TransformingProvider::new(Recording::default(), pipeline);
+
+        provider
+            .send_message(&[Message::user("a   b\n\n\nc")])
+            .await
+            .unwrap();
+        assert_eq!(
+            provider.inner().received.lock().unwrap()[0].content,
+            "a b\n\nc"
+        );
+    }
+}
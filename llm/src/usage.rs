@@ -0,0 +1,563 @@
+//! Billed usage from the Anthropic Admin API.
+//!
+//! Costs shown to users are estimated from token counts and list prices,
+//! which drift from the invoice with caching discounts, batch pricing and
+//! price changes. [`AnthropicUsageClient`] reads the organisation's usage
+//! and cost reports so the estimates can be reconciled with what was
+//! actually billed (see [`BilledUsage::reconcile`]).
+//!
+//! The Admin API requires an admin key (`sk-ant-admin...`), not a regular
+//! API key.
+
+use agent_core::{AgentError, CostSummary, Result};
+use chrono::{DateTime, Utc};
+use communication::{ApiClient, check_status, request_error};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Default Anthropic API base URL
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+
+/// Service name used in error messages
+const SERVICE: &str = "Anthropic Admin API";
+
+/// Width of the time buckets in a report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BucketWidth {
+    /// One-minute buckets (usage reports only)
+    Minute,
+    /// One-hour buckets (usage reports only)
+    Hour,
+    /// One-day buckets
+    #[default]
+    Day,
+}
+
+impl BucketWidth {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Minute => "1m",
+            Self::Hour => "1h",
+            Self::Day => "1d",
+        }
+    }
+}
+
+/// Time range of a usage or cost report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageQuery {
+    /// Start of the range, inclusive
+    pub starting_at: DateTime<Utc>,
+    /// End of the range, exclusive; defaults to now
+    pub ending_at: Option<DateTime<Utc>>,
+    /// Bucket width for usage reports; cost reports are always daily
+    pub bucket_width: BucketWidth,
+}
+
+impl UsageQuery {
+    /// Report from `starting_at` until now in daily buckets
+    pub fn since(starting_at: DateTime<Utc>) -> Self {
+        Self {
+            starting_at,
+            ending_at: None,
+            bucket_width: BucketWidth::Day,
+        }
+    }
+
+    /// Set the end of the range
+    pub fn until(mut self, ending_at: DateTime<Utc>) -> Self {
+        self.ending_at = Some(ending_at);
+        self
+    }
+
+    /// Set the bucket width for usage reports
+    pub fn with_bucket_width(mut self, bucket_width: BucketWidth) -> Self {
+        self.bucket_width = bucket_width;
+        self
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("starting_at", self.starting_at.to_rfc3339())];
+        if let Some(ending_at) = self.ending_at {
+            params.push(("ending_at", ending_at.to_rfc3339()));
+        }
+        params
+    }
+}
+
+/// Tokens used by one model in one time bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelUsage {
+    /// Start of the bucket
+    pub starting_at: DateTime<Utc>,
+    /// End of the bucket
+    pub ending_at: DateTime<Utc>,
+    /// Model name, if reported
+    pub model: Option<String>,
+    /// Input tokens billed at the regular or cache-write rate
+    pub input_tokens: u64,
+    /// Input tokens read from the prompt cache
+    pub cache_read_input_tokens: u64,
+    /// Output tokens
+    pub output_tokens: u64,
+}
+
+/// One billed amount in one daily bucket
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostLine {
+    /// Start of the bucket
+    pub starting_at: DateTime<Utc>,
+    /// End of the bucket
+    pub ending_at: DateTime<Utc>,
+    /// Line item description, e.g. "Claude Sonnet 4 Usage - Input Tokens"
+    pub description: Option<String>,
+    /// Model name, if the cost is model-specific
+    pub model: Option<String>,
+    /// Amount in US dollars
+    pub amount_usd: f64,
+}
+
+/// Usage and costs billed over a time range
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BilledUsage {
+    /// Token usage per bucket and model
+    pub usage: Vec<ModelUsage>,
+    /// Billed amounts per bucket and line item
+    pub costs: Vec<CostLine>,
+}
+
+impl BilledUsage {
+    /// Billed input tokens, including cache reads
+    pub fn input_tokens(&self) -> u64 {
+        self.usage
+            .iter()
+            .map(|u| u.input_tokens + u.cache_read_input_tokens)
+            .sum()
+    }
+
+    /// Billed output tokens
+    pub fn output_tokens(&self) -> u64 {
+        self.usage.iter().map(|u| u.output_tokens).sum()
+    }
+
+    /// Total billed amount in US dollars
+    pub fn total_cost_usd(&self) -> f64 {
+        self.costs.iter().map(|c| c.amount_usd).sum()
+    }
+
+    /// Compare an estimate covering the same time range with the bill
+    pub fn reconcile(&self, estimated: &CostSummary) -> CostReconciliation {
+        CostReconciliation {
+            estimated_input_tokens: estimated.input_tokens as u64,
+            estimated_output_tokens: estimated.output_tokens as u64,
+            estimated_cost_usd: estimated.total_cost_usd,
+            billed_input_tokens: self.input_tokens(),
+            billed_output_tokens: self.output_tokens(),
+            billed_cost_usd: self.total_cost_usd(),
+        }
+    }
+}
+
+/// Estimated versus billed usage
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostReconciliation {
+    /// Input tokens counted locally
+    pub estimated_input_tokens: u64,
+    /// Output tokens counted locally
+    pub estimated_output_tokens: u64,
+    /// Locally estimated cost, if prices were known
+    pub estimated_cost_usd: Option<f64>,
+    /// Input tokens on the bill
+    pub billed_input_tokens: u64,
+    /// Output tokens on the bill
+    pub billed_output_tokens: u64,
+    /// Billed cost
+    pub billed_cost_usd: f64,
+}
+
+impl CostReconciliation {
+    /// Billed minus estimated cost; positive when the estimate was too low
+    pub fn cost_difference_usd(&self) -> Option<f64> {
+        self.estimated_cost_usd
+            .map(|estimated| self.billed_cost_usd - estimated)
+    }
+
+    /// Billed cost divided by the estimate, e.g. 1.1 for a 10% underestimate
+    pub fn cost_ratio(&self) -> Option<f64> {
+        self.estimated_cost_usd
+            .filter(|estimated| *estimated > 0.0)
+            .map(|estimated| self.billed_cost_usd / estimated)
+    }
+
+    /// Whether the billed cost is within `tolerance` (e.g. 0.05 for 5%) of
+    /// the estimate
+    pub fn within(&self, tolerance: f64) -> bool {
+        self.cost_ratio()
+            .is_some_and(|ratio| (ratio - 1.0).abs() <= tolerance)
+    }
+}
+
+/// One page of a report
+#[derive(Debug, Deserialize)]
+struct ReportPage<T> {
+    data: Vec<ReportBucket<T>>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    next_page: Option<String>,
+}
+
+/// One time bucket of a report
+#[derive(Debug, Deserialize)]
+struct ReportBucket<T> {
+    starting_at: DateTime<Utc>,
+    ending_at: DateTime<Utc>,
+    results: Vec<T>,
+}
+
+/// A result row of the messages usage report
+#[derive(Debug, Deserialize)]
+struct UsageResult {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    uncached_input_tokens: u64,
+    #[serde(default)]
+    cache_creation: CacheCreation,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+/// Cache-write tokens by cache lifetime
+#[derive(Debug, Default, Deserialize)]
+struct CacheCreation {
+    #[serde(default)]
+    ephemeral_1h_input_tokens: u64,
+    #[serde(default)]
+    ephemeral_5m_input_tokens: u64,
+}
+
+/// A result row of the cost report
+#[derive(Debug, Deserialize)]
+struct CostResult {
+    /// Amount in cents as a decimal string
+    amount: String,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Client for the Anthropic Admin API usage and cost reports
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_core::CostSummary;
+/// use chrono::{Duration, Utc};
+/// use llm::{AnthropicUsageClient, UsageQuery};
+///
+/// # async fn example(estimated: CostSummary) -> agent_core::Result<()> {
+/// let client = AnthropicUsageClient::new("sk-ant-admin...");
+/// let billed = client
+///     .billed_usage(&UsageQuery::since(Utc::now() - Duration::days(7)))
+///     .await?;
+///
+/// let reconciliation = billed.reconcile(&estimated);
+/// if !reconciliation.within(0.05) {
+///     println!("Estimate off by ${:.2}", reconciliation.cost_difference_usd().unwrap_or(0.0));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AnthropicUsageClient {
+    admin_key: String,
+    base_url: String,
+    client: ApiClient,
+}
+
+impl AnthropicUsageClient {
+    /// Create a client for the given admin key
+    pub fn new(admin_key: impl Into<String>) -> Self {
+        Self {
+            admin_key: admin_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: ApiClient::new(),
+        }
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = ApiClient::with_timeout(timeout);
+        self
+    }
+
+    /// Token usage per time bucket and model
+    pub async fn messages_usage(&self, query: &UsageQuery) -> Result<Vec<ModelUsage>> {
+        let mut params = query.params();
+        params.push(("bucket_width", query.bucket_width.as_str().to_string()));
+        params.push(("group_by[]", "model".to_string()));
+
+        let buckets: Vec<ReportBucket<UsageResult>> = self
+            .fetch_all("/organizations/usage_report/messages", &params)
+            .await?;
+        Ok(buckets
+            .into_iter()
+            .flat_map(|bucket| {
+                let (starting_at, ending_at) = (bucket.starting_at, bucket.ending_at);
+                bucket.results.into_iter().map(move |result| ModelUsage {
+                    starting_at,
+                    ending_at,
+                    model: result.model,
+                    input_tokens: result.uncached_input_tokens
+                        + result.cache_creation.ephemeral_1h_input_tokens
+                        + result.cache_creation.ephemeral_5m_input_tokens,
+                    cache_read_input_tokens: result.cache_read_input_tokens,
+                    output_tokens: result.output_tokens,
+                })
+            })
+            .collect())
+    }
+
+    /// Billed amounts per day and line item
+    pub async fn cost_report(&self, query: &UsageQuery) -> Result<Vec<CostLine>> {
+        let mut params = query.params();
+        params.push(("group_by[]", "description".to_string()));
+
+        let buckets: Vec<ReportBucket<CostResult>> = self
+            .fetch_all("/organizations/cost_report", &params)
+            .await?;
+        let mut lines = Vec::new();
+        for bucket in buckets {
+            for result in bucket.results {
+                if let Some(currency) = &result.currency
+                    && currency != "USD"
+                {
+                    return Err(AgentError::LLMProvider(format!(
+                        "{} returned unsupported currency {}",
+                        SERVICE, currency
+                    )));
+                }
+                let cents: f64 = result.amount.parse().map_err(|_| {
+                    AgentError::LLMProvider(format!(
+                        "{} returned invalid amount '{}'",
+                        SERVICE, result.amount
+                    ))
+                })?;
+                lines.push(CostLine {
+                    starting_at: bucket.starting_at,
+                    ending_at: bucket.ending_at,
+                    description: result.description,
+                    model: result.model,
+                    amount_usd: cents / 100.0,
+                });
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Usage and costs over the query range
+    pub async fn billed_usage(&self, query: &UsageQuery) -> Result<BilledUsage> {
+        Ok(BilledUsage {
+            usage: self.messages_usage(query).await?,
+            costs: self.cost_report(query).await?,
+        })
+    }
+
+    /// Fetch every page of a report
+    async fn fetch_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&'static str, String)],
+    ) -> Result<Vec<ReportBucket<T>>> {
+        let mut buckets = Vec::new();
+        let mut page: Option<String> = None;
+        loop {
+            let mut request = reqwest::Client::new()
+                .get(format!("{}{}", self.base_url, path))
+                .header("x-api-key", &self.admin_key)
+                .header("anthropic-version", "2023-06-01")
+                .timeout(self.client.timeout())
+                .query(params);
+            if let Some(page) = &page {
+                request = request.query(&[("page", page)]);
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| request_error(SERVICE, e))?;
+            let report: ReportPage<T> = check_status(SERVICE, response)
+                .await?
+                .json()
+                .await
+                .map_err(|e| {
+                    AgentError::LLMProvider(format!("Failed to deserialize usage report: {}", e))
+                })?;
+            buckets.extend(report.data);
+
+            match report.next_page {
+                Some(next) if report.has_more => page = Some(next),
+                _ => return Ok(buckets),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn query() -> UsageQuery {
+        UsageQuery::since(Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap())
+            .until(Utc.with_ymd_and_hms(2025, 6, 3, 0, 0, 0).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_messages_usage_follows_pages() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/organizations/usage_report/messages"))
+            .and(header("x-api-key", "admin-key"))
+            .and(query_param("page", "page_2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "starting_at": "2025-06-02T00:00:00Z",
+                    "ending_at": "2025-06-03T00:00:00Z",
+                    "results": [{
+                        "model": "claude-sonnet-4-5",
+                        "uncached_input_tokens": 50,
+                        "output_tokens": 5
+                    }]
+                }],
+                "has_more": false,
+                "next_page": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/organizations/usage_report/messages"))
+            .and(query_param("bucket_width", "1d"))
+            .and(query_param("group_by[]", "model"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "starting_at": "2025-06-01T00:00:00Z",
+                    "ending_at": "2025-06-02T00:00:00Z",
+                    "results": [{
+                        "model": "claude-sonnet-4-5",
+                        "uncached_input_tokens": 100,
+                        "cache_creation": {
+                            "ephemeral_5m_input_tokens": 20,
+                            "ephemeral_1h_input_tokens": 0
+                        },
+                        "cache_read_input_tokens": 300,
+                        "output_tokens": 40
+                    }]
+                }],
+                "has_more": true,
+                "next_page": "page_2"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = AnthropicUsageClient::new("admin-key").with_base_url(server.uri());
+        let usage = client.messages_usage(&query()).await.unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].input_tokens, 120);
+        assert_eq!(usage[0].cache_read_input_tokens, 300);
+        assert_eq!(usage[1].output_tokens, 5);
+    }
+
+    #[tokio::test]
+    async fn test_cost_report_converts_cents() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/organizations/cost_report"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "starting_at": "2025-06-01T00:00:00Z",
+                    "ending_at": "2025-06-02T00:00:00Z",
+                    "results": [
+                        {"currency": "USD", "amount": "123.5", "description": "Input Tokens"},
+                        {"currency": "USD", "amount": "26.5", "description": "Output Tokens"}
+                    ]
+                }],
+                "has_more": false
+            })))
+            .mount(&server)
+            .await;
+
+        let client = AnthropicUsageClient::new("admin-key").with_base_url(server.uri());
+        let costs = client.cost_report(&query()).await.unwrap();
+        assert_eq!(costs.len(), 2);
+        assert!((costs[0].amount_usd - 1.235).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_http_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid admin key"))
+            .mount(&server)
+            .await;
+
+        let client = AnthropicUsageClient::new("bad").with_base_url(server.uri());
+        let result = client.billed_usage(&query()).await;
+        let Err(AgentError::AuthFailed(msg)) = result else {
+            panic!("expected an authentication error");
+        };
+        assert!(msg.contains("HTTP 401") && msg.contains("invalid admin key"));
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let bucket = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let billed = BilledUsage {
+            usage: vec![ModelUsage {
+                starting_at: bucket,
+                ending_at: bucket,
+                model: None,
+                input_tokens: 900,
+                cache_read_input_tokens: 100,
+                output_tokens: 200,
+            }],
+            costs: vec![CostLine {
+                starting_at: bucket,
+                ending_at: bucket,
+                description: None,
+                model: None,
+                amount_usd: 1.1,
+            }],
+        };
+        let estimated = CostSummary {
+            input_tokens: 1000,
+            output_tokens: 200,
+            total_cost_usd: Some(1.0),
+        };
+
+        let reconciliation = billed.reconcile(&estimated);
+        assert_eq!(reconciliation.billed_input_tokens, 1000);
+        assert!((reconciliation.cost_difference_usd().unwrap() - 0.1).abs() < 1e-9);
+        assert!(reconciliation.within(0.15));
+        assert!(!reconciliation.within(0.05));
+
+        let unpriced = billed.reconcile(&CostSummary::default());
+        assert_eq!(unpriced.cost_ratio(), None);
+        assert!(!unpriced.within(1.0));
+    }
+}
@@ -0,0 +1,572 @@
+//! Sharing the context window between the parts of a prompt.
+//!
+//! Conversation history, retrieved document chunks and tool outputs all
+//! compete for the same context window. Instead of each of them filling the
+//! prompt until it overflows, a [`ContextAssembler`] builds the messages for
+//! a turn: the system prompt and the user's query always go in, and the
+//! rest of the window is allocated across the other sources by their
+//! configured [`SourceBudget`]s. Tokens are estimated with the
+//! [`TokenEstimator`] of the model.
+
+use agent_core::{AgentError, Message, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{TextChunker, TokenEstimator};
+
+/// Heading of the system message holding retrieved chunks
+pub const RETRIEVED_PREFIX: &str = "Relevant information:";
+
+/// Heading of the system message holding tool outputs
+pub const TOOL_OUTPUTS_PREFIX: &str = "Tool results:";
+
+/// Appended to a tool output that was cut short
+const TRUNCATION_MARKER: &str = " [...]";
+
+/// A part of the prompt that competes for the context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextSource {
+    /// Earlier messages of the conversation; the most recent are kept
+    History,
+    /// Document chunks retrieved for the query; the best ranked are kept
+    Retrieved,
+    /// Outputs of tools called this turn; the most recent are kept, and
+    /// the last one that fits is cut short rather than dropped
+    ToolOutputs,
+}
+
+impl ContextSource {
+    /// Every source, in the order ties in priority are broken
+    pub const ALL: [ContextSource; 3] = [
+        ContextSource::ToolOutputs,
+        ContextSource::Retrieved,
+        ContextSource::History,
+    ];
+}
+
+/// How much of the context window a source may claim
+///
+/// Sources with a higher `priority` are served first. Every source is first
+/// granted up to its `min_tokens`, in priority order, so a low priority
+/// source can still be guaranteed some room; then the rest of the window
+/// goes to sources in priority order, each up to its `max_tokens`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceBudget {
+    /// Order in which sources are served, highest first
+    pub priority: u32,
+    /// Tokens reserved for the source before others are served in full
+    #[serde(default)]
+    pub min_tokens: usize,
+    /// Most tokens the source may use, if limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+}
+
+impl SourceBudget {
+    /// Create a budget with the given priority and no reservation or limit
+    pub fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            min_tokens: 0,
+            max_tokens: None,
+        }
+    }
+
+    /// Reserve `tokens` for the source
+    pub fn with_min_tokens(mut self, tokens: usize) -> Self {
+        self.min_tokens = tokens;
+        self
+    }
+
+    /// Limit the source to `tokens`
+    pub fn with_max_tokens(mut self, tokens: usize) -> Self {
+        self.max_tokens = Some(tokens);
+        self
+    }
+
+    /// The reservation, capped by the limit
+    fn reserved(&self) -> usize {
+        self.min_tokens.min(self.limit())
+    }
+
+    fn limit(&self) -> usize {
+        self.max_tokens.unwrap_or(usize::MAX)
+    }
+}
+
+/// Everything that could go into the prompt of one turn
+#[derive(Debug, Clone)]
+pub struct TurnContext {
+    /// Instructions sent first, if any
+    pub system_prompt: Option<String>,
+    /// Earlier messages of the conversation, oldest first
+    pub history: Vec<Message>,
+    /// Retrieved chunks, best ranked first
+    pub retrieved: Vec<String>,
+    /// Outputs of tools called this turn, oldest first
+    pub tool_outputs: Vec<String>,
+    /// The user's message for this turn
+    pub query: Message,
+}
+
+impl TurnContext {
+    /// Create a turn for the user's `query`, with nothing else to send
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            system_prompt: None,
+            history: Vec::new(),
+            retrieved: Vec::new(),
+            tool_outputs: Vec::new(),
+            query: Message::user(query),
+        }
+    }
+
+    /// Set the system prompt
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    /// Set the earlier messages of the conversation, oldest first
+    pub fn with_history(mut self, history: Vec<Message>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Set the retrieved chunks, best ranked first
+    pub fn with_retrieved(mut self, retrieved: Vec<String>) -> Self {
+        self.retrieved = retrieved;
+        self
+    }
+
+    /// Set the outputs of tools called this turn, oldest first
+    pub fn with_tool_outputs(mut self, tool_outputs: Vec<String>) -> Self {
+        self.tool_outputs = tool_outputs;
+        self
+    }
+}
+
+/// How much of the context window a source asked for and received
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextAllocation {
+    /// The source
+    pub source: ContextSource,
+    /// Tokens needed to send all of the source's content
+    pub requested: usize,
+    /// Tokens the source's content uses in the assembled prompt
+    pub used: usize,
+    /// Messages, chunks or outputs left out
+    pub dropped: usize,
+    /// Whether a tool output was cut short
+    pub truncated: bool,
+}
+
+/// The messages of a turn, with how the context window was shared
+#[derive(Debug, Clone)]
+pub struct AssembledContext {
+    /// Messages to send: the system prompt, retrieved chunks, tool outputs,
+    /// the kept history and the query
+    pub messages: Vec<Message>,
+    /// Tokens used by the system prompt and the query
+    pub fixed_tokens: usize,
+    /// What each source asked for and received, in the order served
+    pub allocations: Vec<ContextAllocation>,
+}
+
+impl AssembledContext {
+    /// The allocation of `source`
+    pub fn allocation(&self, source: ContextSource) -> Option<&ContextAllocation> {
+        self.allocations
+            .iter()
+            .find(|allocation| allocation.source == source)
+    }
+
+    /// Estimated tokens of all messages
+    pub fn total_tokens(&self) -> usize {
+        self.fixed_tokens
+            + self
+                .allocations
+                .iter()
+                .map(|allocation| allocation.used)
+                .sum::<usize>()
+    }
+}
+
+/// Builds the messages of each turn within the model's context window
+///
+/// By default tool outputs are served first, then retrieved chunks, then
+/// history, with no reservations or limits; set a [`SourceBudget`] per
+/// source to change that. Room a source is granted but cannot use, because
+/// its next message or chunk does not fit, is offered to the sources that
+/// still had content left out, in priority order.
+///
+/// # Examples
+///
+/// ```
+/// use memory::{ContextAssembler, ContextSource, SourceBudget, TokenEstimator, TurnContext};
+/// use agent_core::Message;
+///
+/// # fn example() -> agent_core::Result<()> {
+/// let assembler = ContextAssembler::new(8_000)
+///     .with_estimator(TokenEstimator::for_model("openai", "gpt-4"))
+///     .with_reserved_output_tokens(1_000)
+///     // Always keep some history, but never let it crowd out the rest
+///     .with_budget(
+///         ContextSource::History,
+///         SourceBudget::new(1).with_min_tokens(500).with_max_tokens(3_000),
+///     );
+///
+/// let turn = TurnContext::new("What did the report say about Q3?")
+///     .with_system_prompt("You are a financial analyst.")
+///     .with_history(vec![Message::user("Hi"), Message::assistant("Hello!")])
+///     .with_retrieved(vec!["Q3 revenue grew 12%.".to_string()]);
+/// let context = assembler.assemble(&turn)?;
+/// assert!(context.total_tokens() <= 7_000);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextAssembler {
+    context_window: usize,
+    reserved_output_tokens: usize,
+    estimator: TokenEstimator,
+    budgets: HashMap<ContextSource, SourceBudget>,
+}
+
+impl ContextAssembler {
+    /// Create an assembler for a model with `context_window` tokens
+    pub fn new(context_window: usize) -> Self {
+        Self {
+            context_window,
+            reserved_output_tokens: 0,
+            estimator: TokenEstimator::default(),
+            budgets: HashMap::from([
+                (ContextSource::ToolOutputs, SourceBudget::new(3)),
+                (ContextSource::Retrieved, SourceBudget::new(2)),
+                (ContextSource::History, SourceBudget::new(1)),
+            ]),
+        }
+    }
+
+    /// Set how tokens are estimated
+    pub fn with_estimator(mut self, estimator: TokenEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Keep `tokens` of the context window free for the model's reply
+    pub fn with_reserved_output_tokens(mut self, tokens: usize) -> Self {
+        self.reserved_output_tokens = tokens;
+        self
+    }
+
+    /// Set how much of the context window `source` may claim
+    pub fn with_budget(mut self, source: ContextSource, budget: SourceBudget) -> Self {
+        self.budgets.insert(source, budget);
+        self
+    }
+
+    /// The budget of `source`
+    pub fn budget(&self, source: ContextSource) -> SourceBudget {
+        self.budgets[&source]
+    }
+
+    /// Tokens available for the request
+    pub fn input_budget(&self) -> usize {
+        self.context_window
+            .saturating_sub(self.reserved_output_tokens)
+    }
+
+    /// Build the messages of a turn
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::ContextLengthExceeded` if the system prompt and
+    /// query alone do not fit the input budget.
+    pub fn assemble(&self, turn: &TurnContext) -> Result<AssembledContext> {
+        let system = turn.system_prompt.as_deref().map(Message::system);
+        let fixed_tokens = self
+            .estimator
+            .count_all(system.iter().chain([&turn.query]));
+        let Some(mut free) = self.input_budget().checked_sub(fixed_tokens) else {
+            return Err(AgentError::ContextLengthExceeded(format!(
+                "the system prompt and query use {} tokens, more than the {} available",
+                fixed_tokens,
+                self.input_budget()
+            )));
+        };
+
+        let mut order = ContextSource::ALL;
+        order.sort_by_key(|source| std::cmp::Reverse(self.budget(*source).priority));
+        let requested: Vec<usize> = order
+            .iter()
+            .map(|source| self.fill(*source, turn, usize::MAX).used)
+            .collect();
+
+        // Reservations first, then the rest of the window in priority order
+        let mut granted = vec![0; order.len()];
+        for (index, source) in order.iter().enumerate() {
+            let grant = requested[index].min(self.budget(*source).reserved()).min(free);
+            granted[index] = grant;
+            free -= grant;
+        }
+        for (index, source) in order.iter().enumerate() {
+            let limit = requested[index].min(self.budget(*source).limit());
+            let grant = limit.saturating_sub(granted[index]).min(free);
+            granted[index] += grant;
+            free -= grant;
+        }
+
+        let mut filled: Vec<Filled> = order
+            .iter()
+            .zip(&granted)
+            .map(|(source, granted)| self.fill(*source, turn, *granted))
+            .collect();
+
+        // Offer room granted but left unused to sources still short of room
+        free += granted
+            .iter()
+            .zip(&filled)
+            .map(|(granted, filled)| granted - filled.used)
+            .sum::<usize>();
+        for (index, source) in order.iter().enumerate() {
+            if filled[index].dropped == 0 && !filled[index].truncated {
+                continue;
+            }
+            let limit = self.budget(*source).limit();
+            let available = filled[index].used + free;
+            let refilled = self.fill(*source, turn, available.min(limit));
+            free = available - refilled.used;
+            filled[index] = refilled;
+        }
+
+        let mut assembled = AssembledContext {
+            messages: system.into_iter().collect(),
+            fixed_tokens,
+            allocations: Vec::new(),
+        };
+        let mut history = Vec::new();
+        for ((source, requested), filled) in order.into_iter().zip(requested).zip(filled) {
+            assembled.allocations.push(ContextAllocation {
+                source,
+                requested,
+                used: filled.used,
+                dropped: filled.dropped,
+                truncated: filled.truncated,
+            });
+            match source {
+                ContextSource::History => history = filled.messages,
+                _ => assembled.messages.extend(filled.messages),
+            }
+        }
+        assembled.messages.extend(history);
+        assembled.messages.push(turn.query.clone());
+        Ok(assembled)
+    }
+
+    /// The content of `source` that fits in `budget` tokens
+    fn fill(&self, source: ContextSource, turn: &TurnContext, budget: usize) -> Filled {
+        match source {
+            ContextSource::History => self.fill_history(&turn.history, budget),
+            ContextSource::Retrieved => self.fill_retrieved(&turn.retrieved, budget),
+            ContextSource::ToolOutputs => self.fill_tool_outputs(&turn.tool_outputs, budget),
+        }
+    }
+
+    /// The most recent messages that fit, stopping at the first that does not
+    fn fill_history(&self, history: &[Message], budget: usize) -> Filled {
+        let mut filled = Filled::default();
+        for (index, message) in history.iter().enumerate().rev() {
+            let tokens = self.estimator.count(message);
+            if filled.used + tokens > budget {
+                filled.dropped = index + 1;
+                break;
+            }
+            filled.used += tokens;
+            filled.messages.insert(0, message.clone());
+        }
+        filled
+    }
+
+    /// The best ranked chunks that fit, skipping any that do not
+    fn fill_retrieved(&self, chunks: &[String], budget: usize) -> Filled {
+        let mut kept: Vec<&str> = Vec::new();
+        let mut dropped = 0;
+        for chunk in chunks {
+            kept.push(chunk);
+            if self.section_tokens(RETRIEVED_PREFIX, &kept) > budget {
+                kept.pop();
+                dropped += 1;
+            }
+        }
+        self.section(RETRIEVED_PREFIX, kept, dropped, false)
+    }
+
+    /// The most recent outputs that fit, cutting short the first that does
+    /// not if any room is left
+    fn fill_tool_outputs(&self, outputs: &[String], budget: usize) -> Filled {
+        let mut kept: Vec<String> = Vec::new();
+        for (index, output) in outputs.iter().enumerate().rev() {
+            kept.insert(0, output.clone());
+            if self.section_tokens(TOOL_OUTPUTS_PREFIX, &kept) <= budget {
+                continue;
+            }
+            kept[0] = String::new();
+            let room = budget.saturating_sub(
+                self.section_tokens(TOOL_OUTPUTS_PREFIX, &kept)
+                    + self.estimator.count_text(TRUNCATION_MARKER),
+            );
+            let start = match room {
+                0 => None,
+                room => TextChunker::new(room)
+                    .with_estimator(self.estimator)
+                    .split(output)
+                    .into_iter()
+                    .next(),
+            };
+            let truncated = match start {
+                Some(start) => {
+                    kept[0] = format!("{}{}", start, TRUNCATION_MARKER);
+                    true
+                }
+                None => {
+                    kept.remove(0);
+                    false
+                }
+            };
+            let dropped = if truncated { index } else { index + 1 };
+            let kept = kept.iter().map(String::as_str).collect();
+            return self.section(TOOL_OUTPUTS_PREFIX, kept, dropped, truncated);
+        }
+        let kept = kept.iter().map(String::as_str).collect();
+        self.section(TOOL_OUTPUTS_PREFIX, kept, 0, false)
+    }
+
+    /// A system message with `heading` followed by `items`, or nothing if
+    /// there are no items
+    fn section(&self, heading: &str, items: Vec<&str>, dropped: usize, truncated: bool) -> Filled {
+        let message = (!items.is_empty()).then(|| render_section(heading, &items));
+        Filled {
+            used: self.estimator.count_all(&message),
+            messages: message.into_iter().collect(),
+            dropped,
+            truncated,
+        }
+    }
+
+    fn section_tokens<S: AsRef<str>>(&self, heading: &str, items: &[S]) -> usize {
+        if items.is_empty() {
+            return 0;
+        }
+        let items: Vec<&str> = items.iter().map(AsRef::as_ref).collect();
+        self.estimator.count(&render_section(heading, &items))
+    }
+}
+
+/// The content of one source that fits its budget
+#[derive(Debug, Default)]
+struct Filled {
+    messages: Vec<Message>,
+    used: usize,
+    dropped: usize,
+    truncated: bool,
+}
+
+fn render_section(heading: &str, items: &[&str]) -> Message {
+    Message::system(format!("{}\n\n{}", heading, items.join("\n\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::Role;
+
+    /// One token per character, plus the per-message overhead
+    fn approximate(context_window: usize) -> ContextAssembler {
+        ContextAssembler::new(context_window).with_estimator(TokenEstimator::Approximate {
+            chars_per_token: 1.0,
+        })
+    }
+
+    fn turn() -> TurnContext {
+        TurnContext::new("query")
+            .with_system_prompt("system")
+            .with_history(vec![
+                Message::user("a".repeat(20)),
+                Message::assistant("b".repeat(20)),
+                Message::user("c".repeat(20)),
+            ])
+            .with_retrieved(vec!["x".repeat(30), "y".repeat(10)])
+            .with_tool_outputs(vec!["older output".to_string(), "z".repeat(40)])
+    }
+
+    #[test]
+    fn test_everything_fits() {
+        let context = approximate(1_000).assemble(&turn()).unwrap();
+        let roles: Vec<Role> = context.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(
+            roles,
+            [
+                Role::System,
+                Role::System,
+                Role::System,
+                Role::User,
+                Role::Assistant,
+                Role::User,
+                Role::User
+            ]
+        );
+        assert!(context.messages[1].content.starts_with(TOOL_OUTPUTS_PREFIX));
+        assert!(context.messages[2].content.starts_with(RETRIEVED_PREFIX));
+        assert_eq!(context.messages.last().unwrap().content, "query");
+        assert!(context.allocations.iter().all(|a| a.used == a.requested && a.dropped == 0));
+        assert_eq!(
+            context.total_tokens(),
+            TokenEstimator::Approximate { chars_per_token: 1.0 }.count_all(&context.messages)
+        );
+    }
+
+    #[test]
+    fn test_priorities_and_reservations_share_the_window() {
+        // System prompt and query use 19 tokens, leaving 111; tool outputs
+        // want 73, retrieved chunks 69 and history 72
+        let assembler = approximate(200).with_reserved_output_tokens(70);
+        let context = assembler.assemble(&turn()).unwrap();
+        let tools = context.allocation(ContextSource::ToolOutputs).unwrap();
+        assert_eq!((tools.used, tools.dropped), (tools.requested, 0));
+        // The leftover fits the short chunk but not the long one
+        let retrieved = context.allocation(ContextSource::Retrieved).unwrap();
+        assert_eq!(retrieved.dropped, 1);
+        assert!(context.messages[2].content.ends_with(&"y".repeat(10)));
+        assert_eq!(context.allocation(ContextSource::History).unwrap().used, 0);
+        assert!(context.total_tokens() <= assembler.input_budget());
+
+        // Reserving room for history takes it from higher priority sources,
+        // and the tool output that no longer fits is cut short
+        let assembler = assembler
+            .with_budget(ContextSource::History, SourceBudget::new(1).with_min_tokens(72));
+        let context = assembler.assemble(&turn()).unwrap();
+        let history = context.allocation(ContextSource::History).unwrap();
+        assert_eq!((history.used, history.dropped), (72, 0));
+        assert_eq!(context.allocation(ContextSource::Retrieved).unwrap().used, 0);
+        let tools = context.allocation(ContextSource::ToolOutputs).unwrap();
+        assert!(tools.truncated);
+        assert!(context.messages[1].content.contains("[...]"));
+        assert!(!context.messages[1].content.contains("older output"));
+        assert!(context.total_tokens() <= assembler.input_budget());
+    }
+
+    #[test]
+    fn test_limits_and_overflow() {
+        let assembler = approximate(1_000)
+            .with_budget(ContextSource::Retrieved, SourceBudget::new(5).with_max_tokens(50));
+        let context = assembler.assemble(&turn()).unwrap();
+        assert_eq!(context.allocations[0].source, ContextSource::Retrieved);
+        assert_eq!(context.allocations[0].dropped, 1);
+        assert!(context.allocations[0].used <= 50);
+
+        let error = approximate(10).assemble(&turn()).unwrap_err();
+        assert!(matches!(error, AgentError::ContextLengthExceeded(_)));
+    }
+}
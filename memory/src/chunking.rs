@@ -0,0 +1,146 @@
+//! Splitting long text into chunks that fit a token budget.
+//!
+//! Documents are split at the coarsest boundary that keeps every chunk
+//! within the budget: paragraphs first, then lines, sentences and words,
+//! and only as a last resort inside a word. Consecutive pieces are packed
+//! back together so chunks are as large as the budget allows.
+
+use crate::TokenEstimator;
+
+/// Boundaries tried in order, from coarsest to finest
+const SEPARATORS: &[&str] = &["\n\n", "\n", ". ", " "];
+
+/// Splits text into chunks of at most `max_tokens` tokens
+///
+/// # Examples
+///
+/// ```
+/// use memory::{TextChunker, TokenEstimator};
+///
+/// let chunker = TextChunker::new(10).with_estimator(TokenEstimator::Approximate {
+///     chars_per_token: 4.0,
+/// });
+/// let chunks = chunker.split("First paragraph.\n\nSecond paragraph, a little longer.");
+/// assert_eq!(chunks, ["First paragraph.", "Second paragraph, a little longer."]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextChunker {
+    max_tokens: usize,
+    estimator: TokenEstimator,
+}
+
+impl TextChunker {
+    /// Create a chunker with a budget of `max_tokens` per chunk
+    ///
+    /// A budget of zero is treated as one token.
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens: max_tokens.max(1),
+            estimator: TokenEstimator::default(),
+        }
+    }
+
+    /// Set the estimator used to count tokens
+    pub fn with_estimator(mut self, estimator: TokenEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Maximum tokens per chunk
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
+    /// The estimator used to count tokens
+    pub fn estimator(&self) -> TokenEstimator {
+        self.estimator
+    }
+
+    /// Split `text` into chunks, in order, with surrounding whitespace trimmed
+    ///
+    /// Blank text yields no chunks.
+    pub fn split(&self, text: &str) -> Vec<String> {
+        let mut pieces = Vec::new();
+        self.pieces(text, 0, &mut pieces);
+
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        for piece in pieces {
+            if !current.is_empty()
+                && self.estimator.count_text(&format!("{}{}", current, piece)) > self.max_tokens
+            {
+                chunks.push(current.trim().to_string());
+                current.clear();
+            }
+            current.push_str(piece);
+        }
+        chunks.push(current.trim().to_string());
+        chunks.retain(|chunk| !chunk.is_empty());
+        chunks
+    }
+
+    /// Break `text` into pieces that each fit the budget, splitting at
+    /// `SEPARATORS[level]` and finer boundaries
+    fn pieces<'a>(&self, text: &'a str, level: usize, pieces: &mut Vec<&'a str>) {
+        if self.estimator.count_text(text) <= self.max_tokens {
+            pieces.push(text);
+            return;
+        }
+        let Some(separator) = SEPARATORS.get(level) else {
+            self.split_chars(text, pieces);
+            return;
+        };
+        for part in text.split_inclusive(separator) {
+            self.pieces(part, level + 1, pieces);
+        }
+    }
+
+    /// Cut text without any boundary into runs of characters that fit
+    fn split_chars<'a>(&self, text: &'a str, pieces: &mut Vec<&'a str>) {
+        let mut start = 0;
+        while start < text.len() {
+            let mut end = start;
+            for (offset, c) in text[start..].char_indices() {
+                let next = start + offset + c.len_utf8();
+                if end > start && self.estimator.count_text(&text[start..next]) > self.max_tokens {
+                    break;
+                }
+                end = next;
+            }
+            pieces.push(&text[start..end]);
+            start = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunker(max_tokens: usize) -> TextChunker {
+        TextChunker::new(max_tokens).with_estimator(TokenEstimator::Approximate {
+            chars_per_token: 1.0,
+        })
+    }
+
+    #[test]
+    fn test_paragraphs_are_packed_within_budget() {
+        let text = "aaaa\n\nbbbb\n\ncccccccccc\n\ndd";
+        let chunks = chunker(12).split(text);
+        assert_eq!(chunks, ["aaaa\n\nbbbb", "cccccccccc", "dd"]);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 12));
+    }
+
+    #[test]
+    fn test_long_paragraph_falls_back_to_finer_boundaries() {
+        let text = "One two. Three four five. Six.";
+        let chunks = chunker(12).split(text);
+        assert_eq!(chunks, ["One two.", "Three four", "five. Six."]);
+
+        // A single word larger than the budget is cut inside the word
+        let chunks = chunker(4).split("abcdéfghij");
+        assert_eq!(chunks, ["abcd", "éfgh", "ij"]);
+
+        assert!(chunker(4).split("  \n\n ").is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Context compaction for long conversations.
+//!
+//! Agentic sessions tend to accumulate the same content several times: a
+//! retrieval step returns chunks that were already retrieved, or a tool is
+//! called again with the same result. [`Compactor`] finds these near
+//! duplicates by comparing word shingles and replaces every repeat with a
+//! short placeholder, keeping the first occurrence intact.
+
+use agent_core::{Message, Role};
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::count_tokens;
+
+/// Text that replaces collapsed content
+pub const OMITTED_PLACEHOLDER: &str = "[Omitted: repeats content from earlier in the conversation]";
+
+/// Number of consecutive words in a shingle
+const SHINGLE_WORDS: usize = 3;
+
+/// Fingerprint of a piece of text used for near-duplicate detection
+struct Fingerprint {
+    exact: u64,
+    shingles: HashSet<u64>,
+}
+
+impl Fingerprint {
+    fn new(text: &str) -> Self {
+        let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+        let shingles = if words.len() < SHINGLE_WORDS {
+            words.iter().map(|word| hash(&[word])).collect()
+        } else {
+            words.windows(SHINGLE_WORDS).map(hash).collect()
+        };
+        Self {
+            exact: hash(&words),
+            shingles,
+        }
+    }
+
+    /// Jaccard similarity of the two shingle sets
+    fn similarity(&self, other: &Fingerprint) -> f64 {
+        if self.exact == other.exact {
+            return 1.0;
+        }
+        let shared = self.shingles.intersection(&other.shingles).count();
+        let total = self.shingles.len() + other.shingles.len() - shared;
+        if total == 0 {
+            0.0
+        } else {
+            shared as f64 / total as f64
+        }
+    }
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Summary of what a compaction pass removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// Number of messages or chunks replaced by a placeholder
+    pub collapsed: usize,
+    /// Estimated tokens before compaction
+    pub tokens_before: usize,
+    /// Estimated tokens after compaction
+    pub tokens_after: usize,
+}
+
+impl CompactionReport {
+    /// Estimated tokens removed
+    pub fn tokens_saved(&self) -> usize {
+        self.tokens_before.saturating_sub(self.tokens_after)
+    }
+}
+
+/// Collapses near-duplicate content in a message history
+///
+/// Each message is compared as a whole against everything seen before it;
+/// if it is not a repeat, its chunks (paragraphs separated by blank lines)
+/// are compared individually, so a retrieval result that repeats some
+/// earlier chunks keeps only the new ones. System messages are never
+/// changed, and text shorter than the minimum length is left alone since
+/// replacing it would save little.
+///
+/// # Examples
+///
+/// ```
+/// use agent_core::Message;
+/// use memory::Compactor;
+///
+/// let output = "Search results: the Eiffel Tower is 330 metres tall and was completed in 1889.";
+/// let messages = vec![
+///     Message::user(output),
+///     Message::assistant("Noted."),
+///     Message::user(output),
+/// ];
+///
+/// let (compacted, report) = Compactor::new().with_min_chars(20).compact(&messages);
+/// assert_eq!(report.collapsed, 1);
+/// assert_eq!(compacted[0].content, output);
+/// assert_ne!(compacted[2].content, output);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compactor {
+    threshold: f64,
+    min_chars: usize,
+}
+
+impl Default for Compactor {
+    fn default() -> Self {
+        Self {
+            threshold: 0.9,
+            min_chars: 100,
+        }
+    }
+}
+
+impl Compactor {
+    /// Create a compactor with the default similarity threshold (0.9) and
+    /// minimum length (100 characters)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the shingle similarity (0.0 to 1.0) at which text counts as a
+    /// duplicate; 1.0 only collapses exact repeats
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the minimum length in characters of text considered for collapsing
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+
+    /// Return a copy of `messages` with repeated content collapsed
+    pub fn compact(&self, messages: &[Message]) -> (Vec<Message>, CompactionReport) {
+        let mut seen: Vec<Fingerprint> = Vec::new();
+        let mut report = CompactionReport::default();
+        let mut compacted = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            report.tokens_before += count_tokens(message);
+            let mut message = message.clone();
+            if message.role != Role::System {
+                message.content = self.compact_content(&message.content, &mut seen, &mut report);
+            }
+            report.tokens_after += count_tokens(&message);
+            compacted.push(message);
+        }
+
+        (compacted, report)
+    }
+
+    fn compact_content(
+        &self,
+        content: &str,
+        seen: &mut Vec<Fingerprint>,
+        report: &mut CompactionReport,
+    ) -> String {
+        if content.trim().len() >= self.min_chars {
+            let whole = Fingerprint::new(content);
+            if self.is_duplicate(&whole, seen) {
+                report.collapsed += 1;
+                return OMITTED_PLACEHOLDER.to_string();
+            }
+            seen.push(whole);
+        }
+
+        let chunks: Vec<&str> = content.split("\n\n").collect();
+        if chunks.len() == 1 {
+            return content.to_string();
+        }
+
+        let mut kept = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            if chunk.trim().len() < self.min_chars {
+                kept.push(chunk);
+                continue;
+            }
+            let fingerprint = Fingerprint::new(chunk);
+            if self.is_duplicate(&fingerprint, seen) {
+                report.collapsed += 1;
+                kept.push(OMITTED_PLACEHOLDER);
+            } else {
+                seen.push(fingerprint);
+                kept.push(chunk);
+            }
+        }
+        kept.join("\n\n")
+    }
+
+    fn is_duplicate(&self, fingerprint: &Fingerprint, seen: &[Fingerprint]) -> bool {
+        seen.iter()
+            .any(|earlier| fingerprint.similarity(earlier) >= self.threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_A: &str = "Rust ownership rules: each value has a single owner, and the value is dropped when the owner goes out of scope.";
+    const CHUNK_B: &str = "Borrowing lets code use a value without taking ownership; references must never outlive the value they point to.";
+    const CHUNK_C: &str = "Lifetimes are annotations that let the compiler check that references stay valid for as long as they are used.";
+
+    #[test]
+    fn test_repeated_tool_output_collapsed() {
+        let output = format!("Tool results:\n[search] {{}} (ok): {}", CHUNK_A);
+        let messages = vec![
+            Message::user(output.clone()),
+            Message::assistant("Let me check again."),
+            Message::user(output.clone()),
+        ];
+
+        let (compacted, report) = Compactor::new().compact(&messages);
+        assert_eq!(compacted[0].content, output);
+        assert_eq!(compacted[2].content, OMITTED_PLACEHOLDER);
+        assert_eq!(report.collapsed, 1);
+        assert!(report.tokens_saved() > 0);
+    }
+
+    #[test]
+    fn test_near_duplicate_chunks_collapsed() {
+        let first = format!("{}\n\n{}", CHUNK_A, CHUNK_B);
+        // Re-retrieved chunk with different case and spacing, plus a new one
+        let second = format!(
+            "{}\n\n{}",
+            CHUNK_A.to_uppercase().replace(", ", ",   "),
+            CHUNK_C
+        );
+        let messages = vec![Message::user(first), Message::user(second)];
+
+        let (compacted, report) = Compactor::new().compact(&messages);
+        assert_eq!(
+            compacted[1].content,
+            format!("{}\n\n{}", OMITTED_PLACEHOLDER, CHUNK_C)
+        );
+        assert_eq!(report.collapsed, 1);
+    }
+
+    #[test]
+    fn test_similar_but_distinct_chunks_kept() {
+        let edited = CHUNK_A
+            .replace("dropped", "freed")
+            .replace("single", "unique");
+        let messages = vec![Message::user(CHUNK_A), Message::user(edited.clone())];
+
+        let (compacted, report) = Compactor::new().compact(&messages);
+        assert_eq!(compacted[1].content, edited);
+        assert_eq!(report.collapsed, 0);
+
+        let (compacted, _) = Compactor::new().with_threshold(0.3).compact(&messages);
+        assert_eq!(compacted[1].content, OMITTED_PLACEHOLDER);
+    }
+
+    #[test]
+    fn test_short_and_system_messages_untouched() {
+        let messages = vec![
+            Message::system(CHUNK_A),
+            Message::system(CHUNK_A),
+            Message::user("ok"),
+            Message::user("ok"),
+        ];
+
+        let (compacted, report) = Compactor::new().compact(&messages);
+        let contents: Vec<_> = compacted.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec![CHUNK_A, CHUNK_A, "ok", "ok"]);
+        assert_eq!(report.collapsed, 0);
+        assert_eq!(report.tokens_saved(), 0);
+    }
+}
@@ -0,0 +1,403 @@
+//! Multi-turn conversation memory that fits the model's context window.
+//!
+//! [`ConversationMemory`] keeps the full message history of a conversation
+//! and produces the messages for the next request: system messages, a
+//! running summary of turns that no longer fit, and as many recent turns as
+//! the context window allows. Tokens are estimated with a
+//! [`TokenEstimator`] chosen for the provider and model.
+
+use agent_core::{Message, Result, Role};
+use std::future::Future;
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton};
+
+/// Estimated tokens added per message for role formatting
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Prefix of the system message holding the summary of dropped turns
+pub const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:";
+
+/// Estimates how many tokens a message uses for a given model
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TokenEstimator {
+    /// The `cl100k_base` encoding (GPT-4, GPT-3.5)
+    #[default]
+    Cl100k,
+    /// The `o200k_base` encoding (GPT-4o, GPT-4.1, o-series)
+    O200k,
+    /// A characters-per-token ratio, for models without a public tokenizer
+    Approximate {
+        /// Average characters per token
+        chars_per_token: f32,
+    },
+}
+
+impl TokenEstimator {
+    /// Pick an estimator for a provider and model name
+    ///
+    /// OpenAI models use their tiktoken encoding. Claude's tokenizer is not
+    /// public, so Anthropic models are estimated at 3.5 characters per
+    /// token, which slightly overestimates English text; other providers use
+    /// 4 characters per token.
+    pub fn for_model(provider: &str, model: &str) -> Self {
+        match provider {
+            "openai" if model.starts_with("gpt-4o") || model.starts_with("gpt-4.1") => Self::O200k,
+            "openai" if model.starts_with('o') => Self::O200k,
+            "openai" => Self::Cl100k,
+            "anthropic" => Self::Approximate {
+                chars_per_token: 3.5,
+            },
+            _ => Self::Approximate {
+                chars_per_token: 4.0,
+            },
+        }
+    }
+
+    /// Estimated tokens used by a message, including role formatting
+    pub fn count(&self, message: &Message) -> usize {
+        MESSAGE_OVERHEAD_TOKENS + self.count_text(&message.content)
+    }
+
+    /// Estimated tokens used by plain text
+    pub fn count_text(&self, text: &str) -> usize {
+        match self {
+            Self::Cl100k => cl100k_base_singleton()
+                .encode_with_special_tokens(text)
+                .len(),
+            Self::O200k => o200k_base_singleton()
+                .encode_with_special_tokens(text)
+                .len(),
+            Self::Approximate { chars_per_token } => {
+                (text.chars().count() as f32 / chars_per_token.max(0.1)).ceil() as usize
+            }
+        }
+    }
+
+    /// Estimated tokens used by several messages
+    pub fn count_all<'a>(&self, messages: impl IntoIterator<Item = &'a Message>) -> usize {
+        messages
+            .into_iter()
+            .map(|message| self.count(message))
+            .sum()
+    }
+}
+
+/// Conversation history that keeps requests within the context window
+///
+/// System messages are always sent. Other messages are sent newest first
+/// until the input budget (the context window minus the tokens reserved for
+/// the reply) is used up. [`truncate`](Self::truncate) drops the turns that
+/// no longer fit; [`summarize_overflow`](Self::summarize_overflow) replaces
+/// them with a summary written by the caller's summarizer.
+///
+/// # Examples
+///
+/// ```
+/// use memory::{ConversationMemory, TokenEstimator};
+/// use agent_core::Message;
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let mut memory = ConversationMemory::new(8_000)
+///     .with_estimator(TokenEstimator::for_model("openai", "gpt-4"))
+///     .with_reserved_output_tokens(1_000);
+///
+/// memory.push(Message::system("You are a helpful assistant."));
+/// memory.push(Message::user("Hello!"));
+///
+/// // Before each request, fold turns that no longer fit into a summary
+/// memory
+///     .summarize_overflow(|dropped| async move {
+///         Ok(format!("{} earlier messages about greetings", dropped.len()))
+///     })
+///     .await?;
+/// let request = memory.context();
+/// assert_eq!(request.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConversationMemory {
+    messages: Vec<Message>,
+    summary: Option<Message>,
+    estimator: TokenEstimator,
+    context_window: usize,
+    reserved_output_tokens: usize,
+}
+
+impl ConversationMemory {
+    /// Create an empty memory for a model with `context_window` tokens
+    pub fn new(context_window: usize) -> Self {
+        Self {
+            messages: Vec::new(),
+            summary: None,
+            estimator: TokenEstimator::default(),
+            context_window,
+            reserved_output_tokens: 0,
+        }
+    }
+
+    /// Set how tokens are estimated
+    pub fn with_estimator(mut self, estimator: TokenEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Keep `tokens` of the context window free for the model's reply
+    pub fn with_reserved_output_tokens(mut self, tokens: usize) -> Self {
+        self.reserved_output_tokens = tokens;
+        self
+    }
+
+    /// Add a message to the end of the conversation
+    pub fn push(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// All stored messages, oldest first, without the summary
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// The summary of dropped turns, if any
+    pub fn summary(&self) -> Option<&Message> {
+        self.summary.as_ref()
+    }
+
+    /// Number of stored messages
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Check if no messages are stored
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Remove all messages and the summary
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.summary = None;
+    }
+
+    /// Tokens available for the request
+    pub fn input_budget(&self) -> usize {
+        self.context_window
+            .saturating_sub(self.reserved_output_tokens)
+    }
+
+    /// Estimated tokens of all stored messages and the summary
+    pub fn token_count(&self) -> usize {
+        self.estimator
+            .count_all(self.summary.iter().chain(&self.messages))
+    }
+
+    /// Messages for the next request, within the input budget
+    ///
+    /// System messages come first, then the summary, then the most recent
+    /// other messages that fit, oldest first.
+    pub fn context(&self) -> Vec<Message> {
+        let system: Vec<&Message> = self
+            .messages
+            .iter()
+            .filter(|message| message.role == Role::System)
+            .chain(&self.summary)
+            .collect();
+        let mut remaining = self
+            .input_budget()
+            .saturating_sub(self.estimator.count_all(system.iter().copied()));
+
+        let mut recent = Vec::new();
+        for message in self.turns().rev() {
+            let tokens = self.estimator.count(message);
+            if tokens > remaining {
+                break;
+            }
+            remaining -= tokens;
+            recent.push(message);
+        }
+
+        system
+            .into_iter()
+            .chain(recent.into_iter().rev())
+            .cloned()
+            .collect()
+    }
+
+    /// Drop the oldest turns until the conversation fits the input budget
+    ///
+    /// System messages and the summary are kept. Returns the dropped
+    /// messages, oldest first.
+    pub fn truncate(&mut self) -> Vec<Message> {
+        let mut dropped = Vec::new();
+        while self.token_count() > self.input_budget() {
+            let Some(index) = self
+                .messages
+                .iter()
+                .position(|message| message.role != Role::System)
+            else {
+                break;
+            };
+            dropped.push(self.messages.remove(index));
+        }
+        dropped
+    }
+
+    /// Replace turns that no longer fit with a summary
+    ///
+    /// When the conversation is over budget, the oldest turns are dropped
+    /// and passed to `summarize` together with the previous summary, if any.
+    /// The text it returns becomes the new summary, sent as a system message
+    /// after the other system messages. If the summary itself does not fit,
+    /// further turns are dropped without being summarized. Returns whether
+    /// a summary was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `summarize`; the dropped turns are restored.
+    pub async fn summarize_overflow<F, Fut>(&mut self, summarize: F) -> Result<bool>
+    where
+        F: FnOnce(Vec<Message>) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let before = self.messages.clone();
+        let dropped = self.truncate();
+        if dropped.is_empty() {
+            return Ok(false);
+        }
+
+        let input = self.summary.iter().cloned().chain(dropped).collect();
+        match summarize(input).await {
+            Ok(text) => {
+                self.summary = Some(Message::system(format!("{} {}", SUMMARY_PREFIX, text)));
+                self.truncate();
+                Ok(true)
+            }
+            Err(error) => {
+                self.messages = before;
+                Err(error)
+            }
+        }
+    }
+
+    /// Stored messages that are not system messages
+    fn turns(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.messages
+            .iter()
+            .filter(|message| message.role != Role::System)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::AgentError;
+
+    /// One token per character, plus the per-message overhead
+    fn memory(context_window: usize) -> ConversationMemory {
+        ConversationMemory::new(context_window).with_estimator(TokenEstimator::Approximate {
+            chars_per_token: 1.0,
+        })
+    }
+
+    #[test]
+    fn test_estimator_for_model() {
+        assert_eq!(
+            TokenEstimator::for_model("openai", "gpt-4"),
+            TokenEstimator::Cl100k
+        );
+        assert_eq!(
+            TokenEstimator::for_model("openai", "gpt-4o-mini"),
+            TokenEstimator::O200k
+        );
+        assert_eq!(
+            TokenEstimator::for_model("openai", "o3-mini"),
+            TokenEstimator::O200k
+        );
+        assert!(matches!(
+            TokenEstimator::for_model("anthropic", "claude-sonnet-4-5"),
+            TokenEstimator::Approximate { .. }
+        ));
+
+        let message = Message::user("Hello, world!");
+        assert!(TokenEstimator::Cl100k.count(&message) > MESSAGE_OVERHEAD_TOKENS);
+        assert!(TokenEstimator::O200k.count(&message) > MESSAGE_OVERHEAD_TOKENS);
+    }
+
+    #[test]
+    fn test_context_keeps_system_and_recent_turns() {
+        let mut memory = memory(40).with_reserved_output_tokens(8);
+        memory.push(Message::system("Be brief"));
+        for text in ["first turn", "second", "third"] {
+            memory.push(Message::user(text));
+        }
+
+        // Budget 32: system 12, "third" 9, "second" 10, "first turn" 14
+        let context = memory.context();
+        let contents: Vec<&str> = context
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["Be brief", "second", "third"]);
+        assert_eq!(memory.len(), 4);
+    }
+
+    #[test]
+    fn test_truncate_drops_oldest_turns() {
+        let mut memory = memory(31);
+        memory.push(Message::system("Be brief"));
+        memory.push(Message::user("first turn"));
+        memory.push(Message::assistant("second"));
+        memory.push(Message::user("third"));
+
+        let dropped = memory.truncate();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].content, "first turn");
+        assert!(memory.token_count() <= memory.input_budget());
+        assert_eq!(memory.messages()[0].role, Role::System);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_overflow() {
+        // Messages use 34, 24 and 14 tokens
+        let mut memory = memory(80);
+        memory.push(Message::user("a".repeat(30)));
+        memory.push(Message::assistant("b".repeat(20)));
+        memory.push(Message::user("c".repeat(10)));
+        assert!(
+            !memory
+                .summarize_overflow(|_| async { Ok(String::new()) })
+                .await
+                .unwrap()
+        );
+
+        memory.push(Message::assistant("d".repeat(10)));
+        let summarized = memory
+            .summarize_overflow(|dropped| async move {
+                assert_eq!(dropped.len(), 1);
+                Ok("hi".to_string())
+            })
+            .await
+            .unwrap();
+        assert!(summarized);
+
+        // The summary only fits once the second turn is dropped as well
+        let context = memory.context();
+        assert_eq!(context.len(), 3);
+        assert_eq!(context[0].content, format!("{} hi", SUMMARY_PREFIX));
+        assert_eq!(context[1].content, "c".repeat(10));
+        assert!(memory.token_count() <= memory.input_budget());
+
+        memory.push(Message::user("a much longer fifth turn"));
+        let result = memory
+            .summarize_overflow(|_| async { Err(AgentError::LLMProvider("down".into())) })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            memory.messages().last().unwrap().content,
+            "a much longer fifth turn"
+        );
+        assert_eq!(
+            memory.summary().unwrap().content,
+            format!("{} hi", SUMMARY_PREFIX)
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Dead-letter store for failed background runs.
+//!
+//! Scheduled and webhook-triggered tasks have nobody waiting on the result,
+//! so a failure that is only logged is easily lost. A [`DeadLetterQueue`]
+//! keeps the input, the transcript produced before the failure and the
+//! error of every failed run, so an operator can inspect them and re-drive
+//! a run once the cause is fixed. With [`DeadLetterQueue::open`] the queue
+//! is persisted to a JSON file and survives restarts.
+
+use agent_core::{AgentError, ErrorCategory, Message, RequestContext, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+/// A failed run kept for inspection and re-driving
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Identifier used to inspect, re-drive or discard the run
+    pub id: String,
+    /// Task that failed, e.g. `nightly-report` or `webhook:github`
+    pub task: String,
+    /// Input the task was started with
+    pub input: String,
+    /// Messages produced before the failure
+    pub transcript: Vec<Message>,
+    /// The error that ended the most recent attempt
+    pub error: String,
+    /// Whether the error is likely to go away on its own (rate limits,
+    /// timeouts, outages)
+    pub retryable: bool,
+    /// Number of failed attempts, including re-drives
+    pub attempts: u32,
+    /// When the most recent attempt failed
+    pub failed_at: DateTime<Utc>,
+    /// The request the run belonged to, if one was in scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<RequestContext>,
+}
+
+/// Queue of failed runs, optionally persisted to a JSON file
+///
+/// # Examples
+///
+/// ```
+/// use memory::DeadLetterQueue;
+/// use agent_core::{AgentError, Message};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let mut queue = DeadLetterQueue::new();
+/// let id = queue.record(
+///     "nightly-report",
+///     "Summarize yesterday's tickets",
+///     vec![Message::user("Summarize yesterday's tickets")],
+///     &AgentError::LLMProvider("HTTP 503".to_string()),
+/// )?;
+///
+/// // Later, once the provider is back
+/// let report = queue
+///     .redrive(&id, |letter| async move { Ok(format!("report for: {}", letter.input)) })
+///     .await?;
+/// assert!(report.starts_with("report for"));
+/// assert!(queue.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct DeadLetterQueue {
+    letters: Vec<DeadLetter>,
+    next_id: u64,
+    path: Option<PathBuf>,
+}
+
+impl DeadLetterQueue {
+    /// Create an empty, in-memory queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a queue persisted at `path`, loading any dead letters already
+    /// stored there
+    ///
+    /// Every change is written back to the file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let letters: Vec<DeadLetter> = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        let next_id = letters
+            .iter()
+            .filter_map(|letter| letter.id.strip_prefix("dl-")?.parse::<u64>().ok())
+            .max()
+            .map_or(0, |id| id + 1);
+        Ok(Self {
+            letters,
+            next_id,
+            path: Some(path),
+        })
+    }
+
+    /// Record a failed run and return its ID
+    ///
+    /// # Arguments
+    /// * `task` - Name of the task that failed
+    /// * `input` - Input the task was started with
+    /// * `transcript` - Messages produced before the failure
+    /// * `error` - The error that ended the run
+    pub fn record(
+        &mut self,
+        task: impl Into<String>,
+        input: impl Into<String>,
+        transcript: Vec<Message>,
+        error: &AgentError,
+    ) -> Result<String> {
+        let id = format!("dl-{}", self.next_id);
+        self.next_id += 1;
+        self.letters.push(DeadLetter {
+            id: id.clone(),
+            task: task.into(),
+            input: input.into(),
+            transcript,
+            error: error.to_string(),
+            retryable: ErrorCategory::of(error).is_retryable(),
+            attempts: 1,
+            failed_at: Utc::now(),
+            request: RequestContext::current(),
+        });
+        self.persist()?;
+        Ok(id)
+    }
+
+    /// All dead letters, oldest first
+    pub fn list(&self) -> &[DeadLetter] {
+        &self.letters
+    }
+
+    /// Dead letters for one task, oldest first
+    pub fn for_task<'a>(&'a self, task: &'a str) -> impl Iterator<Item = &'a DeadLetter> {
+        self.letters
+            .iter()
+            .filter(move |letter| letter.task == task)
+    }
+
+    /// Look up a dead letter by ID
+    pub fn get(&self, id: &str) -> Option<&DeadLetter> {
+        self.letters.iter().find(|letter| letter.id == id)
+    }
+
+    /// Discard a dead letter without re-driving it
+    pub fn remove(&mut self, id: &str) -> Result<Option<DeadLetter>> {
+        let Some(index) = self.position(id) else {
+            return Ok(None);
+        };
+        let letter = self.letters.remove(index);
+        self.persist()?;
+        Ok(Some(letter))
+    }
+
+    /// Number of dead letters
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    /// Check if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    /// Run a dead letter again
+    ///
+    /// `run` receives the dead letter and retries the task. On success the
+    /// dead letter is removed and the output returned; on failure it stays
+    /// queued with the new error and one more attempt counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Memory` if no dead letter has this ID, or the
+    /// error returned by `run`.
+    pub async fn redrive<F, Fut, T>(&mut self, id: &str, run: F) -> Result<T>
+    where
+        F: FnOnce(DeadLetter) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let letter = self
+            .get(id)
+            .cloned()
+            .ok_or_else(|| AgentError::Memory(format!("No dead letter with id '{}'", id)))?;
+
+        match run(letter).await {
+            Ok(output) => {
+                self.remove(id)?;
+                Ok(output)
+            }
+            Err(error) => {
+                if let Some(index) = self.position(id) {
+                    let letter = &mut self.letters[index];
+                    letter.error = error.to_string();
+                    letter.retryable = ErrorCategory::of(&error).is_retryable();
+                    letter.attempts += 1;
+                    letter.failed_at = Utc::now();
+                    self.persist()?;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.letters.iter().position(|letter| letter.id == id)
+    }
+
+    fn persist(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_string_pretty(&self.letters)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outage() -> AgentError {
+        AgentError::LLMProvider("OpenAI API HTTP 503 error".to_string())
+    }
+
+    #[test]
+    fn test_record_and_inspect() {
+        let mut queue = DeadLetterQueue::new();
+        let transcript = vec![
+            Message::user("Sync orders"),
+            Message::assistant("Fetching..."),
+        ];
+        let first = queue
+            .record("sync", "Sync orders", transcript, &outage())
+            .unwrap();
+        let second = queue
+            .record(
+                "report",
+                "Weekly report",
+                Vec::new(),
+                &AgentError::Planning("bad".into()),
+            )
+            .unwrap();
+        assert_ne!(first, second);
+
+        let letter = queue.get(&first).unwrap();
+        assert_eq!(letter.transcript.len(), 2);
+        assert!(letter.error.contains("503"));
+        assert!(letter.retryable);
+        assert!(!queue.get(&second).unwrap().retryable);
+        assert_eq!(queue.for_task("report").count(), 1);
+
+        assert!(queue.remove(&second).unwrap().is_some());
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_redrive_stays_queued() {
+        let mut queue = DeadLetterQueue::new();
+        let id = queue
+            .record("sync", "Sync orders", Vec::new(), &outage())
+            .unwrap();
+
+        let result: Result<()> = queue
+            .redrive(&id, |_| async {
+                Err(AgentError::Execution("still down".into()))
+            })
+            .await;
+        assert!(result.is_err());
+        let letter = queue.get(&id).unwrap();
+        assert_eq!(letter.attempts, 2);
+        assert!(letter.error.contains("still down"));
+
+        let output = queue
+            .redrive(&id, |letter| async move { Ok(letter.input.len()) })
+            .await
+            .unwrap();
+        assert_eq!(output, "Sync orders".len());
+        assert!(queue.is_empty());
+
+        let missing: Result<()> = queue.redrive(&id, |_| async { Ok(()) }).await;
+        assert!(matches!(missing, Err(AgentError::Memory(_))));
+    }
+
+    #[test]
+    fn test_persisted_queue_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dead_letters.json");
+
+        let mut queue = DeadLetterQueue::open(&path).unwrap();
+        let first = queue.record("sync", "a", Vec::new(), &outage()).unwrap();
+        queue.record("sync", "b", Vec::new(), &outage()).unwrap();
+        queue.remove(&first).unwrap();
+
+        let mut reopened = DeadLetterQueue::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.list()[0].input, "b");
+        let third = reopened.record("sync", "c", Vec::new(), &outage()).unwrap();
+        assert_eq!(third, "dl-2");
+    }
+}
@@ -0,0 +1,245 @@
+//! User feedback on assistant messages.
+//!
+//! Users rate responses with a thumbs up or down, explain themselves in a
+//! comment, or write the answer they expected as a correction. Feedback is
+//! attached to a [`MessageId`] and stored next to the transcript in a
+//! [`ConversationIndex`](crate::ConversationIndex), which exports it as a
+//! [`FeedbackDataset`] for evaluation or fine-tuning.
+
+use agent_core::{AgentError, Message, Result, Role};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A message within an indexed session
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MessageId {
+    /// The session the message belongs to
+    pub session_id: String,
+    /// Index of the message within its session
+    pub position: usize,
+}
+
+impl MessageId {
+    /// Identify the message at `position` in a session
+    pub fn new(session_id: impl Into<String>, position: usize) -> Self {
+        Self {
+            session_id: session_id.into(),
+            position,
+        }
+    }
+}
+
+/// Thumbs up or thumbs down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    /// The response was helpful
+    Up,
+    /// The response was unhelpful or wrong
+    Down,
+}
+
+/// A user's feedback on one assistant message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Feedback {
+    /// Thumbs up or down, if given
+    pub rating: Option<Rating>,
+    /// Free-text comment, if given
+    pub comment: Option<String>,
+    /// The response the user expected instead, if given
+    pub correction: Option<String>,
+    /// When the feedback was given
+    pub created_at: DateTime<Utc>,
+}
+
+impl Feedback {
+    /// A thumbs up
+    pub fn thumbs_up() -> Self {
+        Self::rated(Some(Rating::Up))
+    }
+
+    /// A thumbs down
+    pub fn thumbs_down() -> Self {
+        Self::rated(Some(Rating::Down))
+    }
+
+    /// A free-text comment without a rating
+    pub fn comment(comment: impl Into<String>) -> Self {
+        Self::rated(None).with_comment(comment)
+    }
+
+    /// The response the user expected, without a rating
+    pub fn correction(correction: impl Into<String>) -> Self {
+        Self::rated(None).with_correction(correction)
+    }
+
+    fn rated(rating: Option<Rating>) -> Self {
+        Self {
+            rating,
+            comment: None,
+            correction: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Add a free-text comment
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Add the response the user expected
+    pub fn with_correction(mut self, correction: impl Into<String>) -> Self {
+        self.correction = Some(correction.into());
+        self
+    }
+
+    /// Returns true if there is no rating, comment or correction
+    pub fn is_empty(&self) -> bool {
+        self.rating.is_none() && self.comment.is_none() && self.correction.is_none()
+    }
+}
+
+/// A rated response with the conversation that led to it
+#[derive(Debug, Clone)]
+pub struct FeedbackExample {
+    /// The rated message
+    pub message: MessageId,
+    /// Messages of the session before the response
+    pub context: Vec<Message>,
+    /// The assistant response the feedback is about
+    pub response: Message,
+    /// The feedback
+    pub feedback: Feedback,
+}
+
+impl FeedbackExample {
+    /// The conversation to train on, if the feedback makes one
+    ///
+    /// A correction replaces the response; a response rated up without a
+    /// correction is kept as is. Anything else, such as a thumbs down
+    /// without a correction, is not a training example.
+    pub fn training_conversation(&self) -> Option<Vec<Message>> {
+        let response = match (&self.feedback.correction, self.feedback.rating) {
+            (Some(correction), _) => Message::assistant(correction.clone()),
+            (None, Some(Rating::Up)) => self.response.clone(),
+            (None, _) => return None,
+        };
+        let mut conversation = self.context.clone();
+        conversation.push(response);
+        Some(conversation)
+    }
+}
+
+/// Feedback exported from a [`ConversationIndex`](crate::ConversationIndex)
+///
+/// # Examples
+///
+/// ```
+/// use memory::{ConversationIndex, Feedback, MessageId};
+/// use agent_core::Message;
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let mut index = ConversationIndex::in_memory()?;
+/// index.index_session(
+///     "support-1",
+///     &[
+///         Message::user("What is the capital of Australia?"),
+///         Message::assistant("Sydney."),
+///     ],
+/// )?;
+/// index.record_feedback(
+///     &MessageId::new("support-1", 1),
+///     &Feedback::thumbs_down().with_correction("Canberra."),
+/// )?;
+///
+/// let dataset = index.export_feedback()?;
+/// let conversations = dataset.training_conversations();
+/// assert_eq!(conversations[0][1].content, "Canberra.");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackDataset {
+    /// Examples ordered by session and position, oldest feedback first
+    pub examples: Vec<FeedbackExample>,
+}
+
+impl FeedbackDataset {
+    /// Number of examples
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    /// Returns true if the dataset has no examples
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// Conversations to fine-tune on; see
+    /// [`FeedbackExample::training_conversation`]
+    pub fn training_conversations(&self) -> Vec<Vec<Message>> {
+        self.examples
+            .iter()
+            .filter_map(FeedbackExample::training_conversation)
+            .collect()
+    }
+
+    /// Serialize as an evaluation set in JSONL, one object per example with
+    /// the context `messages`, the `response` and the feedback fields
+    ///
+    /// # Errors
+    /// Returns an error if serialization fails.
+    pub fn to_eval_jsonl(&self) -> Result<String> {
+        let mut jsonl = String::new();
+        for example in &self.examples {
+            let line = EvalExample {
+                session_id: &example.message.session_id,
+                position: example.message.position,
+                messages: example.context.iter().map(EvalMessage::from).collect(),
+                response: &example.response.content,
+                rating: example.feedback.rating,
+                comment: example.feedback.comment.as_deref(),
+                correction: example.feedback.correction.as_deref(),
+            };
+            jsonl.push_str(
+                &serde_json::to_string(&line).map_err(|e| {
+                    AgentError::Memory(format!("Failed to serialize feedback: {}", e))
+                })?,
+            );
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+}
+
+/// One line of an evaluation JSONL file
+#[derive(Serialize)]
+struct EvalExample<'a> {
+    session_id: &'a str,
+    position: usize,
+    messages: Vec<EvalMessage<'a>>,
+    response: &'a str,
+    rating: Option<Rating>,
+    comment: Option<&'a str>,
+    correction: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct EvalMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+impl<'a> From<&'a Message> for EvalMessage<'a> {
+    fn from(message: &'a Message) -> Self {
+        Self {
+            role: match message.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            },
+            content: &message.content,
+        }
+    }
+}
@@ -0,0 +1,752 @@
+//! Knowledge-graph memory.
+//!
+//! Vector search finds text that resembles a query, but not facts that are
+//! only connected to it: "who manages the person who owns this service?".
+//! A [`KnowledgeGraph`] keeps [`Entity`]s and typed [`Relation`]s between
+//! them, as extracted from conversations and documents (see
+//! `llm::GraphExtractor`), in a [`GraphStore`]: [`InMemoryGraphStore`] for
+//! a single run, or [`SqliteGraphStore`] to keep the graph across restarts.
+//!
+//! Queries return a [`Subgraph`] around the entities a text mentions, which
+//! renders as a system message to inject into a prompt.
+
+use agent_core::{AgentError, Message, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS graph_entities (
+    key TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS graph_relations (
+    source TEXT NOT NULL REFERENCES graph_entities (key) ON DELETE CASCADE,
+    relation TEXT NOT NULL,
+    target TEXT NOT NULL REFERENCES graph_entities (key) ON DELETE CASCADE,
+    PRIMARY KEY (source, relation, target)
+);
+CREATE INDEX IF NOT EXISTS graph_relations_target ON graph_relations (target);
+";
+
+/// Kind given to entities that only appear in a relation
+pub const UNKNOWN_KIND: &str = "unknown";
+
+/// A person, organisation, place, product or other thing the agent knows of
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    /// Name of the entity; names differing only in case are the same entity
+    pub name: String,
+    /// What kind of thing it is, e.g. `person` or `service`
+    pub kind: String,
+    /// What is known about it, if anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Entity {
+    /// An entity without a description
+    pub fn new(name: impl Into<String>, kind: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: kind.into(),
+            description: None,
+        }
+    }
+
+    /// Set what is known about the entity
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The key entities are stored and looked up under
+    pub fn key(&self) -> String {
+        entity_key(&self.name)
+    }
+}
+
+/// A typed, directed link between two entities, e.g. `Alice works_at Acme`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Relation {
+    /// Name of the entity the relation starts from
+    pub source: String,
+    /// Type of the relation, in snake case
+    pub relation: String,
+    /// Name of the entity the relation points to
+    pub target: String,
+}
+
+impl Relation {
+    /// A relation; its type is lowercased and spaces become underscores
+    pub fn new(
+        source: impl Into<String>,
+        relation: impl AsRef<str>,
+        target: impl Into<String>,
+    ) -> Self {
+        Self {
+            source: source.into(),
+            relation: relation_type(relation.as_ref()),
+            target: target.into(),
+        }
+    }
+}
+
+/// Entities and relations to add to a graph, e.g. extracted from a text
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphUpdate {
+    /// Entities to add or update
+    #[serde(default)]
+    pub entities: Vec<Entity>,
+    /// Relations to add
+    #[serde(default)]
+    pub relations: Vec<Relation>,
+}
+
+/// Storage for the entities and relations of a [`KnowledgeGraph`]
+///
+/// Entities are addressed by [`Entity::key`]. Relations are stored with
+/// the keys of their entities as `source` and `target`.
+pub trait GraphStore: Send {
+    /// The entity stored under `key`, if any
+    fn entity(&self, key: &str) -> Result<Option<Entity>>;
+
+    /// Store an entity, replacing the one with the same key
+    fn put_entity(&mut self, entity: &Entity) -> Result<()>;
+
+    /// Store a relation between two stored entities, if not stored already
+    fn put_relation(&mut self, relation: &Relation) -> Result<()>;
+
+    /// Every stored entity
+    fn entities(&self) -> Result<Vec<Entity>>;
+
+    /// The relations starting or ending at the entity under `key`
+    fn relations_of(&self, key: &str) -> Result<Vec<Relation>>;
+
+    /// Remove an entity and its relations, returning whether it existed
+    fn remove_entity(&mut self, key: &str) -> Result<bool>;
+}
+
+/// Graph storage that lives as long as the value
+#[derive(Debug, Default)]
+pub struct InMemoryGraphStore {
+    entities: BTreeMap<String, Entity>,
+    relations: HashSet<Relation>,
+}
+
+impl InMemoryGraphStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GraphStore for InMemoryGraphStore {
+    fn entity(&self, key: &str) -> Result<Option<Entity>> {
+        Ok(self.entities.get(key).cloned())
+    }
+
+    fn put_entity(&mut self, entity: &Entity) -> Result<()> {
+        self.entities.insert(entity.key(), entity.clone());
+        Ok(())
+    }
+
+    fn put_relation(&mut self, relation: &Relation) -> Result<()> {
+        self.relations.insert(relation.clone());
+        Ok(())
+    }
+
+    fn entities(&self) -> Result<Vec<Entity>> {
+        Ok(self.entities.values().cloned().collect())
+    }
+
+    fn relations_of(&self, key: &str) -> Result<Vec<Relation>> {
+        let mut relations: Vec<Relation> = self
+            .relations
+            .iter()
+            .filter(|relation| relation.source == key || relation.target == key)
+            .cloned()
+            .collect();
+        relations.sort();
+        Ok(relations)
+    }
+
+    fn remove_entity(&mut self, key: &str) -> Result<bool> {
+        self.relations
+            .retain(|relation| relation.source != key && relation.target != key);
+        Ok(self.entities.remove(key).is_some())
+    }
+}
+
+/// Graph storage in a SQLite database
+pub struct SqliteGraphStore {
+    connection: Connection,
+}
+
+impl SqliteGraphStore {
+    /// Open or create a store in a SQLite database file
+    ///
+    /// The tables can share a database with a
+    /// [`ConversationStore`](crate::ConversationStore).
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened or initialized.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path).map_err(sql_error)?)
+    }
+
+    /// Create a store that lives only as long as this value
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be initialized.
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn init(connection: Connection) -> Result<Self> {
+        connection
+            .pragma_update(None, "foreign_keys", true)
+            .map_err(sql_error)?;
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        Ok(Self { connection })
+    }
+}
+
+impl GraphStore for SqliteGraphStore {
+    fn entity(&self, key: &str) -> Result<Option<Entity>> {
+        self.connection
+            .query_row(
+                "SELECT name, kind, description FROM graph_entities WHERE key = ?1",
+                [key],
+                |row| {
+                    Ok(Entity {
+                        name: row.get(0)?,
+                        kind: row.get(1)?,
+                        description: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(sql_error)
+    }
+
+    fn put_entity(&mut self, entity: &Entity) -> Result<()> {
+        self.connection
+            .execute(
+                "INSERT INTO graph_entities (key, name, kind, description)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (key) DO UPDATE SET
+                     name = excluded.name,
+                     kind = excluded.kind,
+                     description = excluded.description",
+                params![entity.key(), entity.name, entity.kind, entity.description],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn put_relation(&mut self, relation: &Relation) -> Result<()> {
+        self.connection
+            .execute(
+                "INSERT OR IGNORE INTO graph_relations (source, relation, target)
+                 VALUES (?1, ?2, ?3)",
+                params![relation.source, relation.relation, relation.target],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    fn entities(&self) -> Result<Vec<Entity>> {
+        let mut select = self
+            .connection
+            .prepare("SELECT name, kind, description FROM graph_entities ORDER BY key")
+            .map_err(sql_error)?;
+        select
+            .query_map([], |row| {
+                Ok(Entity {
+                    name: row.get(0)?,
+                    kind: row.get(1)?,
+                    description: row.get(2)?,
+                })
+            })
+            .map_err(sql_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sql_error)
+    }
+
+    fn relations_of(&self, key: &str) -> Result<Vec<Relation>> {
+        let mut select = self
+            .connection
+            .prepare(
+                "SELECT source, relation, target FROM graph_relations
+                 WHERE source = ?1 OR target = ?1
+                 ORDER BY source, relation, target",
+            )
+            .map_err(sql_error)?;
+        select
+            .query_map([key], |row| {
+                Ok(Relation {
+                    source: row.get(0)?,
+                    relation: row.get(1)?,
+                    target: row.get(2)?,
+                })
+            })
+            .map_err(sql_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sql_error)
+    }
+
+    fn remove_entity(&mut self, key: &str) -> Result<bool> {
+        let removed = self
+            .connection
+            .execute("DELETE FROM graph_entities WHERE key = ?1", [key])
+            .map_err(sql_error)?;
+        Ok(removed > 0)
+    }
+}
+
+/// Entities and the relations between them, as returned by graph queries
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subgraph {
+    /// The entities, in the order they were reached
+    pub entities: Vec<Entity>,
+    /// Relations between the entities, using their names
+    pub relations: Vec<Relation>,
+}
+
+impl Subgraph {
+    /// Check if the subgraph has no entities
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Render the subgraph as a list of facts for a prompt
+    pub fn to_prompt(&self) -> String {
+        let mut lines = vec!["Known facts:".to_string()];
+        for entity in &self.entities {
+            let mut line = format!("- {} ({})", entity.name, entity.kind);
+            if let Some(description) = &entity.description {
+                line.push_str(&format!(": {}", description));
+            }
+            lines.push(line);
+        }
+        for relation in &self.relations {
+            lines.push(format!(
+                "- {} {} {}",
+                relation.source,
+                relation.relation.replace('_', " "),
+                relation.target
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Entities and typed relations remembered across conversations
+///
+/// # Examples
+///
+/// ```
+/// use memory::{Entity, GraphUpdate, KnowledgeGraph, Relation};
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let mut graph = KnowledgeGraph::in_memory();
+/// graph.apply(&GraphUpdate {
+///     entities: vec![
+///         Entity::new("Alice", "person"),
+///         Entity::new("Billing API", "service").with_description("Charges customers"),
+///     ],
+///     relations: vec![
+///         Relation::new("Alice", "owns", "Billing API"),
+///         Relation::new("Bob", "manages", "Alice"),
+///     ],
+/// })?;
+///
+/// let subgraph = graph.relevant_subgraph("Who should I ask about the billing api?", 2)?;
+/// assert!(subgraph.to_prompt().contains("Bob manages Alice"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct KnowledgeGraph {
+    store: Box<dyn GraphStore>,
+}
+
+impl KnowledgeGraph {
+    /// Create a graph kept in `store`
+    pub fn new(store: impl GraphStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+        }
+    }
+
+    /// Create a graph kept in memory
+    pub fn in_memory() -> Self {
+        Self::new(InMemoryGraphStore::new())
+    }
+
+    /// Add or update an entity
+    ///
+    /// An entity that is already known keeps its description unless the
+    /// new one has one, and its kind if the new kind is [`UNKNOWN_KIND`].
+    ///
+    /// # Errors
+    /// Returns an error if the name is blank or the store fails.
+    pub fn add_entity(&mut self, entity: &Entity) -> Result<()> {
+        let name = entity.name.trim();
+        if name.is_empty() {
+            return Err(AgentError::Memory(
+                "Entity name cannot be empty".to_string(),
+            ));
+        }
+
+        let mut merged = Entity {
+            name: name.to_string(),
+            kind: entity.kind.trim().to_lowercase(),
+            description: entity.description.clone(),
+        };
+        if merged.kind.is_empty() {
+            merged.kind = UNKNOWN_KIND.to_string();
+        }
+        if let Some(existing) = self.store.entity(&merged.key())? {
+            if merged.kind == UNKNOWN_KIND {
+                merged.kind = existing.kind;
+            }
+            if merged.description.is_none() {
+                merged.description = existing.description;
+            }
+        }
+        self.store.put_entity(&merged)
+    }
+
+    /// Add a relation, creating entities of [`UNKNOWN_KIND`] for names that
+    /// are not known yet
+    ///
+    /// # Errors
+    /// Returns an error if a name or the relation type is blank, or the
+    /// store fails.
+    pub fn add_relation(&mut self, relation: &Relation) -> Result<()> {
+        let relation_type = relation_type(&relation.relation);
+        if relation_type.is_empty() {
+            return Err(AgentError::Memory(
+                "Relation type cannot be empty".to_string(),
+            ));
+        }
+        for name in [&relation.source, &relation.target] {
+            if self.store.entity(&entity_key(name))?.is_none() {
+                self.add_entity(&Entity::new(name.as_str(), UNKNOWN_KIND))?;
+            }
+        }
+        self.store.put_relation(&Relation {
+            source: entity_key(&relation.source),
+            relation: relation_type,
+            target: entity_key(&relation.target),
+        })
+    }
+
+    /// Add the entities, then the relations, of an update
+    ///
+    /// # Errors
+    /// Returns the first error of [`add_entity`](Self::add_entity) or
+    /// [`add_relation`](Self::add_relation).
+    pub fn apply(&mut self, update: &GraphUpdate) -> Result<()> {
+        for entity in &update.entities {
+            self.add_entity(entity)?;
+        }
+        for relation in &update.relations {
+            self.add_relation(relation)?;
+        }
+        Ok(())
+    }
+
+    /// The entity with this name, ignoring case
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn entity(&self, name: &str) -> Result<Option<Entity>> {
+        self.store.entity(&entity_key(name))
+    }
+
+    /// Every entity
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn entities(&self) -> Result<Vec<Entity>> {
+        self.store.entities()
+    }
+
+    /// Relations starting or ending at the named entity, using entity names
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn relations_of(&self, name: &str) -> Result<Vec<Relation>> {
+        self.store
+            .relations_of(&entity_key(name))?
+            .into_iter()
+            .map(|relation| self.named(relation))
+            .collect()
+    }
+
+    /// Forget an entity and its relations, returning whether it was known
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn remove_entity(&mut self, name: &str) -> Result<bool> {
+        self.store.remove_entity(&entity_key(name))
+    }
+
+    /// Known entities whose name appears in `text`, ignoring case
+    ///
+    /// Names only match whole words, so `Al` does not match `Alice`.
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn mentioned_in(&self, text: &str) -> Result<Vec<Entity>> {
+        let text = text.to_lowercase();
+        Ok(self
+            .store
+            .entities()?
+            .into_iter()
+            .filter(|entity| mentions(&text, &entity.key()))
+            .collect())
+    }
+
+    /// The entities within `depth` relations of the named ones, following
+    /// relations in both directions, and the relations between them
+    ///
+    /// Unknown names are ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn neighborhood(&self, names: &[&str], depth: usize) -> Result<Subgraph> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut queue: VecDeque<(String, usize)> = VecDeque::new();
+        let mut subgraph = Subgraph::default();
+        for name in names {
+            let key = entity_key(name);
+            if seen.insert(key.clone()) {
+                queue.push_back((key, 0));
+            }
+        }
+
+        let mut relations = HashSet::new();
+        while let Some((key, distance)) = queue.pop_front() {
+            let Some(entity) = self.store.entity(&key)? else {
+                continue;
+            };
+            subgraph.entities.push(entity);
+            if distance == depth {
+                continue;
+            }
+            for relation in self.store.relations_of(&key)? {
+                let other = if relation.source == key {
+                    relation.target.clone()
+                } else {
+                    relation.source.clone()
+                };
+                if seen.insert(other.clone()) {
+                    queue.push_back((other, distance + 1));
+                }
+                relations.insert(relation);
+            }
+        }
+
+        let mut relations: Vec<Relation> = relations.into_iter().collect();
+        relations.sort();
+        subgraph.relations = relations
+            .into_iter()
+            .map(|relation| self.named(relation))
+            .collect::<Result<_>>()?;
+        Ok(subgraph)
+    }
+
+    /// The neighborhood of the entities `text` mentions
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn relevant_subgraph(&self, text: &str, depth: usize) -> Result<Subgraph> {
+        let mentioned = self.mentioned_in(text)?;
+        let names: Vec<&str> = mentioned
+            .iter()
+            .map(|entity| entity.name.as_str())
+            .collect();
+        self.neighborhood(&names, depth)
+    }
+
+    /// A system message with the facts relevant to `text`, to add to a
+    /// prompt, or `None` if `text` mentions no known entity
+    ///
+    /// # Errors
+    /// Returns an error if the store fails.
+    pub fn context_message(&self, text: &str, depth: usize) -> Result<Option<Message>> {
+        let subgraph = self.relevant_subgraph(text, depth)?;
+        if subgraph.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Message::system(subgraph.to_prompt())))
+    }
+
+    /// Replace the entity keys of a stored relation with entity names
+    fn named(&self, relation: Relation) -> Result<Relation> {
+        let name = |key: &str| -> Result<String> {
+            Ok(self
+                .store
+                .entity(key)?
+                .map_or_else(|| key.to_string(), |entity| entity.name))
+        };
+        Ok(Relation {
+            source: name(&relation.source)?,
+            relation: relation.relation,
+            target: name(&relation.target)?,
+        })
+    }
+}
+
+fn entity_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn relation_type(relation: &str) -> String {
+    relation
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
+}
+
+/// Whether lowercase `text` contains `name` as whole words
+fn mentions(text: &str, name: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric();
+    text.match_indices(name).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + name.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+fn sql_error(error: rusqlite::Error) -> AgentError {
+    AgentError::Memory(format!("Graph store: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team() -> GraphUpdate {
+        GraphUpdate {
+            entities: vec![
+                Entity::new("Alice", "Person").with_description("Backend engineer"),
+                Entity::new("Billing API", "service"),
+                Entity::new("Payments", "team"),
+            ],
+            relations: vec![
+                Relation::new("Alice", "owns", "Billing API"),
+                Relation::new("alice", "member of", "Payments"),
+                Relation::new("Bob", "manages", "Alice"),
+            ],
+        }
+    }
+
+    fn check_store(mut graph: KnowledgeGraph) {
+        graph.apply(&team()).unwrap();
+
+        let alice = graph.entity("ALICE").unwrap().unwrap();
+        assert_eq!(alice.kind, "person");
+        assert_eq!(graph.entity("bob").unwrap().unwrap().kind, UNKNOWN_KIND);
+
+        // Updates without a description or kind keep the known ones
+        graph.add_entity(&Entity::new("alice", "")).unwrap();
+        let alice = graph.entity("Alice").unwrap().unwrap();
+        assert_eq!(alice.description.as_deref(), Some("Backend engineer"));
+        assert_eq!(alice.kind, "person");
+
+        let relations = graph.relations_of("Alice").unwrap();
+        assert_eq!(
+            relations,
+            vec![
+                Relation::new("alice", "member_of", "Payments"),
+                Relation::new("alice", "owns", "Billing API"),
+                Relation::new("Bob", "manages", "alice"),
+            ]
+        );
+
+        assert!(graph.remove_entity("Bob").unwrap());
+        assert_eq!(graph.relations_of("Alice").unwrap().len(), 2);
+        assert!(!graph.remove_entity("Bob").unwrap());
+    }
+
+    #[test]
+    fn test_in_memory_store() {
+        check_store(KnowledgeGraph::in_memory());
+    }
+
+    #[test]
+    fn test_sqlite_store() {
+        check_store(KnowledgeGraph::new(SqliteGraphStore::in_memory().unwrap()));
+    }
+
+    #[test]
+    fn test_sqlite_store_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graph.db");
+        {
+            let mut graph = KnowledgeGraph::new(SqliteGraphStore::open(&path).unwrap());
+            graph.apply(&team()).unwrap();
+        }
+        let graph = KnowledgeGraph::new(SqliteGraphStore::open(&path).unwrap());
+        assert_eq!(graph.entities().unwrap().len(), 4);
+        assert_eq!(graph.relations_of("Payments").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_neighborhood_depth() {
+        let mut graph = KnowledgeGraph::in_memory();
+        graph.apply(&team()).unwrap();
+
+        let near = graph.neighborhood(&["Billing API"], 1).unwrap();
+        let names: Vec<_> = near.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Billing API", "Alice"]);
+        assert_eq!(
+            near.relations,
+            vec![Relation::new("Alice", "owns", "Billing API")]
+        );
+
+        let far = graph.neighborhood(&["Billing API", "nobody"], 2).unwrap();
+        assert_eq!(far.entities.len(), 4);
+        assert_eq!(far.relations.len(), 3);
+    }
+
+    #[test]
+    fn test_mentions_and_context_message() {
+        let mut graph = KnowledgeGraph::in_memory();
+        graph.apply(&team()).unwrap();
+        graph.add_entity(&Entity::new("Al", "person")).unwrap();
+
+        let mentioned = graph
+            .mentioned_in("Is alice around? The billing api is down.")
+            .unwrap();
+        let names: Vec<_> = mentioned.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Alice", "Billing API"]);
+
+        let message = graph
+            .context_message("Who owns the Billing API?", 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            message.content,
+            "Known facts:\n- Billing API (service)\n- Alice (person): Backend engineer\n\
+             - Alice owns Billing API"
+        );
+        assert!(
+            graph
+                .context_message("What's the weather?", 1)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -42,6 +42,8 @@ impl<T: MemoryStore> ConversationHistory<T> {
             role: Role::User,
             content,
             timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         };
         self.store.add_message(message);
     }
@@ -52,6 +54,8 @@ impl<T: MemoryStore> ConversationHistory<T> {
             role: Role::Assistant,
             content,
             timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         };
         self.store.add_message(message);
     }
@@ -62,6 +66,8 @@ impl<T: MemoryStore> ConversationHistory<T> {
             role: Role::System,
             content,
             timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         };
         self.store.add_message(message);
     }
@@ -186,7 +192,7 @@ mod tests {
         assert_eq!(within_budget.len(), 3);
         
         let small_budget = history.get_within_budget(20);
-        assert!(small_budget.len() > 0);
+        assert!(!small_budget.is_empty());
         assert!(small_budget.len() < 3);
     }
 }
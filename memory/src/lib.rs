@@ -7,6 +7,27 @@
 //! - `InMemoryStore` implementation using Vec for MVP
 //! - Token counting functionality using tiktoken-rs for OpenAI models
 //! - `ConversationHistory` wrapper with convenience methods
+//! - `ConversationMemory` for multi-turn history that truncates or summarizes
+//!   old turns to stay within a model's context window
+//! - `LruSessionStore` for bounding memory across many active sessions
+//! - `Compactor` for collapsing repeated content before it is sent to a model
+//! - `DeadLetterQueue` for keeping failed background runs to inspect and re-drive
+//! - `TextChunker` for splitting long documents into chunks within a token budget
+//! - `ConversationIndex` for keyword (SQLite FTS5) search across the
+//!   conversations in a `ConversationStore`, and semantic search over their
+//!   embeddings in a `VectorStore`
+//! - `Feedback` (thumbs up/down, comments, corrections) on indexed messages,
+//!   exportable as an evaluation or fine-tuning `FeedbackDataset`
+//! - `RetentionPolicy` and `RetentionJob` for deleting or anonymizing stored
+//!   messages once they are older than a per-user or per-session limit
+//! - `ConversationStore` for saving conversations to SQLite so agents can
+//!   resume sessions after a restart
+//! - `KnowledgeGraph` for entities and typed relations, in memory or SQLite,
+//!   with queries for the subgraph relevant to a prompt
+//! - `ContextAssembler` for sharing a model's context window between history,
+//!   retrieved chunks and tool outputs by configurable priorities
+//! - `VectorStore` for embeddings with cosine or dot-product top-k search and
+//!   metadata filters, in memory or persisted to a file
 //!
 //! # Examples
 //!
@@ -27,8 +48,41 @@ mod store;
 mod in_memory;
 mod token_counter;
 mod history;
+mod session;
+mod compaction;
+mod dead_letter;
+mod conversation;
+mod chunking;
+mod search;
+mod feedback;
+mod retention;
+mod persistence;
+mod graph;
+mod assembler;
+mod vectorstore;
 
 pub use store::MemoryStore;
 pub use in_memory::InMemoryStore;
 pub use token_counter::count_tokens;
 pub use history::ConversationHistory;
+pub use session::{LruSessionStore, SessionBackend};
+pub use compaction::{CompactionReport, Compactor, OMITTED_PLACEHOLDER};
+pub use dead_letter::{DeadLetter, DeadLetterQueue};
+pub use conversation::{ConversationMemory, SUMMARY_PREFIX, TokenEstimator};
+pub use chunking::TextChunker;
+pub use assembler::{
+    AssembledContext, ContextAllocation, ContextAssembler, ContextSource, RETRIEVED_PREFIX,
+    SourceBudget, TOOL_OUTPUTS_PREFIX, TurnContext,
+};
+pub use feedback::{Feedback, FeedbackDataset, FeedbackExample, MessageId, Rating};
+pub use search::{ConversationIndex, MessageMatch, SessionMatch};
+pub use graph::{
+    Entity, GraphStore, GraphUpdate, InMemoryGraphStore, KnowledgeGraph, Relation,
+    SqliteGraphStore, Subgraph, UNKNOWN_KIND,
+};
+pub use vectorstore::{MetadataFilter, Similarity, VectorMatch, VectorRecord, VectorStore};
+pub use persistence::{ConversationStore, StoredConversation, StoredConversationInfo};
+pub use retention::{
+    ANONYMIZED_PLACEHOLDER, RetentionAction, RetentionJob, RetentionPolicy, RetentionReport,
+    RetentionRule, RetentionStore,
+};
@@ -0,0 +1,685 @@
+//! Durable conversation storage.
+//!
+//! [`ConversationStore`] saves whole conversations to a SQLite database so
+//! an agent can pick a session back up after its process restarts. Each
+//! message is stored as JSON, keeping attachments and any fields added to
+//! [`Message`] later, next to the ID of the agent that owns the
+//! conversation and when it was started and last changed.
+//!
+//! Several processes can share one database file: it is opened in WAL mode
+//! so readers don't block the writer, and writers wait for each other
+//! instead of failing while another holds the lock.
+//!
+//! The store is a [`RetentionStore`], so a [`RetentionPolicy`](crate::RetentionPolicy)
+//! can delete or anonymize old transcripts, and a [`SessionBackend`], so an
+//! [`LruSessionStore`](crate::LruSessionStore) can flush evicted sessions to
+//! it and restore them later. A [`ConversationIndex`](crate::ConversationIndex)
+//! built over the store searches the saved messages.
+
+use agent_core::{AgentError, Message, Result, Role};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior, params};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{ANONYMIZED_PLACEHOLDER, RetentionStore, SessionBackend};
+
+// Each message's role, content and creation time are kept next to its JSON
+// so retention and search can query them
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    user_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS conversations_agent ON conversations (agent_id, updated_at);
+CREATE TABLE IF NOT EXISTS conversation_messages (
+    id INTEGER PRIMARY KEY,
+    conversation_id TEXT NOT NULL REFERENCES conversations (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    message TEXT NOT NULL,
+    UNIQUE (conversation_id, position)
+);
+";
+
+/// Agent that sessions flushed through [`SessionBackend`] are saved under,
+/// unless set with [`ConversationStore::with_agent_id`]
+const DEFAULT_AGENT_ID: &str = "default";
+
+/// How long a write waits for another connection to release the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A conversation loaded from a [`ConversationStore`]
+#[derive(Debug, Clone)]
+pub struct StoredConversation {
+    /// The conversation's ID
+    pub id: String,
+    /// ID of the agent the conversation belongs to
+    pub agent_id: String,
+    /// ID of the user the conversation belongs to, if assigned
+    pub user_id: Option<String>,
+    /// When the conversation was first saved
+    pub created_at: DateTime<Utc>,
+    /// When messages were last saved or appended
+    pub updated_at: DateTime<Utc>,
+    /// The messages, oldest first
+    pub messages: Vec<Message>,
+}
+
+/// A conversation listed by [`ConversationStore::list`], without its messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredConversationInfo {
+    /// The conversation's ID
+    pub id: String,
+    /// ID of the agent the conversation belongs to
+    pub agent_id: String,
+    /// ID of the user the conversation belongs to, if assigned
+    pub user_id: Option<String>,
+    /// When the conversation was first saved
+    pub created_at: DateTime<Utc>,
+    /// When messages were last saved or appended
+    pub updated_at: DateTime<Utc>,
+    /// Number of stored messages
+    pub message_count: usize,
+}
+
+/// Conversations persisted to SQLite
+///
+/// # Examples
+///
+/// ```
+/// use memory::ConversationStore;
+/// use agent_core::Message;
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let store = ConversationStore::in_memory()?;
+/// store.save("session-1", "support-bot", &[Message::user("My order is late")])?;
+/// store.append("session-1", "support-bot", &[Message::assistant("Let me check.")])?;
+///
+/// let conversation = store.load("session-1")?.unwrap();
+/// assert_eq!(conversation.agent_id, "support-bot");
+/// assert_eq!(conversation.messages.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ConversationStore {
+    connection: Connection,
+    agent_id: String,
+}
+
+impl ConversationStore {
+    /// Open or create a store in a SQLite database file
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened or initialized.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let connection = Connection::open(path).map_err(sql_error)?;
+        connection
+            .pragma_update(None, "journal_mode", "WAL")
+            .map_err(sql_error)?;
+        Self::init(connection)
+    }
+
+    /// Create a store that lives only as long as this value
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be initialized.
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn init(connection: Connection) -> Result<Self> {
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(sql_error)?;
+        connection
+            .pragma_update(None, "foreign_keys", true)
+            .map_err(sql_error)?;
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        Ok(Self {
+            connection,
+            agent_id: DEFAULT_AGENT_ID.to_string(),
+        })
+    }
+
+    /// Set the agent that sessions flushed through [`SessionBackend`] are
+    /// saved under
+    pub fn with_agent_id(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = agent_id.into();
+        self
+    }
+
+    /// Agent that sessions flushed through [`SessionBackend`] are saved under
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// The underlying connection, for indexes kept in the same database
+    pub(crate) fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Record the user a conversation belongs to, so retention rules for
+    /// the user apply to it
+    ///
+    /// Returns whether the conversation exists.
+    ///
+    /// # Errors
+    /// Returns an error if the database write fails.
+    pub fn assign_user(&self, id: &str, user_id: &str) -> Result<bool> {
+        let updated = self
+            .connection
+            .execute(
+                "UPDATE conversations SET user_id = ?2 WHERE id = ?1",
+                params![id, user_id],
+            )
+            .map_err(sql_error)?;
+        Ok(updated > 0)
+    }
+
+    /// Save a conversation, replacing any messages previously stored for it
+    ///
+    /// The conversation keeps its original creation time if it already
+    /// exists, and is reassigned to `agent_id`.
+    ///
+    /// # Errors
+    /// Returns an error if a message cannot be serialized or the database
+    /// write fails.
+    pub fn save(&self, id: &str, agent_id: &str, messages: &[Message]) -> Result<()> {
+        let transaction = self.write()?;
+        touch(&transaction, id, agent_id)?;
+        transaction
+            .execute(
+                "DELETE FROM conversation_messages WHERE conversation_id = ?1",
+                [id],
+            )
+            .map_err(sql_error)?;
+        insert_messages(&transaction, id, 0, messages)?;
+        transaction.commit().map_err(sql_error)
+    }
+
+    /// Add messages to the end of a conversation, creating it if needed
+    ///
+    /// Appends from several processes are serialized, so none are lost.
+    ///
+    /// # Errors
+    /// Returns an error if a message cannot be serialized or the database
+    /// write fails.
+    pub fn append(&self, id: &str, agent_id: &str, messages: &[Message]) -> Result<()> {
+        let transaction = self.write()?;
+        touch(&transaction, id, agent_id)?;
+        let next: i64 = transaction
+            .query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM conversation_messages
+                 WHERE conversation_id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .map_err(sql_error)?;
+        insert_messages(&transaction, id, next as usize, messages)?;
+        transaction.commit().map_err(sql_error)
+    }
+
+    /// Load a conversation with all its messages
+    ///
+    /// Returns `None` if no conversation with this ID was saved.
+    ///
+    /// # Errors
+    /// Returns an error if the database read fails or holds invalid data.
+    pub fn load(&self, id: &str) -> Result<Option<StoredConversation>> {
+        let header = self
+            .connection
+            .query_row(
+                "SELECT agent_id, user_id, created_at, updated_at FROM conversations
+                 WHERE id = ?1",
+                [id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(sql_error)?;
+        let Some((agent_id, user_id, created_at, updated_at)) = header else {
+            return Ok(None);
+        };
+
+        let mut select = self
+            .connection
+            .prepare(
+                "SELECT message FROM conversation_messages
+                 WHERE conversation_id = ?1 ORDER BY position",
+            )
+            .map_err(sql_error)?;
+        let messages = select
+            .query_map([id], |row| row.get::<_, String>(0))
+            .map_err(sql_error)?
+            .map(|json| {
+                let json = json.map_err(sql_error)?;
+                serde_json::from_str(&json).map_err(|e| {
+                    AgentError::Memory(format!("Invalid message in conversation '{}': {}", id, e))
+                })
+            })
+            .collect::<Result<Vec<Message>>>()?;
+
+        Ok(Some(StoredConversation {
+            id: id.to_string(),
+            agent_id,
+            user_id,
+            created_at: parse_timestamp(&created_at)?,
+            updated_at: parse_timestamp(&updated_at)?,
+            messages,
+        }))
+    }
+
+    /// Conversations of an agent, or of all agents if `agent_id` is `None`,
+    /// most recently updated first
+    ///
+    /// # Errors
+    /// Returns an error if the database read fails or holds invalid data.
+    pub fn list(&self, agent_id: Option<&str>) -> Result<Vec<StoredConversationInfo>> {
+        let mut select = self
+            .connection
+            .prepare(
+                "SELECT c.id, c.agent_id, c.user_id, c.created_at, c.updated_at,
+                        (SELECT COUNT(*) FROM conversation_messages m
+                         WHERE m.conversation_id = c.id)
+                 FROM conversations c
+                 WHERE ?1 IS NULL OR c.agent_id = ?1
+                 ORDER BY c.updated_at DESC, c.id",
+            )
+            .map_err(sql_error)?;
+        let rows = select
+            .query_map([agent_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        rows.map(|row| {
+            let (id, agent_id, user_id, created_at, updated_at, message_count) =
+                row.map_err(sql_error)?;
+            Ok(StoredConversationInfo {
+                id,
+                agent_id,
+                user_id,
+                created_at: parse_timestamp(&created_at)?,
+                updated_at: parse_timestamp(&updated_at)?,
+                message_count: message_count as usize,
+            })
+        })
+        .collect()
+    }
+
+    /// Delete a conversation and its messages
+    ///
+    /// Returns whether the conversation existed.
+    ///
+    /// # Errors
+    /// Returns an error if the database write fails.
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .connection
+            .execute("DELETE FROM conversations WHERE id = ?1", [id])
+            .map_err(sql_error)?;
+        Ok(deleted > 0)
+    }
+
+    /// Start a transaction that takes the write lock immediately, so a
+    /// concurrent writer can't change the conversation between our read of
+    /// it and our write
+    fn write(&self) -> Result<Transaction<'_>> {
+        Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)
+            .map_err(sql_error)
+    }
+}
+
+/// Retention rules for a conversation's user apply once the user is set
+/// with [`ConversationStore::assign_user`]. A conversation whose messages
+/// have all been deleted is removed.
+impl RetentionStore for ConversationStore {
+    fn sessions(&self) -> Result<Vec<(String, Option<String>)>> {
+        let mut select = self
+            .connection
+            .prepare("SELECT id, user_id FROM conversations")
+            .map_err(sql_error)?;
+        let rows = select
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(sql_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(sql_error)
+    }
+
+    fn delete_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+        let transaction = self.write()?;
+        let deleted = transaction
+            .execute(
+                "DELETE FROM conversation_messages
+                 WHERE conversation_id = ?1 AND created_at < ?2",
+                params![session_id, timestamp(cutoff)],
+            )
+            .map_err(sql_error)?;
+        transaction
+            .execute(
+                "DELETE FROM conversations WHERE id = ?1 AND NOT EXISTS (
+                     SELECT 1 FROM conversation_messages WHERE conversation_id = ?1
+                 )",
+                [session_id],
+            )
+            .map_err(sql_error)?;
+        transaction.commit().map_err(sql_error)?;
+        Ok(deleted)
+    }
+
+    fn anonymize_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+        let transaction = self.write()?;
+        let expired = {
+            let mut select = transaction
+                .prepare(
+                    "SELECT id, message FROM conversation_messages
+                     WHERE conversation_id = ?1 AND created_at < ?2",
+                )
+                .map_err(sql_error)?;
+            select
+                .query_map(params![session_id, timestamp(cutoff)], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(sql_error)?
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(sql_error)?
+        };
+
+        let mut anonymized = 0;
+        for (row_id, json) in expired {
+            let mut message: Message = serde_json::from_str(&json)?;
+            if message.content == ANONYMIZED_PLACEHOLDER && !message.is_multimodal() {
+                continue;
+            }
+            message.content = ANONYMIZED_PLACEHOLDER.to_string();
+            message.attachments.clear();
+            message.images.clear();
+            transaction
+                .execute(
+                    "UPDATE conversation_messages SET content = ?2, message = ?3 WHERE id = ?1",
+                    params![row_id, message.content, serde_json::to_string(&message)?],
+                )
+                .map_err(sql_error)?;
+            anonymized += 1;
+        }
+
+        // The user stays linked while newer messages still fall under their rules
+        transaction
+            .execute(
+                "UPDATE conversations SET user_id = NULL WHERE id = ?1 AND NOT EXISTS (
+                     SELECT 1 FROM conversation_messages
+                     WHERE conversation_id = ?1 AND created_at >= ?2
+                 )",
+                params![session_id, timestamp(cutoff)],
+            )
+            .map_err(sql_error)?;
+        transaction.commit().map_err(sql_error)?;
+        Ok(anonymized)
+    }
+}
+
+/// Flushed sessions are saved under the store's agent ID (see
+/// [`with_agent_id`](ConversationStore::with_agent_id)), replacing the
+/// stored conversation, and stay stored once restored. Messages the session
+/// store trimmed to fit its token budget are therefore dropped here too.
+impl SessionBackend for ConversationStore {
+    fn save(&mut self, session_id: &str, messages: &[Message]) -> Result<()> {
+        ConversationStore::save(self, session_id, &self.agent_id, messages)
+    }
+
+    fn load(&mut self, session_id: &str) -> Result<Option<Vec<Message>>> {
+        Ok(ConversationStore::load(self, session_id)?.map(|conversation| conversation.messages))
+    }
+}
+
+/// Create the conversation if it is new and mark it as updated now
+fn touch(transaction: &Transaction<'_>, id: &str, agent_id: &str) -> Result<()> {
+    let now = timestamp(Utc::now());
+    transaction
+        .execute(
+            "INSERT INTO conversations (id, agent_id, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT (id) DO UPDATE SET agent_id = ?2, updated_at = ?3",
+            params![id, agent_id, now],
+        )
+        .map_err(sql_error)?;
+    Ok(())
+}
+
+fn insert_messages(
+    transaction: &Transaction<'_>,
+    id: &str,
+    first_position: usize,
+    messages: &[Message],
+) -> Result<()> {
+    let mut insert = transaction
+        .prepare(
+            "INSERT INTO conversation_messages
+                 (conversation_id, position, role, content, created_at, message)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .map_err(sql_error)?;
+    for (offset, message) in messages.iter().enumerate() {
+        let json = serde_json::to_string(message)?;
+        insert
+            .execute(params![
+                id,
+                (first_position + offset) as i64,
+                role_name(&message.role),
+                message.content,
+                timestamp(message.timestamp),
+                json
+            ])
+            .map_err(sql_error)?;
+    }
+    Ok(())
+}
+
+/// A timestamp as stored in the database; the fixed format sorts as text
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Parse a timestamp stored by [`timestamp`]
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| {
+            AgentError::Memory(format!(
+                "Invalid timestamp '{}' in conversation store: {}",
+                text, e
+            ))
+        })
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn sql_error(error: rusqlite::Error) -> AgentError {
+    AgentError::Memory(format!("Conversation store: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LruSessionStore, RetentionPolicy, RetentionRule};
+    use agent_core::FileRef;
+    use std::thread;
+
+    fn aged(mut message: Message, days: i64) -> Message {
+        message.timestamp = Utc::now() - chrono::Duration::days(days);
+        message
+    }
+
+    #[test]
+    fn test_conversation_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conversations.db");
+
+        let mut question = Message::user("What is in this picture?");
+        question
+            .attachments
+            .push(FileRef::new("file-1", "image/png"));
+        {
+            let store = ConversationStore::open(&path).unwrap();
+            store
+                .save(
+                    "s-1",
+                    "vision",
+                    &[Message::system("Describe images"), question],
+                )
+                .unwrap();
+        }
+
+        let store = ConversationStore::open(&path).unwrap();
+        let conversation = store.load("s-1").unwrap().unwrap();
+        assert_eq!(conversation.agent_id, "vision");
+        assert_eq!(conversation.created_at, conversation.updated_at);
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[0].role, Role::System);
+        assert_eq!(conversation.messages[1].attachments[0].id, "file-1");
+        assert!(store.load("s-2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_save_replaces_and_append_extends() {
+        let store = ConversationStore::in_memory().unwrap();
+        store
+            .save("a", "bot", &[Message::user("one"), Message::user("two")])
+            .unwrap();
+        store.save("a", "bot", &[Message::user("first")]).unwrap();
+        store
+            .append("a", "bot", &[Message::assistant("second")])
+            .unwrap();
+        store.append("b", "other", &[Message::user("hi")]).unwrap();
+
+        let conversation = store.load("a").unwrap().unwrap();
+        let contents: Vec<_> = conversation
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["first", "second"]);
+        assert!(conversation.updated_at > conversation.created_at);
+
+        let all = store.list(None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id, "b");
+        let mine = store.list(Some("bot")).unwrap();
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].message_count, 2);
+
+        assert!(store.delete("a").unwrap());
+        assert!(!store.delete("a").unwrap());
+        assert!(store.load("a").unwrap().is_none());
+        assert_eq!(store.list(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_appends_from_separate_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conversations.db");
+        ConversationStore::open(&path).unwrap();
+
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let store = ConversationStore::open(&path).unwrap();
+                    for turn in 0..10 {
+                        let message = Message::user(format!("{}-{}", writer, turn));
+                        store.append("shared", "bot", &[message]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = ConversationStore::open(&path).unwrap();
+        assert_eq!(store.load("shared").unwrap().unwrap().messages.len(), 40);
+    }
+
+    #[test]
+    fn test_retention_policy_applies_per_user() {
+        let mut store = ConversationStore::in_memory().unwrap();
+        let mut photo = aged(Message::user("my photo"), 40);
+        photo.attachments.push(FileRef::new("file-1", "image/png"));
+        store
+            .save("private", "bot", &[photo, Message::user("recent")])
+            .unwrap();
+        store
+            .save("shared", "bot", &[aged(Message::user("old"), 40)])
+            .unwrap();
+        assert!(store.assign_user("private", "alice").unwrap());
+        assert!(!store.assign_user("missing", "alice").unwrap());
+
+        let policy = RetentionPolicy::new()
+            .with_default(RetentionRule::delete_after_days(30))
+            .with_user_rule("alice", RetentionRule::anonymize_after_days(30));
+        let report = policy.enforce(&mut store, Utc::now()).unwrap();
+        assert_eq!(report.deleted, 1);
+        assert_eq!(report.anonymized, 1);
+
+        // The only message of "shared" was deleted, so the conversation is gone
+        assert!(store.load("shared").unwrap().is_none());
+        let private = store.load("private").unwrap().unwrap();
+        assert_eq!(private.user_id.as_deref(), Some("alice"));
+        assert_eq!(private.messages[0].content, ANONYMIZED_PLACEHOLDER);
+        assert!(private.messages[0].attachments.is_empty());
+        assert_eq!(private.messages[1].content, "recent");
+
+        let report = policy.enforce(&mut store, Utc::now()).unwrap();
+        assert_eq!(report.anonymized, 0);
+        let report = policy
+            .enforce(&mut store, Utc::now() + chrono::Duration::days(31))
+            .unwrap();
+        assert_eq!(report.anonymized, 1);
+        assert_eq!(store.load("private").unwrap().unwrap().user_id, None);
+    }
+
+    #[test]
+    fn test_session_store_flushes_to_conversation_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conversations.db");
+        let backend = ConversationStore::open(&path).unwrap().with_agent_id("support");
+        let mut sessions = LruSessionStore::new(1, 10_000).with_backend(Box::new(backend));
+
+        sessions.add_message("a", Message::user("hello")).unwrap();
+        sessions.add_message("b", Message::user("other")).unwrap();
+
+        let store = ConversationStore::open(&path).unwrap();
+        let flushed = store.load("a").unwrap().unwrap();
+        assert_eq!(flushed.agent_id, "support");
+        assert_eq!(flushed.messages[0].content, "hello");
+
+        // Restoring a session keeps it stored
+        sessions.add_message("a", Message::assistant("hi")).unwrap();
+        let restored = sessions.get_recent("a", 10).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(store.load("a").unwrap().is_some());
+        assert_eq!(store.load("b").unwrap().unwrap().messages.len(), 1);
+    }
+}
@@ -0,0 +1,422 @@
+//! Retention policies for stored conversations.
+//!
+//! Compliance rules such as GDPR's storage limitation require transcripts
+//! and memories to be removed once they are no longer needed. A
+//! [`RetentionPolicy`] decides how long each session's messages are kept,
+//! by session, by user or by default, and whether expired messages are
+//! deleted or anonymized. Stores implement [`RetentionStore`] so the policy
+//! can be enforced on them, once with [`RetentionPolicy::enforce`] or
+//! periodically by a [`RetentionJob`] running in the background.
+
+use agent_core::{AgentError, Result};
+use chrono::{DateTime, Duration, Utc};
+use config::RetentionConfig;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+pub use config::RetentionAction;
+
+/// Content of anonymized messages
+pub const ANONYMIZED_PLACEHOLDER: &str = "[removed by retention policy]";
+
+/// Default time between sweeps of a [`RetentionJob`]
+const DEFAULT_SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// How long messages are kept and what happens to them afterwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionRule {
+    /// Age after which a message expires
+    pub max_age: Duration,
+    /// What happens to expired messages
+    pub action: RetentionAction,
+}
+
+impl RetentionRule {
+    /// Delete messages older than `days` days
+    pub fn delete_after_days(days: u32) -> Self {
+        Self {
+            max_age: Duration::days(days.into()),
+            action: RetentionAction::Delete,
+        }
+    }
+
+    /// Anonymize messages older than `days` days
+    pub fn anonymize_after_days(days: u32) -> Self {
+        Self {
+            max_age: Duration::days(days.into()),
+            action: RetentionAction::Anonymize,
+        }
+    }
+}
+
+/// A store whose messages can expire
+pub trait RetentionStore {
+    /// IDs of the stored sessions, each with the user it belongs to, if
+    /// known
+    fn sessions(&self) -> Result<Vec<(String, Option<String>)>>;
+
+    /// Delete the messages of a session created before `cutoff`, returning
+    /// how many were deleted
+    fn delete_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize>;
+
+    /// Replace the content of a session's messages created before
+    /// `cutoff` with [`ANONYMIZED_PLACEHOLDER`] and drop anything linking
+    /// them to the user, returning how many were changed
+    ///
+    /// Messages that were already anonymized are not counted again.
+    fn anonymize_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize>;
+}
+
+/// Messages changed by enforcing a retention policy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// Messages deleted
+    pub deleted: usize,
+    /// Messages anonymized
+    pub anonymized: usize,
+}
+
+/// Which rule applies to each session
+///
+/// A rule for the session wins over a rule for its user, which wins over
+/// the default rule. Sessions no rule applies to are kept forever.
+///
+/// # Examples
+///
+/// ```
+/// use memory::{LruSessionStore, RetentionPolicy, RetentionRule};
+/// use agent_core::Message;
+/// use chrono::{Duration, Utc};
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let mut store = LruSessionStore::new(10, 4_000);
+/// store.add_message("support-1", Message::user("My card number is ..."))?;
+///
+/// let policy = RetentionPolicy::new()
+///     .with_default(RetentionRule::anonymize_after_days(90))
+///     .with_session_rule("audit-7", RetentionRule::delete_after_days(365));
+///
+/// let later = Utc::now() + Duration::days(91);
+/// let report = policy.enforce(&mut store, later)?;
+/// assert_eq!(report.anonymized, 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    default: Option<RetentionRule>,
+    users: HashMap<String, RetentionRule>,
+    sessions: HashMap<String, RetentionRule>,
+}
+
+impl RetentionPolicy {
+    /// Create a policy that keeps everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy from the `memory.retention` configuration
+    pub fn from_config(config: &RetentionConfig) -> Self {
+        let rule = |max_age_days: u32, action| RetentionRule {
+            max_age: Duration::days(max_age_days.into()),
+            action,
+        };
+        let rules = |rules: &HashMap<String, config::RetentionRuleConfig>| {
+            rules
+                .iter()
+                .map(|(id, config)| (id.clone(), rule(config.max_age_days, config.action)))
+                .collect()
+        };
+        Self {
+            default: config.max_age_days.map(|days| rule(days, config.action)),
+            users: rules(&config.users),
+            sessions: rules(&config.sessions),
+        }
+    }
+
+    /// Set the rule for sessions without a user or session rule
+    pub fn with_default(mut self, rule: RetentionRule) -> Self {
+        self.default = Some(rule);
+        self
+    }
+
+    /// Set the rule for the sessions of a user
+    pub fn with_user_rule(mut self, user_id: impl Into<String>, rule: RetentionRule) -> Self {
+        self.users.insert(user_id.into(), rule);
+        self
+    }
+
+    /// Set the rule for a session
+    pub fn with_session_rule(mut self, session_id: impl Into<String>, rule: RetentionRule) -> Self {
+        self.sessions.insert(session_id.into(), rule);
+        self
+    }
+
+    /// The rule that applies to a session, if any
+    pub fn rule_for(&self, session_id: &str, user_id: Option<&str>) -> Option<&RetentionRule> {
+        self.sessions
+            .get(session_id)
+            .or_else(|| user_id.and_then(|user_id| self.users.get(user_id)))
+            .or(self.default.as_ref())
+    }
+
+    /// Delete or anonymize the messages in `store` that have expired at
+    /// `now`
+    ///
+    /// # Errors
+    /// Returns the first error from the store; sessions handled before it
+    /// stay changed.
+    pub fn enforce<S: RetentionStore + ?Sized>(
+        &self,
+        store: &mut S,
+        now: DateTime<Utc>,
+    ) -> Result<RetentionReport> {
+        let mut report = RetentionReport::default();
+        for (session_id, user_id) in store.sessions()? {
+            let Some(rule) = self.rule_for(&session_id, user_id.as_deref()) else {
+                continue;
+            };
+            let cutoff = now - rule.max_age;
+            match rule.action {
+                RetentionAction::Delete => {
+                    report.deleted += store.delete_before(&session_id, cutoff)?;
+                }
+                RetentionAction::Anonymize => {
+                    report.anonymized += store.anonymize_before(&session_id, cutoff)?;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Background task that enforces a retention policy at a fixed interval
+///
+/// # Examples
+///
+/// ```no_run
+/// use memory::{ConversationIndex, RetentionJob, RetentionPolicy, RetentionRule};
+/// use std::sync::{Arc, Mutex};
+///
+/// # async fn example() -> agent_core::Result<()> {
+/// let index = Arc::new(Mutex::new(ConversationIndex::open("conversations.db")?));
+/// let policy = RetentionPolicy::new().with_default(RetentionRule::delete_after_days(30));
+///
+/// let job = RetentionJob::new(index.clone(), policy).spawn();
+/// # Ok(())
+/// # }
+/// ```
+pub struct RetentionJob<S> {
+    store: Arc<Mutex<S>>,
+    policy: RetentionPolicy,
+    interval: std::time::Duration,
+}
+
+impl<S: RetentionStore + Send + 'static> RetentionJob<S> {
+    /// Create a job that sweeps `store` hourly
+    pub fn new(store: Arc<Mutex<S>>, policy: RetentionPolicy) -> Self {
+        Self {
+            store,
+            policy,
+            interval: DEFAULT_SWEEP_INTERVAL,
+        }
+    }
+
+    /// Create a job from the `memory.retention` configuration
+    pub fn from_config(store: Arc<Mutex<S>>, config: &RetentionConfig) -> Self {
+        let job = Self::new(store, RetentionPolicy::from_config(config));
+        match config.sweep_interval_secs {
+            Some(secs) => job.with_interval(std::time::Duration::from_secs(secs)),
+            None => job,
+        }
+    }
+
+    /// Set the time between sweeps
+    pub fn with_interval(mut self, interval: std::time::Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Enforce the policy once, now
+    ///
+    /// # Errors
+    /// Returns an error if the store is poisoned or fails.
+    pub fn sweep(&self) -> Result<RetentionReport> {
+        let mut store = self
+            .store
+            .lock()
+            .map_err(|_| AgentError::Memory("Retention store lock was poisoned".to_string()))?;
+        self.policy.enforce(&mut *store, Utc::now())
+    }
+
+    /// Run the job on the current Tokio runtime, sweeping immediately and
+    /// then once per interval
+    ///
+    /// The task runs until it is aborted, or until a sweep fails, in which
+    /// case it ends with that error.
+    pub fn spawn(self) -> JoinHandle<Result<()>> {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(self.interval);
+            loop {
+                ticks.tick().await;
+                self.sweep()?;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::RetentionRuleConfig;
+
+    /// Timestamp and content of a stored message
+    type Entry = (DateTime<Utc>, String);
+
+    /// Messages per session, with an optional user
+    #[derive(Default)]
+    struct Store {
+        sessions: Vec<(String, Option<String>, Vec<Entry>)>,
+    }
+
+    impl RetentionStore for Store {
+        fn sessions(&self) -> Result<Vec<(String, Option<String>)>> {
+            Ok(self
+                .sessions
+                .iter()
+                .map(|(id, user, _)| (id.clone(), user.clone()))
+                .collect())
+        }
+
+        fn delete_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+            let (_, _, messages) = self
+                .sessions
+                .iter_mut()
+                .find(|s| s.0 == session_id)
+                .unwrap();
+            let before = messages.len();
+            messages.retain(|(time, _)| *time >= cutoff);
+            Ok(before - messages.len())
+        }
+
+        fn anonymize_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+            let (_, _, messages) = self
+                .sessions
+                .iter_mut()
+                .find(|s| s.0 == session_id)
+                .unwrap();
+            let mut changed = 0;
+            for (time, content) in messages.iter_mut() {
+                if *time < cutoff && content != ANONYMIZED_PLACEHOLDER {
+                    *content = ANONYMIZED_PLACEHOLDER.to_string();
+                    changed += 1;
+                }
+            }
+            Ok(changed)
+        }
+    }
+
+    #[test]
+    fn test_rules_are_chosen_by_session_then_user() {
+        let policy = RetentionPolicy::new()
+            .with_default(RetentionRule::anonymize_after_days(90))
+            .with_user_rule("u-1", RetentionRule::delete_after_days(30))
+            .with_session_rule("s-1", RetentionRule::delete_after_days(365));
+
+        let rule = |session, user| policy.rule_for(session, user).unwrap().max_age.num_days();
+        assert_eq!(rule("s-1", Some("u-1")), 365);
+        assert_eq!(rule("s-2", Some("u-1")), 30);
+        assert_eq!(rule("s-2", Some("u-2")), 90);
+        assert_eq!(rule("s-2", None), 90);
+        assert!(RetentionPolicy::new().rule_for("s-1", None).is_none());
+    }
+
+    #[test]
+    fn test_enforce_deletes_and_anonymizes_expired_messages() {
+        let now = Utc::now();
+        let days_ago = |days| now - Duration::days(days);
+        let mut store = Store {
+            sessions: vec![
+                (
+                    "kept".to_string(),
+                    None,
+                    vec![(days_ago(400), "old".to_string())],
+                ),
+                (
+                    "user-session".to_string(),
+                    Some("u-1".to_string()),
+                    vec![
+                        (days_ago(40), "old".to_string()),
+                        (days_ago(1), "new".to_string()),
+                    ],
+                ),
+                (
+                    "default".to_string(),
+                    None,
+                    vec![
+                        (days_ago(100), "old".to_string()),
+                        (days_ago(95), "old".to_string()),
+                    ],
+                ),
+            ],
+        };
+
+        let mut config = RetentionConfig {
+            max_age_days: Some(90),
+            action: RetentionAction::Anonymize,
+            ..RetentionConfig::default()
+        };
+        config.users.insert(
+            "u-1".to_string(),
+            RetentionRuleConfig {
+                max_age_days: 30,
+                action: RetentionAction::Delete,
+            },
+        );
+        config.sessions.insert(
+            "kept".to_string(),
+            RetentionRuleConfig {
+                max_age_days: 1_000,
+                action: RetentionAction::Delete,
+            },
+        );
+        let policy = RetentionPolicy::from_config(&config);
+
+        let report = policy.enforce(&mut store, now).unwrap();
+        assert_eq!(
+            report,
+            RetentionReport {
+                deleted: 1,
+                anonymized: 2,
+            }
+        );
+        assert_eq!(store.sessions[0].2.len(), 1);
+        assert_eq!(store.sessions[1].2, vec![(days_ago(1), "new".to_string())]);
+
+        // Anonymized messages are not counted twice
+        let report = policy.enforce(&mut store, now).unwrap();
+        assert_eq!(report, RetentionReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_job_sweeps_in_background() {
+        let store = Arc::new(Mutex::new(Store {
+            sessions: vec![(
+                "s-1".to_string(),
+                None,
+                vec![(Utc::now() - Duration::days(10), "old".to_string())],
+            )],
+        }));
+        let policy = RetentionPolicy::new().with_default(RetentionRule::delete_after_days(7));
+
+        let job = RetentionJob::new(store.clone(), policy)
+            .with_interval(std::time::Duration::from_secs(3600))
+            .spawn();
+        tokio::task::yield_now().await;
+        while !store.lock().unwrap().sessions[0].2.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        job.abort();
+    }
+}
@@ -0,0 +1,962 @@
+//! Search across persisted conversations.
+//!
+//! [`ConversationIndex`] searches the conversations saved in a
+//! [`ConversationStore`] two ways: keyword search through an FTS5
+//! full-text index, ranked by BM25, and semantic search over message
+//! embeddings kept in a [`VectorStore`]. Messages are stored once, by the
+//! conversation store; triggers in the same database keep the full-text
+//! index in step with them, and embeddings of messages that have since
+//! changed or gone are ignored. Embeddings are computed by the caller with
+//! whatever model they use, so this crate stays independent of any
+//! provider. Results are grouped by session, with the matching messages in
+//! each.
+//!
+//! User [`Feedback`] on assistant messages is stored in the same database
+//! and exported with its conversation as a [`FeedbackDataset`].
+//!
+//! The index is a [`RetentionStore`], so old transcripts can be deleted or
+//! anonymized by a [`RetentionPolicy`](crate::RetentionPolicy); feedback and
+//! embeddings go with them.
+
+use agent_core::{AgentError, Message, Result, Role};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{OptionalExtension, params};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    ANONYMIZED_PLACEHOLDER, ConversationStore, Feedback, FeedbackDataset, FeedbackExample,
+    MessageId, Rating, RetentionStore, VectorRecord, VectorStore,
+};
+
+const SCHEMA: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS conversation_messages_fts USING fts5(
+    content, content='conversation_messages', content_rowid='id'
+);
+CREATE TRIGGER IF NOT EXISTS conversation_messages_ai
+AFTER INSERT ON conversation_messages BEGIN
+    INSERT INTO conversation_messages_fts (rowid, content) VALUES (new.id, new.content);
+END;
+CREATE TRIGGER IF NOT EXISTS conversation_messages_ad
+AFTER DELETE ON conversation_messages BEGIN
+    INSERT INTO conversation_messages_fts (conversation_messages_fts, rowid, content)
+    VALUES ('delete', old.id, old.content);
+END;
+CREATE TRIGGER IF NOT EXISTS conversation_messages_au
+AFTER UPDATE OF content ON conversation_messages BEGIN
+    INSERT INTO conversation_messages_fts (conversation_messages_fts, rowid, content)
+    VALUES ('delete', old.id, old.content);
+    INSERT INTO conversation_messages_fts (rowid, content) VALUES (new.id, new.content);
+END;
+CREATE TABLE IF NOT EXISTS feedback (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    rating TEXT,
+    comment TEXT,
+    correction TEXT,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS feedback_message ON feedback (session_id, position);
+";
+
+/// Tokens of context around each keyword match in a snippet
+const SNIPPET_TOKENS: usize = 12;
+
+/// A message that matched a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageMatch {
+    /// Index of the message within its session
+    pub position: usize,
+    /// Who sent the message
+    pub role: Role,
+    /// The matching part of the message, with keyword matches in `[...]`
+    ///
+    /// Semantic matches have no highlighted terms and return the whole
+    /// message.
+    pub snippet: String,
+    /// How well the message matched; higher is better
+    pub score: f32,
+}
+
+/// A session with at least one matching message
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionMatch {
+    /// The session's ID
+    pub session_id: String,
+    /// Score of the best matching message
+    pub score: f32,
+    /// Matching messages, best first
+    pub messages: Vec<MessageMatch>,
+}
+
+/// Full-text and semantic search over the conversations in a
+/// [`ConversationStore`]
+///
+/// # Examples
+///
+/// ```
+/// use memory::ConversationIndex;
+/// use agent_core::Message;
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let mut index = ConversationIndex::in_memory()?;
+/// index.index_session(
+///     "trip-planning",
+///     &[
+///         Message::user("Which trains go from Paris to Lyon?"),
+///         Message::assistant("The TGV leaves Gare de Lyon every hour."),
+///     ],
+/// )?;
+///
+/// let sessions = index.search_keyword("lyon tgv", 10)?;
+/// assert_eq!(sessions[0].session_id, "trip-planning");
+/// # Ok(())
+/// # }
+/// ```
+pub struct ConversationIndex {
+    store: ConversationStore,
+    vectors: VectorStore,
+}
+
+impl ConversationIndex {
+    /// Open or create an index over a [`ConversationStore`] in a SQLite
+    /// database file
+    ///
+    /// Embeddings are kept in memory unless a persisted store is set with
+    /// [`with_vectors`](Self::with_vectors).
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened or initialized.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(ConversationStore::open(path)?)
+    }
+
+    /// Create an index that lives only as long as this value
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be initialized.
+    pub fn in_memory() -> Result<Self> {
+        Self::new(ConversationStore::in_memory()?)
+    }
+
+    /// Index the conversations in `store`, including those saved before
+    ///
+    /// Conversations saved to the same database later, through this index
+    /// or not, are indexed as they are saved.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be initialized.
+    pub fn new(store: ConversationStore) -> Result<Self> {
+        let connection = store.connection();
+        let indexed: bool = connection
+            .query_row(
+                "SELECT EXISTS (
+                     SELECT 1 FROM sqlite_master WHERE name = 'conversation_messages_fts'
+                 )",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(sql_error)?;
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        if !indexed {
+            connection
+                .execute(
+                    "INSERT INTO conversation_messages_fts (conversation_messages_fts)
+                     VALUES ('rebuild')",
+                    [],
+                )
+                .map_err(sql_error)?;
+        }
+        Ok(Self {
+            store,
+            vectors: VectorStore::new(),
+        })
+    }
+
+    /// Keep message embeddings in `vectors`, such as a store opened with
+    /// [`VectorStore::open`] so they outlive the process
+    ///
+    /// The vector store should hold nothing but this index's embeddings.
+    pub fn with_vectors(mut self, vectors: VectorStore) -> Self {
+        self.vectors = vectors;
+        self
+    }
+
+    /// The store holding the indexed conversations
+    pub fn store(&self) -> &ConversationStore {
+        &self.store
+    }
+
+    /// Save the messages of a session, replacing any previously saved
+    /// messages and the embeddings of those that changed
+    ///
+    /// The session is saved under the store's
+    /// [`agent_id`](ConversationStore::agent_id).
+    ///
+    /// # Errors
+    /// Returns an error if the database write fails.
+    pub fn index_session(&mut self, session_id: &str, messages: &[Message]) -> Result<()> {
+        self.store.save(session_id, self.store.agent_id(), messages)?;
+        self.prune_embeddings(session_id)
+    }
+
+    /// Save the messages of a user's session, replacing any previously
+    /// saved messages and the embeddings of those that changed
+    ///
+    /// The user ID lets retention rules for the user apply to the session.
+    ///
+    /// # Errors
+    /// Returns an error if the database write fails.
+    pub fn index_user_session(
+        &mut self,
+        user_id: &str,
+        session_id: &str,
+        messages: &[Message],
+    ) -> Result<()> {
+        self.store.save(session_id, self.store.agent_id(), messages)?;
+        self.store.assign_user(session_id, user_id)?;
+        self.prune_embeddings(session_id)
+    }
+
+    /// Attach embeddings to the stored messages of a session, one per
+    /// message in order, for [`search_semantic`](Self::search_semantic)
+    ///
+    /// # Errors
+    /// Returns an error if the number of embeddings differs from the number
+    /// of stored messages, if the vector store rejects them, or if the
+    /// database query fails.
+    pub fn index_embeddings(&mut self, session_id: &str, embeddings: &[Vec<f32>]) -> Result<()> {
+        let messages = self.stored_texts(session_id)?;
+        if messages.len() != embeddings.len() {
+            return Err(AgentError::Memory(format!(
+                "Session '{}' has {} stored messages but {} embeddings were given",
+                session_id,
+                messages.len(),
+                embeddings.len()
+            )));
+        }
+
+        let records = messages
+            .into_iter()
+            .zip(embeddings)
+            .map(|((position, role, content), embedding)| {
+                VectorRecord::new(format!("{}#{}", session_id, position), embedding.clone())
+                    .with_content(content)
+                    .with_metadata("session_id", session_id)
+                    .with_metadata("position", position)
+                    .with_metadata("role", role_name(&role))
+            })
+            .collect();
+        self.vectors.upsert(records)
+    }
+
+    /// Remove a session, its embeddings and the feedback on it
+    ///
+    /// # Errors
+    /// Returns an error if the database or vector store write fails.
+    pub fn remove_session(&mut self, session_id: &str) -> Result<()> {
+        self.store.delete(session_id)?;
+        self.store
+            .connection()
+            .execute("DELETE FROM feedback WHERE session_id = ?1", [session_id])
+            .map_err(sql_error)?;
+        self.prune_embeddings(session_id)
+    }
+
+    /// Record a user's feedback on a stored assistant message
+    ///
+    /// A message can collect any number of feedback entries. Feedback is
+    /// kept when its session is indexed again, so sessions should only grow
+    /// between calls to [`index_session`](Self::index_session).
+    ///
+    /// # Errors
+    /// Returns an error if the feedback is empty, if the message is not
+    /// stored or is not an assistant message, or if the database write
+    /// fails.
+    pub fn record_feedback(&self, message: &MessageId, feedback: &Feedback) -> Result<()> {
+        if feedback.is_empty() {
+            return Err(AgentError::Memory(
+                "Feedback needs a rating, comment or correction".to_string(),
+            ));
+        }
+        let role: Option<String> = self
+            .store
+            .connection()
+            .query_row(
+                "SELECT role FROM conversation_messages
+                 WHERE conversation_id = ?1 AND position = ?2",
+                params![message.session_id, message.position as i64],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        match role.as_deref() {
+            Some("assistant") => {}
+            Some(_) => {
+                return Err(AgentError::Memory(format!(
+                    "Message {} of session '{}' is not an assistant message",
+                    message.position, message.session_id
+                )));
+            }
+            None => {
+                return Err(AgentError::Memory(format!(
+                    "Session '{}' has no stored message at position {}",
+                    message.session_id, message.position
+                )));
+            }
+        }
+
+        self.store
+            .connection()
+            .execute(
+                "INSERT INTO feedback
+                     (session_id, position, rating, comment, correction, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    message.session_id,
+                    message.position as i64,
+                    feedback.rating.map(rating_name),
+                    feedback.comment,
+                    feedback.correction,
+                    timestamp(feedback.created_at)
+                ],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    /// Feedback recorded on the messages of a session, by position, oldest
+    /// first
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails.
+    pub fn feedback(&self, session_id: &str) -> Result<Vec<(MessageId, Feedback)>> {
+        self.query_feedback(Some(session_id))
+    }
+
+    /// Export all feedback with the conversation leading up to each rated
+    /// message
+    ///
+    /// Feedback on messages that are no longer stored or were anonymized
+    /// is left out.
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails.
+    pub fn export_feedback(&self) -> Result<FeedbackDataset> {
+        let mut dataset = FeedbackDataset::default();
+        let mut session: Option<(String, Vec<(usize, Message)>)> = None;
+        for (message, feedback) in self.query_feedback(None)? {
+            if session.as_ref().is_none_or(|(id, _)| *id != message.session_id) {
+                let messages = self.stored_messages(&message.session_id)?;
+                session = Some((message.session_id.clone(), messages));
+            }
+            let Some((_, messages)) = &session else {
+                continue;
+            };
+            let Some(index) = messages
+                .iter()
+                .position(|(position, _)| *position == message.position)
+            else {
+                continue;
+            };
+            let response = &messages[index].1;
+            if response.content == ANONYMIZED_PLACEHOLDER {
+                continue;
+            }
+            dataset.examples.push(FeedbackExample {
+                context: messages[..index].iter().map(|(_, m)| m.clone()).collect(),
+                response: response.clone(),
+                message,
+                feedback,
+            });
+        }
+        Ok(dataset)
+    }
+
+    fn query_feedback(&self, session_id: Option<&str>) -> Result<Vec<(MessageId, Feedback)>> {
+        let mut statement = self
+            .store
+            .connection()
+            .prepare(
+                "SELECT session_id, position, rating, comment, correction, created_at
+                 FROM feedback WHERE ?1 IS NULL OR session_id = ?1
+                 ORDER BY session_id, position, id",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map([session_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        let mut feedback = Vec::new();
+        for row in rows {
+            let (session_id, position, rating, comment, correction, created_at) =
+                row.map_err(sql_error)?;
+            feedback.push((
+                MessageId::new(session_id, position as usize),
+                Feedback {
+                    rating: rating.as_deref().map(parse_rating).transpose()?,
+                    comment,
+                    correction,
+                    created_at: parse_timestamp(&created_at)?,
+                },
+            ));
+        }
+        Ok(feedback)
+    }
+
+    /// The stored messages of a session with their positions, in order
+    fn stored_messages(&self, session_id: &str) -> Result<Vec<(usize, Message)>> {
+        let mut statement = self
+            .store
+            .connection()
+            .prepare(
+                "SELECT position, message FROM conversation_messages
+                 WHERE conversation_id = ?1 ORDER BY position",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map([session_id], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(sql_error)?;
+
+        let mut messages = Vec::new();
+        for row in rows {
+            let (position, json) = row.map_err(sql_error)?;
+            messages.push((position as usize, serde_json::from_str(&json)?));
+        }
+        Ok(messages)
+    }
+
+    /// Position, role and text of the stored messages of a session, in
+    /// order
+    fn stored_texts(&self, session_id: &str) -> Result<Vec<(usize, Role, String)>> {
+        let mut statement = self
+            .store
+            .connection()
+            .prepare(
+                "SELECT position, role, content FROM conversation_messages
+                 WHERE conversation_id = ?1 ORDER BY position",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map([session_id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        let mut texts = Vec::new();
+        for row in rows {
+            let (position, role, content) = row.map_err(sql_error)?;
+            texts.push((position as usize, parse_role(&role)?, content));
+        }
+        Ok(texts)
+    }
+
+    /// Drop the embeddings of a session's messages that changed or are
+    /// gone
+    fn prune_embeddings(&mut self, session_id: &str) -> Result<()> {
+        let current: HashMap<usize, String> = self
+            .stored_texts(session_id)?
+            .into_iter()
+            .map(|(position, _, content)| (position, content))
+            .collect();
+        let stale: Vec<String> = self
+            .vectors
+            .iter()
+            .filter(|record| {
+                embedded_message(record).is_some_and(|(session, position)| {
+                    session == session_id && current.get(&position) != Some(&record.content)
+                })
+            })
+            .map(|record| record.id.clone())
+            .collect();
+        let stale: Vec<&str> = stale.iter().map(String::as_str).collect();
+        self.vectors.delete(&stale)?;
+        Ok(())
+    }
+
+    /// Find sessions with messages containing all words of `query`
+    ///
+    /// Words are matched as whole tokens, case-insensitively; FTS5 query
+    /// syntax in `query` is treated as plain text. At most `limit`
+    /// sessions are returned, best first.
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails.
+    pub fn search_keyword(&self, query: &str, limit: usize) -> Result<Vec<SessionMatch>> {
+        let expression = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if expression.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut statement = self
+            .store
+            .connection()
+            .prepare(
+                "SELECT m.conversation_id, m.position, m.role,
+                        snippet(conversation_messages_fts, 0, '[', ']', '...', ?2),
+                        bm25(conversation_messages_fts)
+                 FROM conversation_messages_fts
+                 JOIN conversation_messages m ON m.id = conversation_messages_fts.rowid
+                 WHERE conversation_messages_fts MATCH ?1
+                 ORDER BY bm25(conversation_messages_fts)",
+            )
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(params![expression, SNIPPET_TOKENS as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, f64>(4)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            let (session_id, position, role, snippet, rank) = row.map_err(sql_error)?;
+            // BM25 ranks are negative, lower is better
+            let hit = MessageMatch {
+                position: position as usize,
+                role: parse_role(&role)?,
+                snippet,
+                score: -rank as f32,
+            };
+            hits.push((session_id, hit));
+        }
+        Ok(group_by_session(hits, limit))
+    }
+
+    /// Find sessions with messages whose embeddings are closest to
+    /// `query_embedding`
+    ///
+    /// The `limit` closest embeddings from
+    /// [`index_embeddings`](Self::index_embeddings) are looked up in the
+    /// vector store and grouped into sessions; those whose message has
+    /// changed or is gone since are left out.
+    ///
+    /// # Errors
+    /// Returns an error if `query_embedding` has a different number of
+    /// dimensions than the stored embeddings, or if the database query
+    /// fails.
+    pub fn search_semantic(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<SessionMatch>> {
+        let mut sessions: HashMap<String, HashMap<usize, (Role, String)>> = HashMap::new();
+        let mut hits = Vec::new();
+        for found in self.vectors.search(query_embedding, limit, None)? {
+            let Some((session_id, position)) = embedded_message(&found.record) else {
+                continue;
+            };
+            if !sessions.contains_key(session_id) {
+                let texts = self
+                    .stored_texts(session_id)?
+                    .into_iter()
+                    .map(|(position, role, content)| (position, (role, content)))
+                    .collect();
+                sessions.insert(session_id.to_string(), texts);
+            }
+            let Some((role, content)) = sessions[session_id].get(&position) else {
+                continue;
+            };
+            if *content != found.record.content {
+                continue;
+            }
+            let hit = MessageMatch {
+                position,
+                role: role.clone(),
+                snippet: content.clone(),
+                score: found.score,
+            };
+            hits.push((session_id.to_string(), hit));
+        }
+        Ok(group_by_session(hits, limit))
+    }
+}
+
+/// The store's retention applies, and feedback and embeddings of deleted or
+/// anonymized messages go with them
+impl RetentionStore for ConversationIndex {
+    fn sessions(&self) -> Result<Vec<(String, Option<String>)>> {
+        self.store.sessions()
+    }
+
+    fn delete_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+        let deleted = self.store.delete_before(session_id, cutoff)?;
+        self.store
+            .connection()
+            .execute(
+                "DELETE FROM feedback WHERE session_id = ?1 AND position NOT IN (
+                     SELECT position FROM conversation_messages WHERE conversation_id = ?1
+                 )",
+                [session_id],
+            )
+            .map_err(sql_error)?;
+        self.prune_embeddings(session_id)?;
+        Ok(deleted)
+    }
+
+    fn anonymize_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+        let anonymized = self.store.anonymize_before(session_id, cutoff)?;
+        // Comments and corrections can quote the message, so they go too;
+        // ratings are kept
+        self.store
+            .connection()
+            .execute(
+                "UPDATE feedback SET comment = NULL, correction = NULL
+                 WHERE session_id = ?1 AND position IN (
+                     SELECT position FROM conversation_messages
+                     WHERE conversation_id = ?1 AND created_at < ?2
+                 )",
+                params![session_id, timestamp(cutoff)],
+            )
+            .map_err(sql_error)?;
+        self.prune_embeddings(session_id)?;
+        Ok(anonymized)
+    }
+}
+
+/// Session and position of the message an embedding was computed from
+fn embedded_message(record: &VectorRecord) -> Option<(&str, usize)> {
+    let session_id = record.metadata.get("session_id").and_then(Value::as_str)?;
+    let position = record.metadata.get("position").and_then(Value::as_u64)?;
+    Some((session_id, position as usize))
+}
+
+/// A timestamp as stored in the database; the fixed format sorts as text
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Parse a timestamp stored by [`timestamp`]
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| {
+            AgentError::Memory(format!(
+                "Invalid timestamp '{}' in conversation index: {}",
+                text, e
+            ))
+        })
+}
+
+/// Group hits sorted best first into at most `limit` sessions, best first
+fn group_by_session(hits: Vec<(String, MessageMatch)>, limit: usize) -> Vec<SessionMatch> {
+    let mut sessions: Vec<SessionMatch> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for (session_id, hit) in hits {
+        match positions.get(&session_id) {
+            Some(&index) => sessions[index].messages.push(hit),
+            None => {
+                positions.insert(session_id.clone(), sessions.len());
+                sessions.push(SessionMatch {
+                    session_id,
+                    score: hit.score,
+                    messages: vec![hit],
+                });
+            }
+        }
+    }
+    sessions.truncate(limit);
+    sessions
+}
+
+/// Cosine similarity of two vectors; zero if they differ in length or
+/// either is all zeros
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+fn parse_role(name: &str) -> Result<Role> {
+    match name {
+        "system" => Ok(Role::System),
+        "user" => Ok(Role::User),
+        "assistant" => Ok(Role::Assistant),
+        other => Err(AgentError::Memory(format!(
+            "Unknown role '{}' in conversation index",
+            other
+        ))),
+    }
+}
+
+fn rating_name(rating: Rating) -> &'static str {
+    match rating {
+        Rating::Up => "up",
+        Rating::Down => "down",
+    }
+}
+
+fn parse_rating(name: &str) -> Result<Rating> {
+    match name {
+        "up" => Ok(Rating::Up),
+        "down" => Ok(Rating::Down),
+        other => Err(AgentError::Memory(format!(
+            "Unknown rating '{}' in conversation index",
+            other
+        ))),
+    }
+}
+
+fn sql_error(error: rusqlite::Error) -> AgentError {
+    AgentError::Memory(format!("Conversation index: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> ConversationIndex {
+        let mut index = ConversationIndex::in_memory().unwrap();
+        index
+            .index_session(
+                "rust",
+                &[
+                    Message::user("How do I reverse a Vec in Rust?"),
+                    Message::assistant("Call reverse() on the Vec."),
+                    Message::user("And sort a Vec?"),
+                ],
+            )
+            .unwrap();
+        index
+            .index_session(
+                "cooking",
+                &[
+                    Message::user("How long do I boil an egg?"),
+                    Message::assistant("About seven minutes."),
+                ],
+            )
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_keyword_search_groups_messages_by_session() {
+        let mut index = index();
+
+        let sessions = index.search_keyword("vec", 10).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "rust");
+        assert_eq!(sessions[0].messages.len(), 3);
+        assert!(
+            sessions[0]
+                .messages
+                .iter()
+                .any(|m| m.snippet.contains("[Vec]"))
+        );
+
+        let sessions = index.search_keyword("boil EGG", 10).unwrap();
+        assert_eq!(sessions[0].session_id, "cooking");
+        assert_eq!(sessions[0].messages[0].position, 0);
+        assert_eq!(sessions[0].messages[0].role, Role::User);
+
+        // Query syntax is escaped, and reindexing replaces old messages
+        assert!(index.search_keyword("\"vec OR", 10).unwrap().is_empty());
+        index
+            .index_session("rust", &[Message::user("Lifetimes")])
+            .unwrap();
+        assert!(index.search_keyword("vec", 10).unwrap().is_empty());
+        assert!(index.search_keyword("   ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_semantic_search_ranks_by_similarity() {
+        let mut index = index();
+        index
+            .index_embeddings("rust", &[vec![1.0, 0.0], vec![0.9, 0.1], vec![0.7, 0.7]])
+            .unwrap();
+        index
+            .index_embeddings("cooking", &[vec![0.0, 1.0], vec![0.1, 0.9]])
+            .unwrap();
+
+        let sessions = index.search_semantic(&[0.0, 1.0], 2).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "cooking");
+        assert_eq!(
+            sessions[0].messages[0].snippet,
+            "How long do I boil an egg?"
+        );
+
+        let sessions = index.search_semantic(&[1.0, 0.2], 10).unwrap();
+        assert_eq!(sessions[0].session_id, "rust");
+        assert_eq!(sessions[0].messages[0].position, 1);
+
+        let result = index.index_embeddings("cooking", &[vec![1.0]]);
+        assert!(matches!(result, Err(AgentError::Memory(_))));
+
+        // Reindexing drops the embeddings of messages that changed
+        index
+            .index_session(
+                "cooking",
+                &[
+                    Message::user("How long do I boil an egg?"),
+                    Message::assistant("Six minutes for a soft egg."),
+                ],
+            )
+            .unwrap();
+        let sessions = index.search_semantic(&[0.0, 1.0], 2).unwrap();
+        assert_eq!(sessions[0].messages.len(), 1);
+        assert_eq!(sessions[0].messages[0].position, 0);
+        assert!(index.search_semantic(&[1.0], 2).is_err());
+    }
+
+    #[test]
+    fn test_indexes_conversations_saved_to_the_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conversations.db");
+        let store = ConversationStore::open(&path).unwrap();
+        store
+            .save("before", "bot", &[Message::user("Saved before indexing")])
+            .unwrap();
+
+        let index = ConversationIndex::new(store).unwrap();
+        ConversationStore::open(&path)
+            .unwrap()
+            .append("after", "bot", &[Message::user("Appended by another writer")])
+            .unwrap();
+
+        assert_eq!(index.search_keyword("indexing", 10).unwrap()[0].session_id, "before");
+        assert_eq!(index.search_keyword("writer", 10).unwrap()[0].session_id, "after");
+        assert_eq!(index.store().list(None).unwrap().len(), 2);
+
+        index.store().delete("before").unwrap();
+        assert!(index.search_keyword("indexing", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retention_applies_to_indexed_messages() {
+        use crate::{RetentionPolicy, RetentionRule};
+        use chrono::Duration;
+
+        let mut old = Message::user("My passport number is X123");
+        old.timestamp = Utc::now() - Duration::days(100);
+        let mut index = index();
+        index
+            .index_user_session("u-1", "travel", &[old, Message::user("Book a flight")])
+            .unwrap();
+        index
+            .index_embeddings("travel", &[vec![1.0, 0.0], vec![0.0, 1.0]])
+            .unwrap();
+
+        let mut sessions = index.sessions().unwrap();
+        sessions.sort();
+        assert_eq!(sessions[2], ("travel".to_string(), Some("u-1".to_string())));
+
+        let policy =
+            RetentionPolicy::new().with_user_rule("u-1", RetentionRule::anonymize_after_days(30));
+        let report = policy.enforce(&mut index, Utc::now()).unwrap();
+        assert_eq!(report.anonymized, 1);
+
+        // The old message no longer matches keywords or embeddings
+        assert!(index.search_keyword("passport", 10).unwrap().is_empty());
+        assert_eq!(index.search_keyword("flight", 10).unwrap().len(), 1);
+        let sessions = index.search_semantic(&[1.0, 0.0], 10).unwrap();
+        assert_eq!(sessions[0].messages.len(), 1);
+        assert_eq!(sessions[0].messages[0].position, 1);
+
+        let policy = RetentionPolicy::new().with_default(RetentionRule::delete_after_days(30));
+        let report = policy.enforce(&mut index, Utc::now()).unwrap();
+        assert_eq!(report.deleted, 1);
+        assert_eq!(index.search_keyword("removed", 10).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_feedback_is_exported_with_its_conversation() {
+        let mut index = index();
+        index
+            .record_feedback(&MessageId::new("rust", 1), &Feedback::thumbs_up())
+            .unwrap();
+        index
+            .record_feedback(
+                &MessageId::new("cooking", 1),
+                &Feedback::thumbs_down()
+                    .with_comment("Too long")
+                    .with_correction("About six minutes for a soft egg."),
+            )
+            .unwrap();
+        index
+            .record_feedback(&MessageId::new("cooking", 1), &Feedback::comment("Still wrong"))
+            .unwrap();
+
+        // Only indexed assistant messages can be rated, and only with
+        // something to say
+        let mut empty = Feedback::thumbs_up();
+        empty.rating = None;
+        let invalid = [
+            (0, Feedback::thumbs_up()),
+            (9, Feedback::thumbs_up()),
+            (1, empty),
+        ];
+        for (position, feedback) in invalid {
+            let result = index.record_feedback(&MessageId::new("rust", position), &feedback);
+            assert!(matches!(result, Err(AgentError::Memory(_))));
+        }
+
+        let cooking = index.feedback("cooking").unwrap();
+        assert_eq!(cooking.len(), 2);
+        assert_eq!(cooking[0].1.rating, Some(Rating::Down));
+        assert_eq!(cooking[1].1.comment.as_deref(), Some("Still wrong"));
+
+        let dataset = index.export_feedback().unwrap();
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(dataset.examples[0].message, MessageId::new("cooking", 1));
+        assert_eq!(dataset.examples[0].context.len(), 1);
+        assert_eq!(dataset.examples[0].response.content, "About seven minutes.");
+
+        // The correction replaces the response; the comment alone and the
+        // unrated response are not training data
+        let conversations = dataset.training_conversations();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0][1].content, "About six minutes for a soft egg.");
+        assert_eq!(conversations[1][1].content, "Call reverse() on the Vec.");
+
+        let jsonl = dataset.to_eval_jsonl().unwrap();
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["rating"], "down");
+        assert_eq!(first["messages"][0]["role"], "user");
+
+        // Deleting a message deletes its feedback
+        index
+            .delete_before("cooking", Utc::now() + chrono::Duration::days(1))
+            .unwrap();
+        assert!(index.feedback("cooking").unwrap().is_empty());
+        assert_eq!(index.export_feedback().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,477 @@
+//! Memory-bounded LRU store for active conversation sessions.
+//!
+//! This module keeps a bounded number of conversations in memory, each capped
+//! at a token budget. When the session limit is reached the least recently used
+//! conversation is evicted and, if a [`SessionBackend`] is configured, flushed
+//! to it so it can be restored on the next access. A session the backend
+//! fails to save stays resident, and the error is returned to the caller.
+
+use agent_core::{Message, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::{ANONYMIZED_PLACEHOLDER, RetentionStore, count_tokens};
+
+/// Persistent storage for sessions evicted from an [`LruSessionStore`]
+pub trait SessionBackend: Send {
+    /// Persist the messages of an evicted session
+    ///
+    /// # Errors
+    /// Returns an error if the session could not be persisted; the store
+    /// then keeps it resident.
+    fn save(&mut self, session_id: &str, messages: &[Message]) -> Result<()>;
+
+    /// Load a previously persisted session, if any
+    ///
+    /// # Errors
+    /// Returns an error if the backend could not be read.
+    fn load(&mut self, session_id: &str) -> Result<Option<Vec<Message>>>;
+}
+
+/// A single resident conversation with cached per-message token counts
+struct Session {
+    messages: VecDeque<(Message, usize)>,
+    tokens: usize,
+    last_used: u64,
+}
+
+impl Session {
+    fn new(last_used: u64) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            tokens: 0,
+            last_used,
+        }
+    }
+
+    fn push(&mut self, message: Message) {
+        let tokens = count_tokens(&message);
+        self.tokens += tokens;
+        self.messages.push_back((message, tokens));
+    }
+
+    /// Drop the oldest messages until the session fits the budget,
+    /// always keeping the most recent message
+    fn trim_to(&mut self, max_tokens: usize) {
+        while self.tokens > max_tokens && self.messages.len() > 1 {
+            if let Some((_, tokens)) = self.messages.pop_front() {
+                self.tokens -= tokens;
+            }
+        }
+    }
+
+    fn to_messages(&self) -> Vec<Message> {
+        self.messages.iter().map(|(m, _)| m.clone()).collect()
+    }
+}
+
+/// In-process LRU store for active conversation sessions
+///
+/// Bounds RAM usage with two limits:
+/// - `max_sessions`: how many conversations are kept resident at once
+/// - `max_tokens_per_session`: oldest messages are dropped once a
+///   conversation exceeds this many tokens
+///
+/// Reads and writes both count as a use of the session. While the backend
+/// fails to save evicted sessions, they stay resident and the store can
+/// hold more than `max_sessions`; each later access retries the eviction.
+///
+/// # Examples
+///
+/// ```
+/// use memory::LruSessionStore;
+/// use agent_core::Message;
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let mut store = LruSessionStore::new(2, 4_000);
+/// store.add_message("alice", Message::user("Hi"))?;
+/// store.add_message("bob", Message::user("Hello"))?;
+/// store.add_message("carol", Message::user("Hey"))?;
+///
+/// // "alice" was least recently used and has been evicted
+/// assert!(!store.contains("alice"));
+/// assert_eq!(store.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct LruSessionStore {
+    sessions: HashMap<String, Session>,
+    /// Last-use tick -> session ID, oldest first
+    order: BTreeMap<u64, String>,
+    clock: u64,
+    max_sessions: usize,
+    max_tokens_per_session: usize,
+    backend: Option<Box<dyn SessionBackend>>,
+}
+
+impl LruSessionStore {
+    /// Create a new store with the given limits
+    ///
+    /// # Arguments
+    /// * `max_sessions` - Maximum number of resident sessions (at least 1)
+    /// * `max_tokens_per_session` - Token budget for each session
+    pub fn new(max_sessions: usize, max_tokens_per_session: usize) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+            max_sessions: max_sessions.max(1),
+            max_tokens_per_session,
+            backend: None,
+        }
+    }
+
+    /// Flush evicted sessions to a persistent backend and restore them on access
+    pub fn with_backend(mut self, backend: Box<dyn SessionBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Add a message to a session, creating or restoring the session if needed
+    ///
+    /// # Errors
+    /// Returns an error if the session could not be restored from the
+    /// backend, in which case the message is not added, or if an evicted
+    /// session could not be flushed to it, in which case that session stays
+    /// resident.
+    pub fn add_message(&mut self, session_id: &str, message: Message) -> Result<()> {
+        let max_tokens = self.max_tokens_per_session;
+        let session = self.touch(session_id)?;
+        session.push(message);
+        session.trim_to(max_tokens);
+        self.evict_excess()
+    }
+
+    /// Get the most recent N messages of a session in chronological order
+    ///
+    /// # Errors
+    /// Returns an error if the session could not be restored from the
+    /// backend, or if an evicted session could not be flushed to it.
+    pub fn get_recent(&mut self, session_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let Some(session) = self.touch_existing(session_id)? else {
+            return Ok(Vec::new());
+        };
+        let skip = session.messages.len().saturating_sub(limit);
+        Ok(session
+            .messages
+            .iter()
+            .skip(skip)
+            .map(|(m, _)| m.clone())
+            .collect())
+    }
+
+    /// Get the messages of a session that fit within a token budget
+    ///
+    /// # Errors
+    /// Returns an error if the session could not be restored from the
+    /// backend, or if an evicted session could not be flushed to it.
+    pub fn get_within_budget(
+        &mut self,
+        session_id: &str,
+        token_budget: usize,
+    ) -> Result<Vec<Message>> {
+        let Some(session) = self.touch_existing(session_id)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut total_tokens = 0;
+        let mut result: Vec<Message> = session
+            .messages
+            .iter()
+            .rev()
+            .take_while(|(_, tokens)| {
+                total_tokens += tokens;
+                total_tokens <= token_budget
+            })
+            .map(|(m, _)| m.clone())
+            .collect();
+        result.reverse();
+        Ok(result)
+    }
+
+    /// Remove a session entirely, returning its messages
+    ///
+    /// The session is not flushed to the backend.
+    pub fn remove(&mut self, session_id: &str) -> Option<Vec<Message>> {
+        let session = self.sessions.remove(session_id)?;
+        self.order.remove(&session.last_used);
+        Some(session.to_messages())
+    }
+
+    /// Check whether a session is currently resident in memory
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.sessions.contains_key(session_id)
+    }
+
+    /// Number of resident sessions
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns true if no sessions are resident
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Token count of a resident session
+    pub fn session_tokens(&self, session_id: &str) -> Option<usize> {
+        self.sessions.get(session_id).map(|s| s.tokens)
+    }
+
+    /// Load a non-resident session from the backend, if it has one
+    fn restore(&mut self, session_id: &str) -> Result<bool> {
+        if self.sessions.contains_key(session_id) {
+            return Ok(true);
+        }
+        let Some(backend) = self.backend.as_mut() else {
+            return Ok(false);
+        };
+        let Some(messages) = backend.load(session_id)? else {
+            return Ok(false);
+        };
+
+        let mut session = Session::new(0);
+        for message in messages {
+            session.push(message);
+        }
+        session.trim_to(self.max_tokens_per_session);
+        self.sessions.insert(session_id.to_string(), session);
+        Ok(true)
+    }
+
+    /// Mark a session as most recently used, creating it if needed
+    fn touch(&mut self, session_id: &str) -> Result<&mut Session> {
+        self.restore(session_id)?;
+        let session = self
+            .sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| Session::new(0));
+        self.order.remove(&session.last_used);
+
+        self.clock += 1;
+        session.last_used = self.clock;
+        self.order.insert(self.clock, session_id.to_string());
+        Ok(session)
+    }
+
+    /// Mark a session as used only if it is resident or can be restored
+    fn touch_existing(&mut self, session_id: &str) -> Result<Option<&Session>> {
+        if !self.restore(session_id)? {
+            return Ok(None);
+        }
+        self.touch(session_id)?;
+        self.evict_excess()?;
+        Ok(self.sessions.get(session_id))
+    }
+
+    /// Evict least recently used sessions until within the session limit
+    ///
+    /// A session the backend fails to save is put back as the least
+    /// recently used one, so the next eviction tries it again.
+    fn evict_excess(&mut self) -> Result<()> {
+        while self.sessions.len() > self.max_sessions {
+            let Some((last_used, session_id)) = self.order.pop_first() else {
+                break;
+            };
+            let Some(session) = self.sessions.remove(&session_id) else {
+                continue;
+            };
+            if let Some(backend) = self.backend.as_mut()
+                && let Err(e) = backend.save(&session_id, &session.to_messages())
+            {
+                self.order.insert(last_used, session_id.clone());
+                self.sessions.insert(session_id, session);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Retention applies to resident sessions only; sessions flushed to a
+/// backend are the backend's responsibility. Sessions have no user, so
+/// only session and default rules apply.
+impl RetentionStore for LruSessionStore {
+    fn sessions(&self) -> Result<Vec<(String, Option<String>)>> {
+        Ok(self.sessions.keys().map(|id| (id.clone(), None)).collect())
+    }
+
+    fn delete_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return Ok(0);
+        };
+        let before = session.messages.len();
+        session.messages.retain(|(message, _)| message.timestamp >= cutoff);
+        session.tokens = session.messages.iter().map(|(_, tokens)| tokens).sum();
+        let deleted = before - session.messages.len();
+        if session.messages.is_empty() {
+            self.remove(session_id);
+        }
+        Ok(deleted)
+    }
+
+    fn anonymize_before(&mut self, session_id: &str, cutoff: DateTime<Utc>) -> Result<usize> {
+        let Some(session) = self.sessions.get_mut(session_id) else {
+            return Ok(0);
+        };
+        let mut anonymized = 0;
+        for (message, tokens) in session.messages.iter_mut() {
+            if message.timestamp < cutoff
+                && (message.content != ANONYMIZED_PLACEHOLDER || message.is_multimodal())
+            {
+                message.content = ANONYMIZED_PLACEHOLDER.to_string();
+                message.attachments.clear();
+                message.images.clear();
+                *tokens = count_tokens(message);
+                anonymized += 1;
+            }
+        }
+        session.tokens = session.messages.iter().map(|(_, tokens)| tokens).sum();
+        Ok(anonymized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::AgentError;
+    use std::sync::{Arc, Mutex};
+
+    /// Backend that keeps flushed sessions in a shared map
+    #[derive(Clone, Default)]
+    struct SharedBackend {
+        saved: Arc<Mutex<HashMap<String, Vec<Message>>>>,
+    }
+
+    impl SessionBackend for SharedBackend {
+        fn save(&mut self, session_id: &str, messages: &[Message]) -> Result<()> {
+            self.saved
+                .lock()
+                .unwrap()
+                .insert(session_id.to_string(), messages.to_vec());
+            Ok(())
+        }
+
+        fn load(&mut self, session_id: &str) -> Result<Option<Vec<Message>>> {
+            Ok(self.saved.lock().unwrap().remove(session_id))
+        }
+    }
+
+    /// Backend that can't be written to: every save fails
+    struct FailingBackend;
+
+    impl SessionBackend for FailingBackend {
+        fn save(&mut self, _session_id: &str, _messages: &[Message]) -> Result<()> {
+            Err(AgentError::Memory("backend unavailable".to_string()))
+        }
+
+        fn load(&mut self, _session_id: &str) -> Result<Option<Vec<Message>>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_sessions_are_isolated() {
+        let mut store = LruSessionStore::new(10, 10_000);
+        store.add_message("a", Message::user("for a")).unwrap();
+        store.add_message("b", Message::user("for b")).unwrap();
+
+        let a = store.get_recent("a", 10).unwrap();
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].content, "for a");
+        assert!(store.get_recent("missing", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut store = LruSessionStore::new(2, 10_000);
+        store.add_message("a", Message::user("one")).unwrap();
+        store.add_message("b", Message::user("two")).unwrap();
+
+        // Reading "a" makes "b" the least recently used
+        store.get_recent("a", 1).unwrap();
+        store.add_message("c", Message::user("three")).unwrap();
+
+        assert!(store.contains("a"));
+        assert!(!store.contains("b"));
+        assert!(store.contains("c"));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_trims_oldest_messages_over_token_limit() {
+        let message = Message::user("a short message");
+        let per_message = count_tokens(&message);
+
+        let mut store = LruSessionStore::new(1, per_message * 2);
+        for i in 0..5 {
+            store.add_message("a", Message::user(format!("message {}", i))).unwrap();
+        }
+
+        let messages = store.get_recent("a", 10).unwrap();
+        assert!(messages.len() <= 2);
+        assert_eq!(messages.last().unwrap().content, "message 4");
+        assert!(store.session_tokens("a").unwrap() <= per_message * 2);
+    }
+
+    #[test]
+    fn test_evicted_sessions_are_flushed_and_restored() {
+        let backend = SharedBackend::default();
+        let mut store = LruSessionStore::new(1, 10_000).with_backend(Box::new(backend.clone()));
+
+        store.add_message("a", Message::user("remember me")).unwrap();
+        store.add_message("b", Message::user("newer")).unwrap();
+
+        assert!(!store.contains("a"));
+        assert!(backend.saved.lock().unwrap().contains_key("a"));
+
+        let restored = store.get_recent("a", 10).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].content, "remember me");
+
+        // Restoring "a" evicted "b" in turn
+        assert!(!store.contains("b"));
+        assert!(backend.saved.lock().unwrap().contains_key("b"));
+    }
+
+    #[test]
+    fn test_failed_flush_keeps_session_resident() {
+        let mut store = LruSessionStore::new(1, 10_000).with_backend(Box::new(FailingBackend));
+        store.add_message("a", Message::user("keep me")).unwrap();
+
+        let result = store.add_message("b", Message::user("newer"));
+        assert!(matches!(result, Err(AgentError::Memory(_))));
+        // Neither session is lost, and "a" is still the next to evict
+        assert!(store.contains("a"));
+        assert!(store.contains("b"));
+        assert_eq!(store.len(), 2);
+        assert!(store.add_message("b", Message::user("again")).is_err());
+        assert_eq!(store.remove("a").unwrap()[0].content, "keep me");
+
+        // Once back within the limit, nothing needs flushing
+        assert_eq!(store.get_recent("b", 10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_get_within_budget() {
+        let mut store = LruSessionStore::new(1, 10_000);
+        store.add_message("a", Message::user("first")).unwrap();
+        store.add_message("a", Message::user("second")).unwrap();
+
+        let last_tokens = count_tokens(&Message::user("second"));
+        let messages = store.get_within_budget("a", last_tokens).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "second");
+    }
+
+    #[test]
+    fn test_remove_session() {
+        let mut store = LruSessionStore::new(2, 10_000);
+        store.add_message("a", Message::user("bye")).unwrap();
+
+        let removed = store.remove("a").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(store.is_empty());
+        assert!(store.remove("a").is_none());
+    }
+}
@@ -22,6 +22,8 @@ use tiktoken_rs::cl100k_base;
 ///     role: Role::User,
 ///     content: "Hello, world!".to_string(),
 ///     timestamp: Utc::now(),
+///     attachments: Vec::new(),
+///     images: Vec::new(),
 /// };
 ///
 /// let count = count_tokens(&message);
@@ -54,6 +56,8 @@ mod tests {
             role: Role::User,
             content: "Hello, world!".to_string(),
             timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         };
         
         let count = count_tokens(&message);
@@ -67,6 +71,8 @@ mod tests {
             role: Role::Assistant,
             content: "This is a longer message with more words to count tokens for.".to_string(),
             timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            images: Vec::new(),
         };
         
         let count = count_tokens(&message);
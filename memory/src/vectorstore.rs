@@ -0,0 +1,491 @@
+//! Embedding storage with similarity search.
+//!
+//! A [`VectorStore`] keeps embeddings with their text and metadata, in
+//! memory or persisted to a JSON file, and finds the records most similar
+//! to a query embedding. Search is exact: every record passing the
+//! [`MetadataFilter`] is scored, which is fast enough for the tens of
+//! thousands of chunks a single agent typically retrieves from.
+
+use agent_core::{AgentError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::search::cosine_similarity;
+
+/// How query and stored embeddings are compared
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Similarity {
+    /// Cosine of the angle between the vectors, ignoring their length
+    #[default]
+    Cosine,
+    /// Dot product; the same ranking as cosine for normalized embeddings,
+    /// such as OpenAI's, and cheaper to compute
+    DotProduct,
+}
+
+impl Similarity {
+    /// Score `a` against `b`; higher is more similar
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Similarity::Cosine => cosine_similarity(a, b),
+            Similarity::DotProduct => a.iter().zip(b).map(|(x, y)| x * y).sum(),
+        }
+    }
+}
+
+/// An embedding with the text it was computed from and metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorRecord {
+    /// Unique ID; upserting a record with the same ID replaces it
+    pub id: String,
+    /// The embedding
+    pub embedding: Vec<f32>,
+    /// Text the embedding was computed from
+    #[serde(default)]
+    pub content: String,
+    /// Metadata to filter searches on, such as the source document
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+}
+
+impl VectorRecord {
+    /// Create a record with no content or metadata
+    pub fn new(id: impl Into<String>, embedding: Vec<f32>) -> Self {
+        Self {
+            id: id.into(),
+            embedding,
+            content: String::new(),
+            metadata: Map::new(),
+        }
+    }
+
+    /// Set the text the embedding was computed from
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    /// Set a metadata value
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Conditions on record metadata, all of which must hold
+///
+/// # Examples
+///
+/// ```
+/// use memory::MetadataFilter;
+///
+/// let filter = MetadataFilter::new()
+///     .eq("source", "handbook.pdf")
+///     .one_of("language", ["en", "de"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataFilter {
+    conditions: Vec<(String, Condition)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Equals(Value),
+    OneOf(Vec<Value>),
+}
+
+impl MetadataFilter {
+    /// Create a filter that matches every record
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `key` to equal `value`
+    pub fn eq(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.conditions
+            .push((key.into(), Condition::Equals(value.into())));
+        self
+    }
+
+    /// Require `key` to equal one of `values`
+    pub fn one_of<V: Into<Value>>(
+        mut self,
+        key: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.conditions.push((key.into(), Condition::OneOf(values)));
+        self
+    }
+
+    /// Whether `metadata` satisfies every condition
+    pub fn matches(&self, metadata: &Map<String, Value>) -> bool {
+        self.conditions.iter().all(|(key, condition)| {
+            let Some(value) = metadata.get(key) else {
+                return false;
+            };
+            match condition {
+                Condition::Equals(expected) => value == expected,
+                Condition::OneOf(expected) => expected.contains(value),
+            }
+        })
+    }
+}
+
+/// A record found by [`VectorStore::search`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorMatch {
+    /// The matching record
+    pub record: VectorRecord,
+    /// Similarity to the query; higher is more similar
+    pub score: f32,
+}
+
+/// Embeddings with similarity search, in memory or backed by a file
+///
+/// Every embedding in a store has the same number of dimensions, set by
+/// the first records added.
+///
+/// # Examples
+///
+/// ```
+/// use memory::{MetadataFilter, VectorRecord, VectorStore};
+///
+/// # fn example() -> agent_core::Result<()> {
+/// let mut store = VectorStore::new();
+/// store.upsert(vec![
+///     VectorRecord::new("faq-1", vec![0.9, 0.1])
+///         .with_content("Reset your password from the login page.")
+///         .with_metadata("source", "faq"),
+///     VectorRecord::new("blog-1", vec![0.8, 0.3])
+///         .with_content("Our new password policy explained.")
+///         .with_metadata("source", "blog"),
+/// ])?;
+///
+/// let matches = store.search(&[1.0, 0.0], 5, Some(&MetadataFilter::new().eq("source", "faq")))?;
+/// assert_eq!(matches[0].record.id, "faq-1");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct VectorStore {
+    records: BTreeMap<String, VectorRecord>,
+    similarity: Similarity,
+    path: Option<PathBuf>,
+}
+
+impl VectorStore {
+    /// Create an empty, in-memory store using cosine similarity
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a store persisted at `path`, loading any records already
+    /// stored there
+    ///
+    /// Every change is written back to the file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let records: Vec<VectorRecord> = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        let mut store = Self {
+            path: Some(path),
+            ..Self::default()
+        };
+        store.insert_all(records)?;
+        Ok(store)
+    }
+
+    /// Set how embeddings are compared
+    pub fn with_similarity(mut self, similarity: Similarity) -> Self {
+        self.similarity = similarity;
+        self
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether the store has no records
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Number of dimensions of the stored embeddings, if any are stored
+    pub fn dimensions(&self) -> Option<usize> {
+        self.records
+            .values()
+            .next()
+            .map(|record| record.embedding.len())
+    }
+
+    /// The record with `id`, if any
+    pub fn get(&self, id: &str) -> Option<&VectorRecord> {
+        self.records.get(id)
+    }
+
+    /// All records, in ID order
+    pub fn iter(&self) -> impl Iterator<Item = &VectorRecord> {
+        self.records.values()
+    }
+
+    /// Add records, replacing any with the same IDs
+    ///
+    /// # Errors
+    /// Returns `AgentError::Memory` if an embedding is empty or its number
+    /// of dimensions differs from the store's, or an I/O error if the file
+    /// can't be written; no record is changed then.
+    pub fn upsert(&mut self, records: Vec<VectorRecord>) -> Result<()> {
+        let previous = self.insert_all(records)?;
+        if let Err(e) = self.persist() {
+            for (id, record) in previous.into_iter().rev() {
+                match record {
+                    Some(record) => self.records.insert(id, record),
+                    None => self.records.remove(&id),
+                };
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Remove the records with `ids`, returning how many existed
+    ///
+    /// # Errors
+    /// Returns an I/O error if the file can't be written; no record is
+    /// removed then.
+    pub fn delete(&mut self, ids: &[&str]) -> Result<usize> {
+        let removed: Vec<VectorRecord> = ids
+            .iter()
+            .filter_map(|id| self.records.remove(*id))
+            .collect();
+        if !removed.is_empty()
+            && let Err(e) = self.persist()
+        {
+            for record in removed {
+                self.records.insert(record.id.clone(), record);
+            }
+            return Err(e);
+        }
+        Ok(removed.len())
+    }
+
+    /// The `top_k` records most similar to `query`, most similar first
+    ///
+    /// Only records whose metadata satisfies `filter` are considered.
+    ///
+    /// # Errors
+    /// Returns `AgentError::Memory` if `query` has a different number of
+    /// dimensions than the stored embeddings.
+    pub fn search(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<VectorMatch>> {
+        if let Some(dimensions) = self.dimensions()
+            && query.len() != dimensions
+        {
+            return Err(AgentError::Memory(format!(
+                "Query embedding has {} dimensions but the store holds {}",
+                query.len(),
+                dimensions
+            )));
+        }
+        let mut matches: Vec<VectorMatch> = self
+            .records
+            .values()
+            .filter(|record| filter.is_none_or(|filter| filter.matches(&record.metadata)))
+            .map(|record| VectorMatch {
+                score: self.similarity.score(query, &record.embedding),
+                record: record.clone(),
+            })
+            .collect();
+        // Stable, so equal scores stay in ID order
+        matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        matches.truncate(top_k);
+        Ok(matches)
+    }
+
+    /// Check every record, then insert them all
+    /// Insert validated records, returning what each ID held before
+    fn insert_all(
+        &mut self,
+        records: Vec<VectorRecord>,
+    ) -> Result<Vec<(String, Option<VectorRecord>)>> {
+        // Records replaced by this call no longer fix the dimensions
+        let replaced: HashSet<&str> = records.iter().map(|record| record.id.as_str()).collect();
+        let mut dimensions = self
+            .records
+            .values()
+            .find(|record| !replaced.contains(record.id.as_str()))
+            .map(|record| record.embedding.len());
+        for record in &records {
+            if record.embedding.is_empty() {
+                return Err(AgentError::Memory(format!(
+                    "Embedding for '{}' is empty",
+                    record.id
+                )));
+            }
+            let expected = *dimensions.get_or_insert(record.embedding.len());
+            if record.embedding.len() != expected {
+                return Err(AgentError::Memory(format!(
+                    "Embedding for '{}' has {} dimensions but the store holds {}",
+                    record.id,
+                    record.embedding.len(),
+                    expected
+                )));
+            }
+        }
+        Ok(records
+            .into_iter()
+            .map(|record| {
+                let id = record.id.clone();
+                let previous = self.records.insert(id.clone(), record);
+                (id, previous)
+            })
+            .collect())
+    }
+
+    fn persist(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let records: Vec<&VectorRecord> = self.records.values().collect();
+            // Write then rename, so a failed write never truncates the file
+            let mut tmp = path.clone().into_os_string();
+            tmp.push(".tmp");
+            std::fs::write(&tmp, serde_json::to_string(&records)?)?;
+            std::fs::rename(&tmp, path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<VectorRecord> {
+        vec![
+            VectorRecord::new("a", vec![1.0, 0.0])
+                .with_content("alpha")
+                .with_metadata("lang", "en"),
+            VectorRecord::new("b", vec![3.0, 3.0])
+                .with_content("beta")
+                .with_metadata("lang", "de"),
+            VectorRecord::new("c", vec![0.6, 0.8])
+                .with_content("gamma")
+                .with_metadata("lang", "en")
+                .with_metadata("year", 2024),
+        ]
+    }
+
+    #[test]
+    fn test_search_ranks_by_similarity() {
+        let mut store = VectorStore::new();
+        store.upsert(records()).unwrap();
+
+        let ids = |matches: Vec<VectorMatch>| -> Vec<String> {
+            matches.into_iter().map(|m| m.record.id).collect()
+        };
+        let cosine = store.search(&[0.0, 1.0], 2, None).unwrap();
+        assert!((cosine[0].score - 0.8).abs() < 1e-6);
+        assert_eq!(ids(cosine), ["c", "b"]);
+
+        // Dot product favours the long vector
+        let store = store.with_similarity(Similarity::DotProduct);
+        assert_eq!(ids(store.search(&[1.0, 0.0], 3, None).unwrap()), ["b", "a", "c"]);
+
+        let error = store.search(&[1.0, 0.0, 0.0], 3, None).unwrap_err();
+        assert!(matches!(error, AgentError::Memory(_)));
+    }
+
+    #[test]
+    fn test_metadata_filters() {
+        let mut store = VectorStore::new();
+        store.upsert(records()).unwrap();
+
+        let search = |filter: MetadataFilter| -> Vec<String> {
+            let matches = store.search(&[1.0, 0.0], 10, Some(&filter)).unwrap();
+            matches.into_iter().map(|m| m.record.id).collect()
+        };
+        assert_eq!(search(MetadataFilter::new().eq("lang", "en")), ["a", "c"]);
+        assert_eq!(search(MetadataFilter::new().eq("lang", "en").eq("year", 2024)), ["c"]);
+        assert_eq!(search(MetadataFilter::new().one_of("lang", ["de", "fr"])), ["b"]);
+        assert!(search(MetadataFilter::new().eq("missing", true)).is_empty());
+    }
+
+    #[test]
+    fn test_upserts_replace_and_check_dimensions() {
+        let mut store = VectorStore::new();
+        store.upsert(records()).unwrap();
+        store
+            .upsert(vec![VectorRecord::new("a", vec![0.0, 1.0]).with_content("alpha v2")])
+            .unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get("a").unwrap().content, "alpha v2");
+        assert!(store.get("a").unwrap().metadata.is_empty());
+
+        // A bad record rejects the whole batch
+        let error = store
+            .upsert(vec![
+                VectorRecord::new("d", vec![1.0, 1.0]),
+                VectorRecord::new("e", vec![1.0]),
+            ])
+            .unwrap_err();
+        assert!(error.to_string().contains("'e' has 1 dimensions"));
+        assert!(store.get("d").is_none());
+        assert!(store.upsert(vec![VectorRecord::new("f", vec![])]).is_err());
+
+        assert_eq!(store.delete(&["a", "b", "z"]).unwrap(), 2);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_file_backed_store_persists_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vectors.json");
+
+        let mut store = VectorStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        store.upsert(records()).unwrap();
+        store.delete(&["b"]).unwrap();
+
+        let reopened = VectorStore::open(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.dimensions(), Some(2));
+        assert_eq!(reopened.get("c").unwrap(), store.get("c").unwrap());
+        let matches = reopened.search(&[0.6, 0.8], 1, None).unwrap();
+        assert_eq!(matches[0].record.content, "gamma");
+    }
+
+    #[test]
+    fn test_failed_persist_leaves_records_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vectors.json");
+        let mut store = VectorStore::open(&path).unwrap();
+        store.upsert(records()).unwrap();
+
+        // A directory in the way of the temp file makes every write fail
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        std::fs::create_dir(&tmp).unwrap();
+
+        let replacement = VectorRecord::new("a", vec![0.0, 1.0]).with_content("changed");
+        let added = VectorRecord::new("d", vec![1.0, 1.0]);
+        assert!(store.upsert(vec![replacement, added]).is_err());
+        assert!(store.delete(&["b", "c"]).is_err());
+
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get("a").unwrap().content, "alpha");
+        assert!(store.get("d").is_none());
+        assert_eq!(VectorStore::open(&path).unwrap().len(), 3);
+    }
+}
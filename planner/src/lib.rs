@@ -9,7 +9,15 @@
 //! - **Plan**: A sequence of steps with reasoning about why the plan was chosen
 //! - **Step**: Individual actions that can be tool calls, reasoning steps, or responses
 //! - **ToolCall**: Structured invocation of a tool with parameters as JSON
+//! - **RetryPolicy**: How often a failed tool call is retried, and an optional
+//!   fallback step to run if it keeps failing
 //! - **Planner**: Orchestrates plan generation using LLM with system prompts
+//! - **Subgoal**: A part of a larger goal with its own steps and optional
+//!   model profile; [`Planner::create_hierarchical_plan`] splits a goal
+//!   into subgoals recursively instead of planning one flat list of steps
+//! - **PlanTemplate**: A reusable plan with declared parameters, instantiated
+//!   with arguments instead of planned by the model; a [`TemplateLibrary`]
+//!   loads a directory of them
 //! 
 //! # Architecture
 //! 
@@ -28,6 +36,10 @@
 //! 4. **Plan Validation**: Verifies that all tool references in the plan exist
 //!    in the tool registry to prevent runtime errors.
 //! 
+//! With [`Planner::stream_plan`] the response is parsed while it streams in,
+//! and each step is yielded as soon as its JSON object is complete, so it can
+//! be validated or executed before the rest of the plan arrives.
+//! 
 //! # Example
 //! 
 //! ```rust,ignore
@@ -85,10 +97,21 @@
 //!   ]
 //! }
 //! ```
+//!
+//! Plan generation and validation run in `tracing` spans carrying the
+//! request's trace ID and the size of the plan.
+//!
+//! Plans can be rendered for documentation or debugging with
+//! [`Plan::to_dot`] (Graphviz) and [`Plan::to_mermaid`].
 
 mod types;
 mod planner;
+mod visualize;
+mod streaming;
+mod template;
 
 // Re-export public types
-pub use types::{Plan, Step, ToolCall};
+pub use types::{Condition, MAX_LOOP_ITERATIONS, Plan, RetryPolicy, Step, ToolCall};
 pub use planner::Planner;
+pub use streaming::{PlanEvent, PlanStream, PlanStreamParser};
+pub use template::{ParameterType, PlanTemplate, TemplateLibrary, TemplateParameter};
@@ -1,6 +1,13 @@
-use agent_core::{Message, Result};
+use agent_core::{Message, RequestContext, Result};
+use futures::{StreamExt, stream};
+use llm::StreamEvent;
+use serde::Deserialize;
+use std::collections::VecDeque;
 use tools::{ToolInfo, ToolRegistry};
-use crate::types::{Plan, Step};
+use tracing::Span;
+use tracing::field::Empty;
+use crate::streaming::{PlanEvent, PlanStream, PlanStreamParser};
+use crate::types::{MAX_LOOP_ITERATIONS, Plan, Step};
 
 /// The Planner orchestrates plan generation using LLM reasoning.
 /// 
@@ -8,7 +15,15 @@ use crate::types::{Plan, Step};
 /// a structured plan with steps that the executor can run.
 pub struct Planner {
     llm: Box<dyn llm::LLMProvider>,
+    #[allow(dead_code)]
     memory: Box<dyn memory::MemoryStore>,
+    /// Whether plans may include image generation steps
+    image_generation: bool,
+    /// Model profiles text generation steps may use; text generation
+    /// steps are not offered when empty
+    model_profiles: Vec<String>,
+    /// Locale that reasoning and response text must be written in
+    locale: Option<String>,
 }
 
 impl Planner {
@@ -21,7 +36,46 @@ impl Planner {
     /// # Returns
     /// A new Planner instance
     pub fn new(llm: Box<dyn llm::LLMProvider>, memory: Box<dyn memory::MemoryStore>) -> Self {
-        Self { llm, memory }
+        Self {
+            llm,
+            memory,
+            image_generation: false,
+            model_profiles: Vec::new(),
+            locale: None,
+        }
+    }
+
+    /// Allows plans to include image generation steps.
+    /// 
+    /// Only enable this when the executor has an image provider configured.
+    pub fn with_image_generation(mut self, enabled: bool) -> Self {
+        self.image_generation = enabled;
+        self
+    }
+
+    /// Allows plans to include text generation steps run with the given
+    /// model profiles, e.g. `summarizer` or `coder`.
+    /// 
+    /// Only list profiles the executor has providers for.
+    pub fn with_model_profiles(mut self, profiles: Vec<String>) -> Self {
+        self.model_profiles = profiles;
+        self
+    }
+
+    /// Requires reasoning and response text in the given BCP 47 locale.
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Changes the response locale, e.g. when a session switches language.
+    pub fn set_locale(&mut self, locale: Option<String>) {
+        self.locale = locale;
+    }
+
+    /// The locale responses are written in, if one is set.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
     }
     
     /// Builds a system prompt that instructs the LLM on how to generate plans.
@@ -30,6 +84,7 @@ impl Planner {
     /// - Instructions on the expected JSON output format
     /// - Available tools with their descriptions and parameter schemas
     /// - Guidelines for creating effective plans
+    /// - The required response language, if a locale is set
     /// 
     /// # Arguments
     /// * `available_tools` - List of tools the agent can use
@@ -50,6 +105,23 @@ impl Planner {
             }\n\n"
         );
         
+        if self.image_generation {
+            prompt.push_str(
+                "To create an image, use an image generation step: \
+                {\"type\": \"image_generation\", \"prompt\": \"detailed description of the image\"}\n\n"
+            );
+        }
+
+        if !self.model_profiles.is_empty() {
+            prompt.push_str(&format!(
+                "To have a model write text, such as a summary or code, use a text generation \
+                step with the model profile best suited to the task: \
+                {{\"type\": \"text_generation\", \"prompt\": \"instructions for the model\", \
+                \"profile\": \"profile name\"}}\nAvailable profiles: {}\n\n",
+                self.model_profiles.join(", ")
+            ));
+        }
+
         if available_tools.is_empty() {
             prompt.push_str("No tools are available. You can only use reasoning and response steps.\n\n");
         } else {
@@ -69,9 +141,34 @@ impl Planner {
             3. Use reasoning steps to explain your thought process\n\
             4. End with a response step that answers the user's question\n\
             5. Ensure all tool names match exactly the available tools\n\
-            6. Validate that parameters match the tool's schema\n\n\
-            Remember: Respond ONLY with valid JSON. Do not include any other text."
+            6. Validate that parameters match the tool's schema\n\
+            7. A tool call that may fail transiently can have a \"retry\" object with \
+            \"max_retries\", \"delay_ms\" and an optional \"fallback\" step\n\
+            8. To branch on the result of a step, use {\"type\": \"conditional\", \"check\": step, \
+            \"condition\": condition, \"then\": [steps], \"otherwise\": [steps]}; to branch on \
+            an earlier step instead, replace \"check\" with \"step\": its index in \"steps\", \
+            counting from 0. To repeat steps, use {\"type\": \"loop\", \"body\": [steps], \
+            \"until\": condition, \"max_iterations\": n}. A condition is \
+            {\"type\": \"succeeded\"}, {\"type\": \"failed\"}, \
+            {\"type\": \"contains\", \"text\": \"...\"}, {\"type\": \"equals\", \"text\": \"...\"}, \
+            {\"type\": \"matches\", \"pattern\": \"regex\"} or \
+            {\"type\": \"json_path\", \"path\": \"$.field[0]\", \"equals\": value}\n\
+            9. To run independent steps at the same time, use {\"type\": \"parallel\", \
+            \"steps\": [steps]}. Only group steps that neither use each other's results nor \
+            each other's side effects: two searches may run in parallel, but writing a file \
+            and then reading it may not\n\n"
         );
+
+        if let Some(locale) = &self.locale {
+            prompt.push_str(&format!(
+                "Write all reasoning and response text in the language of the locale \
+                '{}', whatever language the user writes in. Keep JSON keys, step types \
+                and tool names in English.\n\n",
+                locale
+            ));
+        }
+
+        prompt.push_str("Remember: Respond ONLY with valid JSON. Do not include any other text.");
         
         prompt
     }
@@ -90,6 +187,15 @@ impl Planner {
     /// 
     /// # Returns
     /// * `Result<Plan>` - The generated plan or an error
+    #[tracing::instrument(
+        name = "planner.create_plan",
+        skip_all,
+        fields(
+            trace_id = RequestContext::current().map(|request| request.trace_id),
+            tools = available_tools.len(),
+            steps = Empty,
+        )
+    )]
     pub async fn create_plan(&self, goal: &str, available_tools: &[ToolInfo]) -> Result<Plan> {
         // Build the system prompt with available tools
         let system_prompt = self.build_system_prompt(available_tools);
@@ -104,7 +210,176 @@ impl Planner {
         let response = self.llm.send_message(&messages).await?;
         
         // Parse the response into a Plan
-        self.parse_plan(&response)
+        let plan = self.parse_plan(&response)?;
+        Span::current().record("steps", plan.steps.len());
+        Ok(plan)
+    }
+
+    /// Creates a plan by decomposing the goal into subgoals recursively.
+    /// 
+    /// The LLM either plans a goal directly or splits it into subgoals,
+    /// each of which may name the model profile best suited to it. Every
+    /// subgoal is planned the same way, up to `max_depth` levels below the
+    /// goal, below which subgoals are planned with
+    /// [`create_plan`](Self::create_plan)'s prompt. The result is a plan of
+    /// [`Step::Subgoal`] steps whose subtrees hold the planned steps, which
+    /// suits large tasks better than one flat list of steps.
+    /// 
+    /// # Arguments
+    /// * `goal` - The user's goal or request
+    /// * `available_tools` - List of tools the agent can use
+    /// * `max_depth` - How many levels of subgoals the goal may be split into
+    /// 
+    /// # Returns
+    /// * `Result<Plan>` - The generated plan, or an error if any level could
+    ///   not be planned or a subgoal names an unknown profile
+    #[tracing::instrument(
+        name = "planner.create_hierarchical_plan",
+        skip_all,
+        fields(
+            trace_id = RequestContext::current().map(|request| request.trace_id),
+            max_depth,
+            subgoals = Empty,
+        )
+    )]
+    pub async fn create_hierarchical_plan(
+        &self,
+        goal: &str,
+        available_tools: &[ToolInfo],
+        max_depth: usize,
+    ) -> Result<Plan> {
+        if max_depth == 0 {
+            return self.create_plan(goal, available_tools).await;
+        }
+
+        let messages = vec![
+            Message::system(self.build_decomposition_prompt(available_tools)),
+            Message::user(goal),
+        ];
+        let response = self.llm.send_message(&messages).await?;
+        let json_str = self.extract_json(&response)?;
+        let decomposition: Decomposition = serde_json::from_str(json_str)
+            .map_err(|e| agent_core::AgentError::Planning(
+                format!("Failed to parse decomposition JSON: {}. Response was: {}", e, json_str)
+            ))?;
+
+        Span::current().record("subgoals", decomposition.subgoals.len());
+        if decomposition.subgoals.is_empty() {
+            return Ok(Plan::new(decomposition.steps, decomposition.reasoning));
+        }
+
+        let mut steps = Vec::with_capacity(decomposition.subgoals.len());
+        for subgoal in decomposition.subgoals {
+            if let Some(profile) = &subgoal.profile
+                && !self.model_profiles.contains(profile)
+            {
+                return Err(agent_core::AgentError::Planning(format!(
+                    "Subgoal '{}' names unknown model profile '{}'",
+                    subgoal.goal, profile
+                )));
+            }
+            let plan = Box::pin(self.create_hierarchical_plan(
+                &subgoal.goal,
+                available_tools,
+                max_depth - 1,
+            ))
+            .await?;
+            steps.push(Step::Subgoal {
+                goal: subgoal.goal,
+                profile: subgoal.profile,
+                steps: plan.steps,
+            });
+        }
+        Ok(Plan::new(steps, decomposition.reasoning))
+    }
+
+    /// Builds the system prompt for one level of hierarchical planning.
+    /// 
+    /// It extends [`build_system_prompt`](Self::build_system_prompt) with
+    /// instructions for splitting the goal into subgoals instead of
+    /// planning its steps, listing the model profiles subgoals may use.
+    pub fn build_decomposition_prompt(&self, available_tools: &[ToolInfo]) -> String {
+        let mut prompt = self.build_system_prompt(available_tools);
+        let mut decomposition = String::from(
+            "If the goal is too large to plan as one list of steps, split it into \
+            subgoals instead. Respond with {\"reasoning\": \"...\", \"subgoals\": \
+            [{\"goal\": \"what the subgoal achieves\"}, ...]} in place of \"steps\"; \
+            each subgoal is planned separately, in order, and may be split further. \
+            Only split goals that need several distinct pieces of work.\n",
+        );
+        if !self.model_profiles.is_empty() {
+            decomposition.push_str(&format!(
+                "A subgoal can add \"profile\": the model profile best suited to all of its \
+                work. Available profiles: {}\n",
+                self.model_profiles.join(", ")
+            ));
+        }
+        decomposition.push('\n');
+        let end = prompt.rfind("Remember:").unwrap_or(prompt.len());
+        prompt.insert_str(end, &decomposition);
+        prompt
+    }
+
+    /// Creates a plan while streaming the LLM response.
+    /// 
+    /// Uses the same prompt as [`create_plan`](Self::create_plan), but yields
+    /// each step as a [`PlanEvent::Step`] as soon as its JSON object has
+    /// streamed in, followed by the complete plan as [`PlanEvent::Complete`].
+    /// Callers can validate, or start executing, early steps while the model
+    /// is still generating later ones.
+    /// 
+    /// The stream ends after the first error; a malformed step fails the
+    /// stream as soon as it is received.
+    /// 
+    /// # Arguments
+    /// * `goal` - The user's goal or request
+    /// * `available_tools` - List of tools the agent can use
+    /// 
+    /// # Returns
+    /// * `Result<PlanStream>` - The plan events or an error starting the stream
+    #[tracing::instrument(
+        name = "planner.stream_plan",
+        skip_all,
+        fields(
+            trace_id = RequestContext::current().map(|request| request.trace_id),
+            tools = available_tools.len(),
+        )
+    )]
+    pub async fn stream_plan(
+        &self,
+        goal: &str,
+        available_tools: &[ToolInfo],
+    ) -> Result<PlanStream> {
+        let system_prompt = self.build_system_prompt(available_tools);
+        let messages = vec![
+            Message::system(&system_prompt),
+            Message::user(goal),
+        ];
+
+        let tokens = self.llm.stream_message(&messages).await?;
+        let state = (tokens, Some(PlanStreamParser::new()), VecDeque::new());
+        Ok(Box::pin(stream::unfold(state, |(mut tokens, mut parser, mut pending)| async move {
+            loop {
+                if let Some(step) = pending.pop_front() {
+                    return Some((Ok(PlanEvent::Step(step)), (tokens, parser, pending)));
+                }
+                let active = parser.as_mut()?;
+                let event = match tokens.next().await {
+                    Some(Ok(StreamEvent::Text(text))) => match active.push(&text) {
+                        Ok(steps) => {
+                            pending.extend(steps);
+                            continue;
+                        }
+                        Err(e) => Err(e),
+                    },
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => Err(e),
+                    None => parser.take()?.finish().map(PlanEvent::Complete),
+                };
+                parser = None;
+                return Some((event, (tokens, parser, pending)));
+            }
+        })))
     }
     
     /// Parses an LLM response into a structured Plan.
@@ -153,12 +428,11 @@ impl Planner {
         }
         
         // Try to find JSON object boundaries
-        if let Some(start) = trimmed.find('{') {
-            if let Some(end) = trimmed.rfind('}') {
-                if end > start {
-                    return Ok(&trimmed[start..=end]);
-                }
-            }
+        if let Some(start) = trimmed.find('{')
+            && let Some(end) = trimmed.rfind('}')
+            && end > start
+        {
+            return Ok(&trimmed[start..=end]);
         }
         
         // If we can't find JSON, return an error
@@ -169,10 +443,13 @@ impl Planner {
     
     /// Validates that a plan only references tools that exist in the registry.
     /// 
-    /// This method checks all ToolCall steps in the plan and ensures that
-    /// each referenced tool is available in the provided registry. This
-    /// prevents runtime errors when the executor tries to invoke a tool
-    /// that doesn't exist.
+    /// This method checks all ToolCall steps in the plan, including retry
+    /// fallbacks and steps inside conditionals, loops and subgoals, and ensures that
+    /// each referenced tool is available in the provided registry. This prevents runtime errors when the executor
+    /// tries to invoke a tool that doesn't exist. It also checks that
+    /// conditions have valid patterns and paths, that conditionals
+    /// without a check test a step that runs before them, and that
+    /// subgoals and parallel groups have steps.
     /// 
     /// # Arguments
     /// * `plan` - The plan to validate
@@ -180,8 +457,15 @@ impl Planner {
     /// 
     /// # Returns
     /// * `Result<()>` - Ok if all tools exist, error otherwise
+    #[tracing::instrument(
+        name = "planner.validate_plan",
+        skip_all,
+        fields(steps = plan.steps.len())
+    )]
     pub fn validate_plan(&self, plan: &Plan, registry: &ToolRegistry) -> Result<()> {
-        for step in &plan.steps {
+        validate_control_flow(&plan.steps)?;
+
+        for step in plan.all_steps() {
             if let Step::ToolCall(tool_call) = step {
                 // Check if the tool exists in the registry
                 if registry.get(&tool_call.tool_name).is_none() {
@@ -204,12 +488,109 @@ impl Planner {
     }
 }
 
+/// Checks the conditions, conditionals, loops, subgoals and parallel groups
+/// in `steps`.
+/// 
+/// The steps of a subgoal run like a plan of their own, so conditionals in
+/// them are checked against the subgoal's steps rather than the plan's.
+fn validate_control_flow(steps: &[Step]) -> Result<()> {
+    for (index, step) in steps.iter().enumerate() {
+        let mut pending = vec![step];
+        while let Some(nested) = pending.pop() {
+            match nested {
+                Step::Conditional { check, step, condition, .. } => {
+                    condition.validate()?;
+                    validate_tested_step(index, check.is_some(), *step)?;
+                }
+                Step::Loop { body, until, max_iterations } => {
+                    until.validate()?;
+                    validate_loop(index, body, *max_iterations)?;
+                }
+                Step::Subgoal { goal, steps, .. } => {
+                    if steps.is_empty() {
+                        return Err(agent_core::AgentError::Planning(format!(
+                            "Subgoal '{}' in step {} has no steps",
+                            goal, index
+                        )));
+                    }
+                    validate_control_flow(steps)?;
+                    continue;
+                }
+                Step::Parallel { steps } if steps.is_empty() => {
+                    return Err(agent_core::AgentError::Planning(format!(
+                        "Parallel group in step {} has no steps",
+                        index
+                    )));
+                }
+                _ => {}
+            }
+            pending.extend(nested.children());
+        }
+    }
+    Ok(())
+}
+
+/// One level of a hierarchical plan as written by the LLM: either the
+/// steps of the goal or the subgoals it is split into.
+#[derive(Deserialize)]
+struct Decomposition {
+    reasoning: String,
+    #[serde(default)]
+    steps: Vec<Step>,
+    #[serde(default)]
+    subgoals: Vec<SubgoalSpec>,
+}
+
+/// A subgoal named in a [`Decomposition`], not yet planned.
+#[derive(Deserialize)]
+struct SubgoalSpec {
+    goal: String,
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+/// Checks that a loop in the plan step at `index` has steps to repeat and
+/// a bounded number of iterations.
+fn validate_loop(index: usize, body: &[Step], max_iterations: u32) -> Result<()> {
+    let problem = if body.is_empty() {
+        "has no steps to repeat".to_string()
+    } else if !(1..=MAX_LOOP_ITERATIONS).contains(&max_iterations) {
+        format!(
+            "runs {} iterations; loops run between 1 and {}",
+            max_iterations, MAX_LOOP_ITERATIONS
+        )
+    } else {
+        return Ok(());
+    };
+    Err(agent_core::AgentError::Planning(format!(
+        "Loop in step {} {}",
+        index, problem
+    )))
+}
+
+/// Checks that a conditional in the plan step at `index` tests either its
+/// own check or a step that runs before it.
+fn validate_tested_step(index: usize, has_check: bool, step: Option<usize>) -> Result<()> {
+    let problem = match (has_check, step) {
+        (true, None) => return Ok(()),
+        (true, Some(_)) => "has both a check and a step to test".to_string(),
+        (false, Some(step)) if step < index => return Ok(()),
+        (false, Some(step)) => format!("tests step {}, which does not run before it", step),
+        (false, None) if index > 0 => return Ok(()),
+        (false, None) => "has no check and no earlier step to test".to_string(),
+    };
+    Err(agent_core::AgentError::Planning(format!(
+        "Conditional in step {} {}",
+        index, problem
+    )))
+}
+
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
-    use crate::types::ToolCall;
+    use crate::types::{Condition, ToolCall};
     use async_trait::async_trait;
     
     /// Mock LLM provider for testing
@@ -447,6 +828,129 @@ mod tests {
         assert!(error_msg.contains("calculator"),
                 "Error should list available tools: {}", error_msg);
     }
+
+    #[test]
+    fn test_validate_plan_checks_retry_fallbacks() {
+        let planner = create_test_planner(vec![]);
+
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(tools::Calculator::new()));
+
+        // Retry policies are read from the model's JSON
+        let plan: Plan = serde_json::from_value(json!({
+            "reasoning": "Retry the calculator, then fall back",
+            "steps": [{
+                "type": "tool_call",
+                "tool_name": "calculator",
+                "parameters": {"operation": "add", "a": 1, "b": 2},
+                "retry": {
+                    "max_retries": 2,
+                    "fallback": {"type": "tool_call", "tool_name": "abacus", "parameters": {}}
+                }
+            }]
+        }))
+        .unwrap();
+
+        let Step::ToolCall(tool_call) = &plan.steps[0] else {
+            panic!("Expected a tool call");
+        };
+        assert_eq!(tool_call.retry.as_ref().unwrap().max_retries, 2);
+
+        let result = planner.validate_plan(&plan, &registry);
+        assert!(result.unwrap_err().to_string().contains("abacus"));
+    }
+
+    #[test]
+    fn test_parse_and_validate_control_flow_steps() {
+        let planner = create_test_planner(vec![]);
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(tools::Calculator::new()));
+
+        let plan = planner
+            .parse_plan(
+                r#"{"reasoning": "Retry until positive", "steps": [
+                    {"type": "loop", "until": {"type": "contains", "text": "positive"}, "body": [
+                        {"type": "tool_call", "tool_name": "calculator", "parameters": {}}
+                    ]},
+                    {"type": "conditional",
+                     "check": {"type": "tool_call", "tool_name": "calculator", "parameters": {}},
+                     "condition": {"type": "failed"},
+                     "then": [{"type": "tool_call", "tool_name": "abacus", "parameters": {}}]}
+                ]}"#,
+            )
+            .unwrap();
+
+        let Step::Loop { until, max_iterations, .. } = &plan.steps[0] else {
+            panic!("Expected a loop");
+        };
+        assert_eq!(until, &Condition::Contains { text: "positive".to_string() });
+        assert_eq!(*max_iterations, 5);
+        let Step::Conditional { otherwise, .. } = &plan.steps[1] else {
+            panic!("Expected a conditional");
+        };
+        assert!(otherwise.is_empty());
+        assert_eq!(plan.all_steps().len(), 5);
+
+        // Tools nested in a branch are validated too
+        let result = planner.validate_plan(&plan, &registry);
+        assert!(result.unwrap_err().to_string().contains("abacus"));
+    }
+
+    #[test]
+    fn test_validate_conditionals_on_earlier_steps() {
+        let planner = create_test_planner(vec![]);
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(tools::Calculator::new()));
+        let plan_with = |conditional: &str| {
+            planner
+                .parse_plan(&format!(
+                    r#"{{"reasoning": "Branch on the lookup", "steps": [
+                        {{"type": "tool_call", "tool_name": "calculator", "parameters": {{}}}},
+                        {{"type": "reasoning", "text": "Checking"}},
+                        {}
+                    ]}}"#,
+                    conditional
+                ))
+                .unwrap()
+        };
+
+        let plan = plan_with(
+            r#"{"type": "conditional", "step": 0,
+                "condition": {"type": "json_path", "path": "$.result", "equals": 4},
+                "then": [{"type": "response", "text": "Four"}]}"#,
+        );
+        let Step::Conditional { check, step, .. } = &plan.steps[2] else {
+            panic!("Expected a conditional");
+        };
+        assert!(check.is_none());
+        assert_eq!(*step, Some(0));
+        assert!(planner.validate_plan(&plan, &registry).is_ok());
+
+        // The previous step is tested when no step is named
+        let plan = plan_with(
+            r#"{"type": "conditional", "condition": {"type": "matches", "pattern": "^Check"},
+                "then": []}"#,
+        );
+        assert!(planner.validate_plan(&plan, &registry).is_ok());
+
+        for (conditional, problem) in [
+            (r#"{"type": "conditional", "step": 2, "condition": {"type": "succeeded"},
+                 "then": []}"#, "does not run before it"),
+            (r#"{"type": "conditional", "step": 0, "condition": {"type": "succeeded"},
+                 "check": {"type": "reasoning", "text": "Again"}, "then": []}"#, "both"),
+            (r#"{"type": "conditional", "condition": {"type": "matches", "pattern": "("},
+                 "then": []}"#, "Invalid pattern"),
+            (r#"{"type": "loop", "until": {"type": "json_path", "path": "$..x"},
+                 "body": [{"type": "reasoning", "text": "Again"}]}"#, "Invalid JSON path"),
+            (r#"{"type": "loop", "until": {"type": "succeeded"}, "body": []}"#, "no steps"),
+            (r#"{"type": "loop", "until": {"type": "succeeded"}, "max_iterations": 0,
+                 "body": [{"type": "reasoning", "text": "Again"}]}"#, "between 1 and 100"),
+            (r#"{"type": "parallel", "steps": []}"#, "Parallel group in step 2 has no steps"),
+        ] {
+            let error = planner.validate_plan(&plan_with(conditional), &registry).unwrap_err();
+            assert!(error.to_string().contains(problem), "{}", error);
+        }
+    }
     
     #[test]
     fn test_validate_plan_with_multiple_invalid_tools() {
@@ -605,6 +1109,63 @@ mod tests {
                 "Should emphasize JSON format");
     }
     
+    #[test]
+    fn test_build_system_prompt_locale() {
+        let mut planner = create_test_planner(vec![]);
+        assert!(!planner.build_system_prompt(&[]).contains("locale"));
+
+        planner.set_locale(Some("de-AT".to_string()));
+        let prompt = planner.build_system_prompt(&[]);
+        assert!(prompt.contains("locale 'de-AT'"));
+        assert!(prompt.ends_with("Do not include any other text."));
+    }
+
+    #[test]
+    fn test_build_system_prompt_image_generation() {
+        // Image generation steps are only offered when enabled
+        let planner = create_test_planner(vec![]);
+        assert!(!planner.build_system_prompt(&[]).contains("image_generation"));
+
+        let planner = create_test_planner(vec![]).with_image_generation(true);
+        assert!(planner.build_system_prompt(&[]).contains("\"type\": \"image_generation\""));
+    }
+
+    #[test]
+    fn test_model_profiles_enable_text_generation() {
+        let planner = create_test_planner(vec![]);
+        assert!(!planner.build_system_prompt(&[]).contains("text_generation"));
+
+        let planner = create_test_planner(vec![])
+            .with_model_profiles(vec!["summarizer".to_string(), "coder".to_string()]);
+        let prompt = planner.build_system_prompt(&[]);
+        assert!(prompt.contains("\"type\": \"text_generation\""));
+        assert!(prompt.contains("Available profiles: summarizer, coder"));
+
+        let plan = planner
+            .parse_plan(r#"{"reasoning": "Code it", "steps": [{"type": "text_generation", "prompt": "Write a parser", "profile": "coder"}]}"#)
+            .unwrap();
+        match &plan.steps[0] {
+            Step::TextGeneration { prompt, profile } => {
+                assert_eq!(prompt, "Write a parser");
+                assert_eq!(profile.as_deref(), Some("coder"));
+            }
+            _ => panic!("Expected a text generation step"),
+        }
+    }
+
+    #[test]
+    fn test_parse_plan_with_image_generation() {
+        let planner = create_test_planner(vec![]);
+        let plan = planner
+            .parse_plan(r#"{"reasoning": "Draw it", "steps": [{"type": "image_generation", "prompt": "a red fox"}]}"#)
+            .expect("Should parse image generation step");
+
+        match &plan.steps[0] {
+            Step::ImageGeneration { prompt } => assert_eq!(prompt, "a red fox"),
+            _ => panic!("First step should be image generation"),
+        }
+    }
+    
     #[tokio::test]
     async fn test_create_plan_with_mock_llm() {
         // Test end-to-end plan creation with mocked LLM
@@ -663,6 +1224,93 @@ mod tests {
         assert!(result.is_err(), "Should fail when LLM has no response");
     }
     
+    #[tokio::test]
+    async fn test_create_hierarchical_plan_decomposes_recursively() {
+        let responses = [
+            r#"{"reasoning": "Research, then write", "subgoals": [
+                {"goal": "Research the topic", "profile": "researcher"},
+                {"goal": "Write the report"}
+            ]}"#,
+            r#"{"reasoning": "Two sources", "subgoals": [
+                {"goal": "Search papers"}, {"goal": "Search news"}
+            ]}"#,
+            // Below the depth limit subgoals are planned directly
+            r#"{"reasoning": "Search", "steps": [{"type": "tool_call", "tool_name": "calculator", "parameters": {}}]}"#,
+            r#"{"reasoning": "Search", "steps": [{"type": "reasoning", "text": "News"}]}"#,
+            r#"{"reasoning": "Simple enough", "steps": [{"type": "response", "text": "Report"}]}"#,
+        ];
+        let planner = create_test_planner(responses.iter().map(|r| r.to_string()).collect())
+            .with_model_profiles(vec!["researcher".to_string()]);
+
+        let plan = planner.create_hierarchical_plan("Write a report", &[], 2).await.unwrap();
+        assert_eq!(plan.reasoning, "Research, then write");
+        assert_eq!(plan.steps.len(), 2);
+        let Step::Subgoal { goal, profile, steps } = &plan.steps[0] else {
+            panic!("Expected a subgoal");
+        };
+        assert_eq!(goal, "Research the topic");
+        assert_eq!(profile.as_deref(), Some("researcher"));
+        assert!(matches!(&steps[0], Step::Subgoal { steps, .. } if steps.len() == 1));
+        assert!(matches!(
+            &plan.steps[1],
+            Step::Subgoal { profile: None, steps, .. } if matches!(steps[..], [Step::Response { .. }])
+        ));
+        assert_eq!(plan.all_steps().len(), 7);
+
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(tools::Calculator::new()));
+        assert!(planner.validate_plan(&plan, &registry).is_ok());
+
+        let prompt = planner.build_decomposition_prompt(&[]);
+        assert!(prompt.contains("\"subgoals\""));
+        assert!(prompt.contains("Available profiles: researcher\n"));
+        assert!(prompt.ends_with("Do not include any other text."));
+    }
+
+    #[tokio::test]
+    async fn test_hierarchical_plan_rejects_unknown_profiles_and_empty_subgoals() {
+        let planner = create_test_planner(vec![
+            r#"{"reasoning": "Split", "subgoals": [{"goal": "Code", "profile": "coder"}]}"#
+                .to_string(),
+        ]);
+        let error = planner.create_hierarchical_plan("Build it", &[], 1).await.unwrap_err();
+        assert!(error.to_string().contains("unknown model profile 'coder'"));
+
+        let plan = planner
+            .parse_plan(r#"{"reasoning": "Split", "steps": [{"type": "subgoal", "goal": "Code", "steps": []}]}"#)
+            .unwrap();
+        let error = planner.validate_plan(&plan, &ToolRegistry::new()).unwrap_err();
+        assert!(error.to_string().contains("Subgoal 'Code' in step 0 has no steps"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_plan_yields_steps_then_plan() {
+        use futures::StreamExt;
+
+        let plan_json = r#"{
+            "reasoning": "Greet the user",
+            "steps": [
+                {"type": "reasoning", "text": "The user said hello"},
+                {"type": "response", "text": "Hello!"}
+            ]
+        }"#;
+        let planner = create_test_planner(vec![plan_json.to_string()]);
+
+        let events: Vec<_> = planner.stream_plan("Hi", &[])
+            .await
+            .expect("Should start streaming")
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], Ok(PlanEvent::Step(Step::Reasoning { .. }))));
+        assert!(matches!(
+            &events[1],
+            Ok(PlanEvent::Step(Step::Response { text })) if text == "Hello!"
+        ));
+        assert!(matches!(&events[2], Ok(PlanEvent::Complete(plan)) if plan.steps.len() == 2));
+    }
+    
     #[test]
     fn test_extract_json_with_pure_json() {
         // Test JSON extraction when response is pure JSON
@@ -0,0 +1,254 @@
+//! Incremental parsing of streamed plans.
+//!
+//! A plan is only useful once its JSON is complete, but its steps are not:
+//! each object in the `steps` array can be validated (and often executed)
+//! the moment its closing brace arrives. [`PlanStreamParser`] scans response
+//! text as it streams in and hands out every step as soon as it is complete.
+//! [`Planner::stream_plan`](crate::Planner::stream_plan) wraps it around a
+//! provider's token stream and yields [`PlanEvent`]s.
+
+use agent_core::{AgentError, Result};
+use futures::Stream;
+use std::pin::Pin;
+
+use crate::types::{Plan, Step};
+
+/// Boxed stream of plan events
+pub type PlanStream = Pin<Box<dyn Stream<Item = Result<PlanEvent>> + Send>>;
+
+/// An event in a streamed plan
+#[derive(Debug, Clone)]
+pub enum PlanEvent {
+    /// A step whose JSON object has been fully received
+    Step(Step),
+    /// The complete plan, sent as the last event
+    ///
+    /// Its steps are the same as the ones streamed before it.
+    Complete(Plan),
+}
+
+/// Extracts plan steps from response text as it arrives.
+///
+/// Text before the plan's opening brace (prose, code fences) and after its
+/// closing brace is ignored, like [`Planner::parse_plan`] does for complete
+/// responses.
+///
+/// [`Planner::parse_plan`]: crate::Planner::parse_plan
+///
+/// # Example
+///
+/// ```
+/// use planner::{PlanStreamParser, Step};
+///
+/// let mut parser = PlanStreamParser::new();
+/// assert!(parser.push(r#"{"reasoning": "Greet", "steps": [{"type": "resp"#).unwrap().is_empty());
+///
+/// let steps = parser.push(r#"onse", "text": "Hi"}, {"type""#).unwrap();
+/// assert!(matches!(&steps[..], [Step::Response { text }] if text == "Hi"));
+///
+/// parser.push(r#": "reasoning", "text": "Done"}]}"#).unwrap();
+/// assert_eq!(parser.finish().unwrap().steps.len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct PlanStreamParser {
+    /// All text received so far
+    buffer: String,
+    /// Byte offset up to which `buffer` has been scanned
+    scanned: usize,
+    /// Nesting depth of objects and arrays
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Start of the last string seen directly inside the plan object
+    string_start: usize,
+    /// The last string seen directly inside the plan object
+    last_key: String,
+    /// Whether the scanner is inside the `steps` array
+    in_steps: bool,
+    /// Start of the step object being received
+    step_start: Option<usize>,
+    /// Byte range of the plan object
+    plan_start: Option<usize>,
+    plan_end: Option<usize>,
+    /// Number of steps handed out so far
+    steps_parsed: usize,
+}
+
+impl PlanStreamParser {
+    /// Creates a parser with no input.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of steps returned by [`push`](Self::push) so far.
+    pub fn steps_parsed(&self) -> usize {
+        self.steps_parsed
+    }
+
+    /// Adds a chunk of response text and returns the steps it completed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Planning` if a completed step is not a valid
+    /// step object, so a malformed plan is rejected before it finishes
+    /// streaming.
+    pub fn push(&mut self, chunk: &str) -> Result<Vec<Step>> {
+        self.buffer.push_str(chunk);
+        let mut steps = Vec::new();
+
+        while self.scanned < self.buffer.len() && self.plan_end.is_none() {
+            let index = self.scanned;
+            let byte = self.buffer.as_bytes()[index];
+            self.scanned += 1;
+
+            if self.plan_start.is_none() {
+                if byte == b'{' {
+                    self.plan_start = Some(index);
+                    self.depth = 1;
+                }
+                continue;
+            }
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    if self.depth == 1 {
+                        self.last_key = self.buffer[self.string_start..index].to_string();
+                    }
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => {
+                    self.in_string = true;
+                    self.string_start = index + 1;
+                }
+                b'{' | b'[' => {
+                    if byte == b'[' && self.depth == 1 && self.last_key == "steps" {
+                        self.in_steps = true;
+                    } else if byte == b'{' && self.depth == 2 && self.in_steps {
+                        self.step_start = Some(index);
+                    }
+                    self.depth += 1;
+                }
+                b'}' | b']' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    match self.depth {
+                        0 => self.plan_end = Some(index),
+                        1 => self.in_steps = false,
+                        2 if self.in_steps => {
+                            if let Some(start) = self.step_start.take() {
+                                steps.push(self.parse_step(start, index)?);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(steps)
+    }
+
+    /// Parses the complete plan once the response has ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AgentError::Planning` if the response ended before the plan
+    /// object was closed, or if it is not a valid plan.
+    pub fn finish(self) -> Result<Plan> {
+        let (Some(start), Some(end)) = (self.plan_start, self.plan_end) else {
+            return Err(AgentError::Planning(format!(
+                "Plan stream ended before the plan was complete. Response was: {}",
+                self.buffer
+            )));
+        };
+        let json = &self.buffer[start..=end];
+        serde_json::from_str(json).map_err(|e| {
+            AgentError::Planning(format!(
+                "Failed to parse plan JSON: {}. Response was: {}",
+                e, json
+            ))
+        })
+    }
+
+    fn parse_step(&mut self, start: usize, end: usize) -> Result<Step> {
+        let json = &self.buffer[start..=end];
+        let step = serde_json::from_str(json).map_err(|e| {
+            AgentError::Planning(format!(
+                "Failed to parse plan step {}: {}. Step was: {}",
+                self.steps_parsed + 1,
+                e,
+                json
+            ))
+        })?;
+        self.steps_parsed += 1;
+        Ok(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = r#"Here is the plan:
+```json
+{
+  "reasoning": "Use \"steps\" [carefully] {really}",
+  "steps": [
+    {"type": "tool_call", "tool_name": "calculator", "parameters": {"expression": "2 + {2}"}},
+    {"type": "reasoning", "text": "The answer is in ]"},
+    {"type": "response", "text": "4"}
+  ]
+}
+```"#;
+
+    #[test]
+    fn test_steps_are_emitted_as_they_complete() {
+        let mut parser = PlanStreamParser::new();
+        let mut emitted = Vec::new();
+        for chunk in PLAN.as_bytes().chunks(3) {
+            let chunk = std::str::from_utf8(chunk).unwrap();
+            let steps = parser.push(chunk).unwrap();
+            assert!(steps.len() <= 1);
+            emitted.extend(steps.into_iter().map(|step| (parser.steps_parsed(), step)));
+        }
+
+        assert_eq!(emitted.len(), 3);
+        assert!(matches!(
+            &emitted[0].1,
+            Step::ToolCall(call) if call.tool_name == "calculator"
+        ));
+        assert!(matches!(&emitted[2].1, Step::Response { text } if text == "4"));
+
+        let plan = parser.finish().unwrap();
+        assert_eq!(plan.reasoning, "Use \"steps\" [carefully] {really}");
+        assert_eq!(plan.steps.len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_step_fails_before_plan_completes() {
+        let mut parser = PlanStreamParser::new();
+        let result = parser.push(r#"{"steps": [{"type": "teleport"}, {"type": "#);
+        assert!(matches!(
+            result,
+            Err(AgentError::Planning(msg)) if msg.contains("step 1")
+        ));
+    }
+
+    #[test]
+    fn test_incomplete_plan_fails_to_finish() {
+        let mut parser = PlanStreamParser::new();
+        let steps = parser
+            .push(r#"{"reasoning": "r", "steps": [{"type": "response", "text": "Hi"}"#)
+            .unwrap();
+        assert_eq!(steps.len(), 1);
+        assert!(matches!(parser.finish(), Err(AgentError::Planning(_))));
+    }
+}
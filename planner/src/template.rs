@@ -0,0 +1,515 @@
+//! Reusable plans with parameters.
+//!
+//! Common workflows such as "research a topic and summarize it" don't need a
+//! fresh plan from the model every time. A [`PlanTemplate`] is a plan whose
+//! steps contain `{{params.<name>}}` placeholders for its declared
+//! parameters; [`PlanTemplate::instantiate`] checks the arguments against
+//! the declarations and fills them in. A [`TemplateLibrary`] loads every
+//! template in a directory.
+//!
+//! Templates are JSON files in the plan format, with a name, a description
+//! and the parameter declarations added:
+//!
+//! ```json
+//! {
+//!   "name": "research_and_summarize",
+//!   "description": "Search the web for a topic and summarize the findings",
+//!   "parameters": [
+//!     {"name": "topic", "type": "string", "description": "What to research"},
+//!     {"name": "words", "type": "integer", "default": 200}
+//!   ],
+//!   "reasoning": "Research {{params.topic}}",
+//!   "steps": [
+//!     {"type": "tool_call", "tool_name": "web_search",
+//!      "parameters": {"query": "{{params.topic}}"}},
+//!     {"type": "text_generation",
+//!      "prompt": "Summarize {{params.topic}} in {{params.words}} words"}
+//!   ]
+//! }
+//! ```
+//!
+//! A string that is exactly one placeholder is replaced by the argument
+//! itself, so numbers, booleans, arrays and objects keep their type in tool
+//! parameters. Placeholders inside longer strings are replaced by the
+//! argument's text. `{{context.<path>}}` placeholders are left for the
+//! executor.
+
+use agent_core::{AgentError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::types::{Plan, Step};
+
+const OPEN: &str = "{{params.";
+
+/// JSON type an argument must have
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    /// Any JSON string
+    #[default]
+    String,
+    /// A whole number
+    Integer,
+    /// Any number
+    Number,
+    /// `true` or `false`
+    Boolean,
+    /// A JSON array
+    Array,
+    /// A JSON object
+    Object,
+}
+
+impl ParameterType {
+    /// Whether `value` has this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Number => value.is_number(),
+            Self::Boolean => value.is_boolean(),
+            Self::Array => value.is_array(),
+            Self::Object => value.is_object(),
+        }
+    }
+}
+
+/// A parameter declared by a template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateParameter {
+    /// Name used in `{{params.<name>}}` placeholders and arguments
+    pub name: String,
+    /// Type the argument must have
+    #[serde(default, rename = "type")]
+    pub kind: ParameterType,
+    /// What the parameter is for
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Value used when no argument is given; parameters without one are
+    /// required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+impl TemplateParameter {
+    /// Whether an argument must be given
+    pub fn is_required(&self) -> bool {
+        self.default.is_none()
+    }
+}
+
+/// A plan with parameters, instantiated with arguments instead of planned
+/// by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanTemplate {
+    /// Name the template is looked up by
+    pub name: String,
+    /// What the workflow does
+    #[serde(default)]
+    pub description: String,
+    /// Parameters the steps may refer to
+    #[serde(default)]
+    pub parameters: Vec<TemplateParameter>,
+    /// Reasoning of the instantiated plan; may contain placeholders
+    #[serde(default)]
+    pub reasoning: String,
+    /// Steps in the plan format, with placeholders
+    pub steps: Vec<Value>,
+}
+
+impl PlanTemplate {
+    /// Parse and validate a template from JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is not a template or the template is
+    /// invalid (see [`validate`](Self::validate)).
+    pub fn from_json(json: &str) -> Result<Self> {
+        let template: Self = serde_json::from_str(json)?;
+        template.validate()?;
+        Ok(template)
+    }
+
+    /// Check that the template is usable
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty, a parameter is declared twice
+    /// or has a default of the wrong type, a step is not a valid step, or a
+    /// placeholder refers to an undeclared parameter.
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(AgentError::Planning("Template name is empty".to_string()));
+        }
+
+        let mut declared = BTreeMap::new();
+        for parameter in &self.parameters {
+            if declared
+                .insert(parameter.name.as_str(), parameter)
+                .is_some()
+            {
+                return Err(self.error(format!("parameter '{}' is declared twice", parameter.name)));
+            }
+            if let Some(default) = &parameter.default
+                && !parameter.kind.matches(default)
+            {
+                return Err(self.error(format!(
+                    "default of parameter '{}' is not of type {:?}",
+                    parameter.name, parameter.kind
+                )));
+            }
+        }
+
+        let mut used = Vec::new();
+        placeholders(&Value::String(self.reasoning.clone()), &mut used);
+        for (index, step) in self.steps.iter().enumerate() {
+            serde_json::from_value::<Step>(step.clone()).map_err(|e| {
+                self.error(format!("step {} is not a valid step: {}", index + 1, e))
+            })?;
+            placeholders(step, &mut used);
+        }
+        if let Some(name) = used
+            .iter()
+            .find(|name| !declared.contains_key(name.as_str()))
+        {
+            return Err(self.error(format!(
+                "placeholder refers to undeclared parameter '{}'",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create a plan from the template with the given arguments
+    ///
+    /// Missing optional arguments take their defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required argument is missing, an argument is
+    /// not declared, or an argument has the wrong type.
+    pub fn instantiate(&self, arguments: &Map<String, Value>) -> Result<Plan> {
+        if let Some(name) = arguments
+            .keys()
+            .find(|name| !self.parameters.iter().any(|p| &p.name == *name))
+        {
+            return Err(self.error(format!("unknown argument '{}'", name)));
+        }
+
+        let mut values = BTreeMap::new();
+        for parameter in &self.parameters {
+            let value = match (arguments.get(&parameter.name), &parameter.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => default,
+                (None, None) => {
+                    return Err(
+                        self.error(format!("missing required argument '{}'", parameter.name))
+                    );
+                }
+            };
+            if !parameter.kind.matches(value) {
+                return Err(self.error(format!(
+                    "argument '{}' must be of type {:?}, got {}",
+                    parameter.name, parameter.kind, value
+                )));
+            }
+            values.insert(parameter.name.as_str(), value);
+        }
+
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| Ok(serde_json::from_value(substitute(step, &values))?))
+            .collect::<Result<Vec<Step>>>()?;
+        Ok(Plan::new(steps, substitute_text(&self.reasoning, &values)))
+    }
+
+    fn error(&self, reason: String) -> AgentError {
+        AgentError::Planning(format!("Plan template '{}': {}", self.name, reason))
+    }
+}
+
+/// Plan templates by name
+///
+/// # Example
+///
+/// ```no_run
+/// use planner::TemplateLibrary;
+/// use serde_json::json;
+///
+/// # fn main() -> agent_core::Result<()> {
+/// let library = TemplateLibrary::load_dir("examples/templates")?;
+/// let arguments = json!({"topic": "solid-state batteries"});
+/// let plan = library.instantiate("research_and_summarize", arguments.as_object().unwrap())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TemplateLibrary {
+    templates: BTreeMap<String, PlanTemplate>,
+}
+
+impl TemplateLibrary {
+    /// Create an empty library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every `.json` file in `dir` as a template
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read, or a file is not a
+    /// valid template or repeats another template's name.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut library = Self::new();
+        for path in paths {
+            let template = PlanTemplate::from_json(&fs::read_to_string(&path)?).map_err(|e| {
+                AgentError::Planning(format!("Failed to load {}: {}", path.display(), e))
+            })?;
+            library.add(template)?;
+        }
+        Ok(library)
+    }
+
+    /// Add a template
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template is invalid or its name is taken.
+    pub fn add(&mut self, template: PlanTemplate) -> Result<()> {
+        template.validate()?;
+        if self.templates.contains_key(&template.name) {
+            return Err(AgentError::Planning(format!(
+                "Plan template '{}' is defined twice",
+                template.name
+            )));
+        }
+        self.templates.insert(template.name.clone(), template);
+        Ok(())
+    }
+
+    /// Look up a template by name
+    pub fn get(&self, name: &str) -> Option<&PlanTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Names of all templates, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.templates.keys().map(String::as_str).collect()
+    }
+
+    /// Number of templates
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Check if the library has no templates
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Instantiate the template called `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such template or the arguments don't
+    /// match its parameters.
+    pub fn instantiate(&self, name: &str, arguments: &Map<String, Value>) -> Result<Plan> {
+        self.get(name)
+            .ok_or_else(|| AgentError::Planning(format!("Unknown plan template '{}'", name)))?
+            .instantiate(arguments)
+    }
+}
+
+/// Collect the parameter names of all placeholders in `value`
+fn placeholders(value: &Value, names: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find(OPEN) {
+                let after = &rest[start + OPEN.len()..];
+                let Some(end) = after.find("}}") else {
+                    break;
+                };
+                names.push(after[..end].trim().to_string());
+                rest = &after[end + 2..];
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| placeholders(item, names)),
+        Value::Object(map) => map.values().for_each(|item| placeholders(item, names)),
+        _ => {}
+    }
+}
+
+/// Fill placeholders in every string of `value`
+fn substitute(value: &Value, values: &BTreeMap<&str, &Value>) -> Value {
+    match value {
+        Value::String(text) => {
+            let whole = text
+                .trim()
+                .strip_prefix(OPEN)
+                .and_then(|inner| inner.strip_suffix("}}"))
+                .filter(|name| !name.contains("}}"))
+                .and_then(|name| values.get(name.trim()));
+            match whole {
+                Some(value) => (*value).clone(),
+                None => Value::String(substitute_text(text, values)),
+            }
+        }
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| substitute(item, values)).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), substitute(item, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Fill placeholders in `text`, inserting strings as-is and other values as
+/// JSON
+fn substitute_text(text: &str, values: &BTreeMap<&str, &Value>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        let after = &rest[start + OPEN.len()..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match values.get(after[..end].trim()) {
+            Some(Value::String(text)) => rendered.push_str(text),
+            Some(other) => rendered.push_str(&other.to_string()),
+            None => rendered.push_str(&rest[start..start + OPEN.len() + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TRIAGE: &str = r#"{
+        "name": "triage",
+        "description": "Label an issue",
+        "parameters": [
+            {"name": "issue", "type": "string"},
+            {"name": "labels", "type": "array", "default": ["bug", "feature"]},
+            {"name": "limit", "type": "integer", "default": 3}
+        ],
+        "reasoning": "Triage {{params.issue}}",
+        "steps": [
+            {"type": "tool_call", "tool_name": "search",
+             "parameters": {"query": "{{params.issue}}", "limit": "{{params.limit}}"}},
+            {"type": "text_generation",
+             "prompt": "Pick one of {{params.labels}} for {{params.issue}} ({{context.locale}})"}
+        ]
+    }"#;
+
+    fn arguments(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_instantiate_fills_typed_arguments_and_defaults() {
+        let template = PlanTemplate::from_json(TRIAGE).unwrap();
+        let plan = template
+            .instantiate(&arguments(json!({"issue": "crash on start", "limit": 5})))
+            .unwrap();
+
+        assert_eq!(plan.reasoning, "Triage crash on start");
+        let Step::ToolCall(call) = &plan.steps[0] else {
+            panic!("expected a tool call");
+        };
+        assert_eq!(
+            call.parameters,
+            json!({"query": "crash on start", "limit": 5})
+        );
+        let Step::TextGeneration { prompt, .. } = &plan.steps[1] else {
+            panic!("expected text generation");
+        };
+        assert_eq!(
+            prompt,
+            r#"Pick one of ["bug","feature"] for crash on start ({{context.locale}})"#
+        );
+    }
+
+    #[test]
+    fn test_instantiate_rejects_bad_arguments() {
+        let template = PlanTemplate::from_json(TRIAGE).unwrap();
+        let error = |args| {
+            template
+                .instantiate(&arguments(args))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(error(json!({})).contains("missing required argument 'issue'"));
+        assert!(error(json!({"issue": "x", "limit": "3"})).contains("argument 'limit' must be"));
+        assert!(error(json!({"issue": "x", "owner": "me"})).contains("unknown argument 'owner'"));
+    }
+
+    #[test]
+    fn test_validate_rejects_broken_templates() {
+        let mut template = PlanTemplate::from_json(TRIAGE).unwrap();
+        template
+            .steps
+            .push(json!({"type": "response", "text": "{{params.missing}}"}));
+        let err = template.validate().unwrap_err();
+        assert!(err.to_string().contains("undeclared parameter 'missing'"));
+
+        let mut template = PlanTemplate::from_json(TRIAGE).unwrap();
+        template.steps.push(json!({"type": "teleport"}));
+        assert!(
+            template
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("step 3")
+        );
+
+        let mut template = PlanTemplate::from_json(TRIAGE).unwrap();
+        template.parameters[2].default = Some(json!("three"));
+        assert!(template.validate().is_err());
+    }
+
+    #[test]
+    fn test_library_loads_example_templates() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/templates");
+        let library = TemplateLibrary::load_dir(dir).unwrap();
+        assert_eq!(library.names(), ["research_and_summarize", "triage_issue"]);
+
+        let plan = library
+            .instantiate(
+                "research_and_summarize",
+                &arguments(json!({"topic": "tidal power"})),
+            )
+            .unwrap();
+        assert!(!plan.steps.is_empty());
+        assert!(library.instantiate("deploy", &Map::new()).is_err());
+
+        let mut library = library;
+        let duplicate = library.get("triage_issue").unwrap().clone();
+        assert!(library.add(duplicate).is_err());
+    }
+}
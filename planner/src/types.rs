@@ -1,3 +1,5 @@
+use agent_core::{AgentError, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -19,11 +21,22 @@ impl Plan {
     pub fn new(steps: Vec<Step>, reasoning: String) -> Self {
         Self { steps, reasoning }
     }
+
+    /// Returns every step of the plan, including steps nested in
+    /// conditionals, loops and retry fallbacks, each before its nested steps.
+    /// 
+    /// Validation that looks for particular steps, such as tool calls,
+    /// should use this rather than `steps` so nested steps are not missed.
+    pub fn all_steps(&self) -> Vec<&Step> {
+        self.steps.iter().flat_map(Step::nested_steps).collect()
+    }
 }
 
 /// Represents a single step in a plan.
 /// 
-/// Steps can be tool calls, reasoning steps, or response generation.
+/// Steps can be tool calls, reasoning steps, image generation, text
+/// generation by a model, or response generation, and conditionals,
+/// loops, subgoals and parallel groups that run other steps.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Step {
@@ -33,6 +46,277 @@ pub enum Step {
     Reasoning { text: String },
     /// A response to be returned to the user
     Response { text: String },
+    /// Generation of an image from a text prompt
+    ImageGeneration { prompt: String },
+    /// Generation of text by a model, such as a summary or code
+    TextGeneration {
+        prompt: String,
+        /// Name of the model profile to run the step with; the default
+        /// model is used if unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+    },
+    /// Runs the `then` steps if `condition` holds and the `otherwise` steps
+    /// if not
+    /// 
+    /// The condition tests the result of `check`, which runs first. Without
+    /// a check it tests the result of the earlier plan step at index `step`,
+    /// counting top-level steps from 0, or of the plan step just before the
+    /// one the conditional is in if `step` is unset.
+    Conditional {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        check: Option<Box<Step>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        step: Option<usize>,
+        condition: Condition,
+        then: Vec<Step>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        otherwise: Vec<Step>,
+    },
+    /// Runs the `body` steps repeatedly until `until` holds for the result
+    /// of the last one, at most `max_iterations` times
+    /// 
+    /// `max_iterations` must be between 1 and [`MAX_LOOP_ITERATIONS`].
+    Loop {
+        body: Vec<Step>,
+        until: Condition,
+        #[serde(default = "default_max_iterations")]
+        max_iterations: u32,
+    },
+    /// A part of the goal, achieved by running `steps` in order
+    /// 
+    /// Subgoals come from hierarchical planning and may nest. Text
+    /// generation steps in the subtree that name no profile run with
+    /// `profile`, unless a nested subgoal assigns its own.
+    Subgoal {
+        goal: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+        steps: Vec<Step>,
+    },
+    /// Runs `steps` at the same time, up to the executor's concurrency limit
+    /// 
+    /// The plan declares the steps independent: none uses the result or a
+    /// side effect of another, such as a file one of them writes, so they
+    /// may run and finish in any order. Steps outside a parallel group run
+    /// one after another.
+    Parallel { steps: Vec<Step> },
+}
+
+impl Step {
+    /// Returns the steps nested directly in this one: the check and
+    /// branches of a conditional, the body of a loop, the steps of a
+    /// subgoal, or the fallback of a tool call's retry policy.
+    pub fn children(&self) -> Vec<&Step> {
+        match self {
+            Step::ToolCall(tool_call) => tool_call
+                .retry
+                .as_ref()
+                .and_then(|retry| retry.fallback.as_deref())
+                .into_iter()
+                .collect(),
+            Step::Conditional { check, then, otherwise, .. } => check
+                .as_deref()
+                .into_iter()
+                .chain(then)
+                .chain(otherwise)
+                .collect(),
+            Step::Loop { body, .. } => body.iter().collect(),
+            Step::Subgoal { steps, .. } | Step::Parallel { steps } => steps.iter().collect(),
+            Step::Reasoning { .. }
+            | Step::Response { .. }
+            | Step::ImageGeneration { .. }
+            | Step::TextGeneration { .. } => Vec::new(),
+        }
+    }
+
+    /// Returns this step and every step nested in it, each before its
+    /// nested steps.
+    pub fn nested_steps(&self) -> Vec<&Step> {
+        let mut all = Vec::new();
+        let mut pending = vec![self];
+        while let Some(step) = pending.pop() {
+            all.push(step);
+            pending.extend(step.children().into_iter().rev());
+        }
+        all
+    }
+
+    /// Returns a copy of this step in which text generation steps that name
+    /// no profile use `profile`.
+    /// 
+    /// Nested subgoals that assign a profile of their own keep it for
+    /// their subtree.
+    pub fn with_default_profile(&self, profile: &str) -> Step {
+        let mut step = self.clone();
+        step.assign_default_profile(profile);
+        step
+    }
+
+    fn assign_default_profile(&mut self, default: &str) {
+        let nested: Vec<&mut Step> = match self {
+            Step::TextGeneration { profile, .. } => {
+                profile.get_or_insert_with(|| default.to_string());
+                return;
+            }
+            Step::Subgoal { profile: Some(_), .. } => return,
+            Step::Subgoal { steps, .. } | Step::Parallel { steps } => steps.iter_mut().collect(),
+            Step::ToolCall(tool_call) => tool_call
+                .retry
+                .as_mut()
+                .and_then(|retry| retry.fallback.as_deref_mut())
+                .into_iter()
+                .collect(),
+            Step::Conditional { check, then, otherwise, .. } => check
+                .as_deref_mut()
+                .into_iter()
+                .chain(then)
+                .chain(otherwise)
+                .collect(),
+            Step::Loop { body, .. } => body.iter_mut().collect(),
+            Step::Reasoning { .. } | Step::Response { .. } | Step::ImageGeneration { .. } => {
+                return;
+            }
+        };
+        for step in nested {
+            step.assign_default_profile(default);
+        }
+    }
+}
+
+/// Most iterations a loop step may ask for, so a plan cannot run a loop
+/// without bound
+pub const MAX_LOOP_ITERATIONS: u32 = 100;
+
+/// Iterations of a loop step when the plan does not say.
+fn default_max_iterations() -> u32 {
+    5
+}
+
+/// A test on the result of a step, deciding a conditional's branch or
+/// when a loop stops.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// The step succeeded
+    Succeeded,
+    /// The step failed
+    Failed,
+    /// The step succeeded and its output contains `text`, ignoring case
+    Contains { text: String },
+    /// The step succeeded and its output is `text`, ignoring surrounding
+    /// whitespace
+    Equals { text: String },
+    /// The step succeeded and its output matches the regular expression
+    /// `pattern`
+    Matches { pattern: String },
+    /// The step succeeded with JSON output that has a value at `path`, such
+    /// as `$.items[0].status`, which equals `equals` if set and otherwise is
+    /// neither `null` nor `false`
+    JsonPath {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        equals: Option<Value>,
+    },
+}
+
+impl Condition {
+    /// Checks the condition against a step's outcome.
+    /// 
+    /// # Arguments
+    /// * `success` - Whether the step succeeded
+    /// * `output` - The step's output, or its error if it failed
+    pub fn holds(&self, success: bool, output: &str) -> bool {
+        match self {
+            Condition::Succeeded => success,
+            Condition::Failed => !success,
+            Condition::Contains { text } => {
+                success && output.to_lowercase().contains(&text.to_lowercase())
+            }
+            Condition::Equals { text } => success && output.trim() == text.trim(),
+            Condition::Matches { pattern } => {
+                success && Regex::new(pattern).is_ok_and(|regex| regex.is_match(output))
+            }
+            Condition::JsonPath { path, equals } => {
+                let Ok(json) = serde_json::from_str::<Value>(output) else {
+                    return false;
+                };
+                let found = parse_json_path(path).and_then(|path| lookup_json_path(&json, &path));
+                match (found, equals) {
+                    (Some(value), Some(expected)) => success && value == expected,
+                    (Some(value), None) => {
+                        success && !matches!(value, Value::Null | Value::Bool(false))
+                    }
+                    (None, _) => false,
+                }
+            }
+        }
+    }
+
+    /// Checks that the condition's pattern or path is well formed.
+    /// 
+    /// # Errors
+    /// Returns a planning error for an invalid regular expression or JSON
+    /// path.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Condition::Matches { pattern } => Regex::new(pattern).map(drop).map_err(|e| {
+                AgentError::Planning(format!("Invalid pattern '{}' in condition: {}", pattern, e))
+            }),
+            Condition::JsonPath { path, .. } => parse_json_path(path).map(drop).ok_or_else(|| {
+                AgentError::Planning(format!("Invalid JSON path '{}' in condition", path))
+            }),
+            Condition::Succeeded
+            | Condition::Failed
+            | Condition::Contains { .. }
+            | Condition::Equals { .. } => Ok(()),
+        }
+    }
+}
+
+/// One step of a JSON path: an object key or an array index.
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parses a JSON path such as `$.items[0].status`, `items[0]["status"]` or
+/// `$`; returns `None` if it is malformed.
+fn parse_json_path(path: &str) -> Option<Vec<PathSegment>> {
+    let path = path.trim();
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']')?;
+            let inner = after[..end].trim();
+            let quoted = ['"', '\'']
+                .iter()
+                .find_map(|quote| inner.strip_prefix(*quote)?.strip_suffix(*quote));
+            segments.push(match quoted {
+                Some(key) => PathSegment::Key(key.to_string()),
+                None => PathSegment::Index(inner.parse().ok()?),
+            });
+            rest = &after[end + 1..];
+        } else {
+            let after = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return None;
+            }
+            segments.push(PathSegment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        }
+    }
+    Some(segments)
+}
+
+/// Follows a parsed JSON path into `value`.
+fn lookup_json_path<'a>(value: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        PathSegment::Key(key) => value.get(key),
+        PathSegment::Index(index) => value.get(index),
+    })
 }
 
 /// Represents a call to a specific tool with parameters.
@@ -42,6 +326,9 @@ pub struct ToolCall {
     pub tool_name: String,
     /// The parameters to pass to the tool (as JSON)
     pub parameters: Value,
+    /// How to retry the call if it fails, if at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
 }
 
 impl ToolCall {
@@ -50,6 +337,170 @@ impl ToolCall {
         Self {
             tool_name,
             parameters,
+            retry: None,
         }
     }
+
+    /// Sets how the call is retried if it fails.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+/// How a failed tool call step is retried before the plan fails.
+/// 
+/// The call is attempted up to `max_retries` more times, waiting `delay_ms`
+/// between attempts, as long as it fails with a retryable error such as a
+/// timeout or rate limit. If every attempt fails, or an attempt fails with
+/// an error a retry cannot fix, and a `fallback` step is set, the fallback
+/// runs in its place and its result becomes the step's result.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Number of attempts after the first one
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Milliseconds to wait before each retry
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Step to run instead if every attempt fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Box<Step>>,
+}
+
+impl RetryPolicy {
+    /// Creates a policy that retries up to `max_retries` times without waiting.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the wait before each retry.
+    pub fn with_delay_ms(mut self, delay_ms: u64) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    /// Sets the step to run if every attempt fails.
+    pub fn with_fallback(mut self, fallback: Step) -> Self {
+        self.fallback = Some(Box::new(fallback));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_matches_condition() {
+        let condition = Condition::Matches {
+            pattern: r"status: (ok|done)".to_string(),
+        };
+        assert!(condition.holds(true, "build status: done"));
+        assert!(!condition.holds(true, "build status: failed"));
+        assert!(!condition.holds(false, "status: ok"));
+        assert!(condition.validate().is_ok());
+
+        let invalid = Condition::Matches {
+            pattern: "(unclosed".to_string(),
+        };
+        assert!(!invalid.holds(true, "(unclosed"));
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_json_path_condition() {
+        let output = json!({"items": [{"status": "open", "urgent": false}], "count": 1}).to_string();
+        let at = |path: &str, equals: Option<Value>| Condition::JsonPath {
+            path: path.to_string(),
+            equals,
+        };
+
+        assert!(at("$.items[0].status", Some(json!("open"))).holds(true, &output));
+        assert!(at(r#"items[0]["status"]"#, Some(json!("open"))).holds(true, &output));
+        assert!(!at("$.items[0].status", Some(json!("closed"))).holds(true, &output));
+        assert!(at("$.count", Some(json!(1))).holds(true, &output));
+
+        // Without `equals` the value must be present and truthy
+        assert!(at("$.count", None).holds(true, &output));
+        assert!(!at("$.items[0].urgent", None).holds(true, &output));
+        assert!(!at("$.items[1]", None).holds(true, &output));
+
+        assert!(!at("$.count", None).holds(false, &output));
+        assert!(!at("$.count", None).holds(true, "not json"));
+
+        assert!(at("$", None).validate().is_ok());
+        assert!(at("$.items[first]", None).validate().is_err());
+        assert!(at("$..items", None).validate().is_err());
+    }
+
+    #[test]
+    fn test_nested_steps_include_loop_bodies_and_parallel_groups() {
+        let on_earlier = Step::Conditional {
+            check: None,
+            step: Some(0),
+            condition: Condition::Succeeded,
+            then: Vec::new(),
+            otherwise: Vec::new(),
+        };
+        let nested = Step::Loop {
+            body: vec![on_earlier],
+            until: Condition::Succeeded,
+            max_iterations: 2,
+        };
+        assert_eq!(nested.nested_steps().len(), 2);
+
+        let parallel: Step = serde_json::from_value(json!({
+            "type": "parallel",
+            "steps": [{"type": "response", "text": "Done"}],
+        }))
+        .unwrap();
+        let Step::Parallel { mut steps } = parallel else {
+            panic!("Expected a parallel step");
+        };
+        steps.insert(0, nested);
+        let parallel = Step::Parallel { steps };
+        let all = parallel.nested_steps();
+        assert_eq!(all.len(), 4);
+        assert!(matches!(all[1], Step::Loop { .. }));
+        assert!(matches!(all[3], Step::Response { .. }));
+    }
+
+    #[test]
+    fn test_subgoal_profiles_apply_to_their_subtree() {
+        let generate = |profile: Option<&str>| Step::TextGeneration {
+            prompt: "Write".to_string(),
+            profile: profile.map(str::to_string),
+        };
+        let step = Step::Loop {
+            body: vec![
+                generate(None),
+                generate(Some("coder")),
+                Step::Subgoal {
+                    goal: "Review".to_string(),
+                    profile: Some("reviewer".to_string()),
+                    steps: vec![generate(None)],
+                },
+            ],
+            until: Condition::Succeeded,
+            max_iterations: 1,
+        };
+
+        let profiles: Vec<_> = step
+            .with_default_profile("writer")
+            .nested_steps()
+            .into_iter()
+            .filter_map(|step| match step {
+                Step::TextGeneration { profile, .. } => Some(profile.clone()),
+                _ => None,
+            })
+            .collect();
+        // The nested subgoal's steps are left for its own profile
+        let writer = Some("writer".to_string());
+        assert_eq!(profiles, vec![writer, Some("coder".to_string()), None]);
+    }
 }
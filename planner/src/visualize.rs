@@ -0,0 +1,307 @@
+//! Plan rendering for documentation and debugging.
+//!
+//! Plans execute their steps in order, so the rendered graph is a chain
+//! from a start node through every step to an end node. Node shapes
+//! distinguish step kinds, and labels show tool names with their parameters.
+
+use crate::{Condition, Plan, Step};
+
+/// Longest label text before it is truncated
+const MAX_LABEL_CHARS: usize = 60;
+
+/// Shape of a rendered step
+#[derive(Clone, Copy)]
+enum Shape {
+    Tool,
+    Reasoning,
+    Response,
+    Image,
+    Text,
+    Control,
+}
+
+/// Node label and shape for a step
+fn describe(index: usize, step: &Step) -> (String, Shape) {
+    let (text, shape) = match step {
+        Step::ToolCall(call) => (
+            format!("{}({})", call.tool_name, call.parameters),
+            Shape::Tool,
+        ),
+        Step::Reasoning { text } => (format!("reason: {}", text), Shape::Reasoning),
+        Step::Response { text } => (format!("respond: {}", text), Shape::Response),
+        Step::ImageGeneration { prompt } => (format!("image: {}", prompt), Shape::Image),
+        Step::TextGeneration { prompt, profile } => {
+            let model = profile.as_deref().unwrap_or("default");
+            (format!("generate [{}]: {}", model, prompt), Shape::Text)
+        }
+        Step::Conditional { check, step, condition, then, otherwise } => {
+            // Labels number steps from 1
+            let subject = match (check, step) {
+                (Some(_), _) => String::new(),
+                (None, Some(step)) => format!("step {} ", step + 1),
+                (None, None) => "previous step ".to_string(),
+            };
+            (
+                format!(
+                    "if {}{}: {} steps, else {}",
+                    subject,
+                    describe_condition(condition),
+                    then.len(),
+                    otherwise.len()
+                ),
+                Shape::Control,
+            )
+        }
+        Step::Loop { body, until, max_iterations } => (
+            format!(
+                "repeat {} steps until {} (at most {})",
+                body.len(),
+                describe_condition(until),
+                max_iterations
+            ),
+            Shape::Control,
+        ),
+        Step::Subgoal { goal, profile, steps } => {
+            let assigned = profile
+                .as_deref()
+                .map(|profile| format!(" [{}]", profile))
+                .unwrap_or_default();
+            (
+                format!("subgoal{}: {} ({} steps)", assigned, goal, steps.len()),
+                Shape::Control,
+            )
+        }
+        Step::Parallel { steps } => (format!("in parallel: {} steps", steps.len()), Shape::Control),
+    };
+    (format!("{}. {}", index + 1, truncate(&text)), shape)
+}
+
+fn describe_condition(condition: &Condition) -> String {
+    match condition {
+        Condition::Succeeded => "succeeded".to_string(),
+        Condition::Failed => "failed".to_string(),
+        Condition::Contains { text } => format!("contains '{}'", text),
+        Condition::Equals { text } => format!("equals '{}'", text),
+        Condition::Matches { pattern } => format!("matches /{}/", pattern),
+        Condition::JsonPath { path, equals: Some(value) } => format!("{} = {}", path, value),
+        Condition::JsonPath { path, equals: None } => format!("{} is set", path),
+    }
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.replace('\n', " ");
+    if text.chars().count() <= MAX_LABEL_CHARS {
+        text
+    } else {
+        let kept: String = text.chars().take(MAX_LABEL_CHARS - 3).collect();
+        format!("{}...", kept)
+    }
+}
+
+impl Plan {
+    /// Render the plan as a Graphviz DOT digraph
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use planner::{Plan, Step};
+    ///
+    /// let plan = Plan::new(
+    ///     vec![Step::Response { text: "Hello".to_string() }],
+    ///     "Greet".to_string(),
+    /// );
+    /// assert!(plan.to_dot().contains("start -> step1"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph plan {\n    rankdir=TB;\n");
+        dot.push_str(&format!(
+            "    start [shape=circle, label=\"start\", tooltip=\"{}\"];\n",
+            escape_dot(&self.reasoning)
+        ));
+        for (index, step) in self.steps.iter().enumerate() {
+            let (label, shape) = describe(index, step);
+            let shape = match shape {
+                Shape::Tool => "box",
+                Shape::Reasoning => "ellipse",
+                Shape::Response => "doubleoctagon",
+                Shape::Image => "parallelogram",
+                Shape::Text => "hexagon",
+                Shape::Control => "diamond",
+            };
+            dot.push_str(&format!(
+                "    step{} [shape={}, label=\"{}\"];\n",
+                index + 1,
+                shape,
+                escape_dot(&label)
+            ));
+        }
+        dot.push_str("    end [shape=doublecircle, label=\"end\"];\n");
+        for (from, to) in self.edges() {
+            dot.push_str(&format!("    {} -> {};\n", from, to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the plan as a Mermaid flowchart
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use planner::{Plan, Step};
+    ///
+    /// let plan = Plan::new(
+    ///     vec![Step::Response { text: "Hello".to_string() }],
+    ///     "Greet".to_string(),
+    /// );
+    /// assert!(plan.to_mermaid().starts_with("flowchart TD"));
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n    start((start))\n");
+        for (index, step) in self.steps.iter().enumerate() {
+            let (label, shape) = describe(index, step);
+            let label = escape_mermaid(&label);
+            let node = match shape {
+                Shape::Tool => format!("[\"{}\"]", label),
+                Shape::Reasoning => format!("(\"{}\")", label),
+                Shape::Response => format!("([\"{}\"])", label),
+                Shape::Image => format!("[/\"{}\"/]", label),
+                Shape::Text => format!("{{{{\"{}\"}}}}", label),
+                Shape::Control => format!("{{\"{}\"}}", label),
+            };
+            mermaid.push_str(&format!("    step{}{}\n", index + 1, node));
+        }
+        mermaid.push_str("    end_node(((end)))\n");
+        for (from, to) in self.edges() {
+            let to = if to == "end" {
+                "end_node".to_string()
+            } else {
+                to
+            };
+            mermaid.push_str(&format!("    {} --> {}\n", from, to));
+        }
+        mermaid
+    }
+
+    /// Edges of the execution chain, from `start` through each step to `end`
+    fn edges(&self) -> Vec<(String, String)> {
+        let nodes: Vec<String> = std::iter::once("start".to_string())
+            .chain((1..=self.steps.len()).map(|n| format!("step{}", n)))
+            .chain(std::iter::once("end".to_string()))
+            .collect();
+        nodes
+            .windows(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect()
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ToolCall;
+    use serde_json::json;
+
+    fn sample_plan() -> Plan {
+        Plan::new(
+            vec![
+                Step::ToolCall(ToolCall::new(
+                    "calculator".to_string(),
+                    json!({"operation": "add", "a": 15, "b": 27}),
+                )),
+                Step::Reasoning {
+                    text: "The sum is \"42\"".to_string(),
+                },
+                Step::Response {
+                    text: "15 + 27 = 42".to_string(),
+                },
+            ],
+            "Add the numbers".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = sample_plan().to_dot();
+        assert!(dot.starts_with("digraph plan {"));
+        assert!(dot.contains("step1 [shape=box, label=\"1. calculator({"));
+        assert!(dot.contains("label=\"2. reason: The sum is \\\"42\\\"\""));
+        assert!(dot.contains("step3 [shape=doubleoctagon"));
+        assert!(dot.contains(
+            "start -> step1;\n    step1 -> step2;\n    step2 -> step3;\n    step3 -> end;"
+        ));
+        assert!(dot.contains("tooltip=\"Add the numbers\""));
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let mermaid = sample_plan().to_mermaid();
+        assert!(mermaid.contains("step2(\"2. reason: The sum is #quot;42#quot;\")"));
+        assert!(mermaid.contains("step3([\"3. respond: 15 + 27 = 42\"])"));
+        assert!(mermaid.contains("step3 --> end_node"));
+        assert!(!mermaid.contains("--> end\n"));
+    }
+
+    #[test]
+    fn test_empty_plan_and_truncation() {
+        let plan = Plan::new(Vec::new(), String::new());
+        assert!(plan.to_dot().contains("start -> end;"));
+
+        let long = "x".repeat(200);
+        let (label, _) = describe(0, &Step::Reasoning { text: long });
+        assert_eq!(label.chars().count(), "1. ".len() + MAX_LABEL_CHARS);
+        assert!(label.ends_with("..."));
+    }
+
+    #[test]
+    fn test_control_steps_are_diamonds() {
+        let response = || Step::Response {
+            text: "ok".to_string(),
+        };
+        let plan = Plan::new(
+            vec![
+                Step::Conditional {
+                    check: Some(Box::new(response())),
+                    step: None,
+                    condition: Condition::Contains {
+                        text: "ok".to_string(),
+                    },
+                    then: vec![response(), response()],
+                    otherwise: Vec::new(),
+                },
+                Step::Loop {
+                    body: vec![response()],
+                    until: Condition::Succeeded,
+                    max_iterations: 3,
+                },
+            ],
+            "Branch and repeat".to_string(),
+        );
+        let dot = plan.to_dot();
+        assert!(dot.contains("step1 [shape=diamond, label=\"1. if contains 'ok': 2 steps, else 0\"]"));
+        assert!(plan
+            .to_mermaid()
+            .contains("step2{\"2. repeat 1 steps until succeeded (at most 3)\"}"));
+
+        let on_earlier = Step::Conditional {
+            check: None,
+            step: Some(0),
+            condition: Condition::JsonPath {
+                path: "$.status".to_string(),
+                equals: Some(serde_json::json!("open")),
+            },
+            then: vec![response()],
+            otherwise: Vec::new(),
+        };
+        let (label, _) = describe(1, &on_earlier);
+        assert_eq!(label, "2. if step 1 $.status = \"open\": 1 steps, else 0");
+    }
+}
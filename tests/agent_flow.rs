@@ -11,7 +11,7 @@ mod common;
 use common::{MockLLM, MockMemoryStore, fixtures};
 use memory::InMemoryStore;
 use planner::{Planner, Step};
-use executor::Executor;
+use executor::{Executor, StepKind};
 use tools::{Calculator, ToolRegistry};
 
 #[tokio::test]
@@ -73,7 +73,7 @@ async fn test_agent_flow_with_single_tool_call() {
     
     // Verify tool call result
     let tool_result = &result.step_results[0];
-    assert!(tool_result.step_type.contains("calculator"));
+    assert_eq!(tool_result.step_type, StepKind::tool_call("calculator"));
     assert!(tool_result.output.contains("42"));
 }
 
@@ -5,6 +5,8 @@
 //! - MockMemoryStore: A simple in-memory store for testing
 //! - Test fixtures: Common test scenarios and data
 
+#![allow(dead_code)]
+
 use agent_core::{Message, Result};
 use async_trait::async_trait;
 use llm::LLMProvider;
@@ -29,6 +29,12 @@ fn create_test_config() -> LLMConfig {
         api_key,
         temperature: 0.7,
         max_tokens: 100,
+        organization: None,
+        project: None,
+        safety_settings: Default::default(),
+        azure: None,
+        openrouter: None,
+        prompt_caching: false,
     }
 }
 
@@ -40,6 +46,12 @@ fn create_invalid_config() -> LLMConfig {
         api_key: "sk-invalid-key-for-testing".to_string(),
         temperature: 0.7,
         max_tokens: 100,
+        organization: None,
+        project: None,
+        safety_settings: Default::default(),
+        azure: None,
+        openrouter: None,
+        prompt_caching: false,
     }
 }
 
@@ -10,7 +10,7 @@
 mod common;
 
 use common::MockMemoryStore;
-use executor::Executor;
+use executor::{Executor, StepKind};
 use memory::InMemoryStore;
 use planner::{Plan, Step, ToolCall};
 use tools::{Calculator, FileReader, ToolRegistry, WebSearchStub};
@@ -76,7 +76,7 @@ async fn test_calculator_tool_called_with_correct_parameters() {
     
     // Verify calculator was called with correct parameters
     let tool_result = &result.step_results[0];
-    assert_eq!(tool_result.step_type, "tool_call:calculator");
+    assert_eq!(tool_result.step_type, StepKind::tool_call("calculator"));
     assert!(tool_result.success);
     
     // Verify the result contains the correct calculation
@@ -156,17 +156,17 @@ async fn test_multiple_tools_called_in_sequence() {
     
     // Verify calculator result
     let calc_result = &result.step_results[1];
-    assert_eq!(calc_result.step_type, "tool_call:calculator");
+    assert_eq!(calc_result.step_type, StepKind::tool_call("calculator"));
     assert!(calc_result.output.contains("42"));
     
     // Verify file reader result
     let file_result = &result.step_results[3];
-    assert_eq!(file_result.step_type, "tool_call:file_reader");
+    assert_eq!(file_result.step_type, StepKind::tool_call("file_reader"));
     assert!(file_result.output.contains(test_content));
     
     // Verify web search result
     let search_result = &result.step_results[5];
-    assert_eq!(search_result.step_type, "tool_call:web_search");
+    assert_eq!(search_result.step_type, StepKind::tool_call("web_search"));
     assert!(search_result.output.contains("rust programming"));
     assert!(search_result.output.contains("mock"));
 }
@@ -452,7 +452,7 @@ async fn test_web_search_tool_returns_mock_results() {
     
     // Verify search result format
     let search_result = &result.step_results[0];
-    assert_eq!(search_result.step_type, "tool_call:web_search");
+    assert_eq!(search_result.step_type, StepKind::tool_call("web_search"));
     assert!(search_result.success);
     assert!(search_result.output.contains("artificial intelligence"));
     assert!(search_result.output.contains("results"));
@@ -534,7 +534,7 @@ async fn test_file_reader_with_valid_file() {
     
     // Verify file contents were read correctly
     let file_result = &result.step_results[0];
-    assert_eq!(file_result.step_type, "tool_call:file_reader");
+    assert_eq!(file_result.step_type, StepKind::tool_call("file_reader"));
     assert!(file_result.success);
     // The output is JSON formatted, so check for key parts of the content
     assert!(file_result.output.contains("This is a test file"));
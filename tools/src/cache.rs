@@ -0,0 +1,273 @@
+use async_trait::async_trait;
+use agent_core::{ExecutionContext, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::tool::Tool;
+
+/// Default maximum number of cached results per tool
+const DEFAULT_MAX_ENTRIES: usize = 256;
+
+/// Tool wrapper that remembers results of identical calls.
+///
+/// Agent loops often repeat the same call (the same search, the same file
+/// read) within a session. Results are keyed by tool name and the
+/// canonicalized parameters, so `{"a": 1, "b": 2}` and `{"b": 2, "a": 1}` hit
+/// the same entry, and are reused until the time-to-live expires. Failed
+/// calls are never cached.
+///
+/// Only cache tools whose result depends on nothing but their parameters;
+/// the execution context is not part of the key.
+///
+/// # Example
+///
+/// ```rust
+/// use tools::{CachedTool, ToolRegistry, WebSearchStub};
+/// use std::time::Duration;
+///
+/// let mut registry = ToolRegistry::new();
+/// registry.register(Box::new(CachedTool::new(
+///     WebSearchStub::new(),
+///     Duration::from_secs(300),
+/// )));
+/// ```
+pub struct CachedTool<T: Tool> {
+    inner: T,
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, (Instant, Value)>>,
+}
+
+impl<T: Tool> CachedTool<T> {
+    /// Wraps a tool, keeping each result for `ttl`.
+    pub fn new(inner: T, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limits the number of cached results (default 256).
+    ///
+    /// When the cache is full, expired results are dropped first, then the
+    /// oldest result.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns the wrapped tool.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns how long results are reused.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the number of cached results, including expired ones not yet
+    /// evicted.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns true if no results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all cached results.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn lookup(&self, key: &str) -> Option<Value> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((stored, value)) if stored.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn store(&self, key: String, value: Value) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+            if entries.len() >= self.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, (stored, _))| *stored)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+/// Cache key for a call: the tool name and its parameters with object keys
+/// sorted
+fn cache_key(tool_name: &str, params: &Value) -> String {
+    format!("{}:{}", tool_name, canonicalize(params))
+}
+
+/// Returns `value` with object keys sorted at every level
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|key| (key.clone(), canonicalize(&map[key])))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[async_trait]
+impl<T: Tool> Tool for CachedTool<T> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.inner.parameters_schema()
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let key = cache_key(self.inner.name(), &params);
+        if let Some(value) = self.lookup(&key) {
+            return Ok(value);
+        }
+        let value = self.inner.execute(params).await?;
+        self.store(key, value.clone());
+        Ok(value)
+    }
+
+    async fn execute_with_context(&self, params: Value, context: &ExecutionContext) -> Result<Value> {
+        let key = cache_key(self.inner.name(), &params);
+        if let Some(value) = self.lookup(&key) {
+            return Ok(value);
+        }
+        let value = self.inner.execute_with_context(params, context).await?;
+        self.store(key, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::AgentError;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts executions and fails when asked to
+    struct CountingTool {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Tool for CountingTool {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn description(&self) -> &str {
+            "Counts calls"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if params["fail"] == true {
+                return Err(AgentError::ToolExecution {
+                    tool_name: "counter".to_string(),
+                    reason: "asked to fail".to_string(),
+                });
+            }
+            Ok(json!({"calls": calls}))
+        }
+    }
+
+    fn counter(ttl: Duration) -> CachedTool<CountingTool> {
+        CachedTool::new(
+            CountingTool {
+                calls: AtomicUsize::new(0),
+            },
+            ttl,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_identical_calls_are_cached() {
+        let tool = counter(Duration::from_secs(60));
+
+        let first = tool.execute(json!({"q": "rust", "page": {"n": 1, "size": 10}})).await;
+        let reordered = tool.execute(json!({"page": {"size": 10, "n": 1}, "q": "rust"})).await;
+        assert_eq!(first.unwrap(), json!({"calls": 1}));
+        assert_eq!(reordered.unwrap(), json!({"calls": 1}));
+
+        let other = tool.execute(json!({"q": "go"})).await.unwrap();
+        assert_eq!(other, json!({"calls": 2}));
+        assert_eq!(tool.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_failed_calls_are_not_reused() {
+        let tool = counter(Duration::ZERO);
+        tool.execute(json!({})).await.unwrap();
+        assert_eq!(tool.execute(json!({})).await.unwrap(), json!({"calls": 2}));
+
+        let tool = counter(Duration::from_secs(60));
+        assert!(tool.execute(json!({"fail": true})).await.is_err());
+        assert!(tool.execute(json!({"fail": true})).await.is_err());
+        assert_eq!(tool.inner().calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_oldest_entry_evicted_when_full() {
+        let tool = counter(Duration::from_secs(60)).with_max_entries(1);
+        tool.execute(json!({"n": 1})).await.unwrap();
+        tool.execute(json!({"n": 2})).await.unwrap();
+        assert_eq!(tool.len(), 1);
+
+        assert_eq!(tool.execute(json!({"n": 1})).await.unwrap(), json!({"calls": 3}));
+    }
+
+    #[test]
+    fn test_cache_key_is_canonical() {
+        assert_eq!(
+            cache_key("search", &json!({"b": [{"y": 1, "x": 2}], "a": null})),
+            cache_key("search", &json!({"a": null, "b": [{"x": 2, "y": 1}]}))
+        );
+        assert_ne!(
+            cache_key("search", &json!({"q": 1})),
+            cache_key("lookup", &json!({"q": 1}))
+        );
+    }
+}
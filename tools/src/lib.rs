@@ -7,8 +7,13 @@
 //! # Core Concepts
 //! 
 //! - **Tool**: A trait defining the interface for all tools
-//! - **ToolRegistry**: A registry for managing and retrieving available tools
+//! - **ToolRegistry**: A registry for managing available tools and dispatching
+//!   calls to them by name
 //! - **ToolInfo**: Metadata about a tool for display and planning
+//! - **CachedTool**: A wrapper that reuses results of identical calls for a
+//!   time-to-live
+//! - **McpClient**: A connection to a Model Context Protocol server whose
+//!   tools are registered like local ones (see the [`mcp`] module)
 //! 
 //! # Example
 //! 
@@ -34,13 +39,16 @@
 
 mod tool;
 mod registry;
+mod cache;
 mod calculator;
 mod file_reader;
 mod web_search;
+pub mod mcp;
 
 // Re-export public types and traits
 pub use tool::{Tool, ToolInfo};
 pub use registry::ToolRegistry;
+pub use cache::CachedTool;
 pub use calculator::Calculator;
 pub use file_reader::FileReader;
 pub use web_search::WebSearchStub;
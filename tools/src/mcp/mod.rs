@@ -0,0 +1,515 @@
+//! Tools from Model Context Protocol (MCP) servers.
+//!
+//! An [`McpClient`] connects to an MCP server, over stdio with
+//! [`StdioTransport`] or over HTTP with [`SseTransport`], and discovers the
+//! tools it offers. [`McpClient::register_tools`] adds them to a
+//! [`ToolRegistry`] as [`McpTool`]s, so the executor calls them like any
+//! other tool and each call is forwarded to the server.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use tools::ToolRegistry;
+//! use tools::mcp::McpClient;
+//!
+//! # async fn example() -> agent_core::Result<()> {
+//! let client = McpClient::stdio("npx", ["-y", "@modelcontextprotocol/server-filesystem", "."])
+//!     .await?
+//!     .with_tool_prefix("fs");
+//!
+//! let mut registry = ToolRegistry::new();
+//! let names = Arc::new(client).register_tools(&mut registry).await?;
+//! println!("Registered {}", names.join(", "));
+//! # Ok(())
+//! # }
+//! ```
+
+mod transport;
+
+pub use transport::{McpTransport, SseTransport, StdioTransport};
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::ffi::OsStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+
+use crate::registry::ToolRegistry;
+use crate::tool::{Tool, ToolInfo};
+
+/// MCP protocol version requested from servers
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// What a server reported about itself when connecting
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpServerInfo {
+    /// Name of the server implementation
+    pub name: String,
+    /// Version of the server implementation
+    pub version: String,
+    /// Protocol version the server agreed to
+    pub protocol_version: String,
+    /// Usage hints for the model, if the server gave any
+    pub instructions: Option<String>,
+}
+
+/// A connection to one MCP server
+///
+/// Requests are sent one at a time, so concurrent tool calls to the same
+/// server wait for each other.
+pub struct McpClient {
+    transport: Box<dyn McpTransport>,
+    /// Held for a whole request, so each response is read by its request
+    exchange: Mutex<()>,
+    next_id: AtomicU64,
+    server: McpServerInfo,
+    tool_prefix: Option<String>,
+}
+
+impl McpClient {
+    /// Connect over `transport` and complete the MCP handshake
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport fails or the server rejects the
+    /// handshake.
+    pub async fn connect(transport: impl McpTransport + 'static) -> Result<Self> {
+        let mut client = Self {
+            transport: Box::new(transport),
+            exchange: Mutex::new(()),
+            next_id: AtomicU64::new(1),
+            server: McpServerInfo {
+                name: String::new(),
+                version: String::new(),
+                protocol_version: String::new(),
+                instructions: None,
+            },
+            tool_prefix: None,
+        };
+
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        });
+        let result = client.request("initialize", params).await?;
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        client.server = McpServerInfo {
+            name: text(&result["serverInfo"]["name"]),
+            version: text(&result["serverInfo"]["version"]),
+            protocol_version: text(&result["protocolVersion"]),
+            instructions: result["instructions"].as_str().map(str::to_string),
+        };
+        client
+            .transport
+            .send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await?;
+        Ok(client)
+    }
+
+    /// Start `program` with `args` and connect to it over stdio
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process cannot be started or the handshake
+    /// fails.
+    pub async fn stdio<I, S>(program: impl AsRef<OsStr>, args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self::connect(StdioTransport::spawn(program, args)?).await
+    }
+
+    /// Connect to the server whose event stream is at `url`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream cannot be opened or the handshake
+    /// fails.
+    pub async fn sse(url: &str) -> Result<Self> {
+        Self::connect(SseTransport::connect(url).await?).await
+    }
+
+    /// Register the server's tools as `{prefix}_{name}`, to avoid clashes
+    /// between servers
+    pub fn with_tool_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.tool_prefix = Some(prefix.into());
+        self
+    }
+
+    /// What the server reported about itself
+    pub fn server(&self) -> &McpServerInfo {
+        &self.server
+    }
+
+    /// List the tools the server offers
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails.
+    pub async fn list_tools(&self) -> Result<Vec<ToolInfo>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({"cursor": cursor}),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+            for tool in result["tools"].as_array().into_iter().flatten() {
+                let Some(name) = tool["name"].as_str() else {
+                    continue;
+                };
+                tools.push(ToolInfo {
+                    name: name.to_string(),
+                    description: tool["description"].as_str().unwrap_or_default().to_string(),
+                    parameters_schema: match &tool["inputSchema"] {
+                        Value::Null => json!({"type": "object"}),
+                        schema => schema.clone(),
+                    },
+                });
+            }
+            cursor = result["nextCursor"].as_str().map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Call a tool on the server
+    ///
+    /// Returns the tool's structured content if it has any, otherwise its
+    /// text content joined by newlines, or the content blocks as they are
+    /// if some are not text.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AgentError::ToolExecution`] if the call fails or the tool
+    /// reports an error.
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<Value> {
+        let failed = |reason: String| AgentError::ToolExecution {
+            tool_name: name.to_string(),
+            reason,
+        };
+        let arguments = match arguments {
+            Value::Null => json!({}),
+            arguments => arguments,
+        };
+        let result = self
+            .request("tools/call", json!({"name": name, "arguments": arguments}))
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+
+        let content = result["content"].as_array().cloned().unwrap_or_default();
+        let texts: Option<Vec<&str>> = content
+            .iter()
+            .map(|block| match block["type"].as_str() {
+                Some("text") => block["text"].as_str(),
+                _ => None,
+            })
+            .collect();
+        if result["isError"].as_bool().unwrap_or(false) {
+            let reason = match &texts {
+                Some(texts) if !texts.is_empty() => texts.join("\n"),
+                _ => "Tool reported an error".to_string(),
+            };
+            return Err(failed(reason));
+        }
+        if let Some(structured) = result.get("structuredContent") {
+            return Ok(structured.clone());
+        }
+        Ok(match texts {
+            Some(texts) => Value::String(texts.join("\n")),
+            None => Value::Array(content),
+        })
+    }
+
+    /// Discover the server's tools and register them in `registry`
+    ///
+    /// # Returns
+    /// The names the tools were registered under
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tools cannot be listed.
+    pub async fn register_tools(
+        self: &Arc<Self>,
+        registry: &mut ToolRegistry,
+    ) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for info in self.list_tools().await? {
+            let tool = McpTool::new(self.clone(), info);
+            names.push(tool.name.clone());
+            registry.register(Box::new(tool));
+        }
+        Ok(names)
+    }
+
+    /// Send a request and wait for its response
+    ///
+    /// Requests from the server while waiting are answered: `ping` with an
+    /// empty result, anything else as an unknown method. Notifications are
+    /// ignored.
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let _exchange = self.exchange.lock().await;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.transport
+            .send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .await?;
+
+        loop {
+            let message = self.transport.receive().await?;
+            if let Some(server_method) = message["method"].as_str() {
+                if let Some(request_id) = message.get("id") {
+                    let reply = match server_method {
+                        "ping" => json!({"jsonrpc": "2.0", "id": request_id, "result": {}}),
+                        _ => json!({
+                            "jsonrpc": "2.0",
+                            "id": request_id,
+                            "error": {"code": -32601, "message": "Method not found"},
+                        }),
+                    };
+                    self.transport.send(reply).await?;
+                }
+                continue;
+            }
+            if message["id"].as_u64() != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                return Err(AgentError::Execution(format!(
+                    "MCP request '{}' failed: {} ({})",
+                    method,
+                    error["message"].as_str().unwrap_or("unknown error"),
+                    error["code"]
+                )));
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+}
+
+/// A tool offered by an MCP server, called through its [`McpClient`]
+pub struct McpTool {
+    client: Arc<McpClient>,
+    /// Name registered in the tool registry, with the client's prefix
+    name: String,
+    info: ToolInfo,
+}
+
+impl McpTool {
+    /// Wrap a tool listed by `client`
+    pub fn new(client: Arc<McpClient>, info: ToolInfo) -> Self {
+        let name = match &client.tool_prefix {
+            Some(prefix) => format!("{}_{}", prefix, info.name),
+            None => info.name.clone(),
+        };
+        Self { client, name, info }
+    }
+
+    /// The tool's name on the server
+    pub fn remote_name(&self) -> &str {
+        &self.info.name
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.info.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.info.parameters_schema.clone()
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        self.client
+            .call_tool(&self.info.name, params)
+            .await
+            .map_err(|e| match e {
+                AgentError::ToolExecution { reason, .. } => AgentError::ToolExecution {
+                    tool_name: self.name.clone(),
+                    reason,
+                },
+                e => e,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::ExecutionContext;
+    use std::collections::VecDeque;
+    use std::sync::Mutex as StdMutex;
+
+    /// In-process server answering requests as they are sent
+    #[derive(Default)]
+    struct FakeServer {
+        outbox: StdMutex<VecDeque<Value>>,
+        sent: StdMutex<Vec<Value>>,
+    }
+
+    impl FakeServer {
+        fn respond(&self, request: &Value) -> Vec<Value> {
+            let id = request["id"].clone();
+            let result = match request["method"].as_str().unwrap() {
+                "initialize" => json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "serverInfo": {"name": "fake", "version": "1.2.0"},
+                    "capabilities": {"tools": {}},
+                }),
+                "tools/list" if request["params"]["cursor"].is_null() => json!({
+                    "tools": [{
+                        "name": "add",
+                        "description": "Adds numbers",
+                        "inputSchema": {"type": "object", "properties": {"a": {}, "b": {}}},
+                    }],
+                    "nextCursor": "page-2",
+                }),
+                "tools/list" => json!({"tools": [{"name": "fail"}, {"name": "image"}]}),
+                "tools/call" => {
+                    let arguments = &request["params"]["arguments"];
+                    match request["params"]["name"].as_str().unwrap() {
+                        "add" => {
+                            let sum =
+                                arguments["a"].as_i64().unwrap() + arguments["b"].as_i64().unwrap();
+                            // Check the client answers server requests mid-call
+                            let ping = json!({"jsonrpc": "2.0", "id": "srv-1", "method": "ping"});
+                            let note =
+                                json!({"jsonrpc": "2.0", "method": "notifications/progress"});
+                            let result =
+                                json!({"content": [{"type": "text", "text": sum.to_string()}]});
+                            return vec![
+                                ping,
+                                note,
+                                json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                            ];
+                        }
+                        "fail" => json!({
+                            "content": [{"type": "text", "text": "disk full"}],
+                            "isError": true,
+                        }),
+                        "image" => {
+                            json!({"content": [{"type": "image", "data": "AA==", "mimeType": "image/png"}]})
+                        }
+                        _ => {
+                            return vec![json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "error": {"code": -32602, "message": "Unknown tool"},
+                            })];
+                        }
+                    }
+                }
+                _ => return Vec::new(),
+            };
+            vec![json!({"jsonrpc": "2.0", "id": id, "result": result})]
+        }
+    }
+
+    #[async_trait]
+    impl McpTransport for Arc<FakeServer> {
+        async fn send(&self, message: Value) -> Result<()> {
+            let replies = if message.get("id").is_some() && message.get("method").is_some() {
+                self.respond(&message)
+            } else {
+                Vec::new()
+            };
+            self.sent.lock().unwrap().push(message);
+            self.outbox.lock().unwrap().extend(replies);
+            Ok(())
+        }
+
+        async fn receive(&self) -> Result<Value> {
+            self.outbox
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| AgentError::Execution("no message".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_paginated_tool_listing() {
+        let server = Arc::new(FakeServer::default());
+        let client = McpClient::connect(server.clone()).await.unwrap();
+        assert_eq!(client.server().name, "fake");
+        assert_eq!(client.server().protocol_version, PROTOCOL_VERSION);
+
+        let tools = client.list_tools().await.unwrap();
+        let names: Vec<_> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, ["add", "fail", "image"]);
+        assert_eq!(tools[0].parameters_schema["properties"]["a"], json!({}));
+        assert_eq!(tools[1].parameters_schema, json!({"type": "object"}));
+
+        let sent = server.sent.lock().unwrap();
+        assert_eq!(sent[0]["params"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(sent[1]["method"], "notifications/initialized");
+        assert_eq!(sent[3]["params"]["cursor"], "page-2");
+    }
+
+    #[tokio::test]
+    async fn test_registered_tools_call_the_server() {
+        let server = Arc::new(FakeServer::default());
+        let client = McpClient::connect(server.clone())
+            .await
+            .unwrap()
+            .with_tool_prefix("math");
+        let mut registry = ToolRegistry::new();
+        let names = Arc::new(client)
+            .register_tools(&mut registry)
+            .await
+            .unwrap();
+        assert_eq!(names, ["math_add", "math_fail", "math_image"]);
+
+        let context = ExecutionContext::default();
+        let sum = registry
+            .execute("math_add", json!({"a": 2, "b": 3}), &context)
+            .await;
+        assert_eq!(sum.unwrap(), json!("5"));
+        let pong = server
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m["id"] == "srv-1");
+        assert!(pong);
+
+        let err = registry
+            .execute("math_fail", json!({}), &context)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AgentError::ToolExecution { tool_name, reason } if tool_name == "math_fail" && reason == "disk full"
+        ));
+
+        let image = registry
+            .execute("math_image", Value::Null, &context)
+            .await
+            .unwrap();
+        assert_eq!(image[0]["mimeType"], "image/png");
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors_fail_the_call() {
+        let client = McpClient::connect(Arc::new(FakeServer::default()))
+            .await
+            .unwrap();
+        let err = client.call_tool("missing", json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("Unknown tool (-32602)"));
+    }
+}
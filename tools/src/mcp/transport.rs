@@ -0,0 +1,253 @@
+//! Transports carrying JSON-RPC messages to and from an MCP server.
+
+use agent_core::{AgentError, Result};
+use async_trait::async_trait;
+use communication::{SseStream, decode_sse_stream, with_request_headers};
+use futures::StreamExt;
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// A connection to an MCP server that sends and receives JSON-RPC messages
+///
+/// Implement this to reach servers over another channel; [`McpClient`]
+/// handles the protocol on top.
+///
+/// [`McpClient`]: super::McpClient
+#[async_trait]
+pub trait McpTransport: Send + Sync {
+    /// Send one message to the server
+    async fn send(&self, message: Value) -> Result<()>;
+
+    /// Wait for the next message from the server
+    async fn receive(&self) -> Result<Value>;
+}
+
+/// Transport to a server run as a child process, exchanging newline
+/// delimited JSON over its stdin and stdout
+///
+/// The server's stderr is passed through, and the process is killed when
+/// the transport is dropped.
+pub struct StdioTransport {
+    _child: Child,
+    stdin: Mutex<ChildStdin>,
+    stdout: Mutex<Lines<BufReader<ChildStdout>>>,
+}
+
+impl StdioTransport {
+    /// Start `program` with `args` as an MCP server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process cannot be started.
+    pub fn spawn<I, S>(program: impl AsRef<OsStr>, args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(program);
+        command.args(args);
+        Self::from_command(command)
+    }
+
+    /// Start a prepared command, e.g. with environment variables set, as an
+    /// MCP server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process cannot be started.
+    pub fn from_command(mut command: Command) -> Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Self {
+            _child: child,
+            stdin: Mutex::new(stdin),
+            stdout: Mutex::new(BufReader::new(stdout).lines()),
+        })
+    }
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(&line).await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Value> {
+        let mut stdout = self.stdout.lock().await;
+        loop {
+            let line = stdout
+                .next_line()
+                .await?
+                .ok_or_else(|| AgentError::Execution("MCP server closed its output".to_string()))?;
+            if !line.trim().is_empty() {
+                return Ok(serde_json::from_str(&line)?);
+            }
+        }
+    }
+}
+
+/// Transport to a server over HTTP with server-sent events
+///
+/// The client opens an event stream on the server's SSE URL, which first
+/// names the endpoint to POST messages to; the server's messages arrive as
+/// `message` events on the stream.
+pub struct SseTransport {
+    client: reqwest::Client,
+    endpoint: String,
+    events: Mutex<SseStream>,
+}
+
+impl SseTransport {
+    /// Open the event stream at `url` and wait for the message endpoint
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream cannot be opened or ends before
+    /// naming an endpoint.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let request = client.get(url).header("Accept", "text/event-stream");
+        let response = with_request_headers(request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AgentError::Execution(format!("MCP connection failed: {}", e)))?;
+        let mut events = decode_sse_stream(response.bytes_stream());
+
+        let endpoint = loop {
+            let event = events.next().await.ok_or_else(|| {
+                AgentError::Execution("MCP event stream ended before the endpoint".to_string())
+            })??;
+            if event.event.as_deref() == Some("endpoint") {
+                break resolve_endpoint(url, &event.data)?;
+            }
+        };
+
+        Ok(Self {
+            client,
+            endpoint,
+            events: Mutex::new(events),
+        })
+    }
+
+    /// URL that messages are posted to
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+#[async_trait]
+impl McpTransport for SseTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        let request = self.client.post(&self.endpoint).json(&message);
+        with_request_headers(request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AgentError::Execution(format!("MCP message failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Value> {
+        let mut events = self.events.lock().await;
+        loop {
+            let event = events
+                .next()
+                .await
+                .ok_or_else(|| AgentError::Execution("MCP event stream closed".to_string()))??;
+            if matches!(event.event.as_deref(), None | Some("message")) {
+                return Ok(serde_json::from_str(&event.data)?);
+            }
+        }
+    }
+}
+
+/// Resolve the endpoint named by the server against the SSE URL
+fn resolve_endpoint(url: &str, endpoint: &str) -> Result<String> {
+    let base = reqwest::Url::parse(url)
+        .map_err(|e| AgentError::Config(format!("Invalid MCP server URL '{}': {}", url, e)))?;
+    let endpoint = base.join(endpoint.trim()).map_err(|e| {
+        AgentError::Execution(format!("Invalid MCP endpoint '{}': {}", endpoint, e))
+    })?;
+    Ok(endpoint.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_endpoint_is_resolved_against_the_stream_url() {
+        let url = "http://localhost:8080/mcp/sse";
+        assert_eq!(
+            resolve_endpoint(url, "/messages?session=1").unwrap(),
+            "http://localhost:8080/messages?session=1"
+        );
+        assert_eq!(
+            resolve_endpoint(url, "http://other:9000/post").unwrap(),
+            "http://other:9000/post"
+        );
+        assert!(resolve_endpoint("not a url", "/messages").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sse_posts_to_the_announced_endpoint() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let reply =
+            json!({"jsonrpc": "2.0", "id": 1, "result": {"serverInfo": {"name": "remote"}}});
+        let body = format!(
+            ": keep-alive\n\nevent: endpoint\ndata: /messages?session=7\n\nevent: message\ndata: {}\n\n",
+            reply
+        );
+        Mock::given(method("GET"))
+            .and(path("/sse"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/sse", server.uri());
+        let transport = SseTransport::connect(&url).await.unwrap();
+        assert_eq!(
+            transport.endpoint(),
+            format!("{}/messages?session=7", server.uri())
+        );
+
+        let client = crate::mcp::McpClient::connect(transport).await.unwrap();
+        assert_eq!(client.server().name, "remote");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_exchanges_json_lines() {
+        // `cat` echoes each message back
+        let transport = StdioTransport::spawn("cat", std::iter::empty::<&str>()).unwrap();
+        let message = json!({"jsonrpc": "2.0", "method": "ping", "id": 1});
+        transport.send(message.clone()).await.unwrap();
+        assert_eq!(transport.receive().await.unwrap(), message);
+    }
+}
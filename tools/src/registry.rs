@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use agent_core::{AgentError, ExecutionContext, Result};
+use serde_json::Value;
 use crate::tool::{Tool, ToolInfo};
 
 /// Registry for managing available tools.
@@ -40,6 +42,29 @@ impl ToolRegistry {
         self.tools.get(name).map(|boxed| boxed.as_ref())
     }
     
+    /// Executes the tool registered under `name`.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the tool to run
+    /// * `params` - The parameters to pass to the tool
+    /// * `context` - The execution context passed to the tool
+    /// 
+    /// # Returns
+    /// The tool's result, its error, or a `ToolExecution` error if no tool
+    /// is registered under `name`
+    pub async fn execute(
+        &self,
+        name: &str,
+        params: Value,
+        context: &ExecutionContext,
+    ) -> Result<Value> {
+        let tool = self.get(name).ok_or_else(|| AgentError::ToolExecution {
+            tool_name: name.to_string(),
+            reason: "Tool not found in registry".to_string(),
+        })?;
+        tool.execute_with_context(params, context).await
+    }
+    
     /// Lists all available tools with their information.
     /// 
     /// # Returns
@@ -161,4 +186,21 @@ mod tests {
         let result = tool.execute(params).await.unwrap();
         assert_eq!(result["result"], 5.0);
     }
+
+    #[tokio::test]
+    async fn test_registry_dispatches_by_name() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(Calculator::new()));
+        let context = ExecutionContext::new();
+
+        let params = serde_json::json!({"operation": "multiply", "a": 4.0, "b": 2.5});
+        let result = registry.execute("calculator", params, &context).await.unwrap();
+        assert_eq!(result["result"], 10.0);
+
+        let result = registry.execute("abacus", Value::Null, &context).await;
+        assert!(matches!(
+            result,
+            Err(AgentError::ToolExecution { tool_name, .. }) if tool_name == "abacus"
+        ));
+    }
 }
@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use agent_core::Result;
+use agent_core::{ExecutionContext, Result, ToolDefinition};
 use serde_json::Value;
 
 /// Trait defining the interface for tools that agents can use.
@@ -25,6 +25,25 @@ pub trait Tool: Send + Sync {
     /// # Returns
     /// A JSON value containing the tool's result
     async fn execute(&self, params: Value) -> Result<Value>;
+
+    /// Executes the tool with access to the caller's execution context.
+    ///
+    /// Placeholders in `params` have already been filled in from `context`.
+    /// Tools that need the context directly (for example to resolve a
+    /// credential reference) override this; the default ignores it.
+    /// Tools that call other services can read the request's trace ID from
+    /// `RequestContext::current()` and forward it.
+    ///
+    /// # Arguments
+    /// * `params` - JSON value containing the tool parameters
+    /// * `context` - User, locale and credential context for this run
+    ///
+    /// # Returns
+    /// A JSON value containing the tool's result
+    async fn execute_with_context(&self, params: Value, context: &ExecutionContext) -> Result<Value> {
+        let _ = context;
+        self.execute(params).await
+    }
 }
 
 /// Information about a tool for display and planning purposes.
@@ -43,4 +62,9 @@ impl ToolInfo {
             parameters_schema: tool.parameters_schema(),
         }
     }
+
+    /// Describes the tool for native tool calling.
+    pub fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(&self.name, &self.description, self.parameters_schema.clone())
+    }
 }